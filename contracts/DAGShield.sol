@@ -5,13 +5,24 @@ import "@openzeppelin/contracts/token/ERC20/ERC20.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
 import "@openzeppelin/contracts/utils/ReentrancyGuard.sol";
 import "@openzeppelin/contracts/utils/Pausable.sol";
+import "@openzeppelin/contracts/metatx/ERC2771Context.sol";
+import "./ThreatMiMC.sol";
+
+interface IModelCommitmentOracle {
+    function modelCommitment() external view returns (bytes32);
+}
+
+interface ISessionKeyRegistry {
+    function resolveOperator(address caller) external view returns (address);
+}
 
 /**
  * @title DAGShield Core Contract
  * @dev Main contract for the DAGShield decentralized AI-DePIN security network
  * Handles threat alerts, node management, and cross-chain coordination
+ * Node calls may be relayed through the trusted ERC-2771 forwarder (DAGShieldForwarder)
  */
-contract DAGShield is Ownable, ReentrancyGuard, Pausable {
+contract DAGShield is Ownable, ReentrancyGuard, Pausable, ERC2771Context {
     
     // Events
     event ThreatDetected(
@@ -41,6 +52,25 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         uint256 amount,
         string rewardType
     );
+    
+    event PowersOfTauHashUpdated(bytes32 transcriptHash);
+    
+    event ThreatVerifierUpdated(address verifier);
+    
+    event ModelOracleUpdated(address oracle);
+    
+    event NodeRegistryUpdated(address registry);
+    
+    event ThreatProven(
+        bytes32 indexed threatHash,
+        address indexed reporter,
+        uint256 provenThreatHash,
+        bytes data
+    );
+    
+    event ThreatDisputed(bytes32 indexed threatHash, address indexed disputer);
+    
+    event ThreatDisputeResolved(bytes32 indexed threatHash, address indexed disputer, bool falsePositive);
 
     // Structs
     struct ThreatAlert {
@@ -84,6 +114,8 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
     mapping(address => uint256) public nodeStakes;
     mapping(address => uint256) public reputationScores;
     mapping(bytes32 => mapping(address => bool)) public hasVoted;
+    // Node disputing each threat alert, until the dispute is resolved
+    mapping(bytes32 => address) public disputers;
     
     bytes32[] public threatIds;
     address[] public activeNodes;
@@ -101,7 +133,32 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
     uint256 public totalThreats;
     uint256 public verifiedThreats;
     
-    constructor(address _tokenContract) Ownable(msg.sender) {
+    // keccak256 of the Powers of Tau transcript the network's Groth16 keys are derived from
+    bytes32 public powersOfTauHash;
+    
+    // Groth16 verifier exported for the threat detection circuit
+    address public threatVerifier;
+    
+    // Oracle whose model commitment threat proofs must be generated against
+    address public modelOracle;
+    
+    // DePINNodeRegistry whose session keys may act for their operator's node
+    address public nodeRegistry;
+    
+    // MiMC threat hash each proven threat's proof was generated over
+    mapping(bytes32 => uint256) public provenThreatHashes;
+    
+    // keccak256 of every accepted threat proof, so none can be submitted twice
+    mapping(bytes32 => bool) public usedThreatProofs;
+    
+    // verifyProof arguments: a (2), b (4), c (2) and the threat hash, threshold and model commitment
+    uint256 public constant THREAT_PROOF_LENGTH = 11 * 32;
+    uint256 public constant MIN_PROOF_THRESHOLD = 700000; // 0.7 at the circuits' 1e6 scale
+    
+    constructor(address _tokenContract, address trustedForwarder)
+        Ownable(msg.sender)
+        ERC2771Context(trustedForwarder)
+    {
         tokenContract = _tokenContract;
     }
     
@@ -112,11 +169,11 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
     function registerNode(string memory nodeId) external payable nonReentrant {
         require(bytes(nodeId).length > 0, "Invalid node ID");
         require(msg.value >= MIN_STAKE, "Insufficient stake");
-        require(!nodes[msg.sender].active, "Node already registered");
+        require(!nodes[_msgSender()].active, "Node already registered");
         
-        nodes[msg.sender] = Node({
+        nodes[_msgSender()] = Node({
             nodeId: nodeId,
-            nodeAddress: msg.sender,
+            nodeAddress: _msgSender(),
             stake: msg.value,
             reputation: 100, // Starting reputation
             totalReports: 0,
@@ -126,11 +183,11 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
             energyEfficiency: 50 // Starting efficiency score
         });
         
-        nodeStakes[msg.sender] = msg.value;
+        nodeStakes[_msgSender()] = msg.value;
         totalStaked += msg.value;
-        activeNodes.push(msg.sender);
+        activeNodes.push(_msgSender());
         
-        emit NodeRegistered(msg.sender, nodeId, msg.value, block.timestamp);
+        emit NodeRegistered(_msgSender(), nodeId, msg.value, block.timestamp);
     }
     
     /**
@@ -146,12 +203,13 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         uint256 confidence,
         uint256 chainId
     ) external nonReentrant whenNotPaused {
-        require(nodes[msg.sender].active, "Node not registered");
+        address operator = _nodeOperator();
+        require(nodes[operator].active, "Node not registered");
         require(confidence >= MIN_CONFIDENCE, "Confidence too low");
         require(bytes(threatType).length > 0, "Invalid threat type");
         
         bytes32 alertId = keccak256(abi.encodePacked(
-            msg.sender,
+            operator,
             targetAddress,
             threatType,
             block.timestamp,
@@ -160,7 +218,7 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         
         threats[alertId] = ThreatAlert({
             id: alertId,
-            reporter: msg.sender,
+            reporter: operator,
             chainId: chainId,
             threatType: threatType,
             targetAddress: targetAddress,
@@ -174,12 +232,12 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         totalThreats++;
         
         // Update node activity
-        nodes[msg.sender].totalReports++;
-        nodes[msg.sender].lastActivity = block.timestamp;
+        nodes[operator].totalReports++;
+        nodes[operator].lastActivity = block.timestamp;
         
         emit ThreatDetected(
             alertId,
-            msg.sender,
+            operator,
             chainId,
             threatType,
             confidence,
@@ -187,18 +245,94 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         );
     }
     
+    /**
+     * @dev Report a threat with a ZK proof that the node's committed model scored it above threshold
+     * @param threatHash keccak256 of the threat data
+     * @param data Threat data the proof was generated over
+     * @param proof Threat verifier's verifyProof arguments, ABI-encoded without the selector
+     */
+    function submitThreatWithProof(
+        bytes32 threatHash,
+        bytes calldata data,
+        bytes calldata proof
+    ) external nonReentrant whenNotPaused {
+        address operator = _nodeOperator();
+        require(nodes[operator].active, "Node not registered");
+        require(threatVerifier != address(0), "Threat verifier not set");
+        require(modelOracle != address(0), "Model oracle not set");
+        require(keccak256(data) == threatHash, "Threat hash mismatch");
+        require(threats[threatHash].timestamp == 0, "Threat already reported");
+        require(proof.length == THREAT_PROOF_LENGTH, "Invalid proof length");
+        require(!usedThreatProofs[keccak256(proof)], "Proof already used");
+        
+        uint256 provenThreatHash = uint256(bytes32(proof[8 * 32:9 * 32]));
+        require(provenThreatHash == ThreatMiMC.threatHash(data), "Proof not over threat data");
+        
+        bytes32 commitment = IModelCommitmentOracle(modelOracle).modelCommitment();
+        require(commitment != bytes32(0), "Model commitment not set");
+        require(bytes32(proof[10 * 32:11 * 32]) == commitment, "Model commitment mismatch");
+        
+        uint256 threshold = uint256(bytes32(proof[9 * 32:10 * 32]));
+        require(threshold >= MIN_PROOF_THRESHOLD, "Proof threshold too low");
+        
+        (bool success, bytes memory result) = threatVerifier.staticcall(abi.encodePacked(
+            bytes4(keccak256("verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[3])")),
+            proof
+        ));
+        require(success && result.length == 32 && abi.decode(result, (bool)), "Invalid threat proof");
+        
+        threats[threatHash] = ThreatAlert({
+            id: threatHash,
+            reporter: operator,
+            chainId: block.chainid,
+            threatType: "zk-proven",
+            targetAddress: "",
+            confidence: threshold / 10000,
+            timestamp: block.timestamp,
+            verified: true,
+            votes: 0
+        });
+        provenThreatHashes[threatHash] = provenThreatHash;
+        usedThreatProofs[keccak256(proof)] = true;
+        
+        threatIds.push(threatHash);
+        totalThreats++;
+        verifiedThreats++;
+        
+        nodes[operator].totalReports++;
+        nodes[operator].lastActivity = block.timestamp;
+        
+        emit ThreatDetected(threatHash, operator, block.chainid, "zk-proven", threshold / 10000, block.timestamp);
+        emit ThreatProven(threatHash, operator, provenThreatHash, data);
+    }
+    
+    /**
+     * @dev MiMC hash the threat circuit computes over `data`, as it must appear in a threat proof
+     */
+    function threatDataHash(bytes calldata data) external pure returns (uint256) {
+        return ThreatMiMC.threatHash(data);
+    }
+    
+    /**
+     * @dev Whether a threat alert with this ID has been recorded
+     */
+    function threatExists(bytes32 alertId) external view returns (bool) {
+        return threats[alertId].timestamp != 0;
+    }
+    
     /**
      * @dev Vote on a threat alert for community verification
      * @param alertId ID of the threat alert
      * @param support True if supporting the alert, false if disputing
      */
     function voteOnThreat(bytes32 alertId, bool support) external nonReentrant {
-        require(nodes[msg.sender].active, "Node not registered");
+        address operator = _nodeOperator();
+        require(nodes[operator].active, "Node not registered");
         require(threats[alertId].id != bytes32(0), "Alert does not exist");
-        require(!hasVoted[alertId][msg.sender], "Already voted");
-        require(threats[alertId].reporter != msg.sender, "Cannot vote on own report");
+        require(!hasVoted[alertId][operator], "Already voted");
+        require(threats[alertId].reporter != operator, "Cannot vote on own report");
         
-        hasVoted[alertId][msg.sender] = true;
+        hasVoted[alertId][operator] = true;
         
         if (support) {
             threats[alertId].votes++;
@@ -216,6 +350,47 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         }
     }
     
+    /**
+     * @dev Dispute a threat alert as a false positive, for the owner to resolve
+     * @param alertId ID of the threat alert
+     */
+    function disputeThreat(bytes32 alertId) external nonReentrant {
+        address operator = _nodeOperator();
+        require(nodes[operator].active, "Node not registered");
+        require(threats[alertId].id != bytes32(0), "Alert does not exist");
+        require(threats[alertId].reporter != operator, "Cannot dispute own report");
+        require(disputers[alertId] == address(0), "Already disputed");
+        
+        disputers[alertId] = operator;
+        emit ThreatDisputed(alertId, operator);
+    }
+    
+    /**
+     * @dev Resolve an open dispute; a false positive loses its verification and costs the
+     * reporter reputation
+     * @param alertId ID of the disputed threat alert
+     * @param falsePositive True if the alert was wrong
+     */
+    function resolveDispute(bytes32 alertId, bool falsePositive) external onlyOwner {
+        address disputer = disputers[alertId];
+        require(disputer != address(0), "No open dispute");
+        delete disputers[alertId];
+        
+        if (falsePositive) {
+            ThreatAlert storage alert = threats[alertId];
+            if (alert.verified) {
+                alert.verified = false;
+                verifiedThreats--;
+                if (nodes[alert.reporter].accurateReports > 0) {
+                    nodes[alert.reporter].accurateReports--;
+                }
+            }
+            uint256 reputation = nodes[alert.reporter].reputation;
+            nodes[alert.reporter].reputation = reputation > 5 ? reputation - 5 : 0;
+        }
+        emit ThreatDisputeResolved(alertId, disputer, falsePositive);
+    }
+    
     /**
      * @dev Create a gamified challenge for nodes
      * @param challengeType Type of challenge
@@ -255,17 +430,18 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         bytes32 challengeId,
         bytes32 solution
     ) external nonReentrant {
-        require(nodes[msg.sender].active, "Node not registered");
+        address operator = _nodeOperator();
+        require(nodes[operator].active, "Node not registered");
         require(challenges[challengeId].id != bytes32(0), "Challenge does not exist");
         require(!challenges[challengeId].completed, "Challenge already completed");
         require(block.timestamp <= challenges[challengeId].deadline, "Challenge expired");
         
         if (solution == challenges[challengeId].expectedResult) {
             challenges[challengeId].completed = true;
-            challenges[challengeId].winner = msg.sender;
+            challenges[challengeId].winner = operator;
             
-            _distributeReward(msg.sender, "challenge_completion");
-            nodes[msg.sender].reputation += 10;
+            _distributeReward(operator, "challenge_completion");
+            nodes[operator].reputation += 10;
         }
     }
     
@@ -357,6 +533,36 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         );
     }
     
+    /**
+     * @dev Register the Powers of Tau transcript nodes derive their ZK keys from
+     * @param transcriptHash keccak256 of the .ptau file
+     */
+    function setPowersOfTauHash(bytes32 transcriptHash) external onlyOwner {
+        require(transcriptHash != bytes32(0), "Empty transcript hash");
+        powersOfTauHash = transcriptHash;
+        emit PowersOfTauHashUpdated(transcriptHash);
+    }
+    
+    /**
+     * @dev Set the Groth16 verifier threat proofs are checked against
+     * @param verifier Verifier contract exported for the threat detection circuit
+     */
+    function setThreatVerifier(address verifier) external onlyOwner {
+        require(verifier != address(0), "Invalid verifier");
+        threatVerifier = verifier;
+        emit ThreatVerifierUpdated(verifier);
+    }
+    
+    /**
+     * @dev Set the oracle whose model commitment threat proofs are checked against
+     * @param oracle DAGShieldOracle publishing the network's model commitment
+     */
+    function setModelOracle(address oracle) external onlyOwner {
+        require(oracle != address(0), "Invalid oracle");
+        modelOracle = oracle;
+        emit ModelOracleUpdated(oracle);
+    }
+    
     /**
      * @dev Emergency pause function
      */
@@ -378,6 +584,39 @@ contract DAGShield is Ownable, ReentrancyGuard, Pausable {
         payable(owner()).transfer(address(this).balance);
     }
     
+    /**
+     * @dev Set the registry session keys are resolved against
+     * @param registry DePINNodeRegistry operators authorize session keys on
+     */
+    function setNodeRegistry(address registry) external onlyOwner {
+        require(registry != address(0), "Invalid registry");
+        nodeRegistry = registry;
+        emit NodeRegistryUpdated(registry);
+    }
+    
+    /**
+     * @dev Node the caller acts for, mapping a live session key to the operator that authorized it
+     */
+    function _nodeOperator() internal view returns (address) {
+        address sender = _msgSender();
+        if (nodeRegistry == address(0)) {
+            return sender;
+        }
+        return ISessionKeyRegistry(nodeRegistry).resolveOperator(sender);
+    }
+    
+    function _msgSender() internal view override(Context, ERC2771Context) returns (address) {
+        return ERC2771Context._msgSender();
+    }
+    
+    function _msgData() internal view override(Context, ERC2771Context) returns (bytes calldata) {
+        return ERC2771Context._msgData();
+    }
+    
+    function _contextSuffixLength() internal view override(Context, ERC2771Context) returns (uint256) {
+        return ERC2771Context._contextSuffixLength();
+    }
+    
     // Fallback function to receive ETH
     receive() external payable {}
 }
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+import "@openzeppelin/contracts/metatx/ERC2771Forwarder.sol";
+
+/**
+ * @title DAGShieldForwarder
+ * @dev ERC-2771 trusted forwarder for gasless threat submissions. Nodes sign
+ * ForwardRequests against nonces(from) and a relayer pays gas to execute() them.
+ */
+contract DAGShieldForwarder is ERC2771Forwarder {
+    constructor() ERC2771Forwarder("DAGShieldForwarder") {}
+}
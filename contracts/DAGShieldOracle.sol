@@ -6,14 +6,21 @@ import "@chainlink/contracts/src/v0.8/ChainlinkClient.sol";
 import "@chainlink/contracts/src/v0.8/ConfirmedOwner.sol";
 import "@openzeppelin/contracts/security/ReentrancyGuard.sol";
 import "@openzeppelin/contracts/security/Pausable.sol";
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/EIP712.sol";
+
+interface INodeOwnerRegistry {
+    function getNodeOwner(string memory nodeId) external view returns (address);
+}
 
 /**
  * @title DAGShield Cross-Chain Oracle
  * @dev Chainlink-powered oracle for cross-chain threat detection and DAG consensus
  * Integrates with U2U DAG network for parallel transaction processing
  */
-contract DAGShieldOracle is ChainlinkClient, ConfirmedOwner, ReentrancyGuard, Pausable {
+contract DAGShieldOracle is ChainlinkClient, ConfirmedOwner, ReentrancyGuard, Pausable, EIP712 {
     using Chainlink for Chainlink.Request;
+    using ECDSA for bytes32;
 
     // Oracle Configuration
     bytes32 private jobId;
@@ -53,20 +60,76 @@ contract DAGShieldOracle is ChainlinkClient, ConfirmedOwner, ReentrancyGuard, Pa
         bool isRelayed;
     }
     
+    struct EnergyProof {
+        uint256 periodStart;
+        uint256 periodEnd;
+        uint256 energyWh;
+        uint256 avgPowerMilliwatts;
+        uint256 efficiencyScore; // 0-100
+        uint256 carbonGrams;
+        address node;
+        uint256 submittedAt;
+    }
+    
     // State Variables
     mapping(bytes32 => DAGTransaction) public dagTransactions;
     mapping(bytes32 => ThreatAlert) public threatAlerts;
     mapping(bytes32 => CrossChainAlert) public crossChainAlerts;
     mapping(address => bool) public authorizedNodes;
     mapping(uint256 => bool) public supportedChains;
+    // keccak256(nodeId, periodStart) => proof
+    mapping(bytes32 => EnergyProof) public energyProofs;
+    // keccak256(nodeId) => end of the latest proven period
+    mapping(bytes32 => uint256) public lastEnergyPeriodEnd;
     
     bytes32[] public pendingDAGTxs;
     bytes32[] public activeThreatAlerts;
     
+    // MiMC Merkle root over the confirmed threat signatures, in leaf order. Nodes rebuild the
+    // tree from ThreatSignatureAdded events; the root is computed off-chain and checked by them
+    bytes32 public threatSignatureRoot;
+    uint256 public threatSignatureCount;
+    
+    // Drainer kit fingerprints, published as DrainerFingerprintAdded events only; nodes keep the
+    // corpus and skip kinds they don't know
+    uint256 public drainerFingerprintCount;
+    
+    // MiMC commitment to the deployed detection model (Merkle root and chunk count); threat
+    // proofs open chunks of it and only count for this model
+    bytes32 public modelCommitment;
+    
+    // circuit id => keccak256 of the compressed verifying key nodes must prove with
+    mapping(uint8 => bytes32) public verifyingKeyHash;
+    
+    // Proof revocation list (JSON) and the revocation key's EIP-191 signature of its keccak256
+    bytes private revokedProofs;
+    bytes private revocationSignature;
+    
+    // Latest detection model release: IPFS CID of the ONNX file, its keccak256, and the
+    // publisher's EIP-191 signature over (chain id, this oracle, version, CID, hash)
+    uint256 public modelVersion;
+    string private modelCid;
+    bytes32 private modelHash;
+    bytes private modelSignature;
+    
+    // DePINNodeRegistry energy proofs are checked against: only a node's operator may sign them
+    address public nodeRegistry;
+    
+    // Nova decider verifier generated from the nodes' epoch folding keys
+    address public epochVerifier;
+    // keccak256(nodeId, epoch) => folded threat digest
+    mapping(bytes32 => bytes32) public epochDigests;
+    
     uint256 public constant MIN_CONSENSUS = 3; // Minimum nodes for consensus
     uint256 public constant DAG_BATCH_SIZE = 100; // Process in batches
     uint256 public totalThreatsDetected;
     uint256 public totalDAGTransactions;
+    uint256 public constant MAX_THREAT_SIGNATURES = 65536; // Leaves of the depth-16 signature tree
+    uint256 public constant SNARK_SCALAR_FIELD = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    uint256 public constant MIN_EPOCH_THRESHOLD = 700000; // Fixed-point (1e6) confidence threshold
+    bytes32 public constant ENERGY_PROOF_TYPEHASH = keccak256(
+        "EnergyProof(string nodeId,uint256 periodStart,uint256 periodEnd,uint256 energyWh,uint256 avgPowerMilliwatts,uint256 efficiencyScore,uint256 carbonGrams)"
+    );
     
     // Events
     event DAGTransactionAdded(bytes32 indexed txHash, address indexed from, address indexed to);
@@ -75,8 +138,18 @@ contract DAGShieldOracle is ChainlinkClient, ConfirmedOwner, ReentrancyGuard, Pa
     event CrossChainAlertRelayed(uint256 indexed sourceChain, uint256 indexed targetChain, bytes32 alertId);
     event NodeAuthorized(address indexed node, bool authorized);
     event ChainSupported(uint256 indexed chainId, bool supported);
+    event EnergyProofSubmitted(string nodeId, address indexed node, uint256 periodStart, uint256 periodEnd, uint256 efficiencyScore);
+    event ThreatSignatureAdded(uint256 indexed index, bytes32 signature, bytes32 threatHash, uint8 threatType);
+    event DrainerFingerprintAdded(uint256 indexed index, bytes32 threatHash, string kit, uint8 kind, bytes fingerprint);
+    event EpochVerifierUpdated(address verifier);
+    event NodeRegistryUpdated(address registry);
+    event ModelCommitmentUpdated(bytes32 commitment);
+    event ModelPublished(uint256 indexed version, string cid, bytes32 modelHash);
+    event VerifyingKeyRegistered(uint8 indexed circuit, bytes32 keyHash);
+    event RevocationListPublished(bytes32 digest);
+    event EpochProofSubmitted(string nodeId, address indexed node, uint256 epoch, uint256 threatCount, bytes32 digest);
     
-    constructor() ConfirmedOwner(msg.sender) {
+    constructor() ConfirmedOwner(msg.sender) EIP712("DAGShieldOracle", "1") {
         setChainlinkToken(0xa36085F69e2889c224210F603D836748e7dC0088); // Kovan LINK
         setChainlinkOracle(0xc57B33452b4F7BB189bB5AfaE9cc4aBa1f7a4FD8); // Kovan Oracle
         jobId = "d5270d1c311941d0b08bead21fea7747"; // Get > Uint256 job
@@ -394,6 +467,202 @@ contract DAGShieldOracle is ChainlinkClient, ConfirmedOwner, ReentrancyGuard, Pa
         );
     }
     
+    /**
+     * @dev Record a node's signed energy summary for a period, used to weight rewards.
+     * The summary is an EIP-712 `EnergyProof` signed by the node's registered operator.
+     * Periods of one node may not overlap.
+     */
+    function submitEnergyProof(
+        string memory nodeId,
+        uint256 periodStart,
+        uint256 periodEnd,
+        uint256 energyWh,
+        uint256 avgPowerMilliwatts,
+        uint256 efficiencyScore,
+        uint256 carbonGrams,
+        bytes memory signature
+    ) external whenNotPaused {
+        require(periodStart < periodEnd && periodEnd <= block.timestamp, "Invalid period");
+        require(efficiencyScore <= 100, "Invalid efficiency score");
+        require(nodeRegistry != address(0), "Node registry not set");
+        
+        bytes32 structHash = keccak256(abi.encode(
+            ENERGY_PROOF_TYPEHASH,
+            keccak256(bytes(nodeId)),
+            periodStart,
+            periodEnd,
+            energyWh,
+            avgPowerMilliwatts,
+            efficiencyScore,
+            carbonGrams
+        ));
+        address signer = _hashTypedDataV4(structHash).recover(signature);
+        require(authorizedNodes[signer], "Unauthorized node");
+        require(signer == INodeOwnerRegistry(nodeRegistry).getNodeOwner(nodeId), "Signer is not the node operator");
+        
+        bytes32 nodeKey = keccak256(bytes(nodeId));
+        require(periodStart >= lastEnergyPeriodEnd[nodeKey], "Period already proven");
+        lastEnergyPeriodEnd[nodeKey] = periodEnd;
+        
+        energyProofs[keccak256(abi.encodePacked(nodeId, periodStart))] = EnergyProof({
+            periodStart: periodStart,
+            periodEnd: periodEnd,
+            energyWh: energyWh,
+            avgPowerMilliwatts: avgPowerMilliwatts,
+            efficiencyScore: efficiencyScore,
+            carbonGrams: carbonGrams,
+            node: signer,
+            submittedAt: block.timestamp
+        });
+        
+        emit EnergyProofSubmitted(nodeId, signer, periodStart, periodEnd, efficiencyScore);
+    }
+    
+    /**
+     * @dev Record a recursive proof folding `threatCount` detections of `epoch`. `proof` is the
+     * decider verifier calldata: selector, step count, initial state, final state, then the proof.
+     * States are (epoch, threshold, count, digest); the digest starts at the node id's field
+     * element, so a proof only counts for the node that made it.
+     */
+    function submitEpochProof(
+        string memory nodeId,
+        uint256 epoch,
+        uint256 threatCount,
+        bytes32 digest,
+        bytes memory proof
+    ) external onlyAuthorizedNode whenNotPaused {
+        require(epochVerifier != address(0), "No epoch verifier");
+        require(threatCount > 0, "Empty epoch");
+        require(proof.length >= 4 + 32 * 9, "Truncated epoch proof");
+        
+        bytes32 epochKey = keccak256(abi.encodePacked(nodeId, epoch));
+        require(epochDigests[epochKey] == bytes32(0), "Epoch already proven");
+        
+        uint256 nodeField = uint256(keccak256(bytes(nodeId))) % SNARK_SCALAR_FIELD;
+        require(_word(proof, 0) == threatCount, "Step count mismatch");
+        require(_word(proof, 1) == epoch && _word(proof, 5) == epoch, "Epoch mismatch");
+        require(_word(proof, 2) >= MIN_EPOCH_THRESHOLD, "Threshold too low");
+        require(_word(proof, 3) == 0 && _word(proof, 7) == threatCount, "Count mismatch");
+        require(_word(proof, 4) == nodeField, "Proof is for another node");
+        require(_word(proof, 8) == uint256(digest), "Digest mismatch");
+        
+        (bool success, bytes memory result) = epochVerifier.staticcall(proof);
+        require(success && result.length >= 32 && abi.decode(result, (bool)), "Invalid epoch proof");
+        
+        epochDigests[epochKey] = digest;
+        emit EpochProofSubmitted(nodeId, msg.sender, epoch, threatCount, digest);
+    }
+    
+    /**
+     * @dev Register the verifying key of a circuit; zero unregisters it. Nodes stop proving
+     * with other keys once they see the change
+     */
+    function setVerifyingKeyHash(uint8 circuit, bytes32 keyHash) external onlyOwner {
+        verifyingKeyHash[circuit] = keyHash;
+        emit VerifyingKeyRegistered(circuit, keyHash);
+    }
+    
+    /**
+     * @dev Publish a signed proof revocation list; nodes check the signature against their
+     * configured revocation key and ignore lists older than the one they hold
+     */
+    function publishRevocationList(bytes calldata list, bytes calldata signature) external onlyOwner {
+        require(list.length > 0, "Empty revocation list");
+        require(signature.length == 65, "Invalid signature");
+        revokedProofs = list;
+        revocationSignature = signature;
+        emit RevocationListPublished(keccak256(list));
+    }
+    
+    /**
+     * @dev Current proof revocation list and its signature; empty before one is published
+     */
+    function revocationList() external view returns (bytes memory list, bytes memory signature) {
+        return (revokedProofs, revocationSignature);
+    }
+    
+    /**
+     * @dev Register the detection model threat proofs must be made for
+     */
+    function setModelCommitment(bytes32 commitment) external onlyOwner {
+        require(uint256(commitment) < SNARK_SCALAR_FIELD, "Not a field element");
+        modelCommitment = commitment;
+        emit ModelCommitmentUpdated(commitment);
+    }
+    
+    /**
+     * @dev Publish a signed model release; nodes check the signature against their configured
+     * publisher and the download against the hash before installing it
+     */
+    function publishModel(uint256 version, string calldata cid, bytes32 hash, bytes calldata signature) external onlyOwner {
+        require(version > modelVersion, "Version not newer");
+        require(bytes(cid).length > 0, "Empty CID");
+        require(signature.length == 65, "Invalid signature");
+        modelVersion = version;
+        modelCid = cid;
+        modelHash = hash;
+        modelSignature = signature;
+        emit ModelPublished(version, cid, hash);
+    }
+    
+    /**
+     * @dev Latest model release; version 0 before one is published
+     */
+    function latestModel() external view returns (uint256 version, string memory cid, bytes32 hash, bytes memory signature) {
+        return (modelVersion, modelCid, modelHash, modelSignature);
+    }
+    
+    /**
+     * @dev Set the registry energy proof signers are checked against
+     */
+    function setNodeRegistry(address registry) external onlyOwner {
+        require(registry != address(0), "Invalid registry");
+        nodeRegistry = registry;
+        emit NodeRegistryUpdated(registry);
+    }
+    
+    /**
+     * @dev Set the decider verifier epoch proofs are checked by
+     */
+    function setEpochVerifier(address verifier) external onlyOwner {
+        epochVerifier = verifier;
+        emit EpochVerifierUpdated(verifier);
+    }
+    
+    /**
+     * @dev Append a confirmed threat signature as the next leaf of the signature tree.
+     * `newRoot` is the tree root with the leaf included.
+     */
+    function addThreatSignature(
+        bytes32 signature,
+        bytes32 threatHash,
+        uint8 threatType,
+        bytes32 newRoot
+    ) external onlyOwner {
+        require(threatSignatureCount < MAX_THREAT_SIGNATURES, "Signature tree full");
+        
+        uint256 index = threatSignatureCount++;
+        threatSignatureRoot = newRoot;
+        
+        emit ThreatSignatureAdded(index, signature, threatHash, threatType);
+    }
+    
+    /**
+     * @dev Publish a fingerprint of drainer kit `kit`: a calldata layout (kind 0), fee splitter
+     * address (kind 1) or bytecode fuzzy hash (kind 2), encoded as nodes decode it
+     */
+    function addDrainerFingerprint(
+        bytes32 threatHash,
+        string calldata kit,
+        uint8 kind,
+        bytes calldata fingerprint
+    ) external onlyOwner {
+        require(bytes(kit).length > 0, "Empty kit name");
+        require(fingerprint.length > 0, "Empty fingerprint");
+        
+        emit DrainerFingerprintAdded(drainerFingerprintCount++, threatHash, kit, kind, fingerprint);
+    }
+    
     /**
      * @dev Authorize/deauthorize node
      */
@@ -437,6 +706,14 @@ contract DAGShieldOracle is ChainlinkClient, ConfirmedOwner, ReentrancyGuard, Pa
     }
     
     // Utility functions
+    
+    // `index`th 32-byte word of calldata after its 4-byte selector
+    function _word(bytes memory data, uint256 index) internal pure returns (uint256 value) {
+        assembly {
+            value := mload(add(add(data, 36), mul(index, 32)))
+        }
+    }
+    
     function _uint2str(uint256 _i) internal pure returns (string memory) {
         if (_i == 0) return "0";
         uint256 j = _i;
@@ -99,6 +99,11 @@ contract DePINNodeRegistry is Ownable, ReentrancyGuard, Pausable {
     mapping(address => uint256) public experiencePoints;
     mapping(address => uint256) public achievementCount;
     
+    // Session keys: operator => key => expiry timestamp
+    mapping(address => mapping(address => uint64)) public sessionKeyExpiry;
+    // Session key => operator that authorized it
+    mapping(address => address) public sessionKeyOperator;
+    
     uint256 public currentChallengeId;
     uint256 public constant MIN_STAKE = 1000 * 10**18; // 1000 DAG
     uint256 public constant MAX_NODES_PER_OWNER = 10;
@@ -121,6 +126,8 @@ contract DePINNodeRegistry is Ownable, ReentrancyGuard, Pausable {
     event ChallengeCompleted(uint256 indexed challengeId, address indexed winner, uint256 reward);
     event NodeSlashed(string indexed nodeId, uint256 slashAmount, string reason);
     event EnergyEfficiencyUpdated(string indexed nodeId, uint256 efficiency);
+    event SessionKeyAuthorized(address indexed operator, address indexed sessionKey, uint64 expiresAt);
+    event SessionKeyRevoked(address indexed operator, address indexed sessionKey);
 
     constructor(address _dagToken) {
         dagToken = DAGShieldToken(_dagToken);
@@ -364,6 +371,54 @@ contract DePINNodeRegistry is Ownable, ReentrancyGuard, Pausable {
         emit NodeSlashed(nodeId, slashAmount, reason);
     }
 
+    /**
+     * @dev Take a node out of service (e.g. before a planned shutdown) or put it back
+     */
+    function setNodeMaintenance(string memory nodeId, bool maintenance) external {
+        DePINNode storage node = nodes[nodeId];
+        require(node.owner == msg.sender, "Not node owner");
+        require(
+            node.status == NodeStatus.Active || node.status == NodeStatus.Maintenance,
+            "Node not in service"
+        );
+
+        NodeStatus newStatus = maintenance ? NodeStatus.Maintenance : NodeStatus.Active;
+        if (node.status != newStatus) {
+            emit NodeStatusUpdated(nodeId, node.status, newStatus);
+            node.status = newStatus;
+        }
+    }
+
+    /**
+     * @dev Authorize a short-lived key to submit threats on behalf of the caller's nodes
+     */
+    function authorizeSessionKey(address sessionKey, uint64 expiresAt) external whenNotPaused {
+        require(ownerNodes[msg.sender].length > 0, "No registered nodes");
+        require(sessionKey != address(0) && sessionKey != msg.sender, "Invalid session key");
+        require(expiresAt > block.timestamp, "Expiry in the past");
+        require(
+            sessionKeyOperator[sessionKey] == address(0) || sessionKeyOperator[sessionKey] == msg.sender,
+            "Session key bound to another operator"
+        );
+
+        sessionKeyExpiry[msg.sender][sessionKey] = expiresAt;
+        sessionKeyOperator[sessionKey] = msg.sender;
+
+        emit SessionKeyAuthorized(msg.sender, sessionKey, expiresAt);
+    }
+
+    /**
+     * @dev Revoke a session key before it expires
+     */
+    function revokeSessionKey(address sessionKey) external {
+        require(sessionKeyExpiry[msg.sender][sessionKey] != 0, "Unknown session key");
+
+        delete sessionKeyExpiry[msg.sender][sessionKey];
+        delete sessionKeyOperator[sessionKey];
+
+        emit SessionKeyRevoked(msg.sender, sessionKey);
+    }
+
     /**
      * @dev Update node reputation based on performance
      */
@@ -483,6 +538,26 @@ contract DePINNodeRegistry is Ownable, ReentrancyGuard, Pausable {
         return nodes[nodeId];
     }
 
+    function getNodeOwner(string memory nodeId) external view returns (address) {
+        return nodes[nodeId].owner;
+    }
+
+    function isSessionKeyValid(address operator, address sessionKey) external view returns (bool) {
+        return sessionKeyExpiry[operator][sessionKey] > block.timestamp;
+    }
+
+    /**
+     * @dev Operator a caller acts for: the authorizing operator for a live session key,
+     * otherwise the caller itself
+     */
+    function resolveOperator(address caller) external view returns (address) {
+        address operator = sessionKeyOperator[caller];
+        if (operator != address(0) && sessionKeyExpiry[operator][caller] > block.timestamp) {
+            return operator;
+        }
+        return caller;
+    }
+
     function getOwnerNodes(address owner) external view returns (string[] memory) {
         return ownerNodes[owner];
     }
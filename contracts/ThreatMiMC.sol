@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+/**
+ * @title Threat data MiMC hash
+ * @dev On-chain copy of the node client's `threat_hash`: a Miyaguchi-Preneel chain over MiMC-5
+ * on BN254, fed the data length and its 31-byte little-endian chunks. The threat circuit
+ * exposes this hash as a public input, so recomputing it ties a proof to the submitted data.
+ */
+library ThreatMiMC {
+    uint256 internal constant FIELD_MODULUS =
+        21888242871839275222246405745257275088548364400416034343698204186575808495617;
+
+    uint256 internal constant ROUNDS = 110;
+
+    // The circuit hashes a fixed number of chunks, zero-padded past the data
+    uint256 internal constant DATA_CHUNKS = 32;
+    uint256 internal constant MAX_DATA_LENGTH = DATA_CHUNKS * 31;
+
+    function threatHash(bytes calldata data) internal pure returns (uint256) {
+        require(data.length <= MAX_DATA_LENGTH, "Threat data too long");
+
+        uint256[] memory constants = roundConstants();
+        uint256 state = compress(constants, 0, data.length);
+        for (uint256 chunk = 0; chunk < DATA_CHUNKS; chunk++) {
+            uint256 value = 0;
+            uint256 start = chunk * 31;
+            for (uint256 i = 0; i < 31 && start + i < data.length; i++) {
+                value |= uint256(uint8(data[start + i])) << (8 * i);
+            }
+            state = compress(constants, state, value);
+        }
+        return state;
+    }
+
+    /**
+     * @dev keccak256("dagshield.energy.mimc" || u64le(round)), read little-endian and reduced
+     */
+    function roundConstants() internal pure returns (uint256[] memory constants) {
+        constants = new uint256[](ROUNDS);
+        for (uint256 round = 0; round < ROUNDS; round++) {
+            bytes32 digest = keccak256(abi.encodePacked("dagshield.energy.mimc", bytes1(uint8(round)), bytes7(0)));
+            constants[round] = reverseBytes(uint256(digest)) % FIELD_MODULUS;
+        }
+    }
+
+    function compress(uint256[] memory constants, uint256 state, uint256 value) private pure returns (uint256) {
+        uint256 x = value;
+        for (uint256 round = 0; round < ROUNDS; round++) {
+            uint256 t = addmod(addmod(x, state, FIELD_MODULUS), constants[round], FIELD_MODULUS);
+            uint256 t2 = mulmod(t, t, FIELD_MODULUS);
+            x = mulmod(mulmod(t2, t2, FIELD_MODULUS), t, FIELD_MODULUS);
+        }
+        // mimc(value, state) + value + state
+        return addmod(addmod(addmod(x, state, FIELD_MODULUS), value, FIELD_MODULUS), state, FIELD_MODULUS);
+    }
+
+    function reverseBytes(uint256 word) private pure returns (uint256 reversed) {
+        for (uint256 i = 0; i < 32; i++) {
+            reversed = (reversed << 8) | (word & 0xff);
+            word >>= 8;
+        }
+    }
+}
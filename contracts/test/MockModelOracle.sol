@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+/**
+ * @title Mock model oracle
+ * @dev Stands in for DAGShieldOracle's model commitment
+ */
+contract MockModelOracle {
+    bytes32 public modelCommitment;
+
+    function setModelCommitment(bytes32 commitment) external {
+        modelCommitment = commitment;
+    }
+}
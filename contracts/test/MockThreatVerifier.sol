@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+/**
+ * @title Mock threat verifier
+ * @dev Accepts every proof, so tests exercise DAGShield's own checks on the public inputs
+ */
+contract MockThreatVerifier {
+    function verifyProof(
+        uint256[2] calldata,
+        uint256[2][2] calldata,
+        uint256[2] calldata,
+        uint256[3] calldata
+    ) external pure returns (bool) {
+        return true;
+    }
+}
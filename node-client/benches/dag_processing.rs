@@ -0,0 +1,55 @@
+//! Adding transactions with dependency chains to the DAG
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use dagshield_node::{
+    config::NodeConfig,
+    dag::{DAGProcessor, Transaction},
+};
+use tokio::runtime::Runtime;
+
+/// Every third transaction depends on the one before it
+fn transactions(count: usize) -> Vec<Transaction> {
+    (0..count)
+        .map(|i| Transaction {
+            id: format!("bench_tx_{}", i),
+            from: format!("0x{:040x}", i),
+            to: format!("0x{:040x}", i + 1),
+            target_address: format!("0x{:040x}", i + 2),
+            chain_id: 1,
+            data: vec![i as u8; 32],
+            timestamp: 0,
+            dependencies: if i > 0 && i % 3 == 0 {
+                vec![format!("bench_tx_{}", i - 1)]
+            } else {
+                vec![]
+            },
+        })
+        .collect()
+}
+
+fn bench_add_transactions(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let config = NodeConfig::default();
+
+    let mut group = c.benchmark_group("dag_add_transactions");
+    for count in [100, 1_000, 10_000] {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter_batched(
+                || (runtime.block_on(DAGProcessor::new(&config)).unwrap(), transactions(count)),
+                |(dag, transactions)| {
+                    runtime.block_on(async {
+                        for transaction in transactions {
+                            dag.add_transaction(transaction).await.unwrap();
+                        }
+                    })
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_add_transactions);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Threat detection of transaction batches with the default AI settings; rule-based unless the
+//! default model file is present
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use dagshield_node::{ai::ThreatDetector, config::NodeConfig, dag::Transaction};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::runtime::Runtime;
+
+/// `count` transactions with ids starting at `first`, calling a mix of function selectors
+fn batch(first: usize, count: usize) -> Vec<Transaction> {
+    (first..first + count)
+        .map(|i| {
+            let mut data = match i % 4 {
+                0 => vec![0x09, 0x5e, 0xa7, 0xb3], // approve
+                1 => vec![0xa9, 0x05, 0x9c, 0xbb], // transfer
+                _ => vec![0x12, 0x34, 0x56, 0x78],
+            };
+            data.extend(vec![i as u8; 64]);
+            Transaction {
+                id: format!("bench_tx_{}", i),
+                from: format!("0x{:040x}", i),
+                to: format!("0x{:040x}", i + 1),
+                target_address: format!("0x{:040x}", i % 16),
+                chain_id: 1,
+                data,
+                timestamp: 0,
+                dependencies: vec![],
+            }
+        })
+        .collect()
+}
+
+fn bench_detect_batch(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let config = NodeConfig::default().ai;
+    let detector = runtime.block_on(ThreatDetector::new(&config)).unwrap();
+
+    // Fresh ids every iteration, so no result comes from the detection cache
+    let next = AtomicUsize::new(0);
+    let size = config.batch_size;
+
+    let mut group = c.benchmark_group("threat_detection");
+    group.throughput(Throughput::Elements(size as u64));
+    group.bench_function("detect_threats_batch", |b| {
+        b.iter_batched(
+            || batch(next.fetch_add(size, Ordering::Relaxed), size),
+            |transactions| runtime.block_on(detector.detect_threats_batch(&transactions)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_detect_batch);
+criterion_main!(benches);
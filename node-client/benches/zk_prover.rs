@@ -0,0 +1,46 @@
+//! Threat proof generation and verification through the node's prover, with the default
+//! backend and MSM engine
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use dagshield_node::zk_prover::{ZKProver, ZKProverConfig};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::runtime::Runtime;
+
+fn bench_threat_proofs(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let model = dir.path().join("model.onnx");
+    std::fs::write(&model, [0x5a; 4096]).unwrap();
+
+    let mut prover = ZKProver::with_config(ZKProverConfig {
+        params_dir: dir.path().join("params"),
+        model_path: Some(model),
+        ..ZKProverConfig::default()
+    });
+    runtime.block_on(prover.initialize()).unwrap();
+
+    // Distinct transactions, so no proof comes from the proof cache
+    let next = AtomicU64::new(0);
+    let transaction = || format!("dagshield benchmark transaction {}", next.fetch_add(1, Ordering::Relaxed)).into_bytes();
+
+    c.bench_function("threat_proof_generation", |b| {
+        b.iter_batched(
+            || transaction(),
+            |data| runtime.block_on(prover.generate_threat_proof(&data, 0.9, "bench-node")).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let proof = runtime.block_on(prover.generate_threat_proof(&transaction(), 0.9, "bench-node")).unwrap();
+    c.bench_function("threat_proof_verification", |b| {
+        b.iter(|| assert!(runtime.block_on(prover.verify_threat_proof(&proof)).unwrap()))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    // A proof takes seconds, so fewer samples than criterion's default 100
+    config = Criterion::default().sample_size(10);
+    targets = bench_threat_proofs
+}
+criterion_main!(benches);
@@ -10,6 +10,13 @@ use tracing::{debug, info, warn, error};
 
 use crate::config::AIConfig;
 use crate::dag::Transaction;
+use crate::detection::{
+    feedback::FeedbackStore,
+    registry::{DetectorRegistry, DetectorStats},
+    reputation::ReputationStore,
+    scoring::{self, ScoringModel},
+    Detection, ThreatCategory, TxContext,
+};
 use crate::node::BenchmarkResults;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +28,69 @@ pub struct ThreatDetectionResult {
     pub recommended_action: String,
 }
 
+/// How long a scored detection waits for the node to report it
+const SCORED_TTL: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Reportable detection of a transaction, kept until the node reports or drops it
+#[derive(Debug, Clone)]
+pub struct ScoredDetection {
+    pub detection: Detection,
+    /// Detector outputs the scoring model combined into `detection`
+    pub contributions: Vec<Detection>,
+    scored_at: std::time::Instant,
+}
+
+/// Model inputs for `transaction`, in the layout the node's model is trained on
+pub fn extract_features(transaction: &Transaction) -> Vec<f32> {
+    let mut features = Vec::new();
+    
+    // Transaction metadata features
+    features.push(transaction.data.len() as f32);
+    features.push(transaction.timestamp as f32);
+    features.push(transaction.chain_id as f32);
+    
+    // Address features (simplified)
+    features.push(transaction.from.len() as f32);
+    features.push(transaction.to.len() as f32);
+    features.push(transaction.target_address.len() as f32);
+    
+    // Data pattern features
+    let data_entropy = calculate_entropy(&transaction.data);
+    features.push(data_entropy);
+    
+    // Behavioral features
+    features.push(if transaction.dependencies.is_empty() { 0.0 } else { 1.0 });
+    features.push(transaction.dependencies.len() as f32);
+    
+    // Pad or truncate to expected model input size
+    features.resize(512, 0.0); // Assuming model expects 512 features
+    
+    features
+}
+
+fn calculate_entropy(data: &[u8]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    
+    let mut counts = [0u32; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    
+    let len = data.len() as f32;
+    let mut entropy = 0.0;
+    
+    for &count in &counts {
+        if count > 0 {
+            let p = count as f32 / len;
+            entropy -= p * p.log2();
+        }
+    }
+    
+    entropy
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreatPattern {
     pub pattern_id: String,
@@ -30,12 +100,28 @@ pub struct ThreatPattern {
     pub last_updated: u64,
 }
 
+/// ONNX session for the model at `path`, with the node's optimizations
+pub fn build_session(path: impl AsRef<std::path::Path>) -> Result<Session> {
+    Ok(SessionBuilder::new()?
+        .with_optimization_level(GraphOptimizationLevel::All)?
+        .with_intra_threads(4)?
+        .with_execution_providers([ExecutionProvider::CPU(Default::default())])?
+        .commit_from_file(path)?)
+}
+
 pub struct ThreatDetector {
     config: AIConfig,
     model_session: Arc<RwLock<Option<Session>>>,
     threat_patterns: Arc<RwLock<HashMap<String, ThreatPattern>>>,
     detection_cache: Arc<RwLock<HashMap<String, ThreatDetectionResult>>>,
     model_stats: Arc<RwLock<ModelStats>>,
+    detectors: Option<Arc<DetectorRegistry>>,
+    scoring: ScoringModel,
+    feedback: Option<Arc<FeedbackStore>>,
+    /// Reportable detections by DAG transaction id, for the node to record and broadcast once
+    /// they are reported
+    scored: Arc<RwLock<HashMap<String, ScoredDetection>>>,
+    reputation: Option<Arc<ReputationStore>>,
 }
 
 #[derive(Debug, Clone)]
@@ -75,6 +161,11 @@ impl ThreatDetector {
             threat_patterns: Arc::new(RwLock::new(HashMap::new())),
             detection_cache: Arc::new(RwLock::new(HashMap::new())),
             model_stats: Arc::new(RwLock::new(ModelStats::default())),
+            detectors: None,
+            scoring: ScoringModel::default(),
+            feedback: None,
+            scored: Arc::new(RwLock::new(HashMap::new())),
+            reputation: None,
         };
         
         // Load AI model
@@ -87,6 +178,48 @@ impl ThreatDetector {
         Ok(detector)
     }
     
+    /// Also run the registered detectors, like analyst-written rules, scored together with the model
+    pub fn with_detectors(mut self, detectors: Arc<DetectorRegistry>) -> Self {
+        self.detectors = Some(detectors);
+        self
+    }
+    
+    /// Counters of the registered detectors
+    pub fn detector_stats(&self) -> Vec<DetectorStats> {
+        self.detectors.as_ref().map(|detectors| detectors.stats()).unwrap_or_default()
+    }
+    
+    /// Combine the model and detectors with `scoring` instead of the default weights
+    pub fn with_scoring(mut self, scoring: ScoringModel) -> Self {
+        self.scoring = scoring;
+        self
+    }
+    
+    /// Keep reportable detections so the node can record the ones it reports for labelling
+    pub fn with_feedback(mut self, feedback: Arc<FeedbackStore>) -> Self {
+        self.feedback = Some(feedback);
+        self
+    }
+    
+    pub fn feedback(&self) -> Option<&Arc<FeedbackStore>> {
+        self.feedback.as_ref()
+    }
+    
+    /// Reportable detection of the DAG transaction `transaction_id`, if it was scored recently
+    pub async fn take_scored(&self, transaction_id: &str) -> Option<ScoredDetection> {
+        self.scored.write().await.remove(transaction_id)
+    }
+    
+    pub fn reputation(&self) -> Option<&Arc<ReputationStore>> {
+        self.reputation.as_ref()
+    }
+    
+    /// Flag the targets of submitted detections in the reputation store
+    pub fn with_reputation(mut self, reputation: Arc<ReputationStore>) -> Self {
+        self.reputation = Some(reputation);
+        self
+    }
+    
     async fn load_model(&self) -> Result<()> {
         info!("📥 Loading AI model from: {}", self.config.model_path);
         
@@ -97,12 +230,7 @@ impl ThreatDetector {
             return Ok(());
         }
         
-        // Create session with optimizations
-        let session = SessionBuilder::new()?
-            .with_optimization_level(GraphOptimizationLevel::All)?
-            .with_intra_threads(4)?
-            .with_execution_providers([ExecutionProvider::CPU(Default::default())])?
-            .commit_from_file(&self.config.model_path)?;
+        let session = build_session(&self.config.model_path)?;
         
         let mut model_session = self.model_session.write().await;
         *model_session = Some(session);
@@ -111,6 +239,12 @@ impl ThreatDetector {
         Ok(())
     }
     
+    /// Replace the active model; detections in flight finish on the old one
+    pub async fn swap_model(&self, session: Session) {
+        *self.model_session.write().await = Some(session);
+        self.detection_cache.write().await.clear();
+    }
+    
     async fn create_dummy_model(&self) -> Result<()> {
         // For development/testing, create a simple rule-based detector
         info!("🔧 Using rule-based threat detection for development");
@@ -189,12 +323,49 @@ impl ThreatDetector {
         }
         
         // Perform threat detection
-        let result = if self.model_session.read().await.is_some() {
+        let mut result = if self.model_session.read().await.is_some() {
             self.detect_with_ai_model(transaction).await?
         } else {
             self.detect_with_rules(transaction).await?
         };
         
+        // Registered detectors catch what the model was not trained on; the scoring model
+        // merges them with the prediction into one confidence
+        let mut detections: Vec<Detection> = ThreatCategory::from_label(&result.threat_type)
+            .map(|category| Detection {
+                detector: "model".to_string(),
+                threat_type: category,
+                confidence: result.confidence as f64,
+                tx_hash: None,
+                target: transaction.target_address.parse().ok(),
+                url: None,
+                explanation: result.explanation.clone(),
+                evidence: None,
+            })
+            .into_iter()
+            .collect();
+        if let Some(detectors) = &self.detectors {
+            detections.extend(detectors.run(&TxContext::from(transaction)).await);
+        }
+        if let Some(score) = self.scoring.score(&detections) {
+            if score.confidence as f32 > self.config.confidence_threshold {
+                let mut scored = self.scored.write().await;
+                scored.retain(|_, scored| scored.scored_at.elapsed() < SCORED_TTL);
+                scored.insert(transaction.id.clone(), ScoredDetection {
+                    detection: score.clone(),
+                    contributions: detections.clone(),
+                    scored_at: std::time::Instant::now(),
+                });
+            }
+            result = ThreatDetectionResult {
+                threat_type: score.threat_type.to_string(),
+                confidence: score.confidence as f32,
+                risk_score: scoring::risk_score(score.confidence),
+                explanation: format!("{} ({})", score.explanation, score.detector),
+                recommended_action: scoring::recommended_action(score.confidence).to_string(),
+            };
+        }
+        
         // Update cache
         {
             let mut cache = self.detection_cache.write().await;
@@ -216,7 +387,7 @@ impl ThreatDetector {
         let session = session_guard.as_ref().unwrap();
         
         // Prepare input features
-        let features = self.extract_features(transaction).await?;
+        let features = extract_features(transaction);
         let input_tensor = self.features_to_tensor(&features)?;
         
         // Run inference
@@ -263,21 +434,12 @@ impl ThreatDetector {
             }
         }
         
-        let risk_score = (max_confidence * 100.0) as u32;
-        let recommended_action = if max_confidence > 0.8 {
-            "Block transaction immediately"
-        } else if max_confidence > 0.5 {
-            "Flag for manual review"
-        } else {
-            "Monitor closely"
-        }.to_string();
-        
         Ok(ThreatDetectionResult {
             threat_type: detected_threat,
             confidence: max_confidence,
-            risk_score,
+            risk_score: scoring::risk_score(max_confidence as f64),
             explanation,
-            recommended_action,
+            recommended_action: scoring::recommended_action(max_confidence as f64).to_string(),
         })
     }
     
@@ -307,56 +469,6 @@ impl ThreatDetector {
         }
     }
     
-    async fn extract_features(&self, transaction: &Transaction) -> Result<Vec<f32>> {
-        let mut features = Vec::new();
-        
-        // Transaction metadata features
-        features.push(transaction.data.len() as f32);
-        features.push(transaction.timestamp as f32);
-        features.push(transaction.chain_id as f32);
-        
-        // Address features (simplified)
-        features.push(transaction.from.len() as f32);
-        features.push(transaction.to.len() as f32);
-        features.push(transaction.target_address.len() as f32);
-        
-        // Data pattern features
-        let data_entropy = self.calculate_entropy(&transaction.data);
-        features.push(data_entropy);
-        
-        // Behavioral features
-        features.push(if transaction.dependencies.is_empty() { 0.0 } else { 1.0 });
-        features.push(transaction.dependencies.len() as f32);
-        
-        // Pad or truncate to expected model input size
-        features.resize(512, 0.0); // Assuming model expects 512 features
-        
-        Ok(features)
-    }
-    
-    fn calculate_entropy(&self, data: &[u8]) -> f32 {
-        if data.is_empty() {
-            return 0.0;
-        }
-        
-        let mut counts = [0u32; 256];
-        for &byte in data {
-            counts[byte as usize] += 1;
-        }
-        
-        let len = data.len() as f32;
-        let mut entropy = 0.0;
-        
-        for &count in &counts {
-            if count > 0 {
-                let p = count as f32 / len;
-                entropy -= p * p.log2();
-            }
-        }
-        
-        entropy
-    }
-    
     fn features_to_tensor(&self, features: &[f32]) -> Result<Value> {
         let shape = vec![1, features.len()]; // Batch size 1
         let tensor = Value::from_array(([1, features.len()], features.to_vec()))?;
@@ -385,15 +497,9 @@ impl ThreatDetector {
         Ok(ThreatDetectionResult {
             threat_type,
             confidence: max_prob,
-            risk_score: (max_prob * 100.0) as u32,
+            risk_score: scoring::risk_score(max_prob as f64),
             explanation: format!("AI model prediction with {:.2}% confidence", max_prob * 100.0),
-            recommended_action: if max_prob > 0.8 {
-                "Block transaction"
-            } else if max_prob > 0.5 {
-                "Review manually"
-            } else {
-                "Monitor"
-            }.to_string(),
+            recommended_action: scoring::recommended_action(max_prob as f64).to_string(),
         })
     }
     
@@ -0,0 +1,617 @@
+//! REST API for node operators
+//!
+//! Serves the quarantine review queue as JSON, so an operator or a companion dashboard can work
+//! through borderline detections: list them, look one up, and approve, reject or escalate it.
+//! Reviews are attributed to the reviewer whose token the request carries, so an escalated
+//! detection's second review needs a different reviewer's token.
+//! `/feedback` lists the detections the node reported, by the id they have on-chain, and
+//! `POST /feedback/:threat_hash/false_positive` (or `/confirmed`) labels one while the node runs;
+//! `/feedback/precision` returns each detector family's precision per period.
+//! `/ws/threats` streams threat events over a WebSocket for wallets and dApps running alongside
+//! the node, optionally filtered by `min_confidence` and `category`. `/energy/forecast` returns
+//! the next-24h power, carbon and battery prediction from the power monitor, and
+//! `/energy/attestation` the attested hardware with current battery health. Once the node's own
+//! detector finds a posted transaction a threat, attestation committee nodes answer
+//! `POST /attestations` with their signed threat proof of it, and `POST /disclosures` returns a
+//! threat proof disclosing only the metadata the node's disclosure policy allows.
+//! `POST /attestations/verify` and `POST /disclosures/verify` check such proofs.
+//! `GET /phishing?url=` scores a URL for phishing, publishing a threat event when it is reported.
+//! Requests need
+//! `Authorization: Bearer <token>`, with the shared token or a reviewer's, when either is
+//! configured; browsers, which can't set headers on WebSockets, may pass it as `access_token`
+//! instead.
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Extension, Path, Query, Request, State,
+    },
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use ethers::{signers::LocalWallet, types::H256};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, info};
+
+use crate::ai::{ThreatDetectionResult, ThreatDetector};
+use crate::dag::Transaction;
+use crate::detection::phishing::PhishingDetector;
+use crate::detection::events::{EventFilter, ThreatEvent, ThreatEvents};
+use crate::detection::feedback::{FeedbackStore, LabelSource, Verdict};
+use crate::detection::quarantine::{Quarantine, QuarantineStatus, ReviewAction, ReviewError};
+use crate::energy_monitor::EnergyMonitor;
+use crate::zk_prover::{attestation::AttestedThreatReport, disclosure::ThreatMetadata, DisclosureProof, ZKProver};
+
+/// REST API settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub listen: SocketAddr,
+    /// Bearer token every request must carry; `None` leaves the API open unless reviewers are set
+    pub token: Option<String>,
+    /// Quarantine reviewers by name, each with their own bearer token
+    #[serde(default)]
+    pub reviewers: HashMap<String, String>,
+    /// Threat events buffered per WebSocket subscriber before it misses some
+    pub event_buffer: usize,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            token: None,
+            reviewers: HashMap::new(),
+            event_buffer: 256,
+        }
+    }
+}
+
+/// What the handlers reach into
+#[derive(Clone)]
+pub struct ApiState {
+    pub token: Option<String>,
+    /// Reviewer name => bearer token
+    pub reviewers: Arc<HashMap<String, String>>,
+    pub quarantine: Option<Arc<Quarantine>>,
+    pub feedback: Option<Arc<FeedbackStore>>,
+    pub events: Option<ThreatEvents>,
+    pub energy: Option<Arc<EnergyMonitor>>,
+    pub prover: Option<Arc<ZKProver>>,
+    pub detector: Option<LocalDetector>,
+    pub phishing: Option<Arc<PhishingDetector>>,
+}
+
+/// This node's own detection, which attestations and disclosure proofs are made from
+#[derive(Clone)]
+pub struct LocalDetector {
+    pub node_id: String,
+    pub detector: Arc<ThreatDetector>,
+    /// Confidence a detection must exceed to be proven
+    pub confidence_threshold: f32,
+    /// Node wallet attestations are signed with; none without U2U
+    pub wallet: Option<LocalWallet>,
+}
+
+impl LocalDetector {
+    /// Detection of `transaction`, refused unless it is a threat here; proofs never carry a
+    /// confidence the caller claims
+    async fn detect(&self, transaction: &Transaction) -> Result<ThreatDetectionResult, ApiError> {
+        let result = self.detector.detect_threat(transaction).await?;
+        if result.confidence <= self.confidence_threshold {
+            return Err(ApiError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Not a threat here (confidence {:.2})", result.confidence),
+            ));
+        }
+        Ok(result)
+    }
+}
+
+/// Error as a JSON body with its status
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+    }
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    status: Option<QuarantineStatus>,
+}
+
+#[derive(Deserialize)]
+struct UrlQuery {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct FeedbackQuery {
+    /// Only detections from this Unix time on
+    since: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct PrecisionQuery {
+    #[serde(default = "default_period_days")]
+    period_days: i64,
+}
+
+fn default_period_days() -> i64 {
+    7
+}
+
+#[derive(Default, Deserialize)]
+struct LabelBody {
+    note: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct ReviewBody {
+    note: Option<String>,
+}
+
+/// Reviewer the request's token belongs to; none for the shared token or an open API
+#[derive(Clone)]
+struct Reviewer(Option<String>);
+
+fn quarantine(state: &ApiState) -> Result<&Quarantine, ApiError> {
+    state
+        .quarantine
+        .as_deref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Quarantine is not enabled".to_string()))
+}
+
+fn parse_id(id: &str) -> Result<H256, ApiError> {
+    id.parse().map_err(|_| ApiError(StatusCode::BAD_REQUEST, format!("Bad detection id {:?}", id)))
+}
+
+async fn list_quarantine(State(state): State<ApiState>, Query(query): Query<ListQuery>) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(quarantine(&state)?.list(query.status)?))
+}
+
+async fn get_quarantined(State(state): State<ApiState>, Path(id): Path<String>) -> Result<impl IntoResponse, ApiError> {
+    let id = parse_id(&id)?;
+    match quarantine(&state)?.get(&id)? {
+        Some(entry) => Ok(Json(entry)),
+        None => Err(ApiError(StatusCode::NOT_FOUND, format!("No quarantined detection {:?}", id))),
+    }
+}
+
+async fn review_quarantined(
+    State(state): State<ApiState>,
+    Path((id, action)): Path<(String, String)>,
+    Extension(Reviewer(reviewer)): Extension<Reviewer>,
+    body: Option<Json<ReviewBody>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let action = match action.as_str() {
+        "approve" => ReviewAction::Approve,
+        "reject" => ReviewAction::Reject,
+        "escalate" => ReviewAction::Escalate,
+        _ => return Err(ApiError(StatusCode::NOT_FOUND, format!("Unknown review action {:?}", action))),
+    };
+    let id = parse_id(&id)?;
+    let Json(body) = body.unwrap_or_default();
+    match quarantine(&state)?.review(&id, action, reviewer, body.note) {
+        Ok(entry) => Ok(Json(entry)),
+        Err(e @ ReviewError::NotFound(_)) => Err(ApiError(StatusCode::NOT_FOUND, e.to_string())),
+        Err(e @ (ReviewError::Closed(..) | ReviewError::SameReviewer(_))) => Err(ApiError(StatusCode::CONFLICT, e.to_string())),
+        Err(e @ ReviewError::ReviewerRequired) => Err(ApiError(StatusCode::BAD_REQUEST, e.to_string())),
+        Err(ReviewError::Store(e)) => Err(e.into()),
+    }
+}
+
+fn feedback(state: &ApiState) -> Result<&FeedbackStore, ApiError> {
+    state
+        .feedback
+        .as_deref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Feedback is not enabled".to_string()))
+}
+
+async fn list_feedback(State(state): State<ApiState>, Query(query): Query<FeedbackQuery>) -> Result<impl IntoResponse, ApiError> {
+    let mut records = feedback(&state)?.records()?;
+    records.retain(|record| record.detected_at >= query.since.unwrap_or(i64::MIN));
+    records.sort_by_key(|record| record.detected_at);
+    Ok(Json(records))
+}
+
+async fn detector_precision(State(state): State<ApiState>, Query(query): Query<PrecisionQuery>) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(feedback(&state)?.precision_report(query.period_days.max(1) * 86_400)?))
+}
+
+async fn get_feedback(State(state): State<ApiState>, Path(threat_hash): Path<String>) -> Result<impl IntoResponse, ApiError> {
+    let threat_hash = parse_id(&threat_hash)?;
+    match feedback(&state)?.get(&threat_hash)? {
+        Some(record) => Ok(Json(record)),
+        None => Err(ApiError(StatusCode::NOT_FOUND, format!("No reported detection {:?}", threat_hash))),
+    }
+}
+
+async fn label_feedback(
+    State(state): State<ApiState>,
+    Path((threat_hash, verdict)): Path<(String, String)>,
+    body: Option<Json<LabelBody>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let verdict = match verdict.as_str() {
+        "false_positive" => Verdict::FalsePositive,
+        "confirmed" => Verdict::Confirmed,
+        _ => return Err(ApiError(StatusCode::NOT_FOUND, format!("Unknown verdict {:?}", verdict))),
+    };
+    let threat_hash = parse_id(&threat_hash)?;
+    let feedback = feedback(&state)?;
+    if feedback.get(&threat_hash)?.is_none() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("No reported detection {:?}", threat_hash)));
+    }
+    let Json(body) = body.unwrap_or_default();
+    Ok(Json(feedback.label(&threat_hash, verdict, LabelSource::Manual, body.note)?))
+}
+
+async fn energy_forecast(State(state): State<ApiState>) -> Result<impl IntoResponse, ApiError> {
+    let energy = state
+        .energy
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Power monitoring is not enabled".to_string()))?;
+    Ok(Json(energy.get_energy_forecast().await?))
+}
+
+async fn energy_attestation(State(state): State<ApiState>) -> Result<impl IntoResponse, ApiError> {
+    let energy = state
+        .energy
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Power monitoring is not enabled".to_string()))?;
+    Ok(Json(energy.hardware_attestation()))
+}
+
+fn prover(state: &ApiState) -> Result<&ZKProver, ApiError> {
+    state
+        .prover
+        .as_deref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "ZK proving is not enabled".to_string()))
+}
+
+/// The prover for the verify endpoints: a disabled one would accept any proof
+fn verifier(state: &ApiState) -> Result<&ZKProver, ApiError> {
+    let prover = prover(state)?;
+    if !prover.enabled {
+        return Err(ApiError(StatusCode::SERVICE_UNAVAILABLE, "ZK verification is disabled".to_string()));
+    }
+    Ok(prover)
+}
+
+fn local_detector(state: &ApiState) -> Result<&LocalDetector, ApiError> {
+    state
+        .detector
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "AI detection is not enabled".to_string()))
+}
+
+async fn attest_threat(State(state): State<ApiState>, Json(transaction): Json<Transaction>) -> Result<impl IntoResponse, ApiError> {
+    let prover = prover(&state)?;
+    let local = local_detector(&state)?;
+    let wallet = local
+        .wallet
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Threat attestation needs U2U".to_string()))?;
+    let result = local.detect(&transaction).await?;
+    let attestation = prover
+        .attest_threat(&transaction.data, result.confidence as f64, &local.node_id, wallet)
+        .await
+        .map_err(|e| ApiError(StatusCode::FORBIDDEN, format!("{:#}", e)))?;
+    Ok(Json(attestation))
+}
+
+async fn disclose_threat(State(state): State<ApiState>, Json(transaction): Json<Transaction>) -> Result<impl IntoResponse, ApiError> {
+    let prover = prover(&state)?;
+    let local = local_detector(&state)?;
+    let result = local.detect(&transaction).await?;
+    let metadata = ThreatMetadata {
+        chain_id: transaction.chain_id,
+        contract_address: transaction.target_address.parse().map_err(|_| {
+            ApiError(StatusCode::BAD_REQUEST, format!("Bad target address {:?}", transaction.target_address))
+        })?,
+        threat_category: result.threat_type.clone(),
+    };
+    let proof = prover
+        .generate_disclosure_proof(&transaction.data, result.confidence as f64, &metadata, &local.node_id)
+        .await?;
+    Ok(Json(proof))
+}
+
+async fn verify_disclosure(State(state): State<ApiState>, Json(proof): Json<DisclosureProof>) -> Result<impl IntoResponse, ApiError> {
+    let valid = verifier(&state)?.verify_disclosure_proof(&proof).await?;
+    Ok(Json(serde_json::json!({ "valid": valid })))
+}
+
+async fn verify_attested_report(
+    State(state): State<ApiState>,
+    Json(report): Json<AttestedThreatReport>,
+) -> Result<impl IntoResponse, ApiError> {
+    let valid = verifier(&state)?.verify_attested_report(&report).await?;
+    Ok(Json(serde_json::json!({ "valid": valid })))
+}
+
+async fn check_url(State(state): State<ApiState>, Query(query): Query<UrlQuery>) -> Result<impl IntoResponse, ApiError> {
+    let phishing = state
+        .phishing
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Phishing detection is not enabled".to_string()))?;
+    let analysis = phishing
+        .analyze(&query.url)
+        .await
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("{:#}", e)))?;
+    if let (Some(detection), Some(events)) = (phishing.report(&analysis), &state.events) {
+        events.publish(ThreatEvent::detection(&detection, None));
+    }
+    Ok(Json(analysis))
+}
+
+async fn threat_stream(
+    State(state): State<ApiState>,
+    Query(filter): Query<EventFilter>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let events = state
+        .events
+        .as_ref()
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Threat events are not enabled".to_string()))?
+        .subscribe();
+    Ok(ws.on_upgrade(move |socket| stream_events(socket, events, filter)))
+}
+
+/// Forward matching events to `socket` until either side closes
+async fn stream_events(mut socket: WebSocket, mut events: broadcast::Receiver<ThreatEvent>, filter: EventFilter) {
+    debug!("Threat event subscriber connected");
+    loop {
+        let event = tokio::select! {
+            received = events.recv() => match received {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => ThreatEvent::Lagged { missed },
+                Err(RecvError::Closed) => break,
+            },
+            // Subscribers only listen; anything but a close is ignored
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        if !filter.matches(&event) {
+            continue;
+        }
+        let Ok(text) = serde_json::to_string(&event) else { continue };
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+    debug!("Threat event subscriber disconnected");
+}
+
+async fn authorize(State(state): State<ApiState>, mut request: Request, next: Next) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let access_token = request
+        .uri()
+        .query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .find_map(|pair| pair.strip_prefix("access_token="));
+    let presented = bearer.or(access_token);
+    let reviewer = presented.and_then(|presented| {
+        state
+            .reviewers
+            .iter()
+            .find(|(_, token)| token.as_str() == presented)
+            .map(|(name, _)| name.clone())
+    });
+    let open = state.token.is_none() && state.reviewers.is_empty();
+    if open || reviewer.is_some() || (state.token.is_some() && presented == state.token.as_deref()) {
+        request.extensions_mut().insert(Reviewer(reviewer));
+        next.run(request).await
+    } else {
+        ApiError(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token".to_string()).into_response()
+    }
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/quarantine", get(list_quarantine))
+        .route("/quarantine/:id", get(get_quarantined))
+        .route("/quarantine/:id/:action", post(review_quarantined))
+        .route("/feedback", get(list_feedback))
+        .route("/feedback/precision", get(detector_precision))
+        .route("/feedback/:threat_hash", get(get_feedback))
+        .route("/feedback/:threat_hash/:verdict", post(label_feedback))
+        .route("/energy/forecast", get(energy_forecast))
+        .route("/energy/attestation", get(energy_attestation))
+        .route("/attestations", post(attest_threat))
+        .route("/attestations/verify", post(verify_attested_report))
+        .route("/disclosures", post(disclose_threat))
+        .route("/disclosures/verify", post(verify_disclosure))
+        .route("/phishing", get(check_url))
+        .route("/ws/threats", get(threat_stream))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// Serve the API on `config.listen` until the listener fails
+pub async fn serve(config: &ApiConfig, state: ApiState) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(config.listen)
+        .await
+        .with_context(|| format!("Failed to listen for API requests on {}", config.listen))?;
+    info!("🛎️ REST API available at http://{}", config.listen);
+    axum::serve(listener, router(state)).await.context("REST API stopped")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::{Detection, ThreatCategory};
+    use futures::StreamExt;
+    use tokio_tungstenite::{connect_async, tungstenite};
+
+    #[tokio::test]
+    async fn test_threat_stream() {
+        let events = ThreatEvents::new(16);
+        let state = ApiState {
+            token: Some("secret".to_string()),
+            reviewers: Arc::default(),
+            quarantine: None,
+            feedback: None,
+            events: Some(events.clone()),
+            energy: None,
+            prover: None,
+            detector: None,
+            phishing: None,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        assert!(connect_async(format!("ws://{}/ws/threats", addr)).await.is_err());
+        let url = format!("ws://{}/ws/threats?access_token=secret&min_confidence=0.8", addr);
+        let (mut socket, _) = connect_async(url).await.unwrap();
+        while events.subscribers() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let detection = |confidence| Detection {
+            detector: "rules:phishing".to_string(),
+            threat_type: ThreatCategory::Phishing,
+            confidence,
+            tx_hash: None,
+            target: None,
+            url: Some("https://claim-airdrop.example".to_string()),
+            explanation: "lure".to_string(),
+            evidence: None,
+        };
+        events.publish(ThreatEvent::detection(&detection(0.5), None));
+        events.publish(ThreatEvent::detection(&detection(0.9), None));
+
+        let Some(Ok(tungstenite::Message::Text(text))) = socket.next().await else { panic!("no event") };
+        let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!((event["event"].as_str(), event["confidence"].as_f64()), (Some("detection"), Some(0.9)));
+        assert_eq!(event["category"], "phishing");
+    }
+
+    #[tokio::test]
+    async fn test_reviews_are_attributed_to_the_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let quarantine = Quarantine::open(crate::detection::quarantine::QuarantineConfig {
+            enabled: true,
+            path: dir.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+        let transaction = Transaction {
+            id: "tx1".to_string(),
+            from: String::new(),
+            to: "0xabc".to_string(),
+            target_address: "0xabc".to_string(),
+            chain_id: 1,
+            data: Vec::new(),
+            timestamp: 0,
+            dependencies: Vec::new(),
+        };
+        let entry = quarantine.hold(&transaction, "phishing", 0.55, "lure", None).unwrap();
+        let reviewers = [("alice", "alice-token"), ("bob", "bob-token")]
+            .into_iter()
+            .map(|(name, token)| (name.to_string(), token.to_string()))
+            .collect();
+        let state = ApiState {
+            token: Some("secret".to_string()),
+            reviewers: Arc::new(reviewers),
+            quarantine: Some(Arc::new(quarantine)),
+            feedback: None,
+            events: None,
+            energy: None,
+            prover: None,
+            detector: None,
+            phishing: None,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        let client = reqwest::Client::new();
+        let review = |action: &str, token: &str| {
+            client
+                .post(format!("http://{}/quarantine/{:?}/{}", addr, entry.id, action))
+                .bearer_auth(token)
+                .json(&serde_json::json!({ "reviewer": "bob" }))
+                .send()
+        };
+
+        assert_eq!(review("escalate", "alice-token").await.unwrap().status(), StatusCode::OK);
+        // A name in the body doesn't make the shared token or alice's anyone else
+        assert_eq!(review("approve", "secret").await.unwrap().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(review("approve", "alice-token").await.unwrap().status(), StatusCode::CONFLICT);
+        assert_eq!(review("approve", "wrong").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let approved: serde_json::Value = review("approve", "bob-token").await.unwrap().json().await.unwrap();
+        assert_eq!(approved["status"], "approved");
+        assert_eq!(approved["reviews"][0]["reviewer"], "alice");
+        assert_eq!(approved["reviews"][1]["reviewer"], "bob");
+    }
+
+    #[tokio::test]
+    async fn test_verify_refused_while_zk_is_disabled() {
+        let state = ApiState {
+            token: Some("secret".to_string()),
+            reviewers: Arc::default(),
+            quarantine: None,
+            feedback: None,
+            events: None,
+            energy: None,
+            prover: Some(Arc::new(ZKProver::new(false))),
+            detector: None,
+            phishing: None,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        let disclosure = DisclosureProof {
+            proof: Vec::new(),
+            public_inputs: Vec::new(),
+            metadata_commitment: "0x00".to_string(),
+            disclosed: Default::default(),
+            public_input_encoding: 0,
+            backend: Default::default(),
+            verification_key_hash: String::new(),
+            timestamp: 0,
+            expires_at: 0,
+            node_id: "node".to_string(),
+        };
+        let report = AttestedThreatReport {
+            threat_hash: "0x00".to_string(),
+            attestations: Vec::new(),
+        };
+        let client = reqwest::Client::new();
+        let verify = |path: &str, body: serde_json::Value| {
+            client.post(format!("http://{}{}", addr, path)).bearer_auth("secret").json(&body).send()
+        };
+
+        // A disabled prover would otherwise call any proof valid
+        let response = verify("/disclosures/verify", serde_json::to_value(&disclosure).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = verify("/attestations/verify", serde_json::to_value(&report).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -1,7 +1,8 @@
 //! Blockchain client for interacting with DAGShield smart contracts
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ethers::{
+    contract::parse_log,
     prelude::*,
     providers::{Http, Provider},
     signers::{LocalWallet, Signer},
@@ -11,6 +12,7 @@ use std::sync::Arc;
 use tracing::{debug, info, warn, error};
 
 use crate::config::BlockchainConfig;
+use crate::detection::{calibration::ThreatOracle, Detection};
 use crate::node::Challenge;
 
 // ABI for DAGShield contract (simplified)
@@ -88,13 +90,14 @@ impl BlockchainClient {
         Ok(format!("{:?}", tx_hash))
     }
     
+    /// Report a threat through `reportThreat`, returning the alert id it was recorded under
     pub async fn report_threat(
         &self,
         threat_type: &str,
         target_address: &str,
         confidence: u32,
         chain_id: u64,
-    ) -> Result<String> {
+    ) -> Result<H256> {
         debug!("🚨 Reporting threat: {} (confidence: {}%)", threat_type, confidence);
         
         let tx = self.contract
@@ -109,11 +112,67 @@ impl BlockchainClient {
             .send()
             .await?;
         
-        let receipt = tx.await?;
-        let tx_hash = receipt.unwrap().transaction_hash;
+        let receipt = tx.await?.context("Threat report dropped from the mempool")?;
+        let alert_id = receipt
+            .logs
+            .into_iter()
+            .find_map(|log| parse_log::<ThreatDetectedFilter>(log).ok())
+            .map(|event| H256(event.alert_id))
+            .context("Threat report emitted no ThreatDetected event")?;
         
-        debug!("✅ Threat reported successfully: {:?}", tx_hash);
-        Ok(format!("{:?}", tx_hash))
+        debug!("✅ Threat reported successfully: {:?} as {:?}", receipt.transaction_hash, alert_id);
+        Ok(alert_id)
+    }
+    
+    /// Submit a reported detection on `chain_id` to DAGOracle, with the report's on-chain id as
+    /// evidence hash so its verdict can be matched back; returns the transaction hash, or None
+    /// when the oracle does not take reports for the chain at the detection's confidence
+    pub async fn submit_oracle_report(&self, oracle: Address, detection: &Detection, chain_id: u64, evidence_hash: H256) -> Result<Option<H256>> {
+        let target = detection.target.context("Oracle reports need a target contract")?;
+        let threat_level = (detection.confidence * 10.0).ceil().clamp(1.0, 10.0) as u8;
+        let threat_type = detection.threat_type.code();
+        let confidence = (detection.confidence * 100.0).round().clamp(0.0, 100.0) as u8;
+        
+        let oracle = ThreatOracle::new(oracle, self.contract.client());
+        let (active, min_confidence, _, _) = oracle
+            .chain_configs(U256::from(chain_id))
+            .call()
+            .await
+            .context("Failed to read the oracle's chain config")?;
+        if !active || U256::from(confidence) < min_confidence {
+            debug!(
+                "🔮 Oracle takes no reports on chain {} at confidence {}, skipping {:?}",
+                chain_id, confidence, evidence_hash
+            );
+            return Ok(None);
+        }
+        
+        // The oracle recovers the personal-sign signature over these fields, tightly packed
+        let mut packed = [0u8; 32].to_vec();
+        U256::from(chain_id).to_big_endian(&mut packed);
+        packed.extend_from_slice(target.as_bytes());
+        packed.extend_from_slice(&[threat_level, threat_type]);
+        packed.extend_from_slice(evidence_hash.as_bytes());
+        let signature = self.wallet.sign_message(ethers::utils::keccak256(&packed)).await?;
+        
+        let tx = oracle
+            .submit_threat_report(
+                U256::from(chain_id),
+                target,
+                threat_level,
+                threat_type,
+                evidence_hash.0,
+                confidence,
+                signature.to_vec().into(),
+            )
+            .gas(self.config.gas_limit)
+            .gas_price(U256::from(self.config.gas_price_gwei) * U256::exp10(9))
+            .send()
+            .await?;
+        let receipt = tx.await?.context("Oracle report dropped from the mempool")?;
+        
+        debug!("🔮 Submitted oracle report {:?} as {:?}", evidence_hash, receipt.transaction_hash);
+        Ok(Some(receipt.transaction_hash))
     }
     
     pub async fn vote_on_threat(&self, alert_id: &str, support: bool) -> Result<String> {
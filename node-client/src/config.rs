@@ -4,6 +4,12 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+use crate::api::ApiConfig;
+use crate::detection::DetectionConfig;
+use crate::energy_monitor::profiles::EnergyMonitorConfig;
+use crate::u2u_integration::U2UConfig;
+use crate::zk_prover::ZKProverConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeConfig {
     pub node: NodeSettings,
@@ -13,6 +19,18 @@ pub struct NodeConfig {
     pub storage: StorageConfig,
     pub energy: EnergyConfig,
     pub metrics: MetricsConfig,
+    /// Measured power, per-subsystem attribution, budgets and battery policy
+    #[serde(default)]
+    pub energy_monitor: EnergyMonitorConfig,
+    #[serde(default)]
+    pub zk: ZKProverConfig,
+    #[serde(default)]
+    pub detection: DetectionConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// U2U DAG client for session keys, energy proofs and ZK key sync (disabled when unset)
+    #[serde(default)]
+    pub u2u: Option<U2UConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +138,14 @@ impl Default for NodeConfig {
                 port: 9090,
                 export_interval_secs: 60,
             },
+            energy_monitor: EnergyMonitorConfig::default(),
+            zk: ZKProverConfig {
+                model_path: Some("./models/threat_detection.onnx".into()),
+                ..ZKProverConfig::default()
+            },
+            detection: DetectionConfig::default(),
+            api: ApiConfig::default(),
+            u2u: None,
         }
     }
 }
@@ -127,7 +153,11 @@ impl Default for NodeConfig {
 impl NodeConfig {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let config: NodeConfig = toml::from_str(&content)?;
+        let mut config: NodeConfig = toml::from_str(&content)?;
+        // Threat proofs commit to the detection model unless another file is configured
+        if config.zk.model_path.is_none() {
+            config.zk.model_path = Some(config.ai.model_path.clone().into());
+        }
         Ok(config)
     }
     
@@ -12,7 +12,7 @@ use tracing::{debug, info, warn};
 use crate::config::NodeConfig;
 use crate::node::BenchmarkResults;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: String,
     pub from: String,
@@ -0,0 +1,273 @@
+/*!
+ * Threat detection beyond the AI model
+ * Detectors that analysts and operators can extend without retraining: declarative rules, phishing
+ * URL analysis, contract bytecode analysis, approval drainers, drainer kit fingerprints, honeypot
+ * simulation, rug-pull precursors on new tokens, mempool sandwiches, the synced database of
+ * confirmed threat signatures and threat intelligence feeds. Detectors look at transactions as a
+ * `TxContext` (or at URLs, contracts and tokens) and report `Detection`s, which are submitted
+ * through the U2U DAG like model detections. The ones looking at transactions plug into the
+ * detector registry, scoring merges their detections per transaction, false-positive feedback
+ * recalibrates its weights and oracle outcomes calibrate each detector's confidences. An ensemble
+ * runs several models and rule sets as one voting detector, and signed model releases from the
+ * oracle replace the model while the node runs. The reputation store profiles addresses for any
+ * detector weighing who a transaction involves, and borderline detections wait in quarantine for
+ * an operator's review. Reported detections and their oracle verdicts are broadcast as threat
+ * events and export as versioned threat reports
+ */
+
+use anyhow::Result;
+use ethers::types::{Address, Bytes, Transaction, H256, U256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub mod approvals;
+pub mod bytecode;
+pub mod calibration;
+pub mod drainer_kits;
+pub mod ensemble;
+pub mod events;
+pub mod feedback;
+pub mod honeypot;
+pub mod intel;
+pub mod mempool;
+pub mod model_updates;
+pub mod phishing;
+pub mod quarantine;
+pub mod registry;
+pub mod report;
+pub mod reputation;
+pub mod rug_pull;
+pub mod rules;
+pub mod scoring;
+pub mod signature_db;
+
+use approvals::ApprovalConfig;
+use bytecode::BytecodeConfig;
+use calibration::CalibrationConfig;
+use drainer_kits::DrainerKitConfig;
+use ensemble::EnsembleConfig;
+use feedback::FeedbackConfig;
+use honeypot::HoneypotConfig;
+use intel::IntelConfig;
+use mempool::MempoolConfig;
+use model_updates::ModelUpdateConfig;
+use phishing::PhishingConfig;
+use quarantine::QuarantineConfig;
+use registry::RegistryConfig;
+use reputation::ReputationConfig;
+use rug_pull::RugPullConfig;
+use rules::RulesConfig;
+use scoring::ScoringConfig;
+use signature_db::SignatureDbConfig;
+
+use crate::u2u_integration::U2UClient;
+
+/// Detection settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectionConfig {
+    #[serde(default)]
+    pub registry: RegistryConfig,
+    #[serde(default)]
+    pub rules: RulesConfig,
+    #[serde(default)]
+    pub phishing: PhishingConfig,
+    #[serde(default)]
+    pub bytecode: BytecodeConfig,
+    #[serde(default)]
+    pub approvals: ApprovalConfig,
+    #[serde(default)]
+    pub drainer_kits: DrainerKitConfig,
+    #[serde(default)]
+    pub honeypot: HoneypotConfig,
+    #[serde(default)]
+    pub rug_pull: RugPullConfig,
+    #[serde(default)]
+    pub mempool: MempoolConfig,
+    #[serde(default)]
+    pub signatures: SignatureDbConfig,
+    #[serde(default)]
+    pub intel: IntelConfig,
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+    #[serde(default)]
+    pub feedback: FeedbackConfig,
+    #[serde(default)]
+    pub calibration: CalibrationConfig,
+    #[serde(default)]
+    pub ensemble: EnsembleConfig,
+    #[serde(default)]
+    pub model_updates: ModelUpdateConfig,
+    #[serde(default)]
+    pub reputation: ReputationConfig,
+    #[serde(default)]
+    pub quarantine: QuarantineConfig,
+}
+
+/// Transaction as detectors see it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TxContext {
+    pub hash: Option<H256>,
+    pub chain_id: u64,
+    pub from: Address,
+    /// `None` for contract deployments
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+impl TxContext {
+    /// First four calldata bytes, if the call has them
+    pub fn selector(&self) -> Option<[u8; 4]> {
+        self.data.get(..4).map(|selector| selector.try_into().unwrap())
+    }
+}
+
+impl From<&Transaction> for TxContext {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            hash: Some(tx.hash),
+            chain_id: tx.chain_id.map_or(0, |chain_id| chain_id.as_u64()),
+            from: tx.from,
+            to: tx.to,
+            value: tx.value,
+            data: tx.input.clone(),
+        }
+    }
+}
+
+impl From<&TxContext> for crate::dag::Transaction {
+    /// Seen now, with no DAG dependencies; the model's features are taken over this
+    fn from(tx: &TxContext) -> Self {
+        let to = tx.to.map(|to| format!("{:?}", to)).unwrap_or_default();
+        Self {
+            id: tx.hash.map(|hash| format!("{:?}", hash)).unwrap_or_default(),
+            from: format!("{:?}", tx.from),
+            to: to.clone(),
+            target_address: to,
+            chain_id: tx.chain_id,
+            data: tx.data.to_vec(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+impl From<&crate::dag::Transaction> for TxContext {
+    /// DAG transactions carry no value; ids that aren't a transaction hash leave `hash` unset
+    /// and unparsable addresses become zero
+    fn from(tx: &crate::dag::Transaction) -> Self {
+        Self {
+            hash: tx.id.parse().ok(),
+            chain_id: tx.chain_id,
+            from: tx.from.parse().unwrap_or_default(),
+            to: tx.to.parse().ok(),
+            value: U256::zero(),
+            data: Bytes::from(tx.data.clone()),
+        }
+    }
+}
+
+/// Kind of threat, shared by every detector and the oracle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreatCategory {
+    Phishing,
+    #[serde(alias = "rugpull")]
+    RugPull,
+    /// Contract exploits, flash loan attacks and MEV extraction like sandwiching
+    #[serde(alias = "smart_contract_exploit", alias = "flash_loan_attack", alias = "sandwich_attack")]
+    Exploit,
+    /// Approval phishing that sweeps allowances
+    #[serde(alias = "approval_drainer")]
+    Drainer,
+    Honeypot,
+    /// Dusting, address poisoning and airdrop spam
+    Spam,
+}
+
+/// Label keywords and the category they name, checked in order
+const CATEGORY_KEYWORDS: &[(&str, ThreatCategory)] = &[
+    ("drain", ThreatCategory::Drainer),
+    ("approval", ThreatCategory::Drainer),
+    ("phish", ThreatCategory::Phishing),
+    ("scam", ThreatCategory::Phishing),
+    ("honeypot", ThreatCategory::Honeypot),
+    ("rug", ThreatCategory::RugPull),
+    ("exploit", ThreatCategory::Exploit),
+    ("flash", ThreatCategory::Exploit),
+    ("reentran", ThreatCategory::Exploit),
+    ("sandwich", ThreatCategory::Exploit),
+    ("front", ThreatCategory::Exploit),
+    ("mev", ThreatCategory::Exploit),
+    ("spam", ThreatCategory::Spam),
+    ("dust", ThreatCategory::Spam),
+    ("poison", ThreatCategory::Spam),
+];
+
+impl ThreatCategory {
+    pub const ALL: [Self; 6] = [Self::Phishing, Self::RugPull, Self::Exploit, Self::Drainer, Self::Honeypot, Self::Spam];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Phishing => "phishing",
+            Self::RugPull => "rug_pull",
+            Self::Exploit => "exploit",
+            Self::Drainer => "drainer",
+            Self::Honeypot => "honeypot",
+            Self::Spam => "spam",
+        }
+    }
+
+    /// Category a free-form label (feed tag, model class, pattern name) names, if any
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.to_ascii_lowercase();
+        CATEGORY_KEYWORDS.iter().find(|(keyword, _)| label.contains(keyword)).map(|&(_, category)| category)
+    }
+
+    /// `threatType` as the oracle stores it; 1-3 predate the other categories
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Phishing => 1,
+            Self::RugPull => 2,
+            Self::Exploit => 3,
+            Self::Drainer => 4,
+            Self::Honeypot => 5,
+            Self::Spam => 6,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.code() == code)
+    }
+}
+
+impl std::fmt::Display for ThreatCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One detector's finding about a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Detection {
+    /// Detector and the rule or heuristic that fired, like `rules:unlimited-approve`
+    pub detector: String,
+    pub threat_type: ThreatCategory,
+    pub confidence: f64,
+    pub tx_hash: Option<H256>,
+    /// Contract or account the threat is about
+    pub target: Option<Address>,
+    /// Site the threat is about, for phishing
+    #[serde(default)]
+    pub url: Option<String>,
+    pub explanation: String,
+    /// Hash of the data backing the finding, like a simulation trace
+    #[serde(default)]
+    pub evidence: Option<H256>,
+}
+
+/// Submit `detection` as threat data through the DAG, once per finding on a transaction even
+/// across reorgs; returns the DAG transaction id
+pub async fn submit(client: &U2UClient, detection: &Detection, node_id: &str) -> Result<String> {
+    client.submit_detection(detection, node_id).await
+}
@@ -0,0 +1,351 @@
+/*!
+ * Approval drainer detection
+ * Approval phishing gets a victim to grant a drainer unlimited allowance over their tokens
+ * (`approve`, `increaseAllowance`, EIP-2612 and DAI `permit`) or over a whole NFT collection
+ * (`setApprovalForAll`), then sweeps them. Unlimited grants are flagged when the spender is
+ * denylisted, is a contract deployed only recently, or is an account with almost no history.
+ * Spenders on the allowlist of known routers and marketplaces are never reported
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{decode, ParamType, Token},
+    providers::Middleware,
+    types::{Address, BlockNumber, U256},
+    utils::id,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::debug;
+
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+/// Allowance at or above 2^128 is unlimited in practice
+const UNLIMITED_ALLOWANCE_BITS: usize = 128;
+
+/// Approval drainer detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    pub enabled: bool,
+    /// Routers and marketplaces unlimited approvals to are expected
+    pub trusted_spenders: Vec<Address>,
+    /// Spenders known to drain approvals
+    #[serde(default)]
+    pub denylist: Vec<Address>,
+    /// Contracts deployed within this many blocks count as fresh
+    pub fresh_contract_blocks: u64,
+    /// Accounts that have sent fewer transactions than this count as fresh
+    pub fresh_account_nonce: u64,
+    /// Assessments at or above this confidence become detections
+    pub report_threshold: f64,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trusted_spenders: [
+                "0x000000000022D473030F116dDEE9F6B43aC78BA3", // Permit2
+                "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D", // Uniswap V2 router
+                "0xE592427A0AEce92De3Edee1F18E0157C05861564", // Uniswap V3 router
+                "0x1111111254EEB25477B68fb85Ed929f73A960582", // 1inch v5 router
+                "0x00000000000000ADc04C56Bf30aC9d3c0aAF14dC", // Seaport 1.5
+            ]
+            .into_iter()
+            .map(|address| address.parse().unwrap())
+            .collect(),
+            denylist: Vec::new(),
+            fresh_contract_blocks: 50_000,
+            fresh_account_nonce: 5,
+            report_threshold: 0.7,
+        }
+    }
+}
+
+/// Kind of allowance a transaction grants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Approve,
+    IncreaseAllowance,
+    /// EIP-2612 or DAI-style signed approval, submitted by anyone holding the signature
+    Permit,
+    /// Every NFT of the collection
+    ApprovalForAll,
+}
+
+/// Allowance granted by a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub kind: ApprovalKind,
+    /// Token or collection the allowance is over
+    pub token: Address,
+    pub spender: Address,
+    /// `None` for grants without an amount
+    pub amount: Option<U256>,
+    pub unlimited: bool,
+}
+
+impl ApprovalRequest {
+    /// Allowance `tx` grants, if it calls one of the approval functions
+    pub fn decode(tx: &TxContext) -> Option<Self> {
+        let token = tx.to?;
+        let selector = tx.selector()?;
+        let args = tx.data.get(4..)?;
+        let unlimited = |amount: U256| amount.bits() > UNLIMITED_ALLOWANCE_BITS;
+        let request = |kind, spender, amount: Option<U256>, unlimited| Self {
+            kind,
+            token,
+            spender,
+            amount,
+            unlimited,
+        };
+
+        if selector == id("approve(address,uint256)") || selector == id("increaseAllowance(address,uint256)") {
+            let kind = if selector == id("approve(address,uint256)") {
+                ApprovalKind::Approve
+            } else {
+                ApprovalKind::IncreaseAllowance
+            };
+            let [Token::Address(spender), Token::Uint(amount)] = decode(&[ParamType::Address, ParamType::Uint(256)], args).ok()?[..] else {
+                return None;
+            };
+            Some(request(kind, spender, Some(amount), unlimited(amount)))
+        } else if selector == id("setApprovalForAll(address,bool)") {
+            let [Token::Address(operator), Token::Bool(approved)] = decode(&[ParamType::Address, ParamType::Bool], args).ok()?[..] else {
+                return None;
+            };
+            approved.then(|| request(ApprovalKind::ApprovalForAll, operator, None, true))
+        } else if selector == id("permit(address,address,uint256,uint256,uint8,bytes32,bytes32)") {
+            let params = [
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(8),
+                ParamType::FixedBytes(32),
+                ParamType::FixedBytes(32),
+            ];
+            let [_, Token::Address(spender), Token::Uint(amount), ..] = decode(&params, args).ok()?[..] else {
+                return None;
+            };
+            Some(request(ApprovalKind::Permit, spender, Some(amount), unlimited(amount)))
+        } else if selector == id("permit(address,address,uint256,uint256,bool,uint8,bytes32,bytes32)") {
+            // DAI: an allowed permit is always unlimited
+            let params = [
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Bool,
+                ParamType::Uint(8),
+                ParamType::FixedBytes(32),
+                ParamType::FixedBytes(32),
+            ];
+            let [_, Token::Address(spender), _, _, Token::Bool(allowed), ..] = decode(&params, args).ok()?[..] else {
+                return None;
+            };
+            allowed.then(|| request(ApprovalKind::Permit, spender, None, true))
+        } else {
+            None
+        }
+    }
+}
+
+/// What is known about a spender
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpenderProfile {
+    pub is_contract: bool,
+    /// Contract deployed within the fresh window; `None` when the node can't tell
+    pub fresh_contract: Option<bool>,
+    /// Transactions the spender has sent
+    pub nonce: u64,
+}
+
+/// Confidence that `request` to a spender like `profile` is a drainer, with the reasons
+pub fn assess(config: &ApprovalConfig, request: &ApprovalRequest, profile: &SpenderProfile) -> Option<(f64, Vec<String>)> {
+    if !request.unlimited || config.trusted_spenders.contains(&request.spender) {
+        return None;
+    }
+
+    let mut signals: Vec<(f64, String)> = Vec::new();
+    if config.denylist.contains(&request.spender) {
+        signals.push((0.95, "spender is denylisted".to_string()));
+    }
+    if profile.fresh_contract == Some(true) {
+        signals.push((0.8, format!("spender contract deployed within {} blocks", config.fresh_contract_blocks)));
+    }
+    if !profile.is_contract {
+        // Routers are contracts; a plain account has no business holding unlimited allowance
+        signals.push((0.6, "spender is not a contract".to_string()));
+        if profile.nonce < config.fresh_account_nonce {
+            signals.push((0.5, format!("spender has sent only {} transactions", profile.nonce)));
+        }
+    }
+    if signals.is_empty() {
+        return None;
+    }
+
+    let confidence = 1.0 - signals.iter().map(|(weight, _)| 1.0 - weight).product::<f64>();
+    Some((confidence, signals.into_iter().map(|(_, reason)| reason).collect()))
+}
+
+/// Flags unlimited approvals to suspicious spenders, profiling them through a provider
+pub struct ApprovalDrainerDetector<M> {
+    config: ApprovalConfig,
+    provider: M,
+    trusted: HashSet<Address>,
+}
+
+impl<M: Middleware> ApprovalDrainerDetector<M> {
+    pub fn new(config: ApprovalConfig, provider: M) -> Self {
+        let trusted = config.trusted_spenders.iter().copied().collect();
+        Self { config, provider, trusted }
+    }
+
+    /// Code, age and history of `spender`
+    pub async fn profile(&self, spender: Address) -> Result<SpenderProfile> {
+        let code = self
+            .provider
+            .get_code(spender, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to fetch code of {:?}", spender))?;
+        let nonce = self
+            .provider
+            .get_transaction_count(spender, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to fetch nonce of {:?}", spender))?;
+        let is_contract = !code.is_empty();
+
+        // Fresh if it had no code at the start of the window; nodes without that state can't tell
+        let mut fresh_contract = None;
+        if is_contract {
+            let head = self.provider.get_block_number().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+            let start = BlockNumber::Number(head.saturating_sub(self.config.fresh_contract_blocks).into());
+            match self.provider.get_code(spender, Some(start.into())).await {
+                Ok(earlier) => fresh_contract = Some(earlier.is_empty()),
+                Err(e) => debug!("🕰️ Couldn't read code of {:?} at block {:?}: {}", spender, start, e),
+            }
+        }
+
+        Ok(SpenderProfile {
+            is_contract,
+            fresh_contract,
+            nonce: nonce.as_u64(),
+        })
+    }
+}
+
+impl<M: Middleware> ThreatDetector for ApprovalDrainerDetector<M> {
+    /// Unlimited approvals to untrusted spenders, with the spender's profile
+    type Features = (ApprovalRequest, SpenderProfile);
+
+    fn name(&self) -> &str {
+        "approvals"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        Some(ThreatCategory::Drainer)
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<Self::Features>> {
+        let Some(request) = ApprovalRequest::decode(tx) else {
+            return Ok(None);
+        };
+        if !request.unlimited || self.trusted.contains(&request.spender) {
+            return Ok(None);
+        }
+        let profile = self.profile(request.spender).await?;
+        Ok(Some((request, profile)))
+    }
+
+    /// Detection when the spender looks suspicious enough
+    fn score(&self, tx: &TxContext, (request, profile): &Self::Features) -> Vec<Detection> {
+        let Some((confidence, reasons)) = assess(&self.config, request, profile) else {
+            return Vec::new();
+        };
+        debug!("🪝 {:?} approval to {:?}: {:.2} ({})", request.kind, request.spender, confidence, reasons.join(", "));
+        if confidence < self.config.report_threshold {
+            return Vec::new();
+        }
+
+        let kind = serde_json::to_value(request.kind).unwrap_or_default();
+        vec![Detection {
+            detector: format!("approvals:{}", kind.as_str().unwrap_or_default()),
+            threat_type: ThreatCategory::Drainer,
+            confidence,
+            tx_hash: tx.hash,
+            target: Some(request.spender),
+            url: None,
+            explanation: format!("Unlimited {:?} of {:?}: {}", request.kind, request.token, reasons.join(", ")),
+            evidence: None,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::encode;
+
+    fn call(signature: &str, args: &[Token]) -> TxContext {
+        TxContext {
+            to: Some(Address::repeat_byte(0x70)),
+            data: [id(signature).to_vec(), encode(args)].concat().into(),
+            ..TxContext::default()
+        }
+    }
+
+    #[test]
+    fn test_unlimited_approvals_to_fresh_spenders_flagged() {
+        let config = ApprovalConfig::default();
+        let drainer = Address::repeat_byte(0xdd);
+        let approve = call("approve(address,uint256)", &[Token::Address(drainer), Token::Uint(U256::MAX)]);
+        let request = ApprovalRequest::decode(&approve).unwrap();
+        assert_eq!((request.kind, request.spender, request.unlimited), (ApprovalKind::Approve, drainer, true));
+
+        // A fresh contract is flagged, an established one isn't
+        let fresh = SpenderProfile {
+            is_contract: true,
+            fresh_contract: Some(true),
+            nonce: 1,
+        };
+        assert!(assess(&config, &request, &fresh).unwrap().0 >= config.report_threshold);
+        let established = SpenderProfile {
+            fresh_contract: Some(false),
+            ..fresh.clone()
+        };
+        assert!(assess(&config, &request, &established).is_none());
+
+        // A new account holding allowance over a whole collection
+        let operator = call("setApprovalForAll(address,bool)", &[Token::Address(drainer), Token::Bool(true)]);
+        let request = ApprovalRequest::decode(&operator).unwrap();
+        let (confidence, reasons) = assess(&config, &request, &SpenderProfile::default()).unwrap();
+        assert!((confidence - 0.8).abs() < 1e-9);
+        assert_eq!(reasons.len(), 2);
+
+        // Bounded amounts and allowlisted routers are left alone
+        let bounded = call("approve(address,uint256)", &[Token::Address(drainer), Token::Uint(U256::exp10(24))]);
+        assert!(!ApprovalRequest::decode(&bounded).unwrap().unlimited);
+        let router = config.trusted_spenders[1];
+        let to_router = call("approve(address,uint256)", &[Token::Address(router), Token::Uint(U256::MAX)]);
+        assert!(assess(&config, &ApprovalRequest::decode(&to_router).unwrap(), &fresh).is_none());
+
+        let permit = call(
+            "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+            &[
+                Token::Address(Address::zero()),
+                Token::Address(drainer),
+                Token::Uint(U256::MAX),
+                Token::Uint(U256::zero()),
+                Token::Uint(27.into()),
+                Token::FixedBytes(vec![0; 32]),
+                Token::FixedBytes(vec![0; 32]),
+            ],
+        );
+        assert_eq!(ApprovalRequest::decode(&permit).unwrap().kind, ApprovalKind::Permit);
+    }
+}
@@ -0,0 +1,510 @@
+/*!
+ * EVM bytecode analysis
+ * Fetches a contract's deployed code, strips the CBOR metadata solc appends, disassembles it and
+ * looks for patterns malicious tokens and contracts share: bot lists that stop holders selling,
+ * upgradeable proxies whose logic can be swapped under users, and SELFDESTRUCT reached through a
+ * branch on the caller. Mint functions and address blacklists beside the ERC-20 interface are
+ * weak signals only, since regulated stablecoins like USDC have both. Functions are recognized by
+ * the selectors the dispatcher compares calldata against (`PUSH4 selector` followed by `EQ`).
+ * EIP-1967 proxies are analyzed together with the implementation their slot points at. Reports
+ * are cached per code hash
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256},
+    utils::{id, keccak256},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+use tracing::debug;
+
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+const STOP: u8 = 0x00;
+const EQ: u8 = 0x14;
+const CALLER: u8 = 0x33;
+const ORIGIN: u8 = 0x32;
+const SLOAD: u8 = 0x54;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+const PUSH1: u8 = 0x60;
+const PUSH4: u8 = 0x63;
+const PUSH32: u8 = 0x7f;
+const RETURN: u8 = 0xf3;
+const DELEGATECALL: u8 = 0xf4;
+const REVERT: u8 = 0xfd;
+const INVALID: u8 = 0xfe;
+const SELFDESTRUCT: u8 = 0xff;
+
+/// Blocks followed from a caller check looking for SELFDESTRUCT
+const MAX_GUARD_DISTANCE: usize = 16;
+
+/// EIP-1967 implementation slot, `keccak256("eip1967.proxy.implementation") - 1`
+const EIP1967_IMPLEMENTATION_SLOT: [u8; 32] = [
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d,
+    0xca, 0x3e, 0x20, 0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+];
+
+/// ERC-20 functions; a contract exposing them is treated as a token
+const TOKEN_FUNCTIONS: &[&str] = &["transfer(address,uint256)", "transferFrom(address,address,uint256)", "balanceOf(address)"];
+const MINT_FUNCTIONS: &[&str] = &["mint(address,uint256)", "mint(uint256)", "_mint(address,uint256)", "mintTo(address,uint256)"];
+/// Address freezes, as compliance-bound tokens have
+const BLACKLIST_FUNCTIONS: &[&str] = &["blacklist(address)", "addToBlacklist(address)", "setBlacklist(address,bool)", "blacklistAddress(address,bool)"];
+/// Bot lists, which scam tokens use to stop buyers selling
+const BOT_FUNCTIONS: &[&str] = &["addBot(address)", "setBots(address[])", "setBot(address,bool)"];
+const UPGRADE_FUNCTIONS: &[&str] = &["upgradeTo(address)", "upgradeToAndCall(address,bytes)", "setImplementation(address)"];
+
+/// Bytecode analysis settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BytecodeConfig {
+    pub enabled: bool,
+    /// Reports at or above this risk become detections
+    pub report_threshold: f64,
+}
+
+impl Default for BytecodeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            report_threshold: 0.7,
+        }
+    }
+}
+
+/// One disassembled instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    /// Immediate of a PUSH, shorter than its width if the code ends first
+    pub immediate: Vec<u8>,
+}
+
+/// Code without the CBOR metadata solc appends, whose length is in the last two bytes, so the
+/// metadata hash is not read as instructions
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    let Some(length_at) = code.len().checked_sub(2) else {
+        return code;
+    };
+    let length = u16::from_be_bytes([code[length_at], code[length_at + 1]]) as usize;
+    match length_at.checked_sub(length) {
+        // Metadata is a CBOR map of a few entries (`ipfs`, `solc`, ...)
+        Some(start) if length > 0 && (0xa1..=0xa5).contains(&code[start]) => &code[..start],
+        _ => code,
+    }
+}
+
+/// Instructions of `code`, in order
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let width = if (PUSH1..=PUSH32).contains(&opcode) { (opcode - PUSH1 + 1) as usize } else { 0 };
+        let end = (pc + 1 + width).min(code.len());
+        instructions.push(Instruction {
+            pc,
+            opcode,
+            immediate: code[pc + 1..end].to_vec(),
+        });
+        pc += 1 + width;
+    }
+    instructions
+}
+
+/// Ranges of `instructions` control only enters at the start of: split before each JUMPDEST and
+/// after each jump or halt
+fn basic_blocks(instructions: &[Instruction]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for (index, instruction) in instructions.iter().enumerate() {
+        if instruction.opcode == JUMPDEST && index > start {
+            blocks.push(start..index);
+            start = index;
+        }
+        if matches!(instruction.opcode, JUMP | JUMPI | STOP | RETURN | REVERT | INVALID | SELFDESTRUCT) {
+            blocks.push(start..index + 1);
+            start = index + 1;
+        }
+    }
+    if start < instructions.len() {
+        blocks.push(start..instructions.len());
+    }
+    blocks
+}
+
+/// Whether a SELFDESTRUCT is reached through a conditional jump on CALLER or ORIGIN compared
+/// with EQ, following static jump targets and fall-throughs
+fn caller_guarded_selfdestruct(instructions: &[Instruction]) -> bool {
+    let blocks = basic_blocks(instructions);
+    let block_at: HashMap<usize, usize> = blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| instructions[block.start].opcode == JUMPDEST)
+        .map(|(index, block)| (instructions[block.start].pc, index))
+        .collect();
+    let successors = |index: usize| {
+        let body = &instructions[blocks[index].clone()];
+        let last = body.last().map_or(STOP, |instruction| instruction.opcode);
+        let mut next = Vec::new();
+        if matches!(last, JUMP | JUMPI) {
+            let target = body
+                .len()
+                .checked_sub(2)
+                .map(|at| &body[at])
+                .filter(|push| (PUSH1..=PUSH32).contains(&push.opcode) && push.immediate.len() <= 8)
+                .map(|push| push.immediate.iter().fold(0usize, |pc, byte| pc << 8 | *byte as usize));
+            next.extend(target.and_then(|pc| block_at.get(&pc).copied()));
+        }
+        if !matches!(last, JUMP | STOP | RETURN | REVERT | INVALID | SELFDESTRUCT) && index + 1 < blocks.len() {
+            next.push(index + 1);
+        }
+        next
+    };
+    let destroys = |index: usize| instructions[blocks[index].clone()].iter().any(|instruction| instruction.opcode == SELFDESTRUCT);
+
+    blocks.iter().enumerate().any(|(index, block)| {
+        let body = &instructions[block.clone()];
+        let branches_on_caller = body.last().is_some_and(|instruction| instruction.opcode == JUMPI)
+            && body.iter().any(|instruction| matches!(instruction.opcode, CALLER | ORIGIN))
+            && body.iter().any(|instruction| instruction.opcode == EQ);
+        if !branches_on_caller {
+            return false;
+        }
+        let mut seen = BTreeSet::from([index]);
+        let mut queue: VecDeque<(usize, usize)> = successors(index).into_iter().map(|next| (next, 1)).collect();
+        while let Some((next, distance)) = queue.pop_front() {
+            if !seen.insert(next) {
+                continue;
+            }
+            if destroys(next) {
+                return true;
+            }
+            if distance < MAX_GUARD_DISTANCE {
+                queue.extend(successors(next).into_iter().map(|after| (after, distance + 1)));
+            }
+        }
+        false
+    })
+}
+
+/// Malicious contract pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// Token with a mint function, so supply can be inflated and dumped
+    HiddenMint,
+    /// Token that can freeze addresses; regulated stablecoins can too, so it is weak alone
+    Blacklist,
+    /// Token with a bot list, typically blocking buyers from selling
+    BlacklistOnSell,
+    /// Upgradeable proxy; its logic can be replaced after users approve it
+    ProxyRug,
+    /// SELFDESTRUCT behind a caller check, destroying funds held by the contract
+    SelfdestructTrap,
+}
+
+impl Pattern {
+    /// Risk the pattern carries on its own
+    fn weight(self) -> f64 {
+        match self {
+            Self::HiddenMint => 0.3,
+            Self::Blacklist => 0.3,
+            Self::BlacklistOnSell => 0.7,
+            Self::ProxyRug => 0.35,
+            Self::SelfdestructTrap => 0.8,
+        }
+    }
+
+    pub fn category(self) -> ThreatCategory {
+        match self {
+            Self::HiddenMint | Self::ProxyRug | Self::SelfdestructTrap => ThreatCategory::RugPull,
+            Self::Blacklist | Self::BlacklistOnSell => ThreatCategory::Honeypot,
+        }
+    }
+}
+
+/// What the analysis found in one contract
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BytecodeReport {
+    pub address: Address,
+    pub code_hash: H256,
+    pub code_size: usize,
+    /// Selectors the dispatcher compares calldata against
+    pub selectors: BTreeSet<String>,
+    pub patterns: BTreeSet<Pattern>,
+    /// Combined risk of the patterns, treated as independent
+    pub risk: f64,
+    /// Whether the code delegates to the implementation in the EIP-1967 slot
+    #[serde(default)]
+    pub eip1967_proxy: bool,
+    /// Implementation analyzed along with an EIP-1967 proxy
+    #[serde(default)]
+    pub implementation: Option<Address>,
+}
+
+fn combined_risk(patterns: &BTreeSet<Pattern>) -> f64 {
+    1.0 - patterns.iter().map(|pattern| 1.0 - pattern.weight()).product::<f64>()
+}
+
+impl BytecodeReport {
+    /// Analyze deployed `code` of `address`
+    pub fn analyze(address: Address, code: &[u8]) -> Self {
+        let instructions = disassemble(strip_metadata(code));
+        let selectors: BTreeSet<[u8; 4]> = instructions
+            .windows(2)
+            .filter(|pair| pair[0].opcode == PUSH4 && pair[0].immediate.len() == 4 && pair[1].opcode == EQ)
+            .map(|pair| pair[0].immediate.clone().try_into().unwrap())
+            .collect();
+        let has_any = |signatures: &[&str]| signatures.iter().any(|signature| selectors.contains(&id(signature)));
+        let has_opcode = |opcode: u8| instructions.iter().any(|instruction| instruction.opcode == opcode);
+
+        let mut patterns = BTreeSet::new();
+        let is_token = TOKEN_FUNCTIONS.iter().all(|signature| selectors.contains(&id(signature)));
+        if is_token && has_any(MINT_FUNCTIONS) {
+            patterns.insert(Pattern::HiddenMint);
+        }
+        if is_token && has_any(BLACKLIST_FUNCTIONS) {
+            patterns.insert(Pattern::Blacklist);
+        }
+        if is_token && has_any(BOT_FUNCTIONS) {
+            patterns.insert(Pattern::BlacklistOnSell);
+        }
+
+        // Logic loaded from storage and delegated to, with a way to change it
+        let eip1967 = instructions
+            .iter()
+            .any(|instruction| instruction.opcode == PUSH32 && instruction.immediate == EIP1967_IMPLEMENTATION_SLOT);
+        let delegates = has_opcode(DELEGATECALL) && has_opcode(SLOAD);
+        if delegates && (eip1967 || has_any(UPGRADE_FUNCTIONS)) {
+            patterns.insert(Pattern::ProxyRug);
+        }
+
+        if caller_guarded_selfdestruct(&instructions) {
+            patterns.insert(Pattern::SelfdestructTrap);
+        }
+
+        Self {
+            address,
+            code_hash: H256::from(keccak256(code)),
+            code_size: code.len(),
+            selectors: selectors.iter().map(|selector| format!("0x{}", hex::encode(selector))).collect(),
+            risk: combined_risk(&patterns),
+            patterns,
+            eip1967_proxy: delegates && eip1967,
+            implementation: None,
+        }
+    }
+
+    /// Add what the analysis of the proxy's `implementation` found
+    pub fn with_implementation(mut self, implementation: &BytecodeReport) -> Self {
+        self.selectors.extend(implementation.selectors.iter().cloned());
+        self.patterns.extend(implementation.patterns.iter().copied());
+        self.risk = combined_risk(&self.patterns);
+        self.implementation = Some(implementation.address);
+        self
+    }
+
+    /// Detection for the report, typed by its riskiest pattern
+    pub fn detection(&self) -> Option<Detection> {
+        let riskiest = self.patterns.iter().max_by(|a, b| a.weight().total_cmp(&b.weight()))?;
+        Some(Detection {
+            detector: "bytecode".to_string(),
+            threat_type: riskiest.category(),
+            confidence: self.risk,
+            tx_hash: None,
+            target: Some(self.address),
+            url: None,
+            explanation: format!("Contract code matches {:?}", self.patterns),
+            evidence: Some(self.code_hash),
+        })
+    }
+}
+
+/// Analyzes deployed contracts through a provider
+pub struct BytecodeAnalyzer<M> {
+    config: BytecodeConfig,
+    provider: Arc<M>,
+    /// Reports by code hash, so clones of one contract are analyzed once
+    reports: Mutex<HashMap<H256, BytecodeReport>>,
+}
+
+impl<M: Middleware> BytecodeAnalyzer<M> {
+    pub fn new(config: BytecodeConfig, provider: Arc<M>) -> Self {
+        Self {
+            config,
+            provider,
+            reports: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fetch and analyze the code deployed at `address`
+    pub async fn analyze(&self, address: Address) -> Result<BytecodeReport> {
+        self.report(address).await?.with_context(|| format!("{:?} has no contract code", address))
+    }
+
+    /// Report on the code at `address`, including the implementation of an EIP-1967 proxy, or
+    /// `None` for accounts without code
+    async fn report(&self, address: Address) -> Result<Option<BytecodeReport>> {
+        let Some(report) = self.code_report(address).await? else {
+            return Ok(None);
+        };
+        if !report.eip1967_proxy {
+            return Ok(Some(report));
+        }
+
+        let slot = self
+            .provider
+            .get_storage_at(address, H256::from(EIP1967_IMPLEMENTATION_SLOT), None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to read the implementation slot of {:?}", address))?;
+        let implementation = Address::from_slice(&slot.as_bytes()[12..]);
+        if implementation.is_zero() {
+            return Ok(Some(report));
+        }
+        Ok(Some(match self.code_report(implementation).await? {
+            Some(logic) => report.with_implementation(&logic),
+            None => report,
+        }))
+    }
+
+    /// Report on the code at `address` alone
+    async fn code_report(&self, address: Address) -> Result<Option<BytecodeReport>> {
+        let code = self
+            .provider
+            .get_code(address, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to fetch code of {:?}", address))?;
+        if code.is_empty() {
+            return Ok(None);
+        }
+
+        let code_hash = H256::from(keccak256(&code));
+        if let Some(report) = self.reports.lock().unwrap().get(&code_hash) {
+            return Ok(Some(BytecodeReport { address, ..report.clone() }));
+        }
+
+        let report = BytecodeReport::analyze(address, &code);
+        debug!("🧬 {:?}: {} bytes, patterns {:?}", address, report.code_size, report.patterns);
+        self.reports.lock().unwrap().insert(code_hash, report.clone());
+        Ok(Some(report))
+    }
+
+    /// Detection for `address` when its risk reaches the report threshold
+    pub async fn detect(&self, address: Address) -> Result<Option<Detection>> {
+        let report = self.analyze(address).await?;
+        Ok(report.detection().filter(|detection| detection.confidence >= self.config.report_threshold))
+    }
+}
+
+impl<M: Middleware> ThreatDetector for BytecodeAnalyzer<M> {
+    /// Report on the called contract
+    type Features = BytecodeReport;
+
+    fn name(&self) -> &str {
+        "bytecode"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<BytecodeReport>> {
+        match tx.to {
+            Some(to) => self.report(to).await,
+            None => Ok(None),
+        }
+    }
+
+    fn score(&self, tx: &TxContext, report: &BytecodeReport) -> Vec<Detection> {
+        report
+            .detection()
+            .filter(|detection| detection.confidence >= self.config.report_threshold)
+            .map(|detection| Detection { tx_hash: tx.hash, ..detection })
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Dispatcher comparing calldata against each function's selector
+    fn dispatcher(signatures: &[&str]) -> Vec<u8> {
+        let mut code = vec![PUSH1, 0x00, 0x35, PUSH1, 0xe0, 0x1c];
+        for signature in signatures {
+            code.push(0x80);
+            code.push(PUSH4);
+            code.extend_from_slice(&id(signature));
+            code.extend_from_slice(&[EQ, PUSH1, 0x40, 0x57]);
+        }
+        code
+    }
+
+    /// `require(msg.sender == owner); selfdestruct(msg.sender)`
+    fn owner_selfdestruct(code: &mut Vec<u8>) {
+        let destination = (code.len() + 13) as u16;
+        code.extend_from_slice(&[CALLER, PUSH1, 0x00, SLOAD, EQ, PUSH1 + 1]);
+        code.extend_from_slice(&destination.to_be_bytes());
+        code.extend_from_slice(&[JUMPI, PUSH1, 0x00, 0x80, REVERT, JUMPDEST, CALLER, SELFDESTRUCT]);
+    }
+
+    #[test]
+    fn test_patterns_found_in_dispatcher() {
+        let token = ["transfer(address,uint256)", "transferFrom(address,address,uint256)", "balanceOf(address)"];
+        let mut honeypot = dispatcher(&[&token[..], &["setBots(address[])"][..]].concat());
+        owner_selfdestruct(&mut honeypot);
+        let report = BytecodeReport::analyze(Address::zero(), &honeypot);
+        assert_eq!(report.patterns, BTreeSet::from([Pattern::BlacklistOnSell, Pattern::SelfdestructTrap]));
+        assert!(report.selectors.contains("0xa9059cbb"));
+        let detection = report.detection().unwrap();
+        assert_eq!(detection.threat_type, ThreatCategory::RugPull);
+        assert!((detection.confidence - 0.94).abs() < 1e-9);
+
+        // SELFDESTRUCT and CALLER bytes in the metadata trailer, or a SELFDESTRUCT no caller
+        // check leads to, are no trap
+        let mut metadata = dispatcher(&token);
+        metadata.extend_from_slice(&[0xa2, CALLER, EQ, PUSH1, 0x00, JUMPI, SELFDESTRUCT, 0x00, 0x07]);
+        assert_eq!(strip_metadata(&metadata), &dispatcher(&token)[..]);
+        assert!(BytecodeReport::analyze(Address::zero(), &metadata).patterns.is_empty());
+        let mut unguarded = dispatcher(&token);
+        unguarded.extend_from_slice(&[CALLER, PUSH1, 0x00, SLOAD, EQ, 0x50, STOP, CALLER, SELFDESTRUCT]);
+        assert!(BytecodeReport::analyze(Address::zero(), &unguarded).patterns.is_empty());
+
+        // A USDC-like token, mintable and freezable by its issuer, stays below the report threshold
+        let stablecoin = dispatcher(&[&token[..], &["mint(address,uint256)", "blacklist(address)", "configureMinter(address,uint256)"][..]].concat());
+        let report = BytecodeReport::analyze(Address::zero(), &stablecoin);
+        assert_eq!(report.patterns, BTreeSet::from([Pattern::HiddenMint, Pattern::Blacklist]));
+        assert!(report.risk < BytecodeConfig::default().report_threshold);
+
+        // A mint function outside a token, or a PUSH cut off by the end of the code, is not flagged
+        let mut plain = dispatcher(&["mint(address,uint256)"]);
+        plain.push(PUSH32);
+        assert!(BytecodeReport::analyze(Address::zero(), &plain).detection().is_none());
+        assert_eq!(disassemble(&plain).last().unwrap().immediate.len(), 0);
+
+        let mut proxy = vec![PUSH32];
+        proxy.extend_from_slice(&EIP1967_IMPLEMENTATION_SLOT);
+        proxy.extend_from_slice(&[SLOAD, 0x5a, DELEGATECALL]);
+        let proxy = BytecodeReport::analyze(Address::zero(), &proxy);
+        assert_eq!(proxy.patterns, BTreeSet::from([Pattern::ProxyRug]));
+        assert!(proxy.eip1967_proxy);
+
+        // A proxy reports what its implementation does
+        let logic = BytecodeReport::analyze(Address::repeat_byte(1), &honeypot);
+        let proxied = proxy.with_implementation(&logic);
+        assert_eq!(proxied.implementation, Some(Address::repeat_byte(1)));
+        assert!(proxied.patterns.contains(&Pattern::SelfdestructTrap) && proxied.selectors.contains("0xa9059cbb"));
+    }
+}
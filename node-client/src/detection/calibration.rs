@@ -0,0 +1,417 @@
+/*!
+ * Confidence calibration
+ * A detector's confidence is only meaningful if threats it reports at 0.8 turn out real about 80%
+ * of the time: rewards and the ZK threshold both take reported confidences at face value. With
+ * calibration on, the node also submits every detection it reports to the oracle, with the
+ * report's on-chain id as evidence hash, as long as the oracle takes reports for its chain at its
+ * confidence. The outcome tracker matches the oracle's reports back to our detections by that
+ * hash and, once a report's consensus window has closed, reads whether consensus verified it.
+ * Those verdicts fit a curve per detector family, Platt scaling or isotonic regression, which the
+ * scoring model applies to that family's raw confidences. Verdicts only exist for confidences the
+ * oracle accepts, so isotonic curves leave confidences outside the sampled range as they are.
+ * Manual and dispute labels only feed the scoring weights, so no outcome counts against a family
+ * twice
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    contract::{abigen, parse_log, EthEvent},
+    providers::Middleware,
+    types::{Address, Filter, H256},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use super::events::{ThreatEvent, ThreatEvents};
+use super::feedback::{DetectionRecord, FeedbackStore, OracleReport};
+use super::scoring::{detector_families, ScoringModel};
+
+abigen!(
+    ThreatOracle,
+    r#"[
+        struct ThreatReport { uint256 chainId; address contractAddress; uint8 threatLevel; uint8 threatType; uint256 timestamp; bytes32 evidenceHash; uint8 confidence; address reporter; bool verified; }
+        event ThreatReported(bytes32 indexed reportId, uint256 chainId, address contractAddress, uint8 threatLevel)
+        function getThreatReport(bytes32 reportId) external view returns (ThreatReport)
+        function submitThreatReport(uint256 _chainId, address _contractAddress, uint8 _threatLevel, uint8 _threatType, bytes32 _evidenceHash, uint8 _confidence, bytes _signature) external
+        function chainConfigs(uint256 chainId) external view returns (bool active, uint256 minConfidence, uint256 consensusThreshold, address relayContract)
+    ]"#
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationMethod {
+    Platt,
+    Isotonic,
+}
+
+/// Calibration settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationConfig {
+    pub enabled: bool,
+    /// Oracle the node submits its threat reports to; calibration stays off while it is zero
+    pub oracle: Address,
+    pub method: CalibrationMethod,
+    /// Outcomes a family needs before it gets a curve
+    pub min_samples: usize,
+    /// The oracle's `CONSENSUS_WINDOW`; a report unverified after it was rejected
+    pub consensus_window_secs: i64,
+    pub poll_interval_secs: u64,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            oracle: Address::zero(),
+            method: CalibrationMethod::Isotonic,
+            min_samples: 50,
+            consensus_window_secs: 300,
+            poll_interval_secs: 600,
+        }
+    }
+}
+
+/// Curve mapping a detector's confidence to the accuracy it has shown at that confidence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Calibration {
+    /// `1 / (1 + e^(a·x + b))`
+    Platt { a: f64, b: f64 },
+    /// Non-decreasing (confidence, accuracy) points, interpolated between; confidences outside
+    /// the points were never sampled and pass through unchanged
+    Isotonic { points: Vec<(f64, f64)> },
+}
+
+impl Calibration {
+    pub fn apply(&self, confidence: f64) -> f64 {
+        match self {
+            Self::Platt { a, b } => 1.0 / (1.0 + (a * confidence + b).exp()),
+            Self::Isotonic { points } => {
+                let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                    return confidence;
+                };
+                if confidence < first.0 || confidence > last.0 {
+                    return confidence.clamp(0.0, 1.0);
+                }
+                if confidence == first.0 {
+                    return first.1;
+                }
+                let upper = points.partition_point(|point| point.0 < confidence);
+                let ((x0, y0), (x1, y1)) = (points[upper - 1], points[upper]);
+                if x1 > x0 {
+                    y0 + (y1 - y0) * (confidence - x0) / (x1 - x0)
+                } else {
+                    y1
+                }
+            }
+        }
+        .clamp(0.0, 1.0)
+    }
+
+    /// Fit a curve to (confidence, was a real threat) samples
+    pub fn fit(method: CalibrationMethod, samples: &[(f64, bool)]) -> Self {
+        match method {
+            CalibrationMethod::Platt => Self::fit_platt(samples),
+            CalibrationMethod::Isotonic => Self::fit_isotonic(samples),
+        }
+    }
+
+    /// Newton's method on the cross-entropy, with Platt's smoothed targets so a detector that
+    /// was always right still gets a finite curve
+    fn fit_platt(samples: &[(f64, bool)]) -> Self {
+        let positives = samples.iter().filter(|(_, real)| *real).count() as f64;
+        let negatives = samples.len() as f64 - positives;
+        let (high, low) = ((positives + 1.0) / (positives + 2.0), 1.0 / (negatives + 2.0));
+
+        let (mut a, mut b) = (0.0, ((negatives + 1.0) / (positives + 1.0)).ln());
+        for _ in 0..100 {
+            let (mut ga, mut gb, mut haa, mut hab, mut hbb) = (0.0, 0.0, 1e-9, 0.0, 1e-9);
+            for &(x, real) in samples {
+                let p = 1.0 / (1.0 + (a * x + b).exp());
+                let t = if real { high } else { low };
+                let w = p * (1.0 - p);
+                ga += (t - p) * x;
+                gb += t - p;
+                haa += w * x * x;
+                hab += w * x;
+                hbb += w;
+            }
+            let det = haa * hbb - hab * hab;
+            let (da, db) = ((hbb * ga - hab * gb) / det, (haa * gb - hab * ga) / det);
+            a -= da;
+            b -= db;
+            if da.abs() + db.abs() < 1e-10 {
+                break;
+            }
+        }
+        Self::Platt { a, b }
+    }
+
+    /// Pool adjacent violators: merge neighbouring blocks until accuracy never falls
+    fn fit_isotonic(samples: &[(f64, bool)]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // (confidence sum, real threats, samples) per block, starting from one per confidence
+        let mut blocks: Vec<(f64, f64, f64)> = Vec::new();
+        let mut last_x = None;
+        for (x, real) in sorted {
+            let real = real as u8 as f64;
+            match blocks.last_mut() {
+                Some(last) if last_x == Some(x) => *last = (last.0 + x, last.1 + real, last.2 + 1.0),
+                _ => blocks.push((x, real, 1.0)),
+            }
+            last_x = Some(x);
+            while let [.., previous, last] = blocks[..] {
+                if previous.1 / previous.2 <= last.1 / last.2 {
+                    break;
+                }
+                blocks.pop();
+                *blocks.last_mut().unwrap() = (previous.0 + last.0, previous.1 + last.1, previous.2 + last.2);
+            }
+        }
+        Self::Isotonic {
+            points: blocks.into_iter().map(|(x, real, n)| (x / n, real / n)).collect(),
+        }
+    }
+}
+
+/// Curves for every family with at least `min_samples` known outcomes
+pub fn fit_curves(records: &[DetectionRecord], method: CalibrationMethod, min_samples: usize) -> HashMap<String, Calibration> {
+    let mut samples: HashMap<&str, Vec<(f64, bool)>> = HashMap::new();
+    for record in records {
+        let Some(real) = record.report.as_ref().and_then(|report| report.verified) else { continue };
+        if record.contributions.is_empty() {
+            for family in detector_families(&record.detection.detector) {
+                samples.entry(family).or_default().push((record.detection.confidence, real));
+            }
+        }
+        for contribution in &record.contributions {
+            let family = contribution.detector.split(':').next().unwrap_or_default();
+            samples.entry(family).or_default().push((contribution.confidence, real));
+        }
+    }
+    samples
+        .into_iter()
+        .filter(|(_, samples)| samples.len() >= min_samples.max(1))
+        .map(|(family, samples)| (family.to_string(), Calibration::fit(method, &samples)))
+        .collect()
+}
+
+/// Follows the oracle's verdicts on reported detections
+pub struct OutcomeTracker<M: Middleware> {
+    config: CalibrationConfig,
+    store: Arc<FeedbackStore>,
+    provider: Arc<M>,
+    oracle: ThreatOracle<M>,
+    events: Option<ThreatEvents>,
+}
+
+impl<M: Middleware + 'static> OutcomeTracker<M> {
+    pub fn new(config: CalibrationConfig, store: Arc<FeedbackStore>, provider: Arc<M>) -> Self {
+        let oracle = ThreatOracle::new(config.oracle, provider.clone());
+        Self { config, store, provider, oracle, events: None }
+    }
+
+    /// Broadcast each verdict to threat event subscribers
+    pub fn with_events(mut self, events: ThreatEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    async fn report(&self, report_id: H256) -> Result<ThreatReport> {
+        let (chain_id, contract_address, threat_level, threat_type, timestamp, evidence_hash, confidence, reporter, verified) = self
+            .oracle
+            .get_threat_report(report_id.0)
+            .call()
+            .await
+            .with_context(|| format!("Failed to read threat report {:?}", report_id))?;
+        Ok(ThreatReport {
+            chain_id,
+            contract_address,
+            threat_level,
+            threat_type,
+            timestamp,
+            evidence_hash,
+            confidence,
+            reporter,
+            verified,
+        })
+    }
+
+    /// Attach oracle reports of detections not matched yet; returns how many were matched
+    pub async fn sync_reports(&self) -> Result<usize> {
+        let pending: Vec<DetectionRecord> = self.store.records()?.into_iter().filter(|record| record.report.is_none()).collect();
+        let targets: HashSet<Address> = pending.iter().filter_map(|record| record.detection.target).collect();
+        let feedback = self.store.config();
+
+        let head = self.provider.get_block_number().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+        let confirmed = head.saturating_sub(feedback.confirmations);
+        let mut matched = 0;
+        while self.store.cursor("reports")? < confirmed {
+            let from = self.store.cursor("reports")? + 1;
+            let to = confirmed.min(from + feedback.log_chunk_blocks.max(1) - 1);
+            let filter = Filter::new()
+                .address(self.config.oracle)
+                .topic0(ThreatReportedFilter::signature())
+                .from_block(from)
+                .to_block(to);
+            let logs = self
+                .provider
+                .get_logs(&filter)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .with_context(|| format!("Failed to fetch threat reports in blocks {}..={}", from, to))?;
+
+            for log in logs {
+                let event: ThreatReportedFilter = parse_log(log)?;
+                // Only reports about something we detected can be ours
+                if !targets.contains(&event.contract_address) {
+                    continue;
+                }
+                let report_id = H256(event.report_id);
+                let report = self.report(report_id).await?;
+                let threat_hash = H256(report.evidence_hash);
+                if pending.iter().any(|record| record.threat_hash == threat_hash) {
+                    let report = OracleReport {
+                        report_id,
+                        reported_at: report.timestamp.as_u64() as i64,
+                        verified: None,
+                    };
+                    self.store.set_report(&threat_hash, report)?;
+                    matched += 1;
+                }
+            }
+            self.store.set_cursor("reports", to)?;
+        }
+        Ok(matched)
+    }
+
+    /// Read the verdict of reports whose consensus window has closed; returns how many resolved
+    pub async fn resolve(&self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let mut resolved = 0;
+        for record in self.store.records()? {
+            let Some(mut report) = record.report else { continue };
+            if report.verified.is_some() || now < report.reported_at + self.config.consensus_window_secs {
+                continue;
+            }
+            let verified = self.report(report.report_id).await?.verified;
+            report.verified = Some(verified);
+            debug!("Threat report {:?} verified: {:?}", report.report_id, report.verified);
+            if let Some(events) = &self.events {
+                events.publish(ThreatEvent::Confirmation {
+                    category: record.detection.threat_type,
+                    confidence: record.detection.confidence,
+                    evidence_hash: record.threat_hash,
+                    report_id: report.report_id,
+                    verified,
+                    confirmed_at: now,
+                });
+            }
+            self.store.set_report(&record.threat_hash, report)?;
+            resolved += 1;
+        }
+        Ok(resolved)
+    }
+
+    /// Curves fitted to every outcome known so far
+    pub fn fit(&self) -> Result<HashMap<String, Calibration>> {
+        Ok(fit_curves(&self.store.records()?, self.config.method, self.config.min_samples))
+    }
+
+    /// Keep tracking outcomes and refitting `scoring`'s curves every poll interval
+    pub async fn run(self, scoring: ScoringModel) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.sync_reports().await {
+                warn!("Threat report sync failed: {:#}", e);
+            }
+            match self.resolve().await {
+                Ok(0) => {}
+                Ok(resolved) => info!("📐 {} threat reports resolved by the oracle", resolved),
+                Err(e) => warn!("Threat report resolution failed: {:#}", e),
+            }
+            match self.fit() {
+                Ok(curves) => scoring.set_calibration(curves),
+                Err(e) => warn!("Confidence calibration failed: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::{Detection, ThreatCategory};
+
+    #[test]
+    fn test_curves_match_observed_accuracy() {
+        // A detector that says 0.9 but is right half the time, and is right at 0.3 a quarter of it
+        let samples: Vec<(f64, bool)> = (0..40)
+            .map(|i| if i < 20 { (0.9, i % 2 == 0) } else { (0.3, i % 4 == 0) })
+            .collect();
+
+        let isotonic = Calibration::fit(CalibrationMethod::Isotonic, &samples);
+        let Calibration::Isotonic { points } = &isotonic else { unreachable!() };
+        assert_eq!(points.len(), 2);
+        for (confidence, accuracy) in [(0.1, 0.1), (0.3, 0.25), (0.6, 0.375), (0.9, 0.5), (1.0, 1.0)] {
+            assert!((isotonic.apply(confidence) - accuracy).abs() < 1e-9);
+        }
+
+        // Accuracy falling with confidence gets pooled flat
+        let inverted = Calibration::fit(CalibrationMethod::Isotonic, &[(0.2, true), (0.8, false)]);
+        assert_eq!(inverted, Calibration::Isotonic { points: vec![(0.5, 0.5)] });
+
+        let platt = Calibration::fit(CalibrationMethod::Platt, &samples);
+        assert!((platt.apply(0.9) - 0.5).abs() < 0.05 && (platt.apply(0.3) - 0.25).abs() < 0.05);
+        let json = serde_json::to_string(&platt).unwrap();
+        assert_eq!(serde_json::from_str::<Calibration>(&json).unwrap(), platt);
+
+        let record = |detector: &str, real: bool| DetectionRecord {
+            threat_hash: H256::zero(),
+            transaction_id: String::new(),
+            chain_id: None,
+            threat_proof: None,
+            detection: Detection {
+                detector: detector.to_string(),
+                threat_type: ThreatCategory::Phishing,
+                confidence: 0.9,
+                tx_hash: None,
+                target: None,
+                url: None,
+                explanation: String::new(),
+                evidence: None,
+            },
+            contributions: Vec::new(),
+            detected_at: 0,
+            label: None,
+            report: Some(OracleReport { report_id: H256::zero(), reported_at: 0, verified: Some(real) }),
+        };
+        let records = vec![record("score:model+rules", true), record("rules:a", false), record("intel:feed", true)];
+        let curves = fit_curves(&records, CalibrationMethod::Isotonic, 2);
+        assert_eq!(curves.keys().collect::<Vec<_>>(), ["rules"]);
+        assert_eq!(curves["rules"].apply(0.9), 0.5);
+    }
+
+    #[test]
+    fn test_isotonic_curve_leaves_unsampled_confidences() {
+        // The oracle only takes reports at 75% and up, so no low-confidence outcome is ever seen
+        let samples: Vec<(f64, bool)> = (0..40)
+            .map(|i| if i < 20 { (0.8, i % 2 == 0) } else { (0.95, i % 4 != 0) })
+            .collect();
+        let curve = Calibration::fit(CalibrationMethod::Isotonic, &samples);
+
+        assert!((curve.apply(0.8) - 0.5).abs() < 1e-9);
+        assert!((curve.apply(0.95) - 0.75).abs() < 1e-9);
+        for confidence in [0.05, 0.3, 0.6, 0.79] {
+            assert_eq!(curve.apply(confidence), confidence);
+        }
+    }
+}
@@ -0,0 +1,561 @@
+/*!
+ * Drainer kit fingerprinting
+ * Wallet drainers are sold as kits, and every deployment of a kit leaves the same traces: the
+ * calldata layout of its sweep calls, the fee splitter that pays the kit author their cut, and
+ * contract code that differs between deployments only in constants like the operator's address.
+ * The corpus holds fingerprints of all three, per kit. Layouts match on the selector and the
+ * shape of every argument word, fee splitters when a transaction calls or pays one, and code by
+ * fuzzy hash: a MinHash over opcode shingles with PUSH immediates dropped, so redeployments with
+ * other constants still land close. Fingerprints are published by DAGShieldOracle as
+ * `DrainerFingerprintAdded` events and arrive with the signature sync, which starts a fresh
+ * corpus from `signatures.start_block`; they're persisted in sled
+ */
+
+use anyhow::{bail, Context, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex, RwLock},
+};
+use tracing::{debug, info};
+
+use super::bytecode::disassemble;
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+/// MinHash permutations; similarity is estimated to about ±0.06
+const FUZZY_HASHES: usize = 64;
+/// Consecutive opcodes per shingle
+const SHINGLE_LEN: usize = 4;
+/// Calldata words a layout covers
+const MAX_LAYOUT_WORDS: usize = 32;
+/// Contracts whose fuzzy hash is cached before the cache starts over
+const CODE_CACHE_SIZE: usize = 10_000;
+const SYNCED_BLOCK_KEY: &[u8] = b"synced_block";
+
+/// Drainer kit fingerprinting settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrainerKitConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// Code at least this similar to a kit's fuzzy hash matches it
+    pub similarity_threshold: f64,
+    /// Kit matches at or above this confidence become detections
+    pub report_threshold: f64,
+}
+
+impl Default for DrainerKitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./data/drainer_kits".to_string(),
+            similarity_threshold: 0.85,
+            report_threshold: 0.6,
+        }
+    }
+}
+
+/// Shape of one calldata word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordClass {
+    Zero,
+    /// Fits 64 bits: amounts in small units, offsets, lengths, flags
+    Small,
+    /// Fits 160 bits, so most likely an address
+    Address,
+    /// All ones, like an unlimited allowance
+    Max,
+    Other,
+}
+
+impl WordClass {
+    fn of(word: &[u8]) -> Self {
+        if word.len() < 32 {
+            Self::Other
+        } else if word.iter().all(|&byte| byte == 0) {
+            Self::Zero
+        } else if word.iter().all(|&byte| byte == 0xff) {
+            Self::Max
+        } else if word[..24].iter().all(|&byte| byte == 0) {
+            Self::Small
+        } else if word[..12].iter().all(|&byte| byte == 0) {
+            Self::Address
+        } else {
+            Self::Other
+        }
+    }
+
+    fn code(self) -> u8 {
+        self as u8
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        [Self::Zero, Self::Small, Self::Address, Self::Max, Self::Other].get(code as usize).copied()
+    }
+}
+
+/// Selector and argument word shapes of a call
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CalldataLayout {
+    pub selector: [u8; 4],
+    pub words: Vec<WordClass>,
+}
+
+impl CalldataLayout {
+    /// Layout of `data`, or `None` without a selector
+    pub fn of(data: &[u8]) -> Option<Self> {
+        let selector = data.get(..4)?.try_into().unwrap();
+        let words = data[4..].chunks(32).take(MAX_LAYOUT_WORDS).map(WordClass::of).collect();
+        Some(Self { selector, words })
+    }
+
+    /// Selector followed by one byte per word class
+    fn to_bytes(&self) -> Vec<u8> {
+        self.selector.iter().copied().chain(self.words.iter().map(|word| word.code())).collect()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some(selector) = bytes.get(..4) else { bail!("Calldata layout without a selector") };
+        let words = bytes[4..]
+            .iter()
+            .map(|&code| WordClass::from_code(code).with_context(|| format!("Unknown word class {}", code)))
+            .collect::<Result<_>>()?;
+        Ok(Self { selector: selector.try_into().unwrap(), words })
+    }
+}
+
+/// MinHash of a contract's opcode shingles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FuzzyHash(pub Vec<u32>);
+
+/// SplitMix64 finalizer, a cheap stand-in for a family of hash permutations
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+impl FuzzyHash {
+    /// Fuzzy hash of `code`, or `None` when it's too short to have a shingle
+    pub fn of(code: &[u8]) -> Option<Self> {
+        let opcodes: Vec<u8> = disassemble(code).iter().map(|instruction| instruction.opcode).collect();
+        let shingles: HashSet<u64> = opcodes
+            .windows(SHINGLE_LEN)
+            .map(|shingle| mix(u32::from_be_bytes(shingle.try_into().unwrap()) as u64))
+            .collect();
+        if shingles.is_empty() {
+            return None;
+        }
+        let minima = (0..FUZZY_HASHES as u64)
+            .map(|i| {
+                let seed = mix(i.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15));
+                shingles.iter().map(|&shingle| (mix(shingle ^ seed) >> 32) as u32).min().unwrap()
+            })
+            .collect();
+        Some(Self(minima))
+    }
+
+    /// Estimated Jaccard similarity of the two contracts' shingle sets
+    pub fn similarity(&self, other: &Self) -> f64 {
+        if self.0.len() != other.0.len() || self.0.is_empty() {
+            return 0.0;
+        }
+        let equal = self.0.iter().zip(&other.0).filter(|(a, b)| a == b).count();
+        equal as f64 / self.0.len() as f64
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|minimum| minimum.to_be_bytes()).collect()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != FUZZY_HASHES * 4 {
+            bail!("Fuzzy hash of {} bytes, expected {}", bytes.len(), FUZZY_HASHES * 4);
+        }
+        Ok(Self(bytes.chunks(4).map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap())).collect()))
+    }
+}
+
+/// Trace a drainer kit leaves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Fingerprint {
+    CalldataLayout(CalldataLayout),
+    /// Address the kit's cut is paid to
+    FeeSplitter(Address),
+    Bytecode(FuzzyHash),
+}
+
+impl Fingerprint {
+    /// Oracle `kind` of a `DrainerFingerprintAdded` event
+    pub fn kind(&self) -> u8 {
+        match self {
+            Self::CalldataLayout(_) => 0,
+            Self::FeeSplitter(_) => 1,
+            Self::Bytecode(_) => 2,
+        }
+    }
+
+    /// Fingerprint from an event's `kind` and `data`
+    pub fn decode(kind: u8, data: &[u8]) -> Result<Self> {
+        match kind {
+            0 => Ok(Self::CalldataLayout(CalldataLayout::from_bytes(data)?)),
+            1 if data.len() == 20 => Ok(Self::FeeSplitter(Address::from_slice(data))),
+            1 => bail!("Fee splitter of {} bytes", data.len()),
+            2 => Ok(Self::Bytecode(FuzzyHash::from_bytes(data)?)),
+            _ => bail!("Unknown fingerprint kind {}", kind),
+        }
+    }
+
+    /// `data` of the event publishing it
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::CalldataLayout(layout) => layout.to_bytes(),
+            Self::FeeSplitter(address) => address.as_bytes().to_vec(),
+            Self::Bytecode(hash) => hash.to_bytes(),
+        }
+    }
+
+    /// Confidence a full match gives on its own. Benign contracts share call layouts, so a
+    /// layout alone stays under the default report threshold
+    fn weight(&self) -> f64 {
+        match self {
+            Self::CalldataLayout(_) => 0.5,
+            Self::FeeSplitter(_) => 0.9,
+            Self::Bytecode(_) => 0.85,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::CalldataLayout(_) => "calldata layout",
+            Self::FeeSplitter(_) => "fee splitter",
+            Self::Bytecode(_) => "contract code",
+        }
+    }
+}
+
+/// Published fingerprint of a kit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KitFingerprint {
+    /// Event index on the oracle
+    pub index: u64,
+    pub kit: String,
+    /// Threat the fingerprint was confirmed under
+    pub threat_hash: H256,
+    pub fingerprint: Fingerprint,
+    pub block: u64,
+}
+
+/// What fingerprints are compared against in a transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KitFeatures {
+    pub layout: Option<CalldataLayout>,
+    /// Called contract and address arguments
+    pub addresses: HashSet<Address>,
+    /// Called contract's code, or the init code of a deployment
+    pub code: Option<FuzzyHash>,
+}
+
+impl KitFeatures {
+    /// Features of `tx`, with `code` of the contract it calls if known
+    pub fn of(tx: &TxContext, code: Option<FuzzyHash>) -> Self {
+        let layout = CalldataLayout::of(&tx.data);
+        let arguments = tx.data.get(4..).unwrap_or_default().chunks(32).filter(|word| WordClass::of(word) == WordClass::Address);
+        let addresses = tx.to.into_iter().chain(arguments.map(|word| Address::from_slice(&word[12..]))).collect();
+        let code = match tx.to {
+            Some(_) => code,
+            None => FuzzyHash::of(&tx.data),
+        };
+        Self { layout, addresses, code }
+    }
+}
+
+struct State {
+    fingerprints: Vec<KitFingerprint>,
+    indices: HashSet<u64>,
+    synced_block: Option<u64>,
+}
+
+/// Persistent corpus of drainer kit fingerprints
+pub struct DrainerKits {
+    config: DrainerKitConfig,
+    tree: sled::Tree,
+    state: RwLock<State>,
+}
+
+impl DrainerKits {
+    pub fn open(config: DrainerKitConfig) -> Result<Self> {
+        let db = sled::open(&config.path).with_context(|| format!("Failed to open drainer kit corpus at {}", config.path))?;
+        let tree = db.open_tree("drainer_kits")?;
+        let fingerprints = tree
+            .scan_prefix(b"fp:")
+            .values()
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect::<Result<Vec<KitFingerprint>>>()
+            .context("Corrupt drainer kit corpus")?;
+        let synced_block = tree
+            .get(SYNCED_BLOCK_KEY)?
+            .map(|value| Ok::<_, anyhow::Error>(u64::from_be_bytes(value.as_ref().try_into().context("Corrupt synced block")?)))
+            .transpose()?;
+        match synced_block {
+            Some(block) => info!("🪝 Loaded {} drainer kit fingerprints (synced to block {})", fingerprints.len(), block),
+            None => info!("🪝 Drainer kit corpus is empty, waiting for the signature sync"),
+        }
+        let state = State {
+            indices: fingerprints.iter().map(|fingerprint| fingerprint.index).collect(),
+            fingerprints,
+            synced_block,
+        };
+        Ok(Self {
+            config,
+            tree,
+            state: RwLock::new(state),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.read().unwrap().fingerprints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Last block fingerprints were synced to, `None` before the first sync
+    pub fn synced_block(&self) -> Option<u64> {
+        self.state.read().unwrap().synced_block
+    }
+
+    /// Whether any kit is fingerprinted by its code, so fetching code is worth it
+    fn has_bytecode(&self) -> bool {
+        self.state.read().unwrap().fingerprints.iter().any(|fingerprint| matches!(fingerprint.fingerprint, Fingerprint::Bytecode(_)))
+    }
+
+    /// Add fingerprints synced up to `synced_block`; ones already known are skipped
+    pub fn ingest(&self, fingerprints: Vec<KitFingerprint>, synced_block: u64) -> Result<usize> {
+        let mut state = self.state.write().unwrap();
+        let added: Vec<KitFingerprint> = fingerprints
+            .into_iter()
+            .filter(|fingerprint| !state.indices.contains(&fingerprint.index))
+            .collect();
+
+        let mut batch = sled::Batch::default();
+        for fingerprint in &added {
+            batch.insert(format!("fp:{:020}", fingerprint.index).as_bytes(), serde_json::to_vec(fingerprint)?);
+        }
+        let synced_block = state.synced_block.map_or(synced_block, |synced| synced.max(synced_block));
+        batch.insert(SYNCED_BLOCK_KEY, &synced_block.to_be_bytes());
+        self.tree.apply_batch(batch)?;
+        self.tree.flush()?;
+
+        let count = added.len();
+        for fingerprint in added {
+            debug!("🪝 Fingerprint #{} of kit {}: {}", fingerprint.index, fingerprint.kit, fingerprint.fingerprint.describe());
+            state.indices.insert(fingerprint.index);
+            state.fingerprints.push(fingerprint);
+        }
+        state.synced_block = Some(synced_block);
+        Ok(count)
+    }
+
+    /// Confidence a fingerprint's match gives, if it matches
+    fn match_strength(&self, fingerprint: &Fingerprint, features: &KitFeatures) -> Option<f64> {
+        match fingerprint {
+            Fingerprint::CalldataLayout(layout) => (features.layout.as_ref() == Some(layout)).then(|| fingerprint.weight()),
+            Fingerprint::FeeSplitter(address) => features.addresses.contains(address).then(|| fingerprint.weight()),
+            Fingerprint::Bytecode(hash) => {
+                let similarity = hash.similarity(features.code.as_ref()?);
+                (similarity >= self.config.similarity_threshold).then(|| fingerprint.weight() * similarity)
+            }
+        }
+    }
+
+    /// One detection per kit whose fingerprints `features` match, treating its matches as
+    /// independent evidence
+    pub fn detect(&self, tx: &TxContext, features: &KitFeatures) -> Vec<Detection> {
+        let state = self.state.read().unwrap();
+        let mut kits: BTreeMap<&str, Vec<(&KitFingerprint, f64)>> = BTreeMap::new();
+        for fingerprint in &state.fingerprints {
+            if let Some(strength) = self.match_strength(&fingerprint.fingerprint, features) {
+                kits.entry(&fingerprint.kit).or_default().push((fingerprint, strength));
+            }
+        }
+
+        kits.into_iter()
+            .filter_map(|(kit, matches)| {
+                let confidence = 1.0 - matches.iter().map(|(_, strength)| 1.0 - strength).product::<f64>();
+                if confidence < self.config.report_threshold {
+                    return None;
+                }
+                let (strongest, _) = matches.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+                let traces: Vec<&str> = matches.iter().map(|(fingerprint, _)| fingerprint.fingerprint.describe()).collect();
+                Some(Detection {
+                    detector: format!("drainer_kits:{}", kit),
+                    threat_type: ThreatCategory::Drainer,
+                    confidence,
+                    tx_hash: tx.hash,
+                    target: tx.to,
+                    url: None,
+                    explanation: format!("Matches the {} of drainer kit {}", traces.join(", "), kit),
+                    evidence: Some(strongest.threat_hash),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Runs the corpus on transactions, fetching called contracts' code through a provider
+pub struct DrainerKitDetector<M> {
+    corpus: Arc<DrainerKits>,
+    provider: Arc<M>,
+    /// Fuzzy hashes by contract; `None` for accounts without code
+    code: Mutex<HashMap<Address, Option<FuzzyHash>>>,
+}
+
+impl<M: Middleware> DrainerKitDetector<M> {
+    pub fn new(corpus: Arc<DrainerKits>, provider: Arc<M>) -> Self {
+        Self {
+            corpus,
+            provider,
+            code: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn fuzzy_hash(&self, address: Address) -> Result<Option<FuzzyHash>> {
+        if let Some(hash) = self.code.lock().unwrap().get(&address) {
+            return Ok(hash.clone());
+        }
+        let code = self
+            .provider
+            .get_code(address, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to fetch code of {:?}", address))?;
+        let hash = FuzzyHash::of(&code);
+        let mut cache = self.code.lock().unwrap();
+        if cache.len() >= CODE_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(address, hash.clone());
+        Ok(hash)
+    }
+}
+
+impl<M: Middleware> ThreatDetector for DrainerKitDetector<M> {
+    type Features = KitFeatures;
+
+    fn name(&self) -> &str {
+        "drainer_kits"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        Some(ThreatCategory::Drainer)
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<KitFeatures>> {
+        if self.corpus.is_empty() {
+            return Ok(None);
+        }
+        let code = match tx.to {
+            Some(to) if self.corpus.has_bytecode() => self.fuzzy_hash(to).await?,
+            _ => None,
+        };
+        Ok(Some(KitFeatures::of(tx, code)))
+    }
+
+    fn score(&self, tx: &TxContext, features: &KitFeatures) -> Vec<Detection> {
+        self.corpus.detect(tx, features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::id;
+
+    #[test]
+    fn test_kits_matched_by_layout_splitter_and_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = DrainerKitConfig {
+            enabled: true,
+            path: dir.path().to_string_lossy().into_owned(),
+            ..DrainerKitConfig::default()
+        };
+
+        // Kit contract: a dispatcher with the operator's address pushed as a constant
+        let kit_code = |operator: u8| {
+            let mut code = Vec::new();
+            for (i, signature) in ["Claim()", "SecurityUpdate()", "multicall(bytes[])", "withdraw(address)"].iter().enumerate() {
+                code.extend_from_slice(&[0x80, 0x63]);
+                code.extend_from_slice(&id(signature));
+                code.extend_from_slice(&[0x14, 0x60, 0x40 + i as u8, 0x57, 0x33, 0x73]);
+                code.extend_from_slice(&[operator; 20]);
+                code.extend_from_slice(&[0x14, 0x15, 0x60, 0x00, 0x55, 0x47, 0x5a, 0xf1]);
+            }
+            code
+        };
+        let original = FuzzyHash::of(&kit_code(1)).unwrap();
+        assert_eq!(original.similarity(&FuzzyHash::of(&kit_code(2)).unwrap()), 1.0);
+        assert!(original.similarity(&FuzzyHash::of(&[0x60, 0x01, 0x60, 0x02, 0x01, 0x00, 0x5b, 0x56, 0x50]).unwrap()) < 0.5);
+
+        // transferFrom(victim, splitter, amount)
+        let splitter = Address::repeat_byte(0x5a);
+        let sweep = |to: Address| {
+            let data = [
+                id("transferFrom(address,address,uint256)").to_vec(),
+                [[0u8; 12].as_slice(), Address::repeat_byte(0x11).as_bytes()].concat(),
+                [[0u8; 12].as_slice(), to.as_bytes()].concat(),
+                [0xff; 32].to_vec(),
+            ]
+            .concat();
+            TxContext { hash: Some(H256::repeat_byte(9)), to: Some(Address::repeat_byte(0x70)), data: data.into(), ..TxContext::default() }
+        };
+        let fingerprint = |index: u64, fingerprint: Fingerprint| KitFingerprint {
+            index,
+            kit: "inferno".to_string(),
+            threat_hash: H256::from_low_u64_be(index),
+            fingerprint,
+            block: 100,
+        };
+        let layout = CalldataLayout::of(&sweep(splitter).data).unwrap();
+        assert_eq!(layout.words, [WordClass::Address, WordClass::Address, WordClass::Max]);
+        assert_eq!(Fingerprint::decode(0, &Fingerprint::CalldataLayout(layout.clone()).encode()).unwrap(), Fingerprint::CalldataLayout(layout.clone()));
+
+        let corpus = DrainerKits::open(config.clone()).unwrap();
+        let published = vec![
+            fingerprint(0, Fingerprint::CalldataLayout(layout)),
+            fingerprint(1, Fingerprint::FeeSplitter(splitter)),
+            fingerprint(2, Fingerprint::Bytecode(original)),
+        ];
+        assert_eq!(corpus.ingest(published.clone(), 120).unwrap(), 3);
+        assert_eq!(corpus.ingest(published, 130).unwrap(), 0);
+
+        // Layout alone is too weak; the splitter receiving the sweep is not
+        let elsewhere = sweep(Address::repeat_byte(0x22));
+        assert!(corpus.detect(&elsewhere, &KitFeatures::of(&elsewhere, None)).is_empty());
+        let paid = sweep(splitter);
+        let detections = corpus.detect(&paid, &KitFeatures::of(&paid, None));
+        assert_eq!(detections.len(), 1);
+        assert_eq!((detections[0].detector.as_str(), detections[0].threat_type), ("drainer_kits:inferno", ThreatCategory::Drainer));
+        assert!((detections[0].confidence - 0.95).abs() < 1e-9);
+        assert_eq!(detections[0].evidence, Some(H256::from_low_u64_be(1)));
+
+        // Redeployed kit contract with another operator, through an unrelated call
+        let call = TxContext { to: Some(Address::repeat_byte(0x71)), data: id("Claim()").to_vec().into(), ..TxContext::default() };
+        let redeployed = KitFeatures::of(&call, FuzzyHash::of(&kit_code(3)));
+        assert!((corpus.detect(&call, &redeployed)[0].confidence - 0.85).abs() < 1e-9);
+        // Deploying it is caught from the init code
+        let deployment = TxContext { to: None, data: kit_code(4).into(), ..TxContext::default() };
+        assert_eq!(corpus.detect(&deployment, &KitFeatures::of(&deployment, None)).len(), 1);
+        drop(corpus);
+
+        let reopened = DrainerKits::open(config).unwrap();
+        assert_eq!((reopened.len(), reopened.synced_block()), (3, Some(130)));
+    }
+}
@@ -0,0 +1,484 @@
+/*!
+ * Ensemble voting
+ * Runs several models or rule sets side by side as one detector. Each member votes for the
+ * category it is most confident in, or for "safe" by reporting nothing, and a configurable
+ * strategy combines the votes: a weighted vote, the most confident member, or a logistic over the
+ * weighted member confidences. That logistic is fixed by the configured weights and bias; nothing
+ * is learned from outcomes. Agreement is the weighted share of members behind the
+ * outcome; below `min_agreement` the confidence is scaled down with it, so members contradicting
+ * each other can't produce a confident detection. How often each member agrees with the outcome
+ * is recorded, which flags members drifting from the rest
+ */
+
+use anyhow::{Context, Result};
+use ort::{ExecutionProvider, GraphOptimizationLevel, Session, SessionBuilder, Value};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tracing::{debug, info};
+
+use super::registry::{DetectorRegistry, RegistryConfig, ThreatDetector};
+use super::rules::{RulesConfig, RulesEngine};
+use super::{Detection, ThreatCategory, TxContext};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnsembleStrategy {
+    /// Per category, the weighted mean confidence over all members, voting or not
+    WeightedVote,
+    /// The most confident member's vote
+    Max,
+    /// `σ(stacking_bias + Σ weight · confidence)` per category: a logistic whose coefficients are
+    /// the configured weights and bias as they stand, not fitted to anything
+    Stacking,
+}
+
+impl EnsembleStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WeightedVote => "weighted_vote",
+            Self::Max => "max",
+            Self::Stacking => "stacking",
+        }
+    }
+}
+
+/// Where a member comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MemberSource {
+    Rules { path: PathBuf },
+    /// ONNX threat classifier; `labels` name its output classes in order
+    Model {
+        path: PathBuf,
+        #[serde(default = "default_labels")]
+        labels: Vec<String>,
+    },
+}
+
+fn default_labels() -> Vec<String> {
+    ["safe", "phishing", "rug_pull", "flash_loan_attack", "smart_contract_exploit"]
+        .map(String::from)
+        .to_vec()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub source: MemberSource,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// Ensemble settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleConfig {
+    pub enabled: bool,
+    pub strategy: EnsembleStrategy,
+    pub members: Vec<MemberConfig>,
+    /// Agreement below which the confidence is scaled down
+    pub min_agreement: f64,
+    /// Intercept of the stacking logistic, set by hand like the member weights
+    pub stacking_bias: f64,
+    pub timeout_ms: u64,
+}
+
+impl Default for EnsembleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strategy: EnsembleStrategy::WeightedVote,
+            members: Vec::new(),
+            min_agreement: 0.5,
+            stacking_bias: -2.0,
+            timeout_ms: 5_000,
+        }
+    }
+}
+
+/// Member detector reporting under the member's name, so members of one kind stay apart
+struct Member<D> {
+    name: String,
+    detector: D,
+}
+
+impl<D: ThreatDetector> ThreatDetector for Member<D> {
+    type Features = D::Features;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        self.detector.category()
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<D::Features>> {
+        self.detector.featurize(tx).await
+    }
+
+    fn score(&self, tx: &TxContext, features: &D::Features) -> Vec<Detection> {
+        self.detector
+            .score(tx, features)
+            .into_iter()
+            .map(|detection| Detection {
+                detector: format!("{}:{}", self.name, detection.detector),
+                ..detection
+            })
+            .collect()
+    }
+}
+
+/// ONNX classifier voting for its most probable class
+pub struct OnnxClassifier {
+    session: Session,
+    labels: Vec<String>,
+}
+
+impl OnnxClassifier {
+    pub fn load(path: &std::path::Path, labels: Vec<String>) -> Result<Self> {
+        let session = SessionBuilder::new()?
+            .with_optimization_level(GraphOptimizationLevel::All)?
+            .with_intra_threads(1)?
+            .with_execution_providers([ExecutionProvider::CPU(Default::default())])?
+            .commit_from_file(path)
+            .with_context(|| format!("Failed to load ensemble model {}", path.display()))?;
+        Ok(Self { session, labels })
+    }
+
+    /// The node's main model featurizer, so members trained like it see the same inputs
+    fn features(tx: &TxContext) -> Vec<f32> {
+        crate::ai::extract_features(&crate::dag::Transaction::from(tx))
+    }
+}
+
+impl ThreatDetector for OnnxClassifier {
+    /// Class probabilities
+    type Features = Vec<f32>;
+
+    fn name(&self) -> &str {
+        "onnx"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<Vec<f32>>> {
+        let features = Self::features(tx);
+        let input = Value::from_array(([1, features.len()], features))?;
+        let outputs = self.session.run(vec![input])?;
+        let probabilities = outputs[0].try_extract_tensor::<f32>()?;
+        Ok(Some(probabilities.iter().copied().collect()))
+    }
+
+    fn score(&self, tx: &TxContext, probabilities: &Vec<f32>) -> Vec<Detection> {
+        let Some((class, &probability)) = probabilities.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)) else {
+            return Vec::new();
+        };
+        // "safe" and unknown labels map to no category, a vote for safe
+        let Some(category) = self.labels.get(class).and_then(|label| ThreatCategory::from_label(label)) else {
+            return Vec::new();
+        };
+        vec![Detection {
+            detector: "onnx".to_string(),
+            threat_type: category,
+            confidence: probability as f64,
+            tx_hash: tx.hash,
+            target: tx.to,
+            url: None,
+            explanation: format!("model predicts {} at {:.0}%", self.labels[class], probability * 100.0),
+            evidence: None,
+        }]
+    }
+}
+
+/// How often a member voted with the ensemble
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MemberAgreement {
+    pub votes: u64,
+    pub agreed: u64,
+}
+
+impl MemberAgreement {
+    pub fn rate(&self) -> f64 {
+        if self.votes == 0 {
+            return 1.0;
+        }
+        self.agreed as f64 / self.votes as f64
+    }
+}
+
+/// Several detectors voting as one
+pub struct Ensemble {
+    config: EnsembleConfig,
+    members: DetectorRegistry,
+    weights: Vec<(String, f64)>,
+    agreement: Mutex<BTreeMap<String, MemberAgreement>>,
+}
+
+impl Ensemble {
+    pub fn new(config: EnsembleConfig) -> Self {
+        let members = DetectorRegistry::new(RegistryConfig {
+            disabled: Vec::new(),
+            timeout_ms: config.timeout_ms,
+        });
+        Self {
+            config,
+            members,
+            weights: Vec::new(),
+            agreement: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Ensemble of the configured members, with rules files watched for changes
+    pub fn load(config: EnsembleConfig) -> Result<Self> {
+        let mut ensemble = Self::new(config.clone());
+        for member in &config.members {
+            match &member.source {
+                MemberSource::Rules { path } => {
+                    let rules = Arc::new(RulesEngine::load(RulesConfig {
+                        enabled: true,
+                        path: path.clone(),
+                        ..RulesConfig::default()
+                    })?);
+                    tokio::spawn(Arc::clone(&rules).watch());
+                    ensemble.add(&member.name, member.weight, rules);
+                }
+                MemberSource::Model { path, labels } => {
+                    ensemble.add(&member.name, member.weight, OnnxClassifier::load(path, labels.clone())?);
+                }
+            }
+        }
+        info!("🗳️ Ensemble of {} members voting by {}", ensemble.len(), config.strategy.as_str());
+        Ok(ensemble)
+    }
+
+    /// Add a member voting with `weight` under `name`
+    pub fn add<D: ThreatDetector + 'static>(&mut self, name: &str, weight: f64, detector: D) {
+        self.members.register(Member { name: name.to_string(), detector });
+        self.weights.push((name.to_string(), weight.max(0.0)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Agreement of every member that has voted
+    pub fn agreement(&self) -> BTreeMap<String, MemberAgreement> {
+        self.agreement.lock().unwrap().clone()
+    }
+
+    /// Each member's vote: its most confident detection, `None` for safe
+    fn votes<'a>(&self, detections: &'a [Detection]) -> Vec<(&str, f64, Option<&'a Detection>)> {
+        self.weights
+            .iter()
+            .map(|(name, weight)| {
+                let vote = detections
+                    .iter()
+                    .filter(|detection| detection.detector.split(':').next() == Some(name.as_str()))
+                    .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+                (name.as_str(), *weight, vote)
+            })
+            .collect()
+    }
+
+    /// Combined (category, confidence) of `votes` under the configured strategy
+    fn combine(&self, votes: &[(&str, f64, Option<&Detection>)]) -> Option<(ThreatCategory, f64)> {
+        let total_weight: f64 = votes.iter().map(|(_, weight, _)| weight).sum();
+        let mut per_category: HashMap<ThreatCategory, f64> = HashMap::new();
+        for (_, weight, vote) in votes {
+            let Some(detection) = vote else { continue };
+            let contribution = match self.config.strategy {
+                EnsembleStrategy::WeightedVote => weight * detection.confidence / total_weight.max(f64::EPSILON),
+                EnsembleStrategy::Max => detection.confidence,
+                EnsembleStrategy::Stacking => weight * detection.confidence,
+            };
+            let entry = per_category.entry(detection.threat_type).or_default();
+            *entry = match self.config.strategy {
+                EnsembleStrategy::Max => entry.max(contribution),
+                _ => *entry + contribution,
+            };
+        }
+
+        let (category, score) = per_category.into_iter().max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))?;
+        let confidence = match self.config.strategy {
+            EnsembleStrategy::Stacking => 1.0 / (1.0 + (-(self.config.stacking_bias + score)).exp()),
+            _ => score,
+        };
+        Some((category, confidence.clamp(0.0, 1.0)))
+    }
+}
+
+impl ThreatDetector for Ensemble {
+    /// Every member's detections
+    type Features = Vec<Detection>;
+
+    fn name(&self) -> &str {
+        "ensemble"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<Vec<Detection>>> {
+        Ok(Some(self.members.run(tx).await))
+    }
+
+    fn score(&self, tx: &TxContext, detections: &Vec<Detection>) -> Vec<Detection> {
+        let votes = self.votes(detections);
+        let outcome = self.combine(&votes);
+        let category = outcome.map(|(category, _)| category);
+
+        let total_weight: f64 = votes.iter().map(|(_, weight, _)| weight).sum();
+        let mut agreeing_weight = 0.0;
+        {
+            let mut agreement = self.agreement.lock().unwrap();
+            for (name, weight, vote) in &votes {
+                let agreed = vote.map(|detection| detection.threat_type) == category;
+                let member = agreement.entry(name.to_string()).or_default();
+                member.votes += 1;
+                member.agreed += agreed as u64;
+                if agreed {
+                    agreeing_weight += weight;
+                }
+            }
+        }
+
+        let Some((category, mut confidence)) = outcome else {
+            return Vec::new();
+        };
+        let agreement = agreeing_weight / total_weight.max(f64::EPSILON);
+        if agreement < self.config.min_agreement {
+            confidence *= agreement / self.config.min_agreement;
+        }
+        debug!("🗳️ Ensemble {:?}: {} at {:.2}, agreement {:.2}", tx.hash, category, confidence, agreement);
+
+        let backing: Vec<&Detection> = votes
+            .iter()
+            .filter_map(|(_, _, vote)| *vote)
+            .filter(|detection| detection.threat_type == category)
+            .collect();
+        let Some(lead) = backing.iter().max_by(|a, b| a.confidence.total_cmp(&b.confidence)) else {
+            return Vec::new();
+        };
+        vec![Detection {
+            detector: format!("ensemble:{}", self.config.strategy.as_str()),
+            threat_type: category,
+            confidence,
+            tx_hash: tx.hash,
+            target: lead.target.or(tx.to),
+            url: lead.url.clone(),
+            explanation: format!(
+                "{}/{} members agree ({:.0}% of weight): {}",
+                backing.len(),
+                votes.len(),
+                agreement * 100.0,
+                backing.iter().map(|detection| detection.explanation.as_str()).collect::<Vec<_>>().join("; ")
+            ),
+            evidence: lead.evidence,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Votes `category` at `confidence` on every transaction, or safe
+    struct Fixed(Option<(ThreatCategory, f64)>);
+
+    impl ThreatDetector for Fixed {
+        type Features = ();
+
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn category(&self) -> Option<ThreatCategory> {
+            self.0.map(|(category, _)| category)
+        }
+
+        async fn featurize(&self, _tx: &TxContext) -> Result<Option<()>> {
+            Ok(Some(()))
+        }
+
+        fn score(&self, _tx: &TxContext, _features: &()) -> Vec<Detection> {
+            self.0
+                .map(|(category, confidence)| Detection {
+                    detector: "fixed".to_string(),
+                    threat_type: category,
+                    confidence,
+                    tx_hash: None,
+                    target: None,
+                    url: None,
+                    explanation: format!("{} vote", category),
+                    evidence: None,
+                })
+                .into_iter()
+                .collect()
+        }
+    }
+
+    fn ensemble(strategy: EnsembleStrategy, members: &[(&str, f64, Option<(ThreatCategory, f64)>)]) -> Ensemble {
+        let mut ensemble = Ensemble::new(EnsembleConfig { strategy, ..EnsembleConfig::default() });
+        for (name, weight, vote) in members {
+            ensemble.add(name, *weight, Fixed(*vote));
+        }
+        ensemble
+    }
+
+    async fn run(ensemble: &Ensemble) -> Option<Detection> {
+        let tx = TxContext::default();
+        let features = ensemble.featurize(&tx).await.unwrap().unwrap();
+        ensemble.score(&tx, &features).pop()
+    }
+
+    #[tokio::test]
+    async fn test_strategies_and_disagreement() {
+        let drainer = Some((ThreatCategory::Drainer, 0.9));
+        let members = [("a", 2.0, drainer), ("b", 1.0, drainer), ("c", 1.0, Some((ThreatCategory::Phishing, 0.6)))];
+
+        // 0.9 · 3/4 of the weight behind drainers
+        let vote = ensemble(EnsembleStrategy::WeightedVote, &members);
+        let detection = run(&vote).await.unwrap();
+        assert_eq!((detection.threat_type, detection.detector.as_str()), (ThreatCategory::Drainer, "ensemble:weighted_vote"));
+        assert!((detection.confidence - 0.675).abs() < 1e-9);
+        assert!(detection.explanation.starts_with("2/3 members agree (75% of weight)"));
+        assert_eq!(vote.agreement()["c"], MemberAgreement { votes: 1, agreed: 0 });
+
+        let max = run(&ensemble(EnsembleStrategy::Max, &members)).await.unwrap();
+        assert!((max.confidence - 0.9).abs() < 1e-9);
+        let stacked = run(&ensemble(EnsembleStrategy::Stacking, &members)).await.unwrap();
+        assert!((stacked.confidence - 1.0 / (1.0 + (-(2.7f64 - 2.0)).exp())).abs() < 1e-9);
+
+        // One confident member against three safe ones agrees with a quarter of the weight
+        let lone = [("a", 1.0, drainer), ("b", 1.0, None), ("c", 1.0, None), ("d", 1.0, None)];
+        let max = ensemble(EnsembleStrategy::Max, &lone);
+        let detection = run(&max).await.unwrap();
+        assert!((detection.confidence - 0.9 * 0.25 / 0.5).abs() < 1e-9);
+        assert_eq!(max.agreement()["b"].rate(), 0.0);
+
+        let safe = ensemble(EnsembleStrategy::WeightedVote, &[("a", 1.0, None), ("b", 1.0, None)]);
+        assert!(run(&safe).await.is_none());
+        assert_eq!(safe.agreement()["a"].rate(), 1.0);
+
+        // Model members get the main model's inputs: length, timestamp, chain, ..., entropy
+        let tx = TxContext { chain_id: 39, data: vec![0, 1].into(), ..TxContext::default() };
+        let features = OnnxClassifier::features(&tx);
+        assert_eq!((features.len(), features[0], features[2], features[6]), (512, 2.0, 39.0, 1.0));
+    }
+}
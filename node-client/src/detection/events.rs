@@ -0,0 +1,160 @@
+/*!
+ * Threat events
+ * Detections the node reports and the oracle's verdicts on them, broadcast as they happen so
+ * local wallets and dApps can warn before a transaction goes through. Every event carries the
+ * category, confidence and, for reported detections, the evidence hash: the on-chain threat hash
+ * the detection went out under, which its oracle report and so its confirmation carry too.
+ * Mempool and URL check detections aren't reported and have none. Slow subscribers miss events
+ * rather than hold up detection
+ */
+
+use ethers::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::{Detection, ThreatCategory};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ThreatEvent {
+    Detection {
+        category: ThreatCategory,
+        confidence: f64,
+        evidence_hash: Option<H256>,
+        detector: String,
+        target: Option<Address>,
+        tx_hash: Option<H256>,
+        url: Option<String>,
+        explanation: String,
+        detected_at: i64,
+    },
+    /// The oracle's consensus verdict on a reported detection
+    Confirmation {
+        category: ThreatCategory,
+        confidence: f64,
+        evidence_hash: H256,
+        report_id: H256,
+        verified: bool,
+        confirmed_at: i64,
+    },
+    /// This subscriber fell behind and missed events
+    Lagged { missed: u64 },
+}
+
+impl ThreatEvent {
+    /// Event for `detection`, reported on chain under `evidence_hash` if it was
+    pub fn detection(detection: &Detection, evidence_hash: Option<H256>) -> Self {
+        Self::Detection {
+            category: detection.threat_type,
+            confidence: detection.confidence,
+            evidence_hash,
+            detector: detection.detector.clone(),
+            target: detection.target,
+            tx_hash: detection.tx_hash,
+            url: detection.url.clone(),
+            explanation: detection.explanation.clone(),
+            detected_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    pub fn category(&self) -> Option<ThreatCategory> {
+        match self {
+            Self::Detection { category, .. } | Self::Confirmation { category, .. } => Some(*category),
+            Self::Lagged { .. } => None,
+        }
+    }
+
+    pub fn confidence(&self) -> Option<f64> {
+        match self {
+            Self::Detection { confidence, .. } | Self::Confirmation { confidence, .. } => Some(*confidence),
+            Self::Lagged { .. } => None,
+        }
+    }
+}
+
+/// Which events a subscriber wants; lag notices always pass
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventFilter {
+    pub min_confidence: Option<f64>,
+    pub category: Option<ThreatCategory>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &ThreatEvent) -> bool {
+        let confident = match (self.min_confidence, event.confidence()) {
+            (Some(min), Some(confidence)) => confidence >= min,
+            _ => true,
+        };
+        let category = match (self.category, event.category()) {
+            (Some(wanted), Some(category)) => wanted == category,
+            _ => true,
+        };
+        confident && category
+    }
+}
+
+/// Broadcast hub for threat events
+#[derive(Debug, Clone)]
+pub struct ThreatEvents {
+    sender: broadcast::Sender<ThreatEvent>,
+}
+
+impl ThreatEvents {
+    /// Hub buffering `capacity` events per subscriber
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity.max(1)).0,
+        }
+    }
+
+    pub fn publish(&self, event: ThreatEvent) {
+        // No subscribers just means nobody is listening yet
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ThreatEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_format_and_filter() {
+        let detection = Detection {
+            detector: "rules:drainer".to_string(),
+            threat_type: ThreatCategory::Drainer,
+            confidence: 0.92,
+            tx_hash: Some(H256::repeat_byte(1)),
+            target: Some(Address::repeat_byte(2)),
+            url: None,
+            explanation: "setApprovalForAll to a drainer".to_string(),
+            evidence: None,
+        };
+        let event = ThreatEvent::detection(&detection, Some(H256::repeat_byte(3)));
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "detection");
+        assert_eq!(json["category"], "drainer");
+        assert_eq!(json["confidence"], 0.92);
+        assert_eq!(json["evidence_hash"], serde_json::to_value(H256::repeat_byte(3)).unwrap());
+        assert!(serde_json::to_value(ThreatEvent::detection(&detection, None)).unwrap()["evidence_hash"].is_null());
+
+        let drainers = EventFilter { min_confidence: Some(0.9), category: Some(ThreatCategory::Drainer) };
+        assert!(drainers.matches(&event));
+        assert!(!EventFilter { min_confidence: Some(0.95), category: None }.matches(&event));
+        assert!(!EventFilter { min_confidence: None, category: Some(ThreatCategory::Phishing) }.matches(&event));
+        assert!(drainers.matches(&ThreatEvent::Lagged { missed: 3 }));
+
+        let events = ThreatEvents::new(1);
+        let mut subscriber = events.subscribe();
+        events.publish(event.clone());
+        events.publish(event);
+        assert!(matches!(subscriber.try_recv(), Err(broadcast::error::TryRecvError::Lagged(1))));
+    }
+}
@@ -0,0 +1,411 @@
+/*!
+ * False-positive feedback
+ * Detections the node reports are kept, keyed by the id the report has on-chain (the
+ * `ThreatDetected` alert id, or the threat hash of a proven threat), so they can be labelled
+ * later: by an operator over the API, or by the resolution of a dispute against the report on
+ * DAGShield. Labels are persisted in sled next to the detections, as are oracle outcomes once
+ * calibration tracks them. Each detector family's precision over labels (unlabelled detections
+ * count as correct) feeds back into the scoring weights, so a family that keeps getting disputed
+ * counts for less, and is reported per period to show how it trends. Oracle outcomes are left to
+ * the calibration curves
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    contract::{abigen, parse_log, EthEvent},
+    providers::Middleware,
+    types::{Address, Filter, H256},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+use super::scoring::{detector_families, ScoringConfig, ScoringModel};
+use super::Detection;
+
+abigen!(
+    ThreatDisputes,
+    r#"[
+        event ThreatDisputeResolved(bytes32 indexed threatHash, address indexed disputer, bool falsePositive)
+    ]"#
+);
+
+/// Feedback settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// DAGShield contract, which emits dispute resolutions; zero disables dispute syncing
+    pub disputes: Address,
+    pub start_block: u64,
+    pub confirmations: u64,
+    pub log_chunk_blocks: u64,
+    pub poll_interval_secs: u64,
+    /// Detections a family needs before its weight is recalibrated
+    pub min_detections: u64,
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./data/feedback".to_string(),
+            disputes: Address::zero(),
+            start_block: 0,
+            confirmations: 12,
+            log_chunk_blocks: 5_000,
+            poll_interval_secs: 300,
+            min_detections: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    FalsePositive,
+    /// A real threat, like a detection whose dispute was rejected
+    Confirmed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelSource {
+    Manual,
+    Dispute,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Label {
+    pub verdict: Verdict,
+    pub source: LabelSource,
+    pub note: Option<String>,
+    pub labeled_at: i64,
+}
+
+/// Oracle report of a detection, found by its evidence hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OracleReport {
+    pub report_id: H256,
+    pub reported_at: i64,
+    /// Whether consensus verified it, once its voting window closed
+    pub verified: Option<bool>,
+}
+
+/// Detection the node reported, with its latest label
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectionRecord {
+    /// Id of the report on-chain
+    pub threat_hash: H256,
+    /// DAG transaction the detection is about
+    #[serde(default)]
+    pub transaction_id: String,
+    /// Chain the transaction was on; unknown for records from before it was kept
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    /// Public input hash of the ZK threat proof the report was submitted with, if any
+    #[serde(default)]
+    pub threat_proof: Option<H256>,
+    pub detection: Detection,
+    /// Detector outputs the scoring model combined into `detection`
+    #[serde(default)]
+    pub contributions: Vec<Detection>,
+    pub detected_at: i64,
+    pub label: Option<Label>,
+    #[serde(default)]
+    pub report: Option<OracleReport>,
+}
+
+impl DetectionRecord {
+    /// Whether the detection was a real threat, if that is known yet. Labels win over the oracle
+    pub fn outcome(&self) -> Option<bool> {
+        match (&self.label, &self.report) {
+            (Some(label), _) => Some(label.verdict == Verdict::Confirmed),
+            (None, Some(report)) => report.verified,
+            (None, None) => None,
+        }
+    }
+}
+
+/// Precision of one detector family in one period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrecisionPoint {
+    pub detector: String,
+    pub period_start: i64,
+    pub detections: u64,
+    pub false_positives: u64,
+    pub precision: f64,
+}
+
+/// Reported detections and their labels
+pub struct FeedbackStore {
+    config: FeedbackConfig,
+    tree: sled::Tree,
+}
+
+impl FeedbackStore {
+    pub fn open(config: FeedbackConfig) -> Result<Self> {
+        let db = sled::open(&config.path).with_context(|| format!("Failed to open feedback store at {}", config.path))?;
+        let tree = db.open_tree("detection_feedback")?;
+        Ok(Self { config, tree })
+    }
+
+    pub fn config(&self) -> &FeedbackConfig {
+        &self.config
+    }
+
+    fn key(threat_hash: &H256) -> Vec<u8> {
+        format!("det:{:?}", threat_hash).into_bytes()
+    }
+
+    /// Keep `detection` of `transaction_id`, scored from `contributions`, for labelling once it
+    /// is reported on-chain as `threat_hash`
+    pub fn record(
+        &self,
+        threat_hash: H256,
+        transaction_id: &str,
+        chain_id: u64,
+        detection: &Detection,
+        contributions: &[Detection],
+        threat_proof: Option<H256>,
+    ) -> Result<()> {
+        if !self.tree.contains_key(Self::key(&threat_hash))? {
+            let record = DetectionRecord {
+                threat_hash,
+                transaction_id: transaction_id.to_string(),
+                chain_id: Some(chain_id),
+                threat_proof,
+                detection: detection.clone(),
+                contributions: contributions.to_vec(),
+                detected_at: chrono::Utc::now().timestamp(),
+                label: None,
+                report: None,
+            };
+            self.put(&record)?;
+        }
+        Ok(())
+    }
+
+    fn put(&self, record: &DetectionRecord) -> Result<()> {
+        self.tree.insert(Self::key(&record.threat_hash), serde_json::to_vec(record)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, threat_hash: &H256) -> Result<Option<DetectionRecord>> {
+        self.tree
+            .get(Self::key(threat_hash))?
+            .map(|value| serde_json::from_slice(&value).context("Corrupt detection record"))
+            .transpose()
+    }
+
+    /// Label a recorded detection, replacing any earlier label
+    pub fn label(&self, threat_hash: &H256, verdict: Verdict, source: LabelSource, note: Option<String>) -> Result<DetectionRecord> {
+        let mut record = self.get(threat_hash)?.with_context(|| format!("No recorded detection {:?}", threat_hash))?;
+        record.label = Some(Label {
+            verdict,
+            source,
+            note,
+            labeled_at: chrono::Utc::now().timestamp(),
+        });
+        self.put(&record)?;
+        self.tree.flush()?;
+        info!("🏷️ Labelled detection {:?} ({}) as {:?}", threat_hash, record.detection.detector, verdict);
+        Ok(record)
+    }
+
+    /// Attach or update the oracle report of a recorded detection
+    pub fn set_report(&self, threat_hash: &H256, report: OracleReport) -> Result<()> {
+        let mut record = self.get(threat_hash)?.with_context(|| format!("No recorded detection {:?}", threat_hash))?;
+        record.report = Some(report);
+        self.put(&record)
+    }
+
+    pub fn mark_false_positive(&self, threat_hash: &H256, note: Option<String>) -> Result<DetectionRecord> {
+        self.label(threat_hash, Verdict::FalsePositive, LabelSource::Manual, note)
+    }
+
+    pub fn records(&self) -> Result<Vec<DetectionRecord>> {
+        self.tree
+            .scan_prefix(b"det:")
+            .values()
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect::<Result<_>>()
+            .context("Corrupt feedback store")
+    }
+
+    /// Detections and detections labelled false positives per family and period of `period_secs`
+    fn tally(&self, period_secs: i64) -> Result<BTreeMap<(String, i64), (u64, u64)>> {
+        let mut tally = BTreeMap::new();
+        for record in self.records()? {
+            let false_positive = record.label.as_ref().is_some_and(|label| label.verdict == Verdict::FalsePositive);
+            let period_start = record.detected_at - record.detected_at.rem_euclid(period_secs.max(1));
+            for family in detector_families(&record.detection.detector) {
+                let counts: &mut (u64, u64) = tally.entry((family.to_string(), period_start)).or_default();
+                counts.0 += 1;
+                counts.1 += false_positive as u64;
+            }
+        }
+        Ok(tally)
+    }
+
+    /// Each family's precision per period of `period_secs`, oldest first
+    pub fn precision_report(&self, period_secs: i64) -> Result<Vec<PrecisionPoint>> {
+        Ok(self
+            .tally(period_secs)?
+            .into_iter()
+            .map(|((detector, period_start), (detections, false_positives))| PrecisionPoint {
+                detector,
+                period_start,
+                detections,
+                false_positives,
+                precision: (detections - false_positives) as f64 / detections as f64,
+            })
+            .collect())
+    }
+
+    /// `base` with the weight of every family with enough detections scaled by its precision,
+    /// smoothed so a single dispute doesn't silence a family
+    pub fn recalibrate(&self, base: &ScoringConfig) -> Result<ScoringConfig> {
+        let mut totals: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        for ((family, _), (detections, false_positives)) in self.tally(i64::MAX)? {
+            let total = totals.entry(family).or_default();
+            total.0 += detections;
+            total.1 += false_positives;
+        }
+
+        let mut config = base.clone();
+        for (family, (detections, false_positives)) in totals {
+            if detections < self.config.min_detections {
+                continue;
+            }
+            let precision = (detections - false_positives + 1) as f64 / (detections + 2) as f64;
+            let weight = base.detector_weights.get(&family).copied().unwrap_or(1.0) * precision;
+            debug!("Detector {} precision {:.2} over {} detections, weight {:.2}", family, precision, detections, weight);
+            config.detector_weights.insert(family, weight);
+        }
+        Ok(config)
+    }
+
+    /// Last block the `name` event sync covered
+    pub fn cursor(&self, name: &str) -> Result<u64> {
+        Ok(match self.tree.get(format!("cursor:{}", name))? {
+            Some(value) => u64::from_be_bytes(value.as_ref().try_into().context("Corrupt sync cursor")?),
+            None => self.config.start_block.saturating_sub(1),
+        })
+    }
+
+    pub fn set_cursor(&self, name: &str, block: u64) -> Result<()> {
+        self.tree.insert(format!("cursor:{}", name), &block.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Label detections from confirmed dispute resolutions; returns how many were ours
+    pub async fn sync_disputes<M: Middleware + 'static>(&self, provider: Arc<M>) -> Result<usize> {
+        let head = provider.get_block_number().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+        let confirmed = head.saturating_sub(self.config.confirmations);
+        let mut labelled = 0;
+
+        while self.cursor("disputes")? < confirmed {
+            let from = self.cursor("disputes")? + 1;
+            let to = confirmed.min(from + self.config.log_chunk_blocks.max(1) - 1);
+            let filter = Filter::new()
+                .address(self.config.disputes)
+                .topic0(ThreatDisputeResolvedFilter::signature())
+                .from_block(from)
+                .to_block(to);
+            let logs = provider
+                .get_logs(&filter)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .with_context(|| format!("Failed to fetch dispute events in blocks {}..={}", from, to))?;
+
+            for log in logs {
+                let event: ThreatDisputeResolvedFilter = parse_log(log)?;
+                let hash = H256(event.threat_hash);
+                // Disputes against other nodes' reports
+                if self.get(&hash)?.is_none() {
+                    continue;
+                }
+                let verdict = if event.false_positive { Verdict::FalsePositive } else { Verdict::Confirmed };
+                self.label(&hash, verdict, LabelSource::Dispute, Some(format!("Disputed by {:?}", event.disputer)))?;
+                labelled += 1;
+            }
+            self.set_cursor("disputes", to)?;
+        }
+        Ok(labelled)
+    }
+
+    /// Keep syncing disputes and recalibrating `scoring` from `base` every poll interval
+    pub async fn watch<M: Middleware + 'static>(self: Arc<Self>, provider: Arc<M>, scoring: ScoringModel, base: ScoringConfig) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if !self.config.disputes.is_zero() {
+                match self.sync_disputes(provider.clone()).await {
+                    Ok(0) => {}
+                    Ok(labelled) => info!("⚖️ Labelled {} detections from dispute resolutions", labelled),
+                    Err(e) => warn!("Dispute sync failed: {:#}", e),
+                }
+            }
+            match self.recalibrate(&base) {
+                Ok(config) => scoring.set_weights(config.detector_weights),
+                Err(e) => warn!("Detector recalibration failed: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::ThreatCategory;
+
+    #[test]
+    fn test_false_positives_lower_weights() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FeedbackConfig {
+            path: dir.path().join("feedback").to_string_lossy().into_owned(),
+            min_detections: 4,
+            ..FeedbackConfig::default()
+        };
+        let store = FeedbackStore::open(config).unwrap();
+        let hashes: Vec<H256> = (0..4)
+            .map(|i| {
+                let detection = Detection {
+                    detector: if i == 0 { "score:model+rules".to_string() } else { "rules:rule-1".to_string() },
+                    threat_type: ThreatCategory::Drainer,
+                    confidence: 0.9,
+                    tx_hash: None,
+                    target: None,
+                    url: None,
+                    explanation: String::new(),
+                    evidence: None,
+                };
+                // The same finding on different transactions is reported, and kept, separately
+                let hash = H256::repeat_byte(i + 1);
+                store.record(hash, &format!("tx-{}", i), 39, &detection, &[], None).unwrap();
+                hash
+            })
+            .collect();
+        assert_eq!(store.records().unwrap().len(), 4);
+        assert!(store.mark_false_positive(&H256::zero(), None).is_err());
+        store.mark_false_positive(&hashes[1], Some("router upgrade".to_string())).unwrap();
+        store.mark_false_positive(&hashes[2], None).unwrap();
+        store.label(&hashes[2], Verdict::Confirmed, LabelSource::Dispute, None).unwrap();
+        let report = OracleReport { report_id: H256::repeat_byte(9), reported_at: 0, verified: Some(false) };
+        store.set_report(&hashes[3], report).unwrap();
+        let outcomes: Vec<_> = hashes.iter().map(|hash| store.get(hash).unwrap().unwrap().outcome()).collect();
+        assert_eq!(outcomes, [None, Some(false), Some(true), Some(false)]);
+
+        // The oracle's rejection is left to calibration; only labels count here
+        let report = store.precision_report(86_400).unwrap();
+        let rules = report.iter().find(|point| point.detector == "rules").unwrap();
+        assert_eq!((rules.detections, rules.false_positives, rules.precision), (4, 1, 0.75));
+
+        // Rules have enough detections to recalibrate, the model doesn't
+        let weights = store.recalibrate(&ScoringConfig::default()).unwrap().detector_weights;
+        assert!((weights["rules"] - 4.0 / 6.0).abs() < 1e-9);
+        assert!(!weights.contains_key("model"));
+    }
+}
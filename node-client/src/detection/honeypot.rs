@@ -0,0 +1,449 @@
+/*!
+ * Honeypot token detection
+ * A honeypot token can be bought but not sold, or only sold at a confiscatory tax. The simulator
+ * buys a token for ETH through a Uniswap V2 style router and sells everything it got straight back,
+ * as an impersonated account on a forked node (anvil or hardhat pointed at the live chain). The
+ * simulator only connects to `fork_url`, and refuses nodes that don't identify as anvil or
+ * hardhat, whose `anvil_*` and `hardhat_*` methods it calls respectively. Each round trip runs
+ * inside an EVM snapshot that is reverted afterwards, so the fork stays clean. Tokens whose sell
+ * reverts or is taxed above the limit are reported, with the hash of the sell's execution trace as
+ * evidence. As a registered detector it round trips the token bought by router swaps from ETH,
+ * once per token, one simulation at a time
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{decode, encode, ParamType, Token},
+    providers::{Http, Middleware, Provider},
+    types::{Address, Bytes, TransactionReceipt, TransactionRequest, H256, U256},
+    utils::{id, keccak256, parse_ether},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tracing::{debug, info, warn};
+
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+/// Account the round trips are made from
+const TRADER: Address = Address::repeat_byte(0xd5);
+
+/// Router swaps buying a token with ETH; all take `(uint256, address[] path, address, uint256)`
+const BUY_FUNCTIONS: &[&str] = &[
+    "swapExactETHForTokens(uint256,address[],address,uint256)",
+    "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+    "swapETHForExactTokens(uint256,address[],address,uint256)",
+];
+
+/// Honeypot simulation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoneypotConfig {
+    pub enabled: bool,
+    /// Forked node the round trips run on; it must support `evm_snapshot` and impersonation
+    pub fork_url: String,
+    /// Uniswap V2 compatible router
+    pub router: Address,
+    /// Wrapped native token the router pairs with
+    pub weth: Address,
+    pub buy_amount_eth: f64,
+    /// Sells losing more than this fraction to the token are reported
+    pub max_sell_tax: f64,
+    pub gas_limit: u64,
+}
+
+impl Default for HoneypotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fork_url: "http://127.0.0.1:8545".to_string(),
+            router: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D".parse().unwrap(),
+            weth: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap(),
+            buy_amount_eth: 0.1,
+            max_sell_tax: 0.1,
+            gas_limit: 1_000_000,
+        }
+    }
+}
+
+/// Outcome of buying a token and selling it straight back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundTrip {
+    pub token: Address,
+    /// ETH spent on the buy
+    pub spent: U256,
+    pub bought: U256,
+    /// Fraction of the quoted tokens the buy did not deliver
+    pub buy_tax: f64,
+    pub sell_reverted: bool,
+    /// ETH the sell returned, net of gas
+    pub sold_for: U256,
+    /// Fraction of the quoted ETH the sell did not deliver; 1 when it reverted
+    pub sell_tax: f64,
+    pub sell_tx: Option<H256>,
+    /// Keccak of the sell's execution trace
+    pub trace_hash: Option<H256>,
+}
+
+impl RoundTrip {
+    /// Honeypot detection if the sell reverted or was taxed above `max_sell_tax`
+    pub fn detection(&self, max_sell_tax: f64) -> Option<Detection> {
+        let explanation = if self.sell_reverted {
+            "Buying succeeds but selling reverts".to_string()
+        } else if self.sell_tax > max_sell_tax {
+            format!("Selling loses {:.1}% to the token (buying {:.1}%)", self.sell_tax * 100.0, self.buy_tax * 100.0)
+        } else {
+            return None;
+        };
+        Some(Detection {
+            detector: "honeypot".to_string(),
+            threat_type: ThreatCategory::Honeypot,
+            confidence: if self.sell_reverted { 0.95 } else { (0.7 + 0.3 * self.sell_tax).min(0.95) },
+            tx_hash: None,
+            target: Some(self.token),
+            url: None,
+            explanation,
+            evidence: self.trace_hash,
+        })
+    }
+}
+
+/// Fraction of `quoted` that `received` falls short of
+fn shortfall(received: U256, quoted: U256) -> f64 {
+    if quoted.is_zero() {
+        return 0.0;
+    }
+    let as_f64 = |amount: U256| amount.to_string().parse::<f64>().unwrap_or_default();
+    (1.0 - as_f64(received) / as_f64(quoted)).clamp(0.0, 1.0)
+}
+
+/// Forked node flavour, which decides the names of its test methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkNode {
+    Anvil,
+    Hardhat,
+}
+
+impl ForkNode {
+    /// Node identified by its `web3_clientVersion`
+    pub fn from_client_version(version: &str) -> Option<Self> {
+        let version = version.to_lowercase();
+        if version.starts_with("anvil") {
+            Some(Self::Anvil)
+        } else if version.starts_with("hardhatnetwork") {
+            Some(Self::Hardhat)
+        } else {
+            None
+        }
+    }
+
+    fn method(self, name: &str) -> String {
+        match self {
+            Self::Anvil => format!("anvil_{}", name),
+            Self::Hardhat => format!("hardhat_{}", name),
+        }
+    }
+}
+
+/// Runs buy-then-sell round trips on a forked node
+#[derive(Clone)]
+pub struct HoneypotSimulator {
+    config: HoneypotConfig,
+    provider: Provider<Http>,
+    node: ForkNode,
+}
+
+impl HoneypotSimulator {
+    /// Connect to `fork_url`, which must be an anvil or hardhat node
+    pub async fn connect(config: HoneypotConfig) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(config.fork_url.as_str())
+            .with_context(|| format!("Invalid fork URL {}", config.fork_url))?;
+        let version = provider
+            .client_version()
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Fork node {} is unreachable", config.fork_url))?;
+        let node = ForkNode::from_client_version(&version).with_context(|| {
+            format!("{} is {:?}, not an anvil or hardhat fork; honeypot round trips only run on forks", config.fork_url, version)
+        })?;
+        info!("🍯 Simulating honeypot round trips on {} ({:?})", config.fork_url, node);
+        Ok(Self { config, provider, node })
+    }
+
+    /// Round trip `token`, leaving the fork as it was
+    pub async fn simulate(&self, token: Address) -> Result<RoundTrip> {
+        let snapshot: U256 = self.rpc("evm_snapshot", ()).await?;
+        let result = self.round_trip(token).await;
+        let reverted: bool = self.rpc("evm_revert", [snapshot]).await?;
+        if !reverted {
+            warn!("⚠️ Fork did not revert to snapshot {} after simulating {:?}", snapshot, token);
+        }
+        result
+    }
+
+    /// Honeypot detection for `token`, if its round trip shows one
+    pub async fn detect(&self, token: Address) -> Result<Option<Detection>> {
+        let round_trip = self.simulate(token).await?;
+        debug!(
+            "🍯 {:?}: buy tax {:.3}, sell tax {:.3}, sell reverted {}",
+            token, round_trip.buy_tax, round_trip.sell_tax, round_trip.sell_reverted
+        );
+        Ok(round_trip.detection(self.config.max_sell_tax))
+    }
+
+    async fn round_trip(&self, token: Address) -> Result<RoundTrip> {
+        let spent = parse_ether(self.config.buy_amount_eth)?;
+        self.rpc::<_, ()>(&self.node.method("setBalance"), (TRADER, spent * 10)).await?;
+        self.rpc::<_, ()>(&self.node.method("impersonateAccount"), [TRADER]).await?;
+
+        // Buy
+        let buy_path = vec![self.config.weth, token];
+        let quoted = self.amount_out(spent, &buy_path).await.context("Token has no liquidity to buy from")?;
+        let buy = self
+            .call(
+                self.config.router,
+                spent,
+                "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+                &[Token::Uint(U256::zero()), path_token(&buy_path), Token::Address(TRADER), Token::Uint(U256::MAX)],
+            )
+            .await?;
+        if buy.status != Some(1.into()) {
+            return Err(anyhow::anyhow!("Buying {:?} reverted, so it can't be round-tripped", token));
+        }
+        let bought = self.balance_of(token).await?;
+        if bought.is_zero() {
+            return Err(anyhow::anyhow!("Buying {:?} delivered no tokens", token));
+        }
+
+        // Sell everything back
+        let approve = self
+            .call(token, U256::zero(), "approve(address,uint256)", &[Token::Address(self.config.router), Token::Uint(bought)])
+            .await?;
+        let sell_path = vec![token, self.config.weth];
+        let quoted_back = self.amount_out(bought, &sell_path).await.unwrap_or_default();
+        let balance_before = self.provider.get_balance(TRADER, None).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+        let sell = self
+            .call(
+                self.config.router,
+                U256::zero(),
+                "swapExactTokensForETHSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+                &[Token::Uint(bought), Token::Uint(U256::zero()), path_token(&sell_path), Token::Address(TRADER), Token::Uint(U256::MAX)],
+            )
+            .await?;
+        let balance_after = self.provider.get_balance(TRADER, None).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let sell_reverted = approve.status != Some(1.into()) || sell.status != Some(1.into());
+        let gas = sell.gas_used.unwrap_or_default() * sell.effective_gas_price.unwrap_or_default();
+        let sold_for = (balance_after + gas).saturating_sub(balance_before);
+        let trace_hash = match self.provider.debug_trace_transaction(sell.transaction_hash, Default::default()).await {
+            Ok(trace) => Some(H256::from(keccak256(serde_json::to_vec(&trace)?))),
+            Err(e) => {
+                warn!("⚠️ Couldn't trace the sell of {:?}: {}", token, e);
+                None
+            }
+        };
+
+        Ok(RoundTrip {
+            token,
+            spent,
+            bought,
+            buy_tax: shortfall(bought, quoted),
+            sell_reverted,
+            sold_for,
+            sell_tax: if sell_reverted { 1.0 } else { shortfall(sold_for, quoted_back) },
+            sell_tx: Some(sell.transaction_hash),
+            trace_hash,
+        })
+    }
+
+    /// Mine a call from the trader; reverts come back as a failed receipt rather than an error
+    async fn call(&self, to: Address, value: U256, signature: &str, args: &[Token]) -> Result<TransactionReceipt> {
+        let request = TransactionRequest::new()
+            .from(TRADER)
+            .to(to)
+            .value(value)
+            .gas(self.config.gas_limit)
+            .data(Bytes::from([id(signature).to_vec(), encode(args)].concat()));
+        self.provider
+            .send_transaction(request, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to send {}", signature))?
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .with_context(|| format!("{} was dropped from the fork", signature))
+    }
+
+    /// Router quote for swapping `amount` along `path`
+    async fn amount_out(&self, amount: U256, path: &[Address]) -> Result<U256> {
+        let output = self
+            .view(self.config.router, "getAmountsOut(uint256,address[])", &[Token::Uint(amount), path_token(path)])
+            .await?;
+        match decode(&[ParamType::Array(Box::new(ParamType::Uint(256)))], &output)?.pop() {
+            Some(Token::Array(amounts)) => amounts.last().cloned().and_then(Token::into_uint).context("Empty router quote"),
+            _ => Err(anyhow::anyhow!("Malformed router quote")),
+        }
+    }
+
+    async fn balance_of(&self, token: Address) -> Result<U256> {
+        let output = self.view(token, "balanceOf(address)", &[Token::Address(TRADER)]).await?;
+        Ok(U256::from_big_endian(output.get(..32).context("Malformed token balance")?))
+    }
+
+    async fn view(&self, to: Address, signature: &str, args: &[Token]) -> Result<Bytes> {
+        let request = TransactionRequest::new().to(to).data(Bytes::from([id(signature).to_vec(), encode(args)].concat()));
+        self.provider
+            .call(&request.into(), None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("{} failed on {:?}", signature, to))
+    }
+
+    async fn rpc<T, R>(&self, method: &str, params: T) -> Result<R>
+    where
+        T: std::fmt::Debug + Serialize + Send + Sync,
+        R: std::fmt::Debug + Serialize + serde::de::DeserializeOwned + Send,
+    {
+        self.provider
+            .request(method, params)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("{} failed; is {} a forked node?", method, self.config.fork_url))
+    }
+}
+
+fn path_token(path: &[Address]) -> Token {
+    Token::Array(path.iter().copied().map(Token::Address).collect())
+}
+
+/// Token a router swap from ETH buys, the last hop of its path
+pub fn bought_token(tx: &TxContext, router: Address) -> Option<Address> {
+    let selector = tx.selector()?;
+    if tx.to != Some(router) || !BUY_FUNCTIONS.iter().any(|signature| id(signature) == selector) {
+        return None;
+    }
+    let params = [ParamType::Uint(256), ParamType::Array(Box::new(ParamType::Address)), ParamType::Address, ParamType::Uint(256)];
+    match decode(&params, &tx.data[4..]).ok()?.swap_remove(1) {
+        Token::Array(path) => path.last().cloned()?.into_address(),
+        _ => None,
+    }
+}
+
+/// Round trips tokens bought through the router, as a registered detector
+pub struct HoneypotDetector {
+    simulator: HoneypotSimulator,
+    /// Round trips share the fork, so they run one at a time
+    fork: Arc<tokio::sync::Mutex<()>>,
+    /// Round trips by token; a token is simulated once
+    round_trips: Arc<Mutex<HashMap<Address, RoundTrip>>>,
+}
+
+impl HoneypotDetector {
+    pub fn new(simulator: HoneypotSimulator) -> Self {
+        Self {
+            simulator,
+            fork: Arc::new(tokio::sync::Mutex::new(())),
+            round_trips: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl ThreatDetector for HoneypotDetector {
+    type Features = RoundTrip;
+
+    fn name(&self) -> &str {
+        "honeypot"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        Some(ThreatCategory::Honeypot)
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<RoundTrip>> {
+        let Some(token) = bought_token(tx, self.simulator.config.router) else {
+            return Ok(None);
+        };
+        if let Some(round_trip) = self.round_trips.lock().unwrap().get(&token) {
+            return Ok(Some(round_trip.clone()));
+        }
+
+        // Spawned so a registry timeout can't drop the round trip before its snapshot is reverted
+        let (simulator, fork, round_trips) = (self.simulator.clone(), Arc::clone(&self.fork), Arc::clone(&self.round_trips));
+        let simulation = tokio::spawn(async move {
+            let _fork = fork.lock().await;
+            if let Some(round_trip) = round_trips.lock().unwrap().get(&token) {
+                return Ok(round_trip.clone());
+            }
+            let round_trip = simulator.simulate(token).await?;
+            round_trips.lock().unwrap().insert(token, round_trip.clone());
+            Ok::<_, anyhow::Error>(round_trip)
+        });
+        Ok(Some(simulation.await??))
+    }
+
+    fn score(&self, tx: &TxContext, round_trip: &RoundTrip) -> Vec<Detection> {
+        round_trip
+            .detection(self.simulator.config.max_sell_tax)
+            .map(|detection| Detection { tx_hash: tx.hash, ..detection })
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(sell_reverted: bool, sold_for: u64) -> RoundTrip {
+        RoundTrip {
+            token: Address::repeat_byte(0x70),
+            spent: 100.into(),
+            bought: 1_000.into(),
+            buy_tax: shortfall(1_000.into(), 1_000.into()),
+            sell_reverted,
+            sold_for: sold_for.into(),
+            sell_tax: if sell_reverted { 1.0 } else { shortfall(sold_for.into(), 98.into()) },
+            sell_tx: None,
+            trace_hash: Some(H256::repeat_byte(0x7e)),
+        }
+    }
+
+    #[test]
+    fn test_reverting_and_taxed_sells_reported() {
+        // Pool fees are quoted, so a fair sell loses nothing to the token
+        assert!(round_trip(false, 98).detection(0.1).is_none());
+
+        let reverted = round_trip(true, 0).detection(0.1).unwrap();
+        assert_eq!((reverted.threat_type, reverted.confidence), (ThreatCategory::Honeypot, 0.95));
+        assert_eq!(reverted.evidence, Some(H256::repeat_byte(0x7e)));
+
+        // Half the proceeds kept by the token
+        let taxed = round_trip(false, 49).detection(0.1).unwrap();
+        assert!((taxed.confidence - 0.85).abs() < 1e-9);
+        assert!(round_trip(false, 49).detection(0.6).is_none());
+        assert_eq!(shortfall(5.into(), U256::zero()), 0.0);
+
+        // Only anvil and hardhat nodes are simulated on
+        assert_eq!(ForkNode::from_client_version("anvil/v0.2.0"), Some(ForkNode::Anvil));
+        assert_eq!(ForkNode::from_client_version("HardhatNetwork/2.22.0/@ethereumjs/vm/7.0.0"), Some(ForkNode::Hardhat));
+        assert_eq!(ForkNode::from_client_version("Geth/v1.14.0-stable/linux-amd64/go1.22"), None);
+        assert_eq!(ForkNode::Hardhat.method("setBalance"), "hardhat_setBalance");
+
+        // The token a router buy is for is the last hop of its path
+        let config = HoneypotConfig::default();
+        let token = Address::repeat_byte(0x70);
+        let args = [Token::Uint(U256::zero()), path_token(&[config.weth, token]), Token::Address(TRADER), Token::Uint(U256::MAX)];
+        let buy = TxContext {
+            hash: None,
+            chain_id: 1,
+            from: TRADER,
+            to: Some(config.router),
+            value: U256::one(),
+            data: Bytes::from([id(BUY_FUNCTIONS[1]).to_vec(), encode(&args)].concat()),
+        };
+        assert_eq!(bought_token(&buy, config.router), Some(token));
+        assert_eq!(bought_token(&TxContext { to: Some(token), ..buy }, config.router), None);
+    }
+}
@@ -0,0 +1,571 @@
+/*!
+ * Threat intelligence feed ingestion
+ * Pulls indicators from STIX 2.1 bundles, TAXII 2.1 collections (paged, resuming from the last
+ * added date) and plain CSV or text blocklists. Indicators are normalized to a contract or
+ * account address, a URL or a domain, categorized from their labels, and dropped when
+ * revoked, benign or expired. Known indicators and TAXII cursors are persisted in sled, so a
+ * restart neither resubmits nor refetches what the node already has. New indicators at or above
+ * the submission threshold are submitted through the DAG like any detection
+ */
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ethers::types::Address;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+use crate::u2u_integration::U2UClient;
+
+const TAXII_MEDIA_TYPE: &str = "application/taxii+json;version=2.1";
+const CURSOR_PREFIX: &[u8] = b"cursor:";
+const INDICATOR_PREFIX: &[u8] = b"ind:";
+
+fn default_path() -> String {
+    "./data/intel".to_string()
+}
+
+/// Threat intelligence settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelConfig {
+    pub enabled: bool,
+    /// Where known indicators and TAXII cursors are kept
+    #[serde(default = "default_path")]
+    pub path: String,
+    pub feeds: Vec<FeedConfig>,
+    /// New indicators at or above this confidence are submitted
+    pub submit_threshold: f64,
+    pub poll_interval_secs: u64,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for IntelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_path(),
+            feeds: Vec::new(),
+            submit_threshold: 0.9,
+            poll_interval_secs: 3600,
+            request_timeout_secs: 30,
+        }
+    }
+}
+
+/// One feed to pull
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedConfig {
+    pub name: String,
+    /// Bundle or list URL, or for TAXII the collection URL (`.../collections/<id>/`)
+    pub url: String,
+    pub format: FeedFormat,
+    /// Confidence of indicators that don't carry their own
+    pub confidence: f64,
+    /// Category of indicators whose labels don't name one
+    pub threat_type: ThreatCategory,
+    /// Basic auth for TAXII servers, as `user:password`
+    #[serde(default)]
+    pub credentials: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// STIX 2.1 bundle
+    Stix,
+    /// TAXII 2.1 collection of STIX objects
+    Taxii,
+    /// CSV with a header row naming an `indicator` (or `address`, `url`, `domain`) column and
+    /// optionally `confidence` and `threat_type`
+    Csv,
+    /// One indicator per line; `#` starts a comment
+    Text,
+}
+
+/// What an indicator points at
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Observable {
+    Address(Address),
+    Url(String),
+    Domain(String),
+}
+
+impl Observable {
+    /// Normalize a raw feed value; IPs and anything else unrecognized are skipped
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().trim_matches(|c| c == '"' || c == '\'');
+        if let Some(hex) = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
+            return (hex.len() == 40).then(|| raw.parse().ok().map(Self::Address)).flatten();
+        }
+        if raw.contains("://") {
+            return url::Url::parse(raw).ok().map(|url| Self::Url(url.to_string()));
+        }
+        let domain = raw.trim_end_matches('.').to_ascii_lowercase();
+        let is_domain = domain.contains('.')
+            && domain.parse::<std::net::IpAddr>().is_err()
+            && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+        is_domain.then_some(Self::Domain(domain))
+    }
+
+    /// Key indicators are deduplicated on
+    pub fn key(&self) -> String {
+        match self {
+            Self::Address(address) => format!("{:?}", address),
+            Self::Url(url) => url.clone(),
+            Self::Domain(domain) => domain.clone(),
+        }
+    }
+}
+
+/// Normalized indicator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Indicator {
+    pub observable: Observable,
+    pub threat_type: ThreatCategory,
+    pub confidence: f64,
+    /// Feed the indicator came from
+    pub source: String,
+    pub description: String,
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl Indicator {
+    pub fn detection(&self) -> Detection {
+        let (target, url) = match &self.observable {
+            Observable::Address(address) => (Some(*address), None),
+            Observable::Url(url) | Observable::Domain(url) => (None, Some(url.clone())),
+        };
+        Detection {
+            detector: format!("intel:{}", self.source),
+            threat_type: self.threat_type,
+            confidence: self.confidence,
+            tx_hash: None,
+            target,
+            url,
+            explanation: match self.description.as_str() {
+                "" => format!("Listed by threat feed {}", self.source),
+                description => format!("{}: {}", self.source, description),
+            },
+            evidence: None,
+        }
+    }
+}
+
+/// Category named by `labels`, else the feed's
+fn threat_type(feed: &FeedConfig, labels: &[&str]) -> ThreatCategory {
+    labels.iter().find_map(|label| ThreatCategory::from_label(label)).unwrap_or(feed.threat_type)
+}
+
+/// Blocklist confidence as a fraction, from either 0-1 or 0-100 scales
+fn fraction(confidence: f64) -> f64 {
+    if confidence > 1.0 { confidence / 100.0 } else { confidence }.clamp(0.0, 1.0)
+}
+
+/// STIX confidence, always 0-100, as a fraction
+fn stix_fraction(confidence: f64) -> f64 {
+    (confidence / 100.0).clamp(0.0, 1.0)
+}
+
+/// Indicators in STIX objects; other object types are ignored
+pub fn parse_stix(feed: &FeedConfig, objects: &[Value], now: DateTime<Utc>) -> Vec<Indicator> {
+    static COMPARISON: OnceLock<Regex> = OnceLock::new();
+    let comparison = COMPARISON.get_or_init(|| Regex::new(r"([\w-]+):[\w.'-]+\s*=\s*'((?:[^'\\]|\\.)*)'").unwrap());
+
+    let strings = |object: &Value, field: &str| -> Vec<String> {
+        object[field].as_array().into_iter().flatten().filter_map(|value| value.as_str().map(String::from)).collect()
+    };
+
+    let mut indicators = Vec::new();
+    for object in objects {
+        if object["type"] != "indicator" || object["revoked"] == true {
+            continue;
+        }
+        let indicator_types = strings(object, "indicator_types");
+        if indicator_types.iter().any(|indicator_type| indicator_type == "benign") {
+            continue;
+        }
+        let valid_until = object["valid_until"].as_str().and_then(|until| DateTime::parse_from_rfc3339(until).ok()).map(|until| until.with_timezone(&Utc));
+        if valid_until.is_some_and(|until| until <= now) {
+            continue;
+        }
+
+        let name = object["name"].as_str().unwrap_or_default();
+        let mut labels: Vec<&str> = indicator_types.iter().map(String::as_str).collect();
+        let object_labels = strings(object, "labels");
+        labels.extend(object_labels.iter().map(String::as_str));
+        labels.push(name);
+
+        let pattern = object["pattern"].as_str().unwrap_or_default();
+        for capture in comparison.captures_iter(pattern) {
+            let Some(observable) = Observable::parse(&capture[2].replace("\\'", "'")) else {
+                debug!("Skipping unsupported {} observable in {}", &capture[1], feed.name);
+                continue;
+            };
+            indicators.push(Indicator {
+                observable,
+                threat_type: threat_type(feed, &labels),
+                confidence: object["confidence"].as_f64().map_or(feed.confidence, stix_fraction),
+                source: feed.name.clone(),
+                description: object["description"].as_str().unwrap_or(name).to_string(),
+                valid_until,
+            });
+        }
+    }
+    indicators
+}
+
+/// Indicators in a CSV blocklist
+pub fn parse_csv(feed: &FeedConfig, body: &str) -> Result<Vec<Indicator>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(body.as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_ascii_lowercase).collect();
+    let column = |names: &[&str]| headers.iter().position(|header| names.contains(&header.as_str()));
+    let value_column = column(&["indicator", "address", "url", "domain", "value"]).unwrap_or(0);
+    let confidence_column = column(&["confidence", "score"]);
+    let type_column = column(&["threat_type", "type", "category"]);
+
+    let mut indicators = Vec::new();
+    for record in reader.records() {
+        let record = record.with_context(|| format!("Malformed CSV row in {}", feed.name))?;
+        let Some(observable) = record.get(value_column).and_then(Observable::parse) else {
+            continue;
+        };
+        let label = type_column.and_then(|column| record.get(column)).unwrap_or_default();
+        indicators.push(Indicator {
+            observable,
+            threat_type: threat_type(feed, &[label]),
+            confidence: confidence_column
+                .and_then(|column| record.get(column)?.parse().ok())
+                .map_or(feed.confidence, fraction),
+            source: feed.name.clone(),
+            description: String::new(),
+            valid_until: None,
+        });
+    }
+    Ok(indicators)
+}
+
+/// Indicators in a plain text blocklist
+pub fn parse_text(feed: &FeedConfig, body: &str) -> Vec<Indicator> {
+    body.lines()
+        .filter_map(|line| Observable::parse(line.split('#').next().unwrap_or_default()))
+        .map(|observable| Indicator {
+            observable,
+            threat_type: feed.threat_type,
+            confidence: feed.confidence,
+            source: feed.name.clone(),
+            description: String::new(),
+            valid_until: None,
+        })
+        .collect()
+}
+
+/// Pulls feeds and keeps the indicators they list
+pub struct IntelIngester {
+    config: IntelConfig,
+    http: reqwest::Client,
+    tree: sled::Tree,
+    /// TAXII `added_after` cursor per feed
+    cursors: Mutex<HashMap<String, String>>,
+    /// Current indicators by observable key
+    indicators: Mutex<HashMap<String, Indicator>>,
+}
+
+impl IntelIngester {
+    /// Open (or create) the indicator store, loading known indicators and cursors
+    pub fn open(config: IntelConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .context("Failed to build threat feed HTTP client")?;
+        let db = sled::open(&config.path).with_context(|| format!("Failed to open threat intel store at {}", config.path))?;
+        let tree = db.open_tree("threat_intel")?;
+        let cursors = tree
+            .scan_prefix(CURSOR_PREFIX)
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((String::from_utf8(key[CURSOR_PREFIX.len()..].to_vec())?, String::from_utf8(value.to_vec())?))
+            })
+            .collect::<Result<HashMap<String, String>>>()
+            .context("Corrupt TAXII cursor")?;
+        let indicators = tree
+            .scan_prefix(INDICATOR_PREFIX)
+            .values()
+            .map(|value| {
+                let indicator: Indicator = serde_json::from_slice(&value?)?;
+                Ok((indicator.observable.key(), indicator))
+            })
+            .collect::<Result<HashMap<String, Indicator>>>()
+            .context("Corrupt threat indicator")?;
+        info!("📡 Threat intel store holds {} indicators", indicators.len());
+        Ok(Self {
+            config,
+            http,
+            tree,
+            cursors: Mutex::new(cursors),
+            indicators: Mutex::new(indicators),
+        })
+    }
+
+    /// Indicator for an address, URL or domain, if a feed lists it
+    pub fn get(&self, observable: &Observable) -> Option<Indicator> {
+        self.indicators.lock().unwrap().get(&observable.key()).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indicators.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Indicators `feed` lists now
+    pub async fn pull(&self, feed: &FeedConfig) -> Result<Vec<Indicator>> {
+        match feed.format {
+            FeedFormat::Taxii => self.pull_taxii(feed).await,
+            FeedFormat::Stix => {
+                let bundle: Value = self.get_body(feed, &feed.url).await?.json().await.context("Malformed STIX bundle")?;
+                let objects = bundle["objects"].as_array().cloned().unwrap_or_default();
+                Ok(parse_stix(feed, &objects, Utc::now()))
+            }
+            FeedFormat::Csv => parse_csv(feed, &self.get_body(feed, &feed.url).await?.text().await?),
+            FeedFormat::Text => Ok(parse_text(feed, &self.get_body(feed, &feed.url).await?.text().await?)),
+        }
+    }
+
+    /// Objects added to the collection since the last pull, following pages
+    async fn pull_taxii(&self, feed: &FeedConfig) -> Result<Vec<Indicator>> {
+        let objects_url = format!("{}/objects/", feed.url.trim_end_matches('/'));
+        let mut added_after = self.cursors.lock().unwrap().get(&feed.name).cloned();
+        let mut next: Option<String> = None;
+        let mut indicators = Vec::new();
+
+        loop {
+            let mut url = url::Url::parse(&objects_url).with_context(|| format!("Invalid TAXII collection URL {}", feed.url))?;
+            {
+                let mut query = url.query_pairs_mut();
+                if let Some(added_after) = &added_after {
+                    query.append_pair("added_after", added_after);
+                }
+                if let Some(next) = &next {
+                    query.append_pair("next", next);
+                }
+            }
+            let response = self.get_body(feed, url.as_str()).await?;
+            let last_added = response
+                .headers()
+                .get("X-TAXII-Date-Added-Last")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+            let envelope: Value = response.json().await.context("Malformed TAXII envelope")?;
+            indicators.extend(parse_stix(feed, envelope["objects"].as_array().map(Vec::as_slice).unwrap_or_default(), Utc::now()));
+
+            if let Some(last_added) = last_added {
+                added_after = Some(last_added);
+            }
+            next = envelope["next"].as_str().map(String::from);
+            if envelope["more"] != true || next.is_none() {
+                break;
+            }
+        }
+
+        if let Some(added_after) = added_after {
+            self.tree.insert([CURSOR_PREFIX, feed.name.as_bytes()].concat(), added_after.as_bytes())?;
+            self.cursors.lock().unwrap().insert(feed.name.clone(), added_after);
+        }
+        Ok(indicators)
+    }
+
+    async fn get_body(&self, feed: &FeedConfig, url: &str) -> Result<reqwest::Response> {
+        let mut request = self.http.get(url);
+        if feed.format == FeedFormat::Taxii {
+            request = request.header(reqwest::header::ACCEPT, TAXII_MEDIA_TYPE);
+        }
+        if let Some((user, password)) = feed.credentials.as_deref().and_then(|credentials| credentials.split_once(':')) {
+            request = request.basic_auth(user, Some(password));
+        }
+        request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to fetch threat feed {}", feed.name))
+    }
+
+    /// Pull every feed, keeping indicators; returns those not seen before or now more confident
+    pub async fn poll(&self) -> Vec<Indicator> {
+        let mut fresh = Vec::new();
+        for feed in &self.config.feeds {
+            let pulled = match self.pull(feed).await {
+                Ok(pulled) => pulled,
+                Err(e) => {
+                    warn!("⚠️ Threat feed {} failed: {:#}", feed.name, e);
+                    continue;
+                }
+            };
+            debug!("📡 {} listed {} indicators", feed.name, pulled.len());
+            match self.record(pulled) {
+                Ok(recorded) => fresh.extend(recorded),
+                Err(e) => warn!("⚠️ Failed to store indicators from {}: {:#}", feed.name, e),
+            }
+        }
+        if let Err(e) = self.expire(Utc::now()) {
+            warn!("⚠️ Failed to drop expired indicators: {:#}", e);
+        }
+        fresh
+    }
+
+    /// Keep `pulled`; returns those not known before or now more confident
+    pub fn record(&self, pulled: Vec<Indicator>) -> Result<Vec<Indicator>> {
+        let mut indicators = self.indicators.lock().unwrap();
+        let mut fresh = Vec::new();
+        let mut batch = sled::Batch::default();
+        for indicator in pulled {
+            let key = indicator.observable.key();
+            if indicators.get(&key).is_some_and(|known| known.confidence >= indicator.confidence) {
+                continue;
+            }
+            batch.insert([INDICATOR_PREFIX, key.as_bytes()].concat(), serde_json::to_vec(&indicator)?);
+            indicators.insert(key, indicator.clone());
+            fresh.push(indicator);
+        }
+        self.tree.apply_batch(batch)?;
+        Ok(fresh)
+    }
+
+    /// Drop indicators no longer valid at `now`
+    fn expire(&self, now: DateTime<Utc>) -> Result<()> {
+        let mut indicators = self.indicators.lock().unwrap();
+        let mut batch = sled::Batch::default();
+        indicators.retain(|key, indicator| {
+            let valid = indicator.valid_until.is_none_or(|until| until > now);
+            if !valid {
+                batch.remove([INDICATOR_PREFIX, key.as_bytes()].concat());
+            }
+            valid
+        });
+        self.tree.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Poll feeds every interval, submitting confident new indicators when there's a client
+    pub async fn run(self: Arc<Self>, client: Option<Arc<U2UClient>>, node_id: String) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            let fresh = self.poll().await;
+            let confident: Vec<&Indicator> = fresh.iter().filter(|indicator| indicator.confidence >= self.config.submit_threshold).collect();
+            if !fresh.is_empty() {
+                info!("📡 {} new threat indicators, {} confident enough to submit", fresh.len(), confident.len());
+            }
+            let Some(client) = &client else {
+                continue;
+            };
+            for indicator in confident {
+                if let Err(e) = super::submit(client, &indicator.detection(), &node_id).await {
+                    warn!("Failed to submit indicator {}: {:#}", indicator.observable.key(), e);
+                }
+            }
+        }
+    }
+}
+
+impl ThreatDetector for IntelIngester {
+    /// Indicators listing the sender or the called address
+    type Features = Vec<Indicator>;
+
+    fn name(&self) -> &str {
+        "intel"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<Vec<Indicator>>> {
+        let listed: Vec<Indicator> = [Some(tx.from), tx.to]
+            .into_iter()
+            .flatten()
+            .filter_map(|address| self.get(&Observable::Address(address)))
+            .collect();
+        Ok((!listed.is_empty()).then_some(listed))
+    }
+
+    fn score(&self, tx: &TxContext, indicators: &Vec<Indicator>) -> Vec<Detection> {
+        indicators.iter().map(|indicator| Detection { tx_hash: tx.hash, ..indicator.detection() }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn feed(format: FeedFormat) -> FeedConfig {
+        FeedConfig {
+            name: "test-feed".to_string(),
+            url: "https://intel.example/feed".to_string(),
+            format,
+            confidence: 0.6,
+            threat_type: ThreatCategory::Spam,
+            credentials: None,
+        }
+    }
+
+    #[test]
+    fn test_feeds_normalized() {
+        let drainer = "0x00000000000000000000000000000000000000dd";
+        let objects = [
+            json!({"type": "indicator", "pattern": format!("[x-crypto-address:value = '{}']", drainer),
+                   "indicator_types": ["malicious-activity"], "labels": ["wallet-drainer"], "confidence": 90}),
+            json!({"type": "indicator", "name": "Uniswap phishing kit",
+                   "pattern": "[url:value = 'https://uniswap-claim.example/'] OR [domain-name:value = 'Uniswap-Claim.example']"}),
+            json!({"type": "indicator", "pattern": "[domain-name:value = 'gone.example']", "revoked": true}),
+            json!({"type": "indicator", "pattern": "[domain-name:value = 'old.example']", "valid_until": "2020-01-01T00:00:00Z"}),
+            json!({"type": "indicator", "pattern": "[ipv4-addr:value = '10.0.0.1']"}),
+            json!({"type": "malware", "name": "drainer"}),
+        ];
+        let indicators = parse_stix(&feed(FeedFormat::Stix), &objects, Utc::now());
+        assert_eq!(indicators.len(), 3);
+        assert_eq!(indicators[0].observable, Observable::Address(drainer.parse().unwrap()));
+        assert_eq!((indicators[0].threat_type, indicators[0].confidence), (ThreatCategory::Drainer, 0.9));
+        assert_eq!(indicators[2].observable, Observable::Domain("uniswap-claim.example".to_string()));
+        assert_eq!((indicators[2].threat_type, indicators[2].confidence), (ThreatCategory::Phishing, 0.6));
+        assert_eq!(indicators[1].detection().url.as_deref(), Some("https://uniswap-claim.example/"));
+
+        let csv = format!("address,confidence,category\n{},95,honeypot\nnot an address,99,scam\n", drainer);
+        let indicators = parse_csv(&feed(FeedFormat::Csv), &csv).unwrap();
+        assert_eq!(indicators.len(), 1);
+        assert_eq!((indicators[0].threat_type, indicators[0].confidence), (ThreatCategory::Honeypot, 0.95));
+
+        let text = format!("# blocklist\n{}  # drainer\nscam.example\n\n", drainer);
+        let indicators = parse_text(&feed(FeedFormat::Text), &text);
+        assert_eq!(indicators.len(), 2);
+        assert_eq!(indicators[1].detection().target, None);
+
+        // STIX confidence is always out of 100
+        let low = [json!({"type": "indicator", "pattern": "[domain-name:value = 'low.example']", "confidence": 1})];
+        assert_eq!(parse_stix(&feed(FeedFormat::Stix), &low, Utc::now())[0].confidence, 0.01);
+
+        // Known indicators survive a restart and aren't fresh again
+        let dir = tempfile::tempdir().unwrap();
+        let config = IntelConfig {
+            path: dir.path().join("intel").to_string_lossy().into_owned(),
+            ..IntelConfig::default()
+        };
+        let ingester = IntelIngester::open(config.clone()).unwrap();
+        assert_eq!(ingester.record(indicators.clone()).unwrap().len(), 2);
+        drop(ingester);
+        let ingester = IntelIngester::open(config).unwrap();
+        assert_eq!(ingester.get(&indicators[0].observable), Some(indicators[0].clone()));
+        assert!(ingester.record(indicators).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,460 @@
+/*!
+ * Mempool sandwich detection
+ * Watches pending transactions for swaps on monitored pools, through Uniswap V2 style routers or
+ * straight on the pair, and correlates them: an account buying ahead of someone else's swap with
+ * a higher tip, then selling back behind it with a tip no higher than the victim's, is
+ * sandwiching that swap. Tips are what each transaction pays the block producer over the current
+ * base fee, so legacy and EIP-1559 transactions compare fairly. Sandwiches are reported at medium confidence, since a coincidental
+ * trader can fit the pattern, and counted per attacker
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{decode, ParamType, Token},
+    providers::{Middleware, Provider, Ws},
+    types::{Address, BlockNumber, Transaction, H256, U256},
+    utils::id,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use super::{Detection, ThreatCategory};
+
+/// Router swaps that take `(amountIn, amountOutMin)` or `(amountOut, amountInMax)` before the path
+const TOKEN_IN_SWAPS: &[&str] = &[
+    "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+    "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+    "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+    "swapTokensForExactETH(uint256,uint256,address[],address,uint256)",
+    "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+    "swapExactTokensForETHSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+];
+/// Router swaps paid in ETH, taking one amount before the path
+const ETH_IN_SWAPS: &[&str] = &[
+    "swapExactETHForTokens(uint256,address[],address,uint256)",
+    "swapETHForExactTokens(uint256,address[],address,uint256)",
+    "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+];
+const PAIR_SWAP: &str = "swap(uint256,uint256,address,bytes)";
+
+/// Pool watched for sandwiches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoredPool {
+    pub address: Address,
+    pub token0: Address,
+    pub token1: Address,
+}
+
+/// Mempool analysis settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    pub enabled: bool,
+    /// WebSocket endpoint streaming pending transactions
+    pub ws_url: String,
+    pub pools: Vec<MonitoredPool>,
+    /// Routers whose swaps are decoded
+    pub routers: Vec<Address>,
+    /// Seconds pending swaps are correlated over, about a block
+    pub window_secs: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ws_url: "ws://127.0.0.1:8546".to_string(),
+            pools: Vec::new(),
+            routers: vec!["0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D".parse().unwrap()],
+            window_secs: 15,
+        }
+    }
+}
+
+/// Swap seen in the mempool, on one pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingSwap {
+    pub hash: H256,
+    pub from: Address,
+    pub nonce: U256,
+    pub pool: Address,
+    /// Token the swap takes out of the pool
+    pub buys: Address,
+    /// Effective tip per gas over the base fee
+    pub bid: U256,
+}
+
+/// Front-run, victim and back-run on one pool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sandwich {
+    pub pool: Address,
+    pub attacker: Address,
+    pub victim: Address,
+    pub front_run: H256,
+    pub victim_tx: H256,
+    pub back_run: H256,
+    /// Front- and back-run sent with consecutive nonces, as bots do
+    pub consecutive_nonces: bool,
+}
+
+impl Sandwich {
+    pub fn detection(&self) -> Detection {
+        Detection {
+            detector: "mempool:sandwich".to_string(),
+            threat_type: ThreatCategory::Exploit,
+            confidence: if self.consecutive_nonces { 0.7 } else { 0.55 },
+            tx_hash: Some(self.front_run),
+            target: Some(self.attacker),
+            url: None,
+            explanation: format!(
+                "{:?} sandwiches {:?} ({:?}) on pool {:?} with {:?} and {:?}",
+                self.attacker, self.victim, self.victim_tx, self.pool, self.front_run, self.back_run
+            ),
+            evidence: None,
+        }
+    }
+}
+
+/// Sandwiches attributed to one account
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttackerStats {
+    pub sandwiches: u64,
+    pub victims: BTreeSet<Address>,
+    pub pools: BTreeSet<Address>,
+    /// Unix seconds
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// What `tx` pays the block producer per gas at `base_fee`
+pub fn effective_tip(tx: &Transaction, base_fee: U256) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) => max_priority_fee.min(max_fee.saturating_sub(base_fee)),
+        _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+/// Swaps `tx` makes on monitored pools, bidding its effective tip at `base_fee`
+pub fn decode_swaps(config: &MempoolConfig, tx: &Transaction, base_fee: U256) -> Vec<PendingSwap> {
+    let (Some(to), Some(selector)) = (tx.to, tx.input.get(..4)) else {
+        return Vec::new();
+    };
+    let args = &tx.input[4..];
+    let swap = |pool: &MonitoredPool, buys| PendingSwap {
+        hash: tx.hash,
+        from: tx.from,
+        nonce: tx.nonce,
+        pool: pool.address,
+        buys,
+        bid: effective_tip(tx, base_fee),
+    };
+
+    // Straight on the pair: the nonzero output is the token bought
+    if selector == id(PAIR_SWAP) {
+        let Some(pool) = config.pools.iter().find(|pool| pool.address == to) else {
+            return Vec::new();
+        };
+        let params = [ParamType::Uint(256), ParamType::Uint(256), ParamType::Address, ParamType::Bytes];
+        return match decode(&params, args).ok().as_deref() {
+            Some([Token::Uint(amount0_out), ..]) => vec![swap(pool, if amount0_out.is_zero() { pool.token1 } else { pool.token0 })],
+            _ => Vec::new(),
+        };
+    }
+
+    if !config.routers.contains(&to) {
+        return Vec::new();
+    }
+    let leading = if TOKEN_IN_SWAPS.iter().any(|signature| selector == id(signature)) {
+        2
+    } else if ETH_IN_SWAPS.iter().any(|signature| selector == id(signature)) {
+        1
+    } else {
+        return Vec::new();
+    };
+    let mut params = vec![ParamType::Uint(256); leading];
+    params.extend([ParamType::Array(Box::new(ParamType::Address)), ParamType::Address, ParamType::Uint(256)]);
+    let Some(Token::Array(path)) = decode(&params, args).ok().and_then(|mut tokens| tokens.drain(leading..).next()) else {
+        return Vec::new();
+    };
+
+    // Every monitored hop of the path
+    let path: Vec<Address> = path.into_iter().filter_map(Token::into_address).collect();
+    path.windows(2)
+        .filter_map(|hop| {
+            let (sold, bought) = (hop[0], hop[1]);
+            config
+                .pools
+                .iter()
+                .find(|pool| (pool.token0, pool.token1) == (sold, bought) || (pool.token1, pool.token0) == (sold, bought))
+                .map(|pool| swap(pool, bought))
+        })
+        .collect()
+}
+
+/// Whether `front` and `back` sandwich `victim`
+fn is_sandwich(front: &PendingSwap, victim: &PendingSwap, back: &PendingSwap) -> bool {
+    front.from == back.from
+        && victim.from != front.from
+        && front.buys == victim.buys
+        && back.buys != front.buys
+        && front.bid > victim.bid
+        && back.bid <= victim.bid
+}
+
+/// Pending swaps per pool and sender over a sliding window, correlated into sandwiches
+#[derive(Debug)]
+pub struct SandwichCorrelator {
+    window: Duration,
+    pools: HashMap<Address, HashMap<Address, Vec<(Instant, PendingSwap)>>>,
+    /// Victim transactions already reported
+    reported: HashMap<H256, Instant>,
+}
+
+impl SandwichCorrelator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pools: HashMap::new(),
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Add `swap` seen at `now`; returns the sandwiches it completes
+    pub fn observe(&mut self, swap: PendingSwap, now: Instant) -> Vec<Sandwich> {
+        let window = self.window;
+        let fresh = |seen: &Instant| now.saturating_duration_since(*seen) <= window;
+        self.pools.retain(|_, senders| {
+            senders.retain(|_, swaps| {
+                swaps.retain(|(seen, _)| fresh(seen));
+                !swaps.is_empty()
+            });
+            !senders.is_empty()
+        });
+        self.reported.retain(|_, seen| fresh(seen));
+
+        let senders = self.pools.entry(swap.pool).or_default();
+        let own = senders.entry(swap.from).or_default();
+        if own.iter().any(|(_, seen)| seen.hash == swap.hash) {
+            return Vec::new();
+        }
+        own.push((now, swap.clone()));
+
+        // Only sandwiches the new swap is part of: as front- or back-run, paired with the sender's
+        // other swaps around anyone else's, or as victim between another sender's pair
+        let mut candidates = Vec::new();
+        let others = || senders.iter().filter(|(sender, _)| **sender != swap.from).flat_map(|(_, swaps)| swaps);
+        for (_, own) in &senders[&swap.from] {
+            for (_, victim) in others() {
+                candidates.push((&swap, victim, own));
+                candidates.push((own, victim, &swap));
+            }
+        }
+        for (_, swaps) in senders.iter().filter(|(sender, _)| **sender != swap.from) {
+            for (_, front) in swaps {
+                for (_, back) in swaps {
+                    candidates.push((front, &swap, back));
+                }
+            }
+        }
+
+        let mut sandwiches = Vec::new();
+        for (front, victim, back) in candidates {
+            if is_sandwich(front, victim, back) && !self.reported.contains_key(&victim.hash) {
+                self.reported.insert(victim.hash, now);
+                sandwiches.push(Sandwich {
+                    pool: swap.pool,
+                    attacker: front.from,
+                    victim: victim.from,
+                    front_run: front.hash,
+                    victim_tx: victim.hash,
+                    back_run: back.hash,
+                    consecutive_nonces: back.nonce == front.nonce + 1,
+                });
+            }
+        }
+        sandwiches
+    }
+}
+
+/// Streams the mempool into the correlator, publishing sandwich detections
+pub struct MempoolAnalyzer {
+    config: MempoolConfig,
+    correlator: Mutex<SandwichCorrelator>,
+    /// Base fee of the latest block, which tips are taken over
+    base_fee: Mutex<U256>,
+    attackers: Mutex<HashMap<Address, AttackerStats>>,
+    detections: broadcast::Sender<Detection>,
+}
+
+impl MempoolAnalyzer {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            correlator: Mutex::new(SandwichCorrelator::new(Duration::from_secs(config.window_secs))),
+            config,
+            base_fee: Mutex::new(U256::zero()),
+            attackers: Mutex::new(HashMap::new()),
+            detections: broadcast::channel(64).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Detection> {
+        self.detections.subscribe()
+    }
+
+    pub fn set_base_fee(&self, base_fee: U256) {
+        *self.base_fee.lock().unwrap() = base_fee;
+    }
+
+    /// Sandwich counts per attacking account
+    pub fn attacker_stats(&self) -> HashMap<Address, AttackerStats> {
+        self.attackers.lock().unwrap().clone()
+    }
+
+    /// Correlate `tx`, recording and publishing any sandwiches it completes
+    pub fn process(&self, tx: &Transaction) -> Vec<Sandwich> {
+        let now = Instant::now();
+        let base_fee = *self.base_fee.lock().unwrap();
+        let sandwiches: Vec<Sandwich> = decode_swaps(&self.config, tx, base_fee)
+            .into_iter()
+            .flat_map(|swap| self.correlator.lock().unwrap().observe(swap, now))
+            .collect();
+
+        let timestamp = chrono::Utc::now().timestamp();
+        for sandwich in &sandwiches {
+            warn!("🥪 {:?} is sandwiching {:?} on pool {:?}", sandwich.attacker, sandwich.victim, sandwich.pool);
+            let mut attackers = self.attackers.lock().unwrap();
+            let stats = attackers.entry(sandwich.attacker).or_insert_with(|| AttackerStats {
+                first_seen: timestamp,
+                ..AttackerStats::default()
+            });
+            stats.sandwiches += 1;
+            stats.victims.insert(sandwich.victim);
+            stats.pools.insert(sandwich.pool);
+            stats.last_seen = timestamp;
+
+            // No subscribers just means nobody is listening yet
+            let _ = self.detections.send(sandwich.detection());
+        }
+        sandwiches
+    }
+
+    /// Follow pending transactions, and the base fee of new blocks, until a subscription ends
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        let provider = Provider::<Ws>::connect(&self.config.ws_url)
+            .await
+            .with_context(|| format!("Failed to connect to {}", self.config.ws_url))?;
+        if let Ok(Some(block)) = provider.get_block(BlockNumber::Latest).await {
+            self.set_base_fee(block.base_fee_per_gas.unwrap_or_default());
+        }
+        let mut blocks = provider.subscribe_blocks().await.context("Failed to subscribe to new blocks")?;
+        let mut pending = provider.subscribe_pending_txs().await.context("Failed to subscribe to pending transactions")?;
+        info!("👀 Watching the mempool for sandwiches on {} pools", self.config.pools.len());
+
+        loop {
+            tokio::select! {
+                block = blocks.next() => match block {
+                    Some(block) => self.set_base_fee(block.base_fee_per_gas.unwrap_or_default()),
+                    None => return Err(anyhow::anyhow!("Block subscription ended")),
+                },
+                hash = pending.next() => {
+                    let Some(hash) = hash else {
+                        return Err(anyhow::anyhow!("Pending transaction subscription ended"));
+                    };
+                    match provider.get_transaction(hash).await {
+                        Ok(Some(tx)) => {
+                            self.process(&tx);
+                        }
+                        // Mined or dropped before we fetched it
+                        Ok(None) => {}
+                        Err(e) => debug!("Failed to fetch pending transaction {:?}: {}", hash, e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::encode;
+
+    #[test]
+    fn test_sandwich_correlated_from_pending_swaps() {
+        let (weth, token) = (Address::repeat_byte(0xee), Address::repeat_byte(0x70));
+        let pool = MonitoredPool {
+            address: Address::repeat_byte(0x99),
+            token0: token,
+            token1: weth,
+        };
+        let config = MempoolConfig {
+            pools: vec![pool.clone()],
+            ..MempoolConfig::default()
+        };
+        let (bot, victim) = (Address::repeat_byte(0xb0), Address::repeat_byte(0x01));
+
+        // Victim buys the token through the router
+        let victim_tx = Transaction {
+            hash: H256::repeat_byte(2),
+            from: victim,
+            to: Some(config.routers[0]),
+            gas_price: Some(20.into()),
+            input: [
+                id(ETH_IN_SWAPS[0]).to_vec(),
+                encode(&[
+                    Token::Uint(0.into()),
+                    Token::Array(vec![Token::Address(weth), Token::Address(token)]),
+                    Token::Address(victim),
+                    Token::Uint(U256::MAX),
+                ]),
+            ]
+            .concat()
+            .into(),
+            ..Transaction::default()
+        };
+        // Bot buys the token straight from the pair ahead of it, then sells it back behind it
+        let pair_swap = |hash, nonce: u64, gas_price: u64, amount0_out: u64, amount1_out: u64| Transaction {
+            hash: H256::repeat_byte(hash),
+            from: bot,
+            nonce: nonce.into(),
+            to: Some(pool.address),
+            gas_price: Some(gas_price.into()),
+            input: [
+                id(PAIR_SWAP).to_vec(),
+                encode(&[Token::Uint(amount0_out.into()), Token::Uint(amount1_out.into()), Token::Address(bot), Token::Bytes(Vec::new())]),
+            ]
+            .concat()
+            .into(),
+            ..Transaction::default()
+        };
+
+        let analyzer = MempoolAnalyzer::new(config.clone());
+        assert_eq!(decode_swaps(&config, &victim_tx, U256::zero())[0].buys, token);
+        assert!(analyzer.process(&victim_tx).is_empty());
+        assert!(analyzer.process(&pair_swap(1, 7, 50, 1_000, 0)).is_empty());
+        let sandwiches = analyzer.process(&pair_swap(3, 8, 19, 0, 1_000));
+        assert_eq!(sandwiches.len(), 1);
+        assert_eq!((sandwiches[0].attacker, sandwiches[0].victim_tx), (bot, victim_tx.hash));
+        assert!(sandwiches[0].consecutive_nonces);
+
+        // Seeing the back-run again doesn't report the victim twice
+        assert!(analyzer.process(&pair_swap(4, 9, 19, 0, 1_000)).is_empty());
+        assert_eq!(analyzer.attacker_stats()[&bot].sandwiches, 1);
+
+        // Tips are taken over the base fee, whatever the transaction type
+        let dynamic = Transaction {
+            max_fee_per_gas: Some(100.into()),
+            max_priority_fee_per_gas: Some(2.into()),
+            ..Transaction::default()
+        };
+        assert_eq!(effective_tip(&victim_tx, 15.into()), 5.into());
+        assert_eq!(effective_tip(&dynamic, 15.into()), 2.into());
+        assert_eq!(effective_tip(&dynamic, 99.into()), 1.into());
+    }
+}
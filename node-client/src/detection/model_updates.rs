@@ -0,0 +1,288 @@
+/*!
+ * Model updates
+ * New threat models are released through DAGShieldOracle: `latestModel` names the release's
+ * version, the IPFS CID of the ONNX file, its keccak256 hash and the publisher's signature over
+ * all three, bound to the oracle's chain and address so a release can't be replayed on another
+ * deployment. The manager polls for a version newer than the one it runs, checks the signature
+ * recovers to the configured publisher before downloading anything, downloads at most
+ * `max_model_bytes`, checks the download against the hash, and
+ * runs it on a few blank inputs so a model that can't load or run is rejected while the old one
+ * still serves. Only then is the file moved over the configured model path, so a restart loads
+ * the same model, and the detector's session swapped without restarting the node
+ */
+
+use anyhow::{bail, ensure, Context, Result};
+use ethers::{
+    abi::{encode, Token},
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, RecoveryMessage, Signature, H256, U256},
+    utils::keccak256,
+};
+use ort::{Session, Value};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use crate::ai::{self, build_session};
+
+abigen!(
+    ModelRegistry,
+    r#"[
+        function latestModel() external view returns (uint256 version, string cid, bytes32 modelHash, bytes signature)
+    ]"#
+);
+
+/// Model update settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelUpdateConfig {
+    pub enabled: bool,
+    /// DAGShieldOracle announcing model releases
+    pub oracle: Address,
+    /// Only releases signed by this address are installed
+    pub publisher: Address,
+    pub ipfs_gateway: String,
+    /// Blank inferences a release must survive before it goes live
+    pub warmup_runs: usize,
+    pub download_timeout_secs: u64,
+    /// Largest model file downloaded
+    #[serde(default = "default_max_model_bytes")]
+    pub max_model_bytes: u64,
+    pub poll_interval_secs: u64,
+}
+
+fn default_max_model_bytes() -> u64 {
+    256 * 1024 * 1024
+}
+
+impl Default for ModelUpdateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            oracle: Address::zero(),
+            publisher: Address::zero(),
+            ipfs_gateway: "https://ipfs.io".to_string(),
+            warmup_runs: 3,
+            download_timeout_secs: 300,
+            max_model_bytes: default_max_model_bytes(),
+            poll_interval_secs: 3_600,
+        }
+    }
+}
+
+/// Model release as the oracle announces it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelRelease {
+    pub version: u64,
+    pub cid: String,
+    pub model_hash: H256,
+    pub signature: Vec<u8>,
+}
+
+impl ModelRelease {
+    /// What the publisher signs, EIP-191 style:
+    /// `keccak256(abi.encode(chainId, oracle, version, cid, modelHash))`
+    pub fn digest(&self, chain_id: u64, oracle: Address) -> H256 {
+        H256(keccak256(encode(&[
+            Token::Uint(U256::from(chain_id)),
+            Token::Address(oracle),
+            Token::Uint(U256::from(self.version)),
+            Token::String(self.cid.clone()),
+            Token::FixedBytes(self.model_hash.as_bytes().to_vec()),
+        ])))
+    }
+
+    /// Check the release was signed by `publisher` for `oracle` on `chain_id`
+    pub fn verify_signature(&self, publisher: Address, chain_id: u64, oracle: Address) -> Result<()> {
+        let signature = Signature::try_from(self.signature.as_slice()).context("Malformed model release signature")?;
+        let signer = signature
+            .recover(RecoveryMessage::Data(self.digest(chain_id, oracle).as_bytes().to_vec()))
+            .context("Unrecoverable model release signature")?;
+        ensure!(signer == publisher, "Model release {} signed by {:?}, not the publisher {:?}", self.version, signer, publisher);
+        Ok(())
+    }
+
+    /// Check `model` is the released file
+    pub fn verify_model(&self, model: &[u8]) -> Result<()> {
+        let hash = H256(keccak256(model));
+        ensure!(hash == self.model_hash, "Model release {} hash mismatch: got {:?}, expected {:?}", self.version, hash, self.model_hash);
+        Ok(())
+    }
+}
+
+/// Keeps the detector on the latest published model
+pub struct ModelManager<M: Middleware> {
+    config: ModelUpdateConfig,
+    provider: Arc<M>,
+    registry: ModelRegistry<M>,
+    http: reqwest::Client,
+    detector: Arc<ai::ThreatDetector>,
+    model_path: PathBuf,
+}
+
+impl<M: Middleware + 'static> ModelManager<M> {
+    pub fn new(config: ModelUpdateConfig, provider: Arc<M>, detector: Arc<ai::ThreatDetector>, model_path: impl Into<PathBuf>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.download_timeout_secs))
+            .build()
+            .context("Failed to build model download HTTP client")?;
+        Ok(Self {
+            registry: ModelRegistry::new(config.oracle, provider.clone()),
+            provider,
+            config,
+            http,
+            detector,
+            model_path: model_path.into(),
+        })
+    }
+
+    /// Version of the installed model, 0 for none from the oracle
+    pub fn installed_version(&self) -> u64 {
+        std::fs::read_to_string(version_path(&self.model_path))
+            .ok()
+            .and_then(|version| version.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub async fn latest(&self) -> Result<ModelRelease> {
+        let (version, cid, model_hash, signature) = self
+            .registry
+            .latest_model()
+            .call()
+            .await
+            .context("Failed to read the latest model release")?;
+        Ok(ModelRelease {
+            version: version.as_u64(),
+            cid,
+            model_hash: H256(model_hash),
+            signature: signature.to_vec(),
+        })
+    }
+
+    async fn download(&self, release: &ModelRelease) -> Result<Vec<u8>> {
+        let url = format!("{}/ipfs/{}", self.config.ipfs_gateway.trim_end_matches('/'), release.cid);
+        let max = self.config.max_model_bytes;
+        let mut response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to download model from {}", url))?;
+        if let Some(length) = response.content_length() {
+            ensure!(length <= max, "Model release {} is {} bytes, over the {} byte limit", release.version, length, max);
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.with_context(|| format!("Failed to download model from {}", url))? {
+            ensure!(body.len() as u64 + chunk.len() as u64 <= max, "Model release {} is over the {} byte limit", release.version, max);
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// Install the latest release if it is newer than the installed model; returns its version if so
+    pub async fn update(&self) -> Result<Option<u64>> {
+        let release = self.latest().await?;
+        if release.version <= self.installed_version() {
+            return Ok(None);
+        }
+        let chain_id = self.provider.get_chainid().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+        release.verify_signature(self.config.publisher, chain_id, self.config.oracle)?;
+        let model = self.download(&release).await?;
+        release.verify_model(&model)?;
+
+        let staged = self.model_path.with_extension(format!("{}.download", release.version));
+        std::fs::write(&staged, &model).with_context(|| format!("Failed to stage model at {}", staged.display()))?;
+        let session = match build_session(&staged).and_then(|session| warm_up(&session, self.config.warmup_runs).map(|_| session)) {
+            Ok(session) => session,
+            Err(e) => {
+                let _ = std::fs::remove_file(&staged);
+                bail!("Model release {} failed warm-up: {:#}", release.version, e);
+            }
+        };
+
+        std::fs::rename(&staged, &self.model_path)
+            .with_context(|| format!("Failed to install model at {}", self.model_path.display()))?;
+        std::fs::write(version_path(&self.model_path), release.version.to_string())?;
+        self.detector.swap_model(session).await;
+        info!("🧠 Model release {} ({}) is live", release.version, release.cid);
+        Ok(Some(release.version))
+    }
+
+    /// Check for releases every poll interval, forever
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            match self.update().await {
+                Ok(Some(_)) => {}
+                Ok(None) => debug!("Model release {} is current", self.installed_version()),
+                Err(e) => warn!("⚠️ Keeping the current model: {:#}", e),
+            }
+        }
+    }
+}
+
+/// Sidecar file recording the release a model file came from
+fn version_path(model_path: &Path) -> PathBuf {
+    let mut path = model_path.as_os_str().to_owned();
+    path.push(".version");
+    PathBuf::from(path)
+}
+
+/// Run `session` on blank inputs `runs` times; errors if it fails or predicts nothing
+fn warm_up(session: &Session, runs: usize) -> Result<()> {
+    for _ in 0..runs.max(1) {
+        let input = Value::from_array(([1, 512], vec![0.0f32; 512]))?;
+        let outputs = session.run(vec![input])?;
+        let output = outputs.first().context("Model produced no outputs")?;
+        ensure!(output.try_extract_tensor::<f32>()?.iter().next().is_some(), "Model produced an empty prediction");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        signers::{LocalWallet, Signer},
+        utils::hash_message,
+    };
+
+    #[test]
+    fn test_release_verification() {
+        let publisher: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let model = b"onnx model bytes".to_vec();
+        let mut release = ModelRelease {
+            version: 7,
+            cid: "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string(),
+            model_hash: H256(keccak256(&model)),
+            signature: Vec::new(),
+        };
+        let oracle = Address::repeat_byte(0x0a);
+        let signed = |release: &ModelRelease| publisher.sign_hash(hash_message(release.digest(39, oracle))).unwrap().to_vec();
+        release.signature = signed(&release);
+
+        release.verify_signature(publisher.address(), 39, oracle).unwrap();
+        release.verify_model(&model).unwrap();
+        assert!(release.verify_model(b"tampered").is_err());
+        assert!(release.verify_signature(Address::repeat_byte(1), 39, oracle).is_err());
+
+        // Nor is it valid on another chain or oracle deployment
+        assert!(release.verify_signature(publisher.address(), 1, oracle).is_err());
+        assert!(release.verify_signature(publisher.address(), 39, Address::repeat_byte(0x0b)).is_err());
+
+        // A signature over one release doesn't carry over to another
+        let mut other = release.clone();
+        other.version = 8;
+        assert!(other.verify_signature(publisher.address(), 39, oracle).is_err());
+        other.signature = signed(&other);
+        other.verify_signature(publisher.address(), 39, oracle).unwrap();
+
+        assert_eq!(version_path(Path::new("models/threat.onnx")), PathBuf::from("models/threat.onnx.version"));
+    }
+}
@@ -0,0 +1,404 @@
+/*!
+ * Phishing URL detection
+ * Scores URLs and domains for phishing: lexical features (length, hyphens, lure keywords, IP
+ * hosts), punycode hosts whose homoglyph skeleton spells a known dApp domain, typosquats within
+ * a small Levenshtein distance of one, and known brands buried in another site's hostname.
+ * Registrable domains come from the public suffix list, so `app.example.co.uk` belongs to
+ * `example.co.uk` and every `*.github.io` site is a domain of its own.
+ * Optional DNS and RDAP (WHOIS over HTTP) lookups add whether the domain resolves and how old
+ * it is. Known dApp domains and their subdomains are never reported
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, time::Duration};
+use tracing::debug;
+use url::Url;
+
+use super::{Detection, ThreatCategory};
+
+/// Words phishing sites lure wallet users with
+const LURE_KEYWORDS: &[&str] = &["airdrop", "claim", "connect", "verify", "wallet", "reward", "migrate", "bonus", "recover"];
+
+/// Phishing detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhishingConfig {
+    pub enabled: bool,
+    /// Registrable domains of legitimate dApps that lookalikes are measured against
+    pub known_domains: Vec<String>,
+    /// Largest edit distance to a known domain reported as a typosquat
+    pub max_edit_distance: usize,
+    /// Analyses at or above this confidence become detections
+    pub report_threshold: f64,
+    /// Check whether the domain resolves
+    #[serde(default)]
+    pub dns_lookup: bool,
+    /// RDAP endpoint the domain is appended to for its registration date, like
+    /// `https://rdap.org/domain/`
+    #[serde(default)]
+    pub rdap_url: Option<String>,
+    /// Domains registered more recently than this count as young
+    pub young_domain_days: u64,
+    pub lookup_timeout_secs: u64,
+}
+
+impl Default for PhishingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            known_domains: [
+                "uniswap.org", "metamask.io", "opensea.io", "aave.com", "curve.fi", "lido.fi", "1inch.io",
+                "pancakeswap.finance", "compound.finance", "etherscan.io", "sushi.com", "blur.io",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            max_edit_distance: 2,
+            report_threshold: 0.7,
+            dns_lookup: false,
+            rdap_url: None,
+            young_domain_days: 30,
+            lookup_timeout_secs: 5,
+        }
+    }
+}
+
+/// Lexical features of a URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UrlFeatures {
+    /// Host as in the URL, punycode for internationalized domains
+    pub host: String,
+    /// Host with punycode decoded
+    pub unicode_host: String,
+    /// Public suffix plus one label, like `example.co.uk`
+    pub registrable_domain: String,
+    pub url_length: usize,
+    /// Labels in front of the registrable domain
+    pub subdomain_depth: usize,
+    pub hyphens: usize,
+    pub digits: usize,
+    pub ip_host: bool,
+    pub https: bool,
+    pub punycode: bool,
+    pub lure_keywords: Vec<String>,
+}
+
+impl UrlFeatures {
+    pub fn extract(url: &Url) -> Result<Self> {
+        let host = url.host_str().context("URL has no host")?.trim_end_matches('.').to_lowercase();
+        let ip_host = host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>().is_ok();
+        let (unicode_host, _) = idna::domain_to_unicode(&host);
+        let labels: Vec<&str> = host.split('.').collect();
+        let registrable_domain = if ip_host { host.clone() } else { registrable_domain(&host) };
+
+        let text = format!("{}{}", host, url.path()).to_lowercase();
+        Ok(Self {
+            url_length: url.as_str().len(),
+            subdomain_depth: if ip_host { 0 } else { labels.len().saturating_sub(registrable_domain.split('.').count()) },
+            hyphens: host.matches('-').count(),
+            digits: host.chars().filter(char::is_ascii_digit).count(),
+            ip_host,
+            https: url.scheme() == "https",
+            punycode: labels.iter().any(|label| label.starts_with("xn--")),
+            lure_keywords: LURE_KEYWORDS
+                .iter()
+                .filter(|keyword| text.contains(*keyword))
+                .map(|keyword| keyword.to_string())
+                .collect(),
+            host,
+            unicode_host,
+            registrable_domain,
+        })
+    }
+}
+
+/// Registrable domain of `host` under the public suffix list; hosts that are themselves a
+/// public suffix, or have none, are their own domain
+pub fn registrable_domain(host: &str) -> String {
+    psl::domain_str(host).unwrap_or(host).to_string()
+}
+
+/// Why a URL looks like phishing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "signal", rename_all = "snake_case")]
+pub enum Signal {
+    /// Host spells a known domain with lookalike characters
+    Homoglyph { impersonates: String },
+    /// Registrable domain is a few edits from a known one
+    Typosquat { impersonates: String, distance: usize },
+    /// A known brand appears in another site's hostname, like `uniswap-claim.xyz`
+    BrandInHost { brand: String },
+    Punycode,
+    IpHost,
+    LureKeywords { keywords: Vec<String> },
+    DeepSubdomains { depth: usize },
+    NoHttps,
+    YoungDomain { days: u64 },
+    Unresolvable,
+}
+
+impl Signal {
+    /// Confidence the signal carries on its own (impersonation) or adds (the rest)
+    fn weight(&self) -> f64 {
+        match self {
+            Self::Homoglyph { .. } => 0.95,
+            Self::Typosquat { distance: 1, .. } => 0.85,
+            Self::Typosquat { .. } => 0.75,
+            Self::BrandInHost { .. } => 0.7,
+            Self::IpHost | Self::LureKeywords { .. } | Self::YoungDomain { .. } => 0.1,
+            Self::Punycode | Self::DeepSubdomains { .. } | Self::NoHttps | Self::Unresolvable => 0.05,
+        }
+    }
+
+    fn impersonates(&self) -> Option<&str> {
+        match self {
+            Self::Homoglyph { impersonates } | Self::Typosquat { impersonates, .. } => Some(impersonates),
+            Self::BrandInHost { brand } => Some(brand),
+            _ => None,
+        }
+    }
+}
+
+/// Verdict on one URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhishingAnalysis {
+    pub url: String,
+    pub features: UrlFeatures,
+    pub signals: Vec<Signal>,
+    /// Known domain the site imitates
+    pub impersonates: Option<String>,
+    pub confidence: f64,
+}
+
+impl PhishingAnalysis {
+    /// Strongest impersonation signal, raised by every other signal
+    fn score(&mut self) {
+        let impersonation = self
+            .signals
+            .iter()
+            .filter(|signal| signal.impersonates().is_some())
+            .max_by(|a, b| a.weight().total_cmp(&b.weight()));
+        self.impersonates = impersonation.and_then(Signal::impersonates).map(String::from);
+        let base = impersonation.map_or(0.2, Signal::weight);
+        let boost: f64 = self.signals.iter().filter(|signal| signal.impersonates().is_none()).map(Signal::weight).sum();
+        self.confidence = if self.signals.is_empty() { 0.0 } else { (base + boost).min(0.99) };
+    }
+
+    pub fn detection(&self) -> Detection {
+        Detection {
+            detector: "phishing".to_string(),
+            threat_type: ThreatCategory::Phishing,
+            confidence: self.confidence,
+            tx_hash: None,
+            target: None,
+            url: Some(self.url.clone()),
+            explanation: match &self.impersonates {
+                Some(known) => format!("{} imitates {} ({} signals)", self.features.host, known, self.signals.len()),
+                None => format!("{} has {} phishing signals", self.features.host, self.signals.len()),
+            },
+            evidence: None,
+        }
+    }
+}
+
+/// ASCII lookalike of a character, for homoglyph skeletons
+fn skeleton_char(c: char) -> char {
+    match c {
+        'а' | 'α' => 'a',
+        'е' | 'ε' => 'e',
+        'о' | 'ο' | 'σ' | '0' => 'o',
+        'р' | 'ρ' => 'p',
+        'с' | 'ϲ' => 'c',
+        'у' | 'γ' => 'y',
+        'х' | 'χ' => 'x',
+        'і' | 'ι' | 'ı' => 'i',
+        'ӏ' | '1' => 'l',
+        'ј' => 'j',
+        'ѕ' => 's',
+        'ԁ' => 'd',
+        'ɡ' => 'g',
+        'ո' => 'n',
+        'ν' => 'v',
+        'ԝ' | 'ω' => 'w',
+        'к' | 'κ' => 'k',
+        'м' => 'm',
+        'т' | 'τ' => 't',
+        'в' => 'b',
+        'н' => 'h',
+        c => c,
+    }
+}
+
+/// Host with lookalike characters and `rn` (for `m`) folded to one spelling
+fn skeleton(host: &str) -> String {
+    host.chars().map(skeleton_char).collect::<String>().replace("rn", "m")
+}
+
+/// Phishing URL analysis
+pub struct PhishingDetector {
+    config: PhishingConfig,
+    http: reqwest::Client,
+}
+
+impl PhishingDetector {
+    pub fn new(config: PhishingConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.lookup_timeout_secs))
+            .build()
+            .context("Failed to build RDAP client")?;
+        Ok(Self { config, http })
+    }
+
+    /// Analyze a URL from its text alone; bare domains are taken as `https://`
+    pub fn analyze_lexical(&self, input: &str) -> Result<PhishingAnalysis> {
+        let url = Url::parse(input)
+            .or_else(|_| Url::parse(&format!("https://{}", input)))
+            .with_context(|| format!("Invalid URL {}", input))?;
+        let features = UrlFeatures::extract(&url)?;
+        let mut analysis = PhishingAnalysis {
+            url: url.to_string(),
+            signals: Vec::new(),
+            impersonates: None,
+            confidence: 0.0,
+            features,
+        };
+
+        let features = &analysis.features;
+        if self.config.known_domains.contains(&features.registrable_domain) {
+            return Ok(analysis);
+        }
+
+        let mut signals = Vec::new();
+        if !features.ip_host {
+            let unicode_domain = registrable_domain(&features.unicode_host);
+            let skeleton = skeleton(&unicode_domain);
+            for known in &self.config.known_domains {
+                if unicode_domain != *known && skeleton == self::skeleton(known) {
+                    signals.push(Signal::Homoglyph { impersonates: known.clone() });
+                    continue;
+                }
+                let distance = strsim::levenshtein(&features.registrable_domain, known);
+                if (1..=self.config.max_edit_distance).contains(&distance) {
+                    signals.push(Signal::Typosquat { impersonates: known.clone(), distance });
+                    continue;
+                }
+                let brand = known.split('.').next().unwrap_or(known);
+                if brand.len() >= 4 && features.host.contains(brand) {
+                    signals.push(Signal::BrandInHost { brand: known.clone() });
+                }
+            }
+        }
+
+        if features.punycode {
+            signals.push(Signal::Punycode);
+        }
+        if features.ip_host {
+            signals.push(Signal::IpHost);
+        }
+        if !features.lure_keywords.is_empty() {
+            signals.push(Signal::LureKeywords { keywords: features.lure_keywords.clone() });
+        }
+        if features.subdomain_depth >= 3 {
+            signals.push(Signal::DeepSubdomains { depth: features.subdomain_depth });
+        }
+        if !features.https {
+            signals.push(Signal::NoHttps);
+        }
+
+        analysis.signals = signals;
+        analysis.score();
+        Ok(analysis)
+    }
+
+    /// Analyze a URL, adding the configured DNS and RDAP lookups for suspicious ones
+    pub async fn analyze(&self, input: &str) -> Result<PhishingAnalysis> {
+        let mut analysis = self.analyze_lexical(input)?;
+        if analysis.signals.is_empty() || analysis.features.ip_host {
+            return Ok(analysis);
+        }
+
+        if self.config.dns_lookup {
+            let resolves = tokio::net::lookup_host((analysis.features.host.as_str(), 443))
+                .await
+                .is_ok_and(|mut addresses| addresses.next().is_some());
+            if !resolves {
+                analysis.signals.push(Signal::Unresolvable);
+            }
+        }
+        if let Some(rdap_url) = &self.config.rdap_url {
+            match self.domain_age_days(rdap_url, &analysis.features.registrable_domain).await {
+                Ok(days) if days < self.config.young_domain_days => analysis.signals.push(Signal::YoungDomain { days }),
+                Ok(_) => {}
+                Err(e) => debug!("🌐 No registration date for {}: {:#}", analysis.features.registrable_domain, e),
+            }
+        }
+
+        analysis.score();
+        Ok(analysis)
+    }
+
+    /// Detection for `input` when it scores at or above the report threshold
+    pub async fn detect(&self, input: &str) -> Result<Option<Detection>> {
+        Ok(self.report(&self.analyze(input).await?))
+    }
+
+    /// Detection for an analysis at or above the report threshold
+    pub fn report(&self, analysis: &PhishingAnalysis) -> Option<Detection> {
+        (analysis.confidence >= self.config.report_threshold).then(|| analysis.detection())
+    }
+
+    /// Days since the domain's RDAP registration event
+    async fn domain_age_days(&self, rdap_url: &str, domain: &str) -> Result<u64> {
+        let record: serde_json::Value = self
+            .http
+            .get(format!("{}{}", rdap_url, domain))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let registered = record["events"]
+            .as_array()
+            .and_then(|events| events.iter().find(|event| event["eventAction"] == "registration"))
+            .and_then(|event| event["eventDate"].as_str())
+            .context("RDAP record has no registration event")?;
+        let registered = chrono::DateTime::parse_from_rfc3339(registered)?;
+        Ok((chrono::Utc::now() - registered.with_timezone(&chrono::Utc)).num_days().max(0) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookalikes_scored_above_known_domains() {
+        let detector = PhishingDetector::new(PhishingConfig::default()).unwrap();
+        let confidence = |url: &str| detector.analyze_lexical(url).unwrap().confidence;
+
+        // Known dApps and their subdomains are never reported
+        assert_eq!(confidence("https://app.uniswap.org/swap"), 0.0);
+
+        // Cyrillic 'і' and 'а' spell uniswap.org; the URL carries the punycode host
+        let homoglyph = detector.analyze_lexical("https://unіswаp.org").unwrap();
+        assert!(homoglyph.features.host.starts_with("xn--"));
+        assert_eq!(homoglyph.features.unicode_host, "unіswаp.org");
+        assert_eq!(homoglyph.impersonates.as_deref(), Some("uniswap.org"));
+        assert!(homoglyph.confidence >= 0.95);
+
+        let typosquat = detector.analyze_lexical("https://uniswaap.org/claim").unwrap();
+        assert!(typosquat.signals.contains(&Signal::Typosquat { impersonates: "uniswap.org".to_string(), distance: 1 }));
+        assert!(typosquat.detection().confidence > 0.85);
+
+        assert!(confidence("http://metamask-wallet-verify.xyz") >= 0.7);
+        assert!(confidence("http://203.0.113.7/airdrop") < 0.7);
+        assert!(confidence("https://example.com") < 0.7);
+
+        // Multi-label public suffixes and hosting suffixes keep their registrable domain
+        assert_eq!(registrable_domain("app.example.co.uk"), "example.co.uk");
+        assert_eq!(registrable_domain("uniswap.github.io"), "uniswap.github.io");
+        let hosted = detector.analyze_lexical("https://claim.uniswap.github.io").unwrap();
+        assert_eq!((hosted.features.registrable_domain.as_str(), hosted.features.subdomain_depth), ("uniswap.github.io", 1));
+        assert!(hosted.confidence > 0.0);
+    }
+}
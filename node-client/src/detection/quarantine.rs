@@ -0,0 +1,348 @@
+/*!
+ * Quarantine
+ * Borderline detections, confident enough to matter but not enough to report on their own, are
+ * held here instead of going out as low-priority reports. An operator reviews them over the REST
+ * API: approved ones are reported the next time the node processes threats, through the same path
+ * as its other detections, rejected ones never are, and escalated ones stay held for a second,
+ * different reviewer, identified by their API credential. Reviews are applied with a sled
+ * compare-and-swap, so of two concurrent reviews of one detection only one lands. The queue is
+ * persisted in sled so a restart doesn't drop detections waiting for review; pending ones nobody
+ * reviews in time expire
+ */
+
+use anyhow::{Context, Result};
+use ethers::types::H256;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::dag::Transaction;
+use crate::detection::Detection;
+use crate::u2u_integration::dedupe::threat_hash;
+
+/// Quarantine settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// Confidence band `[min_confidence, max_confidence)` held for review
+    pub min_confidence: f64,
+    pub max_confidence: f64,
+    /// Pending detections older than this expire unreviewed; 0 keeps them
+    pub expire_secs: i64,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./data/quarantine".to_string(),
+            min_confidence: 0.4,
+            max_confidence: 0.7,
+            expire_secs: 86_400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineStatus {
+    Pending,
+    /// Waiting for a second reviewer
+    Escalated,
+    /// Approved, to be reported the next time the node processes threats
+    Approved,
+    Rejected,
+    Submitted,
+    Expired,
+}
+
+impl QuarantineStatus {
+    /// Whether a reviewer can still act on it
+    pub fn is_open(self) -> bool {
+        matches!(self, Self::Pending | Self::Escalated)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewAction {
+    Approve,
+    Reject,
+    Escalate,
+}
+
+/// One reviewer's decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    pub action: ReviewAction,
+    pub reviewer: Option<String>,
+    pub note: Option<String>,
+    pub reviewed_at: i64,
+}
+
+/// Why a review was refused
+#[derive(Debug, thiserror::Error)]
+pub enum ReviewError {
+    #[error("No quarantined detection {0:?}")]
+    NotFound(H256),
+    #[error("Quarantined detection {0:?} is already {1:?}")]
+    Closed(H256, QuarantineStatus),
+    #[error("Escalated detections need a named reviewer")]
+    ReviewerRequired,
+    #[error("{0} escalated this detection and can't also review it")]
+    SameReviewer(String),
+    #[error(transparent)]
+    Store(#[from] anyhow::Error),
+}
+
+/// Detection held for review, with what reporting it needs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantinedDetection {
+    pub id: H256,
+    pub transaction_id: String,
+    pub threat_type: String,
+    pub target_address: String,
+    pub chain_id: u64,
+    pub confidence: f64,
+    pub explanation: String,
+    pub status: QuarantineStatus,
+    pub queued_at: i64,
+    pub reviews: Vec<Review>,
+    /// Transaction the detection was made on, proven and deduplicated when it is reported
+    #[serde(default)]
+    pub transaction: Option<Transaction>,
+    /// Scored detection and the detector outputs behind it, kept for the feedback record
+    #[serde(default)]
+    pub detection: Option<Detection>,
+    #[serde(default)]
+    pub contributions: Vec<Detection>,
+}
+
+impl QuarantinedDetection {
+    /// Transaction to report the detection on; entries held without one get a stand-in
+    /// carrying only what the queue recorded
+    pub fn transaction(&self) -> Transaction {
+        self.transaction.clone().unwrap_or_else(|| Transaction {
+            id: self.transaction_id.clone(),
+            from: String::new(),
+            to: String::new(),
+            target_address: self.target_address.clone(),
+            chain_id: self.chain_id,
+            data: Vec::new(),
+            timestamp: self.queued_at.max(0) as u64,
+            dependencies: Vec::new(),
+        })
+    }
+}
+
+/// Persistent review queue
+pub struct Quarantine {
+    config: QuarantineConfig,
+    tree: sled::Tree,
+}
+
+impl Quarantine {
+    pub fn open(config: QuarantineConfig) -> Result<Self> {
+        let db = sled::open(&config.path).with_context(|| format!("Failed to open quarantine at {}", config.path))?;
+        let tree = db.open_tree("quarantine")?;
+        Ok(Self { config, tree })
+    }
+
+    /// Whether detections at `confidence` are held for review
+    pub fn covers(&self, confidence: f64) -> bool {
+        (self.config.min_confidence..self.config.max_confidence).contains(&confidence)
+    }
+
+    fn key(id: &H256) -> Vec<u8> {
+        [b"q:".as_slice(), id.as_bytes()].concat()
+    }
+
+    fn put(&self, entry: &QuarantinedDetection) -> Result<()> {
+        self.tree.insert(Self::key(&entry.id), serde_json::to_vec(entry)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &H256) -> Result<Option<QuarantinedDetection>> {
+        self.tree
+            .get(Self::key(id))?
+            .map(|value| serde_json::from_slice(&value).context("Corrupt quarantined detection"))
+            .transpose()
+    }
+
+    /// Hold a detection on `transaction`, with the scored detection behind it if any; the same
+    /// threat on the same transaction is held once
+    pub fn hold(
+        &self,
+        transaction: &Transaction,
+        threat_type: &str,
+        confidence: f64,
+        explanation: &str,
+        scored: Option<(&Detection, &[Detection])>,
+    ) -> Result<QuarantinedDetection> {
+        let id = threat_hash(format!("{}:{}:{}", transaction.id, threat_type, transaction.target_address).as_bytes());
+        if let Some(entry) = self.get(&id)? {
+            return Ok(entry);
+        }
+        let entry = QuarantinedDetection {
+            id,
+            transaction_id: transaction.id.clone(),
+            threat_type: threat_type.to_string(),
+            target_address: transaction.target_address.clone(),
+            chain_id: transaction.chain_id,
+            confidence,
+            explanation: explanation.to_string(),
+            status: QuarantineStatus::Pending,
+            queued_at: chrono::Utc::now().timestamp(),
+            reviews: Vec::new(),
+            transaction: Some(transaction.clone()),
+            detection: scored.map(|(detection, _)| detection.clone()),
+            contributions: scored.map(|(_, contributions)| contributions.to_vec()).unwrap_or_default(),
+        };
+        self.put(&entry)?;
+        info!("🧪 Quarantined {} on {} at {:.2} for review", threat_type, transaction.target_address, confidence);
+        Ok(entry)
+    }
+
+    /// Every held detection, with `status` if given, oldest first
+    pub fn list(&self, status: Option<QuarantineStatus>) -> Result<Vec<QuarantinedDetection>> {
+        let mut entries = self
+            .tree
+            .scan_prefix(b"q:")
+            .values()
+            .map(|value| Ok(serde_json::from_slice::<QuarantinedDetection>(&value?)?))
+            .collect::<Result<Vec<_>>>()
+            .context("Corrupt quarantine")?;
+        entries.retain(|entry| status.map_or(true, |status| entry.status == status));
+        entries.sort_by_key(|entry| entry.queued_at);
+        Ok(entries)
+    }
+
+    /// Apply a reviewer's decision to an open detection. An escalated one needs a named reviewer
+    /// other than whoever escalated it
+    pub fn review(&self, id: &H256, action: ReviewAction, reviewer: Option<String>, note: Option<String>) -> Result<QuarantinedDetection, ReviewError> {
+        let key = Self::key(id);
+        loop {
+            let current = self.tree.get(&key).context("Failed to read quarantine")?.ok_or(ReviewError::NotFound(*id))?;
+            let mut entry: QuarantinedDetection = serde_json::from_slice(&current).context("Corrupt quarantined detection")?;
+            if !entry.status.is_open() {
+                return Err(ReviewError::Closed(*id, entry.status));
+            }
+            if entry.status == QuarantineStatus::Escalated {
+                let reviewer = reviewer.as_deref().ok_or(ReviewError::ReviewerRequired)?;
+                if entry.reviews.iter().any(|review| review.reviewer.as_deref() == Some(reviewer)) {
+                    return Err(ReviewError::SameReviewer(reviewer.to_string()));
+                }
+            }
+            entry.status = match action {
+                ReviewAction::Approve => QuarantineStatus::Approved,
+                ReviewAction::Reject => QuarantineStatus::Rejected,
+                ReviewAction::Escalate => QuarantineStatus::Escalated,
+            };
+            entry.reviews.push(Review {
+                action,
+                reviewer: reviewer.clone(),
+                note: note.clone(),
+                reviewed_at: chrono::Utc::now().timestamp(),
+            });
+            let updated = serde_json::to_vec(&entry).context("Failed to encode quarantined detection")?;
+            // Someone else reviewed it first: start over from what they left
+            let swapped = self.tree.compare_and_swap(&key, Some(current), Some(updated)).context("Failed to write quarantine")?;
+            if swapped.is_ok() {
+                self.tree.flush().context("Failed to flush quarantine")?;
+                info!("🧪 Quarantined detection {:?} is now {:?}", id, entry.status);
+                return Ok(entry);
+            }
+        }
+    }
+
+    /// Approved detections waiting to be reported
+    pub fn approved(&self) -> Result<Vec<QuarantinedDetection>> {
+        self.list(Some(QuarantineStatus::Approved))
+    }
+
+    pub fn mark_submitted(&self, id: &H256) -> Result<()> {
+        let mut entry = self.get(id)?.with_context(|| format!("No quarantined detection {:?}", id))?;
+        entry.status = QuarantineStatus::Submitted;
+        self.put(&entry)
+    }
+
+    /// Expire pending detections past `expire_secs`; returns how many
+    pub fn expire(&self) -> Result<usize> {
+        if self.config.expire_secs <= 0 {
+            return Ok(0);
+        }
+        let cutoff = chrono::Utc::now().timestamp() - self.config.expire_secs;
+        let mut expired = 0;
+        for mut entry in self.list(Some(QuarantineStatus::Pending))? {
+            if entry.queued_at < cutoff {
+                entry.status = QuarantineStatus::Expired;
+                self.put(&entry)?;
+                expired += 1;
+            }
+        }
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(id: &str, target_address: &str) -> Transaction {
+        Transaction {
+            id: id.to_string(),
+            from: String::new(),
+            to: target_address.to_string(),
+            target_address: target_address.to_string(),
+            chain_id: 1,
+            data: id.as_bytes().to_vec(),
+            timestamp: 0,
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_review_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = QuarantineConfig {
+            enabled: true,
+            path: dir.path().to_string_lossy().into_owned(),
+            ..QuarantineConfig::default()
+        };
+        let quarantine = Quarantine::open(config.clone()).unwrap();
+        assert!(quarantine.covers(0.4) && quarantine.covers(0.69) && !quarantine.covers(0.7) && !quarantine.covers(0.3));
+
+        let first = quarantine.hold(&transaction("tx1", "0xabc"), "phishing", 0.55, "lure", None).unwrap();
+        assert_eq!(quarantine.hold(&transaction("tx1", "0xabc"), "phishing", 0.55, "lure", None).unwrap(), first);
+        let second = quarantine.hold(&transaction("tx2", "0xdef"), "rug_pull", 0.45, "liquidity pulled", None).unwrap();
+        assert_eq!(quarantine.list(Some(QuarantineStatus::Pending)).unwrap().len(), 2);
+
+        let escalated = quarantine.review(&first.id, ReviewAction::Escalate, Some("alice".into()), None).unwrap();
+        assert_eq!(escalated.status, QuarantineStatus::Escalated);
+        // The second opinion has to come from someone else
+        assert!(matches!(quarantine.review(&first.id, ReviewAction::Approve, None, None), Err(ReviewError::ReviewerRequired)));
+        assert!(matches!(
+            quarantine.review(&first.id, ReviewAction::Approve, Some("alice".into()), None),
+            Err(ReviewError::SameReviewer(_))
+        ));
+        quarantine.review(&first.id, ReviewAction::Approve, Some("bob".into()), Some("confirmed lure".into())).unwrap();
+        quarantine.review(&second.id, ReviewAction::Reject, None, None).unwrap();
+        assert!(matches!(quarantine.review(&second.id, ReviewAction::Approve, None, None), Err(ReviewError::Closed(..))));
+
+        let approved = quarantine.approved().unwrap();
+        assert_eq!(approved.len(), 1);
+        assert_eq!(approved[0].reviews.len(), 2);
+        // Approved detections are reported on the transaction they were held for
+        assert_eq!(approved[0].transaction().data, b"tx1".to_vec());
+        quarantine.mark_submitted(&first.id).unwrap();
+        assert!(quarantine.approved().unwrap().is_empty());
+
+        drop(quarantine);
+        let quarantine = Quarantine::open(QuarantineConfig { expire_secs: 1, ..config }).unwrap();
+        assert_eq!(quarantine.get(&second.id).unwrap().unwrap().status, QuarantineStatus::Rejected);
+        let stale = quarantine.hold(&transaction("tx3", "0x123"), "spam", 0.5, "dust", None).unwrap();
+        quarantine.put(&QuarantinedDetection { queued_at: stale.queued_at - 10, ..stale.clone() }).unwrap();
+        assert_eq!(quarantine.expire().unwrap(), 1);
+        assert_eq!(quarantine.get(&stale.id).unwrap().unwrap().status, QuarantineStatus::Expired);
+    }
+}
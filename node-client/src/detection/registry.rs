@@ -0,0 +1,285 @@
+/*!
+ * Detector registry
+ * Detection techniques plug into the pipeline by implementing `ThreatDetector`: an async
+ * `featurize` step gathering what the technique needs about a transaction (decoding, RPC reads)
+ * and a synchronous `score` step turning that into detections. The registry runs every enabled
+ * detector on a transaction concurrently, each under a timeout so one slow RPC can't hold the
+ * others back, merges their detections for the scoring model and counts runs, detections, errors
+ * and latency per detector for the metrics exporter
+ */
+
+use anyhow::Result;
+use futures::future::{join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+use super::{Detection, ThreatCategory, TxContext};
+
+/// Registry settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    /// Detectors registered but not run, by name
+    pub disabled: Vec<String>,
+    /// Longest one detector may take on one transaction
+    pub timeout_ms: u64,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            disabled: Vec::new(),
+            timeout_ms: 5_000,
+        }
+    }
+}
+
+/// A detection technique
+pub trait ThreatDetector: Send + Sync {
+    /// What `score` needs about a transaction
+    type Features: Send;
+
+    /// Detector family, the prefix of its detections' `detector`
+    fn name(&self) -> &str;
+
+    /// Category it reports, `None` for detectors covering several, like rules
+    fn category(&self) -> Option<ThreatCategory>;
+
+    /// Features of `tx`, or `None` when `tx` is nothing this detector looks at
+    fn featurize(&self, tx: &TxContext) -> impl Future<Output = Result<Option<Self::Features>>> + Send;
+
+    fn score(&self, tx: &TxContext, features: &Self::Features) -> Vec<Detection>;
+}
+
+impl<D: ThreatDetector> ThreatDetector for Arc<D> {
+    type Features = D::Features;
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        (**self).category()
+    }
+
+    fn featurize(&self, tx: &TxContext) -> impl Future<Output = Result<Option<Self::Features>>> + Send {
+        (**self).featurize(tx)
+    }
+
+    fn score(&self, tx: &TxContext, features: &Self::Features) -> Vec<Detection> {
+        (**self).score(tx, features)
+    }
+}
+
+/// `ThreatDetector` with its features erased, so detectors of any kind share one list
+trait ErasedDetector: Send + Sync {
+    fn name(&self) -> &str;
+    fn category(&self) -> Option<ThreatCategory>;
+    fn detect<'a>(&'a self, tx: &'a TxContext) -> BoxFuture<'a, Result<Vec<Detection>>>;
+}
+
+impl<D: ThreatDetector> ErasedDetector for D {
+    fn name(&self) -> &str {
+        ThreatDetector::name(self)
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        ThreatDetector::category(self)
+    }
+
+    fn detect<'a>(&'a self, tx: &'a TxContext) -> BoxFuture<'a, Result<Vec<Detection>>> {
+        Box::pin(async move {
+            Ok(match self.featurize(tx).await? {
+                Some(features) => self.score(tx, &features),
+                None => Vec::new(),
+            })
+        })
+    }
+}
+
+/// Counters of one detector
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectorStats {
+    pub name: String,
+    pub category: Option<ThreatCategory>,
+    pub enabled: bool,
+    pub runs: u64,
+    pub detections: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    pub avg_latency_ms: f64,
+}
+
+struct Entry {
+    detector: Box<dyn ErasedDetector>,
+    enabled: AtomicBool,
+    stats: Mutex<DetectorStats>,
+}
+
+/// Detectors the pipeline runs on every transaction
+#[derive(Default)]
+pub struct DetectorRegistry {
+    config: RegistryConfig,
+    entries: Vec<Entry>,
+}
+
+impl DetectorRegistry {
+    pub fn new(config: RegistryConfig) -> Self {
+        Self { config, entries: Vec::new() }
+    }
+
+    /// Add a detector, enabled unless the config disables it
+    pub fn register<D: ThreatDetector + 'static>(&mut self, detector: D) {
+        let name = ThreatDetector::name(&detector).to_string();
+        let enabled = !self.config.disabled.contains(&name);
+        debug!("🧩 Registered detector {} (enabled: {})", name, enabled);
+        let stats = DetectorStats {
+            name,
+            category: ThreatDetector::category(&detector),
+            enabled,
+            runs: 0,
+            detections: 0,
+            errors: 0,
+            timeouts: 0,
+            avg_latency_ms: 0.0,
+        };
+        self.entries.push(Entry {
+            detector: Box::new(detector),
+            enabled: AtomicBool::new(enabled),
+            stats: Mutex::new(stats),
+        });
+    }
+
+    /// Enable or disable the detector called `name`; returns whether there is one
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        let Some(entry) = self.entries.iter().find(|entry| entry.detector.name() == name) else {
+            return false;
+        };
+        entry.enabled.store(enabled, Ordering::Relaxed);
+        entry.stats.lock().unwrap().enabled = enabled;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Run every enabled detector on `tx` concurrently; returns their detections, strongest first.
+    /// A detector that fails or times out is logged and counted, and the others still report
+    pub async fn run(&self, tx: &TxContext) -> Vec<Detection> {
+        let timeout = Duration::from_millis(self.config.timeout_ms.max(1));
+        let runs = self.entries.iter().filter(|entry| entry.enabled.load(Ordering::Relaxed)).map(|entry| async move {
+            let start = Instant::now();
+            let outcome = tokio::time::timeout(timeout, entry.detector.detect(tx)).await;
+            let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+            let mut stats = entry.stats.lock().unwrap();
+            stats.runs += 1;
+            stats.avg_latency_ms += (elapsed_ms - stats.avg_latency_ms) / stats.runs as f64;
+            match outcome {
+                Ok(Ok(detections)) => {
+                    stats.detections += detections.len() as u64;
+                    detections
+                }
+                Ok(Err(e)) => {
+                    stats.errors += 1;
+                    warn!("Detector {} failed on {:?}: {:#}", stats.name, tx.hash, e);
+                    Vec::new()
+                }
+                Err(_) => {
+                    stats.timeouts += 1;
+                    warn!("Detector {} timed out on {:?}", stats.name, tx.hash);
+                    Vec::new()
+                }
+            }
+        });
+
+        let mut detections: Vec<Detection> = join_all(runs).await.into_iter().flatten().collect();
+        detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        detections
+    }
+
+    /// Counters of every registered detector, in registration order
+    pub fn stats(&self) -> Vec<DetectorStats> {
+        self.entries.iter().map(|entry| entry.stats.lock().unwrap().clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flags calldata starting with its byte, after waiting `delay_ms`
+    struct ByteDetector {
+        name: &'static str,
+        byte: u8,
+        delay_ms: u64,
+    }
+
+    impl ThreatDetector for ByteDetector {
+        type Features = u8;
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn category(&self) -> Option<ThreatCategory> {
+            Some(ThreatCategory::Spam)
+        }
+
+        async fn featurize(&self, tx: &TxContext) -> Result<Option<u8>> {
+            tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            let first = *tx.data.first().ok_or_else(|| anyhow::anyhow!("no calldata"))?;
+            Ok((first == self.byte).then_some(first))
+        }
+
+        fn score(&self, tx: &TxContext, _features: &u8) -> Vec<Detection> {
+            vec![Detection {
+                detector: self.name.to_string(),
+                threat_type: ThreatCategory::Spam,
+                confidence: 0.5,
+                tx_hash: tx.hash,
+                target: tx.to,
+                url: None,
+                explanation: format!("Calldata starts with {:#04x}", self.byte),
+                evidence: None,
+            }]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detectors_run_concurrently_and_are_counted() {
+        let mut registry = DetectorRegistry::new(RegistryConfig {
+            disabled: vec!["disabled".to_string()],
+            timeout_ms: 200,
+        });
+        registry.register(ByteDetector { name: "first", byte: 1, delay_ms: 100 });
+        registry.register(Arc::new(ByteDetector { name: "second", byte: 1, delay_ms: 100 }));
+        registry.register(ByteDetector { name: "slow", byte: 1, delay_ms: 1_000 });
+        registry.register(ByteDetector { name: "disabled", byte: 1, delay_ms: 0 });
+
+        let tx = TxContext { data: vec![1, 2, 3].into(), ..TxContext::default() };
+        let start = Instant::now();
+        let detections = registry.run(&tx).await;
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert_eq!(detections.iter().map(|d| d.detector.as_str()).collect::<Vec<_>>(), ["first", "second"]);
+
+        assert!(registry.set_enabled("second", false) && !registry.set_enabled("missing", true));
+        assert!(registry.run(&TxContext::default()).await.is_empty());
+
+        let stats = registry.stats();
+        let counts: Vec<_> = stats.iter().map(|s| (s.name.as_str(), s.runs, s.detections, s.errors, s.timeouts)).collect();
+        assert_eq!(counts, [("first", 2, 1, 1, 0), ("second", 1, 1, 0, 0), ("slow", 2, 0, 0, 2), ("disabled", 0, 0, 0, 0)]);
+    }
+}
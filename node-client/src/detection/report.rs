@@ -0,0 +1,301 @@
+/*!
+ * Threat reports
+ * The node's detections in a stable, versioned format for security operations tooling. A report
+ * carries its on-chain threat id and the hash of its backing evidence, every
+ * detector output behind it, references to the proofs attesting it (the oracle report and its
+ * verdict, ZK threat proofs) and the chain context it was seen in. The JSON Schema is generated
+ * from these types, so it can't drift from what the exporters write: JSON lines, one report per
+ * line, or a SARIF 2.1.0 log with one result per report. Bump `SCHEMA_VERSION` on any change to
+ * the format; additions are minor, anything else major
+ */
+
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use ethers::types::{Address, H256};
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeSet, io::Write};
+
+use super::feedback::{DetectionRecord, Verdict};
+use super::scoring::{detector_families, recommended_action, risk_score};
+use super::{Detection, ThreatCategory};
+
+pub const SCHEMA_VERSION: &str = "1.0.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofKind {
+    /// Report on the threat oracle; `id` is its report id
+    OracleReport,
+    /// Groth16 threat proof; `id` is its public input hash
+    ThreatProof,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProofReference {
+    pub kind: ProofKind,
+    #[schemars(with = "String")]
+    pub id: H256,
+    /// Whether the proof was accepted, once known
+    pub verified: Option<bool>,
+}
+
+/// One detector output behind a report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DetectorProvenance {
+    pub detector: String,
+    pub family: String,
+    pub category: ThreatCategory,
+    pub confidence: f64,
+    pub explanation: String,
+    #[schemars(with = "Option<String>")]
+    pub evidence_hash: Option<H256>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ChainContext {
+    pub chain_id: u64,
+    #[schemars(with = "Option<String>")]
+    pub tx_hash: Option<H256>,
+    /// Contract or address the threat concerns
+    #[schemars(with = "Option<String>")]
+    pub target: Option<Address>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ThreatReport {
+    pub schema_version: String,
+    /// Id of the threat on the threat detector contract
+    #[schemars(with = "String")]
+    pub id: H256,
+    pub node_id: String,
+    pub category: ThreatCategory,
+    pub confidence: f64,
+    pub risk_score: u32,
+    pub summary: String,
+    pub recommended_action: String,
+    pub detected_at: DateTime<Utc>,
+    /// Hash of the data backing the finding, like a simulation trace
+    #[schemars(with = "Option<String>")]
+    pub evidence_hash: Option<H256>,
+    pub provenance: Vec<DetectorProvenance>,
+    pub proofs: Vec<ProofReference>,
+    pub chain: ChainContext,
+    /// Operator or dispute verdict, if the detection was labelled
+    pub label: Option<Verdict>,
+}
+
+impl DetectorProvenance {
+    fn of(detection: &Detection) -> Self {
+        Self {
+            detector: detection.detector.clone(),
+            family: detector_families(&detection.detector).join("+"),
+            category: detection.threat_type,
+            confidence: detection.confidence,
+            explanation: detection.explanation.clone(),
+            evidence_hash: detection.evidence,
+        }
+    }
+}
+
+impl ThreatReport {
+    /// Report of a recorded detection seen by `node_id`, on `default_chain_id` if the record
+    /// predates keeping its chain
+    pub fn from_record(record: &DetectionRecord, node_id: &str, default_chain_id: u64) -> Self {
+        let detection = &record.detection;
+        let provenance = if record.contributions.is_empty() {
+            vec![DetectorProvenance::of(detection)]
+        } else {
+            record.contributions.iter().map(DetectorProvenance::of).collect()
+        };
+        let proofs = record
+            .report
+            .iter()
+            .map(|report| ProofReference {
+                kind: ProofKind::OracleReport,
+                id: report.report_id,
+                verified: report.verified,
+            })
+            .collect();
+        let report = Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            id: record.threat_hash,
+            node_id: node_id.to_string(),
+            category: detection.threat_type,
+            confidence: detection.confidence,
+            risk_score: risk_score(detection.confidence),
+            summary: detection.explanation.clone(),
+            recommended_action: recommended_action(detection.confidence).to_string(),
+            detected_at: Utc.timestamp_opt(record.detected_at, 0).single().unwrap_or_default(),
+            evidence_hash: detection.evidence,
+            provenance,
+            proofs,
+            chain: ChainContext {
+                chain_id: record.chain_id.unwrap_or(default_chain_id),
+                tx_hash: detection.tx_hash,
+                target: detection.target,
+                url: detection.url.clone(),
+            },
+            label: record.label.as_ref().map(|label| label.verdict),
+        };
+        // The contract checks the proof on submission, so a recorded one was accepted
+        match record.threat_proof {
+            Some(id) => report.with_proof(ProofReference { kind: ProofKind::ThreatProof, id, verified: Some(true) }),
+            None => report,
+        }
+    }
+
+    /// Attach a proof attesting the report
+    pub fn with_proof(mut self, proof: ProofReference) -> Self {
+        self.proofs.push(proof);
+        self
+    }
+
+    /// SARIF severity: `error` where the node would block, `warning` where it would flag
+    fn sarif_level(&self) -> &'static str {
+        if self.confidence > 0.8 {
+            "error"
+        } else if self.confidence > 0.5 {
+            "warning"
+        } else {
+            "note"
+        }
+    }
+}
+
+/// JSON Schema of `ThreatReport`
+pub fn schema() -> RootSchema {
+    let mut schema = schema_for!(ThreatReport);
+    let metadata = schema.schema.metadata();
+    metadata.id = Some(format!("https://dagshield.io/schemas/threat-report/{}.json", SCHEMA_VERSION));
+    metadata.description = Some(format!("DAGShield threat report, schema version {}", SCHEMA_VERSION));
+    schema
+}
+
+/// Write `reports` as JSON lines
+pub fn write_jsonl(reports: &[ThreatReport], mut out: impl Write) -> Result<()> {
+    for report in reports {
+        serde_json::to_writer(&mut out, report)?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// `reports` as a SARIF 2.1.0 log: one rule per category, one result per report, located at the
+/// target address
+pub fn to_sarif(reports: &[ThreatReport]) -> Value {
+    let categories: BTreeSet<ThreatCategory> = reports.iter().map(|report| report.category).collect();
+    let rules: Vec<Value> = categories
+        .iter()
+        .map(|category| {
+            json!({
+                "id": category.as_str(),
+                "name": category.as_str(),
+                "shortDescription": { "text": format!("{} threat", category) },
+            })
+        })
+        .collect();
+    let results: Vec<Value> = reports
+        .iter()
+        .map(|report| {
+            let location = report
+                .chain
+                .target
+                .map(|target| format!("{:?}", target))
+                .or_else(|| report.chain.url.clone())
+                .unwrap_or_default();
+            json!({
+                "ruleId": report.category.as_str(),
+                "ruleIndex": categories.iter().position(|&category| category == report.category),
+                "level": report.sarif_level(),
+                "message": { "text": report.summary },
+                "locations": [{
+                    "logicalLocations": [{ "fullyQualifiedName": location, "kind": "address" }],
+                }],
+                "fingerprints": { "threatHash/v1": format!("{:?}", report.id) },
+                "properties": report,
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "DAGShield",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://dagshield.io",
+                    "properties": { "threatReportSchema": SCHEMA_VERSION },
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::feedback::{Label, LabelSource, OracleReport};
+
+    #[test]
+    fn test_report_and_exports() {
+        let detection = |detector: &str, confidence| Detection {
+            detector: detector.to_string(),
+            threat_type: ThreatCategory::Drainer,
+            confidence,
+            tx_hash: Some(H256::repeat_byte(1)),
+            target: Some(Address::repeat_byte(2)),
+            url: None,
+            explanation: format!("{} says drainer", detector),
+            evidence: Some(H256::repeat_byte(3)),
+        };
+        let record = DetectionRecord {
+            threat_hash: H256::repeat_byte(4),
+            transaction_id: "tx-4".to_string(),
+            chain_id: Some(2484),
+            threat_proof: Some(H256::repeat_byte(6)),
+            detection: detection("score:model+rules", 0.9),
+            contributions: vec![detection("model", 0.8), detection("rules:approval_drain", 0.95)],
+            detected_at: 1_700_000_000,
+            label: Some(Label { verdict: Verdict::Confirmed, source: LabelSource::Manual, note: None, labeled_at: 0 }),
+            report: Some(OracleReport { report_id: H256::repeat_byte(5), reported_at: 0, verified: Some(true) }),
+        };
+
+        let report = ThreatReport::from_record(&record, "node-1", 39);
+        assert_eq!(report.provenance.iter().map(|p| p.family.as_str()).collect::<Vec<_>>(), ["model", "rules"]);
+        assert_eq!(
+            report.proofs,
+            [
+                ProofReference { kind: ProofKind::OracleReport, id: H256::repeat_byte(5), verified: Some(true) },
+                ProofReference { kind: ProofKind::ThreatProof, id: H256::repeat_byte(6), verified: Some(true) },
+            ]
+        );
+        assert_eq!((report.chain.chain_id, report.risk_score, report.label), (2484, 90, Some(Verdict::Confirmed)));
+        // Records from before the chain was kept fall back to the configured one
+        let legacy = DetectionRecord { chain_id: None, ..record.clone() };
+        assert_eq!(ThreatReport::from_record(&legacy, "node-1", 39).chain.chain_id, 39);
+
+        let mut jsonl = Vec::new();
+        write_jsonl(&[report.clone(), report.clone()], &mut jsonl).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&jsonl).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(serde_json::from_str::<ThreatReport>(lines[0]).unwrap(), report);
+
+        let sarif = to_sarif(&[report]);
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!((result["ruleId"].as_str(), result["level"].as_str()), (Some("drainer"), Some("error")));
+        assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"][0]["id"], "drainer");
+
+        // Every report field is in the published schema
+        let schema = serde_json::to_value(schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for field in ["schema_version", "id", "provenance", "proofs", "chain", "evidence_hash"] {
+            assert!(properties.contains_key(field), "{} missing from schema", field);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+/*!
+ * Address reputation
+ * A local profile of every address seen on chain: when it first appeared, who funded it, how often
+ * the node has flagged it and which label lists name it. Profiles are persisted in sled and kept
+ * current block by block once blocks are `confirmations` deep. `reputation` folds a profile into
+ * one risk, so any detector can weigh who it is looking at: a fresh address funded by a flagged
+ * one is suspicious before it has done anything, an exchange hot wallet isn't. As a detector
+ * itself it reports transactions to addresses risky enough on reputation alone
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+const SYNCED_BLOCK_KEY: &[u8] = b"synced_block";
+/// Age past which an address no longer counts as fresh
+const MATURE_AGE_SECS: f64 = 30.0 * 86_400.0;
+
+/// Named address list, one address per line, `#` starting a comment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelListConfig {
+    pub name: String,
+    pub path: PathBuf,
+    /// Risk the label adds; negative for trusted labels like exchanges
+    pub risk: f64,
+    /// Category addresses on the list are reported under
+    #[serde(default)]
+    pub category: Option<ThreatCategory>,
+}
+
+/// Reputation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// Block a fresh store starts syncing from; unset, it starts at the confirmed head
+    #[serde(default)]
+    pub start_block: Option<u64>,
+    pub confirmations: u64,
+    /// Most blocks folded in per poll, so catching up doesn't starve the RPC
+    pub max_blocks_per_poll: u64,
+    pub poll_interval_secs: u64,
+    pub labels: Vec<LabelListConfig>,
+    /// Risk at which a transaction's recipient is reported
+    pub report_risk: f64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./data/reputation".to_string(),
+            start_block: None,
+            confirmations: 12,
+            max_blocks_per_poll: 100,
+            poll_interval_secs: 15,
+            labels: Vec::new(),
+            report_risk: 0.8,
+        }
+    }
+}
+
+/// What the node knows about an address
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressProfile {
+    pub first_seen_block: u64,
+    pub first_seen_at: u64,
+    /// Sender of the first value the address received
+    pub funded_by: Option<Address>,
+    pub transactions: u64,
+    /// Times the node reported the address, per category
+    pub flags: BTreeMap<ThreatCategory, u64>,
+    pub last_flagged_at: Option<u64>,
+}
+
+/// An address's standing, 0 risk being fully trusted
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reputation {
+    pub address: Address,
+    pub risk: f64,
+    pub age_secs: Option<u64>,
+    pub funded_by: Option<Address>,
+    pub flags: u64,
+    pub labels: Vec<String>,
+    /// Category the address is most associated with, if any
+    pub category: Option<ThreatCategory>,
+    pub reasons: Vec<String>,
+}
+
+/// Persistent address profiles
+pub struct ReputationStore {
+    config: ReputationConfig,
+    tree: sled::Tree,
+    /// Indices of the label lists naming each address
+    labels: RwLock<HashMap<Address, Vec<usize>>>,
+}
+
+impl ReputationStore {
+    /// Open (or create) the store and load the label lists
+    pub fn open(config: ReputationConfig) -> Result<Self> {
+        let db = sled::open(&config.path).with_context(|| format!("Failed to open reputation store at {}", config.path))?;
+        let store = Self {
+            tree: db.open_tree("address_reputation")?,
+            labels: RwLock::new(HashMap::new()),
+            config,
+        };
+        store.load_labels()?;
+        match store.synced_block()? {
+            Some(block) => info!("🪪 Reputation store holds {} addresses (synced to block {})", store.len(), block),
+            None => info!("🪪 Reputation store is empty, syncing from block {}", store.config.start_block.map_or("head".to_string(), |block| block.to_string())),
+        }
+        Ok(store)
+    }
+
+    /// Re-read every label list; on error the current labels stay
+    pub fn load_labels(&self) -> Result<()> {
+        let mut labels: HashMap<Address, Vec<usize>> = HashMap::new();
+        for (index, list) in self.config.labels.iter().enumerate() {
+            let contents = std::fs::read_to_string(&list.path)
+                .with_context(|| format!("Failed to read label list {}", list.path.display()))?;
+            for line in contents.lines().map(|line| line.split('#').next().unwrap_or_default().trim()) {
+                if line.is_empty() {
+                    continue;
+                }
+                let address: Address = line
+                    .parse()
+                    .with_context(|| format!("Bad address {:?} in label list {}", line, list.name))?;
+                labels.entry(address).or_default().push(index);
+            }
+        }
+        *self.labels.write().unwrap() = labels;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.scan_prefix(b"addr:").count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn key(address: &Address) -> Vec<u8> {
+        [b"addr:".as_slice(), address.as_bytes()].concat()
+    }
+
+    pub fn profile(&self, address: &Address) -> Result<Option<AddressProfile>> {
+        self.tree
+            .get(Self::key(address))?
+            .map(|value| serde_json::from_slice(&value).context("Corrupt address profile"))
+            .transpose()
+    }
+
+    /// Last block folded in, `None` before the first sync
+    pub fn synced_block(&self) -> Result<Option<u64>> {
+        self.tree
+            .get(SYNCED_BLOCK_KEY)?
+            .map(|value| Ok(u64::from_be_bytes(value.as_ref().try_into().context("Corrupt synced block")?)))
+            .transpose()
+    }
+
+    /// Fold the (from, to, value) transfers of block `number` into the profiles
+    pub fn ingest_block(&self, number: u64, timestamp: u64, transactions: &[(Address, Option<Address>, U256)]) -> Result<()> {
+        let mut profiles: HashMap<Address, AddressProfile> = HashMap::new();
+        for &(from, to, value) in transactions {
+            for address in std::iter::once(from).chain(to) {
+                if !profiles.contains_key(&address) {
+                    // Flagged before it was seen on chain, its profile has no first sighting yet
+                    let mut profile = self.profile(&address)?.unwrap_or_default();
+                    if profile.first_seen_at == 0 {
+                        (profile.first_seen_block, profile.first_seen_at) = (number, timestamp);
+                    }
+                    profiles.insert(address, profile);
+                }
+            }
+            profiles.get_mut(&from).unwrap().transactions += 1;
+            if let Some(to) = to.filter(|&to| to != from && !value.is_zero()) {
+                let recipient = profiles.get_mut(&to).unwrap();
+                recipient.funded_by.get_or_insert(from);
+            }
+        }
+
+        let mut batch = sled::Batch::default();
+        for (address, profile) in &profiles {
+            batch.insert(Self::key(address), serde_json::to_vec(profile)?);
+        }
+        batch.insert(SYNCED_BLOCK_KEY, &number.to_be_bytes());
+        self.tree.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Count a report of `address` as `category`; atomic, so concurrent reports all count
+    pub fn flag(&self, address: Address, category: ThreatCategory) -> Result<()> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut error = None;
+        self.tree.update_and_fetch(Self::key(&address), |value| {
+            let mut profile: AddressProfile = match value.map(serde_json::from_slice).transpose() {
+                Ok(profile) => profile.unwrap_or_default(),
+                Err(e) => {
+                    error = Some(e);
+                    return value.map(<[u8]>::to_vec);
+                }
+            };
+            *profile.flags.entry(category).or_default() += 1;
+            profile.last_flagged_at = Some(now);
+            Some(serde_json::to_vec(&profile).expect("profiles serialize"))
+        })?;
+        match error {
+            Some(e) => Err(e).context("Corrupt address profile"),
+            None => Ok(()),
+        }
+    }
+
+    /// Standing of `address`: fresh addresses, ones funded by flagged addresses, flagged ones and
+    /// ones on risky label lists score higher, trusted labels lower
+    pub fn reputation(&self, address: Address) -> Result<Reputation> {
+        let profile = self.profile(&address)?;
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut reasons = Vec::new();
+
+        let age_secs = profile.as_ref().filter(|profile| profile.first_seen_at > 0).map(|profile| now.saturating_sub(profile.first_seen_at));
+        let age_risk = match age_secs {
+            Some(age) => (1.0 - age as f64 / MATURE_AGE_SECS).max(0.0),
+            None => 0.5,
+        };
+        if age_risk > 0.5 {
+            reasons.push(format!("fresh address ({} days old)", age_secs.unwrap_or_default() / 86_400));
+        }
+
+        let flags: u64 = profile.iter().flat_map(|profile| profile.flags.values()).sum();
+        let flag_risk = 1.0 - 0.5f64.powi(flags.min(64) as i32);
+        if flags > 0 {
+            reasons.push(format!("flagged {} times", flags));
+        }
+
+        let funded_by = profile.as_ref().and_then(|profile| profile.funded_by);
+        let funder_flags: u64 = match funded_by {
+            Some(funder) => self.profile(&funder)?.map(|funder| funder.flags.values().sum()).unwrap_or_default(),
+            None => 0,
+        };
+        let funder_risk = 1.0 - 0.5f64.powi(funder_flags.min(64) as i32);
+        if let Some(funder) = funded_by.filter(|_| funder_flags > 0) {
+            reasons.push(format!("funded by {:?}, flagged {} times", funder, funder_flags));
+        }
+
+        let lists = self.labels.read().unwrap().get(&address).cloned().unwrap_or_default();
+        let lists: Vec<&LabelListConfig> = lists.iter().map(|&index| &self.config.labels[index]).collect();
+        let label_risk: f64 = lists.iter().map(|list| list.risk).sum();
+        if !lists.is_empty() {
+            reasons.push(format!("listed as {}", lists.iter().map(|list| list.name.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+
+        let risk = (0.15 * age_risk + 0.25 * funder_risk + 0.6 * flag_risk + label_risk).clamp(0.0, 1.0);
+        let category = profile
+            .iter()
+            .flat_map(|profile| profile.flags.iter())
+            .max_by_key(|(_, count)| **count)
+            .map(|(&category, _)| category)
+            .or_else(|| lists.iter().filter(|list| list.risk > 0.0).find_map(|list| list.category));
+        Ok(Reputation {
+            address,
+            risk,
+            age_secs,
+            funded_by,
+            flags,
+            labels: lists.iter().map(|list| list.name.clone()).collect(),
+            category,
+            reasons,
+        })
+    }
+
+    /// Fold in confirmed blocks up to `max_blocks_per_poll`; returns how many were. A fresh store
+    /// without a `start_block` starts at the confirmed head rather than replaying the chain
+    pub async fn sync<M: Middleware + 'static>(&self, provider: Arc<M>) -> Result<u64> {
+        let head = provider.get_block_number().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+        let confirmed = head.saturating_sub(self.config.confirmations);
+        let from = match self.synced_block()? {
+            Some(block) => block + 1,
+            None => self.config.start_block.unwrap_or(confirmed),
+        };
+        let to = confirmed.min(from + self.config.max_blocks_per_poll.max(1) - 1);
+        for number in from..=to {
+            let block = provider
+                .get_block_with_txs(number)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .with_context(|| format!("Failed to fetch block {}", number))?
+                .with_context(|| format!("Block {} not found", number))?;
+            let transfers: Vec<_> = block.transactions.iter().map(|tx| (tx.from, tx.to, tx.value)).collect();
+            self.ingest_block(number, block.timestamp.as_u64(), &transfers)?;
+        }
+        Ok((from..=to).count() as u64)
+    }
+
+    /// Keep syncing every poll interval
+    pub async fn watch<M: Middleware + 'static>(self: Arc<Self>, provider: Arc<M>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            match self.sync(provider.clone()).await {
+                Ok(0) => {}
+                Ok(blocks) => debug!("Address reputation synced {} blocks", blocks),
+                Err(e) => warn!("Address reputation sync failed: {:#}", e),
+            }
+        }
+    }
+}
+
+impl ThreatDetector for ReputationStore {
+    type Features = Reputation;
+
+    fn name(&self) -> &str {
+        "reputation"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<Reputation>> {
+        let Some(to) = tx.to else { return Ok(None) };
+        Ok(Some(self.reputation(to)?))
+    }
+
+    fn score(&self, tx: &TxContext, reputation: &Reputation) -> Vec<Detection> {
+        let Some(category) = reputation.category.filter(|_| reputation.risk >= self.config.report_risk) else {
+            return Vec::new();
+        };
+        vec![Detection {
+            detector: "reputation".to_string(),
+            threat_type: category,
+            confidence: reputation.risk,
+            tx_hash: tx.hash,
+            target: Some(reputation.address),
+            url: None,
+            explanation: format!("Recipient has a bad reputation: {}", reputation.reasons.join(", ")),
+            evidence: None,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_and_scores() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("exchanges.txt");
+        let (exchange, scammer, mule, fresh) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(4));
+        std::fs::write(&list, format!("# hot wallets\n{:?}\n", exchange)).unwrap();
+        let config = ReputationConfig {
+            enabled: true,
+            path: dir.path().join("db").to_string_lossy().into_owned(),
+            labels: vec![LabelListConfig { name: "exchange".to_string(), path: list, risk: -0.5, category: None }],
+            ..ReputationConfig::default()
+        };
+
+        let store = ReputationStore::open(config.clone()).unwrap();
+        let old = chrono::Utc::now().timestamp() as u64 - 365 * 86_400;
+        store.ingest_block(1, old, &[(exchange, Some(scammer), U256::from(10)), (scammer, Some(exchange), U256::zero())]).unwrap();
+        store.ingest_block(2, old + 12, &[(scammer, Some(mule), U256::from(5)), (exchange, Some(mule), U256::from(1))]).unwrap();
+        assert_eq!(store.profile(&scammer).unwrap().unwrap().transactions, 2);
+        assert_eq!(store.profile(&mule).unwrap().unwrap().funded_by, Some(scammer));
+
+        store.flag(scammer, ThreatCategory::Drainer).unwrap();
+        store.flag(scammer, ThreatCategory::Drainer).unwrap();
+        let reputation = store.reputation(scammer).unwrap();
+        assert!((reputation.risk - 0.6 * 0.75).abs() < 1e-9);
+        assert_eq!((reputation.flags, reputation.category), (2, Some(ThreatCategory::Drainer)));
+
+        // Old and trusted scores nothing, funding from a flagged address counts against
+        assert_eq!(store.reputation(exchange).unwrap().risk, 0.0);
+        assert!((store.reputation(mule).unwrap().risk - 0.25 * 0.75).abs() < 1e-9);
+        assert!(store.reputation(fresh).unwrap().reasons.is_empty());
+
+        drop(store);
+        let store = ReputationStore::open(config).unwrap();
+        assert_eq!((store.len(), store.synced_block().unwrap()), (3, Some(2)));
+        let tx = TxContext { to: Some(scammer), ..TxContext::default() };
+        let mut reputation = store.reputation(scammer).unwrap();
+        assert!(store.score(&tx, &reputation).is_empty());
+        reputation.risk = 0.9;
+        assert_eq!(store.score(&tx, &reputation)[0].threat_type, ThreatCategory::Drainer);
+    }
+}
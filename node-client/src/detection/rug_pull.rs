@@ -0,0 +1,681 @@
+/*!
+ * Rug-pull early warning
+ * Rugs are set up before they're pulled. A token launching with its liquidity unlocked, an owner
+ * who can still mint, trading behind a switch only the owner flips, or most of the supply in a
+ * handful of wallets can all be read off the chain while buyers are still getting in. New tokens
+ * are assessed when they're deployed or get their first liquidity, and again on buys while they
+ * are on the watchlist; liquidity added to an established token's pair doesn't launch it. Each
+ * precursor found adds to one risk, graded into advisory, warning and critical, and reported as a
+ * rug pull detection on the transaction that would walk into it. Reads are made concurrently to
+ * fit the registry's detector timeout, and a balance that can't be read is left out
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{decode, encode, ParamType, Token},
+    providers::Middleware,
+    types::{Address, BlockId, BlockNumber, Bytes, Filter, TransactionRequest, H256, U256},
+    utils::{id, keccak256},
+};
+use futures::{future::join_all, stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+use tracing::debug;
+
+use super::bytecode::{BytecodeReport, Pattern};
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+/// Where burned LP tokens and renounced ownership end up: zero and `0x…dEaD`
+const BURN_ADDRESSES: [Address; 2] = [
+    Address([0; 20]),
+    Address([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xde, 0xad]),
+];
+/// Views launch-gated tokens expose their trading switch through
+const TRADING_FLAGS: &[&str] = &["tradingOpen()", "tradingEnabled()", "tradingActive()", "tradingOpened()"];
+/// Functions an owner can stop trading with
+const TRADING_SWITCHES: &[&str] = &[
+    "setTradingEnabled(bool)",
+    "setTrading(bool)",
+    "enableTrading(bool)",
+    "pause()",
+    "setMaxTxAmount(uint256)",
+];
+/// Holder balances read at once
+const CONCURRENT_CALLS: usize = 16;
+/// Blocks back the block time is estimated over
+const BLOCK_TIME_SAMPLE: u64 = 1_000;
+
+/// Rug-pull early warning settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RugPullConfig {
+    pub enabled: bool,
+    /// Uniswap V2 compatible router new tokens launch on
+    pub router: Address,
+    pub factory: Address,
+    /// Wrapped native token the router pairs with
+    pub weth: Address,
+    /// Liquidity lockers; LP tokens they hold count as locked
+    pub lockers: Vec<Address>,
+    /// Share of LP tokens locked or burned below which liquidity counts as pullable
+    pub min_locked_liquidity: f64,
+    /// Share of supply the largest holders may have before it counts as concentrated
+    pub max_top_holders_share: f64,
+    pub top_holders: usize,
+    /// Blocks of transfers scanned for holders
+    pub holder_scan_blocks: u64,
+    /// Most holders whose balances are read per token
+    pub max_holders: usize,
+    /// How long a launched token stays on the watchlist
+    pub watch_secs: u64,
+    /// How long an assessment is reused
+    pub recheck_secs: u64,
+    /// Risk at which a warning is reported
+    pub report_risk: f64,
+}
+
+impl Default for RugPullConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            router: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D".parse().unwrap(),
+            factory: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f".parse().unwrap(),
+            weth: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap(),
+            lockers: [
+                "0x663A5C229c09b049E36dCc11a9B0d4a8Eb9db214", // Unicrypt
+                "0xE2fE530C047f2d85298b07D9333C05737f1435fB", // Team Finance
+                "0x71B5759d73262FBb223956913ecF4ecC51057641", // PinkLock
+            ]
+            .into_iter()
+            .map(|address| address.parse().unwrap())
+            .collect(),
+            min_locked_liquidity: 0.8,
+            max_top_holders_share: 0.5,
+            top_holders: 5,
+            holder_scan_blocks: 5_000,
+            max_holders: 100,
+            watch_secs: 7 * 86_400,
+            recheck_secs: 300,
+            report_risk: 0.4,
+        }
+    }
+}
+
+/// Setup a rug is pulled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precursor {
+    /// Liquidity provider tokens neither locked nor burned, so the pool can be emptied
+    UnlockedLiquidity,
+    /// Owner still holds a mint function to inflate supply and dump it
+    OwnerCanMint,
+    /// Trading flag is off, so only whitelisted wallets trade
+    TradingDisabled,
+    /// Owner can stop trading or choke transfers after launch
+    TradingSwitch,
+    /// A few wallets hold most of the supply
+    ConcentratedHolders,
+}
+
+impl Precursor {
+    fn weight(self) -> f64 {
+        match self {
+            Self::UnlockedLiquidity => 0.5,
+            Self::OwnerCanMint => 0.5,
+            Self::TradingDisabled => 0.35,
+            Self::TradingSwitch => 0.25,
+            Self::ConcentratedHolders => 0.3,
+        }
+    }
+}
+
+/// How close a token looks to being rugged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grade {
+    Advisory,
+    Warning,
+    Critical,
+}
+
+impl Grade {
+    fn of(risk: f64) -> Option<Self> {
+        if risk >= 0.8 {
+            Some(Self::Critical)
+        } else if risk >= 0.6 {
+            Some(Self::Warning)
+        } else if risk >= 0.4 {
+            Some(Self::Advisory)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Advisory => "advisory",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// What was read off the chain about a token
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenProbe {
+    pub code: BytecodeReport,
+    /// `None` when the token has no `owner()` or it was renounced
+    pub owner: Option<Address>,
+    /// Value of the first trading flag the token exposes
+    pub trading_enabled: Option<bool>,
+    /// Share of the pair's LP tokens locked or burned; `None` without a pair
+    pub locked_liquidity: Option<f64>,
+    /// Supply shares of the holders found, excluding the pool, lockers and burn addresses
+    pub holder_shares: Vec<f64>,
+}
+
+/// Precursors found on a token and the risk they add up to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RugAssessment {
+    pub token: Address,
+    pub precursors: BTreeSet<Precursor>,
+    pub reasons: Vec<String>,
+    pub risk: f64,
+    pub grade: Option<Grade>,
+    /// Hash of the token's code
+    pub code_hash: H256,
+}
+
+impl RugAssessment {
+    pub fn assess(config: &RugPullConfig, token: Address, probe: &TokenProbe) -> Self {
+        let has_function = |signature: &str| probe.code.selectors.contains(&format!("0x{}", hex::encode(id(signature))));
+        let mut signals: Vec<(Precursor, f64, String)> = Vec::new();
+
+        if let Some(locked) = probe.locked_liquidity.filter(|&locked| locked < config.min_locked_liquidity) {
+            let precursor = Precursor::UnlockedLiquidity;
+            signals.push((precursor, precursor.weight(), format!("only {:.0}% of liquidity locked or burned", locked * 100.0)));
+        }
+        if let Some(owner) = probe.owner {
+            if probe.code.patterns.contains(&Pattern::HiddenMint) {
+                let precursor = Precursor::OwnerCanMint;
+                signals.push((precursor, precursor.weight(), format!("owner {:?} can mint", owner)));
+            }
+            if TRADING_SWITCHES.iter().any(|signature| has_function(signature)) || probe.code.patterns.contains(&Pattern::BlacklistOnSell) {
+                let precursor = Precursor::TradingSwitch;
+                signals.push((precursor, precursor.weight(), "owner can stop trading".to_string()));
+            }
+        }
+        if probe.trading_enabled == Some(false) {
+            let precursor = Precursor::TradingDisabled;
+            signals.push((precursor, precursor.weight(), "trading is disabled".to_string()));
+        }
+
+        let mut shares = probe.holder_shares.clone();
+        shares.sort_by(|a, b| b.total_cmp(a));
+        let top_share: f64 = shares.iter().take(config.top_holders).sum();
+        if top_share > config.max_top_holders_share {
+            // Grows with how far past the limit the top holders are
+            let excess = (top_share - config.max_top_holders_share) / (1.0 - config.max_top_holders_share).max(f64::EPSILON);
+            let precursor = Precursor::ConcentratedHolders;
+            let weight = (precursor.weight() + 0.4 * excess).min(0.7);
+            signals.push((precursor, weight, format!("top {} holders own {:.0}% of supply", config.top_holders, top_share * 100.0)));
+        }
+
+        let risk = 1.0 - signals.iter().map(|(_, weight, _)| 1.0 - weight).product::<f64>();
+        Self {
+            token,
+            precursors: signals.iter().map(|(precursor, _, _)| *precursor).collect(),
+            reasons: signals.into_iter().map(|(_, _, reason)| reason).collect(),
+            risk,
+            grade: Grade::of(risk),
+            code_hash: probe.code.code_hash,
+        }
+    }
+
+    /// Warning on `tx` if the token's risk reaches `report_risk`
+    pub fn detection(&self, tx: &TxContext, report_risk: f64) -> Option<Detection> {
+        let grade = self.grade.filter(|_| self.risk >= report_risk)?;
+        Some(Detection {
+            detector: format!("rug_pull:{}", grade.as_str()),
+            threat_type: ThreatCategory::RugPull,
+            confidence: self.risk,
+            tx_hash: tx.hash,
+            target: Some(self.token),
+            url: None,
+            explanation: format!("Rug-pull {} for {:?}: {}", grade.as_str(), self.token, self.reasons.join(", ")),
+            evidence: Some(self.code_hash),
+        })
+    }
+}
+
+/// Why a transaction's tokens are assessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Deployment, pair creation or liquidity added; a launch, putting the token on the
+    /// watchlist, only if the pair had no liquidity before or the token is new
+    Launch,
+    /// Buy through the router, assessed only for watched tokens
+    Buy,
+}
+
+/// Tokens `tx` launches or buys through the configured router and factory
+pub fn traded_tokens(config: &RugPullConfig, tx: &TxContext) -> Vec<(Address, Trigger)> {
+    let (Some(to), Some(selector), Some(args)) = (tx.to, tx.selector(), tx.data.get(4..)) else {
+        return Vec::new();
+    };
+    let not_weth = |tokens: Vec<Address>| tokens.into_iter().filter(|&token| token != config.weth).collect::<Vec<_>>();
+
+    let (tokens, trigger) = if to == config.factory && selector == id("createPair(address,address)") {
+        let Ok(tokens) = decode(&[ParamType::Address, ParamType::Address], args) else { return Vec::new() };
+        (not_weth(tokens.into_iter().filter_map(Token::into_address).collect()), Trigger::Launch)
+    } else if to != config.router {
+        return Vec::new();
+    } else if selector == id("addLiquidityETH(address,uint256,uint256,uint256,address,uint256)") {
+        let Ok(tokens) = decode(&[ParamType::Address], &args[..args.len().min(32)]) else { return Vec::new() };
+        (tokens.into_iter().filter_map(Token::into_address).collect(), Trigger::Launch)
+    } else if selector == id("addLiquidity(address,address,uint256,uint256,uint256,uint256,address,uint256)") {
+        let Ok(tokens) = decode(&[ParamType::Address, ParamType::Address], &args[..args.len().min(64)]) else { return Vec::new() };
+        (not_weth(tokens.into_iter().filter_map(Token::into_address).collect()), Trigger::Launch)
+    } else if [
+        "swapExactETHForTokens(uint256,address[],address,uint256)",
+        "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+        "swapETHForExactTokens(uint256,address[],address,uint256)",
+    ]
+    .iter()
+    .any(|signature| selector == id(signature))
+    {
+        let params = [ParamType::Uint(256), ParamType::Array(Box::new(ParamType::Address)), ParamType::Address, ParamType::Uint(256)];
+        let Ok(tokens) = decode(&params, args) else { return Vec::new() };
+        let Some(Token::Array(path)) = tokens.into_iter().nth(1) else { return Vec::new() };
+        (path.into_iter().last().and_then(Token::into_address).into_iter().collect(), Trigger::Buy)
+    } else {
+        return Vec::new();
+    };
+    tokens.into_iter().map(|token| (token, trigger)).collect()
+}
+
+/// Fraction `part` is of `whole`
+fn share(part: U256, whole: U256) -> f64 {
+    if whole.is_zero() {
+        return 0.0;
+    }
+    let as_f64 = |amount: U256| amount.to_string().parse::<f64>().unwrap_or_default();
+    (as_f64(part) / as_f64(whole)).clamp(0.0, 1.0)
+}
+
+/// Watches new tokens for rug precursors, reading them through a provider
+pub struct RugPullMonitor<M> {
+    config: RugPullConfig,
+    provider: Arc<M>,
+    /// Launched tokens and when they launched
+    watched: Mutex<HashMap<Address, u64>>,
+    /// Latest assessment of each token and when it was made
+    assessments: Mutex<HashMap<Address, (u64, RugAssessment)>>,
+}
+
+impl<M: Middleware> RugPullMonitor<M> {
+    pub fn new(config: RugPullConfig, provider: Arc<M>) -> Self {
+        Self {
+            config,
+            provider,
+            watched: Mutex::new(HashMap::new()),
+            assessments: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Assessment of `token`, reused for `recheck_secs`
+    pub async fn assess(&self, token: Address) -> Result<RugAssessment> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        if let Some((assessed_at, assessment)) = self.assessments.lock().unwrap().get(&token) {
+            if now.saturating_sub(*assessed_at) < self.config.recheck_secs {
+                return Ok(assessment.clone());
+            }
+        }
+        let assessment = RugAssessment::assess(&self.config, token, &self.probe(token).await?);
+        debug!("🧯 {:?}: rug risk {:.2} from {:?}", token, assessment.risk, assessment.precursors);
+        self.assessments.lock().unwrap().insert(token, (now, assessment.clone()));
+        Ok(assessment)
+    }
+
+    /// Read what the precursors need about `token`
+    pub async fn probe(&self, token: Address) -> Result<TokenProbe> {
+        let code = self
+            .provider
+            .get_code(token, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("Failed to fetch code of {:?}", token))?;
+        let code = BytecodeReport::analyze(token, &code);
+        let has_function = |signature: &str| code.selectors.contains(&format!("0x{}", hex::encode(id(signature))));
+
+        let owner = async {
+            if !has_function("owner()") {
+                return None;
+            }
+            let owner = self.view_word(token, "owner()", &[]).await.ok().map(|word| Address::from_slice(&word[12..]));
+            owner.filter(|owner| !BURN_ADDRESSES.contains(owner))
+        };
+        let trading_enabled = async {
+            for flag in TRADING_FLAGS.iter().filter(|flag| has_function(flag)) {
+                if let Ok(word) = self.view_word(token, flag, &[]).await {
+                    return Some(word != [0; 32]);
+                }
+            }
+            None
+        };
+        let (owner, trading_enabled, pair, holders) = futures::join!(owner, trading_enabled, self.pair(token, None), self.holders(token));
+
+        let excluded: HashSet<Address> = pair
+            .into_iter()
+            .chain(self.config.lockers.iter().copied())
+            .chain(BURN_ADDRESSES)
+            .chain([token])
+            .collect();
+        let holder_shares = async {
+            let holders = match holders {
+                Ok(holders) => holders.into_iter().filter(|holder| !excluded.contains(holder)).collect::<Vec<_>>(),
+                Err(e) => {
+                    debug!("Couldn't list holders of {:?}: {:#}", token, e);
+                    return Vec::new();
+                }
+            };
+            let (supply, balances) = futures::join!(
+                self.total_supply(token),
+                stream::iter(holders).map(|holder| self.balance_of(token, holder)).buffered(CONCURRENT_CALLS).collect::<Vec<_>>()
+            );
+            let supply = match supply {
+                Ok(supply) => supply,
+                Err(e) => {
+                    debug!("Couldn't read the supply of {:?}: {:#}", token, e);
+                    return Vec::new();
+                }
+            };
+            // Holders whose balance can't be read are left out rather than failing the probe
+            balances.into_iter().filter_map(Result::ok).map(|balance| share(balance, supply)).collect()
+        };
+        let locked_liquidity = async {
+            match pair {
+                Some(pair) => self.locked_liquidity(pair).await,
+                None => None,
+            }
+        };
+        let (locked_liquidity, holder_shares) = futures::join!(locked_liquidity, holder_shares);
+
+        Ok(TokenProbe {
+            code,
+            owner,
+            trading_enabled,
+            locked_liquidity,
+            holder_shares,
+        })
+    }
+
+    /// `token`'s pair with the wrapped native token as of `block`, if it has one
+    async fn pair(&self, token: Address, block: Option<BlockId>) -> Option<Address> {
+        let args = [Token::Address(token), Token::Address(self.config.weth)];
+        self.view_word_at(self.config.factory, "getPair(address,address)", &args, block)
+            .await
+            .map(|word| Address::from_slice(&word[12..]))
+            .ok()
+            .filter(|pair| !pair.is_zero())
+    }
+
+    /// Share of `pair`'s LP tokens locked or burned; `None` if any of it can't be read, since
+    /// counting an unread locker as empty would call locked liquidity pullable
+    async fn locked_liquidity(&self, pair: Address) -> Option<f64> {
+        let holders: Vec<Address> = self.config.lockers.iter().copied().chain(BURN_ADDRESSES).collect();
+        let (supply, balances) = futures::join!(
+            self.total_supply(pair),
+            join_all(holders.iter().map(|&holder| self.balance_of(pair, holder)))
+        );
+        let locked = balances.into_iter().try_fold(U256::zero(), |locked, balance| balance.map(|balance| locked + balance));
+        match (supply, locked) {
+            (Ok(supply), Ok(locked)) => Some(share(locked, supply)),
+            (Err(e), _) | (_, Err(e)) => {
+                debug!("Couldn't read locked liquidity of {:?}: {:#}", pair, e);
+                None
+            }
+        }
+    }
+
+    /// Whether liquidity added to `token` by `tx` launches it: its pair had no liquidity before
+    /// `tx`, or the token was deployed within `watch_secs`
+    async fn is_launch(&self, token: Address, tx: &TxContext) -> bool {
+        // A mined transaction is judged against the block before it, a pending one against the head
+        let before = match tx.hash {
+            Some(hash) => match self.provider.get_transaction(hash).await {
+                Ok(mined) => mined.and_then(|mined| mined.block_number).map(|block| BlockId::from(block.as_u64().saturating_sub(1))),
+                Err(e) => {
+                    debug!("Couldn't look up {:?}: {}", hash, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let prior_supply = match self.pair(token, before).await {
+            Some(pair) => match self.view_word_at(pair, "totalSupply()", &[], before).await {
+                Ok(word) => U256::from_big_endian(&word),
+                Err(e) => {
+                    debug!("Couldn't read the liquidity of {:?}: {:#}", pair, e);
+                    return false;
+                }
+            },
+            None => U256::zero(),
+        };
+        if prior_supply.is_zero() {
+            return true;
+        }
+        match self.deployed_within(token, self.config.watch_secs).await {
+            Ok(recent) => recent,
+            Err(e) => {
+                debug!("Couldn't tell when {:?} was deployed: {:#}", token, e);
+                false
+            }
+        }
+    }
+
+    /// Whether `token` had no code `secs` ago, at a block estimated from the recent block time
+    async fn deployed_within(&self, token: Address, secs: u64) -> Result<bool> {
+        let block = |number: BlockNumber| async move {
+            self.provider
+                .get_block(number)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?
+                .with_context(|| format!("Block {:?} not found", number))
+        };
+        let head = block(BlockNumber::Latest).await?;
+        let head_number = head.number.context("Head block has no number")?.as_u64();
+        let sample_number = head_number.saturating_sub(BLOCK_TIME_SAMPLE);
+        let sample = block(sample_number.into()).await?;
+        let block_secs = head.timestamp.saturating_sub(sample.timestamp).as_u64() as f64 / (head_number - sample_number).max(1) as f64;
+        let window_start = head_number.saturating_sub((secs as f64 / block_secs.max(0.1)) as u64);
+        let code = self
+            .provider
+            .get_code(token, Some(window_start.into()))
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(code.is_empty())
+    }
+
+    /// Recipients of the token's recent transfers, up to `max_holders`
+    async fn holders(&self, token: Address) -> Result<Vec<Address>> {
+        let head = self.provider.get_block_number().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+        let filter = Filter::new()
+            .address(token)
+            .topic0(H256::from(keccak256("Transfer(address,address,uint256)")))
+            .from_block(head.saturating_sub(self.config.holder_scan_blocks))
+            .to_block(head);
+        let logs = self.provider.get_logs(&filter).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut holders = Vec::new();
+        for log in logs {
+            let Some(to) = log.topics.get(2) else { continue };
+            let holder = Address::from(*to);
+            if !holders.contains(&holder) {
+                holders.push(holder);
+            }
+            if holders.len() >= self.config.max_holders {
+                break;
+            }
+        }
+        Ok(holders)
+    }
+
+    async fn total_supply(&self, token: Address) -> Result<U256> {
+        Ok(U256::from_big_endian(&self.view_word(token, "totalSupply()", &[]).await?))
+    }
+
+    async fn balance_of(&self, token: Address, holder: Address) -> Result<U256> {
+        Ok(U256::from_big_endian(&self.view_word(token, "balanceOf(address)", &[Token::Address(holder)]).await?))
+    }
+
+    /// First word a view returns
+    async fn view_word(&self, to: Address, signature: &str, args: &[Token]) -> Result<[u8; 32]> {
+        self.view_word_at(to, signature, args, None).await
+    }
+
+    /// First word a view returns as of `block`, the head if `None`
+    async fn view_word_at(&self, to: Address, signature: &str, args: &[Token], block: Option<BlockId>) -> Result<[u8; 32]> {
+        let request = TransactionRequest::new().to(to).data(Bytes::from([id(signature).to_vec(), encode(args)].concat()));
+        let output = self
+            .provider
+            .call(&request.into(), block)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("{} failed on {:?}", signature, to))?;
+        Ok(output.get(..32).with_context(|| format!("Malformed {} result", signature))?.try_into().unwrap())
+    }
+
+    /// Tokens of `tx` worth assessing; launches start watching their token
+    async fn tokens(&self, tx: &TxContext) -> Result<Vec<Address>> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut launched = Vec::new();
+        let mut bought = Vec::new();
+        for (token, trigger) in traded_tokens(&self.config, tx) {
+            match trigger {
+                Trigger::Launch => launched.push(token),
+                Trigger::Buy => bought.push(token),
+            }
+        }
+        // Liquidity for an established token isn't a launch; watched tokens already launched
+        let watched: HashSet<Address> = self.watched.lock().unwrap().keys().copied().collect();
+        let watched = &watched;
+        let launches = join_all(launched.iter().map(|&token| async move { watched.contains(&token) || self.is_launch(token, tx).await })).await;
+        let mut launched: Vec<Address> = launched.into_iter().zip(launches).filter_map(|(token, launch)| launch.then_some(token)).collect();
+        if tx.to.is_none() {
+            if let Some(hash) = tx.hash {
+                let receipt = self.provider.get_transaction_receipt(hash).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+                launched.extend(receipt.and_then(|receipt| receipt.contract_address));
+            }
+        }
+
+        let mut watched = self.watched.lock().unwrap();
+        watched.retain(|_, launched_at| now.saturating_sub(*launched_at) < self.config.watch_secs);
+        for token in &launched {
+            watched.entry(*token).or_insert(now);
+        }
+        bought.retain(|token| watched.contains_key(token));
+        Ok(launched.into_iter().chain(bought).collect())
+    }
+}
+
+impl<M: Middleware> ThreatDetector for RugPullMonitor<M> {
+    /// Assessments of the tokens the transaction launches or buys
+    type Features = Vec<RugAssessment>;
+
+    fn name(&self) -> &str {
+        "rug_pull"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        Some(ThreatCategory::RugPull)
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<Vec<RugAssessment>>> {
+        let tokens = self.tokens(tx).await?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let mut assessments = Vec::new();
+        for token in tokens {
+            assessments.push(self.assess(token).await?);
+        }
+        Ok(Some(assessments))
+    }
+
+    fn score(&self, tx: &TxContext, assessments: &Vec<RugAssessment>) -> Vec<Detection> {
+        assessments
+            .iter()
+            .filter_map(|assessment| assessment.detection(tx, self.config.report_risk))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precursors_graded() {
+        let config = RugPullConfig::default();
+        let token = Address::repeat_byte(0x70);
+        // Token dispatcher with a mint and a trading switch
+        let mut code = Vec::new();
+        for signature in ["transfer(address,uint256)", "transferFrom(address,address,uint256)", "balanceOf(address)", "mint(address,uint256)", "setTradingEnabled(bool)", "owner()"] {
+            code.push(0x63);
+            code.extend_from_slice(&id(signature));
+            code.extend_from_slice(&[0x14, 0x60, 0x40, 0x57]);
+        }
+        let probe = TokenProbe {
+            code: BytecodeReport::analyze(token, &code),
+            owner: Some(Address::repeat_byte(0x0e)),
+            trading_enabled: Some(true),
+            locked_liquidity: Some(0.1),
+            holder_shares: vec![0.05, 0.3, 0.02, 0.4],
+        };
+
+        let assessment = RugAssessment::assess(&config, token, &probe);
+        assert_eq!(
+            assessment.precursors,
+            BTreeSet::from([Precursor::UnlockedLiquidity, Precursor::OwnerCanMint, Precursor::TradingSwitch, Precursor::ConcentratedHolders])
+        );
+        assert_eq!(assessment.grade, Some(Grade::Critical));
+        let detection = assessment.detection(&TxContext::default(), config.report_risk).unwrap();
+        assert_eq!((detection.detector.as_str(), detection.threat_type), ("rug_pull:critical", ThreatCategory::RugPull));
+
+        // Renounced, locked and spread out: only the trading flag is left, below advisory
+        let safe = TokenProbe { owner: None, trading_enabled: Some(false), locked_liquidity: Some(0.95), holder_shares: vec![0.1, 0.05], ..probe.clone() };
+        let assessment = RugAssessment::assess(&config, token, &safe);
+        assert_eq!((assessment.precursors.len(), assessment.grade), (1, None));
+        assert!(assessment.detection(&TxContext::default(), config.report_risk).is_none());
+        let unlocked = RugAssessment::assess(&config, token, &TokenProbe { locked_liquidity: Some(0.5), ..safe });
+        assert_eq!(unlocked.grade, Some(Grade::Warning));
+
+        // Liquidity added through the router launches the token; buys are recognized by the path
+        let add_liquidity = TxContext {
+            to: Some(config.router),
+            data: [
+                id("addLiquidityETH(address,uint256,uint256,uint256,address,uint256)").to_vec(),
+                encode(&[Token::Address(token), Token::Uint(1.into()), Token::Uint(0.into()), Token::Uint(0.into()), Token::Address(token), Token::Uint(0.into())]),
+            ]
+            .concat()
+            .into(),
+            ..TxContext::default()
+        };
+        assert_eq!(traded_tokens(&config, &add_liquidity), [(token, Trigger::Launch)]);
+        let buy = TxContext {
+            to: Some(config.router),
+            data: [
+                id("swapExactETHForTokens(uint256,address[],address,uint256)").to_vec(),
+                encode(&[Token::Uint(0.into()), Token::Array(vec![Token::Address(config.weth), Token::Address(token)]), Token::Address(token), Token::Uint(0.into())]),
+            ]
+            .concat()
+            .into(),
+            ..TxContext::default()
+        };
+        assert_eq!(traded_tokens(&config, &buy), [(token, Trigger::Buy)]);
+        assert!(traded_tokens(&config, &TxContext { to: Some(token), ..buy }).is_empty());
+    }
+}
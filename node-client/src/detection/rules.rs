@@ -0,0 +1,429 @@
+/*!
+ * Rule-based detection
+ * Declarative detection rules loaded from a TOML or YAML file. A rule fires when all of its
+ * conditions hold: calldata selector matches, value thresholds, address list membership and
+ * regexes over the hex calldata. Rules are compiled once per load; the file is re-read
+ * periodically and swapped in when it changes, so analysts can ship detections without a restart.
+ * A file that fails to load leaves the previous rules running
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    types::{Address, U256},
+    utils::parse_ether,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+
+/// Rules engine settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulesConfig {
+    pub enabled: bool,
+    /// `.toml`, `.yaml` or `.yml` rules file
+    pub path: PathBuf,
+    /// Seconds between checks of the file for changes; 0 never reloads
+    pub reload_interval_secs: u64,
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: PathBuf::from("./rules/detection.toml"),
+            reload_interval_secs: 30,
+        }
+    }
+}
+
+/// Contents of a rules file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleFile {
+    /// Named address lists conditions refer to
+    #[serde(default)]
+    pub lists: HashMap<String, Vec<Address>>,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+/// One detection: fires when every condition holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub threat_type: ThreatCategory,
+    pub confidence: f64,
+    #[serde(default)]
+    pub description: String,
+    pub when: Vec<Condition>,
+}
+
+/// Which address of the transaction a list condition checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressField {
+    From,
+    To,
+}
+
+/// One rule condition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// Calldata starts with one of these selectors, like `0x095ea7b3`
+    Selector { selectors: Vec<String> },
+    /// Value within the bounds, in ether
+    Value {
+        #[serde(default)]
+        min_eth: Option<f64>,
+        #[serde(default)]
+        max_eth: Option<f64>,
+    },
+    /// Address is (or with `negate`, is not) on a named list
+    AddressList {
+        field: AddressField,
+        list: String,
+        #[serde(default)]
+        negate: bool,
+    },
+    /// Regex over the lowercase hex calldata, without `0x`
+    Calldata { pattern: String },
+}
+
+/// Condition ready to evaluate
+enum Compiled {
+    Selector(HashSet<[u8; 4]>),
+    Value { min: Option<U256>, max: Option<U256> },
+    AddressList { field: AddressField, list: Arc<HashSet<Address>>, negate: bool },
+    Calldata(Regex),
+}
+
+impl Compiled {
+    fn holds(&self, tx: &TxContext, calldata_hex: &str) -> bool {
+        match self {
+            Self::Selector(selectors) => tx.selector().is_some_and(|selector| selectors.contains(&selector)),
+            Self::Value { min, max } => {
+                min.is_none_or(|min| tx.value >= min) && max.is_none_or(|max| tx.value <= max)
+            }
+            Self::AddressList { field, list, negate } => {
+                let address = match field {
+                    AddressField::From => Some(tx.from),
+                    AddressField::To => tx.to,
+                };
+                address.is_some_and(|address| list.contains(&address)) != *negate
+            }
+            Self::Calldata(regex) => regex.is_match(calldata_hex),
+        }
+    }
+}
+
+struct CompiledRule {
+    rule: Rule,
+    conditions: Vec<Compiled>,
+}
+
+/// Rules of one load
+#[derive(Default)]
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+    /// blake3 of the file they were loaded from
+    source_hash: Option<[u8; 32]>,
+}
+
+impl RuleSet {
+    /// Check and compile every rule; fails naming the first bad rule
+    pub fn compile(file: RuleFile) -> Result<Self> {
+        let lists: HashMap<String, Arc<HashSet<Address>>> = file
+            .lists
+            .into_iter()
+            .map(|(name, addresses)| (name, Arc::new(addresses.into_iter().collect())))
+            .collect();
+
+        let mut ids = HashSet::new();
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|rule| {
+                if !ids.insert(rule.id.clone()) {
+                    return Err(anyhow::anyhow!("Rule {} is defined twice", rule.id));
+                }
+                if !(0.0..=1.0).contains(&rule.confidence) {
+                    return Err(anyhow::anyhow!("Rule {} confidence {} is outside 0..=1", rule.id, rule.confidence));
+                }
+                if rule.when.is_empty() {
+                    return Err(anyhow::anyhow!("Rule {} has no conditions", rule.id));
+                }
+                let conditions = rule
+                    .when
+                    .iter()
+                    .map(|condition| compile_condition(condition, &lists))
+                    .collect::<Result<_>>()
+                    .with_context(|| format!("Invalid rule {}", rule.id))?;
+                Ok(CompiledRule { rule, conditions })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { rules, source_hash: None })
+    }
+
+    /// Parse a rules file by its extension
+    pub fn parse(path: &Path, contents: &str) -> Result<Self> {
+        let file: RuleFile = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(contents)?,
+            Some("yaml" | "yml") => serde_yaml::from_str(contents)?,
+            _ => return Err(anyhow::anyhow!("Rules file {} is neither TOML nor YAML", path.display())),
+        };
+        Self::compile(file)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// A detection for every rule `tx` satisfies
+    pub fn evaluate(&self, tx: &TxContext) -> Vec<Detection> {
+        let calldata_hex = hex::encode(&tx.data);
+        self.rules
+            .iter()
+            .filter(|compiled| compiled.conditions.iter().all(|condition| condition.holds(tx, &calldata_hex)))
+            .map(|compiled| Detection {
+                detector: format!("rules:{}", compiled.rule.id),
+                threat_type: compiled.rule.threat_type,
+                confidence: compiled.rule.confidence,
+                tx_hash: tx.hash,
+                target: tx.to,
+                url: None,
+                explanation: match compiled.rule.description.as_str() {
+                    "" => format!("Matched rule {}", compiled.rule.id),
+                    description => description.to_string(),
+                },
+                evidence: None,
+            })
+            .collect()
+    }
+}
+
+fn compile_condition(condition: &Condition, lists: &HashMap<String, Arc<HashSet<Address>>>) -> Result<Compiled> {
+    Ok(match condition {
+        Condition::Selector { selectors } => Compiled::Selector(
+            selectors
+                .iter()
+                .map(|selector| {
+                    let bytes = hex::decode(selector.trim_start_matches("0x"))
+                        .with_context(|| format!("Selector {} is not hex", selector))?;
+                    <[u8; 4]>::try_from(bytes).map_err(|_| anyhow::anyhow!("Selector {} is not 4 bytes", selector))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Condition::Value { min_eth, max_eth } => {
+            let wei = |eth: &Option<f64>| {
+                eth.map(|eth| parse_ether(eth).with_context(|| format!("Invalid ether amount {}", eth)))
+                    .transpose()
+            };
+            Compiled::Value { min: wei(min_eth)?, max: wei(max_eth)? }
+        }
+        Condition::AddressList { field, list, negate } => Compiled::AddressList {
+            field: *field,
+            list: lists.get(list).cloned().with_context(|| format!("Unknown address list {}", list))?,
+            negate: *negate,
+        },
+        Condition::Calldata { pattern } => {
+            Compiled::Calldata(Regex::new(pattern).with_context(|| format!("Invalid calldata regex {}", pattern))?)
+        }
+    })
+}
+
+/// Rules loaded from a file, reloaded when it changes
+pub struct RulesEngine {
+    config: RulesConfig,
+    rules: RwLock<Arc<RuleSet>>,
+}
+
+impl RulesEngine {
+    /// Load the configured rules file
+    pub fn load(config: RulesConfig) -> Result<Self> {
+        let engine = Self {
+            config,
+            rules: RwLock::new(Arc::new(RuleSet::default())),
+        };
+        engine.reload()?;
+        Ok(engine)
+    }
+
+    /// Re-read the rules file and swap in its rules if it changed; returns whether it did.
+    /// On error the current rules stay active
+    pub fn reload(&self) -> Result<bool> {
+        let path = &self.config.path;
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+        let hash = *blake3::hash(contents.as_bytes()).as_bytes();
+        if self.rules.read().unwrap().source_hash == Some(hash) {
+            return Ok(false);
+        }
+
+        let mut rules = RuleSet::parse(path, &contents)
+            .with_context(|| format!("Failed to load rules from {}", path.display()))?;
+        rules.source_hash = Some(hash);
+        info!("📜 Loaded {} detection rules from {}", rules.len(), path.display());
+        *self.rules.write().unwrap() = Arc::new(rules);
+        Ok(true)
+    }
+
+    /// Rules currently in force
+    pub fn rules(&self) -> Arc<RuleSet> {
+        Arc::clone(&self.rules.read().unwrap())
+    }
+
+    pub fn evaluate(&self, tx: &TxContext) -> Vec<Detection> {
+        let detections = self.rules().evaluate(tx);
+        if !detections.is_empty() {
+            debug!("📜 {} detection rules matched transaction {:?}", detections.len(), tx.hash);
+        }
+        detections
+    }
+
+    /// Check the rules file for changes every `reload_interval_secs`, forever
+    pub async fn watch(self: Arc<Self>) {
+        if self.config.reload_interval_secs == 0 {
+            return;
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.reload_interval_secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.reload() {
+                warn!("⚠️ Keeping current detection rules: {:#}", e);
+            }
+        }
+    }
+}
+
+impl ThreatDetector for RulesEngine {
+    /// Rules match the transaction itself
+    type Features = ();
+
+    fn name(&self) -> &str {
+        "rules"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, _tx: &TxContext) -> Result<Option<()>> {
+        Ok(Some(()))
+    }
+
+    fn score(&self, tx: &TxContext, _features: &()) -> Vec<Detection> {
+        self.evaluate(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Bytes;
+
+    const RULES: &str = r#"
+[lists]
+drainers = ["0x00000000000000000000000000000000000000dd"]
+
+[[rule]]
+id = "unlimited-approve-to-drainer"
+threat_type = "phishing"
+confidence = 0.95
+description = "Unlimited approval for a known drainer"
+
+[[rule.when]]
+kind = "selector"
+selectors = ["0x095ea7b3"]
+
+[[rule.when]]
+kind = "calldata"
+pattern = "^095ea7b30{24}0{38}ddf{64}$"
+
+[[rule]]
+id = "large-transfer-to-drainer"
+threat_type = "drainer"
+confidence = 0.6
+
+[[rule.when]]
+kind = "value"
+min_eth = 10.0
+
+[[rule.when]]
+kind = "address_list"
+field = "to"
+list = "drainers"
+"#;
+
+    fn approve(spender: Address) -> TxContext {
+        let mut data = hex::decode("095ea7b3").unwrap();
+        data.extend_from_slice(&[0u8; 12]);
+        data.extend_from_slice(spender.as_bytes());
+        data.extend_from_slice(&[0xff; 32]);
+        TxContext {
+            to: Some(Address::repeat_byte(0x70)),
+            data: Bytes::from(data),
+            ..TxContext::default()
+        }
+    }
+
+    #[test]
+    fn test_rules_fire_and_reload() {
+        let drainer: Address = "0x00000000000000000000000000000000000000dd".parse().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.toml");
+        std::fs::write(&path, RULES).unwrap();
+        let engine = RulesEngine::load(RulesConfig {
+            enabled: true,
+            path: path.clone(),
+            reload_interval_secs: 0,
+        })
+        .unwrap();
+        assert_eq!(engine.rules().len(), 2);
+
+        let detections = engine.evaluate(&approve(drainer));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].detector, "rules:unlimited-approve-to-drainer");
+        assert!(engine.evaluate(&approve(Address::repeat_byte(0x11))).is_empty());
+
+        // Value thresholds and lists combine
+        let transfer = |eth: u64| TxContext {
+            to: Some(drainer),
+            value: parse_ether(eth).unwrap(),
+            ..TxContext::default()
+        };
+        assert_eq!(engine.evaluate(&transfer(20))[0].threat_type, ThreatCategory::Drainer);
+        assert!(engine.evaluate(&transfer(1)).is_empty());
+
+        // A broken edit keeps the old rules; a fixed one is picked up without a restart
+        assert!(!engine.reload().unwrap());
+        std::fs::write(&path, "[[rule]]\nid = \"broken\"").unwrap();
+        assert!(engine.reload().is_err());
+        assert_eq!(engine.rules().len(), 2);
+        let yaml = dir.path().join("rules.yaml");
+        std::fs::write(
+            &yaml,
+            "rule:\n  - id: any-approve\n    threat_type: phishing\n    confidence: 0.5\n    when:\n      - kind: selector\n        selectors: [\"0x095ea7b3\"]\n",
+        )
+        .unwrap();
+        let engine = RulesEngine::load(RulesConfig {
+            path: yaml,
+            ..RulesConfig::default()
+        })
+        .unwrap();
+        assert_eq!(engine.evaluate(&approve(Address::repeat_byte(0x11))).len(), 1);
+    }
+}
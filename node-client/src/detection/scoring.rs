@@ -0,0 +1,181 @@
+/*!
+ * Threat scoring
+ * Combines what every detector reported about one transaction into a single detection, whose
+ * confidence is what submission priority and threat proofs see. Detectors are grouped by family
+ * (the `detector` prefix before `:`) so several rules firing together count once, each family's
+ * confidence is mapped through its calibration curve and scaled by its configured weight, and families that agree on a category reinforce
+ * each other as independent evidence. The category with the highest combined confidence wins
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use super::calibration::Calibration;
+use super::{Detection, ThreatCategory};
+
+/// Scoring settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoringConfig {
+    /// Weight per detector family, scaling its confidence; families not listed weigh 1
+    pub detector_weights: HashMap<String, f64>,
+    /// Curve per detector family mapping its confidences to observed accuracy
+    #[serde(default)]
+    pub calibration: HashMap<String, Calibration>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            detector_weights: [("mempool", 0.8), ("intel", 0.9)]
+                .into_iter()
+                .map(|(family, weight)| (family.to_string(), weight))
+                .collect(),
+            calibration: HashMap::new(),
+        }
+    }
+}
+
+/// Risk score out of 100 for a confidence
+pub fn risk_score(confidence: f64) -> u32 {
+    (confidence.clamp(0.0, 1.0) * 100.0) as u32
+}
+
+/// What to do about a threat of this confidence
+pub fn recommended_action(confidence: f64) -> &'static str {
+    if confidence > 0.8 {
+        "Block transaction immediately"
+    } else if confidence > 0.5 {
+        "Flag for manual review"
+    } else {
+        "Monitor closely"
+    }
+}
+
+/// Detector families behind `detector`: its prefix, or every family a combined score merged
+pub fn detector_families(detector: &str) -> Vec<&str> {
+    match detector.strip_prefix("score:") {
+        Some(families) => families.split('+').collect(),
+        None => vec![detector.split(':').next().unwrap_or_default()],
+    }
+}
+
+/// Combines detector outputs into one confidence; clones share their weights
+#[derive(Debug, Clone, Default)]
+pub struct ScoringModel {
+    config: Arc<RwLock<ScoringConfig>>,
+}
+
+impl ScoringModel {
+    pub fn new(config: ScoringConfig) -> Self {
+        Self { config: Arc::new(RwLock::new(config)) }
+    }
+
+    pub fn config(&self) -> ScoringConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Replace the weights, as recalibration from feedback does
+    pub fn set_weights(&self, detector_weights: HashMap<String, f64>) {
+        self.config.write().unwrap().detector_weights = detector_weights;
+    }
+
+    /// Replace the calibration curves, as fitting them to oracle outcomes does
+    pub fn set_calibration(&self, calibration: HashMap<String, Calibration>) {
+        self.config.write().unwrap().calibration = calibration;
+    }
+
+    /// Combined detection for `detections` about one transaction, or `None` if there are none.
+    /// It takes its target and evidence from the strongest contribution to the winning category
+    pub fn score(&self, detections: &[Detection]) -> Option<Detection> {
+        // Strongest weighted detection per category and family
+        let config = self.config.read().unwrap();
+        let mut strongest: BTreeMap<ThreatCategory, BTreeMap<&str, (f64, &Detection)>> = BTreeMap::new();
+        for detection in detections {
+            let family = detection.detector.split(':').next().unwrap_or_default();
+            let weight = config.detector_weights.get(family).copied().unwrap_or(1.0).clamp(0.0, 1.0);
+            let confidence = detection.confidence.clamp(0.0, 1.0);
+            let calibrated = config.calibration.get(family).map_or(confidence, |curve| curve.apply(confidence));
+            let weighted = calibrated * weight;
+            let families = strongest.entry(detection.threat_type).or_default();
+            match families.get(family) {
+                Some((best, _)) if *best >= weighted => {}
+                _ => {
+                    families.insert(family, (weighted, detection));
+                }
+            }
+        }
+
+        let (category, families, confidence) = strongest
+            .into_iter()
+            .map(|(category, families)| {
+                let confidence = 1.0 - families.values().map(|(weighted, _)| 1.0 - weighted).product::<f64>();
+                (category, families, confidence)
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2))?;
+
+        let (_, lead) = families.values().max_by(|a, b| a.0.total_cmp(&b.0))?;
+        Some(Detection {
+            detector: format!("score:{}", families.keys().copied().collect::<Vec<_>>().join("+")),
+            threat_type: category,
+            confidence,
+            tx_hash: lead.tx_hash,
+            target: lead.target,
+            url: lead.url.clone(),
+            explanation: families.values().map(|(_, detection)| detection.explanation.as_str()).collect::<Vec<_>>().join("; "),
+            evidence: lead.evidence,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(detector: &str, threat_type: ThreatCategory, confidence: f64) -> Detection {
+        Detection {
+            detector: detector.to_string(),
+            threat_type,
+            confidence,
+            tx_hash: None,
+            target: None,
+            url: None,
+            explanation: detector.to_string(),
+            evidence: None,
+        }
+    }
+
+    #[test]
+    fn test_families_reinforce_and_rules_count_once() {
+        let model = ScoringModel::default();
+        assert!(model.score(&[]).is_none());
+
+        // Two rules of one family count as the stronger one
+        let rules = [
+            detection("rules:a", ThreatCategory::Drainer, 0.6),
+            detection("rules:b", ThreatCategory::Drainer, 0.5),
+        ];
+        assert!((model.score(&rules).unwrap().confidence - 0.6).abs() < 1e-9);
+
+        // Independent families agreeing reinforce; the weaker category loses
+        let mut agreeing = rules.to_vec();
+        agreeing.push(detection("approvals:approve", ThreatCategory::Drainer, 0.5));
+        agreeing.push(detection("model", ThreatCategory::Phishing, 0.7));
+        let score = model.score(&agreeing).unwrap();
+        assert_eq!((score.threat_type, score.detector.as_str()), (ThreatCategory::Drainer, "score:approvals+rules"));
+        assert!((score.confidence - 0.8).abs() < 1e-9);
+
+        // Weighted families count for less
+        let sandwich = model.score(&[detection("mempool:sandwich", ThreatCategory::Exploit, 0.7)]).unwrap();
+        assert!((sandwich.confidence - 0.56).abs() < 1e-9);
+        assert_eq!(detector_families(&score.detector), ["approvals", "rules"]);
+        assert_eq!(detector_families("mempool:sandwich"), ["mempool"]);
+
+        assert_eq!(ThreatCategory::from_label("flash_loan_attack"), Some(ThreatCategory::Exploit));
+        assert_eq!(ThreatCategory::from_code(ThreatCategory::Honeypot.code()), Some(ThreatCategory::Honeypot));
+        let category: ThreatCategory = serde_json::from_str("\"approval_drainer\"").unwrap();
+        assert_eq!((category, recommended_action(0.9)), (ThreatCategory::Drainer, "Block transaction immediately"));
+    }
+}
@@ -0,0 +1,451 @@
+/*!
+ * Threat signature database
+ * Confirmed threat signatures are announced by the oracle as `ThreatSignatureAdded` events, each
+ * with its leaf index in the signature Merkle tree. The database follows those events once they
+ * are `confirmations` deep, persists them in sled, and answers "is this a known signature" with a
+ * bloom filter in front of an exact index. It rebuilds the signature tree in leaf order, so the
+ * root and paths signature match proofs need are at hand, and checks that root against the one
+ * the oracle commits to. Drainer kit fingerprints the oracle publishes ride along in the same sync
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ethers::{
+    contract::{abigen, parse_log, EthEvent},
+    providers::Middleware,
+    types::{Address, Filter, H256},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use super::drainer_kits::{DrainerKits, Fingerprint, KitFingerprint};
+use super::registry::ThreatDetector;
+use super::{Detection, ThreatCategory, TxContext};
+use crate::zk_prover::{
+    signatures::{root_from_bytes, root_to_bytes, signature_of, SignatureTree},
+    transaction_fields,
+};
+
+abigen!(
+    ThreatSignatureRegistry,
+    r#"[
+        event ThreatSignatureAdded(uint256 indexed index, bytes32 signature, bytes32 threatHash, uint8 threatType)
+        event DrainerFingerprintAdded(uint256 indexed index, bytes32 threatHash, string kit, uint8 kind, bytes fingerprint)
+        function threatSignatureRoot() external view returns (bytes32)
+    ]"#
+);
+
+/// Bloom filter size; about 1% false positives at the signature tree's 65536 leaves
+const BLOOM_BITS: usize = 1 << 20;
+const BLOOM_HASHES: u64 = 7;
+const SYNCED_BLOCK_KEY: &[u8] = b"synced_block";
+
+/// Signature database settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureDbConfig {
+    pub enabled: bool,
+    pub path: String,
+    /// Oracle emitting the signature events
+    pub oracle: Address,
+    /// Block the oracle was deployed in; syncing starts there
+    pub start_block: u64,
+    /// Blocks an event must be buried under before it is synced
+    pub confirmations: u64,
+    /// Largest block range requested in one `eth_getLogs`
+    pub log_chunk_blocks: u64,
+    pub poll_interval_secs: u64,
+}
+
+impl Default for SignatureDbConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./data/signatures".to_string(),
+            oracle: Address::zero(),
+            start_block: 0,
+            confirmations: 12,
+            log_chunk_blocks: 5_000,
+            poll_interval_secs: 60,
+        }
+    }
+}
+
+/// Confirmed threat signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureRecord {
+    /// Leaf index in the signature tree
+    pub index: u64,
+    /// Leaf: the MiMC signature of the threat's leading transaction data
+    pub signature: H256,
+    pub threat_hash: H256,
+    /// Oracle `threatType`, see `ThreatCategory::code`
+    pub threat_type: u8,
+    pub block: u64,
+}
+
+/// Bit array probed at `BLOOM_HASHES` positions per signature
+#[derive(Debug, Clone)]
+struct Bloom {
+    bits: Vec<u64>,
+}
+
+impl Bloom {
+    fn new() -> Self {
+        Self { bits: vec![0; BLOOM_BITS / 64] }
+    }
+
+    /// Signatures are hash outputs, so their own bytes seed double hashing
+    fn positions(signature: &H256) -> impl Iterator<Item = usize> {
+        let h1 = u64::from_be_bytes(signature[..8].try_into().unwrap());
+        let h2 = u64::from_be_bytes(signature[8..16].try_into().unwrap()) | 1;
+        (0..BLOOM_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % BLOOM_BITS as u64) as usize)
+    }
+
+    fn insert(&mut self, signature: &H256) {
+        for position in Self::positions(signature) {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    fn may_contain(&self, signature: &H256) -> bool {
+        Self::positions(signature).all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+}
+
+struct State {
+    records: Vec<SignatureRecord>,
+    by_signature: HashMap<H256, usize>,
+    bloom: Bloom,
+    tree: SignatureTree,
+    synced_block: u64,
+}
+
+/// Locally synced set of confirmed threat signatures
+pub struct SignatureDb {
+    config: SignatureDbConfig,
+    tree: sled::Tree,
+    state: RwLock<State>,
+    /// Corpus kit fingerprints are synced into
+    drainer_kits: Option<Arc<DrainerKits>>,
+}
+
+impl SignatureDb {
+    /// Open (or create) the database, loading the signatures synced so far
+    pub fn open(config: SignatureDbConfig) -> Result<Self> {
+        let db = sled::open(&config.path).with_context(|| format!("Failed to open signature database at {}", config.path))?;
+        let tree = db.open_tree("threat_signatures")?;
+
+        let records = tree
+            .scan_prefix(b"sig:")
+            .values()
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect::<Result<Vec<SignatureRecord>>>()
+            .context("Corrupt signature database")?;
+        let synced_block = match tree.get(SYNCED_BLOCK_KEY)? {
+            Some(value) => u64::from_be_bytes(value.as_ref().try_into().context("Corrupt synced block")?),
+            None => config.start_block.saturating_sub(1),
+        };
+
+        let state = State {
+            records: Vec::new(),
+            by_signature: HashMap::new(),
+            bloom: Bloom::new(),
+            tree: SignatureTree::new(Vec::new())?,
+            synced_block,
+        };
+        let signature_db = Self {
+            config,
+            tree,
+            state: RwLock::new(state),
+            drainer_kits: None,
+        };
+        signature_db.apply(records, synced_block, false)?;
+        info!("🗂️ Loaded {} threat signatures (synced to block {})", signature_db.len(), synced_block);
+        Ok(signature_db)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.read().unwrap().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn synced_block(&self) -> u64 {
+        self.state.read().unwrap().synced_block
+    }
+
+    /// Also sync drainer kit fingerprints into `drainer_kits`
+    pub fn with_drainer_kits(mut self, drainer_kits: Arc<DrainerKits>) -> Self {
+        self.drainer_kits = Some(drainer_kits);
+        self
+    }
+
+    /// Block both signatures and fingerprints are synced to; a corpus added later catches up
+    fn sync_start(&self) -> u64 {
+        let synced = self.synced_block();
+        // A fresh corpus starts where a fresh signature database would, not at genesis
+        let fresh = self.config.start_block.saturating_sub(1);
+        self.drainer_kits.as_ref().map_or(synced, |kits| synced.min(kits.synced_block().unwrap_or(fresh)))
+    }
+
+    /// Record of `signature`, if it is a confirmed threat signature
+    pub fn get(&self, signature: &H256) -> Option<SignatureRecord> {
+        let state = self.state.read().unwrap();
+        if !state.bloom.may_contain(signature) {
+            return None;
+        }
+        state.by_signature.get(signature).map(|&index| state.records[index].clone())
+    }
+
+    pub fn contains(&self, signature: &H256) -> bool {
+        self.get(signature).is_some()
+    }
+
+    /// Signature of `data` as the signature match circuit computes it
+    pub fn signature_of(data: &[u8]) -> Result<H256> {
+        Ok(H256(root_to_bytes(&signature_of(&transaction_fields(data)?))))
+    }
+
+    /// Known signature `tx` matches, as a detection
+    pub fn detect(&self, tx: &TxContext) -> Option<Detection> {
+        self.matching(tx).map(|record| Self::detection(tx, &record))
+    }
+
+    /// Record of the signature `tx` matches
+    fn matching(&self, tx: &TxContext) -> Option<SignatureRecord> {
+        // Calldata too long for the circuit can't match a signature
+        self.get(&Self::signature_of(&tx.data).ok()?)
+    }
+
+    fn detection(tx: &TxContext, record: &SignatureRecord) -> Detection {
+        Detection {
+            detector: "signatures".to_string(),
+            // Codes newer than this node are still known threats
+            threat_type: ThreatCategory::from_code(record.threat_type).unwrap_or(ThreatCategory::Exploit),
+            confidence: 0.99,
+            tx_hash: tx.hash,
+            target: tx.to,
+            url: None,
+            explanation: format!("Matches confirmed threat signature #{} (type {})", record.index, record.threat_type),
+            evidence: Some(record.threat_hash),
+        }
+    }
+
+    /// Signature tree for signature match proofs
+    pub fn merkle_tree(&self) -> SignatureTree {
+        self.state.read().unwrap().tree.clone()
+    }
+
+    pub fn root(&self) -> Fr {
+        self.state.read().unwrap().tree.root()
+    }
+
+    /// Add `records` synced up to `synced_block`; they must continue the leaf sequence
+    pub fn ingest(&self, records: Vec<SignatureRecord>, synced_block: u64) -> Result<usize> {
+        self.apply(records, synced_block, true)
+    }
+
+    fn apply(&self, records: Vec<SignatureRecord>, synced_block: u64, persist: bool) -> Result<usize> {
+        let mut state = self.state.write().unwrap();
+        let mut added = Vec::new();
+        for record in records {
+            let next = (state.records.len() + added.len()) as u64;
+            if record.index < next {
+                // Replayed by an overlapping log range
+                continue;
+            }
+            if record.index > next {
+                return Err(anyhow::anyhow!("Missing threat signature #{} (next event is #{})", next, record.index));
+            }
+            root_from_bytes(&record.signature.0).with_context(|| format!("Threat signature #{} is not a field element", record.index))?;
+            added.push(record);
+        }
+
+        let leaves = state.records.iter().chain(&added).map(|record| root_from_bytes(&record.signature.0));
+        let tree = SignatureTree::new(leaves.collect::<Result<_>>()?)?;
+
+        if persist {
+            let mut batch = sled::Batch::default();
+            for record in &added {
+                batch.insert(format!("sig:{:020}", record.index).as_bytes(), serde_json::to_vec(record)?);
+            }
+            batch.insert(SYNCED_BLOCK_KEY, &synced_block.to_be_bytes());
+            self.tree.apply_batch(batch)?;
+            self.tree.flush()?;
+        }
+
+        let count = added.len();
+        for record in added {
+            state.bloom.insert(&record.signature);
+            let position = state.records.len();
+            state.by_signature.insert(record.signature, position);
+            state.records.push(record);
+        }
+        state.tree = tree;
+        state.synced_block = state.synced_block.max(synced_block);
+        Ok(count)
+    }
+
+    /// Pull confirmed signature events up to the confirmed head; returns how many were added
+    pub async fn sync<M: Middleware + 'static>(&self, provider: Arc<M>) -> Result<usize> {
+        let head = provider.get_block_number().await.map_err(|e| anyhow::anyhow!("{}", e))?.as_u64();
+        let confirmed = head.saturating_sub(self.config.confirmations);
+        let mut added = 0;
+
+        while self.sync_start() < confirmed {
+            let from = self.sync_start() + 1;
+            let to = confirmed.min(from + self.config.log_chunk_blocks.max(1) - 1);
+            let filter = Filter::new()
+                .address(self.config.oracle)
+                .topic0(vec![ThreatSignatureAddedFilter::signature(), DrainerFingerprintAddedFilter::signature()])
+                .from_block(from)
+                .to_block(to);
+            let logs = provider
+                .get_logs(&filter)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .with_context(|| format!("Failed to fetch signature events in blocks {}..={}", from, to))?;
+
+            let (signature_logs, fingerprint_logs): (Vec<_>, Vec<_>) = logs
+                .into_iter()
+                .partition(|log| log.topics.first() == Some(&ThreatSignatureAddedFilter::signature()));
+            if let Some(kits) = &self.drainer_kits {
+                let fingerprints = fingerprint_logs
+                    .into_iter()
+                    .filter_map(|log| {
+                        let block = log.block_number.unwrap_or_default().as_u64();
+                        let event: DrainerFingerprintAddedFilter = parse_log(log).ok()?;
+                        match Fingerprint::decode(event.kind, &event.fingerprint) {
+                            Ok(fingerprint) => Some(KitFingerprint {
+                                index: event.index.as_u64(),
+                                kit: event.kit,
+                                threat_hash: H256(event.threat_hash),
+                                fingerprint,
+                                block,
+                            }),
+                            Err(e) => {
+                                // Kinds newer than this node are skipped, not fatal
+                                warn!("Skipping drainer fingerprint #{}: {:#}", event.index, e);
+                                None
+                            }
+                        }
+                    })
+                    .collect();
+                let added = kits.ingest(fingerprints, to)?;
+                if added > 0 {
+                    info!("🪝 Synced {} drainer kit fingerprints ({} total)", added, kits.len());
+                }
+            }
+
+            let records = signature_logs
+                .into_iter()
+                .map(|log| {
+                    let block = log.block_number.unwrap_or_default().as_u64();
+                    let event: ThreatSignatureAddedFilter = parse_log(log)?;
+                    Ok(SignatureRecord {
+                        index: event.index.as_u64(),
+                        signature: H256(event.signature),
+                        threat_hash: H256(event.threat_hash),
+                        threat_type: event.threat_type,
+                        block,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            added += self.ingest(records, to)?;
+        }
+
+        if added > 0 {
+            let registry = ThreatSignatureRegistry::new(self.config.oracle, provider);
+            let onchain = registry.threat_signature_root().call().await.context("Failed to read threatSignatureRoot")?;
+            if onchain != root_to_bytes(&self.root()) {
+                // The oracle may already commit to signatures not confirmed deep enough yet
+                warn!("⚠️ Synced signature root differs from the oracle's 0x{}", hex::encode(onchain));
+            }
+            info!("🗂️ Synced {} new threat signatures ({} total)", added, self.len());
+        }
+        Ok(added)
+    }
+
+    /// Keep syncing every poll interval
+    pub async fn watch<M: Middleware + 'static>(self: Arc<Self>, provider: Arc<M>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.sync(provider.clone()).await {
+                warn!("Threat signature sync failed: {:#}", e);
+            } else {
+                debug!("Threat signatures synced to block {}", self.synced_block());
+            }
+        }
+    }
+}
+
+impl ThreatDetector for SignatureDb {
+    type Features = SignatureRecord;
+
+    fn name(&self) -> &str {
+        "signatures"
+    }
+
+    fn category(&self) -> Option<ThreatCategory> {
+        None
+    }
+
+    async fn featurize(&self, tx: &TxContext) -> Result<Option<SignatureRecord>> {
+        Ok(self.matching(tx))
+    }
+
+    fn score(&self, tx: &TxContext, record: &SignatureRecord) -> Vec<Detection> {
+        vec![Self::detection(tx, record)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_indexed_persisted_and_rooted() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SignatureDbConfig {
+            path: dir.path().join("signatures").to_string_lossy().into_owned(),
+            ..SignatureDbConfig::default()
+        };
+        let attacks: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 64]).collect();
+        let record = |index: usize| SignatureRecord {
+            index: index as u64,
+            signature: SignatureDb::signature_of(&attacks[index]).unwrap(),
+            threat_hash: H256::repeat_byte(index as u8),
+            threat_type: 1,
+            block: 100 + index as u64,
+        };
+
+        let db = SignatureDb::open(config.clone()).unwrap();
+        assert_eq!(db.ingest(vec![record(0), record(1)], 101).unwrap(), 2);
+        // Overlapping ranges replay events; gaps are refused
+        assert_eq!(db.ingest(vec![record(1), record(2)], 102).unwrap(), 1);
+        assert!(db.ingest(vec![SignatureRecord { index: 5, ..record(3) }], 105).is_err());
+
+        let tx = TxContext {
+            data: [attacks[2].clone(), b"other arguments".to_vec()].concat().into(),
+            ..TxContext::default()
+        };
+        assert_eq!(db.detect(&tx).unwrap().evidence, Some(H256::repeat_byte(2)));
+        assert!(!db.contains(&SignatureDb::signature_of(b"benign").unwrap()));
+
+        let leaves = (0..3).map(|i| root_from_bytes(&record(i).signature.0).unwrap()).collect();
+        let root = SignatureTree::new(leaves).unwrap().root();
+        assert_eq!(db.root(), root);
+        drop(db);
+
+        let reopened = SignatureDb::open(config).unwrap();
+        assert_eq!((reopened.len(), reopened.synced_block(), reopened.root()), (3, 102, root));
+    }
+}
@@ -8,27 +8,143 @@ use battery::{Battery, Manager as BatteryManager};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
     time::{Duration, Instant},
 };
 use sysinfo::{CpuExt, System, SystemExt};
-use tokio::time::{interval, sleep};
+use tokio::{sync::broadcast, task::JoinHandle, time::sleep};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+pub mod activity;
+pub mod alerts;
+pub mod amdgpu;
+pub mod anomaly;
+pub mod arm;
+pub mod attribution;
+pub mod batteries;
+pub mod battery_health;
+pub mod budget;
+pub mod calibration;
+pub mod carbon;
+pub mod carbon_report;
+pub mod container;
+pub mod export;
+pub mod forecast;
+pub mod gpu;
+pub mod gpu_adapters;
+pub mod hardware;
+pub mod history_store;
+pub mod ipmi;
+pub mod mobile;
+#[cfg(feature = "nvml")]
+pub mod nvml;
+pub mod power_policy;
+pub mod powermetrics;
+pub mod profiles;
+pub mod rapl;
+pub mod renewable;
+pub mod rollup;
+pub mod scheduler;
+pub mod shutdown;
+pub mod smart_plug;
+pub mod subsystems;
+pub mod thermal;
+pub mod ups;
+pub mod wmi_power;
+
+use crate::ai::ThreatDetectionResult;
+use crate::zk_prover::{EnergyRangeProof, ZKProver};
+
+use activity::ActivityTracker;
+use alerts::AlertManager;
+use anomaly::MiningAnomalyDetector;
+use arm::{ArmSoc, HwmonPowerReader};
+use attribution::{HostPower, ProcessAttributor, ProcessEnergy};
+use batteries::BatteryReading;
+use battery_health::{BatteryHealth, BatteryHealthTracker};
+use budget::{BudgetAction, BudgetHook, BudgetStatus, BudgetTracker};
+use calibration::{
+    CalibratedCoefficients, CalibrationConfig, CalibrationReference, CalibrationStep, CalibrationWorkload, CpuLoad,
+    CpuModel,
+};
+use carbon::{CarbonIntensityConfig, CarbonIntensityProvider};
+use carbon_report::{CarbonOffset, CarbonReport};
+use container::{CgroupReader, ContainerShare, DeploymentMode};
+use export::ExportFormat;
+use forecast::{EnergyForecast, Smoothing};
+use gpu::{GpuMonitor, GpuReading};
+use gpu_adapters::{GpuAdapter, GpuPowerProfile, GpuVendor};
+use hardware::{MemoryGeneration, StorageDevice, StorageKind};
+use history_store::EnergyHistoryStore;
+use ipmi::{IpmiReader, PsuReading};
+use mobile::MobileBatteryProvider;
+use power_policy::{PowerMode, PowerPolicy};
+use powermetrics::PowermetricsBackend;
+use profiles::{EnergyMonitorConfig, MonitoringProfile};
+use rapl::RaplReader;
+use renewable::{RenewableTracker, SourceMix};
+use rollup::EnergyHistory;
+use scheduler::{CarbonScheduler, SchedulingConditions};
+use shutdown::ShutdownHook;
+use smart_plug::{MeasurementSource, SmartPlug};
+use subsystems::{SubsystemEnergy, SubsystemPower, SubsystemSampler};
+use thermal::{ThermalEventKind, ThermalGovernor, ThermalReading};
+use ups::{NutClient, UpsStatus};
+use wmi_power::{PowerRail, WmiPowerBackend};
+
 /// Real energy consumption data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EnergyData {
     pub total_watts: f64,
     pub cpu_watts: f64,
     pub gpu_watts: f64,
     pub memory_watts: f64,
     pub network_watts: f64,
+    /// Neural/ML accelerator power (Apple ANE), when measured
+    #[serde(default)]
+    pub npu_watts: f64,
     pub battery_level: Option<f64>,
     pub battery_time_remaining: Option<Duration>,
     pub is_charging: Option<bool>,
     pub efficiency_score: u8, // 0-100
+    /// Net of the renewable share in `source_mix`
     pub carbon_footprint_kg_per_hour: f64,
     pub timestamp: u64,
+    /// Per-GPU measurements from vendor backends (empty when GPU power is modeled)
+    #[serde(default)]
+    pub gpus: Vec<GpuReading>,
+    /// Per-rail power sensors (Windows hardware monitor)
+    #[serde(default)]
+    pub power_rails: Vec<PowerRail>,
+    /// Share of the host's power attributed to the DAGShield node itself
+    #[serde(default)]
+    pub node_process: Option<ProcessEnergy>,
+    /// CPU/GPU temperatures and whether the node was thermally throttled
+    #[serde(default)]
+    pub thermal: Option<ThermalReading>,
+    /// How `total_watts` was obtained
+    #[serde(default)]
+    pub measurement_source: MeasurementSource,
+    /// Input power, load and on-battery state of the UPS feeding the node
+    #[serde(default)]
+    pub ups: Option<UpsStatus>,
+    /// Renewable share of the supply, when the operator tags the node
+    #[serde(default)]
+    pub source_mix: Option<SourceMix>,
+    /// Each battery behind the combined `battery_level`
+    #[serde(default)]
+    pub batteries: Vec<BatteryReading>,
+    /// Per-PSU input power from the BMC (servers)
+    #[serde(default)]
+    pub psus: Vec<PsuReading>,
+    /// Host CPU power split across ZK proving, DAG submission, detection and P2P
+    #[serde(default)]
+    pub subsystems: Vec<SubsystemPower>,
 }
 
 /// Hardware specifications for power calculation
@@ -42,8 +158,21 @@ pub struct HardwareSpecs {
     pub memory_type: String,
     pub gpu_model: Option<String>,
     pub gpu_memory_gb: Option<u32>,
-    pub storage_type: String, // SSD/HDD
+    pub storage_type: String, // NVMe/SSD/HDD
     pub network_interfaces: Vec<String>,
+    #[serde(default)]
+    pub memory_generation: Option<MemoryGeneration>,
+    #[serde(default)]
+    pub storage_devices: Vec<StorageDevice>,
+    /// Physical GPUs; `gpu_model` is the first discrete one (or the first integrated)
+    #[serde(default)]
+    pub gpu_adapters: Vec<GpuAdapter>,
+    /// ARM SoC family; its power curve replaces the x86 TDP model
+    #[serde(default)]
+    pub arm_soc: Option<ArmSoc>,
+    /// Capacity and cycle count of each battery
+    #[serde(default)]
+    pub battery_health: Vec<BatteryHealth>,
 }
 
 /// Energy monitoring system
@@ -51,11 +180,74 @@ pub struct EnergyMonitor {
     pub enabled: bool,
     pub system: Arc<RwLock<System>>,
     pub battery_manager: Option<BatteryManager>,
+    /// Phone/tablet battery supplied by the host app (Android JNI, iOS via UniFFI)
+    pub mobile_battery: Option<Arc<dyn MobileBatteryProvider>>,
+    /// Degradation trend and wear warnings (None without a battery)
+    pub battery_health: Option<BatteryHealthTracker>,
     pub hardware_specs: HardwareSpecs,
-    pub baseline_power: f64,
-    pub power_coefficients: PowerCoefficients,
-    pub energy_history: Arc<RwLock<Vec<EnergyData>>>,
-    pub carbon_intensity: f64, // kg CO2 per kWh
+    /// Replaced in place by `calibrate`
+    pub power_coefficients: RwLock<PowerCoefficients>,
+    pub energy_history: Arc<RwLock<EnergyHistory>>,
+    pub carbon_intensity: f64, // kg CO2 per kWh (static fallback)
+    pub carbon_provider: CarbonIntensityProvider,
+    /// Measured CPU/DRAM energy counters (Linux powercap), preferred over the model
+    pub rapl: Option<Arc<RaplReader>>,
+    /// Board input power monitors (Jetson, Pi HATs)
+    pub hwmon_power: Option<Arc<HwmonPowerReader>>,
+    /// Server PSU sensors through the BMC
+    pub ipmi: Option<IpmiReader>,
+    pub gpu_monitor: GpuMonitor,
+    /// Apple Silicon measurements (macOS)
+    pub powermetrics: Option<PowermetricsBackend>,
+    /// Hardware monitor sensors published through WMI (Windows)
+    pub wmi_power: Option<WmiPowerBackend>,
+    pub process_attributor: Option<ProcessAttributor>,
+    pub subsystem_sampler: SubsystemSampler,
+    /// Persistent history; the in-memory history only keeps recent readings
+    pub history_store: Option<EnergyHistoryStore>,
+    pub config: EnergyMonitorConfig,
+    /// Active sampling/alerting profile, switchable at runtime
+    pub profile: Arc<RwLock<MonitoringProfile>>,
+    /// Battery-driven throttling decision, refreshed every sample
+    pub power_policy: Arc<RwLock<PowerPolicy>>,
+    /// Host input idle time and screen lock (desktop and mobile nodes)
+    pub activity: Option<ActivityTracker>,
+    /// Run when the battery reaches the critical level
+    pub shutdown_hook: Option<ShutdownHook>,
+    pub thermal: ThermalGovernor,
+    pub budget_tracker: Arc<RwLock<BudgetTracker>>,
+    pub budget_hooks: Arc<RwLock<Vec<BudgetHook>>>,
+    /// Flags sustained power jumps the node's own workload does not explain
+    pub anomaly_detector: Arc<RwLock<MiningAnomalyDetector>>,
+    pub threat_events: broadcast::Sender<ThreatDetectionResult>,
+    /// Set in container deployments; readings are scaled to the cgroup's share
+    pub cgroup: Option<CgroupReader>,
+    /// Ground-truth wall power and model calibration
+    pub smart_plug: Option<Arc<SmartPlug>>,
+    /// NUT client for server-class nodes on a UPS
+    pub ups: Option<NutClient>,
+    /// Declared or metered renewable supply
+    pub renewable: Option<RenewableTracker>,
+    /// Alert rules delivered to webhooks
+    pub alerts: Option<Arc<AlertManager>>,
+    /// Deferrable work waiting for a low-carbon, plugged-in window
+    pub scheduler: Arc<CarbonScheduler>,
+    /// Cancelled by `stop_monitoring` to end the monitoring and scheduler loops
+    pub cancel: CancellationToken,
+}
+
+/// Component split of one sample, computed while the sysinfo guard is held
+struct ComponentSample {
+    cpu_watts: f64,
+    memory_watts: f64,
+    network_watts: f64,
+    total_watts: f64,
+    measurement_source: MeasurementSource,
+    node_process: Option<ProcessEnergy>,
+    subsystems: Vec<SubsystemPower>,
+    efficiency_score: u8,
+    timestamp: u64,
+    thermal: Option<ThermalReading>,
 }
 
 /// Power calculation coefficients for different components
@@ -65,8 +257,17 @@ pub struct PowerCoefficients {
     pub memory_per_gb: f64,
     pub gpu_base: f64,
     pub network_per_mbps: f64,
+    pub storage_nvme: f64,
     pub storage_ssd: f64,
     pub storage_hdd: f64,
+    /// Idle/active GPU power by (vendor, integrated)
+    pub gpu_power_table: HashMap<(GpuVendor, bool), GpuPowerProfile>,
+    /// Calibrated CPU power at 0% load (replaces the TDP estimate)
+    pub cpu_idle_watts: Option<f64>,
+    /// Calibrated CPU power added at 100% load
+    pub cpu_dynamic_watts: Option<f64>,
+    /// Calibrated correction to the modeled baseline
+    pub idle_offset_watts: f64,
 }
 
 impl Default for PowerCoefficients {
@@ -76,20 +277,51 @@ impl Default for PowerCoefficients {
             memory_per_gb: 3.0,         // Watts per GB
             gpu_base: 50.0,             // Base GPU power
             network_per_mbps: 0.1,      // Watts per Mbps
+            storage_nvme: 4.0,          // NVMe SSD power
             storage_ssd: 2.0,           // SSD power
             storage_hdd: 6.0,           // HDD power
+            gpu_power_table: gpu_adapters::default_power_table().into_iter().collect(),
+            cpu_idle_watts: None,
+            cpu_dynamic_watts: None,
+            idle_offset_watts: 0.0,
         }
     }
 }
 
+impl PowerCoefficients {
+    /// Power profile for a GPU, falling back to a generic discrete card
+    pub fn gpu_profile(&self, adapter: &GpuAdapter) -> GpuPowerProfile {
+        self.gpu_power_table
+            .get(&(adapter.vendor, adapter.integrated))
+            .copied()
+            .unwrap_or(GpuPowerProfile {
+                idle_watts: 20.0,
+                active_watts: 200.0,
+            })
+    }
+
+    /// Use the terms fitted for this machine
+    pub fn apply_calibration(&mut self, calibration: &CalibratedCoefficients) {
+        self.cpu_idle_watts = calibration.cpu_idle_watts.or(self.cpu_idle_watts);
+        self.cpu_dynamic_watts = Some(calibration.cpu_dynamic_watts);
+        self.idle_offset_watts = calibration.idle_offset_watts;
+    }
+}
+
 impl EnergyMonitor {
     /// Create new energy monitor with REAL hardware detection
     pub fn new(enabled: bool) -> Self {
         let mut system = System::new_all();
         system.refresh_all();
 
-        let hardware_specs = Self::detect_hardware_specs(&system);
-        let baseline_power = Self::calculate_baseline_power(&hardware_specs);
+        let mut hardware_specs = Self::detect_hardware_specs(&system);
+
+        // Vendor backends know VRAM sizes that adapter enumeration does not
+        let gpu_monitor = GpuMonitor::detect();
+        Self::fill_gpu_memory(&mut hardware_specs, &gpu_monitor.read_all());
+
+        let power_coefficients = PowerCoefficients::default();
+        let baseline_power = Self::calculate_baseline_power(&hardware_specs, &power_coefficients);
         
         // Try to initialize battery manager
         let battery_manager = match BatteryManager::new() {
@@ -102,13 +334,32 @@ impl EnergyMonitor {
                 None
             }
         };
+        if let Some(manager) = &battery_manager {
+            hardware_specs.battery_health = Self::read_battery_health(manager);
+        }
+
+        let rapl = RaplReader::detect().map(Arc::new);
+        if rapl.is_some() {
+            info!("✅ RAPL energy counters available, using measured CPU power");
+        }
+
+        let hwmon_power = HwmonPowerReader::detect().map(Arc::new);
+        if hwmon_power.is_some() {
+            info!("✅ Board power monitor found, using measured input power");
+        }
+
+        let ipmi = IpmiReader::detect();
+        if ipmi.is_some() {
+            info!("✅ IPMI available, using measured PSU input power");
+        }
 
         // Get carbon intensity for user's region (simplified)
         let carbon_intensity = Self::get_regional_carbon_intensity();
 
         info!("🔋 Energy monitor initialized:");
-        info!("   Hardware: {} cores, {}GB RAM", 
-              hardware_specs.cpu_cores, hardware_specs.memory_size_gb);
+        info!("   Hardware: {} cores, {}GB {} RAM, {} storage", 
+              hardware_specs.cpu_cores, hardware_specs.memory_size_gb,
+              hardware_specs.memory_type, hardware_specs.storage_type);
         info!("   Baseline power: {:.1}W", baseline_power);
         info!("   Carbon intensity: {:.3} kg CO2/kWh", carbon_intensity);
 
@@ -116,67 +367,541 @@ impl EnergyMonitor {
             enabled,
             system: Arc::new(RwLock::new(system)),
             battery_manager,
+            mobile_battery: None,
+            battery_health: None,
             hardware_specs,
-            baseline_power,
-            power_coefficients: PowerCoefficients::default(),
-            energy_history: Arc::new(RwLock::new(Vec::new())),
+            power_coefficients: RwLock::new(power_coefficients),
+            energy_history: Arc::new(RwLock::new(EnergyHistory::default())),
             carbon_intensity,
+            carbon_provider: CarbonIntensityProvider::new(CarbonIntensityConfig::default()),
+            rapl,
+            hwmon_power,
+            ipmi,
+            gpu_monitor,
+            powermetrics: PowermetricsBackend::detect(),
+            wmi_power: WmiPowerBackend::detect(),
+            process_attributor: ProcessAttributor::for_current_process(),
+            subsystem_sampler: SubsystemSampler::default(),
+            history_store: None,
+            config: EnergyMonitorConfig {
+                enabled,
+                ..EnergyMonitorConfig::default()
+            },
+            profile: Arc::new(RwLock::new(MonitoringProfile::standard())),
+            power_policy: Arc::new(RwLock::new(PowerPolicy::default())),
+            activity: None,
+            shutdown_hook: None,
+            thermal: ThermalGovernor::new(Default::default()),
+            budget_tracker: Arc::new(RwLock::new(BudgetTracker::default())),
+            budget_hooks: Arc::new(RwLock::new(Vec::new())),
+            anomaly_detector: Arc::new(RwLock::new(MiningAnomalyDetector::default())),
+            threat_events: broadcast::channel(64).0,
+            cgroup: None,
+            smart_plug: None,
+            ups: None,
+            renewable: None,
+            alerts: None,
+            scheduler: Arc::new(CarbonScheduler::new(Default::default())),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Create energy monitor from configuration
+    pub fn from_config(config: EnergyMonitorConfig) -> Result<Self> {
+        let profile = config
+            .find_profile(&config.profile)
+            .with_context(|| format!("Unknown monitoring profile: {}", config.profile))?;
+
+        let mut monitor = Self::new(config.enabled).with_carbon_intensity(config.carbon.clone());
+        if let Some(path) = config.calibration.path.as_deref().filter(|path| Path::new(path).exists()) {
+            let calibration = CalibratedCoefficients::load(path)?;
+            info!("   Power model calibrated against {:?} (R² {:.3})", calibration.reference, calibration.r_squared);
+            monitor.power_coefficients.write().unwrap().apply_calibration(&calibration);
+        }
+        if let Some(path) = &config.history_path {
+            monitor = monitor.with_history_store(path, config.history_retention_secs)?;
+        }
+
+        info!("   Monitoring profile: {} ({}s sampling)", profile.name, profile.sampling_interval_secs);
+        *monitor.profile.write().unwrap() = profile;
+        monitor.thermal = ThermalGovernor::new(config.thermal.clone());
+
+        // Resume budgets for the current period from persisted history
+        let mut tracker = BudgetTracker::new(config.budgets.clone());
+        let now = chrono::Utc::now().timestamp() as u64;
+        if let (Some(store), Some(since)) = (&monitor.history_store, tracker.earliest_period_start(now)) {
+            for reading in store.range(since, now + 1)? {
+                tracker.record(&reading);
+            }
+        }
+        *monitor.budget_tracker.write().unwrap() = tracker;
+        *monitor.anomaly_detector.write().unwrap() = MiningAnomalyDetector::new(config.anomaly.clone());
+
+        if config.deployment.resolve() == DeploymentMode::Container {
+            monitor.cgroup = CgroupReader::detect();
+            match &monitor.cgroup {
+                Some(cgroup) => {
+                    let limits = cgroup.limits();
+                    info!("   Container mode: {:.1} of {} cores, memory limit {:?}",
+                          limits.allowed_cores(monitor.hardware_specs.cpu_cores as usize),
+                          monitor.hardware_specs.cpu_cores, limits.memory_limit_bytes);
+                }
+                None => warn!("⚠️ Container mode requested but no cgroup v2 hierarchy found, using host totals"),
+            }
+        }
+
+        if config.smart_plug.enabled {
+            info!("   Smart plug: {:?} at {}", config.smart_plug.kind, config.smart_plug.host);
+            monitor.smart_plug = Some(Arc::new(SmartPlug::new(config.smart_plug.clone())));
+        }
+
+        if config.ups.enabled {
+            info!("   UPS: {}@{}:{}", config.ups.ups_name, config.ups.host, config.ups.port);
+            monitor.ups = Some(NutClient::new(config.ups.clone()));
+        }
+
+        if config.renewable.enabled {
+            info!("   Renewable supply: {:?}, {:.0}% declared{}", config.renewable.source,
+                  config.renewable.declared_fraction * 100.0,
+                  if config.renewable.meter.is_some() { ", metered" } else { "" });
+            monitor.renewable = Some(RenewableTracker::new(config.renewable.clone()));
+        }
+
+        if config.battery_health.enabled && monitor.battery_manager.is_some() {
+            let tracker = BatteryHealthTracker::new(config.battery_health.clone())?;
+            let now = chrono::Utc::now().timestamp() as u64;
+            monitor.hardware_specs.battery_health = tracker.update(monitor.hardware_specs.battery_health.clone(), now);
+            for health in &monitor.hardware_specs.battery_health {
+                info!("   Battery {}: {:.1}% health, {} cycles", health.id, health.health_percent,
+                      health.cycle_count.map_or("unknown".to_string(), |cycles| cycles.to_string()));
+            }
+            monitor.battery_health = Some(tracker);
+        }
+
+        if let Some(provider) = mobile::registered_provider() {
+            monitor = monitor.with_mobile_battery(provider);
+        }
+
+        monitor.scheduler = Arc::new(CarbonScheduler::new(config.scheduler.clone()));
+
+        if let Some(policy) = config.activity.policy() {
+            info!("   Activity detection: {:?}, backing off within {}s of user input",
+                  config.activity.device_type, policy.idle_after_secs);
+            monitor.activity = Some(ActivityTracker::default());
+        }
+
+        if config.alerts.enabled {
+            info!("   Alerts: {} rules, {} webhooks", config.alerts.rules.len(), config.alerts.webhooks.len());
+            let host = monitor.system.read().unwrap().host_name().unwrap_or_else(|| "unknown".to_string());
+            monitor.alerts = Some(Arc::new(AlertManager::new(config.alerts.clone(), host)));
+        }
+        monitor.config = config;
+
+        Ok(monitor)
+    }
+
+    /// Switch the active monitoring profile; takes effect on the next sample
+    pub fn set_profile(&self, name: &str) -> Result<()> {
+        let profile = self.config
+            .find_profile(name)
+            .with_context(|| format!("Unknown monitoring profile: {}", name))?;
+
+        info!("🔋 Switching energy monitoring profile to {}", profile.name);
+        *self.profile.write().unwrap() = profile;
+        Ok(())
+    }
+
+    /// Currently active monitoring profile
+    pub fn active_profile(&self) -> MonitoringProfile {
+        self.profile.read().unwrap().clone()
+    }
+
+    /// Current battery-aware power policy
+    pub fn current_power_policy(&self) -> PowerPolicy {
+        self.power_policy.read().unwrap().clone()
+    }
+
+    /// Re-evaluate the power policy from the battery state and host activity of the latest
+    /// sample; the batteries, UPS and activity are only queried by the sampler
+    pub fn refresh_power_policy(&self) -> PowerPolicy {
+        let (level, is_charging) = self
+            .latest_reading()
+            .map_or((None, None), |reading| (reading.battery_level, reading.is_charging));
+        self.update_power_policy(level, is_charging)
+    }
+
+    fn update_power_policy(&self, battery_level: Option<f64>, is_charging: Option<bool>) -> PowerPolicy {
+        let mut policy = PowerPolicy::evaluate(&self.config.power_policy, battery_level, is_charging);
+        if self.thermal.is_throttled() {
+            policy = policy.with_thermal_throttle(self.thermal.config().batch_fraction);
+        }
+        if let (Some(activity), Some(activity_policy)) = (&self.activity, self.config.activity.policy()) {
+            let ramp = activity.ramp(activity_policy, chrono::Utc::now().timestamp() as u64);
+            policy = policy.with_activity_ramp(activity_policy, ramp);
+        }
+        let previous = std::mem::replace(&mut *self.power_policy.write().unwrap(), policy.clone());
+
+        if previous.user_active != policy.user_active {
+            if policy.user_active {
+                info!("🖥️ Host in use, backing off node workload");
+            } else {
+                info!("🖥️ Host idle, ramping node workload back up");
+            }
+        }
+
+        if previous.mode != policy.mode {
+            match policy.mode {
+                PowerMode::Full => info!("🔌 Power policy: full operation restored"),
+                mode => warn!(
+                    "🔋 Power policy: {:?} at {:.0}% (scans x{}, batches {:.0}%, ZK proofs {})",
+                    mode,
+                    battery_level.unwrap_or_default(),
+                    policy.scan_interval_multiplier,
+                    policy.batch_fraction * 100.0,
+                    if policy.defer_zk_proofs { "deferred" } else { "enabled" }
+                ),
+            }
+        }
+        policy
+    }
+
+    /// Local threat events raised by the energy anomaly detector
+    pub fn subscribe_threats(&self) -> broadcast::Receiver<ThreatDetectionResult> {
+        self.threat_events.subscribe()
+    }
+
+    /// Register a hook run whenever a budget threshold is crossed
+    pub fn on_budget_event(&self, hook: BudgetHook) {
+        self.budget_hooks.write().unwrap().push(hook);
+    }
+
+    /// Consumption against every configured budget in the current period
+    pub fn get_budget_status(&self) -> Vec<BudgetStatus> {
+        self.budget_tracker.read().unwrap().status()
+    }
+
+    fn check_budgets(&self, energy_data: &EnergyData) {
+        let events = self.budget_tracker.write().unwrap().record(energy_data);
+        if events.is_empty() {
+            return;
+        }
+
+        let hooks = self.budget_hooks.read().unwrap().clone();
+        for event in &events {
+            let message = format!(
+                "budget {} at {:.0}% ({:.3}/{:.3} {:?})",
+                event.budget, event.fraction * 100.0, event.consumed, event.limit, event.metric
+            );
+            match event.action {
+                BudgetAction::Alert => warn!("💰 Energy {}", message),
+                BudgetAction::LowerDutyCycle => warn!("💰 Energy {}, lowering duty cycle", message),
+                BudgetAction::PauseDetection => error!("💰 Energy {}, pausing detection", message),
+            }
+            for hook in &hooks {
+                hook(event);
+            }
         }
     }
 
+    fn sampling_interval_secs(&self) -> u64 {
+        self.profile.read().unwrap().sampling_interval_secs.max(1)
+    }
+
+    /// Use a live grid carbon intensity provider instead of the global average
+    pub fn with_carbon_intensity(mut self, config: CarbonIntensityConfig) -> Self {
+        self.carbon_provider = CarbonIntensityProvider::new(config);
+        self.carbon_intensity = self.carbon_provider.fallback_intensity();
+        self
+    }
+
+    /// Read the battery through the mobile OS instead of the `battery` crate
+    pub fn with_mobile_battery(mut self, provider: Arc<dyn MobileBatteryProvider>) -> Self {
+        info!("✅ Mobile battery monitoring enabled");
+        self.mobile_battery = Some(provider);
+        self
+    }
+
+    /// Register the sequence run before stopping on critical battery
+    pub fn with_shutdown_hook(mut self, hook: ShutdownHook) -> Self {
+        self.shutdown_hook = Some(hook);
+        self
+    }
+
+    /// Persist readings to an on-disk store with the given retention
+    pub fn with_history_store(mut self, path: &str, retention_secs: u64) -> Result<Self> {
+        let store = EnergyHistoryStore::open(path, retention_secs)?;
+        info!("💾 Energy history persisted at {} ({} readings)", path, store.len());
+
+        // Rebuild the last day of in-memory history and rollups from disk
+        let now = chrono::Utc::now().timestamp() as u64;
+        {
+            let mut history = self.energy_history.write().unwrap();
+            for reading in store.range(now.saturating_sub(24 * 3600), now + 1)? {
+                history.push(reading);
+            }
+        }
+        self.history_store = Some(store);
+        Ok(self)
+    }
+
     /// Get current REAL energy consumption
     pub async fn get_current_consumption(&self) -> Result<EnergyData> {
         if !self.enabled {
             return Ok(EnergyData {
-                total_watts: 0.0,
-                cpu_watts: 0.0,
-                gpu_watts: 0.0,
-                memory_watts: 0.0,
-                network_watts: 0.0,
-                battery_level: None,
-                battery_time_remaining: None,
-                is_charging: None,
                 efficiency_score: 100,
-                carbon_footprint_kg_per_hour: 0.0,
                 timestamp: chrono::Utc::now().timestamp() as u64,
+                measurement_source: MeasurementSource::Model,
+                ..EnergyData::default()
             });
         }
 
-        // Refresh system information
-        {
-            let mut system = self.system.write().unwrap();
-            system.refresh_cpu();
-            system.refresh_memory();
-            system.refresh_networks();
-        }
+        // Refresh system information and read the sysfs power counters off the async runtime
+        // (sysinfo, powercap and hwmon are all synchronous file reads)
+        let system = self.system.clone();
+        let refresh_components = self.thermal.config().enabled;
+        let node_pid = self.process_attributor.as_ref().map(|attributor| attributor.pid());
+        let rapl = self.rapl.clone();
+        let hwmon_power = self.hwmon_power.clone();
+        let (measured, board_watts) = tokio::task::spawn_blocking(move || {
+            {
+                let mut system = system.write().unwrap();
+                system.refresh_cpu();
+                system.refresh_memory();
+                system.refresh_networks();
+                if refresh_components {
+                    system.refresh_components();
+                }
+                if let Some(pid) = node_pid {
+                    system.refresh_process(pid);
+                }
+            }
 
-        let system = self.system.read().unwrap();
+            // Prefer measured RAPL energy over the coefficient model
+            let measured = rapl.and_then(|rapl| match rapl.sample() {
+                Ok(sample) => sample,
+                Err(e) => {
+                    debug!("RAPL read failed, using power model: {}", e);
+                    None
+                }
+            });
+            let board_watts = hwmon_power.and_then(|reader| reader.read_board_watts());
+            (measured, board_watts)
+        })
+        .await
+        .context("System refresh task failed")?;
 
-        // Calculate CPU power consumption
-        let cpu_usage = system.global_cpu_info().cpu_usage() / 100.0;
-        let cpu_watts = self.calculate_cpu_power(cpu_usage);
+        // Apple Silicon: measured package, GPU and ANE power
+        let apple_power = match &self.powermetrics {
+            Some(backend) => match backend.sample().await {
+                Ok(sample) => Some(sample),
+                Err(e) => {
+                    debug!("powermetrics unavailable, using power model: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
 
-        // Calculate memory power consumption
-        let memory_usage = system.used_memory() as f64 / system.total_memory() as f64;
-        let memory_watts = self.calculate_memory_power(memory_usage);
+        // Windows: package and rail sensors from the hardware monitor
+        let wmi_power = match &self.wmi_power {
+            Some(backend) => match backend.sample().await {
+                Ok(sample) => Some(sample),
+                Err(e) => {
+                    debug!("WMI power sensors unavailable, using power model: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
 
-        // Calculate GPU power (simplified - would need GPU-specific APIs)
-        let gpu_watts = self.calculate_gpu_power().await;
+        // Wall power covers the whole host, so it does not apply inside a container
+        let wall_watts = match (&self.smart_plug, &self.cgroup) {
+            (Some(plug), None) => plug.latest_watts(),
+            _ => None,
+        };
 
-        // Calculate network power
-        let network_watts = self.calculate_network_power(&system);
+        // Measured GPU power when a vendor backend is available, otherwise estimated
+        let gpus = self.gpu_monitor.read_all();
+        let measured_gpu_watts = apple_power.as_ref().map(|sample| sample.gpu_watts)
+            .or_else(|| (!gpus.is_empty()).then(|| gpus.iter().map(|gpu| gpu.power_watts).sum()))
+            .or_else(|| wmi_power.as_ref().and_then(|sample| sample.gpu_watts));
+        let gpu_watts = match measured_gpu_watts {
+            Some(watts) => watts,
+            None => self.calculate_gpu_power().await,
+        };
+        let npu_watts = apple_power.as_ref().map_or(0.0, |sample| sample.ane_watts);
 
-        // Get battery information
-        let (battery_level, battery_time_remaining, is_charging) = 
-            self.get_battery_info().await;
+        // PSU input power covers the whole server, so like wall power it skips containers
+        let ipmi_power = match (&self.ipmi, &self.cgroup) {
+            (Some(reader), None) => match reader.sample().await {
+                Ok(sample) => Some(sample),
+                Err(e) => {
+                    debug!("IPMI power sensors unavailable: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
 
-        let total_watts = self.baseline_power + cpu_watts + gpu_watts + memory_watts + network_watts;
+        // Get battery information; a UPS stands in when the node has no battery of its own
+        let ups = self.read_ups().await;
+        let batteries = self.read_batteries();
+        let (battery_level, battery_time_remaining, is_charging) = match (batteries::aggregate(&batteries), &ups) {
+            ((None, _, _), Some(status)) => status.battery_info(),
+            (battery, _) => battery,
+        };
 
-        // Calculate efficiency score
-        let efficiency_score = self.calculate_efficiency_score(total_watts, cpu_usage);
+        // The sysinfo guard is not Send, so it must be released before the next await
+        let ComponentSample {
+            cpu_watts,
+            memory_watts,
+            network_watts,
+            total_watts,
+            measurement_source,
+            node_process,
+            subsystems,
+            efficiency_score,
+            timestamp,
+            thermal,
+        } = {
+            let system = self.system.read().unwrap();
+
+            // Calculate CPU power consumption
+            let cpu_usage = system.global_cpu_info().cpu_usage() / 100.0;
+            let measured_cpu_watts = apple_power.as_ref().map(|sample| sample.cpu_watts)
+                .or_else(|| wmi_power.as_ref().and_then(|sample| sample.package_watts))
+                .or_else(|| measured.as_ref().map(|sample| sample.package_watts));
+            let cpu_watts = match measured_cpu_watts {
+                Some(watts) => watts,
+                None => self.calculate_cpu_power(cpu_usage),
+            };
+
+            // Calculate memory power consumption
+            let memory_usage = system.used_memory() as f64 / system.total_memory() as f64;
+            let memory_watts = match measured.as_ref().and_then(|sample| sample.dram_watts) {
+                Some(dram_watts) => dram_watts,
+                None => self.calculate_memory_power(memory_usage),
+            };
+
+            // Containers only own part of the host; network counters are already per-namespace
+            let (baseline_watts, cpu_watts, memory_watts) = match &self.cgroup {
+                Some(cgroup) => {
+                    let share = ContainerShare::compute(
+                        &cgroup.limits(),
+                        cgroup.sample_cores_used(),
+                        system.cpus().len(),
+                        cpu_usage as f64,
+                        cgroup.memory_current(),
+                        system.used_memory(),
+                    );
+                    (self.baseline_power() * share.baseline, cpu_watts * share.cpu, memory_watts * share.memory)
+                }
+                None => (self.baseline_power(), cpu_watts, memory_watts),
+            };
+
+            // Calculate network power
+            let network_watts = self.calculate_network_power(&system);
+
+            let mut total_watts =
+                baseline_watts + cpu_watts + gpu_watts + memory_watts + network_watts + npu_watts;
+
+            // Board input sensors measure the whole device: keep the modeled split for the other
+            // components and give the CPU whatever remains
+            let mut measurement_source = if measured_cpu_watts.is_some() {
+                MeasurementSource::Counters
+            } else {
+                MeasurementSource::Model
+            };
+            let cpu_watts = match board_watts {
+                Some(board) if self.cgroup.is_none() => {
+                    let others = total_watts - cpu_watts;
+                    total_watts = board;
+                    measurement_source = MeasurementSource::BoardSensor;
+                    (board - others).max(0.0)
+                }
+                _ => cpu_watts,
+            };
+
+            // PSU input sensors measure at the wall side of the supplies
+            if let Some(psu_watts) = ipmi_power.as_ref().and_then(|sample| sample.total_watts()) {
+                total_watts = psu_watts;
+                measurement_source = MeasurementSource::Ipmi;
+            }
+
+            // Wall power is ground truth (PSU losses included); components stay modeled for attribution
+            if let Some(plug) = &self.smart_plug {
+                match wall_watts {
+                    Some(wall) => {
+                        plug.calibration().observe(wall, total_watts);
+                        total_watts = wall;
+                        measurement_source = MeasurementSource::SmartPlug;
+                    }
+                    None if measurement_source == MeasurementSource::Model && self.cgroup.is_none() => {
+                        if let Some(scale) = plug.calibration().scale() {
+                            total_watts *= scale;
+                            measurement_source = MeasurementSource::CalibratedModel;
+                        }
+                    }
+                    None => {}
+                }
+            }
+
+            // Attribute part of the host draw to the node process and its subsystems
+            let host_power = HostPower {
+                total_watts,
+                cpu_watts,
+                memory_watts,
+                cpu_utilization: cpu_usage as f64,
+                used_memory_bytes: system.used_memory(),
+            };
+            let node_process = self.process_attributor.as_ref().and_then(|attributor| attributor.sample(&system, &host_power));
+            let subsystems = self.subsystem_sampler.sample(&host_power, system.cpus().len());
+
+            // Calculate efficiency score
+            let efficiency_score = self.calculate_efficiency_score(total_watts, cpu_usage);
+
+            let timestamp = chrono::Utc::now().timestamp() as u64;
+
+            // Thermal state: throttle proofs and batches while running hot
+            let thermal = self.thermal.config().enabled.then(|| {
+                let (cpu_temp_c, gpu_temp_c) = thermal::read_temperatures(&system, &gpus);
+                let (reading, event) = self.thermal.update(cpu_temp_c, gpu_temp_c, timestamp);
+                if let Some(event) = event {
+                    match event.kind {
+                        ThermalEventKind::ThrottleStarted => warn!(
+                            "🌡️ Thermal limit exceeded (CPU {:?}°C, GPU {:?}°C), throttling workload",
+                            cpu_temp_c, gpu_temp_c
+                        ),
+                        ThermalEventKind::ThrottleEnded => info!("🌡️ Temperatures back to normal, throttle lifted"),
+                    }
+                    self.energy_history.write().unwrap().record_thermal_event(event);
+                }
+                reading
+            });
+
+            ComponentSample {
+                cpu_watts,
+                memory_watts,
+                network_watts,
+                total_watts,
+                measurement_source,
+                node_process,
+                subsystems,
+                efficiency_score,
+                timestamp,
+                thermal,
+            }
+        };
 
         // Calculate carbon footprint
-        let carbon_footprint_kg_per_hour = (total_watts / 1000.0) * self.carbon_intensity;
+        let carbon_intensity = self.carbon_provider.current_intensity().await;
+        let gross_carbon_kg_per_hour = (total_watts / 1000.0) * carbon_intensity;
+        let source_mix = match &self.renewable {
+            Some(tracker) => Some(tracker.source_mix(total_watts).await),
+            None => None,
+        };
+        let carbon_footprint_kg_per_hour = source_mix
+            .map_or(gross_carbon_kg_per_hour, |mix| mix.net_carbon(gross_carbon_kg_per_hour));
 
         let energy_data = EnergyData {
             total_watts,
@@ -184,25 +909,47 @@ impl EnergyMonitor {
             gpu_watts,
             memory_watts,
             network_watts,
+            npu_watts,
             battery_level,
             battery_time_remaining,
             is_charging,
             efficiency_score,
             carbon_footprint_kg_per_hour,
-            timestamp: chrono::Utc::now().timestamp() as u64,
+            timestamp,
+            gpus,
+            power_rails: wmi_power.map(|sample| sample.rails).unwrap_or_default(),
+            node_process,
+            thermal,
+            measurement_source,
+            ups,
+            source_mix,
+            batteries,
+            psus: ipmi_power.map(|sample| sample.psus).unwrap_or_default(),
+            subsystems,
         };
 
-        // Store in history
-        {
+        // Store in history (bounded ring buffer + rollups)
+        let anomaly = {
             let mut history = self.energy_history.write().unwrap();
             history.push(energy_data.clone());
-            
-            // Keep only last 1000 readings
-            if history.len() > 1000 {
-                history.remove(0);
+            self.anomaly_detector.write().unwrap().evaluate(history.raw())
+        };
+
+        if let Some(anomaly) = anomaly {
+            let threat = anomaly.to_threat();
+            warn!("🚨 Possible cryptojacking on host: {}", threat.explanation);
+            // No subscribers just means nobody is listening yet
+            let _ = self.threat_events.send(threat);
+        }
+
+        if let Some(store) = &self.history_store {
+            if let Err(e) = store.insert(&energy_data) {
+                warn!("Failed to persist energy reading: {}", e);
             }
         }
 
+        self.check_budgets(&energy_data);
+
         debug!("⚡ Energy consumption: {:.1}W (CPU: {:.1}W, GPU: {:.1}W, MEM: {:.1}W, NET: {:.1}W)", 
                total_watts, cpu_watts, gpu_watts, memory_watts, network_watts);
 
@@ -216,17 +963,32 @@ impl EnergyMonitor {
         let cpu_cores = system.cpus().len() as u32;
         let cpu_base_frequency = cpu.frequency() as f64 / 1000.0; // Convert MHz to GHz
         
-        // Estimate CPU TDP based on model (simplified)
-        let cpu_tdp = Self::estimate_cpu_tdp(&cpu_model, cpu_cores);
+        // Estimate CPU TDP based on model (simplified); ARM SoCs use their measured peak
+        let arm_soc = arm::detect_soc(&cpu_model);
+        let cpu_tdp = match arm_soc {
+            Some(soc) => soc.power_curve(cpu_cores).max_watts,
+            None => Self::estimate_cpu_tdp(&cpu_model, cpu_cores),
+        };
         
         let memory_size_gb = (system.total_memory() / 1024 / 1024 / 1024) as u32;
-        let memory_type = "DDR4".to_string(); // Simplified detection
+        // SMBIOS memory type; DDR4 when it cannot be read (e.g. dmidecode without root)
+        let memory_generation = hardware::detect_memory_type();
+        let memory_type = memory_generation.map_or("DDR4".to_string(), |generation| generation.to_string());
         
         // GPU detection (simplified - would need platform-specific APIs)
-        let (gpu_model, gpu_memory_gb) = Self::detect_gpu();
+        let (gpu_model, gpu_memory_gb, gpu_adapters) = Self::detect_gpu();
         
-        // Storage type detection (simplified)
-        let storage_type = "SSD".to_string(); // Most modern systems
+        // Primary storage is the most power-hungry kind present; assume SSD when unknown
+        let storage_devices = hardware::detect_storage();
+        let storage_type = storage_devices
+            .iter()
+            .map(|device| device.kind)
+            .max_by_key(|kind| match kind {
+                StorageKind::SataSsd => 0,
+                StorageKind::Nvme => 1,
+                StorageKind::Hdd => 2,
+            })
+            .map_or("SSD".to_string(), |kind| kind.to_string());
         
         // Network interfaces
         let network_interfaces: Vec<String> = system.networks()
@@ -245,67 +1007,133 @@ impl EnergyMonitor {
             gpu_memory_gb,
             storage_type,
             network_interfaces,
+            memory_generation,
+            storage_devices,
+            gpu_adapters,
+            arm_soc,
+            battery_health: Vec::new(),
         }
     }
 
     /// Calculate baseline power consumption
-    fn calculate_baseline_power(specs: &HardwareSpecs) -> f64 {
+    fn calculate_baseline_power(specs: &HardwareSpecs, coefficients: &PowerCoefficients) -> f64 {
+        // Measured SoC curves already cover the whole board (DRAM, storage, USB)
+        if let Some(soc) = specs.arm_soc.filter(ArmSoc::is_single_board) {
+            return soc.power_curve(specs.cpu_cores).idle_watts + coefficients.idle_offset_watts;
+        }
+
+        // CPU idle power is part of the CPU term (model or counters), not the baseline
         let mut baseline = 0.0;
         
-        // CPU idle power (typically 10-20% of TDP)
-        baseline += specs.cpu_tdp * 0.15;
-        
         // Memory power
-        baseline += specs.memory_size_gb as f64 * 3.0;
+        baseline += specs.memory_size_gb as f64 * coefficients.memory_per_gb * Self::memory_power_factor(specs);
         
-        // GPU idle power
-        if specs.gpu_model.is_some() {
-            baseline += 20.0; // Typical GPU idle power
-        }
+        // GPU idle power from the vendor table
+        baseline += specs.gpu_adapters
+            .iter()
+            .map(|adapter| coefficients.gpu_profile(adapter).idle_watts)
+            .sum::<f64>();
         
-        // Motherboard, storage, fans, etc.
-        baseline += 25.0;
+        // Storage devices (one SATA SSD when detection found nothing)
+        baseline += if specs.storage_devices.is_empty() {
+            coefficients.storage_ssd
+        } else {
+            specs.storage_devices
+                .iter()
+                .map(|device| match device.kind {
+                    StorageKind::Nvme => coefficients.storage_nvme,
+                    StorageKind::SataSsd => coefficients.storage_ssd,
+                    StorageKind::Hdd => coefficients.storage_hdd,
+                })
+                .sum()
+        };
+
+        // Motherboard, fans, etc.
+        baseline += 23.0;
         
-        baseline
+        baseline + coefficients.idle_offset_watts
     }
 
-    /// Calculate CPU power consumption based on usage
+    /// Host power that does not depend on load: memory, GPU idle, storage and board
+    pub fn baseline_power(&self) -> f64 {
+        Self::calculate_baseline_power(&self.hardware_specs, &self.power_coefficients.read().unwrap())
+    }
+
+    /// Calculate CPU power consumption (idle included) based on usage
     fn calculate_cpu_power(&self, usage: f32) -> f64 {
+        if self.power_coefficients.read().unwrap().cpu_dynamic_watts.is_some() {
+            let model = self.cpu_model();
+            return model.idle_watts + model.dynamic_watts * usage as f64;
+        }
+
+        // ARM: the SoC curve; single-board curves leave idle in the baseline with the board
+        if let Some(soc) = self.hardware_specs.arm_soc {
+            let curve = soc.power_curve(self.hardware_specs.cpu_cores);
+            let board_idle = if soc.is_single_board() { curve.idle_watts } else { 0.0 };
+            return curve.power_at(usage as f64) - board_idle;
+        }
+
         let base_power = self.hardware_specs.cpu_tdp * 0.15; // Idle power
         let max_additional = self.hardware_specs.cpu_tdp * 0.85; // Max additional power
         
         base_power + (max_additional * usage as f64)
     }
 
+    /// Linear CPU terms currently in use (calibrated, or the TDP/SoC defaults)
+    fn cpu_model(&self) -> CpuModel {
+        let (idle_watts, dynamic_watts) = match self.hardware_specs.arm_soc {
+            Some(soc) => {
+                let curve = soc.power_curve(self.hardware_specs.cpu_cores);
+                let idle = if soc.is_single_board() { 0.0 } else { curve.idle_watts };
+                (idle, curve.max_watts - curve.idle_watts)
+            }
+            None => (self.hardware_specs.cpu_tdp * 0.15, self.hardware_specs.cpu_tdp * 0.85),
+        };
+
+        let coefficients = self.power_coefficients.read().unwrap();
+        CpuModel {
+            idle_watts: coefficients.cpu_idle_watts.unwrap_or(idle_watts),
+            dynamic_watts: coefficients.cpu_dynamic_watts.unwrap_or(dynamic_watts),
+            idle_offset_watts: coefficients.idle_offset_watts,
+        }
+    }
+
     /// Calculate memory power consumption
     fn calculate_memory_power(&self, usage: f64) -> f64 {
-        let base_power = self.hardware_specs.memory_size_gb as f64 * 2.0;
-        let additional_power = self.hardware_specs.memory_size_gb as f64 * 1.0 * usage;
+        if self.hardware_specs.arm_soc.is_some_and(|soc| soc.is_single_board()) {
+            return 0.0; // Included in the SoC curve
+        }
+
+        let factor = Self::memory_power_factor(&self.hardware_specs);
+        let base_power = self.hardware_specs.memory_size_gb as f64 * 2.0 * factor;
+        let additional_power = self.hardware_specs.memory_size_gb as f64 * 1.0 * usage * factor;
         
         base_power + additional_power
     }
 
+    /// Per-GB power relative to DDR4 for the detected memory generation
+    fn memory_power_factor(specs: &HardwareSpecs) -> f64 {
+        specs.memory_generation.map_or(1.0, |generation| generation.power_factor())
+    }
+
     /// Calculate GPU power consumption (simplified)
     async fn calculate_gpu_power(&self) -> f64 {
-        if self.hardware_specs.gpu_model.is_none() {
-            return 0.0;
-        }
-
-        // In a real implementation, you would use:
-        // - NVIDIA ML (nvidia-ml-py) for NVIDIA GPUs
-        // - AMD GPU tools for AMD GPUs
-        // - Intel GPU tools for Intel GPUs
-        
-        // For now, estimate based on typical usage
-        let base_gpu_power = 30.0; // Idle GPU power
+        // Measured backends are preferred; this models GPUs from the vendor power table
         let estimated_usage = 0.1; // 10% usage for crypto operations
-        let max_gpu_power = 200.0; // Typical gaming GPU max power
-        
-        base_gpu_power + (max_gpu_power * estimated_usage)
+        let coefficients = self.power_coefficients.read().unwrap();
+
+        self.hardware_specs.gpu_adapters
+            .iter()
+            .map(|adapter| {
+                let profile = coefficients.gpu_profile(adapter);
+                profile.idle_watts + (profile.active_watts - profile.idle_watts) * estimated_usage
+            })
+            .sum()
     }
 
     /// Calculate network power consumption
     fn calculate_network_power(&self, system: &System) -> f64 {
+        let per_mbps = self.power_coefficients.read().unwrap().network_per_mbps;
         let mut total_network_power = 0.0;
         
         for (interface_name, network) in system.networks() {
@@ -317,7 +1145,7 @@ impl EnergyMonitor {
             let bytes_per_sec = network.received() + network.transmitted();
             let mbps = (bytes_per_sec as f64 * 8.0) / (1024.0 * 1024.0);
             
-            total_network_power += mbps * self.power_coefficients.network_per_mbps;
+            total_network_power += mbps * per_mbps;
         }
         
         // Add base network interface power
@@ -326,35 +1154,74 @@ impl EnergyMonitor {
         total_network_power
     }
 
-    /// Get REAL battery information
-    async fn get_battery_info(&self) -> (Option<f64>, Option<Duration>, Option<bool>) {
-        if let Some(ref manager) = self.battery_manager {
-            match manager.batteries() {
-                Ok(batteries) => {
-                    for battery_result in batteries {
-                        if let Ok(battery) = battery_result {
-                            let level = battery.state_of_charge().value as f64 * 100.0;
-                            
-                            let time_remaining = battery.time_to_empty()
-                                .map(|t| Duration::from_secs(t.value as u64));
-                            
-                            let is_charging = match battery.state() {
-                                battery::State::Charging => Some(true),
-                                battery::State::Discharging => Some(false),
-                                _ => None,
-                            };
-                            
-                            return (Some(level), time_remaining, is_charging);
-                        }
-                    }
+    /// Current UPS state; None without a UPS or when upsd is unreachable
+    async fn read_ups(&self) -> Option<UpsStatus> {
+        let client = self.ups.as_ref()?;
+        match client.status().await {
+            Ok(status) => {
+                let was_on_battery = client.swap_on_battery(status.on_battery);
+                if status.on_battery && !was_on_battery {
+                    warn!("🔋 UPS on battery ({:?}% charge, {:?}s runtime)", status.battery_charge, status.runtime_secs);
+                } else if !status.on_battery && was_on_battery {
+                    info!("🔌 UPS back on line power");
                 }
+                Some(status)
+            }
+            Err(e) => {
+                debug!("UPS query failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Get REAL battery information for every battery
+    fn read_batteries(&self) -> Vec<BatteryReading> {
+        if let Some(provider) = &self.mobile_battery {
+            return match provider.read() {
+                Ok(reading) => vec![reading.to_battery_reading()],
                 Err(e) => {
-                    debug!("Battery info error: {}", e);
+                    debug!("Mobile battery error: {}", e);
+                    Vec::new()
                 }
+            };
+        }
+        let Some(manager) = &self.battery_manager else {
+            return Vec::new();
+        };
+        match manager.batteries() {
+            Ok(batteries) => batteries
+                .enumerate()
+                .filter_map(|(index, battery)| Some(BatteryReading::from_battery(index, &battery.ok()?)))
+                .collect(),
+            Err(e) => {
+                debug!("Battery info error: {}", e);
+                Vec::new()
             }
         }
-        
-        (None, None, None)
+    }
+
+    /// Capacity and cycle count of every battery
+    fn read_battery_health(manager: &BatteryManager) -> Vec<BatteryHealth> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        match manager.batteries() {
+            Ok(batteries) => batteries
+                .enumerate()
+                .filter_map(|(index, battery)| Some(BatteryHealth::from_battery(index, &battery.ok()?, now)))
+                .collect(),
+            Err(e) => {
+                debug!("Battery health error: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Hardware description reported to the network, with current battery health
+    pub fn hardware_attestation(&self) -> HardwareSpecs {
+        let mut specs = self.hardware_specs.clone();
+        if let Some(tracker) = &self.battery_health {
+            specs.battery_health = tracker.latest();
+        }
+        specs
     }
 
     /// Calculate efficiency score based on power usage and performance
@@ -368,15 +1235,9 @@ impl EnergyMonitor {
         efficiency as u8
     }
 
-    /// Get regional carbon intensity (simplified)
+    /// Get regional carbon intensity before a live provider is configured
     fn get_regional_carbon_intensity() -> f64 {
-        // In a real implementation, this would:
-        // 1. Detect user's location
-        // 2. Query real-time grid carbon intensity APIs
-        // 3. Use services like WattTime, electricityMap, etc.
-        
-        // For now, use global average
-        0.475 // kg CO2 per kWh (global average)
+        carbon::GLOBAL_AVERAGE_INTENSITY // kg CO2 per kWh (global average)
     }
 
     /// Estimate CPU TDP based on model
@@ -408,23 +1269,193 @@ impl EnergyMonitor {
         }
     }
 
-    /// Detect GPU (simplified)
-    fn detect_gpu() -> (Option<String>, Option<u32>) {
-        // In a real implementation, use:
-        // - Windows: WMI queries
-        // - Linux: lspci, nvidia-smi, etc.
-        // - macOS: system_profiler
-        
-        // For now, return None (integrated graphics assumed)
-        (None, None)
+    /// Detect GPUs through adapter enumeration
+    fn detect_gpu() -> (Option<String>, Option<u32>, Vec<GpuAdapter>) {
+        let adapters = gpu_adapters::enumerate_adapters();
+        let primary = adapters
+            .iter()
+            .find(|adapter| !adapter.integrated)
+            .or_else(|| adapters.first());
+
+        let gpu_model = primary.map(|adapter| adapter.name.clone());
+        let gpu_memory_gb = primary
+            .and_then(|adapter| adapter.vram_mb)
+            .map(|mb| (mb / 1024) as u32);
+
+        (gpu_model, gpu_memory_gb, adapters)
+    }
+
+    /// Copy VRAM sizes reported by vendor backends onto enumerated adapters
+    fn fill_gpu_memory(specs: &mut HardwareSpecs, readings: &[GpuReading]) {
+        for adapter in specs.gpu_adapters.iter_mut().filter(|adapter| adapter.vram_mb.is_none()) {
+            let vendor = format!("{:?}", adapter.vendor).to_uppercase();
+            adapter.vram_mb = readings
+                .iter()
+                .filter(|reading| reading.vendor.to_uppercase() == vendor)
+                .find_map(|reading| reading.memory_total_mb);
+        }
+
+        if specs.gpu_memory_gb.is_none() {
+            let primary = specs.gpu_adapters.iter().find(|adapter| !adapter.integrated);
+            specs.gpu_memory_gb = primary
+                .and_then(|adapter| adapter.vram_mb)
+                .map(|mb| (mb / 1024) as u32);
+        }
+    }
+
+    /// Most recent reading taken by the monitoring loop
+    pub fn latest_reading(&self) -> Option<EnergyData> {
+        self.energy_history.read().unwrap().latest().cloned()
     }
 
     /// Get energy statistics
     pub fn get_energy_stats(&self) -> Result<EnergyStats> {
+        let interval_secs = self.sampling_interval_secs();
         let history = self.energy_history.read().unwrap();
-        
+        Ok(Self::compute_stats(history.raw(), interval_secs))
+    }
+
+    /// Get energy statistics since `since` (unix seconds) from the minute/hour rollups;
+    /// cheap even for weekly or monthly horizons
+    pub fn get_rollup_stats(&self, since: u64) -> EnergyStats {
+        let rollups = self.energy_history.read().unwrap().rollups_since(since);
+
+        match rollup::combine(&rollups) {
+            Some(total) => EnergyStats {
+                avg_power_watts: total.avg_watts,
+                min_power_watts: total.min_watts,
+                max_power_watts: total.max_watts,
+                total_energy_kwh: total.energy_kwh,
+                avg_efficiency_score: total.avg_efficiency as u8,
+                total_carbon_kg: total.carbon_kg,
+                uptime_hours: total.covered_secs as f64 / 3600.0,
+                // Rollups do not keep the per-subsystem split
+                subsystems: Vec::new(),
+            },
+            None => Self::compute_stats(&[], self.sampling_interval_secs()),
+        }
+    }
+
+    /// Readings with `from <= timestamp < to` (unix seconds), from the store when configured
+    fn readings_in_range(&self, from: u64, to: u64) -> Result<Vec<EnergyData>> {
+        Ok(match &self.history_store {
+            Some(store) => store.range(from, to)?,
+            None => self.energy_history.read().unwrap()
+                .raw()
+                .iter()
+                .filter(|d| d.timestamp >= from && d.timestamp < to)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Get energy statistics for readings with `from <= timestamp < to` (unix seconds)
+    pub fn get_energy_stats_range(&self, from: u64, to: u64) -> Result<EnergyStats> {
+        let readings = self.readings_in_range(from, to)?;
+        Ok(Self::compute_stats(&readings, self.sampling_interval_secs()))
+    }
+
+    /// Export readings in `[from, to)` to `path`; returns the number of rows written
+    pub fn export(&self, (from, to): (u64, u64), format: ExportFormat, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let readings = self.readings_in_range(from, to)?;
+        let rows = export::export_readings(&readings, format, path)?;
+        info!("📤 Exported {} energy readings to {}", rows, path.display());
+        Ok(rows)
+    }
+
+    /// Forecast the next 24 hours from hourly history (persisted store when configured)
+    pub async fn get_energy_forecast(&self) -> Result<EnergyForecast> {
+        const LOOKBACK_SECS: u64 = 14 * 24 * 3600;
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        // The history lock is released before the (slow) persisted scan below
+        let (in_memory_hours, battery_runway, source_mix) = {
+            let history = self.energy_history.read().unwrap();
+            let raw = history.raw();
+            let in_memory_hours = self.history_store.is_none().then(|| history.hour_rollups());
+            (in_memory_hours, forecast::battery_runway(raw), raw.last().and_then(|reading| reading.source_mix))
+        };
+        let hours = match &self.history_store {
+            Some(store) => {
+                let mut replay = EnergyHistory::new(1);
+                for reading in store.range(now.saturating_sub(LOOKBACK_SECS), now + 1)? {
+                    replay.push(reading);
+                }
+                replay.hour_rollups()
+            }
+            None => in_memory_hours.unwrap_or_default(),
+        };
+
+        let predicted = forecast::forecast_series(&forecast::hourly_watts(&hours), forecast::HORIZON_HOURS, Smoothing::default())
+            .context("No energy history to forecast from")?;
+
+        // Grid forecast when the provider has one, otherwise the intensity seen so far
+        let grid = self.carbon_provider.forecast(forecast::HORIZON_HOURS as u32).await;
+        let hourly_intensity: Vec<f64> = if grid.is_empty() {
+            let total = rollup::combine(&hours);
+            vec![total
+                .filter(|total| total.energy_kwh > 0.0)
+                .map_or(self.carbon_intensity, |total| total.carbon_kg / total.energy_kwh)]
+        } else {
+            (0..forecast::HORIZON_HOURS as u64)
+                .map(|hour| {
+                    let start = now + hour * 3600;
+                    let intensity = grid
+                        .iter()
+                        .filter(|point| point.timestamp < start + 3600)
+                        .next_back()
+                        .map_or(self.carbon_intensity, |point| point.intensity);
+                    source_mix.map_or(intensity, |mix| mix.net_carbon(intensity))
+                })
+                .collect()
+        };
+
+        Ok(EnergyForecast::new(now, predicted, &hourly_intensity, battery_runway))
+    }
+
+    /// Carbon report for a calendar month (UTC); sign it with `CarbonReport::sign`
+    pub async fn monthly_carbon_report(
+        &self,
+        node_id: &str,
+        year: i32,
+        month: u32,
+        offsets: Vec<CarbonOffset>,
+    ) -> Result<CarbonReport> {
+        let period = carbon_report::month_bounds(year, month)?;
+        let stats = self.get_energy_stats_range(period.0, period.1)?;
+        // The provider only knows the current mix, which says nothing about a past month
+        let in_progress = period.1 > chrono::Utc::now().timestamp() as u64;
+        let grid_mix = if in_progress { self.carbon_provider.grid_mix().await } else { None };
+
+        info!("🌱 Carbon report {}-{:02}: {:.2} kWh, {:.2} kg CO2", year, month,
+              stats.total_energy_kwh, stats.total_carbon_kg);
+
+        Ok(CarbonReport::new(node_id, period, &stats, grid_mix, offsets))
+    }
+
+    /// ZK proof that average power over `[from, to)` stayed at or below `max_avg_watts`,
+    /// without disclosing the actual consumption or uptime
+    pub async fn prove_average_power_below(
+        &self,
+        prover: &ZKProver,
+        node_id: &str,
+        (from, to): (u64, u64),
+        max_avg_watts: f64,
+    ) -> Result<EnergyRangeProof> {
+        let stats = self.get_energy_stats_range(from, to)?;
+        let energy_mj = (stats.total_energy_kwh * 3_600_000_000.0).round() as u64;
+        let uptime_secs = (stats.uptime_hours * 3600.0).round() as u64;
+
+        prover
+            .generate_energy_range_proof(energy_mj, uptime_secs.min(to.saturating_sub(from)), (from, to), max_avg_watts, node_id)
+            .await
+    }
+
+    /// Summarize readings taken every `interval_secs`
+    fn compute_stats(history: &[EnergyData], interval_secs: u64) -> EnergyStats {
         if history.is_empty() {
-            return Ok(EnergyStats {
+            return EnergyStats {
                 avg_power_watts: 0.0,
                 min_power_watts: 0.0,
                 max_power_watts: 0.0,
@@ -432,20 +1463,21 @@ impl EnergyMonitor {
                 avg_efficiency_score: 100,
                 total_carbon_kg: 0.0,
                 uptime_hours: 0.0,
-            });
+                subsystems: Vec::new(),
+            };
         }
 
         let avg_power = history.iter().map(|d| d.total_watts).sum::<f64>() / history.len() as f64;
         let min_power = history.iter().map(|d| d.total_watts).fold(f64::INFINITY, f64::min);
         let max_power = history.iter().map(|d| d.total_watts).fold(f64::NEG_INFINITY, f64::max);
         
-        let uptime_hours = history.len() as f64 / 120.0; // Assuming 30-second intervals
+        let uptime_hours = history.len() as f64 * interval_secs as f64 / 3600.0;
         let total_energy_kwh = (avg_power * uptime_hours) / 1000.0;
         
         let avg_efficiency = history.iter().map(|d| d.efficiency_score as f64).sum::<f64>() / history.len() as f64;
         let total_carbon = history.iter().map(|d| d.carbon_footprint_kg_per_hour).sum::<f64>() * (uptime_hours / history.len() as f64);
 
-        Ok(EnergyStats {
+        EnergyStats {
             avg_power_watts: avg_power,
             min_power_watts: min_power,
             max_power_watts: max_power,
@@ -453,9 +1485,171 @@ impl EnergyMonitor {
             avg_efficiency_score: avg_efficiency as u8,
             total_carbon_kg: total_carbon,
             uptime_hours,
+            subsystems: subsystems::breakdown(history, interval_secs as f64 / 3600.0),
+        }
+    }
+
+    /// Calibrate the power model: hold each configured load step (plus `workload`, e.g. proof
+    /// generation) while comparing against the smart plug or RAPL, then apply and persist the fit
+    pub async fn calibrate(&self, workload: Option<CalibrationWorkload>) -> Result<CalibratedCoefficients> {
+        let reference = if self.smart_plug.is_some() && self.cgroup.is_none() {
+            CalibrationReference::SmartPlug
+        } else if self.rapl.is_some() {
+            CalibrationReference::Rapl
+        } else {
+            return Err(anyhow::anyhow!("Calibration needs RAPL counters or a smart plug"));
+        };
+
+        let config = self.config.calibration.clone();
+        let cores = self.hardware_specs.cpu_cores as usize;
+        info!("🎛️ Calibrating power model against {:?} ({} steps of {}s)",
+              reference, config.load_steps.len() + workload.is_some() as usize, config.step_secs);
+
+        let mut steps = Vec::new();
+        for &load in &config.load_steps {
+            let _load = CpuLoad::start(load, cores);
+            steps.push(self.measure_calibration_step(format!("{:.0}% CPU", load * 100.0), reference, &config).await?);
+        }
+
+        if let Some(workload) = workload {
+            let stop = Arc::new(AtomicBool::new(false));
+            let runner = {
+                let stop = stop.clone();
+                tokio::spawn(async move {
+                    while !stop.load(Ordering::Relaxed) {
+                        workload().await?;
+                    }
+                    anyhow::Ok(())
+                })
+            };
+            let step = self.measure_calibration_step("proof generation".to_string(), reference, &config).await;
+            stop.store(true, Ordering::Relaxed);
+            runner.await.context("Calibration workload panicked")??;
+            steps.push(step?);
+        }
+
+        for step in &steps {
+            debug!("   {}: {:.1}% CPU, {:.1}W measured, {:.1}W modeled",
+                   step.label, step.cpu_usage * 100.0, step.reference_watts, step.modeled_watts);
+        }
+
+        let calibration = CalibratedCoefficients::fit(reference, steps, self.cpu_model())?;
+        info!("✅ Calibration done: CPU {:.1}W dynamic, idle offset {:+.1}W (R² {:.3})",
+              calibration.cpu_dynamic_watts, calibration.idle_offset_watts, calibration.r_squared);
+
+        if let Some(path) = &config.path {
+            calibration.save(path)?;
+        }
+        self.power_coefficients.write().unwrap().apply_calibration(&calibration);
+
+        Ok(calibration)
+    }
+
+    /// Average reference and modeled power over one load step
+    async fn measure_calibration_step(
+        &self,
+        label: String,
+        reference: CalibrationReference,
+        config: &CalibrationConfig,
+    ) -> Result<CalibrationStep> {
+        sleep(Duration::from_secs(config.settle_secs)).await;
+        // Restart the counter interval so the settle period is excluded
+        self.read_calibration_reference(reference).await;
+        self.system.write().unwrap().refresh_cpu();
+
+        let deadline = Instant::now() + Duration::from_secs(config.step_secs);
+        let mut samples = Vec::new();
+        while Instant::now() < deadline {
+            sleep(Duration::from_secs(1)).await;
+
+            let (usage, memory_usage) = {
+                let mut system = self.system.write().unwrap();
+                system.refresh_cpu();
+                system.refresh_memory();
+                (system.global_cpu_info().cpu_usage() / 100.0, system.used_memory() as f64 / system.total_memory() as f64)
+            };
+            let Some(reference_watts) = self.read_calibration_reference(reference).await else {
+                continue;
+            };
+
+            let modeled_watts = match reference {
+                CalibrationReference::Rapl => self.calculate_cpu_power(usage),
+                CalibrationReference::SmartPlug => {
+                    self.baseline_power()
+                        + self.calculate_cpu_power(usage)
+                        + self.calculate_memory_power(memory_usage)
+                        + self.calculate_gpu_power().await
+                }
+            };
+            samples.push((usage as f64, reference_watts, modeled_watts));
+        }
+
+        if samples.is_empty() {
+            return Err(anyhow::anyhow!("No {:?} readings during the {} step", reference, label));
+        }
+        let n = samples.len() as f64;
+        Ok(CalibrationStep {
+            label,
+            cpu_usage: samples.iter().map(|sample| sample.0).sum::<f64>() / n,
+            reference_watts: samples.iter().map(|sample| sample.1).sum::<f64>() / n,
+            modeled_watts: samples.iter().map(|sample| sample.2).sum::<f64>() / n,
         })
     }
 
+    async fn read_calibration_reference(&self, reference: CalibrationReference) -> Option<f64> {
+        match reference {
+            CalibrationReference::Rapl => {
+                let rapl = Arc::clone(self.rapl.as_ref()?);
+                let sample = tokio::task::spawn_blocking(move || rapl.sample()).await.ok()?;
+                sample.ok().flatten().map(|sample| sample.package_watts)
+            }
+            CalibrationReference::SmartPlug => self.smart_plug.as_ref()?.read_watts().await.ok(),
+        }
+    }
+
+    /// Start tasks registered with `scheduler` once carbon intensity and power state allow
+    pub async fn run_task_scheduler(&self) {
+        let config = self.scheduler.config().clone();
+        info!("🌱 Carbon-aware scheduler running (every {}s)", config.check_interval_secs);
+
+        loop {
+            tokio::select! {
+                _ = self.cancel.cancelled() => return,
+                _ = sleep(Duration::from_secs(config.check_interval_secs.max(1))) => {}
+            }
+            if self.scheduler.pending_count() == 0 {
+                continue;
+            }
+
+            let conditions = SchedulingConditions {
+                now: chrono::Utc::now().timestamp() as u64,
+                intensity: self.carbon_provider.current_intensity().await,
+                forecast: self.carbon_provider.forecast(config.forecast_horizon_hours).await,
+                on_mains: self.current_power_policy().is_charging != Some(false),
+            };
+
+            for task in self.scheduler.due_tasks(&conditions) {
+                info!("🌱 Running deferred task {} at {:.3} kg CO2/kWh", task.name, conditions.intensity);
+                tokio::spawn(async move {
+                    if let Err(e) = (task.run)().await {
+                        warn!("Deferred task {} failed: {}", task.name, e);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Run `start_monitoring` as a background task
+    pub fn spawn_monitoring(self: &Arc<Self>) -> JoinHandle<Result<()>> {
+        let monitor = self.clone();
+        tokio::spawn(async move { monitor.start_monitoring().await })
+    }
+
+    /// Stop the monitoring and scheduler loops; `start_monitoring` flushes history and returns
+    pub fn stop_monitoring(&self) {
+        self.cancel.cancel();
+    }
+
     /// Start continuous monitoring
     pub async fn start_monitoring(&self) -> Result<()> {
         if !self.enabled {
@@ -464,23 +1658,81 @@ impl EnergyMonitor {
 
         info!("🔋 Starting continuous energy monitoring...");
         
-        let mut interval = interval(Duration::from_secs(30));
+        // Wall power is polled on its own so a slow plug does not delay samples
+        if let Some(plug) = &self.smart_plug {
+            let (plug, cancel) = (Arc::clone(plug), self.cancel.clone());
+            tokio::spawn(async move { plug.run_polling(cancel).await });
+        }
         
         loop {
-            interval.tick().await;
+            // Re-read every iteration so profile switches apply immediately
+            let profile = self.active_profile();
+            tokio::select! {
+                _ = self.cancel.cancelled() => {
+                    info!("🔋 Energy monitoring stopped");
+                    if let Some(store) = &self.history_store {
+                        store.flush()?;
+                    }
+                    return Ok(());
+                }
+                _ = sleep(Duration::from_secs(profile.sampling_interval_secs.max(1))) => {}
+            }
             
             match self.get_current_consumption().await {
                 Ok(energy_data) => {
+                    if let Some(store) = &self.history_store {
+                        if let Err(e) = store.prune(energy_data.timestamp) {
+                            warn!("Failed to prune energy history: {}", e);
+                        }
+                    }
+
+                    if let (Some(activity), Some(policy)) = (&self.activity, self.config.activity.policy()) {
+                        activity.refresh(policy).await;
+                    }
+                    self.update_power_policy(energy_data.battery_level, energy_data.is_charging);
+
+                    if let (Some(tracker), Some(manager)) = (&self.battery_health, &self.battery_manager) {
+                        if tracker.due(energy_data.timestamp) {
+                            tracker.update(Self::read_battery_health(manager), energy_data.timestamp);
+                        }
+                    }
+
+                    if self.config.shutdown.is_critical(energy_data.battery_level, energy_data.is_charging) {
+                        error!(
+                            "🪫 Critical battery ({:.1}%), shutting down node",
+                            energy_data.battery_level.unwrap_or_default()
+                        );
+                        if let Some(hook) = &self.shutdown_hook {
+                            if let Err(e) = hook().await {
+                                error!("Graceful shutdown failed: {}", e);
+                            }
+                        }
+                        if let Some(store) = &self.history_store {
+                            store.flush()?;
+                        }
+                        return Ok(());
+                    }
+
+                    let thresholds = &profile.alert_thresholds;
+
                     // Log significant changes
-                    if energy_data.total_watts > 100.0 {
+                    if energy_data.total_watts > thresholds.high_power_watts {
                         warn!("⚡ High power consumption: {:.1}W", energy_data.total_watts);
                     }
                     
                     if let Some(battery_level) = energy_data.battery_level {
-                        if battery_level < 20.0 {
+                        if battery_level < thresholds.low_battery_percent {
                             warn!("🔋 Low battery: {:.1}%", battery_level);
                         }
                     }
+
+                    if let Some(alerts) = &self.alerts {
+                        let events = alerts.evaluate(self.energy_history.read().unwrap().raw());
+                        for event in events {
+                            info!("🚨 Alert {} {:?}: {}", event.rule, event.status, event.message);
+                            alerts.dispatch(event);
+                        }
+                    }
                 }
                 Err(e) => {
                     error!("Energy monitoring error: {}", e);
@@ -500,6 +1752,9 @@ pub struct EnergyStats {
     pub avg_efficiency_score: u8,
     pub total_carbon_kg: f64,
     pub uptime_hours: f64,
+    /// Energy by node subsystem
+    #[serde(default)]
+    pub subsystems: Vec<SubsystemEnergy>,
 }
 
 #[cfg(test)]
@@ -510,7 +1765,7 @@ mod tests {
     async fn test_energy_monitor_creation() {
         let monitor = EnergyMonitor::new(true);
         assert!(monitor.enabled);
-        assert!(monitor.baseline_power > 0.0);
+        assert!(monitor.baseline_power() > 0.0);
     }
 
     #[tokio::test]
@@ -0,0 +1,262 @@
+/*!
+ * Host user activity detection
+ * Reads input idle time and screen lock state (logind on Linux, IOKit HID idle time on macOS,
+ * GetLastInputInfo on Windows) so desktop nodes back off while their owner is using the machine
+ * and return to full workload once it is idle
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+use tokio::process::Command;
+
+use crate::u2u_integration::DeviceType;
+
+/// How the workload reacts to an active user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityPolicy {
+    /// Input idle time after which the user counts as away
+    pub idle_after_secs: u64,
+    /// A locked screen counts as idle regardless of input
+    pub locked_is_idle: bool,
+    pub active_scan_multiplier: u32,
+    /// Fraction of the DAG batch size kept while the user is active
+    pub active_batch_fraction: f64,
+    pub defer_proofs_when_active: bool,
+    /// After the user leaves, workload returns to full linearly over this long
+    #[serde(default = "default_ramp_up_secs")]
+    pub ramp_up_secs: u64,
+}
+
+fn default_ramp_up_secs() -> u64 {
+    600
+}
+
+impl ActivityPolicy {
+    fn desktop() -> Self {
+        Self {
+            idle_after_secs: 300,
+            locked_is_idle: true,
+            active_scan_multiplier: 3,
+            active_batch_fraction: 0.25,
+            defer_proofs_when_active: true,
+            ramp_up_secs: default_ramp_up_secs(),
+        }
+    }
+
+    fn mobile() -> Self {
+        Self {
+            idle_after_secs: 120,
+            active_scan_multiplier: 6,
+            active_batch_fraction: 0.1,
+            ramp_up_secs: 300,
+            ..Self::desktop()
+        }
+    }
+}
+
+/// Activity-based workload settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityConfig {
+    pub enabled: bool,
+    pub device_type: DeviceType,
+    /// Device types without an entry ignore user activity (servers, IoT)
+    pub policies: HashMap<DeviceType, ActivityPolicy>,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            device_type: DeviceType::Desktop,
+            policies: HashMap::from([
+                (DeviceType::Desktop, ActivityPolicy::desktop()),
+                (DeviceType::Mobile, ActivityPolicy::mobile()),
+            ]),
+        }
+    }
+}
+
+impl ActivityConfig {
+    /// Policy for the configured device type
+    pub fn policy(&self) -> Option<&ActivityPolicy> {
+        self.policies.get(&self.device_type).filter(|_| self.enabled)
+    }
+}
+
+/// Input idle time and lock state; `None` when the platform does not say
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostActivity {
+    pub idle_secs: Option<u64>,
+    pub screen_locked: Option<bool>,
+}
+
+impl HostActivity {
+    /// Whether someone is using the machine; unknown counts as idle so headless hosts run at full speed
+    pub fn user_active(&self, policy: &ActivityPolicy) -> bool {
+        if policy.locked_is_idle && self.screen_locked == Some(true) {
+            return false;
+        }
+        self.idle_secs.is_some_and(|idle| idle < policy.idle_after_secs)
+    }
+}
+
+/// Parse `loginctl show-session` output for a graphical session; `now_micros` is realtime
+pub fn parse_loginctl_session(output: &str, now_micros: u64) -> Option<HostActivity> {
+    let properties: HashMap<&str, &str> = output.lines().filter_map(|line| line.split_once('=')).collect();
+    if !matches!(properties.get("Type"), Some(&"x11") | Some(&"wayland")) || properties.get("Active") != Some(&"yes") {
+        return None;
+    }
+
+    // IdleHint is only set once the desktop's own idle delay has passed
+    let idle_secs = match properties.get("IdleHint") {
+        Some(&"yes") => properties
+            .get("IdleSinceHint")
+            .and_then(|since| since.parse::<u64>().ok())
+            .map(|since| now_micros.saturating_sub(since) / 1_000_000),
+        Some(_) => Some(0),
+        None => None,
+    };
+
+    Some(HostActivity {
+        idle_secs,
+        screen_locked: properties.get("LockedHint").map(|locked| *locked == "yes"),
+    })
+}
+
+/// Parse `HIDIdleTime` (nanoseconds) from `ioreg -c IOHIDSystem`
+pub fn parse_hid_idle_time(output: &str) -> Option<u64> {
+    output.lines().find_map(|line| {
+        let (_, value) = line.split_once("\"HIDIdleTime\" =")?;
+        value.trim().parse::<u64>().ok().map(|nanos| nanos / 1_000_000_000)
+    })
+}
+
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().await.ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Current user activity on this host
+pub async fn detect() -> HostActivity {
+    #[cfg(windows)]
+    {
+        const LAST_INPUT: &str = "Add-Type -Namespace W -Name U -MemberDefinition '\
+            [StructLayout(LayoutKind.Sequential)] public struct L { public uint cbSize; public uint dwTime; }\
+            [DllImport(\"user32.dll\")] public static extern bool GetLastInputInfo(ref L l);';\
+            $l = New-Object W.U+L; $l.cbSize = 8; [void][W.U]::GetLastInputInfo([ref]$l);\
+            [uint32]([Environment]::TickCount - $l.dwTime)";
+        let idle_secs = command_output("powershell", &["-NoProfile", "-NonInteractive", "-Command", LAST_INPUT])
+            .await
+            .and_then(|output| output.trim().parse::<u64>().ok())
+            .map(|millis| millis / 1000);
+        // A locked workstation keeps accumulating idle time, so input idle covers it
+        HostActivity { idle_secs, screen_locked: None }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let idle_secs = command_output("ioreg", &["-c", "IOHIDSystem", "-d", "4"])
+            .await
+            .and_then(|output| parse_hid_idle_time(&output));
+        HostActivity { idle_secs, screen_locked: None }
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let Some(sessions) = command_output("loginctl", &["list-sessions", "--no-legend"]).await else {
+            return HostActivity::default();
+        };
+        let now_micros = chrono::Utc::now().timestamp_micros() as u64;
+
+        for id in sessions.lines().filter_map(|line| line.split_whitespace().next()) {
+            let properties = "--property=Type,Active,IdleHint,IdleSinceHint,LockedHint";
+            let Some(output) = command_output("loginctl", &["show-session", id, properties]).await else {
+                continue;
+            };
+            if let Some(activity) = parse_loginctl_session(&output, now_micros) {
+                return activity;
+            }
+        }
+        HostActivity::default()
+    }
+}
+
+/// Latest activity reading for the power policy
+#[derive(Default)]
+pub struct ActivityTracker {
+    latest: RwLock<HostActivity>,
+    /// When the user was last seen active (unix seconds, 0 for never)
+    last_active: AtomicU64,
+}
+
+impl ActivityTracker {
+    /// Query the platform; called once per power sample
+    pub async fn refresh(&self, policy: &ActivityPolicy) -> HostActivity {
+        let activity = detect().await;
+        self.observe(activity, policy, chrono::Utc::now().timestamp() as u64);
+        activity
+    }
+
+    fn observe(&self, activity: HostActivity, policy: &ActivityPolicy, now: u64) {
+        if activity.user_active(policy) {
+            self.last_active.store(now, Ordering::Relaxed);
+        }
+        *self.latest.write().unwrap() = activity;
+    }
+
+    pub fn latest(&self) -> HostActivity {
+        *self.latest.read().unwrap()
+    }
+
+    /// How far the workload has ramped back up since the user left: 0 while they are active,
+    /// 1 once `ramp_up_secs` have passed (or when they were never seen)
+    pub fn ramp(&self, policy: &ActivityPolicy, now: u64) -> f64 {
+        if self.latest().user_active(policy) {
+            return 0.0;
+        }
+        match self.last_active.load(Ordering::Relaxed) {
+            0 => 1.0,
+            _ if policy.ramp_up_secs == 0 => 1.0,
+            last => (now.saturating_sub(last) as f64 / policy.ramp_up_secs as f64).min(1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loginctl_idle_and_lock() {
+        let policy = ActivityPolicy::desktop();
+        let now = 1_700_000_000_000_000;
+
+        let busy = parse_loginctl_session("Type=wayland\nActive=yes\nIdleHint=no\nIdleSinceHint=0\nLockedHint=no\n", now).unwrap();
+        assert_eq!(busy.idle_secs, Some(0));
+        assert!(busy.user_active(&policy));
+
+        let away = format!("Type=x11\nActive=yes\nIdleHint=yes\nIdleSinceHint={}\nLockedHint=no\n", now - 600_000_000);
+        assert_eq!(parse_loginctl_session(&away, now).unwrap().idle_secs, Some(600));
+
+        let locked = parse_loginctl_session("Type=x11\nActive=yes\nIdleHint=no\nLockedHint=yes\n", now).unwrap();
+        assert!(!locked.user_active(&policy));
+
+        // Text consoles and background sessions are skipped
+        assert_eq!(parse_loginctl_session("Type=tty\nActive=yes\nIdleHint=no\n", now), None);
+
+        // Workload ramps back up over `ramp_up_secs` once the user leaves
+        let tracker = ActivityTracker::default();
+        assert_eq!(tracker.ramp(&policy, 0), 1.0);
+        tracker.observe(busy, &policy, 1_000);
+        assert_eq!(tracker.ramp(&policy, 1_000), 0.0);
+        tracker.observe(locked, &policy, 1_300);
+        assert!((tracker.ramp(&policy, 1_300) - 0.5).abs() < 1e-9);
+        assert_eq!(tracker.ramp(&policy, 2_000), 1.0);
+    }
+}
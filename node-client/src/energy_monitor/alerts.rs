@@ -0,0 +1,263 @@
+/*!
+ * Energy alert webhooks
+ * Configurable rules (sustained high power, low battery, efficiency drops) evaluated over the
+ * recent history; firing and resolved transitions are POSTed as JSON to operator webhooks
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::warn;
+
+use super::EnergyData;
+
+/// One alert condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// Every reading for `for_minutes` above `watts`
+    HighPower { watts: f64, for_minutes: f64 },
+    /// Battery (or UPS) below `percent` while discharging
+    LowBattery { percent: f64 },
+    /// Average efficiency score over `window_minutes` fell `points` below the window before
+    EfficiencyDrop { points: f64, window_minutes: f64 },
+}
+
+impl AlertRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HighPower { .. } => "high_power",
+            Self::LowBattery { .. } => "low_battery",
+            Self::EfficiencyDrop { .. } => "efficiency_drop",
+        }
+    }
+
+    /// `(value, threshold)` when the rule holds for `history` (oldest first)
+    pub fn check(&self, history: &[EnergyData]) -> Option<(f64, f64)> {
+        let latest = history.last()?;
+        let since = |minutes: f64| latest.timestamp.saturating_sub((minutes * 60.0) as u64);
+
+        match *self {
+            Self::HighPower { watts, for_minutes } => {
+                let start = since(for_minutes);
+                // Require history covering the whole window
+                if latest.timestamp - history.first()?.timestamp < (for_minutes * 60.0) as u64 {
+                    return None;
+                }
+                let lowest = history
+                    .iter()
+                    .filter(|reading| reading.timestamp >= start)
+                    .map(|reading| reading.total_watts)
+                    .fold(f64::INFINITY, f64::min);
+                (lowest > watts).then_some((lowest, watts))
+            }
+            Self::LowBattery { percent } => {
+                let level = latest.battery_level?;
+                (level < percent && latest.is_charging != Some(true)).then_some((level, percent))
+            }
+            Self::EfficiencyDrop { points, window_minutes } => {
+                let (recent_start, previous_start) = (since(window_minutes), since(2.0 * window_minutes));
+                let average = |from: u64, to: u64| {
+                    let scores: Vec<f64> = history
+                        .iter()
+                        .filter(|reading| reading.timestamp > from && reading.timestamp <= to)
+                        .map(|reading| reading.efficiency_score as f64)
+                        .collect();
+                    (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64)
+                };
+                let recent = average(recent_start, latest.timestamp)?;
+                let previous = average(previous_start, recent_start)?;
+                (previous - recent >= points).then_some((recent, previous - points))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+/// Webhook JSON payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub rule: String,
+    pub status: AlertStatus,
+    pub host: String,
+    pub message: String,
+    pub value: Option<f64>,
+    pub threshold: Option<f64>,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Extra headers, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Alert rules and where to send them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertConfig {
+    pub enabled: bool,
+    pub rules: Vec<AlertRule>,
+    pub webhooks: Vec<WebhookConfig>,
+    /// Also send a notification when a condition clears
+    pub send_resolved: bool,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: vec![
+                AlertRule::HighPower { watts: 100.0, for_minutes: 5.0 },
+                AlertRule::LowBattery { percent: 20.0 },
+                AlertRule::EfficiencyDrop { points: 20.0, window_minutes: 30.0 },
+            ],
+            webhooks: Vec::new(),
+            send_resolved: true,
+            request_timeout_secs: 5,
+        }
+    }
+}
+
+/// Edge-triggered rule evaluation and webhook delivery
+pub struct AlertManager {
+    config: AlertConfig,
+    host: String,
+    active: Mutex<Vec<bool>>,
+    http: reqwest::Client,
+}
+
+impl AlertManager {
+    pub fn new(config: AlertConfig, host: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            active: Mutex::new(vec![false; config.rules.len()]),
+            config,
+            host,
+            http,
+        }
+    }
+
+    /// Transitions since the previous evaluation
+    pub fn evaluate(&self, history: &[EnergyData]) -> Vec<AlertEvent> {
+        let Some(latest) = history.last() else {
+            return Vec::new();
+        };
+        let mut active = self.active.lock().unwrap();
+        let mut events = Vec::new();
+
+        for (rule, was_active) in self.config.rules.iter().zip(active.iter_mut()) {
+            let check = rule.check(history);
+            let status = match (check.is_some(), *was_active) {
+                (true, false) => AlertStatus::Firing,
+                (false, true) => AlertStatus::Resolved,
+                _ => continue,
+            };
+            *was_active = check.is_some();
+
+            let message = match (rule, check) {
+                (AlertRule::HighPower { for_minutes, .. }, Some((watts, limit))) => {
+                    format!("Power above {:.0}W for {:.0} minutes ({:.1}W)", limit, for_minutes, watts)
+                }
+                (AlertRule::LowBattery { .. }, Some((level, _))) => format!("Battery at {:.1}% and discharging", level),
+                (AlertRule::EfficiencyDrop { window_minutes, points }, Some((score, _))) => {
+                    format!("Efficiency score fell {:.0}+ points to {:.0} over {:.0} minutes", points, score, window_minutes)
+                }
+                (rule, None) => format!("{} cleared", rule.name()),
+            };
+            events.push(AlertEvent {
+                rule: rule.name().to_string(),
+                status,
+                host: self.host.clone(),
+                message,
+                value: check.map(|(value, _)| value),
+                threshold: check.map(|(_, threshold)| threshold),
+                timestamp: latest.timestamp,
+            });
+        }
+
+        if !self.config.send_resolved {
+            events.retain(|event| event.status == AlertStatus::Firing);
+        }
+        events
+    }
+
+    /// POST to every webhook in the background
+    pub fn dispatch(self: &Arc<Self>, event: AlertEvent) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            for webhook in &manager.config.webhooks {
+                let mut request = manager.http.post(&webhook.url).json(&event);
+                for (name, value) in &webhook.headers {
+                    request = request.header(name, value);
+                }
+                if let Err(e) = request.send().await.and_then(|response| response.error_for_status()) {
+                    warn!("Alert webhook {} failed: {}", webhook.url, e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, watts: f64, efficiency_score: u8) -> EnergyData {
+        EnergyData {
+            total_watts: watts,
+            cpu_watts: watts,
+            efficiency_score,
+            timestamp,
+            ..EnergyData::default()
+        }
+    }
+
+    #[test]
+    fn test_high_power_fires_once_then_resolves() {
+        let config = AlertConfig {
+            enabled: true,
+            rules: vec![AlertRule::HighPower { watts: 100.0, for_minutes: 5.0 }],
+            ..AlertConfig::default()
+        };
+        let manager = AlertManager::new(config, "node-1".to_string());
+
+        // 4 minutes above the limit is not enough
+        let mut history: Vec<EnergyData> = (0..=8).map(|i| reading(i * 30, 150.0, 80)).collect();
+        assert!(manager.evaluate(&history).is_empty());
+
+        history.extend((9..=10).map(|i| reading(i * 30, 150.0, 80)));
+        let events = manager.evaluate(&history);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status, AlertStatus::Firing);
+        assert!(manager.evaluate(&history).is_empty());
+
+        history.push(reading(330, 60.0, 80));
+        assert_eq!(manager.evaluate(&history)[0].status, AlertStatus::Resolved);
+    }
+
+    #[test]
+    fn test_efficiency_drop() {
+        let rule = AlertRule::EfficiencyDrop { points: 20.0, window_minutes: 10.0 };
+        let mut history: Vec<EnergyData> = (1..=20).map(|i| reading(i * 60, 50.0, 80)).collect();
+        assert_eq!(rule.check(&history), None);
+
+        history.extend((21..=30).map(|i| reading(i * 60, 50.0, 55)));
+        assert_eq!(rule.check(&history), Some((55.0, 60.0)));
+    }
+}
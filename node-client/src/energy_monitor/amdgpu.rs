@@ -0,0 +1,164 @@
+/*!
+ * AMD GPU power via amdgpu sysfs/hwmon (Linux)
+ * Reads the driver's power, temperature, fan and VRAM sensors for every Radeon card
+ */
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::gpu::GpuReading;
+
+/// Default DRM class root
+pub const DRM_ROOT: &str = "/sys/class/drm";
+
+/// PCI vendor ID of AMD/ATI
+const AMD_VENDOR_ID: &str = "0x1002";
+
+/// One amdgpu card with its hwmon directory
+#[derive(Debug, Clone)]
+struct AmdGpuCard {
+    index: u32,
+    device_dir: PathBuf,
+    hwmon_dir: PathBuf,
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// amdgpu hwmon backend
+pub struct AmdGpuBackend {
+    cards: Vec<AmdGpuCard>,
+}
+
+impl AmdGpuBackend {
+    /// Detect amdgpu cards under the default DRM root
+    pub fn detect() -> Option<Self> {
+        Self::detect_in(Path::new(DRM_ROOT))
+    }
+
+    /// Detect amdgpu cards under `root`; returns None when there are none with power sensors
+    pub fn detect_in(root: &Path) -> Option<Self> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(root)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_prefix("card"))
+                    .is_some_and(|suffix| suffix.chars().all(|c| c.is_ascii_digit()))
+            })
+            .collect();
+        entries.sort();
+
+        let mut cards = Vec::new();
+        for card in entries {
+            let device_dir = card.join("device");
+            if read_trimmed(&device_dir.join("vendor")).as_deref() != Some(AMD_VENDOR_ID) {
+                continue;
+            }
+
+            let hwmon_dir = fs::read_dir(device_dir.join("hwmon"))
+                .ok()
+                .and_then(|mut dirs| dirs.find_map(|d| d.ok()))
+                .map(|d| d.path());
+            let Some(hwmon_dir) = hwmon_dir else {
+                continue;
+            };
+
+            if !hwmon_dir.join("power1_average").exists() && !hwmon_dir.join("power1_input").exists() {
+                continue;
+            }
+
+            cards.push(AmdGpuCard {
+                index: cards.len() as u32,
+                device_dir,
+                hwmon_dir,
+            });
+        }
+
+        if cards.is_empty() {
+            None
+        } else {
+            Some(Self { cards })
+        }
+    }
+
+    /// Number of detected cards
+    pub fn device_count(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Read sensors of every card
+    pub fn read(&self) -> Vec<GpuReading> {
+        self.cards
+            .iter()
+            .map(|card| {
+                // hwmon reports microwatts, millidegrees and RPM
+                let power_uw = read_u64(&card.hwmon_dir.join("power1_average"))
+                    .or_else(|| read_u64(&card.hwmon_dir.join("power1_input")))
+                    .unwrap_or(0);
+
+                GpuReading {
+                    index: card.index,
+                    vendor: "AMD".to_string(),
+                    name: read_trimmed(&card.device_dir.join("product_name"))
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or_else(|| "AMD Radeon GPU".to_string()),
+                    power_watts: power_uw as f64 / 1_000_000.0,
+                    utilization_percent: read_u64(&card.device_dir.join("gpu_busy_percent"))
+                        .map(|p| p as f64),
+                    temperature_c: read_u64(&card.hwmon_dir.join("temp1_input"))
+                        .map(|t| t as f64 / 1000.0),
+                    fan_rpm: read_u64(&card.hwmon_dir.join("fan1_input")).map(|rpm| rpm as u32),
+                    memory_used_mb: read_u64(&card.device_dir.join("mem_info_vram_used"))
+                        .map(|b| b / 1024 / 1024),
+                    memory_total_mb: read_u64(&card.device_dir.join("mem_info_vram_total"))
+                        .map(|b| b / 1024 / 1024),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_amdgpu_hwmon() {
+        let root = tempfile::tempdir().unwrap();
+        let device = root.path().join("card0/device");
+        let hwmon = device.join("hwmon/hwmon3");
+        fs::create_dir_all(&hwmon).unwrap();
+        fs::write(device.join("vendor"), "0x1002\n").unwrap();
+        fs::write(device.join("gpu_busy_percent"), "42\n").unwrap();
+        fs::write(device.join("mem_info_vram_total"), "8589934592\n").unwrap();
+        fs::write(hwmon.join("power1_average"), "35000000\n").unwrap();
+        fs::write(hwmon.join("temp1_input"), "54000\n").unwrap();
+        fs::write(hwmon.join("fan1_input"), "1200\n").unwrap();
+
+        // Non-AMD card is ignored
+        let other = root.path().join("card1/device");
+        fs::create_dir_all(&other).unwrap();
+        fs::write(other.join("vendor"), "0x10de\n").unwrap();
+
+        let backend = AmdGpuBackend::detect_in(root.path()).unwrap();
+        assert_eq!(backend.device_count(), 1);
+
+        let gpu = &backend.read()[0];
+        assert_eq!(gpu.power_watts, 35.0);
+        assert_eq!(gpu.temperature_c, Some(54.0));
+        assert_eq!(gpu.fan_rpm, Some(1200));
+        assert_eq!(gpu.utilization_percent, Some(42.0));
+        assert_eq!(gpu.memory_total_mb, Some(8192));
+        assert_eq!(gpu.memory_used_mb, None);
+    }
+}
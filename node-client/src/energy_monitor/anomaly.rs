@@ -0,0 +1,191 @@
+/*!
+ * Crypto-mining malware detection from energy anomalies
+ * A sudden, sustained CPU/GPU power jump that the node's own workload does not explain is
+ * the classic cryptojacking signature, so the energy history doubles as a host sensor
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::ThreatDetectionResult;
+
+use super::EnergyData;
+
+/// Detector thresholds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    pub enabled: bool,
+    /// Readings forming the baseline before the suspect window
+    pub baseline_samples: usize,
+    /// Consecutive elevated readings required before alerting
+    pub sustain_samples: usize,
+    /// Minimum jump over the baseline, regardless of its variance
+    pub min_jump_watts: f64,
+    /// Jump must also exceed this many baseline standard deviations
+    pub sigma: f64,
+    /// Above this share of host power the node itself explains the load
+    pub max_node_share: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            baseline_samples: 40,
+            sustain_samples: 6,
+            min_jump_watts: 15.0,
+            sigma: 4.0,
+            max_node_share: 0.3,
+        }
+    }
+}
+
+/// Sustained power jump not attributable to DAGShield
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyAnomaly {
+    pub timestamp: u64,
+    pub baseline_watts: f64,
+    pub observed_watts: f64,
+    /// GPU part of the jump
+    pub gpu_delta_watts: f64,
+    pub duration_secs: u64,
+    /// Node process share of host power during the anomaly
+    pub node_share: f64,
+    pub confidence: f32,
+}
+
+impl EnergyAnomaly {
+    /// Local threat event for the detection pipeline
+    pub fn to_threat(&self) -> ThreatDetectionResult {
+        let delta = self.observed_watts - self.baseline_watts;
+        let gpu_bound = self.gpu_delta_watts > delta / 2.0;
+
+        ThreatDetectionResult {
+            threat_type: if gpu_bound { "cryptojacking_gpu" } else { "cryptojacking_cpu" }.to_string(),
+            confidence: self.confidence,
+            risk_score: (self.confidence * 100.0) as u32,
+            explanation: format!(
+                "Unexplained {} power rose from {:.1}W to {:.1}W for {}s while the node used {:.0}% of host power",
+                if gpu_bound { "GPU" } else { "CPU" },
+                self.baseline_watts,
+                self.observed_watts,
+                self.duration_secs,
+                self.node_share * 100.0
+            ),
+            recommended_action: "Inspect running processes for unauthorized miners".to_string(),
+        }
+    }
+}
+
+/// CPU+GPU power not attributed to the node process
+pub fn unattributed_watts(data: &EnergyData) -> f64 {
+    let node_watts = data.node_process.as_ref().map_or(0.0, |node| node.cpu_watts);
+    (data.cpu_watts + data.gpu_watts - node_watts).max(0.0)
+}
+
+fn mean_and_std(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = values.clone().count().max(1) as f64;
+    let mean = values.clone().sum::<f64>() / n;
+    let variance = values.map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Stateful detector; alerts once per anomaly episode
+#[derive(Debug, Clone, Default)]
+pub struct MiningAnomalyDetector {
+    config: AnomalyConfig,
+    alerted: bool,
+}
+
+impl MiningAnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self { config, alerted: false }
+    }
+
+    /// Check the most recent readings (oldest first)
+    pub fn evaluate(&mut self, history: &[EnergyData]) -> Option<EnergyAnomaly> {
+        let config = &self.config;
+        let sustain = config.sustain_samples.max(1);
+        if !config.enabled || history.len() < config.baseline_samples + sustain {
+            return None;
+        }
+
+        let (baseline, recent) = history[history.len() - config.baseline_samples - sustain..]
+            .split_at(config.baseline_samples);
+
+        let (baseline_watts, baseline_std) = mean_and_std(baseline.iter().map(unattributed_watts));
+        let threshold = baseline_watts + (config.sigma * baseline_std).max(config.min_jump_watts);
+
+        let elevated = recent.iter().all(|data| unattributed_watts(data) > threshold);
+        if !elevated {
+            self.alerted = false;
+            return None;
+        }
+
+        let node_share = recent
+            .iter()
+            .map(|data| data.node_process.as_ref().map_or(0.0, |node| node.share_of_host))
+            .sum::<f64>()
+            / recent.len() as f64;
+        if self.alerted || node_share > config.max_node_share {
+            return None;
+        }
+        self.alerted = true;
+
+        let observed_watts = recent.iter().map(unattributed_watts).sum::<f64>() / recent.len() as f64;
+        let gpu_delta_watts = recent.iter().map(|d| d.gpu_watts).sum::<f64>() / recent.len() as f64
+            - baseline.iter().map(|d| d.gpu_watts).sum::<f64>() / baseline.len() as f64;
+        let first = recent.first()?;
+        let last = recent.last()?;
+
+        // Confidence grows with how far the jump clears the threshold
+        let margin = (observed_watts - threshold) / (threshold - baseline_watts);
+        let confidence = (0.6 + 0.3 * margin.min(1.0)).min(0.95) as f32;
+
+        Some(EnergyAnomaly {
+            timestamp: last.timestamp,
+            baseline_watts,
+            observed_watts,
+            gpu_delta_watts,
+            duration_secs: last.timestamp.saturating_sub(first.timestamp),
+            node_share,
+            confidence,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, cpu_watts: f64, gpu_watts: f64) -> EnergyData {
+        EnergyData {
+            total_watts: 20.0 + cpu_watts + gpu_watts,
+            cpu_watts,
+            gpu_watts,
+            efficiency_score: 50,
+            timestamp,
+            ..EnergyData::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_sustained_gpu_jump_once() {
+        let mut detector = MiningAnomalyDetector::new(AnomalyConfig::default());
+        let mut history: Vec<EnergyData> = (0..40).map(|i| reading(i * 30, 10.0, 5.0)).collect();
+
+        // Short spike is ignored
+        history.push(reading(1_200, 10.0, 120.0));
+        assert!(detector.evaluate(&history).is_none());
+
+        for i in 41..47 {
+            history.push(reading(i * 30, 12.0, 120.0));
+        }
+        let anomaly = detector.evaluate(&history[1..]).unwrap();
+        assert_eq!(anomaly.to_threat().threat_type, "cryptojacking_gpu");
+        assert!(anomaly.confidence > 0.6);
+
+        // Same episode does not alert twice
+        history.push(reading(47 * 30, 12.0, 120.0));
+        assert!(detector.evaluate(&history[2..]).is_none());
+    }
+}
@@ -0,0 +1,260 @@
+/*!
+ * ARM SoC power model
+ * Identifies Raspberry Pi, Jetson, Apple Silicon and ARM server parts with per-SoC power
+ * curves, and reads board power monitors (INA2xx/INA3221) exposed under /sys/class/hwmon
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Default hwmon root
+pub const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+/// Known ARM SoC families
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArmSoc {
+    RaspberryPiZero2,
+    RaspberryPi3,
+    RaspberryPi4,
+    RaspberryPi5,
+    JetsonNano,
+    JetsonXavier,
+    JetsonOrin,
+    AppleSilicon,
+    /// M-series Pro/Max/Ultra
+    AppleSiliconPro,
+    /// Graviton, Ampere Altra and other Neoverse servers
+    ArmServer,
+    GenericArm,
+}
+
+/// Whole-SoC power as a function of CPU utilization
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SocPowerCurve {
+    pub idle_watts: f64,
+    pub max_watts: f64,
+    /// DVFS makes power grow faster than linearly with load
+    pub exponent: f64,
+}
+
+impl SocPowerCurve {
+    /// Power at `usage` (0.0-1.0)
+    pub fn power_at(&self, usage: f64) -> f64 {
+        self.idle_watts + (self.max_watts - self.idle_watts) * usage.clamp(0.0, 1.0).powf(self.exponent)
+    }
+}
+
+impl ArmSoc {
+    /// Measured idle and full-load figures (board included for single-board computers)
+    pub fn power_curve(&self, cores: u32) -> SocPowerCurve {
+        let curve = |idle_watts, max_watts, exponent| SocPowerCurve { idle_watts, max_watts, exponent };
+        let cores = cores.max(1) as f64;
+        match self {
+            Self::RaspberryPiZero2 => curve(0.6, 3.0, 1.2),
+            Self::RaspberryPi3 => curve(1.9, 5.1, 1.2),
+            Self::RaspberryPi4 => curve(2.7, 6.4, 1.2),
+            Self::RaspberryPi5 => curve(2.7, 8.5, 1.3),
+            Self::JetsonNano => curve(1.5, 10.0, 1.3),
+            Self::JetsonXavier => curve(4.0, 20.0, 1.3),
+            Self::JetsonOrin => curve(5.0, 40.0, 1.3),
+            Self::AppleSilicon => curve(0.5, 20.0, 1.5),
+            Self::AppleSiliconPro => curve(1.0, 40.0, 1.5),
+            Self::ArmServer => curve(0.5 * cores, 2.5 * cores, 1.1),
+            Self::GenericArm => curve(0.3 * cores, 1.5 * cores, 1.2),
+        }
+    }
+
+    /// SoC platforms have no separate chipset, fans or discrete peripherals worth modeling
+    pub fn is_single_board(&self) -> bool {
+        !matches!(self, Self::ArmServer)
+    }
+}
+
+/// Classify from the device-tree model, CPU brand and DMI vendor
+pub fn classify_soc(
+    device_model: Option<&str>,
+    cpu_brand: &str,
+    dmi_vendor: Option<&str>,
+    is_arm: bool,
+) -> Option<ArmSoc> {
+    if let Some(model) = device_model {
+        let soc = if model.contains("Raspberry Pi Zero 2") {
+            Some(ArmSoc::RaspberryPiZero2)
+        } else if model.contains("Raspberry Pi 5") {
+            Some(ArmSoc::RaspberryPi5)
+        } else if model.contains("Raspberry Pi 4") || model.contains("Raspberry Pi Compute Module 4") {
+            Some(ArmSoc::RaspberryPi4)
+        } else if model.contains("Raspberry Pi 3") {
+            Some(ArmSoc::RaspberryPi3)
+        } else if model.contains("Jetson Nano") {
+            Some(ArmSoc::JetsonNano)
+        } else if model.contains("Jetson Xavier") {
+            Some(ArmSoc::JetsonXavier)
+        } else if model.contains("Orin") {
+            Some(ArmSoc::JetsonOrin)
+        } else {
+            None
+        };
+        if soc.is_some() {
+            return soc;
+        }
+    }
+
+    if cpu_brand.starts_with("Apple M") {
+        let pro = ["Pro", "Max", "Ultra"].iter().any(|tier| cpu_brand.contains(tier));
+        return Some(if pro { ArmSoc::AppleSiliconPro } else { ArmSoc::AppleSilicon });
+    }
+
+    let server = ["Neoverse", "Graviton", "Ampere", "Altra"].iter().any(|name| cpu_brand.contains(name))
+        || dmi_vendor.is_some_and(|vendor| vendor.trim() == "Amazon EC2");
+    match (is_arm, server) {
+        (true, true) => Some(ArmSoc::ArmServer),
+        (true, false) => Some(ArmSoc::GenericArm),
+        (false, _) => None,
+    }
+}
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    // Device-tree strings are NUL terminated
+    fs::read_to_string(path).ok().map(|s| s.trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+}
+
+/// SoC of this machine, if it is ARM-based
+pub fn detect_soc(cpu_brand: &str) -> Option<ArmSoc> {
+    let device_model = read_trimmed("/proc/device-tree/model")
+        .or_else(|| read_trimmed("/sys/firmware/devicetree/base/model"));
+    let dmi_vendor = read_trimmed("/sys/devices/virtual/dmi/id/sys_vendor");
+    let is_arm = cfg!(any(target_arch = "aarch64", target_arch = "arm"));
+
+    classify_soc(device_model.as_deref(), cpu_brand, dmi_vendor.as_deref(), is_arm)
+}
+
+/// One power monitor channel
+#[derive(Debug, Clone)]
+struct HwmonChannel {
+    label: String,
+    /// `powerN_input` (µW)
+    power_path: Option<PathBuf>,
+    /// `currN_input` (mA) and `inN_input` (mV)
+    current_voltage: Option<(PathBuf, PathBuf)>,
+}
+
+impl HwmonChannel {
+    fn read_watts(&self) -> Option<f64> {
+        let value = |path: &Path| read_trimmed(path)?.parse::<f64>().ok();
+        match (&self.power_path, &self.current_voltage) {
+            (Some(power), _) => value(power).map(|uw| uw / 1e6),
+            (None, Some((current, voltage))) => Some(value(current)? * value(voltage)? / 1e6),
+            (None, None) => None,
+        }
+    }
+
+    /// Rail feeding the whole board (Jetson `VDD_IN`, `POM_5V_IN`)
+    fn is_board_input(&self) -> bool {
+        let label = self.label.to_uppercase();
+        label == "VIN" || label.ends_with("_IN")
+    }
+}
+
+/// Board power from INA2xx/INA3221 current monitors
+#[derive(Debug, Clone)]
+pub struct HwmonPowerReader {
+    channels: Vec<HwmonChannel>,
+}
+
+/// TI INA2xx/INA3xx current monitors on supply rails
+const POWER_MONITOR_PREFIXES: &[&str] = &["ina2", "ina3"];
+
+impl HwmonPowerReader {
+    pub fn detect() -> Option<Self> {
+        Self::detect_in(Path::new(HWMON_ROOT))
+    }
+
+    /// Power monitor channels under `root`; None when there are none
+    pub fn detect_in(root: &Path) -> Option<Self> {
+        let mut channels = Vec::new();
+
+        for entry in fs::read_dir(root).ok()?.flatten() {
+            let dir = entry.path();
+            let Some(name) = read_trimmed(dir.join("name")) else {
+                continue;
+            };
+            if !POWER_MONITOR_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+                continue;
+            }
+
+            for index in 1..=8 {
+                let label = read_trimmed(dir.join(format!("in{}_label", index)))
+                    .or_else(|| read_trimmed(dir.join(format!("power{}_label", index))))
+                    .unwrap_or_else(|| format!("{}:{}", name, index));
+                let power = dir.join(format!("power{}_input", index));
+                let current = dir.join(format!("curr{}_input", index));
+                let voltage = dir.join(format!("in{}_input", index));
+
+                let channel = HwmonChannel {
+                    label,
+                    power_path: power.exists().then_some(power),
+                    current_voltage: (current.exists() && voltage.exists()).then_some((current, voltage)),
+                };
+                if channel.power_path.is_some() || channel.current_voltage.is_some() {
+                    channels.push(channel);
+                }
+            }
+        }
+
+        (!channels.is_empty()).then_some(Self { channels })
+    }
+
+    /// Board input power; sums all rails when no input rail is labelled
+    pub fn read_board_watts(&self) -> Option<f64> {
+        if let Some(input) = self.channels.iter().find(|channel| channel.is_board_input()) {
+            return input.read_watts();
+        }
+
+        let readings: Vec<f64> = self.channels.iter().filter_map(HwmonChannel::read_watts).collect();
+        (!readings.is_empty()).then(|| readings.iter().sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_soc() {
+        let pi = classify_soc(Some("Raspberry Pi 4 Model B Rev 1.4"), "", None, true);
+        assert_eq!(pi, Some(ArmSoc::RaspberryPi4));
+        assert_eq!(
+            classify_soc(Some("NVIDIA Jetson Orin Nano Developer Kit"), "", None, true),
+            Some(ArmSoc::JetsonOrin)
+        );
+        assert_eq!(classify_soc(None, "Apple M2 Max", None, true), Some(ArmSoc::AppleSiliconPro));
+        assert_eq!(classify_soc(None, "", Some("Amazon EC2\n"), true), Some(ArmSoc::ArmServer));
+        assert_eq!(classify_soc(None, "Intel(R) Core(TM) i7", None, false), None);
+
+        let curve = ArmSoc::RaspberryPi4.power_curve(4);
+        assert_eq!(curve.power_at(0.0), 2.7);
+        assert_eq!(curve.power_at(1.0), 6.4);
+        assert!(curve.power_at(0.5) < (2.7 + 6.4) / 2.0);
+    }
+
+    #[test]
+    fn test_hwmon_board_input() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("hwmon3");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("name"), "ina3221\n").unwrap();
+        for (index, label, ma, mv) in [(1, "VDD_IN", "1000", "5000"), (2, "VDD_CPU_GPU_CV", "400", "5000")] {
+            fs::write(dir.join(format!("in{}_label", index)), label).unwrap();
+            fs::write(dir.join(format!("curr{}_input", index)), ma).unwrap();
+            fs::write(dir.join(format!("in{}_input", index)), mv).unwrap();
+        }
+
+        // Input rail only, the CPU rail is already part of it
+        let reader = HwmonPowerReader::detect_in(root.path()).unwrap();
+        assert_eq!(reader.read_board_watts(), Some(5.0));
+    }
+}
@@ -0,0 +1,193 @@
+/*!
+ * Per-process energy attribution for the DAGShield node
+ * Splits host power by the node's share of CPU time (process accounting or its cgroup
+ * counters), resident memory and disk I/O
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::Instant,
+};
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+/// Approximate storage energy per byte transferred (J/byte, SSD class)
+const JOULES_PER_IO_BYTE: f64 = 1.0e-8;
+
+/// Where the node's CPU time came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttributionSource {
+    /// Process CPU accounting from the OS
+    ProcessCpuTime,
+    /// cgroup v2 `cpu.stat` of the node's cgroup (includes helper processes)
+    Cgroup,
+}
+
+/// Energy attributed to the node process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEnergy {
+    pub pid: u32,
+    /// Fraction of total machine CPU capacity used by the node (0-1)
+    pub cpu_utilization: f64,
+    pub cpu_watts: f64,
+    pub memory_watts: f64,
+    pub io_watts: f64,
+    pub total_watts: f64,
+    /// Node watts / host watts
+    pub share_of_host: f64,
+    pub source: AttributionSource,
+}
+
+/// Host-level inputs for one attribution
+#[derive(Debug, Clone, Copy)]
+pub struct HostPower {
+    pub total_watts: f64,
+    pub cpu_watts: f64,
+    pub memory_watts: f64,
+    /// Host CPU utilization (0-1)
+    pub cpu_utilization: f64,
+    pub used_memory_bytes: u64,
+}
+
+/// Split host power using the node's CPU utilization, resident memory and I/O rate
+pub fn attribute_power(
+    host: &HostPower,
+    process_cpu_utilization: f64,
+    process_memory_bytes: u64,
+    io_bytes_per_sec: f64,
+) -> (f64, f64, f64) {
+    // CPU power follows the share of busy time; an idle host has nothing to split
+    let cpu_share = if host.cpu_utilization > 0.0 {
+        (process_cpu_utilization / host.cpu_utilization).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let memory_share = if host.used_memory_bytes > 0 {
+        (process_memory_bytes as f64 / host.used_memory_bytes as f64).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (
+        host.cpu_watts * cpu_share,
+        host.memory_watts * memory_share,
+        io_bytes_per_sec * JOULES_PER_IO_BYTE,
+    )
+}
+
+/// cgroup v2 directory of the current process
+fn own_cgroup_cpu_stat() -> Option<PathBuf> {
+    let cgroup = fs::read_to_string("/proc/self/cgroup").ok()?;
+    // cgroup v2 has a single "0::<path>" line
+    let path = cgroup.lines().find_map(|line| line.strip_prefix("0::"))?;
+    let cpu_stat = PathBuf::from("/sys/fs/cgroup").join(path.trim_start_matches('/')).join("cpu.stat");
+    cpu_stat.exists().then_some(cpu_stat)
+}
+
+fn read_usage_usec(cpu_stat: &PathBuf) -> Option<u64> {
+    fs::read_to_string(cpu_stat)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Samples the node process and attributes host power to it
+pub struct ProcessAttributor {
+    pid: Pid,
+    cgroup_cpu_stat: Option<PathBuf>,
+    last_sample: Mutex<Option<(Instant, Option<u64>)>>,
+}
+
+impl ProcessAttributor {
+    /// Attributor for the current process
+    pub fn for_current_process() -> Option<Self> {
+        let pid = sysinfo::get_current_pid().ok()?;
+
+        Some(Self {
+            pid,
+            cgroup_cpu_stat: own_cgroup_cpu_stat(),
+            last_sample: Mutex::new(None),
+        })
+    }
+
+    /// Process ID being tracked
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Attribute host power to the node; `system` must have this process refreshed
+    pub fn sample(&self, system: &System, host: &HostPower) -> Option<ProcessEnergy> {
+        let process = system.process(self.pid)?;
+        let cpu_count = system.cpus().len().max(1) as f64;
+
+        let now = Instant::now();
+        let cgroup_usage = self.cgroup_cpu_stat.as_ref().and_then(read_usage_usec);
+        let previous = self.last_sample.lock().unwrap().replace((now, cgroup_usage));
+        let elapsed = previous.map(|(then, _)| now.duration_since(then).as_secs_f64());
+
+        // cgroup counters cover the whole service; fall back to process accounting
+        let cgroup_utilization = match (previous, cgroup_usage, elapsed) {
+            (Some((_, Some(before))), Some(after), Some(elapsed)) if elapsed > 0.0 => {
+                Some(after.saturating_sub(before) as f64 / 1_000_000.0 / elapsed / cpu_count)
+            }
+            _ => None,
+        };
+        let (cpu_utilization, source) = match cgroup_utilization {
+            Some(utilization) => (utilization, AttributionSource::Cgroup),
+            None => (
+                process.cpu_usage() as f64 / 100.0 / cpu_count,
+                AttributionSource::ProcessCpuTime,
+            ),
+        };
+
+        // Disk usage counters are deltas since the previous process refresh
+        let disk = process.disk_usage();
+        let io_bytes_per_sec = match elapsed {
+            Some(elapsed) if elapsed > 0.0 => (disk.read_bytes + disk.written_bytes) as f64 / elapsed,
+            _ => 0.0,
+        };
+
+        let (cpu_watts, memory_watts, io_watts) =
+            attribute_power(host, cpu_utilization, process.memory(), io_bytes_per_sec);
+        let total_watts = cpu_watts + memory_watts + io_watts;
+
+        Some(ProcessEnergy {
+            pid: self.pid.as_u32(),
+            cpu_utilization,
+            cpu_watts,
+            memory_watts,
+            io_watts,
+            total_watts,
+            share_of_host: if host.total_watts > 0.0 { total_watts / host.total_watts } else { 0.0 },
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_power_shares() {
+        let host = HostPower {
+            total_watts: 100.0,
+            cpu_watts: 60.0,
+            memory_watts: 20.0,
+            cpu_utilization: 0.5,
+            used_memory_bytes: 8 * 1024 * 1024 * 1024,
+        };
+
+        let (cpu, memory, io) = attribute_power(&host, 0.25, 2 * 1024 * 1024 * 1024, 100_000_000.0);
+        assert_eq!(cpu, 30.0);
+        assert_eq!(memory, 5.0);
+        assert!((io - 1.0).abs() < 1e-9);
+
+        // Node can never be charged more than the host
+        let (cpu, _, _) = attribute_power(&host, 0.9, 0, 0.0);
+        assert_eq!(cpu, 60.0);
+    }
+}
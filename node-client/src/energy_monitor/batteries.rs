@@ -0,0 +1,123 @@
+/*!
+ * Multi-battery aggregation
+ * Laptops with a second bay and some handhelds expose several batteries; charge is combined
+ * by stored energy rather than averaging percentages
+ */
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// One battery's state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryReading {
+    /// Serial number, or position when the battery reports none
+    pub id: String,
+    pub level: f64,
+    pub energy_wh: f64,
+    pub full_wh: f64,
+    /// Charge or discharge power
+    pub rate_watts: f64,
+    pub is_charging: Option<bool>,
+    pub time_remaining: Option<Duration>,
+    #[serde(default)]
+    pub temperature_c: Option<f64>,
+}
+
+impl BatteryReading {
+    /// Read a battery from the `battery` crate
+    pub fn from_battery(index: usize, battery: &battery::Battery) -> Self {
+        let id = battery
+            .serial_number()
+            .map(str::trim)
+            .filter(|serial| !serial.is_empty())
+            .map_or_else(|| format!("battery{}", index), str::to_string);
+
+        // Energy values are in joules
+        Self {
+            id,
+            level: battery.state_of_charge().value as f64 * 100.0,
+            energy_wh: battery.energy().value as f64 / 3600.0,
+            full_wh: battery.energy_full().value as f64 / 3600.0,
+            rate_watts: battery.energy_rate().value as f64,
+            is_charging: match battery.state() {
+                battery::State::Charging => Some(true),
+                battery::State::Discharging => Some(false),
+                _ => None,
+            },
+            time_remaining: battery.time_to_empty().map(|t| Duration::from_secs(t.value as u64)),
+            // Kelvin
+            temperature_c: battery.temperature().map(|t| t.value as f64 - 273.15),
+        }
+    }
+}
+
+/// Combined `(level, time_remaining, is_charging)` across batteries
+pub fn aggregate(batteries: &[BatteryReading]) -> (Option<f64>, Option<Duration>, Option<bool>) {
+    if batteries.is_empty() {
+        return (None, None, None);
+    }
+
+    let energy_wh: f64 = batteries.iter().map(|battery| battery.energy_wh).sum();
+    let full_wh: f64 = batteries.iter().map(|battery| battery.full_wh).sum();
+    let level = if full_wh > 0.0 {
+        energy_wh / full_wh * 100.0
+    } else {
+        batteries.iter().map(|battery| battery.level).sum::<f64>() / batteries.len() as f64
+    };
+
+    // Any battery charging means the system is on external power
+    let is_charging = if batteries.iter().any(|battery| battery.is_charging == Some(true)) {
+        Some(true)
+    } else if batteries.iter().any(|battery| battery.is_charging == Some(false)) {
+        Some(false)
+    } else {
+        None
+    };
+
+    let time_remaining = (is_charging == Some(false)).then(|| {
+        let drain_watts: f64 = batteries
+            .iter()
+            .filter(|battery| battery.is_charging == Some(false))
+            .map(|battery| battery.rate_watts)
+            .sum();
+        if drain_watts > 0.0 {
+            Some(Duration::from_secs_f64(energy_wh / drain_watts * 3600.0))
+        } else {
+            // Batteries drain one after another
+            batteries.iter().map(|battery| battery.time_remaining).sum()
+        }
+    });
+
+    (Some(level), time_remaining.flatten(), is_charging)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battery(id: &str, energy_wh: f64, full_wh: f64, rate_watts: f64, is_charging: Option<bool>) -> BatteryReading {
+        BatteryReading {
+            id: id.to_string(),
+            level: energy_wh / full_wh * 100.0,
+            energy_wh,
+            full_wh,
+            rate_watts,
+            is_charging,
+            time_remaining: None,
+            temperature_c: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_by_energy() {
+        // Internal 24 Wh pack nearly empty, 72 Wh external pack full and draining at 12 W
+        let batteries = vec![battery("int", 2.4, 24.0, 0.0, None), battery("ext", 72.0, 72.0, 12.0, Some(false))];
+        let (level, time_remaining, is_charging) = aggregate(&batteries);
+
+        assert!((level.unwrap() - 77.5).abs() < 1e-9);
+        assert_eq!(is_charging, Some(false));
+        assert_eq!(time_remaining, Some(Duration::from_secs(6 * 3600 + 12 * 60)));
+
+        assert_eq!(aggregate(&[]), (None, None, None));
+    }
+}
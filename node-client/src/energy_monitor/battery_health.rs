@@ -0,0 +1,262 @@
+/*!
+ * Battery health and degradation tracking
+ * Records cycle count and full-charge vs. design capacity per battery, persists a daily
+ * sample so the degradation trend survives restarts, and warns when health drops too far
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+};
+use tracing::warn;
+
+use super::calibration::fit_linear;
+
+/// Persisted samples are at least this far apart
+const SAMPLE_SPACING_SECS: u64 = 24 * 3600;
+/// History needed before a trend is reported
+const MIN_TREND_SECS: u64 = 14 * 24 * 3600;
+const SECS_PER_YEAR: f64 = 365.25 * 24.0 * 3600.0;
+
+/// Battery health settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryHealthConfig {
+    pub enabled: bool,
+    /// Warn when full-charge capacity falls below this share of design capacity
+    pub warn_below_percent: f64,
+    pub check_interval_secs: u64,
+    /// Where daily samples are kept (trend restarts with the process when unset)
+    pub history_path: Option<String>,
+}
+
+impl Default for BatteryHealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            warn_below_percent: 80.0,
+            check_interval_secs: 3600,
+            history_path: None,
+        }
+    }
+}
+
+/// Health of one battery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryHealth {
+    /// Serial number, or position when the battery reports none
+    pub id: String,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub cycle_count: Option<u32>,
+    pub design_capacity_wh: f64,
+    pub full_capacity_wh: f64,
+    /// Full-charge capacity as a percentage of design capacity
+    pub health_percent: f64,
+    /// Health lost per year (positive when degrading)
+    pub degradation_percent_per_year: Option<f64>,
+    pub measured_at: u64,
+}
+
+impl BatteryHealth {
+    pub fn new(id: String, cycle_count: Option<u32>, design_capacity_wh: f64, full_capacity_wh: f64, measured_at: u64) -> Self {
+        let health_percent = if design_capacity_wh > 0.0 {
+            full_capacity_wh / design_capacity_wh * 100.0
+        } else {
+            100.0
+        };
+
+        Self {
+            id,
+            vendor: None,
+            model: None,
+            cycle_count,
+            design_capacity_wh,
+            full_capacity_wh,
+            health_percent,
+            degradation_percent_per_year: None,
+            measured_at,
+        }
+    }
+
+    /// Read a battery from the `battery` crate
+    pub fn from_battery(index: usize, battery: &battery::Battery, measured_at: u64) -> Self {
+        let id = battery
+            .serial_number()
+            .map(str::trim)
+            .filter(|serial| !serial.is_empty())
+            .map_or_else(|| format!("battery{}", index), str::to_string);
+        // Energy values are in joules
+        let mut health = Self::new(
+            id,
+            battery.cycle_count(),
+            battery.energy_full_design().value as f64 / 3600.0,
+            battery.energy_full().value as f64 / 3600.0,
+            measured_at,
+        );
+        health.vendor = battery.vendor().map(str::to_string);
+        health.model = battery.model().map(str::to_string);
+        health
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HealthSample {
+    pub timestamp: u64,
+    pub health_percent: f64,
+    pub cycle_count: Option<u32>,
+}
+
+/// Daily samples per battery id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthHistory {
+    pub batteries: HashMap<String, Vec<HealthSample>>,
+}
+
+impl HealthHistory {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid battery health history in {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, serde_json::to_string(self)?).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Add a sample if the last one is a day old; returns whether it was added
+    pub fn record(&mut self, health: &BatteryHealth) -> bool {
+        let samples = self.batteries.entry(health.id.clone()).or_default();
+        if samples.last().is_some_and(|last| health.measured_at < last.timestamp + SAMPLE_SPACING_SECS) {
+            return false;
+        }
+        samples.push(HealthSample {
+            timestamp: health.measured_at,
+            health_percent: health.health_percent,
+            cycle_count: health.cycle_count,
+        });
+        true
+    }
+
+    /// Health lost per year from a linear fit over the samples
+    pub fn degradation_per_year(&self, id: &str) -> Option<f64> {
+        let samples = self.batteries.get(id)?;
+        let (first, last) = (samples.first()?, samples.last()?);
+        if last.timestamp - first.timestamp < MIN_TREND_SECS {
+            return None;
+        }
+
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|sample| ((sample.timestamp - first.timestamp) as f64 / SECS_PER_YEAR, sample.health_percent))
+            .collect();
+        Some(-fit_linear(&points)?.slope)
+    }
+}
+
+/// Periodic health checks for the monitor
+pub struct BatteryHealthTracker {
+    config: BatteryHealthConfig,
+    history: Mutex<HealthHistory>,
+    latest: RwLock<Vec<BatteryHealth>>,
+    last_check: AtomicU64,
+    /// Batteries already reported as worn, so each is warned about once
+    warned: Mutex<HashSet<String>>,
+}
+
+impl BatteryHealthTracker {
+    pub fn new(config: BatteryHealthConfig) -> Result<Self> {
+        let history = match config.history_path.as_deref().filter(|path| Path::new(path).exists()) {
+            Some(path) => HealthHistory::load(path)?,
+            None => HealthHistory::default(),
+        };
+
+        Ok(Self {
+            config,
+            history: Mutex::new(history),
+            latest: RwLock::new(Vec::new()),
+            last_check: AtomicU64::new(0),
+            warned: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Whether a check is due at `now`
+    pub fn due(&self, now: u64) -> bool {
+        now >= self.last_check.load(Ordering::Relaxed) + self.config.check_interval_secs
+    }
+
+    /// Record fresh readings, fill in trends and warn on worn batteries
+    pub fn update(&self, readings: Vec<BatteryHealth>, now: u64) -> Vec<BatteryHealth> {
+        self.last_check.store(now, Ordering::Relaxed);
+
+        let mut history = self.history.lock().unwrap();
+        let mut warned = self.warned.lock().unwrap();
+        let mut recorded = false;
+        let readings: Vec<BatteryHealth> = readings
+            .into_iter()
+            .map(|mut health| {
+                recorded |= history.record(&health);
+                health.degradation_percent_per_year = history.degradation_per_year(&health.id);
+                let worn = health.health_percent < self.config.warn_below_percent;
+                if !worn {
+                    warned.remove(&health.id);
+                } else if warned.insert(health.id.clone()) {
+                    warn!(
+                        "🪫 Battery {} health at {:.1}% of design capacity ({} cycles)",
+                        health.id,
+                        health.health_percent,
+                        health.cycle_count.map_or("unknown".to_string(), |cycles| cycles.to_string())
+                    );
+                }
+                health
+            })
+            .collect();
+
+        if let Some(path) = self.config.history_path.as_deref().filter(|_| recorded) {
+            if let Err(e) = history.save(path) {
+                warn!("Failed to save battery health history: {}", e);
+            }
+        }
+
+        *self.latest.write().unwrap() = readings.clone();
+        readings
+    }
+
+    /// Most recent health of every battery
+    pub fn latest(&self) -> Vec<BatteryHealth> {
+        self.latest.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degradation_trend() {
+        let day = 24 * 3600;
+        let mut history = HealthHistory::default();
+
+        // Losing 0.05 points a day, sampled twice a day
+        for half_day in 0..=120 {
+            let timestamp = half_day * day / 2;
+            let health = BatteryHealth::new("bat".to_string(), None, 50.0, 50.0 * (1.0 - 0.0005 * (timestamp / day) as f64), timestamp);
+            history.record(&health);
+        }
+
+        assert_eq!(history.batteries["bat"].len(), 61);
+        let trend = history.degradation_per_year("bat").unwrap();
+        assert!((trend - 0.05 * 365.25).abs() < 0.1);
+        assert_eq!(history.degradation_per_year("other"), None);
+
+        let worn = BatteryHealth::new("bat".to_string(), Some(812), 50.0, 38.0, 0);
+        assert!((worn.health_percent - 76.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,264 @@
+/*!
+ * Energy and carbon budgets
+ * Tracks cumulative consumption per day or month against operator-set caps and fires
+ * registered hooks (alert, lower duty cycle, pause detection) as thresholds are crossed
+ */
+
+use chrono::{Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::EnergyData;
+
+/// Gaps longer than this are not counted as consumption
+const MAX_SAMPLE_GAP_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    /// Start (unix seconds, UTC) of the period containing `timestamp`
+    pub fn start_of(&self, timestamp: u64) -> u64 {
+        match self {
+            Self::Daily => timestamp - timestamp % 86_400,
+            Self::Monthly => {
+                let now = Utc.timestamp_opt(timestamp as i64, 0).single().unwrap_or_else(Utc::now);
+                Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+                    .single()
+                    .map_or(timestamp, |start| start.timestamp() as u64)
+            }
+        }
+    }
+}
+
+/// Quantity a budget caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetMetric {
+    EnergyKwh,
+    CarbonKg,
+}
+
+/// What the node should do once a threshold is crossed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetAction {
+    Alert,
+    LowerDutyCycle,
+    PauseDetection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetThreshold {
+    /// Fraction of the limit (1.0 = budget exhausted)
+    pub fraction: f64,
+    pub action: BudgetAction,
+}
+
+/// One budget cap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyBudget {
+    pub name: String,
+    pub period: BudgetPeriod,
+    pub metric: BudgetMetric,
+    pub limit: f64,
+    #[serde(default = "default_thresholds")]
+    pub thresholds: Vec<BudgetThreshold>,
+}
+
+fn default_thresholds() -> Vec<BudgetThreshold> {
+    vec![
+        BudgetThreshold { fraction: 0.8, action: BudgetAction::Alert },
+        BudgetThreshold { fraction: 0.9, action: BudgetAction::LowerDutyCycle },
+        BudgetThreshold { fraction: 1.0, action: BudgetAction::PauseDetection },
+    ]
+}
+
+/// Fired when consumption crosses a threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetEvent {
+    pub budget: String,
+    pub metric: BudgetMetric,
+    pub period_start: u64,
+    pub fraction: f64,
+    pub action: BudgetAction,
+    pub consumed: f64,
+    pub limit: f64,
+    pub timestamp: u64,
+}
+
+/// Consumption so far in the current period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub budget: String,
+    pub metric: BudgetMetric,
+    pub period_start: u64,
+    pub consumed: f64,
+    pub limit: f64,
+    /// Consumed / limit
+    pub used_fraction: f64,
+}
+
+/// Callback run for every budget event
+pub type BudgetHook = Arc<dyn Fn(&BudgetEvent) + Send + Sync>;
+
+#[derive(Debug, Clone)]
+struct BudgetState {
+    period_start: u64,
+    consumed: f64,
+    /// Thresholds already fired this period
+    fired: Vec<bool>,
+}
+
+/// Accumulates consumption for every configured budget
+#[derive(Debug, Clone, Default)]
+pub struct BudgetTracker {
+    budgets: Vec<EnergyBudget>,
+    states: Vec<BudgetState>,
+    last_timestamp: Option<u64>,
+}
+
+impl BudgetTracker {
+    pub fn new(mut budgets: Vec<EnergyBudget>) -> Self {
+        for budget in &mut budgets {
+            budget.thresholds.sort_by(|a, b| a.fraction.total_cmp(&b.fraction));
+        }
+        let states = budgets
+            .iter()
+            .map(|budget| BudgetState {
+                period_start: 0,
+                consumed: 0.0,
+                fired: vec![false; budget.thresholds.len()],
+            })
+            .collect();
+
+        Self {
+            budgets,
+            states,
+            last_timestamp: None,
+        }
+    }
+
+    pub fn budgets(&self) -> &[EnergyBudget] {
+        &self.budgets
+    }
+
+    /// Account a reading; returns the thresholds it crossed
+    pub fn record(&mut self, data: &EnergyData) -> Vec<BudgetEvent> {
+        let dt = match self.last_timestamp {
+            Some(last) if data.timestamp > last && data.timestamp - last <= MAX_SAMPLE_GAP_SECS => {
+                data.timestamp - last
+            }
+            _ => 0,
+        };
+        self.last_timestamp = Some(data.timestamp);
+        let hours = dt as f64 / 3600.0;
+
+        let mut events = Vec::new();
+        for (budget, state) in self.budgets.iter().zip(self.states.iter_mut()) {
+            let period_start = budget.period.start_of(data.timestamp);
+            if period_start != state.period_start {
+                state.period_start = period_start;
+                state.consumed = 0.0;
+                state.fired.iter_mut().for_each(|fired| *fired = false);
+            }
+
+            state.consumed += match budget.metric {
+                BudgetMetric::EnergyKwh => data.total_watts * hours / 1000.0,
+                BudgetMetric::CarbonKg => data.carbon_footprint_kg_per_hour * hours,
+            };
+            if budget.limit <= 0.0 {
+                continue;
+            }
+
+            for (threshold, fired) in budget.thresholds.iter().zip(state.fired.iter_mut()) {
+                if !*fired && state.consumed >= budget.limit * threshold.fraction {
+                    *fired = true;
+                    events.push(BudgetEvent {
+                        budget: budget.name.clone(),
+                        metric: budget.metric,
+                        period_start,
+                        fraction: threshold.fraction,
+                        action: threshold.action,
+                        consumed: state.consumed,
+                        limit: budget.limit,
+                        timestamp: data.timestamp,
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    /// Current consumption against every budget
+    pub fn status(&self) -> Vec<BudgetStatus> {
+        self.budgets
+            .iter()
+            .zip(self.states.iter())
+            .map(|(budget, state)| BudgetStatus {
+                budget: budget.name.clone(),
+                metric: budget.metric,
+                period_start: state.period_start,
+                consumed: state.consumed,
+                limit: budget.limit,
+                used_fraction: if budget.limit > 0.0 { state.consumed / budget.limit } else { 0.0 },
+            })
+            .collect()
+    }
+
+    /// Earliest period start across budgets, for replaying persisted history
+    pub fn earliest_period_start(&self, now: u64) -> Option<u64> {
+        self.budgets.iter().map(|budget| budget.period.start_of(now)).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, watts: f64) -> EnergyData {
+        EnergyData {
+            total_watts: watts,
+            efficiency_score: 50,
+            timestamp,
+            ..EnergyData::default()
+        }
+    }
+
+    #[test]
+    fn test_period_start() {
+        // 2024-03-15T12:00:00Z
+        let ts = 1_710_504_000;
+        assert_eq!(BudgetPeriod::Daily.start_of(ts), 1_710_460_800);
+        // 2024-03-01T00:00:00Z
+        assert_eq!(BudgetPeriod::Monthly.start_of(ts), 1_709_251_200);
+    }
+
+    #[test]
+    fn test_thresholds_fire_once_per_period() {
+        let mut tracker = BudgetTracker::new(vec![EnergyBudget {
+            name: "daily".to_string(),
+            period: BudgetPeriod::Daily,
+            metric: BudgetMetric::EnergyKwh,
+            limit: 0.1,
+            thresholds: default_thresholds(),
+        }]);
+
+        // 100 W for just over an hour (> 0.1 kWh), sampled every 60 s
+        let mut events = Vec::new();
+        for i in 0..=61 {
+            events.extend(tracker.record(&reading(i * 60, 100.0)));
+        }
+        let actions: Vec<BudgetAction> = events.iter().map(|e| e.action).collect();
+        assert_eq!(
+            actions,
+            vec![BudgetAction::Alert, BudgetAction::LowerDutyCycle, BudgetAction::PauseDetection]
+        );
+        assert!(tracker.record(&reading(3_720, 100.0)).is_empty());
+
+        // Next day resets the budget
+        tracker.record(&reading(86_400, 100.0));
+        assert_eq!(tracker.status()[0].consumed, 0.0);
+    }
+}
@@ -0,0 +1,241 @@
+/*!
+ * Power model calibration
+ * Runs controlled load steps (idle, partial and full CPU, optional proof generation) while
+ * comparing the model against RAPL or a smart plug, then fits per-machine CPU and idle terms
+ */
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// Calibration settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationConfig {
+    /// Where fitted coefficients are stored and loaded from on startup
+    pub path: Option<String>,
+    /// CPU load fractions to hold (0-1)
+    pub load_steps: Vec<f64>,
+    pub step_secs: u64,
+    /// Discarded at the start of each step while power settles
+    pub settle_secs: u64,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            load_steps: vec![0.0, 0.25, 0.5, 1.0],
+            step_secs: 30,
+            settle_secs: 5,
+        }
+    }
+}
+
+/// Measurement the model is fitted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CalibrationReference {
+    /// CPU package counters; fits the CPU terms only
+    Rapl,
+    /// Wall power; fits the CPU slope and the whole-system idle
+    SmartPlug,
+}
+
+/// Averages over one load step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationStep {
+    pub label: String,
+    pub cpu_usage: f64,
+    pub reference_watts: f64,
+    pub modeled_watts: f64,
+}
+
+/// Least-squares line through (x, y) points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearFit {
+    pub intercept: f64,
+    pub slope: f64,
+    pub r_squared: f64,
+}
+
+pub fn fit_linear(points: &[(f64, f64)]) -> Option<LinearFit> {
+    let n = points.len() as f64;
+    if points.len() < 2 {
+        return None;
+    }
+
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let syy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if sxx <= f64::EPSILON {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy > 0.0 { sxy * sxy / (sxx * syy) } else { 1.0 };
+    Some(LinearFit {
+        intercept: mean_y - slope * mean_x,
+        slope,
+        r_squared,
+    })
+}
+
+/// Linear CPU model in use when calibration starts
+#[derive(Debug, Clone, Copy)]
+pub struct CpuModel {
+    pub idle_watts: f64,
+    pub dynamic_watts: f64,
+    pub idle_offset_watts: f64,
+}
+
+/// Fitted, persisted correction to `PowerCoefficients`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibratedCoefficients {
+    pub reference: CalibrationReference,
+    /// CPU power at 0% load (RAPL only)
+    pub cpu_idle_watts: Option<f64>,
+    /// CPU power added at 100% load
+    pub cpu_dynamic_watts: f64,
+    /// Added to the modeled baseline so idle matches the wall
+    pub idle_offset_watts: f64,
+    pub r_squared: f64,
+    pub calibrated_at: u64,
+    pub steps: Vec<CalibrationStep>,
+}
+
+impl CalibratedCoefficients {
+    /// Fit against `steps`; `current` is the model the steps' `modeled_watts` came from
+    pub fn fit(reference: CalibrationReference, steps: Vec<CalibrationStep>, current: CpuModel) -> Result<Self> {
+        let measured: Vec<(f64, f64)> = steps.iter().map(|step| (step.cpu_usage, step.reference_watts)).collect();
+        let fit = fit_linear(&measured).context("Calibration needs at least two distinct load levels")?;
+
+        let (cpu_idle_watts, cpu_dynamic_watts, idle_offset_watts) = match reference {
+            CalibrationReference::Rapl => (Some(fit.intercept.max(0.0)), fit.slope.max(0.0), current.idle_offset_watts),
+            CalibrationReference::SmartPlug => {
+                // Correct the model by the difference between the two lines
+                let modeled: Vec<(f64, f64)> = steps.iter().map(|step| (step.cpu_usage, step.modeled_watts)).collect();
+                let model = fit_linear(&modeled).context("Model readings do not vary with load")?;
+                (
+                    None,
+                    (current.dynamic_watts + fit.slope - model.slope).max(0.0),
+                    current.idle_offset_watts + fit.intercept - model.intercept,
+                )
+            }
+        };
+
+        Ok(Self {
+            reference,
+            cpu_idle_watts,
+            cpu_dynamic_watts,
+            idle_offset_watts,
+            r_squared: fit.r_squared,
+            calibrated_at: chrono::Utc::now().timestamp() as u64,
+            steps,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid calibration in {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Extra load step, typically a ZK proof generation
+pub type CalibrationWorkload = Arc<dyn Fn() -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Duty-cycled busy loops holding every core at a target load
+pub struct CpuLoad {
+    stop: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl CpuLoad {
+    const PERIOD: Duration = Duration::from_millis(100);
+
+    pub fn start(fraction: f64, cores: usize) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let busy = Self::PERIOD.mul_f64(fraction.clamp(0.0, 1.0));
+
+        let workers = if busy.is_zero() {
+            Vec::new()
+        } else {
+            (0..cores.max(1))
+                .map(|_| {
+                    let stop = stop.clone();
+                    std::thread::spawn(move || {
+                        while !stop.load(Ordering::Relaxed) {
+                            let start = Instant::now();
+                            while start.elapsed() < busy {
+                                std::hint::spin_loop();
+                            }
+                            std::thread::sleep(Self::PERIOD.saturating_sub(busy));
+                        }
+                    })
+                })
+                .collect()
+        };
+
+        Self { stop, workers }
+    }
+}
+
+impl Drop for CpuLoad {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(cpu_usage: f64, reference_watts: f64, modeled_watts: f64) -> CalibrationStep {
+        CalibrationStep {
+            label: format!("{:.0}%", cpu_usage * 100.0),
+            cpu_usage,
+            reference_watts,
+            modeled_watts,
+        }
+    }
+
+    #[test]
+    fn test_fit_against_wall_power() {
+        let current = CpuModel {
+            idle_watts: 9.75,
+            dynamic_watts: 55.25,
+            idle_offset_watts: 0.0,
+        };
+        // Wall reads 40 W idle and 120 W flat out; the model says 60 W and 115 W
+        let steps = vec![step(0.0, 40.0, 60.0), step(0.5, 80.0, 87.5), step(1.0, 120.0, 115.0)];
+
+        let fitted = CalibratedCoefficients::fit(CalibrationReference::SmartPlug, steps, current).unwrap();
+        assert!((fitted.idle_offset_watts + 20.0).abs() < 1e-9);
+        assert!((fitted.cpu_dynamic_watts - 80.25).abs() < 1e-9);
+        assert!((fitted.r_squared - 1.0).abs() < 1e-9);
+        assert_eq!(fitted.cpu_idle_watts, None);
+
+        let single = vec![step(0.5, 80.0, 87.5)];
+        assert!(CalibratedCoefficients::fit(CalibrationReference::Rapl, single, current).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+/*!
+ * Grid carbon intensity providers
+ * Live intensity from electricityMap or WattTime, cached with a TTL and falling back to
+ * static country averages when no provider is configured or the API is unreachable
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+/// Global average grid intensity (kg CO2 per kWh)
+pub const GLOBAL_AVERAGE_INTENSITY: f64 = 0.475;
+
+const ELECTRICITY_MAPS_URL: &str = "https://api.electricitymap.org/v3/carbon-intensity/latest";
+const ELECTRICITY_MAPS_BREAKDOWN_URL: &str = "https://api.electricitymap.org/v3/power-breakdown/latest";
+const ELECTRICITY_MAPS_FORECAST_URL: &str = "https://api.electricitymap.org/v3/carbon-intensity/forecast";
+const WATTTIME_LOGIN_URL: &str = "https://api.watttime.org/login";
+const WATTTIME_FORECAST_URL: &str = "https://api.watttime.org/v3/forecast";
+
+/// Annual average grid intensity by ISO country code (kg CO2 per kWh)
+const COUNTRY_AVERAGES: &[(&str, f64)] = &[
+    ("AU", 0.53),
+    ("BR", 0.10),
+    ("CA", 0.13),
+    ("CH", 0.05),
+    ("CN", 0.58),
+    ("DE", 0.38),
+    ("ES", 0.17),
+    ("FR", 0.06),
+    ("GB", 0.21),
+    ("IN", 0.71),
+    ("IT", 0.33),
+    ("JP", 0.46),
+    ("KR", 0.44),
+    ("NL", 0.33),
+    ("NO", 0.03),
+    ("PL", 0.66),
+    ("SE", 0.04),
+    ("SG", 0.47),
+    ("US", 0.37),
+    ("VN", 0.47),
+    ("ZA", 0.71),
+];
+
+/// Live data source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CarbonProvider {
+    /// Static country averages only
+    #[default]
+    Static,
+    ElectricityMaps,
+    WattTime,
+}
+
+/// Carbon intensity settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarbonIntensityConfig {
+    pub provider: CarbonProvider,
+    /// ISO country code used for the static fallback (e.g. "DE")
+    pub country_code: String,
+    /// Provider zone/region (electricityMap zone such as "DE", WattTime region such as "CAISO_NORTH")
+    pub zone: String,
+    /// electricityMap auth token
+    pub api_key: Option<String>,
+    /// WattTime account
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub cache_ttl_secs: u64,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for CarbonIntensityConfig {
+    fn default() -> Self {
+        Self {
+            provider: CarbonProvider::Static,
+            country_code: String::new(),
+            zone: String::new(),
+            api_key: None,
+            username: None,
+            password: None,
+            cache_ttl_secs: 900,
+            request_timeout_secs: 10,
+        }
+    }
+}
+
+/// Static average for a country, or the global average
+pub fn static_intensity(country_code: &str) -> f64 {
+    COUNTRY_AVERAGES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(country_code))
+        .map_or(GLOBAL_AVERAGE_INTENSITY, |(_, intensity)| *intensity)
+}
+
+/// Convert WattTime MOER (lbs CO2 per MWh) to kg CO2 per kWh
+pub fn lbs_per_mwh_to_kg_per_kwh(lbs_per_mwh: f64) -> f64 {
+    lbs_per_mwh * 0.453_592 / 1000.0
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ElectricityMapsResponse {
+    /// gCO2eq per kWh
+    carbon_intensity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ElectricityMapsForecast {
+    forecast: Vec<ElectricityMapsForecastPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ElectricityMapsForecastPoint {
+    carbon_intensity: f64,
+    datetime: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ElectricityMapsBreakdown {
+    renewable_percentage: Option<f64>,
+    fossil_free_percentage: Option<f64>,
+    /// MW consumed per source
+    #[serde(default)]
+    power_consumption_breakdown: BTreeMap<String, Option<f64>>,
+}
+
+/// Generation mix of the local grid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridMix {
+    pub zone: String,
+    pub renewable_percent: Option<f64>,
+    pub fossil_free_percent: Option<f64>,
+    /// Share of consumption per source (percent)
+    pub sources: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WattTimeLogin {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WattTimeForecast {
+    data: Vec<WattTimePoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WattTimePoint {
+    value: f64,
+    point_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Forecast intensity at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IntensityPoint {
+    /// Unix seconds
+    pub timestamp: u64,
+    /// kg CO2 per kWh
+    pub intensity: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedIntensity {
+    value: f64,
+    fetched_at: Instant,
+}
+
+/// Cached carbon intensity source
+pub struct CarbonIntensityProvider {
+    config: CarbonIntensityConfig,
+    http: reqwest::Client,
+    cache: RwLock<Option<CachedIntensity>>,
+    forecast_cache: RwLock<Option<(Instant, Vec<IntensityPoint>)>>,
+    watttime_token: RwLock<Option<String>>,
+}
+
+impl CarbonIntensityProvider {
+    pub fn new(config: CarbonIntensityConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            http,
+            cache: RwLock::new(None),
+            forecast_cache: RwLock::new(None),
+            watttime_token: RwLock::new(None),
+        }
+    }
+
+    /// Static fallback for the configured country
+    pub fn fallback_intensity(&self) -> f64 {
+        static_intensity(&self.config.country_code)
+    }
+
+    /// Current intensity in kg CO2 per kWh; never fails
+    pub async fn current_intensity(&self) -> f64 {
+        if self.config.provider == CarbonProvider::Static {
+            return self.fallback_intensity();
+        }
+
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+        if let Some(cached) = *self.cache.read().unwrap() {
+            if cached.fetched_at.elapsed() < ttl {
+                return cached.value;
+            }
+        }
+
+        match self.fetch().await {
+            Ok(value) => {
+                debug!("🌍 Grid carbon intensity for {}: {:.3} kg CO2/kWh", self.config.zone, value);
+                *self.cache.write().unwrap() = Some(CachedIntensity {
+                    value,
+                    fetched_at: Instant::now(),
+                });
+                value
+            }
+            Err(e) => {
+                warn!("Carbon intensity provider unavailable, using static average: {}", e);
+                // Keep serving a stale live value over the static average when we have one
+                self.cache.read().unwrap()
+                    .map_or_else(|| self.fallback_intensity(), |cached| cached.value)
+            }
+        }
+    }
+
+    /// Forecast intensity for the next `horizon_hours`; empty for the static provider or on error
+    pub async fn forecast(&self, horizon_hours: u32) -> Vec<IntensityPoint> {
+        if self.config.provider == CarbonProvider::Static {
+            return Vec::new();
+        }
+
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+        if let Some((fetched_at, points)) = &*self.forecast_cache.read().unwrap() {
+            if fetched_at.elapsed() < ttl {
+                return points.clone();
+            }
+        }
+
+        let fetched = match self.config.provider {
+            CarbonProvider::Static => Ok(Vec::new()),
+            CarbonProvider::ElectricityMaps => self.fetch_electricity_maps_forecast().await,
+            CarbonProvider::WattTime => self.fetch_watttime_forecast(horizon_hours).await,
+        };
+        match fetched {
+            Ok(points) => {
+                *self.forecast_cache.write().unwrap() = Some((Instant::now(), points.clone()));
+                points
+            }
+            Err(e) => {
+                warn!("Carbon intensity forecast unavailable: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn fetch_electricity_maps_forecast(&self) -> Result<Vec<IntensityPoint>> {
+        let api_key = self.config.api_key.as_deref().context("electricityMap API key not configured")?;
+
+        let response: ElectricityMapsForecast = self.http
+            .get(ELECTRICITY_MAPS_FORECAST_URL)
+            .query(&[("zone", self.config.zone.as_str())])
+            .header("auth-token", api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response
+            .forecast
+            .into_iter()
+            .map(|point| IntensityPoint {
+                timestamp: point.datetime.timestamp() as u64,
+                intensity: point.carbon_intensity / 1000.0,
+            })
+            .collect())
+    }
+
+    /// Current grid generation mix (electricityMap only)
+    pub async fn grid_mix(&self) -> Option<GridMix> {
+        if self.config.provider != CarbonProvider::ElectricityMaps {
+            return None;
+        }
+
+        match self.fetch_grid_mix().await {
+            Ok(mix) => Some(mix),
+            Err(e) => {
+                warn!("Grid mix unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn fetch_grid_mix(&self) -> Result<GridMix> {
+        let api_key = self.config.api_key.as_deref().context("electricityMap API key not configured")?;
+
+        let breakdown: ElectricityMapsBreakdown = self.http
+            .get(ELECTRICITY_MAPS_BREAKDOWN_URL)
+            .query(&[("zone", self.config.zone.as_str())])
+            .header("auth-token", api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let consumption: BTreeMap<String, f64> = breakdown
+            .power_consumption_breakdown
+            .into_iter()
+            .filter_map(|(source, mw)| Some((source, mw?.max(0.0))))
+            .collect();
+        let total: f64 = consumption.values().sum();
+
+        Ok(GridMix {
+            zone: self.config.zone.clone(),
+            renewable_percent: breakdown.renewable_percentage,
+            fossil_free_percent: breakdown.fossil_free_percentage,
+            sources: consumption
+                .into_iter()
+                .map(|(source, mw)| (source, if total > 0.0 { mw / total * 100.0 } else { 0.0 }))
+                .collect(),
+        })
+    }
+
+    async fn fetch(&self) -> Result<f64> {
+        match self.config.provider {
+            CarbonProvider::Static => Ok(self.fallback_intensity()),
+            CarbonProvider::ElectricityMaps => self.fetch_electricity_maps().await,
+            CarbonProvider::WattTime => self.fetch_watttime().await,
+        }
+    }
+
+    async fn fetch_electricity_maps(&self) -> Result<f64> {
+        let api_key = self.config.api_key.as_deref().context("electricityMap API key not configured")?;
+
+        let response: ElectricityMapsResponse = self.http
+            .get(ELECTRICITY_MAPS_URL)
+            .query(&[("zone", self.config.zone.as_str())])
+            .header("auth-token", api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // g/kWh -> kg/kWh
+        Ok(response.carbon_intensity / 1000.0)
+    }
+
+    async fn watttime_login(&self) -> Result<String> {
+        let username = self.config.username.as_deref().context("WattTime username not configured")?;
+
+        let login: WattTimeLogin = self.http
+            .get(WATTTIME_LOGIN_URL)
+            .basic_auth(username, self.config.password.as_deref())
+            .send()
+            .await?
+            .error_for_status()
+            .context("WattTime login failed")?
+            .json()
+            .await?;
+
+        *self.watttime_token.write().unwrap() = Some(login.token.clone());
+        Ok(login.token)
+    }
+
+    async fn fetch_watttime(&self) -> Result<f64> {
+        let forecast = self.watttime_forecast(0).await?;
+        let point = forecast.data.first().context("Empty WattTime forecast")?;
+
+        Ok(lbs_per_mwh_to_kg_per_kwh(point.value))
+    }
+
+    async fn fetch_watttime_forecast(&self, horizon_hours: u32) -> Result<Vec<IntensityPoint>> {
+        let forecast = self.watttime_forecast(horizon_hours).await?;
+        Ok(forecast
+            .data
+            .into_iter()
+            .filter_map(|point| {
+                Some(IntensityPoint {
+                    timestamp: point.point_time?.timestamp() as u64,
+                    intensity: lbs_per_mwh_to_kg_per_kwh(point.value),
+                })
+            })
+            .collect())
+    }
+
+    async fn watttime_forecast(&self, horizon_hours: u32) -> Result<WattTimeForecast> {
+        let cached_token = self.watttime_token.read().unwrap().clone();
+        let token = match cached_token {
+            Some(token) => token,
+            None => self.watttime_login().await?,
+        };
+
+        let horizon_hours = horizon_hours.to_string();
+        let request = |token: String| {
+            self.http
+                .get(WATTTIME_FORECAST_URL)
+                .query(&[
+                    ("region", self.config.zone.as_str()),
+                    ("signal_type", "co2_moer"),
+                    ("horizon_hours", horizon_hours.as_str()),
+                ])
+                .bearer_auth(token)
+                .send()
+        };
+
+        let mut response = request(token).await?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            // Tokens expire after 30 minutes
+            response = request(self.watttime_login().await?).await?;
+        }
+
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_fallback() {
+        assert_eq!(static_intensity("fr"), 0.06);
+        assert_eq!(static_intensity(""), GLOBAL_AVERAGE_INTENSITY);
+        assert_eq!(static_intensity("XX"), GLOBAL_AVERAGE_INTENSITY);
+    }
+
+    #[test]
+    fn test_moer_conversion() {
+        let kg = lbs_per_mwh_to_kg_per_kwh(1000.0);
+        assert!((kg - 0.4536).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,329 @@
+/*!
+ * Signed monthly carbon reports
+ * Summarizes a month of consumption (kWh, kg CO2, grid mix, purchased offsets) and signs it
+ * with the node wallet so the DAGShield oracle can verify it for green-node incentives
+ */
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use ethers::{
+    abi::{self, Token},
+    signers::{LocalWallet, Signer},
+    types::{Address, Signature, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+
+use super::carbon::GridMix;
+use super::EnergyStats;
+use crate::zk_prover::EnergyRangeProof;
+
+/// Report format version, bumped when fields or the digest encoding change
+pub const REPORT_VERSION: u32 = 2;
+
+/// Quantities enter the digest as integer millionths
+const DIGEST_SCALE: f64 = 1e6;
+
+fn fixed_point(value: f64) -> Token {
+    Token::Uint(U256::from((value.max(0.0) * DIGEST_SCALE).round() as u64))
+}
+
+/// Offset certificate bought by the operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarbonOffset {
+    pub registry: String,
+    pub certificate_id: String,
+    pub kg_co2: f64,
+    pub purchased_at: u64,
+}
+
+/// Monthly consumption and emissions summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarbonReport {
+    pub version: u32,
+    pub node_id: String,
+    /// Unix seconds, `period_start <= t < period_end`
+    pub period_start: u64,
+    pub period_end: u64,
+    pub energy_kwh: f64,
+    pub carbon_kg: f64,
+    /// Average grid intensity over the period (kg CO2 per kWh)
+    pub avg_intensity_kg_per_kwh: f64,
+    pub avg_power_watts: f64,
+    pub uptime_hours: f64,
+    /// Grid mix when the report was generated; only attached while the month is in progress,
+    /// since the provider has no history to price a past month with
+    pub grid_mix: Option<GridMix>,
+    pub offsets: Vec<CarbonOffset>,
+    pub offsets_kg: f64,
+    /// Emissions after offsets, never below zero
+    pub net_carbon_kg: f64,
+    /// Optional ZK proof that average power stayed under a threshold
+    #[serde(default)]
+    pub power_proof: Option<EnergyRangeProof>,
+    pub generated_at: u64,
+}
+
+/// Report plus the node wallet's signature over its digest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedCarbonReport {
+    pub report: CarbonReport,
+    pub digest: H256,
+    pub signature: String,
+    pub signer: Address,
+}
+
+/// `[start, end)` of a calendar month in UTC
+pub fn month_bounds(year: i32, month: u32) -> Result<(u64, u64)> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1).context("Invalid report month")?;
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .context("Invalid report month")?;
+
+    let to_unix = |date: NaiveDate| {
+        Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).timestamp() as u64
+    };
+    Ok((to_unix(start), to_unix(next)))
+}
+
+impl CarbonReport {
+    /// Build a report from the period's energy statistics
+    pub fn new(
+        node_id: &str,
+        (period_start, period_end): (u64, u64),
+        stats: &EnergyStats,
+        grid_mix: Option<GridMix>,
+        offsets: Vec<CarbonOffset>,
+    ) -> Self {
+        let offsets_kg: f64 = offsets.iter().map(|offset| offset.kg_co2).sum();
+
+        Self {
+            version: REPORT_VERSION,
+            node_id: node_id.to_string(),
+            period_start,
+            period_end,
+            energy_kwh: stats.total_energy_kwh,
+            carbon_kg: stats.total_carbon_kg,
+            avg_intensity_kg_per_kwh: if stats.total_energy_kwh > 0.0 {
+                stats.total_carbon_kg / stats.total_energy_kwh
+            } else {
+                0.0
+            },
+            avg_power_watts: stats.avg_power_watts,
+            uptime_hours: stats.uptime_hours,
+            grid_mix,
+            offsets,
+            offsets_kg,
+            net_carbon_kg: (stats.total_carbon_kg - offsets_kg).max(0.0),
+            power_proof: None,
+            generated_at: Utc::now().timestamp() as u64,
+        }
+    }
+
+    /// Attach a power range proof before signing
+    pub fn with_power_proof(mut self, proof: EnergyRangeProof) -> Self {
+        self.power_proof = Some(proof);
+        self
+    }
+
+    /// keccak256 of the ABI encoding of every field, quantities as integer millionths, so the
+    /// digest does not depend on JSON field order or float formatting
+    pub fn digest(&self) -> H256 {
+        let grid_mix = self.grid_mix.as_ref();
+        let sources = grid_mix
+            .map(|mix| {
+                mix.sources
+                    .iter()
+                    .map(|(source, share)| Token::Tuple(vec![Token::String(source.clone()), fixed_point(*share)]))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let offsets = self
+            .offsets
+            .iter()
+            .map(|offset| {
+                Token::Tuple(vec![
+                    Token::String(offset.registry.clone()),
+                    Token::String(offset.certificate_id.clone()),
+                    fixed_point(offset.kg_co2),
+                    Token::Uint(offset.purchased_at.into()),
+                ])
+            })
+            .collect();
+        let power_proof = self.power_proof.as_ref().map_or_else(Vec::new, |proof| {
+            abi::encode(&[
+                Token::Bytes(proof.proof.clone()),
+                Token::Uint(proof.max_avg_milliwatts.into()),
+                Token::String(proof.commitment.clone()),
+            ])
+        });
+
+        let encoded = abi::encode(&[
+            Token::Uint(self.version.into()),
+            Token::String(self.node_id.clone()),
+            Token::Uint(self.period_start.into()),
+            Token::Uint(self.period_end.into()),
+            fixed_point(self.energy_kwh),
+            fixed_point(self.carbon_kg),
+            fixed_point(self.avg_intensity_kg_per_kwh),
+            fixed_point(self.avg_power_watts),
+            fixed_point(self.uptime_hours),
+            Token::String(grid_mix.map_or_else(String::new, |mix| mix.zone.clone())),
+            fixed_point(grid_mix.and_then(|mix| mix.renewable_percent).unwrap_or_default()),
+            fixed_point(grid_mix.and_then(|mix| mix.fossil_free_percent).unwrap_or_default()),
+            Token::Array(sources),
+            Token::Array(offsets),
+            fixed_point(self.offsets_kg),
+            fixed_point(self.net_carbon_kg),
+            Token::FixedBytes(keccak256(power_proof).to_vec()),
+            Token::Uint(self.generated_at.into()),
+        ]);
+        H256::from(keccak256(encoded))
+    }
+
+    /// Sign the report digest with the node wallet (EIP-191 personal message)
+    pub async fn sign(self, wallet: &LocalWallet) -> Result<SignedCarbonReport> {
+        let digest = self.digest();
+        let signature = wallet.sign_message(digest.as_bytes()).await?;
+
+        Ok(SignedCarbonReport {
+            report: self,
+            digest,
+            signature: format!("0x{}", signature),
+            signer: wallet.address(),
+        })
+    }
+}
+
+impl SignedCarbonReport {
+    /// Check that the digest matches the report and the signature recovers to `signer`
+    pub fn verify(&self) -> Result<()> {
+        if self.report.digest() != self.digest {
+            return Err(anyhow::anyhow!("Carbon report digest mismatch"));
+        }
+
+        let signature: Signature = self.signature.trim_start_matches("0x").parse()?;
+        signature
+            .verify(self.digest.as_bytes(), self.signer)
+            .context("Carbon report signature does not match signer")
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Self-contained HTML document, ready for HTML-to-PDF conversion
+    pub fn to_html(&self) -> String {
+        let report = &self.report;
+        let date = |ts: u64| {
+            Utc.timestamp_opt(ts as i64, 0)
+                .single()
+                .map_or_else(String::new, |d| d.format("%Y-%m-%d").to_string())
+        };
+        let month = Utc
+            .timestamp_opt(report.period_start as i64, 0)
+            .single()
+            .map_or_else(String::new, |d| format!("{}-{:02}", d.year(), d.month()));
+
+        let mut rows = vec![
+            ("Node", report.node_id.clone()),
+            ("Period", format!("{} to {}", date(report.period_start), date(report.period_end))),
+            ("Energy", format!("{:.3} kWh", report.energy_kwh)),
+            ("Emissions", format!("{:.3} kg CO2", report.carbon_kg)),
+            ("Average grid intensity", format!("{:.3} kg CO2/kWh", report.avg_intensity_kg_per_kwh)),
+            ("Average power", format!("{:.1} W", report.avg_power_watts)),
+            ("Uptime", format!("{:.1} h", report.uptime_hours)),
+        ];
+        if let Some(mix) = &report.grid_mix {
+            if let Some(renewable) = mix.renewable_percent {
+                rows.push(("Grid renewable share", format!("{:.1}% ({})", renewable, mix.zone)));
+            }
+        }
+        for offset in &report.offsets {
+            rows.push((
+                "Offset",
+                format!("{:.1} kg CO2 ({} #{})", offset.kg_co2, offset.registry, offset.certificate_id),
+            ));
+        }
+        rows.push(("Net emissions", format!("{:.3} kg CO2", report.net_carbon_kg)));
+        if let Some(proof) = &report.power_proof {
+            rows.push((
+                "Average power proof",
+                format!("ZK proof of at most {:.1} W", proof.max_avg_milliwatts as f64 / 1000.0),
+            ));
+        }
+
+        let table: String = rows
+            .iter()
+            .map(|(label, value)| format!("<tr><th>{}</th><td>{}</td></tr>\n", label, html_escape(value)))
+            .collect();
+
+        format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>DAGShield carbon report {month}</title>\
+             <style>body{{font-family:sans-serif}}th{{text-align:left;padding-right:2em}}</style></head>\n\
+             <body><h1>DAGShield carbon report {month}</h1>\n<table>\n{table}</table>\n\
+             <p><small>Signer {signer:?}<br>Digest {digest:?}<br>Signature {signature}</small></p></body></html>\n",
+            month = month,
+            table = table,
+            signer = self.signer,
+            digest = self.digest,
+            signature = self.signature,
+        )
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> EnergyStats {
+        EnergyStats {
+            avg_power_watts: 50.0,
+            min_power_watts: 40.0,
+            max_power_watts: 60.0,
+            total_energy_kwh: 36.0,
+            avg_efficiency_score: 80,
+            total_carbon_kg: 12.0,
+            uptime_hours: 720.0,
+            subsystems: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        assert_eq!(month_bounds(2024, 2).unwrap(), (1_706_745_600, 1_709_251_200));
+        assert_eq!(month_bounds(2023, 12).unwrap().1, 1_704_067_200);
+        assert!(month_bounds(2024, 13).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_verify() {
+        let wallet: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let offsets = vec![CarbonOffset {
+            registry: "Gold Standard".to_string(),
+            certificate_id: "GS-1".to_string(),
+            kg_co2: 20.0,
+            purchased_at: 0,
+        }];
+
+        let report = CarbonReport::new("node-1", month_bounds(2024, 2).unwrap(), &stats(), None, offsets);
+        assert_eq!(report.net_carbon_kg, 0.0);
+        assert!((report.avg_intensity_kg_per_kwh - 1.0 / 3.0).abs() < 1e-9);
+
+        let mut signed = report.sign(&wallet).await.unwrap();
+        signed.verify().unwrap();
+        assert!(signed.to_html().contains("Gold Standard"));
+
+        signed.report.energy_kwh = 1.0;
+        assert!(signed.verify().is_err());
+    }
+}
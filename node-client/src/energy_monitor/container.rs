@@ -0,0 +1,224 @@
+/*!
+ * Container-aware energy measurement
+ * Inside Docker/Kubernetes sysinfo reports host totals, so the cgroup v2 controllers
+ * (`cpu.stat`, `cpu.max`, `cpuset.cpus.effective`, `memory.current`) are used to scale
+ * the host power model down to the container's share
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
+
+/// Where the node runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeploymentMode {
+    /// Host-wide measurement
+    #[default]
+    Host,
+    /// Scale measurements to the enclosing cgroup
+    Container,
+    /// Container when a container runtime is detected
+    Auto,
+}
+
+impl DeploymentMode {
+    /// Resolve `Auto` against the environment
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto if running_in_container() => Self::Container,
+            Self::Auto => Self::Host,
+            mode => mode,
+        }
+    }
+}
+
+/// Docker, Podman or Kubernetes markers
+pub fn running_in_container() -> bool {
+    Path::new("/.dockerenv").exists()
+        || Path::new("/run/.containerenv").exists()
+        || std::env::var_os("KUBERNETES_SERVICE_HOST").is_some()
+}
+
+/// Resource limits of the cgroup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CgroupLimits {
+    /// `cpu.max` quota in cores
+    pub cpu_quota_cores: Option<f64>,
+    /// CPUs in `cpuset.cpus.effective`
+    pub cpuset_cpus: Option<usize>,
+    pub memory_limit_bytes: Option<u64>,
+}
+
+impl CgroupLimits {
+    /// Cores the container may use at most
+    pub fn allowed_cores(&self, host_cores: usize) -> f64 {
+        [self.cpu_quota_cores, self.cpuset_cpus.map(|cpus| cpus as f64)]
+            .into_iter()
+            .flatten()
+            .fold(host_cores as f64, f64::min)
+    }
+}
+
+/// Fractions of host power attributed to the container
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ContainerShare {
+    /// Share of the host's busy CPU time
+    pub cpu: f64,
+    /// Share of the host's used memory
+    pub memory: f64,
+    /// Share of host idle power, by reserved cores
+    pub baseline: f64,
+}
+
+impl ContainerShare {
+    pub fn compute(
+        limits: &CgroupLimits,
+        container_cores_used: Option<f64>,
+        host_cores: usize,
+        host_cpu_usage: f64,
+        container_memory_bytes: Option<u64>,
+        host_used_memory_bytes: u64,
+    ) -> Self {
+        let host_cores = host_cores.max(1);
+        let baseline = (limits.allowed_cores(host_cores) / host_cores as f64).clamp(0.0, 1.0);
+
+        let host_busy_cores = host_cpu_usage * host_cores as f64;
+        let cpu = match container_cores_used {
+            Some(used) if host_busy_cores > 0.0 => (used / host_busy_cores).clamp(0.0, 1.0),
+            Some(_) => 0.0,
+            None => baseline,
+        };
+
+        let memory = match container_memory_bytes {
+            Some(used) if host_used_memory_bytes > 0 => {
+                (used as f64 / host_used_memory_bytes as f64).clamp(0.0, 1.0)
+            }
+            _ => baseline,
+        };
+
+        Self { cpu, memory, baseline }
+    }
+}
+
+/// `cpu.max`: `"<quota> <period>"` or `"max <period>"`
+pub fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut fields = content.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next()?.parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Number of CPUs in a cpuset list such as `0-3,6`
+pub fn parse_cpuset(content: &str) -> Option<usize> {
+    let mut count = 0;
+    for range in content.trim().split(',').filter(|range| !range.is_empty()) {
+        count += match range.split_once('-') {
+            Some((start, end)) => end.parse::<usize>().ok()?.checked_sub(start.parse().ok()?)? + 1,
+            None => range.parse::<usize>().map(|_| 1).ok()?,
+        };
+    }
+    (count > 0).then_some(count)
+}
+
+/// `usage_usec` from `cpu.stat`
+pub fn parse_cpu_usage_usec(content: &str) -> Option<u64> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Unified (v2) hierarchy path from `/proc/self/cgroup`
+pub fn parse_proc_cgroup(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+/// Reads the node's own cgroup v2 controllers
+#[derive(Debug)]
+pub struct CgroupReader {
+    dir: PathBuf,
+    last_usage: Mutex<Option<(u64, Instant)>>,
+}
+
+impl CgroupReader {
+    /// Reader for the cgroup this process belongs to (cgroup v2 only)
+    pub fn detect() -> Option<Self> {
+        let mount = Path::new("/sys/fs/cgroup");
+        let relative = fs::read_to_string("/proc/self/cgroup").ok()?;
+        let dir = mount.join(parse_proc_cgroup(&relative)?.trim().trim_start_matches('/'));
+        Self::at(dir)
+    }
+
+    /// Reader for an explicit cgroup directory
+    pub fn at(dir: PathBuf) -> Option<Self> {
+        dir.join("cpu.stat").exists().then(|| Self {
+            dir,
+            last_usage: Mutex::new(None),
+        })
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        fs::read_to_string(self.dir.join(file)).ok()
+    }
+
+    pub fn limits(&self) -> CgroupLimits {
+        CgroupLimits {
+            cpu_quota_cores: self.read("cpu.max").and_then(|content| parse_cpu_max(&content)),
+            cpuset_cpus: self.read("cpuset.cpus.effective").and_then(|content| parse_cpuset(&content)),
+            memory_limit_bytes: self.read("memory.max").and_then(|content| content.trim().parse().ok()),
+        }
+    }
+
+    pub fn memory_current(&self) -> Option<u64> {
+        self.read("memory.current")?.trim().parse().ok()
+    }
+
+    /// Average cores used since the previous call (None on the first call)
+    pub fn sample_cores_used(&self) -> Option<f64> {
+        let usage = parse_cpu_usage_usec(&self.read("cpu.stat")?)?;
+        let now = Instant::now();
+        let previous = self.last_usage.lock().unwrap().replace((usage, now))?;
+
+        let elapsed = now.duration_since(previous.1).as_secs_f64();
+        (elapsed > 0.0).then(|| usage.saturating_sub(previous.0) as f64 / 1e6 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroup_files() {
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2.0));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_cpuset("0-3,6\n"), Some(5));
+        assert_eq!(parse_cpuset(""), None);
+        assert_eq!(parse_cpu_usage_usec("usage_usec 1500\nuser_usec 1000\n"), Some(1500));
+        assert_eq!(parse_proc_cgroup("0::/system.slice/docker-1.scope\n"), Some("/system.slice/docker-1.scope"));
+    }
+
+    #[test]
+    fn test_container_share() {
+        let limits = CgroupLimits {
+            cpu_quota_cores: Some(2.0),
+            cpuset_cpus: Some(4),
+            memory_limit_bytes: Some(1 << 30),
+        };
+        assert_eq!(limits.allowed_cores(8), 2.0);
+
+        // Container uses 1.5 of the 3 busy host cores and a quarter of used memory
+        let share = ContainerShare::compute(&limits, Some(1.5), 8, 3.0 / 8.0, Some(1 << 30), 4 << 30);
+        assert_eq!(share.baseline, 0.25);
+        assert!((share.cpu - 0.5).abs() < 1e-9);
+        assert_eq!(share.memory, 0.25);
+
+        // Before the first CPU delta the reserved share stands in
+        let share = ContainerShare::compute(&limits, None, 8, 0.5, None, 0);
+        assert_eq!(share.cpu, 0.25);
+    }
+}
@@ -0,0 +1,195 @@
+/*!
+ * Energy data export
+ * Writes readings as CSV, newline-delimited JSON or Parquet (behind the `parquet` feature)
+ * for operators' own analytics
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use super::EnergyData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line, with every field of the reading
+    NdJson,
+    Parquet,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" | "jsonl" | "ndjson" => Ok(Self::NdJson),
+            "parquet" => Ok(Self::Parquet),
+            other => Err(anyhow::anyhow!("Unknown export format: {}", other)),
+        }
+    }
+}
+
+/// Flat row used by the tabular formats
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub timestamp: u64,
+    pub total_watts: f64,
+    pub cpu_watts: f64,
+    pub gpu_watts: f64,
+    pub memory_watts: f64,
+    pub network_watts: f64,
+    pub npu_watts: f64,
+    pub battery_level: Option<f64>,
+    pub is_charging: Option<bool>,
+    pub efficiency_score: u8,
+    pub carbon_footprint_kg_per_hour: f64,
+    pub measurement_source: String,
+    pub node_watts: Option<f64>,
+    pub renewable_fraction: Option<f64>,
+}
+
+impl From<&EnergyData> for ExportRow {
+    fn from(data: &EnergyData) -> Self {
+        Self {
+            timestamp: data.timestamp,
+            total_watts: data.total_watts,
+            cpu_watts: data.cpu_watts,
+            gpu_watts: data.gpu_watts,
+            memory_watts: data.memory_watts,
+            network_watts: data.network_watts,
+            npu_watts: data.npu_watts,
+            battery_level: data.battery_level,
+            is_charging: data.is_charging,
+            efficiency_score: data.efficiency_score,
+            carbon_footprint_kg_per_hour: data.carbon_footprint_kg_per_hour,
+            measurement_source: format!("{:?}", data.measurement_source),
+            node_watts: data.node_process.as_ref().map(|process| process.total_watts),
+            renewable_fraction: data.source_mix.map(|mix| mix.renewable_fraction),
+        }
+    }
+}
+
+pub fn write_csv<W: Write>(readings: &[EnergyData], writer: W) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    for reading in readings {
+        csv.serialize(ExportRow::from(reading))?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+pub fn write_ndjson<W: Write>(readings: &[EnergyData], mut writer: W) -> Result<()> {
+    for reading in readings {
+        serde_json::to_writer(&mut writer, reading)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send>(readings: &[EnergyData], writer: W) -> Result<()> {
+    use arrow_array::{ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, UInt64Array, UInt8Array};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let rows: Vec<ExportRow> = readings.iter().map(ExportRow::from).collect();
+    let float = |field: fn(&ExportRow) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(rows.iter().map(field)))
+    };
+    let optional = |field: fn(&ExportRow) -> Option<f64>| -> ArrayRef {
+        Arc::new(rows.iter().map(field).collect::<Float64Array>())
+    };
+
+    let batch = RecordBatch::try_from_iter([
+        ("timestamp", Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.timestamp))) as ArrayRef),
+        ("total_watts", float(|row| row.total_watts)),
+        ("cpu_watts", float(|row| row.cpu_watts)),
+        ("gpu_watts", float(|row| row.gpu_watts)),
+        ("memory_watts", float(|row| row.memory_watts)),
+        ("network_watts", float(|row| row.network_watts)),
+        ("npu_watts", float(|row| row.npu_watts)),
+        ("battery_level", optional(|row| row.battery_level)),
+        ("is_charging", Arc::new(rows.iter().map(|row| row.is_charging).collect::<BooleanArray>())),
+        ("efficiency_score", Arc::new(UInt8Array::from_iter_values(rows.iter().map(|row| row.efficiency_score)))),
+        ("carbon_footprint_kg_per_hour", float(|row| row.carbon_footprint_kg_per_hour)),
+        (
+            "measurement_source",
+            Arc::new(rows.iter().map(|row| Some(row.measurement_source.as_str())).collect::<StringArray>()),
+        ),
+        ("node_watts", optional(|row| row.node_watts)),
+        ("renewable_fraction", optional(|row| row.renewable_fraction)),
+    ])?;
+
+    let mut parquet = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    parquet.write(&batch)?;
+    parquet.close()?;
+    Ok(())
+}
+
+/// Write `readings` to `path`; returns the number of rows
+pub fn export_readings(readings: &[EnergyData], format: ExportFormat, path: &Path) -> Result<usize> {
+    let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let writer = BufWriter::new(file);
+
+    match format {
+        ExportFormat::Csv => write_csv(readings, writer)?,
+        ExportFormat::NdJson => write_ndjson(readings, writer)?,
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => write_parquet(readings, writer)?,
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => return Err(anyhow::anyhow!("Built without the `parquet` feature")),
+    }
+    Ok(readings.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, total_watts: f64) -> EnergyData {
+        EnergyData {
+            total_watts,
+            cpu_watts: total_watts,
+            battery_level: Some(80.0),
+            is_charging: Some(true),
+            efficiency_score: 90,
+            carbon_footprint_kg_per_hour: 0.01,
+            timestamp,
+            ..EnergyData::default()
+        }
+    }
+
+    #[test]
+    fn test_csv_and_ndjson_export() {
+        let readings = vec![reading(60, 42.5), reading(120, 50.0)];
+
+        let mut csv = Vec::new();
+        write_csv(&readings, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("timestamp,total_watts,cpu_watts"));
+        assert!(lines[1].starts_with("60,42.5,42.5"));
+        assert!(lines[1].contains(",Model,"));
+
+        let mut ndjson = Vec::new();
+        write_ndjson(&readings, &mut ndjson).unwrap();
+        let parsed: Vec<EnergyData> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].total_watts, 50.0);
+
+        assert_eq!("jsonl".parse::<ExportFormat>().unwrap(), ExportFormat::NdJson);
+    }
+}
@@ -0,0 +1,192 @@
+/*!
+ * Energy consumption forecasting
+ * Holt-Winters (daily season) over hourly power, falling back to Holt or a plain EWMA when
+ * there is too little history; predicts next-24h energy, carbon and battery runway
+ */
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::calibration::fit_linear;
+use super::rollup::Rollup;
+use super::EnergyData;
+
+/// Hours in the forecast horizon and the seasonal period
+pub const HORIZON_HOURS: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForecastMethod {
+    Ewma,
+    /// Level and trend
+    Holt,
+    /// Level, trend and daily season
+    HoltWinters,
+}
+
+/// Smoothing factors
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Smoothing {
+    pub alpha: f64,
+    pub beta: f64,
+    pub gamma: f64,
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Self {
+            alpha: 0.3,
+            beta: 0.05,
+            gamma: 0.2,
+        }
+    }
+}
+
+/// Forecast the next `horizon` values of `series`
+pub fn forecast_series(series: &[f64], horizon: usize, smoothing: Smoothing) -> Option<(Vec<f64>, ForecastMethod)> {
+    let season = HORIZON_HOURS;
+    let Smoothing { alpha, beta, gamma } = smoothing;
+
+    if series.len() >= 2 * season {
+        // Additive Holt-Winters initialized from the first two seasons
+        let first: f64 = series[..season].iter().sum::<f64>() / season as f64;
+        let second: f64 = series[season..2 * season].iter().sum::<f64>() / season as f64;
+        let mut level = first;
+        let mut trend = (second - first) / season as f64;
+        let mut seasonal: Vec<f64> = series[..season].iter().map(|value| value - first).collect();
+
+        for (t, &value) in series.iter().enumerate().skip(season) {
+            let s = seasonal[t % season];
+            let previous_level = level;
+            level = alpha * (value - s) + (1.0 - alpha) * (level + trend);
+            trend = beta * (level - previous_level) + (1.0 - beta) * trend;
+            seasonal[t % season] = gamma * (value - level) + (1.0 - gamma) * s;
+        }
+
+        let n = series.len();
+        let values = (1..=horizon)
+            .map(|h| (level + h as f64 * trend + seasonal[(n + h - 1) % season]).max(0.0))
+            .collect();
+        return Some((values, ForecastMethod::HoltWinters));
+    }
+
+    if series.len() >= 4 {
+        let mut level = series[0];
+        let mut trend = series[1] - series[0];
+        for &value in &series[1..] {
+            let previous_level = level;
+            level = alpha * value + (1.0 - alpha) * (level + trend);
+            trend = beta * (level - previous_level) + (1.0 - beta) * trend;
+        }
+        let values = (1..=horizon).map(|h| (level + h as f64 * trend).max(0.0)).collect();
+        return Some((values, ForecastMethod::Holt));
+    }
+
+    let mut level = *series.first()?;
+    for &value in &series[1..] {
+        level = alpha * value + (1.0 - alpha) * level;
+    }
+    Some((vec![level; horizon], ForecastMethod::Ewma))
+}
+
+/// Hourly average power, gaps filled with the previous hour
+pub fn hourly_watts(hours: &[Rollup]) -> Vec<f64> {
+    let mut series = Vec::new();
+    let mut previous: Option<&Rollup> = None;
+    for hour in hours {
+        if let Some(previous) = previous {
+            let missing = (hour.start.saturating_sub(previous.start) / 3600).saturating_sub(1);
+            series.extend(std::iter::repeat_n(previous.avg_watts, missing as usize));
+        }
+        series.push(hour.avg_watts);
+        previous = Some(hour);
+    }
+    series
+}
+
+/// Time until the battery empties at the recent discharge rate
+pub fn battery_runway(readings: &[EnergyData]) -> Option<Duration> {
+    let latest = readings.last()?;
+    if latest.is_charging != Some(false) {
+        return None;
+    }
+    // Reported by the OS/UPS when available
+    if let Some(remaining) = latest.battery_time_remaining {
+        return Some(remaining);
+    }
+
+    let discharging: Vec<(f64, f64)> = readings
+        .iter()
+        .rev()
+        .take_while(|reading| reading.is_charging == Some(false))
+        .filter_map(|reading| Some((reading.timestamp as f64 / 3600.0, reading.battery_level?)))
+        .collect();
+    let fit = fit_linear(&discharging)?;
+    let percent_per_hour = -fit.slope;
+    let level = latest.battery_level?;
+
+    (percent_per_hour > 0.0).then(|| Duration::from_secs_f64(level / percent_per_hour * 3600.0))
+}
+
+/// Next-24h prediction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyForecast {
+    pub generated_at: u64,
+    pub method: ForecastMethod,
+    /// Average watts per hour, starting with the next hour
+    pub hourly_watts: Vec<f64>,
+    pub next_24h_kwh: f64,
+    pub expected_carbon_kg: f64,
+    pub battery_runway: Option<Duration>,
+}
+
+impl EnergyForecast {
+    /// Combine power predictions with per-hour carbon intensity (kg CO2 per kWh)
+    pub fn new(
+        generated_at: u64,
+        (hourly_watts, method): (Vec<f64>, ForecastMethod),
+        hourly_intensity: &[f64],
+        battery_runway: Option<Duration>,
+    ) -> Self {
+        let next_24h_kwh = hourly_watts.iter().sum::<f64>() / 1000.0;
+        let expected_carbon_kg = hourly_watts
+            .iter()
+            .enumerate()
+            .map(|(hour, watts)| {
+                let intensity = hourly_intensity.get(hour).or(hourly_intensity.last()).copied().unwrap_or(0.0);
+                watts / 1000.0 * intensity
+            })
+            .sum();
+
+        Self {
+            generated_at,
+            method,
+            hourly_watts,
+            next_24h_kwh,
+            expected_carbon_kg,
+            battery_runway,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holt_winters_tracks_daily_cycle() {
+        // 60 W at night, 120 W during the day, for three days
+        let day: Vec<f64> = (0..24).map(|hour| if (8..20).contains(&hour) { 120.0 } else { 60.0 }).collect();
+        let series: Vec<f64> = day.iter().cycle().take(72).copied().collect();
+
+        let (values, method) = forecast_series(&series, HORIZON_HOURS, Smoothing::default()).unwrap();
+        assert_eq!(method, ForecastMethod::HoltWinters);
+        assert!((values[3] - 60.0).abs() < 5.0);
+        assert!((values[12] - 120.0).abs() < 5.0);
+
+        let forecast = EnergyForecast::new(0, (values, method), &[0.5], None);
+        assert!((forecast.next_24h_kwh - 2.16).abs() < 0.1);
+        assert!((forecast.expected_carbon_kg - forecast.next_24h_kwh * 0.5).abs() < 1e-9);
+
+        assert_eq!(forecast_series(&[80.0], 2, Smoothing::default()), Some((vec![80.0, 80.0], ForecastMethod::Ewma)));
+    }
+}
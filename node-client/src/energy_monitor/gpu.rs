@@ -0,0 +1,86 @@
+/*!
+ * GPU power backends
+ * Collects per-GPU power, utilization, temperature and memory from vendor interfaces
+ */
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "nvml")]
+use tracing::debug;
+use tracing::info;
+
+use super::amdgpu::AmdGpuBackend;
+#[cfg(feature = "nvml")]
+use super::nvml::NvmlBackend;
+
+/// Measured state of one GPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuReading {
+    pub index: u32,
+    pub vendor: String,
+    pub name: String,
+    pub power_watts: f64,
+    pub utilization_percent: Option<f64>,
+    pub temperature_c: Option<f64>,
+    pub fan_rpm: Option<u32>,
+    pub memory_used_mb: Option<u64>,
+    pub memory_total_mb: Option<u64>,
+}
+
+/// All GPU backends available on this machine
+#[derive(Default)]
+pub struct GpuMonitor {
+    #[cfg(feature = "nvml")]
+    nvml: Option<NvmlBackend>,
+    amdgpu: Option<AmdGpuBackend>,
+}
+
+impl GpuMonitor {
+    /// Probe every compiled-in backend
+    pub fn detect() -> Self {
+        #[cfg(feature = "nvml")]
+        let nvml = NvmlBackend::init();
+        #[cfg(feature = "nvml")]
+        if let Some(nvml) = &nvml {
+            info!("✅ NVML GPU monitoring enabled ({} GPUs)", nvml.device_count());
+        }
+
+        let amdgpu = AmdGpuBackend::detect();
+        if let Some(amdgpu) = &amdgpu {
+            info!("✅ amdgpu hwmon monitoring enabled ({} GPUs)", amdgpu.device_count());
+        }
+
+        Self {
+            #[cfg(feature = "nvml")]
+            nvml,
+            amdgpu,
+        }
+    }
+
+    /// Whether any backend reports real measurements
+    pub fn has_backends(&self) -> bool {
+        #[cfg(feature = "nvml")]
+        if self.nvml.is_some() {
+            return true;
+        }
+        self.amdgpu.is_some()
+    }
+
+    /// Read all GPUs from all backends
+    pub fn read_all(&self) -> Vec<GpuReading> {
+        let mut readings = Vec::new();
+
+        #[cfg(feature = "nvml")]
+        if let Some(nvml) = &self.nvml {
+            match nvml.read() {
+                Ok(gpus) => readings.extend(gpus),
+                Err(e) => debug!("NVML read failed: {}", e),
+            }
+        }
+
+        if let Some(amdgpu) = &self.amdgpu {
+            readings.extend(amdgpu.read());
+        }
+
+        readings
+    }
+}
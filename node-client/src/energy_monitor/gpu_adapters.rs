@@ -0,0 +1,148 @@
+/*!
+ * GPU enumeration for hardware specs
+ * Lists physical GPUs through wgpu (Vulkan / Metal / DX12 adapters) so the power model knows
+ * the model, vendor and whether the GPU is integrated, even without a vendor power backend
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GpuVendor {
+    Nvidia,
+    Amd,
+    Intel,
+    Apple,
+    Qualcomm,
+    Arm,
+    Other,
+}
+
+impl GpuVendor {
+    /// Vendor from a PCI vendor ID
+    pub fn from_pci_id(id: u32) -> Self {
+        match id {
+            0x10DE => Self::Nvidia,
+            0x1002 | 0x1022 => Self::Amd,
+            0x8086 => Self::Intel,
+            0x106B => Self::Apple,
+            0x5143 => Self::Qualcomm,
+            0x13B5 => Self::Arm,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// One physical GPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuAdapter {
+    pub name: String,
+    pub vendor: GpuVendor,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub integrated: bool,
+    /// Filled from vendor backends (NVML/amdgpu) when they report it
+    pub vram_mb: Option<u64>,
+}
+
+/// Idle and loaded power of a GPU class
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GpuPowerProfile {
+    pub idle_watts: f64,
+    pub active_watts: f64,
+}
+
+/// Default vendor power table keyed by (vendor, integrated)
+pub fn default_power_table() -> Vec<((GpuVendor, bool), GpuPowerProfile)> {
+    let profile = |idle_watts, active_watts| GpuPowerProfile { idle_watts, active_watts };
+    vec![
+        ((GpuVendor::Nvidia, false), profile(15.0, 180.0)),
+        ((GpuVendor::Amd, false), profile(12.0, 160.0)),
+        ((GpuVendor::Amd, true), profile(3.0, 25.0)),
+        ((GpuVendor::Intel, false), profile(20.0, 150.0)),
+        ((GpuVendor::Intel, true), profile(2.0, 15.0)),
+        ((GpuVendor::Apple, true), profile(1.0, 20.0)),
+        ((GpuVendor::Qualcomm, true), profile(0.5, 5.0)),
+        ((GpuVendor::Arm, true), profile(0.5, 5.0)),
+    ]
+}
+
+/// Drop duplicates (one adapter per backend) and software rasterizers
+pub fn dedupe_adapters(adapters: Vec<GpuAdapter>) -> Vec<GpuAdapter> {
+    let mut unique: Vec<GpuAdapter> = Vec::new();
+    for adapter in adapters {
+        let seen = unique.iter().any(|existing| {
+            (existing.vendor_id, existing.device_id, &existing.name)
+                == (adapter.vendor_id, adapter.device_id, &adapter.name)
+        });
+        if !seen {
+            unique.push(adapter);
+        }
+    }
+    unique
+}
+
+/// Enumerate GPUs via wgpu (requires the `wgpu` feature)
+#[cfg(feature = "wgpu")]
+pub fn enumerate_adapters() -> Vec<GpuAdapter> {
+    let backends = wgpu::Backends::VULKAN | wgpu::Backends::METAL | wgpu::Backends::DX12;
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+
+    let adapters = instance
+        .enumerate_adapters(backends)
+        .into_iter()
+        .map(|adapter| adapter.get_info())
+        .filter(|info| {
+            matches!(
+                info.device_type,
+                wgpu::DeviceType::DiscreteGpu | wgpu::DeviceType::IntegratedGpu
+            )
+        })
+        .map(|info| GpuAdapter {
+            vendor: GpuVendor::from_pci_id(info.vendor),
+            vendor_id: info.vendor,
+            device_id: info.device,
+            integrated: info.device_type == wgpu::DeviceType::IntegratedGpu,
+            name: info.name,
+            vram_mb: None,
+        })
+        .collect();
+
+    dedupe_adapters(adapters)
+}
+
+#[cfg(not(feature = "wgpu"))]
+pub fn enumerate_adapters() -> Vec<GpuAdapter> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, vendor_id: u32, device_id: u32) -> GpuAdapter {
+        GpuAdapter {
+            name: name.to_string(),
+            vendor: GpuVendor::from_pci_id(vendor_id),
+            vendor_id,
+            device_id,
+            integrated: false,
+            vram_mb: None,
+        }
+    }
+
+    #[test]
+    fn test_dedupe_and_vendor() {
+        let adapters = dedupe_adapters(vec![
+            adapter("NVIDIA GeForce RTX 4070", 0x10DE, 0x2786),
+            adapter("NVIDIA GeForce RTX 4070", 0x10DE, 0x2786),
+            adapter("Intel(R) UHD Graphics 770", 0x8086, 0x4680),
+        ]);
+
+        assert_eq!(adapters.len(), 2);
+        assert_eq!(adapters[0].vendor, GpuVendor::Nvidia);
+        assert_eq!(adapters[1].vendor, GpuVendor::Intel);
+    }
+}
@@ -0,0 +1,309 @@
+/*!
+ * Storage and memory type detection
+ * Identifies NVMe / SATA SSD / HDD drives (sysfs, IOKit via system_profiler, WMI) and the
+ * DRAM generation (SMBIOS via dmidecode or sysfs) so the power model uses the right coefficients
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path::Path};
+
+/// Kind of storage device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageKind {
+    Nvme,
+    SataSsd,
+    Hdd,
+}
+
+impl fmt::Display for StorageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Nvme => "NVMe",
+            Self::SataSsd => "SSD",
+            Self::Hdd => "HDD",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageDevice {
+    pub name: String,
+    pub kind: StorageKind,
+}
+
+/// DRAM generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemoryGeneration {
+    Ddr3,
+    Ddr4,
+    Ddr5,
+    Lpddr4,
+    Lpddr5,
+}
+
+impl fmt::Display for MemoryGeneration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ddr3 => "DDR3",
+            Self::Ddr4 => "DDR4",
+            Self::Ddr5 => "DDR5",
+            Self::Lpddr4 => "LPDDR4",
+            Self::Lpddr5 => "LPDDR5",
+        })
+    }
+}
+
+impl MemoryGeneration {
+    /// Parse a type string such as `DDR4` or `LPDDR4X`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_uppercase();
+        [
+            ("LPDDR5", Self::Lpddr5),
+            ("LPDDR4", Self::Lpddr4),
+            ("DDR5", Self::Ddr5),
+            ("DDR4", Self::Ddr4),
+            ("DDR3", Self::Ddr3),
+        ]
+        .into_iter()
+        .find_map(|(prefix, generation)| value.starts_with(prefix).then_some(generation))
+    }
+
+    /// SMBIOS type 17 "Memory Type" code
+    pub fn from_smbios(code: u8) -> Option<Self> {
+        match code {
+            0x18 => Some(Self::Ddr3),
+            0x1A => Some(Self::Ddr4),
+            0x1E => Some(Self::Lpddr4),
+            0x22 => Some(Self::Ddr5),
+            0x23 => Some(Self::Lpddr5),
+            _ => None,
+        }
+    }
+
+    /// Power per GB relative to DDR4
+    pub fn power_factor(&self) -> f64 {
+        match self {
+            Self::Ddr3 => 1.25,
+            Self::Ddr4 => 1.0,
+            Self::Ddr5 => 0.85,
+            Self::Lpddr4 => 0.5,
+            Self::Lpddr5 => 0.4,
+        }
+    }
+}
+
+/// Block devices under a sysfs `block` directory
+pub fn detect_storage_in(sys_block: &Path) -> Vec<StorageDevice> {
+    const VIRTUAL_PREFIXES: [&str; 7] = ["loop", "ram", "zram", "dm-", "md", "sr", "nbd"];
+
+    let Ok(entries) = fs::read_dir(sys_block) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<StorageDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if VIRTUAL_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+                return None;
+            }
+
+            let kind = if name.starts_with("nvme") {
+                StorageKind::Nvme
+            } else {
+                let rotational = fs::read_to_string(entry.path().join("queue/rotational")).ok()?;
+                if rotational.trim() == "1" {
+                    StorageKind::Hdd
+                } else {
+                    StorageKind::SataSsd
+                }
+            };
+            Some(StorageDevice { name, kind })
+        })
+        .collect();
+
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+/// Drives from `system_profiler SPStorageDataType` (one block per volume, deduplicated by device)
+pub fn parse_system_profiler_storage(output: &str) -> Vec<StorageDevice> {
+    let mut devices: Vec<StorageDevice> = Vec::new();
+
+    for block in output.split("Physical Drive:").skip(1) {
+        let field = |key: &str| {
+            block
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(key))
+                .map(|value| value.trim().to_string())
+        };
+        let name = field("Device Name:").unwrap_or_default();
+        let kind = match (field("Medium Type:").as_deref(), field("Protocol:").as_deref()) {
+            (Some("HDD") | Some("Rotational"), _) => StorageKind::Hdd,
+            (_, Some("PCI-Express") | Some("Apple Fabric") | Some("NVMExpress")) => StorageKind::Nvme,
+            (Some("SSD"), _) => StorageKind::SataSsd,
+            _ => continue,
+        };
+
+        if !devices.iter().any(|device| device.name == name) {
+            devices.push(StorageDevice { name, kind });
+        }
+    }
+    devices
+}
+
+/// First known memory type in `dmidecode -t memory` or `system_profiler SPMemoryDataType`
+pub fn parse_memory_type(output: &str) -> Option<MemoryGeneration> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Type:"))
+        .find_map(MemoryGeneration::parse)
+}
+
+#[cfg(not(windows))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// SMBIOS memory device entries exposed by the kernel (usually root-only)
+#[cfg(not(any(windows, target_os = "macos")))]
+fn memory_type_from_sysfs_dmi() -> Option<MemoryGeneration> {
+    fs::read_dir("/sys/firmware/dmi/entries")
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("17-"))
+        .filter_map(|entry| fs::read(entry.path().join("raw")).ok())
+        .find_map(|raw| raw.get(0x12).copied().and_then(MemoryGeneration::from_smbios))
+}
+
+#[cfg(windows)]
+mod windows {
+    use serde::Deserialize;
+    use wmi::{COMLibrary, WMIConnection};
+
+    use super::{MemoryGeneration, StorageDevice, StorageKind};
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct PhysicalDisk {
+        friendly_name: String,
+        media_type: u16,
+        bus_type: u16,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct PhysicalMemory {
+        #[serde(rename = "SMBIOSMemoryType")]
+        smbios_memory_type: u32,
+    }
+
+    pub fn storage() -> Option<Vec<StorageDevice>> {
+        let com = COMLibrary::new().ok()?;
+        let wmi = WMIConnection::with_namespace_path("ROOT\\Microsoft\\Windows\\Storage", com).ok()?;
+        let disks: Vec<PhysicalDisk> = wmi
+            .raw_query("SELECT FriendlyName, MediaType, BusType FROM MSFT_PhysicalDisk")
+            .ok()?;
+
+        Some(
+            disks
+                .into_iter()
+                .filter_map(|disk| {
+                    // BusType 17 = NVMe; MediaType 3 = HDD, 4 = SSD
+                    let kind = match (disk.bus_type, disk.media_type) {
+                        (17, _) => StorageKind::Nvme,
+                        (_, 3) => StorageKind::Hdd,
+                        (_, 4) => StorageKind::SataSsd,
+                        _ => return None,
+                    };
+                    Some(StorageDevice { name: disk.friendly_name, kind })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn memory_type() -> Option<MemoryGeneration> {
+        let com = COMLibrary::new().ok()?;
+        let wmi = WMIConnection::new(com).ok()?;
+        let modules: Vec<PhysicalMemory> = wmi
+            .raw_query("SELECT SMBIOSMemoryType FROM Win32_PhysicalMemory")
+            .ok()?;
+
+        modules
+            .into_iter()
+            .find_map(|module| MemoryGeneration::from_smbios(module.smbios_memory_type as u8))
+    }
+}
+
+/// Storage devices on this machine
+pub fn detect_storage() -> Vec<StorageDevice> {
+    #[cfg(windows)]
+    {
+        windows::storage().unwrap_or_default()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        command_output("system_profiler", &["SPStorageDataType"])
+            .map(|output| parse_system_profiler_storage(&output))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        detect_storage_in(Path::new("/sys/block"))
+    }
+}
+
+/// DRAM generation of the installed memory
+pub fn detect_memory_type() -> Option<MemoryGeneration> {
+    #[cfg(windows)]
+    {
+        windows::memory_type()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        command_output("system_profiler", &["SPMemoryDataType"]).and_then(|output| parse_memory_type(&output))
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        command_output("dmidecode", &["-t", "memory"])
+            .and_then(|output| parse_memory_type(&output))
+            .or_else(memory_type_from_sysfs_dmi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_storage_from_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, rotational) in [("nvme0n1", "0"), ("sda", "0"), ("sdb", "1"), ("loop0", "0")] {
+            let queue = dir.path().join(name).join("queue");
+            fs::create_dir_all(&queue).unwrap();
+            fs::write(queue.join("rotational"), rotational).unwrap();
+        }
+
+        let kinds: Vec<StorageKind> = detect_storage_in(dir.path()).iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![StorageKind::Nvme, StorageKind::SataSsd, StorageKind::Hdd]);
+    }
+
+    #[test]
+    fn test_parse_memory_and_mac_storage() {
+        let dmidecode = "Memory Device\n\tError Correction Type: None\n\tType: Unknown\n\tType: DDR5\n";
+        assert_eq!(parse_memory_type(dmidecode), Some(MemoryGeneration::Ddr5));
+        assert_eq!(MemoryGeneration::parse("LPDDR4X"), Some(MemoryGeneration::Lpddr4));
+
+        let profiler = "Macintosh HD:\n  Physical Drive:\n    Device Name: APPLE SSD AP0512Q\n    \
+                        Medium Type: SSD\n    Protocol: Apple Fabric\nData:\n  Physical Drive:\n    \
+                        Device Name: APPLE SSD AP0512Q\n    Medium Type: SSD\n    Protocol: Apple Fabric\n";
+        let drives = parse_system_profiler_storage(profiler);
+        assert_eq!(drives.len(), 1);
+        assert_eq!(drives[0].kind, StorageKind::Nvme);
+    }
+}
@@ -0,0 +1,133 @@
+/*!
+ * Persistent energy history
+ * Readings are stored in an embedded sled tree keyed by timestamp so history survives
+ * restarts and statistics can be computed over arbitrary time ranges
+ */
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use tracing::debug;
+
+use super::EnergyData;
+
+/// sled-backed store of energy readings
+pub struct EnergyHistoryStore {
+    db: sled::Db,
+    tree: sled::Tree,
+    retention_secs: u64,
+}
+
+/// Key ordering = time ordering; the sequence suffix keeps same-second readings apart
+fn reading_key(timestamp: u64, sequence: u64) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    key[..8].copy_from_slice(&timestamp.to_be_bytes());
+    key[8..].copy_from_slice(&sequence.to_be_bytes());
+    key
+}
+
+impl EnergyHistoryStore {
+    /// Open (or create) the store at `path`
+    pub fn open<P: AsRef<Path>>(path: P, retention_secs: u64) -> Result<Self> {
+        let db = sled::open(path.as_ref())
+            .with_context(|| format!("Failed to open energy history at {}", path.as_ref().display()))?;
+        let tree = db.open_tree("energy_history")?;
+
+        Ok(Self {
+            db,
+            tree,
+            retention_secs,
+        })
+    }
+
+    /// Persist a reading
+    pub fn insert(&self, data: &EnergyData) -> Result<()> {
+        let key = reading_key(data.timestamp, self.db.generate_id()?);
+        self.tree.insert(key, serde_json::to_vec(data)?)?;
+        Ok(())
+    }
+
+    /// Readings with `from <= timestamp < to`, oldest first
+    pub fn range(&self, from: u64, to: u64) -> Result<Vec<EnergyData>> {
+        self.tree
+            .range(reading_key(from, 0)..reading_key(to, 0))
+            .values()
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect()
+    }
+
+    /// Most recent `limit` readings, oldest first
+    pub fn latest(&self, limit: usize) -> Result<Vec<EnergyData>> {
+        let mut readings = self.tree
+            .iter()
+            .rev()
+            .values()
+            .take(limit)
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect::<Result<Vec<EnergyData>>>()?;
+        readings.reverse();
+        Ok(readings)
+    }
+
+    /// Delete readings older than the retention window; returns how many were removed
+    pub fn prune(&self, now: u64) -> Result<usize> {
+        let cutoff = reading_key(now.saturating_sub(self.retention_secs), 0);
+        let mut removed = 0;
+
+        for key in self.tree.range(..cutoff).keys() {
+            self.tree.remove(key?)?;
+            removed += 1;
+        }
+
+        if removed > 0 {
+            debug!("🗑️ Pruned {} energy readings past retention", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Number of stored readings
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Flush pending writes to disk
+    pub fn flush(&self) -> Result<()> {
+        self.tree.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, watts: f64) -> EnergyData {
+        EnergyData {
+            total_watts: watts,
+            efficiency_score: 50,
+            timestamp,
+            ..EnergyData::default()
+        }
+    }
+
+    #[test]
+    fn test_range_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EnergyHistoryStore::open(dir.path(), 3600).unwrap();
+
+        for (ts, watts) in [(1_000, 10.0), (1_000, 11.0), (2_000, 20.0), (5_000, 50.0)] {
+            store.insert(&reading(ts, watts)).unwrap();
+        }
+
+        let range = store.range(1_000, 2_001).unwrap();
+        assert_eq!(range.len(), 3);
+        assert_eq!(range[2].total_watts, 20.0);
+        assert_eq!(store.latest(1).unwrap()[0].timestamp, 5_000);
+
+        assert_eq!(store.prune(5_000).unwrap(), 3);
+        assert_eq!(store.len(), 1);
+    }
+}
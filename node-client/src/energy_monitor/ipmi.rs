@@ -0,0 +1,156 @@
+/*!
+ * Server PSU power via IPMI
+ * Reads per-PSU input power sensors from the BMC with `ipmitool`; the sum is the node's wall
+ * draw. The SDR scan takes a few seconds, so readings are cached between polls
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::process::Command;
+
+/// Kernel IPMI device nodes
+pub const IPMI_DEVICES: [&str; 3] = ["/dev/ipmi0", "/dev/ipmi/0", "/dev/ipmidev/0"];
+
+/// One power supply's input power
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PsuReading {
+    pub name: String,
+    pub input_watts: f64,
+}
+
+/// Power sensors from one SDR scan
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IpmiPowerSample {
+    pub psus: Vec<PsuReading>,
+    /// Whole-system sensor (e.g. Dell "Pwr Consumption"), when the BMC has one
+    pub system_watts: Option<f64>,
+}
+
+impl IpmiPowerSample {
+    /// Wall draw: the PSU inputs, or the system sensor when no PSU reports power
+    pub fn total_watts(&self) -> Option<f64> {
+        if self.psus.is_empty() {
+            self.system_watts
+        } else {
+            Some(self.psus.iter().map(|psu| psu.input_watts).sum())
+        }
+    }
+}
+
+fn is_psu_sensor(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    upper.contains("PSU")
+        || upper.contains("POWER SUPPLY")
+        || upper.strip_prefix("PS").is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit() || c == ' ' || c == '_'))
+}
+
+/// Parse `ipmitool sdr elist full` (`name | id | status | entity | reading`)
+pub fn parse_sdr(output: &str) -> IpmiPowerSample {
+    let mut sample = IpmiPowerSample::default();
+
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let (Some(name), Some(status), Some(reading)) = (fields.first(), fields.get(2), fields.last()) else {
+            continue;
+        };
+        let Some(watts) = reading.strip_suffix("Watts").and_then(|value| value.trim().parse::<f64>().ok()) else {
+            continue;
+        };
+        if *status == "ns" {
+            continue;
+        }
+
+        let upper = name.to_ascii_uppercase();
+        if is_psu_sensor(name) {
+            // Output sensors would double count the same supply
+            if !upper.contains("OUT") {
+                sample.psus.push(PsuReading {
+                    name: name.to_string(),
+                    input_watts: watts,
+                });
+            }
+        } else if ["PWR CONSUMPTION", "TOTAL POWER", "SYSTEM POWER", "SYS POWER"].iter().any(|label| upper.contains(label)) {
+            sample.system_watts.get_or_insert(watts);
+        }
+    }
+
+    sample
+}
+
+/// `ipmitool` backend with a poll-interval cache
+pub struct IpmiReader {
+    poll_interval: Duration,
+    cache: Mutex<Option<(Instant, IpmiPowerSample)>>,
+}
+
+impl IpmiReader {
+    /// Available on Linux when the kernel IPMI driver is loaded
+    pub fn detect() -> Option<Self> {
+        if !cfg!(target_os = "linux") || !IPMI_DEVICES.iter().any(|device| Path::new(device).exists()) {
+            return None;
+        }
+
+        Some(Self {
+            poll_interval: Duration::from_secs(30),
+            cache: Mutex::new(None),
+        })
+    }
+
+    /// Latest PSU readings, scanning the SDR when the cache is stale
+    pub async fn sample(&self) -> Result<IpmiPowerSample> {
+        if let Some((read_at, sample)) = self.cache.lock().unwrap().as_ref() {
+            if read_at.elapsed() < self.poll_interval {
+                return Ok(sample.clone());
+            }
+        }
+
+        let output = tokio::time::timeout(
+            Duration::from_secs(15),
+            Command::new("ipmitool").args(["sdr", "elist", "full"]).output(),
+        )
+        .await
+        .context("ipmitool timed out")?
+        .context("Failed to run ipmitool")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "ipmitool failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let sample = parse_sdr(&String::from_utf8_lossy(&output.stdout));
+        if sample.total_watts().is_none() {
+            return Err(anyhow::anyhow!("No power sensors in the BMC's SDR"));
+        }
+        *self.cache.lock().unwrap() = Some((Instant::now(), sample.clone()));
+        Ok(sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sdr_psus() {
+        let output = "\
+Inlet Temp       | 04h | ok  |  7.1 | 22 degrees C
+PS1 Input Power  | 64h | ok  | 10.1 | 182 Watts
+PS1 Output Power | 65h | ok  | 10.1 | 164 Watts
+PS2 Input Power  | 66h | ok  | 10.2 | 176 Watts
+PS3 Input Power  | 67h | ns  | 10.3 | 0 Watts
+Pwr Consumption  | 77h | ok  |  7.1 | 350 Watts
+";
+        let sample = parse_sdr(output);
+        assert_eq!(sample.psus.len(), 2);
+        assert_eq!(sample.psus[1].name, "PS2 Input Power");
+        assert_eq!(sample.system_watts, Some(350.0));
+        assert_eq!(sample.total_watts(), Some(358.0));
+    }
+}
@@ -0,0 +1,218 @@
+/*!
+ * Mobile battery backends
+ * Android reads the sticky BATTERY_CHANGED intent and BatteryManager over JNI; on iOS the host
+ * app implements `IosBatterySource` in Swift (IOKit power sources) and hands it over with
+ * `register_ios_battery_source` through the UniFFI bindings (`uniffi` feature)
+ */
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+
+use super::batteries::BatteryReading;
+
+/// Battery state reported by the mobile OS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct MobileBatteryReading {
+    /// Percent (0-100)
+    pub level: f64,
+    pub is_charging: Option<bool>,
+    pub temperature_c: Option<f64>,
+    pub voltage_v: Option<f64>,
+    /// Battery current magnitude, when the platform exposes it
+    pub current_a: Option<f64>,
+}
+
+impl MobileBatteryReading {
+    pub fn to_battery_reading(self) -> BatteryReading {
+        BatteryReading {
+            id: "mobile".to_string(),
+            level: self.level,
+            // Capacity is not exposed; the aggregate falls back to the level
+            energy_wh: 0.0,
+            full_wh: 0.0,
+            rate_watts: self.power_watts().unwrap_or_default(),
+            is_charging: self.is_charging,
+            time_remaining: None,
+            temperature_c: self.temperature_c,
+        }
+    }
+
+    /// Charge or discharge power from voltage and current
+    pub fn power_watts(&self) -> Option<f64> {
+        Some(self.voltage_v? * self.current_a?)
+    }
+}
+
+/// Platform battery source for phones and tablets
+pub trait MobileBatteryProvider: Send + Sync {
+    fn read(&self) -> Result<MobileBatteryReading>;
+}
+
+/// Android `BatteryManager.BATTERY_STATUS_*` to a charging flag (true while on external power)
+pub fn android_charging_status(status: i32) -> Option<bool> {
+    match status {
+        2 | 5 => Some(true),  // CHARGING, FULL (still plugged in)
+        3 | 4 => Some(false), // DISCHARGING, NOT_CHARGING
+        _ => None,            // UNKNOWN
+    }
+}
+
+/// Provider registered by the host app before the node starts
+static REGISTERED: OnceLock<Arc<dyn MobileBatteryProvider>> = OnceLock::new();
+
+/// Make `provider` the battery source of monitors built afterwards; false if one is already set
+pub fn register_provider(provider: Arc<dyn MobileBatteryProvider>) -> bool {
+    REGISTERED.set(provider).is_ok()
+}
+
+pub fn registered_provider() -> Option<Arc<dyn MobileBatteryProvider>> {
+    REGISTERED.get().cloned()
+}
+
+/// Battery source the iOS app implements in Swift over `IOPSCopyPowerSourcesInfo` and
+/// `UIDevice.batteryState`; `None` when the reading is unavailable
+#[cfg(feature = "uniffi")]
+#[uniffi::export(with_foreign)]
+pub trait IosBatterySource: Send + Sync {
+    fn read(&self) -> Option<MobileBatteryReading>;
+}
+
+#[cfg(feature = "uniffi")]
+struct IosBatteryProvider(Arc<dyn IosBatterySource>);
+
+#[cfg(feature = "uniffi")]
+impl MobileBatteryProvider for IosBatteryProvider {
+    fn read(&self) -> Result<MobileBatteryReading> {
+        self.0.read().ok_or_else(|| anyhow::anyhow!("iOS battery state unavailable"))
+    }
+}
+
+/// Called from Swift once at launch, before the node is started
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+pub fn register_ios_battery_source(source: Arc<dyn IosBatterySource>) -> bool {
+    register_provider(Arc::new(IosBatteryProvider(source)))
+}
+
+#[cfg(target_os = "android")]
+pub use android::AndroidBatteryProvider;
+
+#[cfg(target_os = "android")]
+mod android {
+    use anyhow::{Context, Result};
+    use jni::{
+        objects::{GlobalRef, JObject, JValue},
+        JNIEnv, JavaVM,
+    };
+
+    use super::{android_charging_status, MobileBatteryProvider, MobileBatteryReading};
+
+    const ACTION_BATTERY_CHANGED: &str = "android.intent.action.BATTERY_CHANGED";
+    const BATTERY_PROPERTY_CURRENT_NOW: i32 = 2;
+
+    /// Reads battery state through the app's `Context`
+    pub struct AndroidBatteryProvider {
+        vm: JavaVM,
+        context: GlobalRef,
+    }
+
+    impl AndroidBatteryProvider {
+        /// `context` is the Android application context handed over by the host app
+        pub fn new(vm: JavaVM, context: GlobalRef) -> Self {
+            Self { vm, context }
+        }
+
+        fn int_extra(env: &mut JNIEnv, intent: &JObject, name: &str) -> Result<i32> {
+            let key = env.new_string(name)?;
+            Ok(env
+                .call_method(intent, "getIntExtra", "(Ljava/lang/String;I)I", &[JValue::Object(&key), JValue::Int(-1)])?
+                .i()?)
+        }
+
+        /// Instantaneous current in amps from `BatteryManager` (sign varies by vendor)
+        fn current_now(&self, env: &mut JNIEnv) -> Result<Option<f64>> {
+            let service = env.new_string("batterymanager")?;
+            let manager = env
+                .call_method(
+                    self.context.as_obj(),
+                    "getSystemService",
+                    "(Ljava/lang/String;)Ljava/lang/Object;",
+                    &[JValue::Object(&service)],
+                )?
+                .l()?;
+            if manager.is_null() {
+                return Ok(None);
+            }
+
+            let micro_amps = env
+                .call_method(&manager, "getIntProperty", "(I)I", &[JValue::Int(BATTERY_PROPERTY_CURRENT_NOW)])?
+                .i()?;
+            // Integer.MIN_VALUE when unsupported
+            Ok((micro_amps != i32::MIN).then(|| (micro_amps as f64 / 1_000_000.0).abs()))
+        }
+    }
+
+    impl MobileBatteryProvider for AndroidBatteryProvider {
+        fn read(&self) -> Result<MobileBatteryReading> {
+            let mut env = self.vm.attach_current_thread().context("Failed to attach to the JVM")?;
+
+            // Registering a null receiver returns the sticky battery intent
+            let action = env.new_string(ACTION_BATTERY_CHANGED)?;
+            let filter = env.new_object("android/content/IntentFilter", "(Ljava/lang/String;)V", &[JValue::Object(&action)])?;
+            let intent = env
+                .call_method(
+                    self.context.as_obj(),
+                    "registerReceiver",
+                    "(Landroid/content/BroadcastReceiver;Landroid/content/IntentFilter;)Landroid/content/Intent;",
+                    &[JValue::Object(&JObject::null()), JValue::Object(&filter)],
+                )?
+                .l()?;
+            if intent.is_null() {
+                return Err(anyhow::anyhow!("No battery status broadcast"));
+            }
+
+            let level = Self::int_extra(&mut env, &intent, "level")?;
+            let scale = Self::int_extra(&mut env, &intent, "scale")?;
+            if level < 0 || scale <= 0 {
+                return Err(anyhow::anyhow!("Battery level unavailable"));
+            }
+            let status = Self::int_extra(&mut env, &intent, "status")?;
+            let temperature = Self::int_extra(&mut env, &intent, "temperature")?;
+            let voltage = Self::int_extra(&mut env, &intent, "voltage")?;
+
+            Ok(MobileBatteryReading {
+                level: level as f64 / scale as f64 * 100.0,
+                is_charging: android_charging_status(status),
+                // Tenths of a degree and millivolts
+                temperature_c: (temperature != -1).then(|| temperature as f64 / 10.0),
+                voltage_v: (voltage > 0).then(|| voltage as f64 / 1000.0),
+                current_a: self.current_now(&mut env).unwrap_or(None),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mobile_reading_conversion() {
+        let reading = MobileBatteryReading {
+            level: 64.0,
+            is_charging: android_charging_status(3),
+            temperature_c: Some(31.5),
+            voltage_v: Some(3.9),
+            current_a: Some(0.5),
+        };
+        let battery = reading.to_battery_reading();
+
+        assert_eq!(battery.is_charging, Some(false));
+        assert!((battery.rate_watts - 1.95).abs() < 1e-9);
+        assert_eq!(crate::energy_monitor::batteries::aggregate(&[battery]).0, Some(64.0));
+        assert_eq!(android_charging_status(5), Some(true));
+        assert_eq!(android_charging_status(1), None);
+    }
+}
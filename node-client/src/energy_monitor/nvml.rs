@@ -0,0 +1,63 @@
+/*!
+ * NVIDIA GPU power via NVML
+ * Requires the `nvml` feature and the NVIDIA driver's libnvidia-ml at runtime
+ */
+
+use anyhow::Result;
+use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, Nvml};
+use tracing::debug;
+
+use super::gpu::GpuReading;
+
+/// NVML handle
+pub struct NvmlBackend {
+    nvml: Nvml,
+    device_count: u32,
+}
+
+impl NvmlBackend {
+    /// Load NVML; returns None on machines without an NVIDIA driver
+    pub fn init() -> Option<Self> {
+        let nvml = match Nvml::init() {
+            Ok(nvml) => nvml,
+            Err(e) => {
+                debug!("NVML unavailable: {}", e);
+                return None;
+            }
+        };
+        let device_count = nvml.device_count().ok().filter(|&count| count > 0)?;
+
+        Some(Self { nvml, device_count })
+    }
+
+    /// Number of NVIDIA GPUs
+    pub fn device_count(&self) -> u32 {
+        self.device_count
+    }
+
+    /// Read power, utilization, temperature and memory of every GPU
+    pub fn read(&self) -> Result<Vec<GpuReading>> {
+        let mut readings = Vec::with_capacity(self.device_count as usize);
+
+        for index in 0..self.device_count {
+            let device = self.nvml.device_by_index(index)?;
+            let memory = device.memory_info().ok();
+
+            readings.push(GpuReading {
+                index,
+                vendor: "NVIDIA".to_string(),
+                name: device.name().unwrap_or_else(|_| "Unknown NVIDIA GPU".to_string()),
+                // NVML reports milliwatts
+                power_watts: device.power_usage()? as f64 / 1000.0,
+                utilization_percent: device.utilization_rates().ok().map(|u| u.gpu as f64),
+                temperature_c: device.temperature(TemperatureSensor::Gpu).ok().map(|t| t as f64),
+                // NVML only exposes fan speed as a percentage
+                fan_rpm: None,
+                memory_used_mb: memory.as_ref().map(|m| m.used / 1024 / 1024),
+                memory_total_mb: memory.as_ref().map(|m| m.total / 1024 / 1024),
+            });
+        }
+
+        Ok(readings)
+    }
+}
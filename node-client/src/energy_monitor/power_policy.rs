@@ -0,0 +1,197 @@
+/*!
+ * Battery-aware power policy
+ * Derives how hard the node should work from the battery state: scan frequency, ZK proof
+ * generation and DAG batch sizes are scaled back while discharging and restored on mains power
+ */
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::activity::ActivityPolicy;
+
+/// Operating mode selected from the battery state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerMode {
+    /// Mains power, charging, or no battery
+    Full,
+    /// Discharging below `reduced_below_percent`
+    Reduced,
+    /// Discharging below `minimal_below_percent`
+    Minimal,
+}
+
+/// Battery thresholds and how much each mode throttles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerPolicyConfig {
+    pub enabled: bool,
+    pub reduced_below_percent: f64,
+    pub minimal_below_percent: f64,
+    /// Scan interval multiplier in reduced / minimal mode
+    pub reduced_scan_multiplier: u32,
+    pub minimal_scan_multiplier: u32,
+    /// Fraction of the configured DAG batch size kept in reduced / minimal mode
+    pub reduced_batch_fraction: f64,
+    pub minimal_batch_fraction: f64,
+    /// Defer ZK proof generation already in reduced mode (always deferred in minimal)
+    pub defer_proofs_when_reduced: bool,
+}
+
+impl Default for PowerPolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            reduced_below_percent: 50.0,
+            minimal_below_percent: 20.0,
+            reduced_scan_multiplier: 2,
+            minimal_scan_multiplier: 6,
+            reduced_batch_fraction: 0.5,
+            minimal_batch_fraction: 0.1,
+            defer_proofs_when_reduced: false,
+        }
+    }
+}
+
+/// Current throttling decision, consumed by the scanner, prover and DAG batcher
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerPolicy {
+    pub mode: PowerMode,
+    pub battery_level: Option<f64>,
+    pub is_charging: Option<bool>,
+    pub scan_interval_multiplier: u32,
+    pub batch_fraction: f64,
+    pub defer_zk_proofs: bool,
+    /// Tightened further because temperatures exceeded their limits
+    #[serde(default)]
+    pub thermal_throttled: bool,
+    /// Backed off because someone is using the host
+    #[serde(default)]
+    pub user_active: bool,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        Self::full(None, None)
+    }
+}
+
+impl PowerPolicy {
+    fn full(battery_level: Option<f64>, is_charging: Option<bool>) -> Self {
+        Self {
+            mode: PowerMode::Full,
+            battery_level,
+            is_charging,
+            scan_interval_multiplier: 1,
+            batch_fraction: 1.0,
+            defer_zk_proofs: false,
+            thermal_throttled: false,
+            user_active: false,
+        }
+    }
+
+    /// Pick the policy for a battery reading
+    pub fn evaluate(config: &PowerPolicyConfig, battery_level: Option<f64>, is_charging: Option<bool>) -> Self {
+        // Only throttle when we positively know the battery is draining
+        let level = match (battery_level, is_charging) {
+            (Some(level), Some(false)) if config.enabled => level,
+            _ => return Self::full(battery_level, is_charging),
+        };
+
+        if level < config.minimal_below_percent {
+            Self {
+                mode: PowerMode::Minimal,
+                battery_level,
+                is_charging,
+                scan_interval_multiplier: config.minimal_scan_multiplier.max(1),
+                batch_fraction: config.minimal_batch_fraction.clamp(0.0, 1.0),
+                defer_zk_proofs: true,
+                thermal_throttled: false,
+                user_active: false,
+            }
+        } else if level < config.reduced_below_percent {
+            Self {
+                mode: PowerMode::Reduced,
+                battery_level,
+                is_charging,
+                scan_interval_multiplier: config.reduced_scan_multiplier.max(1),
+                batch_fraction: config.reduced_batch_fraction.clamp(0.0, 1.0),
+                defer_zk_proofs: config.defer_proofs_when_reduced,
+                thermal_throttled: false,
+                user_active: false,
+            }
+        } else {
+            Self::full(battery_level, is_charging)
+        }
+    }
+
+    /// Overlay thermal throttling: defer proofs and cap the batch fraction
+    pub fn with_thermal_throttle(mut self, batch_fraction: f64) -> Self {
+        self.thermal_throttled = true;
+        self.defer_zk_proofs = true;
+        self.batch_fraction = self.batch_fraction.min(batch_fraction.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Overlay the back-off for an active user; the stricter of each setting wins
+    pub fn with_user_activity(self, policy: &ActivityPolicy) -> Self {
+        self.with_activity_ramp(policy, 0.0)
+    }
+
+    /// Overlay the back-off eased by `ramp` (0 = user active, 1 = fully idle), so the workload
+    /// climbs back to full gradually after the user leaves
+    pub fn with_activity_ramp(mut self, policy: &ActivityPolicy, ramp: f64) -> Self {
+        let ramp = ramp.clamp(0.0, 1.0);
+        if ramp >= 1.0 {
+            return self;
+        }
+        let active_multiplier = policy.active_scan_multiplier.max(1) as f64;
+        let active_fraction = policy.active_batch_fraction.clamp(0.0, 1.0);
+
+        self.user_active = ramp == 0.0;
+        self.defer_zk_proofs |= policy.defer_proofs_when_active;
+        self.scan_interval_multiplier = self
+            .scan_interval_multiplier
+            .max((active_multiplier - (active_multiplier - 1.0) * ramp).round() as u32);
+        self.batch_fraction = self.batch_fraction.min(active_fraction + (1.0 - active_fraction) * ramp);
+        self
+    }
+
+    /// Scan interval to use instead of `base`
+    pub fn scan_interval(&self, base: Duration) -> Duration {
+        base * self.scan_interval_multiplier
+    }
+
+    /// DAG batch size to use instead of `base` (never below one)
+    pub fn batch_size(&self, base: usize) -> usize {
+        ((base as f64 * self.batch_fraction).round() as usize).max(1)
+    }
+
+    /// Whether non-urgent ZK proof generation should wait
+    pub fn should_defer_proofs(&self) -> bool {
+        self.defer_zk_proofs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_follows_battery_state() {
+        let config = PowerPolicyConfig::default();
+
+        let policy = PowerPolicy::evaluate(&config, Some(35.0), Some(false));
+        assert_eq!(policy.mode, PowerMode::Reduced);
+        assert_eq!(policy.scan_interval(Duration::from_secs(10)), Duration::from_secs(20));
+        assert_eq!(policy.batch_size(50), 25);
+
+        let policy = PowerPolicy::evaluate(&config, Some(10.0), Some(false));
+        assert_eq!(policy.mode, PowerMode::Minimal);
+        assert!(policy.should_defer_proofs());
+        assert_eq!(policy.batch_size(5), 1);
+
+        // Charging or unknown state restores full operation
+        assert_eq!(PowerPolicy::evaluate(&config, Some(10.0), Some(true)).mode, PowerMode::Full);
+        assert_eq!(PowerPolicy::evaluate(&config, Some(10.0), None).mode, PowerMode::Full);
+        assert_eq!(PowerPolicy::evaluate(&config, None, None).mode, PowerMode::Full);
+    }
+}
@@ -0,0 +1,211 @@
+/*!
+ * Energy monitor configuration and monitoring profiles
+ * Profiles bundle a sampling interval and alert thresholds and can be switched at runtime
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::activity::ActivityConfig;
+use super::alerts::AlertConfig;
+use super::anomaly::AnomalyConfig;
+use super::battery_health::BatteryHealthConfig;
+use super::budget::EnergyBudget;
+use super::calibration::CalibrationConfig;
+use super::carbon::CarbonIntensityConfig;
+use super::container::DeploymentMode;
+use super::power_policy::PowerPolicyConfig;
+use super::renewable::RenewableConfig;
+use super::scheduler::SchedulerConfig;
+use super::shutdown::LowBatteryShutdownConfig;
+use super::smart_plug::SmartPlugConfig;
+use super::thermal::ThermalConfig;
+use super::ups::UpsConfig;
+
+/// Levels at which the monitor raises warnings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertThresholds {
+    pub high_power_watts: f64,
+    pub low_battery_percent: f64,
+}
+
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            high_power_watts: 100.0,
+            low_battery_percent: 20.0,
+        }
+    }
+}
+
+/// Named sampling/alerting preset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitoringProfile {
+    pub name: String,
+    pub sampling_interval_secs: u64,
+    pub alert_thresholds: AlertThresholds,
+}
+
+impl MonitoringProfile {
+    /// Balanced default: 30 s sampling
+    pub fn standard() -> Self {
+        Self {
+            name: "default".to_string(),
+            sampling_interval_secs: 30,
+            alert_thresholds: AlertThresholds::default(),
+        }
+    }
+
+    /// Infrequent sampling for laptops and mobile devices on battery
+    pub fn battery_saver() -> Self {
+        Self {
+            name: "battery-saver".to_string(),
+            sampling_interval_secs: 120,
+            alert_thresholds: AlertThresholds {
+                high_power_watts: 45.0,
+                low_battery_percent: 30.0,
+            },
+        }
+    }
+
+    /// Fine-grained sampling for mains-powered servers
+    pub fn server() -> Self {
+        Self {
+            name: "server".to_string(),
+            sampling_interval_secs: 10,
+            alert_thresholds: AlertThresholds {
+                high_power_watts: 400.0,
+                low_battery_percent: 20.0,
+            },
+        }
+    }
+
+    /// Built-in profiles by name
+    pub fn builtin() -> HashMap<String, MonitoringProfile> {
+        [Self::standard(), Self::battery_saver(), Self::server()]
+            .into_iter()
+            .map(|profile| (profile.name.clone(), profile))
+            .collect()
+    }
+}
+
+/// Energy monitor settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyMonitorConfig {
+    pub enabled: bool,
+    /// Profile active at startup
+    pub profile: String,
+    /// Additional or overriding profiles, merged over the built-in ones
+    #[serde(default)]
+    pub profiles: HashMap<String, MonitoringProfile>,
+    /// Persistent history location (in-memory only when unset)
+    pub history_path: Option<String>,
+    pub history_retention_secs: u64,
+    #[serde(default)]
+    pub carbon: CarbonIntensityConfig,
+    #[serde(default)]
+    pub power_policy: PowerPolicyConfig,
+    #[serde(default)]
+    pub shutdown: LowBatteryShutdownConfig,
+    #[serde(default)]
+    pub thermal: ThermalConfig,
+    /// Daily/monthly kWh or carbon caps
+    #[serde(default)]
+    pub budgets: Vec<EnergyBudget>,
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+    /// `Container` scales host power to the node's cgroup
+    #[serde(default)]
+    pub deployment: DeploymentMode,
+    /// Wall power from a smart plug, also used to calibrate the model
+    #[serde(default)]
+    pub smart_plug: SmartPlugConfig,
+    /// Network UPS Tools server for nodes on a UPS
+    #[serde(default)]
+    pub ups: UpsConfig,
+    /// Renewable share of the supply; carbon is reported net of it
+    #[serde(default)]
+    pub renewable: RenewableConfig,
+    /// Load steps and storage for the fitted power model
+    #[serde(default)]
+    pub calibration: CalibrationConfig,
+    /// Alert rules and webhook endpoints
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    /// Carbon-aware timing of deferrable tasks
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    /// Capacity and cycle tracking for the node's batteries
+    #[serde(default)]
+    pub battery_health: BatteryHealthConfig,
+    /// Back off while the owner uses the machine, per device type
+    #[serde(default)]
+    pub activity: ActivityConfig,
+}
+
+impl Default for EnergyMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            profile: "default".to_string(),
+            profiles: HashMap::new(),
+            history_path: None,
+            history_retention_secs: 30 * 24 * 3600,
+            carbon: CarbonIntensityConfig::default(),
+            power_policy: PowerPolicyConfig::default(),
+            shutdown: LowBatteryShutdownConfig::default(),
+            thermal: ThermalConfig::default(),
+            budgets: Vec::new(),
+            anomaly: AnomalyConfig::default(),
+            deployment: DeploymentMode::default(),
+            smart_plug: SmartPlugConfig::default(),
+            ups: UpsConfig::default(),
+            renewable: RenewableConfig::default(),
+            calibration: CalibrationConfig::default(),
+            alerts: AlertConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            battery_health: BatteryHealthConfig::default(),
+            activity: ActivityConfig::default(),
+        }
+    }
+}
+
+impl EnergyMonitorConfig {
+    /// Look up a profile, preferring user-defined ones over built-ins
+    pub fn find_profile(&self, name: &str) -> Option<MonitoringProfile> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .or_else(|| MonitoringProfile::builtin().remove(name))
+    }
+
+    /// Names of every selectable profile
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = MonitoringProfile::builtin()
+            .into_keys()
+            .chain(self.profiles.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_lookup() {
+        let mut config = EnergyMonitorConfig::default();
+        assert_eq!(config.find_profile("server").unwrap().sampling_interval_secs, 10);
+        assert!(config.find_profile("missing").is_none());
+
+        let mut custom = MonitoringProfile::server();
+        custom.sampling_interval_secs = 5;
+        config.profiles.insert("server".to_string(), custom);
+
+        assert_eq!(config.find_profile("server").unwrap().sampling_interval_secs, 5);
+        assert_eq!(config.profile_names(), vec!["battery-saver", "default", "server"]);
+    }
+}
@@ -0,0 +1,219 @@
+/*!
+ * RAPL energy counters (Linux powercap)
+ * Reads measured package/core/DRAM energy from /sys/class/powercap; Intel and recent AMD
+ * CPUs both expose their counters through the intel-rapl powercap driver
+ */
+
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
+use tracing::debug;
+
+/// Default powercap root
+pub const POWERCAP_ROOT: &str = "/sys/class/powercap";
+
+/// Kind of RAPL zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaplDomainKind {
+    Package,
+    Core,
+    Uncore,
+    Dram,
+    Psys,
+}
+
+impl RaplDomainKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            n if n.starts_with("package") => Some(Self::Package),
+            "core" => Some(Self::Core),
+            "uncore" => Some(Self::Uncore),
+            "dram" => Some(Self::Dram),
+            "psys" => Some(Self::Psys),
+            _ => None,
+        }
+    }
+}
+
+/// One powercap zone with an energy counter
+#[derive(Debug, Clone)]
+pub struct RaplDomain {
+    pub kind: RaplDomainKind,
+    pub energy_path: PathBuf,
+    pub max_energy_uj: u64,
+}
+
+/// Power measured over the interval between two samples
+#[derive(Debug, Clone, Default)]
+pub struct RaplSample {
+    pub package_watts: f64,
+    pub core_watts: Option<f64>,
+    pub dram_watts: Option<f64>,
+}
+
+/// Energy consumed between two counter readings, accounting for wraparound
+pub fn energy_delta_uj(previous: u64, current: u64, max_energy_uj: u64) -> u64 {
+    if current >= previous {
+        current - previous
+    } else {
+        max_energy_uj.saturating_sub(previous) + current
+    }
+}
+
+fn read_u64(path: &Path) -> Result<u64> {
+    fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid counter in {}", path.display()))
+}
+
+/// Reader for all RAPL zones on the machine
+pub struct RaplReader {
+    domains: Vec<RaplDomain>,
+    last: Mutex<Option<(Instant, Vec<u64>)>>,
+}
+
+impl RaplReader {
+    /// Detect RAPL zones under the default powercap root
+    pub fn detect() -> Option<Self> {
+        Self::detect_in(Path::new(POWERCAP_ROOT))
+    }
+
+    /// Detect RAPL zones under `root`; returns None when no readable package counter exists
+    pub fn detect_in(root: &Path) -> Option<Self> {
+        let mut domains = Vec::new();
+
+        for entry in fs::read_dir(root).ok()?.flatten() {
+            let zone = entry.path();
+            let is_rapl = zone
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("intel-rapl:"));
+            if !is_rapl {
+                continue;
+            }
+
+            let Some(kind) = fs::read_to_string(zone.join("name"))
+                .ok()
+                .and_then(|name| RaplDomainKind::from_name(name.trim()))
+            else {
+                continue;
+            };
+
+            // energy_uj is root-only on many distributions since CVE-2020-8694
+            let energy_path = zone.join("energy_uj");
+            if read_u64(&energy_path).is_err() {
+                continue;
+            }
+
+            domains.push(RaplDomain {
+                kind,
+                energy_path,
+                max_energy_uj: read_u64(&zone.join("max_energy_range_uj")).unwrap_or(u64::MAX),
+            });
+        }
+
+        if !domains.iter().any(|d| d.kind == RaplDomainKind::Package) {
+            return None;
+        }
+
+        debug!("⚡ RAPL zones detected: {}", domains.len());
+        Some(Self {
+            domains,
+            last: Mutex::new(None),
+        })
+    }
+
+    /// Detected zones
+    pub fn domains(&self) -> &[RaplDomain] {
+        &self.domains
+    }
+
+    /// Read counters and return average power since the previous call (None on first call)
+    pub fn sample(&self) -> Result<Option<RaplSample>> {
+        let now = Instant::now();
+        let readings = self.domains
+            .iter()
+            .map(|d| read_u64(&d.energy_path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let previous = self.last.lock().unwrap().replace((now, readings.clone()));
+        let Some((then, previous)) = previous else {
+            return Ok(None);
+        };
+
+        let elapsed = now.duration_since(then).as_secs_f64();
+        if elapsed <= 0.0 {
+            return Ok(None);
+        }
+
+        let watts = |kind: RaplDomainKind| -> Option<f64> {
+            let mut found = false;
+            let joules: f64 = self.domains
+                .iter()
+                .zip(previous.iter().zip(&readings))
+                .filter(|(d, _)| d.kind == kind)
+                .map(|(d, (&prev, &cur))| {
+                    found = true;
+                    energy_delta_uj(prev, cur, d.max_energy_uj) as f64 / 1_000_000.0
+                })
+                .sum();
+            found.then(|| joules / elapsed)
+        };
+
+        Ok(Some(RaplSample {
+            package_watts: watts(RaplDomainKind::Package).unwrap_or(0.0),
+            core_watts: watts(RaplDomainKind::Core),
+            dram_watts: watts(RaplDomainKind::Dram),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_zone(root: &Path, zone: &str, name: &str, energy: u64) {
+        let dir = root.join(zone);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("name"), format!("{}\n", name)).unwrap();
+        fs::write(dir.join("energy_uj"), energy.to_string()).unwrap();
+        fs::write(dir.join("max_energy_range_uj"), "262143328850").unwrap();
+    }
+
+    #[test]
+    fn test_energy_delta_wraparound() {
+        assert_eq!(energy_delta_uj(100, 250, 1_000), 150);
+        assert_eq!(energy_delta_uj(900, 50, 1_000), 150);
+    }
+
+    #[test]
+    fn test_detect_and_sample() {
+        let root = tempfile::tempdir().unwrap();
+        write_zone(root.path(), "intel-rapl:0", "package-0", 1_000_000);
+        write_zone(root.path(), "intel-rapl:0:2", "dram", 500_000);
+        write_zone(root.path(), "intel-rapl:1", "psys", 0);
+
+        let reader = RaplReader::detect_in(root.path()).unwrap();
+        assert_eq!(reader.domains().len(), 3);
+        assert!(reader.sample().unwrap().is_none());
+
+        fs::write(root.path().join("intel-rapl:0/energy_uj"), "3000000").unwrap();
+        let sample = reader.sample().unwrap().unwrap();
+        assert!(sample.package_watts > 0.0);
+        assert!(sample.core_watts.is_none());
+        assert_eq!(sample.dram_watts, Some(0.0));
+    }
+
+    #[test]
+    fn test_no_package_zone() {
+        let root = tempfile::tempdir().unwrap();
+        write_zone(root.path(), "intel-rapl:1", "psys", 0);
+        assert!(RaplReader::detect_in(root.path()).is_none());
+    }
+}
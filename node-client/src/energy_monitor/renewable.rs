@@ -0,0 +1,230 @@
+/*!
+ * Renewable energy source tagging
+ * Operators declare the renewable share of the node's supply; Enphase Envoy and SolarEdge
+ * production readings can back the declaration, and the resulting source mix nets out the
+ * renewable share of the node's carbon footprint
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RenewableSource {
+    #[default]
+    Solar,
+    Wind,
+    Hydro,
+    /// Green tariff or other contracted supply
+    Other,
+}
+
+/// Production meter backing the declaration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum SolarMeterConfig {
+    /// Enphase Envoy local API (`/production.json`), token required on firmware 7+
+    Enphase { host: String, token: Option<String> },
+    /// SolarEdge monitoring API (`currentPowerFlow`)
+    SolarEdge { site_id: String, api_key: String },
+}
+
+/// Renewable supply settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenewableConfig {
+    pub enabled: bool,
+    pub source: RenewableSource,
+    /// Share of the node's supply the operator declares renewable (0-1)
+    pub declared_fraction: f64,
+    pub meter: Option<SolarMeterConfig>,
+    /// SolarEdge allows 300 requests a day per site
+    pub meter_poll_secs: u64,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for RenewableConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: RenewableSource::Solar,
+            declared_fraction: 0.0,
+            meter: None,
+            meter_poll_secs: 900,
+            request_timeout_secs: 5,
+        }
+    }
+}
+
+/// Supply mix of one energy reading
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SourceMix {
+    pub source: RenewableSource,
+    /// Share of the node's power covered by renewables (0-1)
+    pub renewable_fraction: f64,
+    /// Backed by a production meter rather than the operator's declaration
+    pub verified: bool,
+}
+
+impl SourceMix {
+    pub fn declared(source: RenewableSource, fraction: f64) -> Self {
+        Self {
+            source,
+            renewable_fraction: fraction.clamp(0.0, 1.0),
+            verified: false,
+        }
+    }
+
+    /// Mix when the meter reports `production_watts` while the node draws `node_watts`
+    pub fn metered(source: RenewableSource, production_watts: f64, node_watts: f64) -> Self {
+        let renewable_fraction = if node_watts > 0.0 {
+            (production_watts.max(0.0) / node_watts).min(1.0)
+        } else {
+            1.0
+        };
+        Self {
+            source,
+            renewable_fraction,
+            verified: true,
+        }
+    }
+
+    /// Grid carbon left after the renewable share
+    pub fn net_carbon(&self, gross_kg: f64) -> f64 {
+        gross_kg * (1.0 - self.renewable_fraction)
+    }
+}
+
+/// Current production (W) from Envoy `/production.json`; revenue-grade meter preferred
+pub fn parse_enphase_production(json: &Value) -> Option<f64> {
+    let production = json.get("production")?.as_array()?;
+    let watts_of = |kind: &str| {
+        production
+            .iter()
+            .find(|entry| entry.get("type").and_then(Value::as_str) == Some(kind))
+            .and_then(|entry| entry.get("wNow")?.as_f64())
+    };
+    watts_of("eim").or_else(|| watts_of("inverters"))
+}
+
+/// Current PV power (W) from SolarEdge `currentPowerFlow`
+pub fn parse_solaredge_power_flow(json: &Value) -> Option<f64> {
+    let flow = json.get("siteCurrentPowerFlow")?;
+    let power = flow.get("PV")?.get("currentPower")?.as_f64()?;
+    match flow.get("unit").and_then(Value::as_str) {
+        Some("W") => Some(power),
+        Some("MW") => Some(power * 1e6),
+        _ => Some(power * 1000.0),
+    }
+}
+
+/// Tags readings with their source mix
+pub struct RenewableTracker {
+    config: RenewableConfig,
+    http: reqwest::Client,
+    last_production: Mutex<Option<(Instant, f64)>>,
+}
+
+impl RenewableTracker {
+    pub fn new(config: RenewableConfig) -> Self {
+        // Envoys serve a self-signed certificate on the local network
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .danger_accept_invalid_certs(matches!(config.meter, Some(SolarMeterConfig::Enphase { .. })))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            http,
+            last_production: Mutex::new(None),
+        }
+    }
+
+    async fn read_production(&self, meter: &SolarMeterConfig) -> Result<f64> {
+        match meter {
+            SolarMeterConfig::Enphase { host, token } => {
+                let mut request = self.http.get(format!("https://{}/production.json", host));
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                let json: Value = request.send().await?.error_for_status()?.json().await?;
+                parse_enphase_production(&json).context("Envoy reply has no production meter")
+            }
+            SolarMeterConfig::SolarEdge { site_id, api_key } => {
+                let json: Value = self
+                    .http
+                    .get(format!("https://monitoringapi.solaredge.com/site/{}/currentPowerFlow", site_id))
+                    .query(&[("api_key", api_key)])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                parse_solaredge_power_flow(&json).context("SolarEdge reply has no PV power")
+            }
+        }
+    }
+
+    /// Production, polled at most every `meter_poll_secs`
+    async fn production_watts(&self, meter: &SolarMeterConfig) -> Result<f64> {
+        let poll = Duration::from_secs(self.config.meter_poll_secs);
+        if let Some((at, watts)) = *self.last_production.lock().unwrap() {
+            if at.elapsed() < poll {
+                return Ok(watts);
+            }
+        }
+
+        let watts = self.read_production(meter).await?;
+        *self.last_production.lock().unwrap() = Some((Instant::now(), watts));
+        Ok(watts)
+    }
+
+    /// Mix for a reading of `node_watts`; falls back to the declaration without a meter reading
+    pub async fn source_mix(&self, node_watts: f64) -> SourceMix {
+        let declared = SourceMix::declared(self.config.source, self.config.declared_fraction);
+        let Some(meter) = &self.config.meter else {
+            return declared;
+        };
+
+        match self.production_watts(meter).await {
+            Ok(watts) => SourceMix::metered(self.config.source, watts, node_watts),
+            Err(e) => {
+                debug!("Renewable meter read failed, using declared mix: {}", e);
+                declared
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_meter_parsing_and_net_carbon() {
+        let envoy = json!({"production": [
+            {"type": "inverters", "wNow": 2900},
+            {"type": "eim", "measurementType": "production", "wNow": 3012.5}
+        ]});
+        assert_eq!(parse_enphase_production(&envoy), Some(3012.5));
+
+        let solaredge = json!({"siteCurrentPowerFlow": {"unit": "kW", "PV": {"status": "Active", "currentPower": 0.06}}});
+        let watts = parse_solaredge_power_flow(&solaredge).unwrap();
+        assert!((watts - 60.0).abs() < 1e-9);
+
+        // 60 W of PV covers half of a 120 W node
+        let mix = SourceMix::metered(RenewableSource::Solar, watts, 120.0);
+        assert!(mix.verified);
+        assert!((mix.net_carbon(0.05) - 0.025).abs() < 1e-9);
+
+        let declared = SourceMix::declared(RenewableSource::Wind, 1.5);
+        assert_eq!(declared.renewable_fraction, 1.0);
+        assert_eq!(declared.net_carbon(0.05), 0.0);
+    }
+}
@@ -0,0 +1,291 @@
+/*!
+ * In-memory energy history with tiered rollups
+ * Raw readings live in a bounded ring buffer and are folded into per-minute and per-hour
+ * aggregates, so weekly/monthly figures come from a few hundred rollups instead of raw samples
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::thermal::ThermalEvent;
+use super::EnergyData;
+
+/// Raw readings kept (~8 hours at 30s)
+pub const RAW_CAPACITY: usize = 1000;
+/// Per-minute rollups kept (24 hours)
+pub const MINUTE_CAPACITY: usize = 24 * 60;
+/// Per-hour rollups kept (~90 days)
+pub const HOUR_CAPACITY: usize = 90 * 24;
+/// Thermal throttle transitions kept
+pub const THERMAL_EVENT_CAPACITY: usize = 256;
+
+/// Gaps longer than this (monitor stopped, machine asleep) are not counted as energy
+const MAX_SAMPLE_GAP_SECS: u64 = 300;
+
+/// Aggregate of all readings inside one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rollup {
+    /// Bucket start (unix seconds)
+    pub start: u64,
+    pub samples: u32,
+    pub avg_watts: f64,
+    pub min_watts: f64,
+    pub max_watts: f64,
+    pub energy_kwh: f64,
+    pub carbon_kg: f64,
+    pub avg_efficiency: f64,
+    /// Seconds of monitored time inside the bucket
+    pub covered_secs: u64,
+}
+
+impl Rollup {
+    fn new(start: u64) -> Self {
+        Self {
+            start,
+            samples: 0,
+            avg_watts: 0.0,
+            min_watts: f64::INFINITY,
+            max_watts: f64::NEG_INFINITY,
+            energy_kwh: 0.0,
+            carbon_kg: 0.0,
+            avg_efficiency: 0.0,
+            covered_secs: 0,
+        }
+    }
+
+    fn add(&mut self, data: &EnergyData, dt_secs: u64) {
+        let n = self.samples as f64;
+        self.avg_watts = (self.avg_watts * n + data.total_watts) / (n + 1.0);
+        self.avg_efficiency = (self.avg_efficiency * n + data.efficiency_score as f64) / (n + 1.0);
+        self.min_watts = self.min_watts.min(data.total_watts);
+        self.max_watts = self.max_watts.max(data.total_watts);
+
+        let hours = dt_secs as f64 / 3600.0;
+        self.energy_kwh += data.total_watts * hours / 1000.0;
+        self.carbon_kg += data.carbon_footprint_kg_per_hour * hours;
+        self.covered_secs += dt_secs;
+        self.samples += 1;
+    }
+
+    fn merge(&mut self, other: &Rollup) {
+        if other.samples == 0 {
+            return;
+        }
+        let (n, m) = (self.samples as f64, other.samples as f64);
+        self.avg_watts = (self.avg_watts * n + other.avg_watts * m) / (n + m);
+        self.avg_efficiency = (self.avg_efficiency * n + other.avg_efficiency * m) / (n + m);
+        self.min_watts = self.min_watts.min(other.min_watts);
+        self.max_watts = self.max_watts.max(other.max_watts);
+        self.energy_kwh += other.energy_kwh;
+        self.carbon_kg += other.carbon_kg;
+        self.covered_secs += other.covered_secs;
+        self.samples += other.samples;
+    }
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, item: T, capacity: usize) {
+    if buffer.len() == capacity {
+        buffer.pop_front();
+    }
+    buffer.push_back(item);
+}
+
+/// Ring buffer of raw readings plus minute and hour rollups
+#[derive(Debug, Clone)]
+pub struct EnergyHistory {
+    /// Grows to twice `raw_capacity` before the oldest half is dropped, so the
+    /// newest `raw_capacity` readings are always one contiguous slice
+    raw: Vec<EnergyData>,
+    minutes: VecDeque<Rollup>,
+    hours: VecDeque<Rollup>,
+    current_minute: Option<Rollup>,
+    current_hour: Option<Rollup>,
+    last_timestamp: Option<u64>,
+    raw_capacity: usize,
+    thermal_events: VecDeque<ThermalEvent>,
+}
+
+impl Default for EnergyHistory {
+    fn default() -> Self {
+        Self::new(RAW_CAPACITY)
+    }
+}
+
+impl EnergyHistory {
+    pub fn new(raw_capacity: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(raw_capacity.max(1) * 2),
+            minutes: VecDeque::new(),
+            hours: VecDeque::new(),
+            current_minute: None,
+            current_hour: None,
+            last_timestamp: None,
+            raw_capacity: raw_capacity.max(1),
+            thermal_events: VecDeque::new(),
+        }
+    }
+
+    /// Record a reading and update the rollups
+    pub fn push(&mut self, data: EnergyData) {
+        let dt = match self.last_timestamp {
+            Some(last) if data.timestamp > last && data.timestamp - last <= MAX_SAMPLE_GAP_SECS => {
+                data.timestamp - last
+            }
+            _ => 0,
+        };
+        self.last_timestamp = Some(data.timestamp);
+
+        let minute_start = data.timestamp - data.timestamp % 60;
+        if self.current_minute.as_ref().is_some_and(|m| m.start != minute_start) {
+            let finished = self.current_minute.take().unwrap();
+            self.close_minute(finished);
+        }
+        self.current_minute
+            .get_or_insert_with(|| Rollup::new(minute_start))
+            .add(&data, dt);
+
+        if self.raw.len() == self.raw_capacity * 2 {
+            self.raw.drain(..self.raw_capacity);
+        }
+        self.raw.push(data);
+    }
+
+    fn close_minute(&mut self, minute: Rollup) {
+        let hour_start = minute.start - minute.start % 3600;
+        if self.current_hour.as_ref().is_some_and(|h| h.start != hour_start) {
+            let finished = self.current_hour.take().unwrap();
+            push_bounded(&mut self.hours, finished, HOUR_CAPACITY);
+        }
+        self.current_hour
+            .get_or_insert_with(|| Rollup::new(hour_start))
+            .merge(&minute);
+
+        push_bounded(&mut self.minutes, minute, MINUTE_CAPACITY);
+    }
+
+    /// Record a thermal throttle transition
+    pub fn record_thermal_event(&mut self, event: ThermalEvent) {
+        push_bounded(&mut self.thermal_events, event, THERMAL_EVENT_CAPACITY);
+    }
+
+    /// Thermal throttle transitions, oldest first
+    pub fn thermal_events(&self) -> Vec<ThermalEvent> {
+        self.thermal_events.iter().cloned().collect()
+    }
+
+    /// Raw readings, oldest first
+    pub fn raw(&self) -> &[EnergyData] {
+        &self.raw[self.raw.len().saturating_sub(self.raw_capacity)..]
+    }
+
+    /// Most recent raw reading
+    pub fn latest(&self) -> Option<&EnergyData> {
+        self.raw.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.raw().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Completed minute rollups plus the one in progress
+    pub fn minute_rollups(&self) -> Vec<Rollup> {
+        self.minutes.iter().chain(self.current_minute.iter()).cloned().collect()
+    }
+
+    /// Completed hour rollups plus the one in progress (including its open minute)
+    pub fn hour_rollups(&self) -> Vec<Rollup> {
+        let mut current = self.current_hour.clone();
+        if let Some(minute) = &self.current_minute {
+            let hour_start = minute.start - minute.start % 3600;
+            match current.as_mut() {
+                Some(hour) if hour.start == hour_start => hour.merge(minute),
+                _ => {
+                    // Open minute starts a new hour that has no closed minutes yet
+                    let mut hour = Rollup::new(hour_start);
+                    hour.merge(minute);
+                    return self.hours.iter().chain(current.iter()).cloned().chain([hour]).collect();
+                }
+            }
+        }
+        self.hours.iter().chain(current.iter()).cloned().collect()
+    }
+
+    /// Rollups covering everything since `since`, from the finest tier that reaches back that far
+    pub fn rollups_since(&self, since: u64) -> Vec<Rollup> {
+        let minutes = self.minute_rollups();
+        let (tier, width) = if minutes.first().is_none_or(|m| m.start <= since) {
+            (minutes, 60)
+        } else {
+            (self.hour_rollups(), 3600)
+        };
+        tier.into_iter().filter(|r| r.start + width > since).collect()
+    }
+}
+
+/// Combine rollups into one aggregate
+pub fn combine(rollups: &[Rollup]) -> Option<Rollup> {
+    let first = rollups.first()?;
+    let mut total = Rollup::new(first.start);
+    for rollup in rollups {
+        total.merge(rollup);
+    }
+    (total.samples > 0).then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, watts: f64) -> EnergyData {
+        EnergyData {
+            total_watts: watts,
+            efficiency_score: 50,
+            carbon_footprint_kg_per_hour: watts / 1000.0 * 0.5,
+            timestamp,
+            ..EnergyData::default()
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_bounded() {
+        let mut history = EnergyHistory::new(3);
+        for i in 0..10 {
+            history.push(reading(i * 30, 10.0));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.raw()[0].timestamp, 210);
+    }
+
+    #[test]
+    fn test_rollups_accumulate_energy() {
+        let mut history = EnergyHistory::default();
+        // Two hours at a constant 100 W, one reading every 30 seconds
+        for i in 0..=240 {
+            history.push(reading(i * 30, 100.0));
+        }
+
+        let hours = history.hour_rollups();
+        assert_eq!(hours.len(), 3);
+
+        let total = combine(&hours).unwrap();
+        assert!((total.energy_kwh - 0.2).abs() < 1e-9);
+        assert!((total.carbon_kg - 0.1).abs() < 1e-9);
+        assert_eq!(total.covered_secs, 7200);
+        assert_eq!(total.samples, 241);
+        assert_eq!(history.minute_rollups().len(), 121);
+    }
+
+    #[test]
+    fn test_gaps_are_not_counted() {
+        let mut history = EnergyHistory::default();
+        history.push(reading(0, 100.0));
+        history.push(reading(10_000, 100.0));
+
+        let total = combine(&history.hour_rollups()).unwrap();
+        assert_eq!(total.energy_kwh, 0.0);
+    }
+}
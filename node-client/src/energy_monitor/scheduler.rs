@@ -0,0 +1,212 @@
+/*!
+ * Carbon-aware task scheduling
+ * Subsystems register deferrable work (ZK parameter generation, proof batching, reward
+ * claims) with a deadline; the scheduler runs it in the lowest-carbon forecast window before
+ * the deadline, and only on mains power when the task asks for it
+ */
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use super::carbon::IntensityPoint;
+
+/// Scheduler settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    pub enabled: bool,
+    pub check_interval_secs: u64,
+    pub forecast_horizon_hours: u32,
+    /// Run when the current intensity is within this fraction of the best forecast window
+    pub tolerance: f64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval_secs: 300,
+            forecast_horizon_hours: 24,
+            tolerance: 0.05,
+        }
+    }
+}
+
+/// How a task may be deferred
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskPolicy {
+    /// Longest the task may wait, from registration
+    pub max_delay_secs: u64,
+    pub estimated_duration_secs: u64,
+    /// Always run at or below this intensity (kg CO2 per kWh)
+    pub run_below_intensity: Option<f64>,
+    /// Wait for mains power (until the deadline)
+    pub mains_only: bool,
+}
+
+impl Default for TaskPolicy {
+    fn default() -> Self {
+        Self {
+            max_delay_secs: 6 * 3600,
+            estimated_duration_secs: 300,
+            run_below_intensity: None,
+            mains_only: true,
+        }
+    }
+}
+
+pub type TaskFn = Arc<dyn Fn() -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Work registered with the scheduler
+#[derive(Clone)]
+pub struct DeferrableTask {
+    pub name: String,
+    pub policy: TaskPolicy,
+    pub run: TaskFn,
+}
+
+/// Grid and power state at a scheduling decision
+#[derive(Debug, Clone)]
+pub struct SchedulingConditions {
+    pub now: u64,
+    pub intensity: f64,
+    pub forecast: Vec<IntensityPoint>,
+    pub on_mains: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    RunNow,
+    /// Expected best start (unix seconds), or None when waiting for mains power
+    Defer(Option<u64>),
+}
+
+/// Decide whether a task with `deadline` should start now
+pub fn decide(policy: &TaskPolicy, deadline: u64, conditions: &SchedulingConditions, tolerance: f64) -> Decision {
+    let latest_start = deadline.saturating_sub(policy.estimated_duration_secs);
+    if conditions.now >= latest_start {
+        return Decision::RunNow;
+    }
+    if policy.mains_only && !conditions.on_mains {
+        return Decision::Defer(None);
+    }
+    if policy.run_below_intensity.is_some_and(|limit| conditions.intensity <= limit) {
+        return Decision::RunNow;
+    }
+
+    let best = conditions
+        .forecast
+        .iter()
+        .filter(|point| point.timestamp > conditions.now && point.timestamp <= latest_start)
+        .min_by(|a, b| a.intensity.total_cmp(&b.intensity));
+    match best {
+        Some(best) if conditions.intensity > best.intensity * (1.0 + tolerance) => Decision::Defer(Some(best.timestamp)),
+        // Now is as good as it gets (or there is no forecast to wait for)
+        _ => Decision::RunNow,
+    }
+}
+
+struct PendingTask {
+    id: u64,
+    deadline: u64,
+    task: DeferrableTask,
+}
+
+/// Registry of deferrable tasks
+pub struct CarbonScheduler {
+    config: SchedulerConfig,
+    next_id: AtomicU64,
+    pending: Mutex<Vec<PendingTask>>,
+}
+
+impl CarbonScheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config,
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    /// Register a task; returns an id for `cancel`
+    pub fn register(&self, task: DeferrableTask) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let deadline = chrono::Utc::now().timestamp() as u64 + task.policy.max_delay_secs;
+        self.pending.lock().unwrap().push(PendingTask { id, deadline, task });
+        id
+    }
+
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let before = pending.len();
+        pending.retain(|task| task.id != id);
+        pending.len() != before
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Remove and return the tasks that should start under `conditions`
+    pub fn due_tasks(&self, conditions: &SchedulingConditions) -> Vec<DeferrableTask> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut due = Vec::new();
+        pending.retain(|entry| {
+            // Disabled scheduling runs everything immediately
+            let run = !self.config.enabled
+                || decide(&entry.task.policy, entry.deadline, conditions, self.config.tolerance) == Decision::RunNow;
+            if run {
+                due.push(entry.task.clone());
+            }
+            !run
+        });
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defers_to_low_carbon_window() {
+        let policy = TaskPolicy {
+            max_delay_secs: 8 * 3600,
+            estimated_duration_secs: 600,
+            run_below_intensity: None,
+            mains_only: true,
+        };
+        let forecast: Vec<IntensityPoint> = [0.40, 0.35, 0.12, 0.30]
+            .iter()
+            .enumerate()
+            .map(|(hour, &intensity)| IntensityPoint { timestamp: 3600 * (hour as u64 + 1), intensity })
+            .collect();
+        let mut conditions = SchedulingConditions {
+            now: 0,
+            intensity: 0.45,
+            forecast,
+            on_mains: true,
+        };
+        let deadline = 8 * 3600;
+
+        assert_eq!(decide(&policy, deadline, &conditions, 0.05), Decision::Defer(Some(3 * 3600)));
+
+        conditions.now = 3 * 3600;
+        conditions.intensity = 0.12;
+        assert_eq!(decide(&policy, deadline, &conditions, 0.05), Decision::RunNow);
+
+        // On battery the task waits, but never past its deadline
+        conditions.on_mains = false;
+        assert_eq!(decide(&policy, deadline, &conditions, 0.05), Decision::Defer(None));
+        conditions.now = deadline - 600;
+        assert_eq!(decide(&policy, deadline, &conditions, 0.05), Decision::RunNow);
+    }
+}
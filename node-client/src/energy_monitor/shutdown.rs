@@ -0,0 +1,60 @@
+/*!
+ * Graceful low-battery shutdown
+ * Below the critical level the monitor runs the registered shutdown hook (flush state,
+ * submit critical threats, final heartbeat) and stops instead of letting the node die mid-write
+ */
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Low-battery shutdown settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowBatteryShutdownConfig {
+    pub enabled: bool,
+    /// Shut down when discharging below this level
+    pub critical_battery_percent: f64,
+    /// Where pending DAG transactions are written
+    pub tx_pool_path: String,
+    /// Pending threat submissions at or above this priority are sent before stopping
+    pub critical_priority: u8,
+}
+
+impl Default for LowBatteryShutdownConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            critical_battery_percent: 5.0,
+            tx_pool_path: "dagshield_tx_pool.json".to_string(),
+            critical_priority: 100,
+        }
+    }
+}
+
+impl LowBatteryShutdownConfig {
+    /// Whether a battery reading calls for shutdown
+    pub fn is_critical(&self, battery_level: Option<f64>, is_charging: Option<bool>) -> bool {
+        self.enabled
+            && is_charging == Some(false)
+            && battery_level.is_some_and(|level| level < self.critical_battery_percent)
+    }
+}
+
+/// Orderly shutdown sequence run by the monitor
+pub type ShutdownHook = Arc<dyn Fn() -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_critical_only_when_discharging() {
+        let config = LowBatteryShutdownConfig::default();
+
+        assert!(config.is_critical(Some(3.0), Some(false)));
+        assert!(!config.is_critical(Some(3.0), Some(true)));
+        assert!(!config.is_critical(Some(3.0), None));
+        assert!(!config.is_critical(Some(10.0), Some(false)));
+    }
+}
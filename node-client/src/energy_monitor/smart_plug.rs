@@ -0,0 +1,413 @@
+/*!
+ * Smart plug wall power
+ * Reads ground-truth wall power from Tasmota, Shelly or TP-Link Kasa plugs on the local
+ * network (HTTP, Kasa TCP or Tasmota MQTT) and calibrates the software model against it.
+ * The plug is polled in the background so a slow plug never holds up a sample
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{interval, timeout, MissedTickBehavior},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+/// Largest Kasa reply accepted; a realtime emeter reply is a few hundred bytes
+const KASA_MAX_REPLY_BYTES: usize = 16 * 1024;
+
+/// Where a reading's total power came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MeasurementSource {
+    /// Coefficient model only
+    #[default]
+    Model,
+    /// Model scaled by a smart plug calibration
+    CalibratedModel,
+    /// CPU/SoC counters (RAPL, powermetrics, WMI) plus modeled components
+    Counters,
+    /// Board input power monitor
+    BoardSensor,
+    /// Server PSU input sensors read over IPMI
+    Ipmi,
+    /// Wall power from a smart plug
+    SmartPlug,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SmartPlugKind {
+    /// Tasmota HTTP API (`Status 8`)
+    Tasmota,
+    /// Shelly Gen1 (`/status`)
+    Shelly,
+    /// Shelly Gen2+ RPC (`Switch.GetStatus`)
+    ShellyRpc,
+    /// TP-Link Kasa local protocol (TCP 9999)
+    Kasa,
+    /// Tasmota `tele/<topic>/SENSOR` over MQTT (requires the `mqtt` feature)
+    TasmotaMqtt,
+}
+
+/// Smart plug settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartPlugConfig {
+    pub enabled: bool,
+    pub kind: SmartPlugKind,
+    /// Plug host (HTTP/Kasa) or MQTT broker host
+    pub host: String,
+    pub port: Option<u16>,
+    /// Tasmota MQTT topic
+    pub mqtt_topic: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub request_timeout_secs: u64,
+    /// Smoothing for the measured/modeled ratio (0-1, higher reacts faster)
+    pub calibration_alpha: f64,
+    /// How often the plug is read in the background
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+}
+
+fn default_poll_interval_secs() -> u64 {
+    10
+}
+
+impl Default for SmartPlugConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: SmartPlugKind::Tasmota,
+            host: String::new(),
+            port: None,
+            mqtt_topic: None,
+            username: None,
+            password: None,
+            request_timeout_secs: 3,
+            calibration_alpha: 0.1,
+            poll_interval_secs: default_poll_interval_secs(),
+        }
+    }
+}
+
+fn number_or_sum(value: &Value) -> Option<f64> {
+    match value {
+        Value::Array(values) => Some(values.iter().filter_map(Value::as_f64).sum()),
+        value => value.as_f64(),
+    }
+}
+
+/// `ENERGY.Power` from Tasmota `Status 8` or a `tele/.../SENSOR` message (summed over channels)
+pub fn parse_tasmota(json: &Value) -> Option<f64> {
+    let energy = json.get("StatusSNS").unwrap_or(json).get("ENERGY")?;
+    number_or_sum(energy.get("Power")?)
+}
+
+/// Shelly Gen1 `/status` (`meters` or `emeters`) or Gen2 `Switch.GetStatus` (`apower`)
+pub fn parse_shelly(json: &Value) -> Option<f64> {
+    if let Some(apower) = json.get("apower").and_then(Value::as_f64) {
+        return Some(apower);
+    }
+
+    let meters = json.get("meters").or_else(|| json.get("emeters"))?.as_array()?;
+    let watts: Vec<f64> = meters.iter().filter_map(|meter| meter.get("power")?.as_f64()).collect();
+    (!watts.is_empty()).then(|| watts.iter().sum())
+}
+
+/// Kasa `emeter.get_realtime` (`power` in W on v1 firmware, `power_mw` on v2)
+pub fn parse_kasa(json: &Value) -> Option<f64> {
+    let realtime = json.get("emeter")?.get("get_realtime")?;
+    realtime
+        .get("power")
+        .and_then(Value::as_f64)
+        .or_else(|| realtime.get("power_mw").and_then(Value::as_f64).map(|mw| mw / 1000.0))
+}
+
+/// Kasa "autokey" XOR cipher with a 4-byte big-endian length prefix
+pub fn kasa_encrypt(plain: &[u8]) -> Vec<u8> {
+    let mut key = 171u8;
+    let mut out = (plain.len() as u32).to_be_bytes().to_vec();
+    for byte in plain {
+        key ^= byte;
+        out.push(key);
+    }
+    out
+}
+
+pub fn kasa_decrypt(cipher: &[u8]) -> Vec<u8> {
+    let mut key = 171u8;
+    cipher
+        .iter()
+        .map(|&byte| {
+            let plain = key ^ byte;
+            key = byte;
+            plain
+        })
+        .collect()
+}
+
+/// Running measured/modeled ratio used when the plug is unreachable
+#[derive(Debug, Default)]
+pub struct WallPowerCalibration {
+    alpha: f64,
+    scale: Mutex<Option<f64>>,
+}
+
+impl WallPowerCalibration {
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            scale: Mutex::new(None),
+        }
+    }
+
+    /// Update with a simultaneous measurement and model estimate
+    pub fn observe(&self, measured_watts: f64, modeled_watts: f64) {
+        if measured_watts <= 0.0 || modeled_watts <= 0.0 {
+            return;
+        }
+        let ratio = measured_watts / modeled_watts;
+        let mut scale = self.scale.lock().unwrap();
+        *scale = Some(scale.map_or(ratio, |previous| previous + self.alpha * (ratio - previous)));
+    }
+
+    pub fn scale(&self) -> Option<f64> {
+        *self.scale.lock().unwrap()
+    }
+}
+
+/// Smart plug reader
+pub struct SmartPlug {
+    config: SmartPlugConfig,
+    http: reqwest::Client,
+    calibration: WallPowerCalibration,
+    /// Latest reading from `run_polling`
+    latest: Mutex<Option<(f64, Instant)>>,
+    #[cfg(feature = "mqtt")]
+    mqtt: mqtt::TasmotaSubscriber,
+}
+
+impl SmartPlug {
+    pub fn new(config: SmartPlugConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            calibration: WallPowerCalibration::new(config.calibration_alpha),
+            latest: Mutex::new(None),
+            #[cfg(feature = "mqtt")]
+            mqtt: mqtt::TasmotaSubscriber::new(&config),
+            config,
+            http,
+        }
+    }
+
+    pub fn calibration(&self) -> &WallPowerCalibration {
+        &self.calibration
+    }
+
+    fn base_url(&self) -> String {
+        match self.config.port {
+            Some(port) => format!("http://{}:{}", self.config.host, port),
+            None => format!("http://{}", self.config.host),
+        }
+    }
+
+    async fn get_json(&self, path: &str) -> Result<Value> {
+        let mut request = self.http.get(format!("{}{}", self.base_url(), path));
+        if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.as_deref());
+        }
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn read_kasa(&self) -> Result<f64> {
+        let address = (self.config.host.as_str(), self.config.port.unwrap_or(9999));
+        let limit = Duration::from_secs(self.config.request_timeout_secs);
+
+        let exchange = async {
+            let mut stream = TcpStream::connect(address).await?;
+            stream.write_all(&kasa_encrypt(br#"{"emeter":{"get_realtime":{}}}"#)).await?;
+
+            let mut length = [0u8; 4];
+            stream.read_exact(&mut length).await?;
+            let length = u32::from_be_bytes(length) as usize;
+            if length > KASA_MAX_REPLY_BYTES {
+                return Err(anyhow::anyhow!("Kasa reply of {} bytes is too large", length));
+            }
+            let mut payload = vec![0u8; length];
+            stream.read_exact(&mut payload).await?;
+            anyhow::Ok(kasa_decrypt(&payload))
+        };
+
+        let plain = timeout(limit, exchange).await.context("Kasa plug timed out")??;
+        parse_kasa(&serde_json::from_slice(&plain)?).context("Kasa plug has no energy meter")
+    }
+
+    /// Read the plug every `poll_interval_secs` until `cancel` fires
+    pub async fn run_polling(&self, cancel: CancellationToken) {
+        let mut poll = interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = poll.tick() => {}
+            }
+            match self.read_watts().await {
+                Ok(watts) => *self.latest.lock().unwrap() = Some((watts, Instant::now())),
+                Err(e) => debug!("Smart plug read failed: {}", e),
+            }
+        }
+    }
+
+    /// Latest polled wall power, unless it is older than two poll intervals
+    pub fn latest_watts(&self) -> Option<f64> {
+        let max_age = Duration::from_secs(self.config.poll_interval_secs.max(1) * 2);
+        self.latest
+            .lock()
+            .unwrap()
+            .filter(|(_, read_at)| read_at.elapsed() <= max_age)
+            .map(|(watts, _)| watts)
+    }
+
+    /// Current wall power in watts, read from the plug now
+    pub async fn read_watts(&self) -> Result<f64> {
+        match self.config.kind {
+            SmartPlugKind::Tasmota => parse_tasmota(&self.get_json("/cm?cmnd=Status%208").await?)
+                .context("Tasmota reply has no ENERGY.Power"),
+            SmartPlugKind::Shelly => parse_shelly(&self.get_json("/status").await?)
+                .context("Shelly reply has no power meter"),
+            SmartPlugKind::ShellyRpc => parse_shelly(&self.get_json("/rpc/Switch.GetStatus?id=0").await?)
+                .context("Shelly reply has no apower"),
+            SmartPlugKind::Kasa => self.read_kasa().await,
+            #[cfg(feature = "mqtt")]
+            SmartPlugKind::TasmotaMqtt => self.mqtt.latest(Duration::from_secs(300)),
+            #[cfg(not(feature = "mqtt"))]
+            SmartPlugKind::TasmotaMqtt => Err(anyhow::anyhow!("Built without the `mqtt` feature")),
+        }
+    }
+}
+
+#[cfg(feature = "mqtt")]
+mod mqtt {
+    use anyhow::{Context, Result};
+    use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+    use std::{
+        sync::{Arc, Mutex, Once},
+        time::{Duration, Instant},
+    };
+    use tracing::warn;
+
+    use super::{parse_tasmota, SmartPlugConfig};
+
+    /// Keeps the latest `tele/<topic>/SENSOR` power reading
+    pub struct TasmotaSubscriber {
+        options: MqttOptions,
+        topic: String,
+        latest: Arc<Mutex<Option<(f64, Instant)>>>,
+        started: Once,
+    }
+
+    impl TasmotaSubscriber {
+        pub fn new(config: &SmartPlugConfig) -> Self {
+            let mut options = MqttOptions::new("dagshield-node", config.host.clone(), config.port.unwrap_or(1883));
+            options.set_keep_alive(Duration::from_secs(30));
+            if let Some(username) = &config.username {
+                options.set_credentials(username.clone(), config.password.clone().unwrap_or_default());
+            }
+
+            Self {
+                options,
+                topic: format!("tele/{}/SENSOR", config.mqtt_topic.as_deref().unwrap_or("tasmota")),
+                latest: Arc::new(Mutex::new(None)),
+                started: Once::new(),
+            }
+        }
+
+        /// Start the subscription on first use (needs a running tokio runtime)
+        fn ensure_started(&self) {
+            self.started.call_once(|| {
+                let (client, mut eventloop) = AsyncClient::new(self.options.clone(), 10);
+                let topic = self.topic.clone();
+                let latest = self.latest.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) = client.subscribe(&topic, QoS::AtMostOnce).await {
+                        warn!("MQTT subscribe to {} failed: {}", topic, e);
+                    }
+                    loop {
+                        match eventloop.poll().await {
+                            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                                let power = serde_json::from_slice(&publish.payload)
+                                    .ok()
+                                    .and_then(|json| parse_tasmota(&json));
+                                if let Some(watts) = power {
+                                    *latest.lock().unwrap() = Some((watts, Instant::now()));
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                warn!("MQTT connection error: {}", e);
+                                tokio::time::sleep(Duration::from_secs(5)).await;
+                            }
+                        }
+                    }
+                });
+            });
+        }
+
+        /// Latest reading no older than `max_age`
+        pub fn latest(&self, max_age: Duration) -> Result<f64> {
+            self.ensure_started();
+            let latest = *self.latest.lock().unwrap();
+            latest
+                .filter(|(_, at)| at.elapsed() <= max_age)
+                .map(|(watts, _)| watts)
+                .context("No recent Tasmota MQTT power reading")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plug_replies() {
+        let tasmota: Value = serde_json::from_str(r#"{"StatusSNS":{"ENERGY":{"Total":1.2,"Power":[20,5]}}}"#).unwrap();
+        assert_eq!(parse_tasmota(&tasmota), Some(25.0));
+        let tele: Value = serde_json::from_str(r#"{"ENERGY":{"Power":42}}"#).unwrap();
+        assert_eq!(parse_tasmota(&tele), Some(42.0));
+
+        let shelly: Value = serde_json::from_str(r#"{"meters":[{"power":12.5,"is_valid":true}]}"#).unwrap();
+        assert_eq!(parse_shelly(&shelly), Some(12.5));
+        let rpc: Value = serde_json::from_str(r#"{"id":0,"apower":31.2}"#).unwrap();
+        assert_eq!(parse_shelly(&rpc), Some(31.2));
+
+        let kasa: Value = serde_json::from_str(r#"{"emeter":{"get_realtime":{"power_mw":15500,"err_code":0}}}"#).unwrap();
+        assert_eq!(parse_kasa(&kasa), Some(15.5));
+    }
+
+    #[test]
+    fn test_kasa_cipher_and_calibration() {
+        let message = br#"{"system":{"get_sysinfo":{}}}"#;
+        let encrypted = kasa_encrypt(message);
+        assert_eq!(&encrypted[..4], &(message.len() as u32).to_be_bytes());
+        assert_eq!(encrypted[4], 171 ^ b'{');
+        assert_eq!(kasa_decrypt(&encrypted[4..]), message);
+
+        let calibration = WallPowerCalibration::new(0.5);
+        calibration.observe(60.0, 50.0);
+        calibration.observe(50.0, 50.0);
+        assert!((calibration.scale().unwrap() - 1.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,225 @@
+/*!
+ * Per-subsystem energy attribution
+ * ZK proving, DAG submission, the detection engine and P2P networking record the thread CPU
+ * time of their work (per future poll or per blocking call); each sample splits the host's
+ * CPU power by those shares
+ */
+
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use super::attribution::HostPower;
+use super::EnergyData;
+
+/// Node subsystems energy is attributed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    ZkProving,
+    DagSubmission,
+    Detection,
+    P2p,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 4] = [Self::ZkProving, Self::DagSubmission, Self::Detection, Self::P2p];
+}
+
+/// CPU nanoseconds spent per subsystem since startup
+static CPU_NANOS: [AtomicU64; 4] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid, writable timespec
+    let rc = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+    (rc == 0).then(|| Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// CPU time of a stretch of work on one thread (wall time where thread clocks are unavailable)
+struct Span {
+    cpu: Option<Duration>,
+    wall: Instant,
+}
+
+impl Span {
+    fn start() -> Self {
+        Self {
+            cpu: thread_cpu_time(),
+            wall: Instant::now(),
+        }
+    }
+
+    fn finish(self, subsystem: Subsystem) {
+        let spent = match (self.cpu, thread_cpu_time()) {
+            (Some(start), Some(end)) => end.saturating_sub(start),
+            _ => self.wall.elapsed(),
+        };
+        CPU_NANOS[subsystem as usize].fetch_add(spent.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Run blocking work (e.g. proof generation) and charge its CPU time to `subsystem`
+pub fn measure<T>(subsystem: Subsystem, work: impl FnOnce() -> T) -> T {
+    let span = Span::start();
+    let result = work();
+    span.finish(subsystem);
+    result
+}
+
+/// Future whose polls are charged to a subsystem
+pub struct Instrumented<F> {
+    subsystem: Subsystem,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let span = Span::start();
+        let poll = self.inner.as_mut().poll(cx);
+        span.finish(self.subsystem);
+        poll
+    }
+}
+
+/// Charge the CPU time of `future` to `subsystem`
+pub fn instrument<F: Future>(subsystem: Subsystem, future: F) -> Instrumented<F> {
+    Instrumented {
+        subsystem,
+        inner: Box::pin(future),
+    }
+}
+
+/// Power of one subsystem at a reading
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SubsystemPower {
+    pub subsystem: Subsystem,
+    /// Fraction of total machine CPU capacity (0-1)
+    pub cpu_utilization: f64,
+    pub watts: f64,
+}
+
+/// Turns CPU time deltas into power between readings
+#[derive(Default)]
+pub struct SubsystemSampler {
+    last: Mutex<Option<(Instant, [u64; 4])>>,
+}
+
+impl SubsystemSampler {
+    /// Split `host` CPU power by subsystem CPU time since the previous call
+    pub fn sample(&self, host: &HostPower, cores: usize) -> Vec<SubsystemPower> {
+        let now = Instant::now();
+        let totals = CPU_NANOS.each_ref().map(|nanos| nanos.load(Ordering::Relaxed));
+        let Some((since, previous)) = self.last.lock().unwrap().replace((now, totals)) else {
+            return Vec::new();
+        };
+
+        let capacity_nanos = now.duration_since(since).as_nanos() as f64 * cores.max(1) as f64;
+        if capacity_nanos <= 0.0 {
+            return Vec::new();
+        }
+
+        Subsystem::ALL
+            .iter()
+            .map(|&subsystem| {
+                let index = subsystem as usize;
+                let cpu_utilization = totals[index].saturating_sub(previous[index]) as f64 / capacity_nanos;
+                let share = if host.cpu_utilization > 0.0 {
+                    (cpu_utilization / host.cpu_utilization).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                SubsystemPower {
+                    subsystem,
+                    cpu_utilization,
+                    watts: host.cpu_watts * share,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Energy per subsystem over a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemEnergy {
+    pub subsystem: Subsystem,
+    pub energy_kwh: f64,
+    /// Of the node's attributed energy (of the host's when the node is not attributed)
+    pub share_of_node: f64,
+}
+
+/// Per-subsystem energy for readings `hours_per_reading` apart
+pub fn breakdown(history: &[EnergyData], hours_per_reading: f64) -> Vec<SubsystemEnergy> {
+    let node_kwh: f64 = history
+        .iter()
+        .map(|reading| reading.node_process.as_ref().map_or(reading.total_watts, |process| process.total_watts))
+        .sum::<f64>()
+        * hours_per_reading
+        / 1000.0;
+
+    Subsystem::ALL
+        .iter()
+        .map(|&subsystem| {
+            let energy_kwh = history
+                .iter()
+                .flat_map(|reading| &reading.subsystems)
+                .filter(|power| power.subsystem == subsystem)
+                .map(|power| power.watts)
+                .sum::<f64>()
+                * hours_per_reading
+                / 1000.0;
+            SubsystemEnergy {
+                subsystem,
+                energy_kwh,
+                share_of_node: if node_kwh > 0.0 { energy_kwh / node_kwh } else { 0.0 },
+            }
+        })
+        .filter(|energy| energy.energy_kwh > 0.0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_time_is_charged_to_subsystem() {
+        let host = HostPower {
+            total_watts: 60.0,
+            cpu_watts: 40.0,
+            memory_watts: 5.0,
+            cpu_utilization: 1.0,
+            used_memory_bytes: 0,
+        };
+        let sampler = SubsystemSampler::default();
+        assert!(sampler.sample(&host, 1).is_empty());
+
+        measure(Subsystem::ZkProving, || {
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(50) {
+                std::hint::spin_loop();
+            }
+        });
+
+        let powers = sampler.sample(&host, 1);
+        let proving = powers.iter().find(|power| power.subsystem == Subsystem::ZkProving).unwrap();
+        assert!(proving.cpu_utilization > 0.5);
+        assert!(proving.watts > 20.0 && proving.watts <= 40.0);
+    }
+}
@@ -0,0 +1,193 @@
+/*!
+ * Thermal monitoring and throttling
+ * Samples CPU/GPU temperatures and throttles proof generation and batch processing while
+ * they exceed configured limits, so fanless edge devices do not cook themselves
+ */
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use sysinfo::{ComponentExt, System, SystemExt};
+
+use super::gpu::GpuReading;
+
+/// Sensor labels that belong to the CPU package or cores
+const CPU_SENSOR_LABELS: &[&str] = &["cpu", "package", "core", "tctl", "tdie", "k10temp", "coretemp", "soc"];
+/// Sensor labels that belong to a GPU
+const GPU_SENSOR_LABELS: &[&str] = &["gpu", "amdgpu", "nouveau", "nvidia"];
+
+/// Thermal limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalConfig {
+    pub enabled: bool,
+    pub cpu_limit_c: f64,
+    pub gpu_limit_c: f64,
+    /// Temperatures must drop this far below the limit before throttling ends
+    pub hysteresis_c: f64,
+    /// Fraction of the DAG batch size kept while throttled
+    pub batch_fraction: f64,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cpu_limit_c: 85.0,
+            gpu_limit_c: 83.0,
+            hysteresis_c: 5.0,
+            batch_fraction: 0.25,
+        }
+    }
+}
+
+/// Temperatures at the time of a reading
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalReading {
+    pub cpu_temp_c: Option<f64>,
+    pub gpu_temp_c: Option<f64>,
+    pub throttled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThermalEventKind {
+    ThrottleStarted,
+    ThrottleEnded,
+}
+
+/// Transition into or out of thermal throttling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalEvent {
+    pub timestamp: u64,
+    pub kind: ThermalEventKind,
+    pub cpu_temp_c: Option<f64>,
+    pub gpu_temp_c: Option<f64>,
+}
+
+/// Hottest sensor whose label matches one of `patterns`
+pub fn hottest_matching<'a>(
+    sensors: impl IntoIterator<Item = (&'a str, f32)>,
+    patterns: &[&str],
+) -> Option<f64> {
+    sensors
+        .into_iter()
+        .filter(|(label, temperature)| {
+            let label = label.to_lowercase();
+            temperature.is_finite() && patterns.iter().any(|pattern| label.contains(pattern))
+        })
+        .map(|(_, temperature)| temperature as f64)
+        .reduce(f64::max)
+}
+
+/// CPU and GPU temperatures; GPU backends take precedence over generic sensors
+pub fn read_temperatures(system: &System, gpus: &[GpuReading]) -> (Option<f64>, Option<f64>) {
+    let sensors: Vec<(&str, f32)> = system
+        .components()
+        .iter()
+        .map(|component| (component.label(), component.temperature()))
+        .collect();
+
+    let cpu = hottest_matching(sensors.iter().copied(), CPU_SENSOR_LABELS);
+    let gpu = gpus
+        .iter()
+        .filter_map(|gpu| gpu.temperature_c)
+        .reduce(f64::max)
+        .or_else(|| hottest_matching(sensors.iter().copied(), GPU_SENSOR_LABELS));
+
+    (cpu, gpu)
+}
+
+/// Tracks whether the node is thermally throttled
+pub struct ThermalGovernor {
+    config: ThermalConfig,
+    throttled: Mutex<bool>,
+}
+
+impl ThermalGovernor {
+    pub fn new(config: ThermalConfig) -> Self {
+        Self {
+            config,
+            throttled: Mutex::new(false),
+        }
+    }
+
+    pub fn config(&self) -> &ThermalConfig {
+        &self.config
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        *self.throttled.lock().unwrap()
+    }
+
+    /// Apply new temperatures; returns the reading and an event when the state changed
+    pub fn update(
+        &self,
+        cpu_temp_c: Option<f64>,
+        gpu_temp_c: Option<f64>,
+        timestamp: u64,
+    ) -> (ThermalReading, Option<ThermalEvent>) {
+        let over = |temp: Option<f64>, limit: f64| temp.is_some_and(|t| t >= limit);
+        let hysteresis = self.config.hysteresis_c;
+
+        let mut throttled = self.throttled.lock().unwrap();
+        let was_throttled = *throttled;
+
+        *throttled = self.config.enabled
+            && if was_throttled {
+                // Stay throttled until every sensor has cooled past the hysteresis band
+                over(cpu_temp_c, self.config.cpu_limit_c - hysteresis)
+                    || over(gpu_temp_c, self.config.gpu_limit_c - hysteresis)
+            } else {
+                over(cpu_temp_c, self.config.cpu_limit_c) || over(gpu_temp_c, self.config.gpu_limit_c)
+            };
+
+        let event = (*throttled != was_throttled).then(|| ThermalEvent {
+            timestamp,
+            kind: if *throttled {
+                ThermalEventKind::ThrottleStarted
+            } else {
+                ThermalEventKind::ThrottleEnded
+            },
+            cpu_temp_c,
+            gpu_temp_c,
+        });
+
+        (
+            ThermalReading {
+                cpu_temp_c,
+                gpu_temp_c,
+                throttled: *throttled,
+            },
+            event,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensor_matching() {
+        let sensors = [("k10temp Tctl", 71.0), ("amdgpu edge", 64.0), ("nvme Composite", 45.0)];
+        assert_eq!(hottest_matching(sensors, CPU_SENSOR_LABELS), Some(71.0));
+        assert_eq!(hottest_matching(sensors, GPU_SENSOR_LABELS), Some(64.0));
+        assert_eq!(hottest_matching([("acpitz", 40.0)], CPU_SENSOR_LABELS), None);
+    }
+
+    #[test]
+    fn test_throttle_hysteresis() {
+        let governor = ThermalGovernor::new(ThermalConfig::default());
+
+        let (reading, event) = governor.update(Some(90.0), None, 1);
+        assert!(reading.throttled);
+        assert_eq!(event.unwrap().kind, ThermalEventKind::ThrottleStarted);
+
+        // Below the limit but inside the hysteresis band: still throttled, no new event
+        let (reading, event) = governor.update(Some(82.0), None, 2);
+        assert!(reading.throttled);
+        assert!(event.is_none());
+
+        let (reading, event) = governor.update(Some(70.0), Some(60.0), 3);
+        assert!(!reading.throttled);
+        assert_eq!(event.unwrap().kind, ThermalEventKind::ThrottleEnded);
+    }
+}
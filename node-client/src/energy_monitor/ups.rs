@@ -0,0 +1,194 @@
+/*!
+ * UPS monitoring via Network UPS Tools
+ * Talks the NUT network protocol (upsd, TCP 3493) so nodes on a UPS report input power and
+ * load, and an on-battery UPS drives the same degradation policy as a discharging laptop
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+
+/// upsd connection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpsConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// UPS name as configured in ups.conf
+    pub ups_name: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for UpsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 3493,
+            ups_name: "ups".to_string(),
+            username: None,
+            password: None,
+            request_timeout_secs: 3,
+        }
+    }
+}
+
+/// UPS state from upsd variables
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpsStatus {
+    pub on_battery: bool,
+    pub low_battery: bool,
+    pub charging: bool,
+    pub battery_charge: Option<f64>,
+    pub load_percent: Option<f64>,
+    /// Real power drawn by the UPS load (`ups.realpower`, or load × nominal)
+    pub input_watts: Option<f64>,
+    pub runtime_secs: Option<u64>,
+}
+
+impl UpsStatus {
+    pub fn from_vars(vars: &HashMap<String, String>) -> Self {
+        let number = |name: &str| vars.get(name).and_then(|value| value.parse::<f64>().ok());
+        let flags: Vec<&str> = vars
+            .get("ups.status")
+            .map(|status| status.split_whitespace().collect())
+            .unwrap_or_default();
+        let load_percent = number("ups.load");
+
+        Self {
+            on_battery: flags.contains(&"OB"),
+            low_battery: flags.contains(&"LB"),
+            charging: flags.contains(&"CHRG"),
+            battery_charge: number("battery.charge"),
+            load_percent,
+            input_watts: number("ups.realpower").or_else(|| {
+                Some(load_percent? / 100.0 * number("ups.realpower.nominal")?)
+            }),
+            runtime_secs: number("battery.runtime").map(|secs| secs as u64),
+        }
+    }
+
+    /// Battery level, runtime and charging state in the laptop battery's terms
+    ///
+    /// `LB` is the UPS's own shutdown signal, so it reports an empty battery whatever the charge.
+    pub fn battery_info(&self) -> (Option<f64>, Option<Duration>, Option<bool>) {
+        let level = if self.on_battery && self.low_battery { Some(0.0) } else { self.battery_charge };
+        let runtime = self.runtime_secs.map(Duration::from_secs);
+        (level, runtime, Some(!self.on_battery))
+    }
+}
+
+/// Parse `VAR <ups> <name> "<value>"` lines of a `LIST VAR` reply
+pub fn parse_list_var(reply: &str) -> HashMap<String, String> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let rest = line.strip_prefix("VAR ")?;
+            let (_ups, rest) = rest.split_once(' ')?;
+            let (name, value) = rest.split_once(' ')?;
+            Some((name.to_string(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+/// Minimal NUT client
+pub struct NutClient {
+    config: UpsConfig,
+    on_battery: AtomicBool,
+}
+
+impl NutClient {
+    pub fn new(config: UpsConfig) -> Self {
+        Self {
+            config,
+            on_battery: AtomicBool::new(false),
+        }
+    }
+
+    /// Record the latest on-battery state, returning the previous one
+    pub fn swap_on_battery(&self, on_battery: bool) -> bool {
+        self.on_battery.swap(on_battery, Ordering::Relaxed)
+    }
+
+    /// Query every variable of the configured UPS
+    pub async fn status(&self) -> Result<UpsStatus> {
+        let limit = Duration::from_secs(self.config.request_timeout_secs);
+        let vars = timeout(limit, self.list_vars())
+            .await
+            .context("upsd timed out")??;
+        Ok(UpsStatus::from_vars(&vars))
+    }
+
+    async fn list_vars(&self) -> Result<HashMap<String, String>> {
+        let stream = TcpStream::connect((self.config.host.as_str(), self.config.port))
+            .await
+            .with_context(|| format!("Failed to connect to upsd at {}", self.config.host))?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            for command in [format!("USERNAME {}\n", username), format!("PASSWORD {}\n", password)] {
+                writer.write_all(command.as_bytes()).await?;
+                let reply = lines.next_line().await?.unwrap_or_default();
+                if reply.starts_with("ERR") {
+                    return Err(anyhow::anyhow!("upsd rejected credentials: {}", reply));
+                }
+            }
+        }
+
+        writer.write_all(format!("LIST VAR {}\n", self.config.ups_name).as_bytes()).await?;
+
+        let mut reply = String::new();
+        while let Some(line) = lines.next_line().await? {
+            if line.starts_with("ERR") {
+                return Err(anyhow::anyhow!("upsd error for {}: {}", self.config.ups_name, line));
+            }
+            if line.starts_with("END LIST VAR") {
+                break;
+            }
+            reply.push_str(&line);
+            reply.push('\n');
+        }
+
+        // Best effort; the connection is dropped either way
+        let _ = writer.write_all(b"LOGOUT\n").await;
+        Ok(parse_list_var(&reply))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_on_battery_status() {
+        let reply = "BEGIN LIST VAR ups\n\
+                     VAR ups battery.charge \"64\"\n\
+                     VAR ups battery.runtime \"1260\"\n\
+                     VAR ups ups.load \"23\"\n\
+                     VAR ups ups.realpower.nominal \"900\"\n\
+                     VAR ups ups.status \"OB DISCHRG\"\n";
+
+        let status = UpsStatus::from_vars(&parse_list_var(reply));
+        assert!(status.on_battery);
+        assert!(!status.low_battery);
+        assert_eq!(status.battery_charge, Some(64.0));
+        assert_eq!(status.runtime_secs, Some(1260));
+        assert!((status.input_watts.unwrap() - 207.0).abs() < 1e-9);
+        assert_eq!(status.battery_info().2, Some(false));
+
+        let vars = parse_list_var("VAR ups ups.status \"OB LB\"\nVAR ups battery.charge \"30\"\n");
+        assert_eq!(UpsStatus::from_vars(&vars).battery_info().0, Some(0.0));
+    }
+}
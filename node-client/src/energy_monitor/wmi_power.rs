@@ -0,0 +1,147 @@
+/*!
+ * Windows power sensors via WMI
+ * Reads package and per-rail power published by LibreHardwareMonitor (or OpenHardwareMonitor)
+ * into WMI; the monitor service must be running for sensors to appear
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// WMI namespaces checked, in order
+pub const WMI_NAMESPACES: [&str; 2] = ["root\\LibreHardwareMonitor", "root\\OpenHardwareMonitor"];
+
+/// One power sensor (rail) reported by the hardware monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerRail {
+    pub name: String,
+    /// Hardware monitor identifier, e.g. `/amdcpu/0/power/0` or `/gpu-nvidia/0/power/0`
+    pub identifier: String,
+    pub watts: f64,
+}
+
+/// Package/GPU power derived from the rail list
+#[derive(Debug, Clone, Default)]
+pub struct WmiPowerSample {
+    pub package_watts: Option<f64>,
+    pub gpu_watts: Option<f64>,
+    pub rails: Vec<PowerRail>,
+}
+
+/// Pick package and GPU totals out of the reported rails
+pub fn summarize_rails(rails: Vec<PowerRail>) -> WmiPowerSample {
+    let package_watts = rails
+        .iter()
+        .filter(|rail| rail.identifier.contains("cpu") && rail.name.contains("Package"))
+        .map(|rail| rail.watts)
+        .reduce(|a, b| a + b);
+
+    let gpu_watts = rails
+        .iter()
+        .filter(|rail| rail.identifier.starts_with("/gpu") && !rail.name.contains("Core"))
+        .map(|rail| rail.watts)
+        .reduce(|a, b| a + b);
+
+    WmiPowerSample {
+        package_watts,
+        gpu_watts,
+        rails,
+    }
+}
+
+#[cfg(windows)]
+mod backend {
+    use anyhow::{Context, Result};
+    use serde::Deserialize;
+    use wmi::{COMLibrary, WMIConnection};
+
+    use super::{summarize_rails, PowerRail, WmiPowerSample, WMI_NAMESPACES};
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Sensor {
+        name: String,
+        identifier: String,
+        value: f32,
+    }
+
+    fn query(namespace: &str) -> Result<Vec<PowerRail>> {
+        let com = COMLibrary::new()?;
+        let wmi = WMIConnection::with_namespace_path(namespace, com)?;
+        let sensors: Vec<Sensor> = wmi
+            .raw_query("SELECT Name, Identifier, Value FROM Sensor WHERE SensorType = 'Power'")?;
+
+        Ok(sensors
+            .into_iter()
+            .map(|sensor| PowerRail {
+                name: sensor.name,
+                identifier: sensor.identifier,
+                watts: sensor.value as f64,
+            })
+            .collect())
+    }
+
+    /// Query the first namespace that has power sensors (blocking COM calls)
+    pub fn sample_blocking() -> Result<WmiPowerSample> {
+        WMI_NAMESPACES
+            .iter()
+            .filter_map(|namespace| query(namespace).ok())
+            .find(|rails| !rails.is_empty())
+            .map(summarize_rails)
+            .context("No hardware monitor power sensors in WMI")
+    }
+}
+
+/// WMI power backend
+pub struct WmiPowerBackend;
+
+impl WmiPowerBackend {
+    /// Available on Windows when a hardware monitor publishes power sensors
+    #[cfg(windows)]
+    pub fn detect() -> Option<Self> {
+        backend::sample_blocking().ok().map(|_| Self)
+    }
+
+    #[cfg(not(windows))]
+    pub fn detect() -> Option<Self> {
+        None
+    }
+
+    /// Take one sample; COM calls run on the blocking pool
+    #[cfg(windows)]
+    pub async fn sample(&self) -> anyhow::Result<WmiPowerSample> {
+        tokio::task::spawn_blocking(backend::sample_blocking).await?
+    }
+
+    #[cfg(not(windows))]
+    pub async fn sample(&self) -> anyhow::Result<WmiPowerSample> {
+        Err(anyhow::anyhow!("WMI power sensors are only available on Windows"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rail(name: &str, identifier: &str, watts: f64) -> PowerRail {
+        PowerRail {
+            name: name.to_string(),
+            identifier: identifier.to_string(),
+            watts,
+        }
+    }
+
+    #[test]
+    fn test_summarize_rails() {
+        let sample = summarize_rails(vec![
+            rail("Package", "/amdcpu/0/power/0", 42.5),
+            rail("Core #1", "/amdcpu/0/power/1", 6.0),
+            rail("GPU Package", "/gpu-nvidia/0/power/0", 120.0),
+            rail("GPU Core", "/gpu-nvidia/0/power/1", 80.0),
+        ]);
+
+        assert_eq!(sample.package_watts, Some(42.5));
+        assert_eq!(sample.gpu_watts, Some(120.0));
+        assert_eq!(sample.rails.len(), 4);
+
+        assert!(summarize_rails(Vec::new()).package_watts.is_none());
+    }
+}
@@ -0,0 +1,22 @@
+//! DAGShield node library
+//!
+//! The node's components as a library, shared by the `dagshield-node` binary, the benchmarks and
+//! the mobile bindings (`uniffi` feature) that iOS and Android host apps link against.
+
+pub mod api;
+pub mod config;
+pub mod node;
+pub mod dag;
+pub mod ai;
+pub mod detection;
+pub mod blockchain;
+pub mod network;
+pub mod energy;
+pub mod energy_monitor;
+pub mod metrics;
+pub mod storage;
+pub mod u2u_integration;
+pub mod zk_prover;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
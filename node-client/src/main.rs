@@ -4,23 +4,21 @@
 //! Handles DAG processing, AI threat detection, blockchain interaction, and energy monitoring.
 
 use anyhow::Result;
-use clap::Parser;
-use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use futures::FutureExt;
+use std::{path::PathBuf, sync::Arc};
 use tokio::signal;
 use tracing::{info, error};
 
-mod config;
-mod node;
-mod dag;
-mod ai;
-mod blockchain;
-mod network;
-mod energy;
-mod metrics;
-mod storage;
+use dagshield_node::{config, detection, node, u2u_integration::U2UClient, zk_prover};
 
 use config::NodeConfig;
 use node::DAGShieldNode;
+use zk_prover::{benchmark::TrackingAllocator, PASSPHRASE_ENV};
+
+/// Counts heap growth for `zk-benchmark`
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
 
 #[derive(Parser)]
 #[command(name = "dagshield-node")]
@@ -45,6 +43,91 @@ struct Cli {
     /// Run in benchmark mode
     #[arg(long)]
     benchmark: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Phase-2 trusted setup ceremony for the threat circuit
+    #[command(subcommand)]
+    Ceremony(CeremonyCommand),
+    /// Prove and verify every ZK circuit with the configured backend and print the results as JSON
+    ZkBenchmark {
+        /// Proofs per circuit
+        #[arg(short, long, default_value_t = 5)]
+        iterations: usize,
+        /// Also report constraints, witnesses and proving time per named circuit region
+        #[arg(long)]
+        profile: bool,
+    },
+    /// Prove threat proofs for low-power nodes on `zk.relay.listen`
+    ZkRelay,
+    /// Score URLs or domains for phishing and print the analyses as JSON
+    CheckUrl {
+        urls: Vec<String>,
+    },
+    /// Label a reported detection as a false positive (stop the node first, it holds the store;
+    /// a running node labels over the API)
+    MarkFalsePositive {
+        /// On-chain id of the report: its alert id, or a proven threat's hash
+        threat_hash: ethers::types::H256,
+        #[arg(short, long)]
+        note: Option<String>,
+    },
+    /// Print each detector's precision per period as JSON
+    DetectorPrecision {
+        #[arg(short, long, default_value_t = 7)]
+        period_days: i64,
+    },
+    /// Print the JSON Schema of exported threat reports
+    ThreatReportSchema,
+    /// Export recorded detections as threat reports (stop the node first, it holds the store)
+    ExportReports {
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Jsonl)]
+        format: ReportFormat,
+        /// Written to stdout if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Only detections from the last this many days
+        #[arg(long)]
+        since_days: Option<i64>,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ReportFormat {
+    Jsonl,
+    Sarif,
+}
+
+#[derive(Subcommand)]
+enum CeremonyCommand {
+    /// Start a transcript from a Powers of Tau file (coordinator only)
+    Init {
+        ptau: PathBuf,
+        #[arg(short, long, default_value = "phase2_transcript.bin")]
+        output: PathBuf,
+    },
+    /// Download the coordinator's current transcript
+    Download {
+        url: String,
+        #[arg(short, long, default_value = "phase2_transcript.bin")]
+        output: PathBuf,
+    },
+    /// Apply a contribution to a transcript in place and print its hash
+    Contribute {
+        transcript: PathBuf,
+        /// Extra entropy mixed with OS randomness
+        #[arg(long, default_value = "")]
+        entropy: String,
+    },
+    /// Verify every contribution against keys derived from a Powers of Tau file
+    Verify {
+        transcript: PathBuf,
+        ptau: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -57,6 +140,23 @@ async fn main() -> Result<()> {
         .with_env_filter(format!("dagshield_node={},warn", log_level))
         .init();
     
+    match cli.command {
+        Some(Command::Ceremony(command)) => return run_ceremony(command).await,
+        Some(Command::ZkBenchmark { iterations, profile }) => return run_zk_benchmark(&cli.config, iterations, profile).await,
+        Some(Command::ZkRelay) => return run_zk_relay(&cli.config).await,
+        Some(Command::CheckUrl { urls }) => return run_check_url(&cli.config, &urls).await,
+        Some(Command::MarkFalsePositive { threat_hash, note }) => return run_mark_false_positive(&cli.config, threat_hash, note),
+        Some(Command::DetectorPrecision { period_days }) => return run_detector_precision(&cli.config, period_days),
+        Some(Command::ThreatReportSchema) => {
+            println!("{}", serde_json::to_string_pretty(&detection::report::schema())?);
+            return Ok(());
+        }
+        Some(Command::ExportReports { format, output, since_days }) => {
+            return run_export_reports(&cli.config, cli.node_id.as_deref(), format, output, since_days);
+        }
+        None => {}
+    }
+
     info!("🛡️ Starting DAGShield Node Client v{}", env!("CARGO_PKG_VERSION"));
     
     // Load configuration
@@ -107,6 +207,133 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+async fn run_ceremony(command: CeremonyCommand) -> Result<()> {
+    use zk_prover::ceremony::{download_transcript, initial_parameters, Phase2Transcript};
+
+    match command {
+        CeremonyCommand::Init { ptau, output } => {
+            Phase2Transcript::new(initial_parameters(&std::fs::read(&ptau)?)?)?.save(&output)?;
+            info!("💾 Transcript saved to {}", output.display());
+        }
+        CeremonyCommand::Download { url, output } => {
+            download_transcript(&url, &output).await?;
+            info!("💾 Transcript saved to {}", output.display());
+        }
+        CeremonyCommand::Contribute { transcript: path, entropy } => {
+            let mut transcript = Phase2Transcript::load(&path)?;
+            let hash = transcript.contribute(entropy.as_bytes())?;
+            transcript.save(&path)?;
+            info!("📣 Publish your contribution hash: {}", hex::encode(hash));
+        }
+        CeremonyCommand::Verify { transcript, ptau } => {
+            let ptau = std::fs::read(&ptau)?;
+            info!("🔎 Powers of Tau hash: {}", hex::encode(zk_prover::ptau::transcript_hash(&ptau)));
+            let hashes = Phase2Transcript::load(&transcript)?.verify(&initial_parameters(&ptau)?)?;
+            for (i, hash) in hashes.iter().enumerate() {
+                info!("   #{}: {}", i + 1, hex::encode(hash));
+            }
+            info!("✅ Transcript valid with {} contributions", hashes.len());
+        }
+    }
+    Ok(())
+}
+
+async fn run_zk_benchmark(config_path: &str, iterations: usize, profile: bool) -> Result<()> {
+    let config = NodeConfig::load(config_path)?;
+    let mut prover = zk_prover::ZKProver::with_config(config.zk);
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        prover.set_passphrase(passphrase);
+    }
+    prover.initialize().await?;
+
+    let benchmark = prover.benchmark(iterations, profile).await?;
+    println!("{}", serde_json::to_string_pretty(&benchmark)?);
+    Ok(())
+}
+
+async fn run_zk_relay(config_path: &str) -> Result<()> {
+    let config = NodeConfig::load(config_path)?;
+    let listen = config.zk.relay.listen.ok_or_else(|| anyhow::anyhow!("No relay address configured (zk.relay.listen)"))?;
+    // Requesters are authenticated against their stake in the node registry
+    let u2u = config.u2u.clone().ok_or_else(|| anyhow::anyhow!("A relay prover needs the node registry (u2u)"))?;
+    let client = Arc::new(U2UClient::new(u2u).await?);
+    let stakes: zk_prover::relay::StakeLookup = Arc::new(move |node_id: String| {
+        let client = Arc::clone(&client);
+        async move { client.get_node_stake(&node_id).await }.boxed()
+    });
+
+    let mut prover = zk_prover::ZKProver::with_config(config.zk);
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        prover.set_passphrase(passphrase);
+    }
+    prover.initialize().await?;
+
+    zk_prover::relay::serve(Arc::new(prover), listen, stakes).await
+}
+
+async fn run_check_url(config_path: &str, urls: &[String]) -> Result<()> {
+    let config = NodeConfig::load(config_path)?;
+    let detector = detection::phishing::PhishingDetector::new(config.detection.phishing)?;
+
+    let mut analyses = Vec::with_capacity(urls.len());
+    for url in urls {
+        analyses.push(detector.analyze(url).await?);
+    }
+    println!("{}", serde_json::to_string_pretty(&analyses)?);
+    Ok(())
+}
+
+fn run_mark_false_positive(config_path: &str, threat_hash: ethers::types::H256, note: Option<String>) -> Result<()> {
+    let config = NodeConfig::load(config_path)?;
+    let store = detection::feedback::FeedbackStore::open(config.detection.feedback)?;
+    let record = store.mark_false_positive(&threat_hash, note)?;
+    println!("{}", serde_json::to_string_pretty(&record)?);
+    Ok(())
+}
+
+fn run_detector_precision(config_path: &str, period_days: i64) -> Result<()> {
+    let config = NodeConfig::load(config_path)?;
+    let store = detection::feedback::FeedbackStore::open(config.detection.feedback)?;
+    println!("{}", serde_json::to_string_pretty(&store.precision_report(period_days * 86_400)?)?);
+    Ok(())
+}
+
+fn run_export_reports(
+    config_path: &str,
+    node_id: Option<&str>,
+    format: ReportFormat,
+    output: Option<PathBuf>,
+    since_days: Option<i64>,
+) -> Result<()> {
+    use detection::report::{to_sarif, write_jsonl, ThreatReport};
+    use std::io::Write;
+
+    let config = NodeConfig::load(config_path)?;
+    let store = detection::feedback::FeedbackStore::open(config.detection.feedback)?;
+    let cutoff = since_days.map_or(i64::MIN, |days| chrono::Utc::now().timestamp() - days * 86_400);
+    let mut records = store.records()?;
+    records.retain(|record| record.detected_at >= cutoff);
+    records.sort_by_key(|record| record.detected_at);
+    let reports: Vec<ThreatReport> = records
+        .iter()
+        .map(|record| ThreatReport::from_record(record, node_id.unwrap_or("unknown"), config.blockchain.chain_id))
+        .collect();
+
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    match format {
+        ReportFormat::Jsonl => write_jsonl(&reports, &mut out)?,
+        ReportFormat::Sarif => serde_json::to_writer_pretty(&mut out, &to_sarif(&reports))?,
+    }
+    out.flush()?;
+    if let Some(path) = output {
+        info!("💾 Exported {} threat reports to {}", reports.len(), path.display());
+    }
+    Ok(())
+}
+
 async fn run_benchmark(node: &Arc<DAGShieldNode>) -> Result<()> {
     use std::time::Instant;
     
@@ -0,0 +1,195 @@
+//! Prometheus metrics exporter for DAGShield node
+//!
+//! Serves `/metrics` over HTTP and exposes energy readings, energy statistics, U2U
+//! transaction metrics, threat detectors, host threats and the ZK proof queue as gauges/counters labelled with
+//! `node_id` and `device_type`.
+
+use anyhow::{Context, Result};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use std::{net::SocketAddr, sync::RwLock};
+use tracing::info;
+
+use ::metrics::{counter, gauge, Label};
+
+use crate::ai::ThreatDetectionResult;
+use crate::config::MetricsConfig;
+use crate::detection::registry::DetectorStats;
+use crate::energy_monitor::{EnergyData, EnergyStats};
+use crate::u2u_integration::{DeviceType, U2UMetrics};
+use crate::zk_prover::queue::ProofQueueStats;
+
+pub struct MetricsCollector {
+    config: MetricsConfig,
+    labels: RwLock<Vec<Label>>,
+}
+
+impl MetricsCollector {
+    pub async fn new(config: &MetricsConfig) -> Result<Self> {
+        Ok(Self {
+            config: config.clone(),
+            labels: RwLock::new(vec![
+                Label::new("node_id", "unknown"),
+                Label::new("device_type", "unknown"),
+            ]),
+        })
+    }
+
+    /// Set the labels attached to every exported series
+    pub fn set_node_labels(&self, node_id: &str, device_type: Option<&DeviceType>) {
+        let device_type = device_type
+            .map(|d| format!("{:?}", d).to_lowercase())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        *self.labels.write().unwrap() = vec![
+            Label::new("node_id", node_id.to_string()),
+            Label::new("device_type", device_type),
+        ];
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        self.labels.read().unwrap().clone()
+    }
+
+    /// Serve the Prometheus endpoint until the task is aborted
+    pub async fn start(&self) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let addr = SocketAddr::from(([0, 0, 0, 0], self.config.port));
+        let (recorder, exporter) = PrometheusBuilder::new()
+            .with_http_listener(addr)
+            .build()
+            .context("Failed to build Prometheus exporter")?;
+        ::metrics::set_global_recorder(recorder)
+            .context("Metrics recorder already installed")?;
+
+        info!("📈 Prometheus metrics available at http://{}/metrics", addr);
+
+        exporter.await.map_err(|e| anyhow::anyhow!("Prometheus exporter stopped: {:?}", e))
+    }
+
+    /// Export a single energy reading
+    pub fn record_energy(&self, data: &EnergyData) {
+        let labels = self.labels();
+
+        gauge!("dagshield_power_watts", labels.clone()).set(data.total_watts);
+        for (component, watts) in [
+            ("cpu", data.cpu_watts),
+            ("gpu", data.gpu_watts),
+            ("memory", data.memory_watts),
+            ("network", data.network_watts),
+            ("npu", data.npu_watts),
+        ] {
+            let mut component_labels = labels.clone();
+            component_labels.push(Label::new("component", component));
+            gauge!("dagshield_component_power_watts", component_labels).set(watts);
+        }
+
+        gauge!("dagshield_efficiency_score", labels.clone()).set(data.efficiency_score as f64);
+        gauge!("dagshield_carbon_kg_per_hour", labels.clone()).set(data.carbon_footprint_kg_per_hour);
+
+        if let Some(level) = data.battery_level {
+            gauge!("dagshield_battery_level_percent", labels.clone()).set(level);
+        }
+        if let Some(charging) = data.is_charging {
+            gauge!("dagshield_battery_charging", labels.clone()).set(if charging { 1.0 } else { 0.0 });
+        }
+
+        for gpu in &data.gpus {
+            let mut gpu_labels = labels.clone();
+            gpu_labels.push(Label::new("gpu", gpu.index.to_string()));
+            gpu_labels.push(Label::new("vendor", gpu.vendor.clone()));
+            gauge!("dagshield_gpu_power_watts", gpu_labels.clone()).set(gpu.power_watts);
+            if let Some(temperature) = gpu.temperature_c {
+                gauge!("dagshield_gpu_temperature_celsius", gpu_labels).set(temperature);
+            }
+        }
+
+        if let Some(node) = &data.node_process {
+            gauge!("dagshield_node_process_power_watts", labels).set(node.total_watts);
+        }
+    }
+
+    /// Export aggregated energy statistics
+    pub fn record_energy_stats(&self, stats: &EnergyStats) {
+        let labels = self.labels();
+
+        gauge!("dagshield_energy_avg_power_watts", labels.clone()).set(stats.avg_power_watts);
+        gauge!("dagshield_energy_min_power_watts", labels.clone()).set(stats.min_power_watts);
+        gauge!("dagshield_energy_max_power_watts", labels.clone()).set(stats.max_power_watts);
+        gauge!("dagshield_energy_total_kwh", labels.clone()).set(stats.total_energy_kwh);
+        gauge!("dagshield_energy_total_carbon_kg", labels.clone()).set(stats.total_carbon_kg);
+        gauge!("dagshield_energy_uptime_hours", labels).set(stats.uptime_hours);
+    }
+
+    /// Export U2U transaction metrics
+    pub fn record_u2u(&self, metrics: &U2UMetrics) {
+        let labels = self.labels();
+
+        counter!("dagshield_u2u_transactions_total", labels.clone()).absolute(metrics.total_transactions);
+        counter!("dagshield_u2u_transactions_successful_total", labels.clone())
+            .absolute(metrics.successful_transactions);
+        counter!("dagshield_u2u_transactions_failed_total", labels.clone())
+            .absolute(metrics.failed_transactions);
+        gauge!("dagshield_u2u_avg_confirmation_seconds", labels.clone())
+            .set(metrics.avg_confirmation_time.as_secs_f64());
+        gauge!("dagshield_u2u_dag_efficiency", labels.clone()).set(metrics.dag_efficiency);
+        gauge!("dagshield_u2u_parallel_ratio", labels.clone()).set(metrics.parallel_processing_ratio);
+        gauge!("dagshield_u2u_gas_savings", labels).set(metrics.gas_savings);
+    }
+
+    /// Export ZK proof queue depth and drop counts
+    pub fn record_proof_queue(&self, stats: &ProofQueueStats) {
+        let labels = self.labels();
+
+        gauge!("dagshield_zk_queue_depth", labels.clone()).set(stats.queued as f64);
+        gauge!("dagshield_zk_queue_running", labels.clone()).set(stats.running as f64);
+        gauge!("dagshield_zk_queue_in_flight", labels.clone()).set(stats.in_flight as f64);
+        gauge!("dagshield_zk_queue_backlog_ratio", labels.clone()).set(stats.backlog());
+        counter!("dagshield_zk_queue_coalesced_total", labels.clone()).absolute(stats.coalesced);
+        counter!("dagshield_zk_queue_shed_total", labels.clone()).absolute(stats.shed);
+        counter!("dagshield_zk_queue_expired_total", labels).absolute(stats.expired);
+    }
+
+    /// Count a threat found on the node's own host, like a cryptominer raising power draw
+    pub fn record_host_threat(&self, threat: &ThreatDetectionResult) {
+        let mut labels = self.labels();
+        labels.push(Label::new("threat_type", threat.threat_type.clone()));
+
+        counter!("dagshield_host_threats_total", labels).increment(1);
+    }
+
+    /// Export run, detection and failure counts and latency per threat detector
+    pub fn record_detectors(&self, detectors: &[DetectorStats]) {
+        for stats in detectors {
+            let mut labels = self.labels();
+            labels.push(Label::new("detector", stats.name.clone()));
+
+            gauge!("dagshield_detector_enabled", labels.clone()).set(stats.enabled as u8 as f64);
+            counter!("dagshield_detector_runs_total", labels.clone()).absolute(stats.runs);
+            counter!("dagshield_detector_detections_total", labels.clone()).absolute(stats.detections);
+            counter!("dagshield_detector_errors_total", labels.clone()).absolute(stats.errors);
+            counter!("dagshield_detector_timeouts_total", labels.clone()).absolute(stats.timeouts);
+            gauge!("dagshield_detector_avg_latency_ms", labels).set(stats.avg_latency_ms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_node_labels() {
+        let config = MetricsConfig { enabled: false, port: 0, export_interval_secs: 60 };
+        let collector = MetricsCollector::new(&config).await.unwrap();
+        assert_eq!(collector.labels()[1], Label::new("device_type", "unknown"));
+
+        collector.set_node_labels("node-1", Some(&DeviceType::EdgeDevice));
+        assert_eq!(
+            collector.labels(),
+            vec![Label::new("node_id", "node-1"), Label::new("device_type", "edgedevice")]
+        );
+    }
+}
@@ -1,19 +1,44 @@
 //! Core DAGShield node implementation
 
 use anyhow::Result;
-use std::sync::Arc;
-use tokio::sync::{RwLock, mpsc};
+use ethers::providers::{Http, Provider};
+use ethers::types::H256;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast::error::RecvError, RwLock, mpsc};
 use tracing::{info, warn, error, debug};
 use uuid::Uuid;
 
+use crate::api::{self, ApiState, LocalDetector};
 use crate::config::NodeConfig;
-use crate::dag::DAGProcessor;
+use crate::dag::{DAGProcessor, Transaction};
 use crate::ai::ThreatDetector;
+use crate::detection::{
+    approvals::ApprovalDrainerDetector, bytecode::BytecodeAnalyzer, calibration::OutcomeTracker,
+    drainer_kits::{DrainerKitDetector, DrainerKits}, ensemble::Ensemble,
+    events::{ThreatEvent, ThreatEvents}, honeypot::{HoneypotDetector, HoneypotSimulator}, feedback::FeedbackStore, intel::IntelIngester, mempool::MempoolAnalyzer,
+    model_updates::ModelManager, phishing::PhishingDetector, quarantine::Quarantine,
+    registry::DetectorRegistry, reputation::ReputationStore, rug_pull::RugPullMonitor, rules::RulesEngine, scoring::{detector_families, ScoringModel}, signature_db::SignatureDb,
+    Detection, ThreatCategory,
+};
 use crate::blockchain::BlockchainClient;
 use crate::network::NetworkManager;
 use crate::energy::EnergyMonitor;
+use crate::energy_monitor::{
+    budget::{BudgetAction, BudgetEvent},
+    shutdown::{LowBatteryShutdownConfig, ShutdownHook},
+    subsystems::{self, Subsystem},
+    EnergyMonitor as PowerMonitor,
+};
 use crate::metrics::MetricsCollector;
 use crate::storage::NodeStorage;
+use crate::u2u_integration::{
+    chain_tracker::ChainTracker,
+    dedupe::{threat_hash, DedupeConfig, DuplicateDetection, ReplayDeduper},
+    heartbeat::NodeHeartbeat,
+    U2UClient,
+};
+use crate::zk_prover::{ZKProver, PASSPHRASE_ENV};
 
 #[derive(Debug, Clone)]
 pub struct NodeStats {
@@ -37,9 +62,27 @@ pub struct DAGShieldNode {
     config: NodeConfig,
     dag_processor: Arc<DAGProcessor>,
     threat_detector: Option<Arc<ThreatDetector>>,
+    quarantine: Option<Arc<Quarantine>>,
+    /// URL checks served over the API (None when `detection.phishing` is off)
+    phishing: Option<Arc<PhishingDetector>>,
+    /// Sandwich detection over the pending pool (None when `detection.mempool` is off)
+    mempool: Option<Arc<MempoolAnalyzer>>,
+    /// Threat feed indicators (None when `detection.intel` is off)
+    intel: Option<Arc<IntelIngester>>,
+    events: Option<ThreatEvents>,
     blockchain_client: Arc<BlockchainClient>,
+    u2u: Option<Arc<U2UClient>>,
+    /// Detections reported per (transaction, category) and the chain they are anchored to, shared
+    /// with the U2U client (None when its fork-aware dedupe is off)
+    replays: Option<(Arc<std::sync::RwLock<ReplayDeduper>>, Arc<std::sync::RwLock<ChainTracker>>)>,
     network_manager: Arc<NetworkManager>,
     energy_monitor: Arc<EnergyMonitor>,
+    /// Measured power split across the node's subsystems (None when disabled)
+    power_monitor: Option<Arc<PowerMonitor>>,
+    /// Last threshold each energy budget crossed in its current period
+    budget_events: Arc<Mutex<HashMap<String, BudgetEvent>>>,
+    /// Proofs for detections and energy reports (None when `zk.enabled` is off)
+    zk_prover: Option<Arc<ZKProver>>,
     metrics_collector: Arc<MetricsCollector>,
     storage: Arc<NodeStorage>,
     stats: Arc<RwLock<NodeStats>>,
@@ -62,25 +105,147 @@ impl DAGShieldNode {
         // Initialize DAG processor
         let dag_processor = Arc::new(DAGProcessor::new(&config).await?);
         
+        // Threat events only have subscribers through the API
+        let events = config.api.enabled.then(|| ThreatEvents::new(config.api.event_buffer));
+        
+        let intel = if config.detection.intel.enabled {
+            Some(Arc::new(IntelIngester::open(config.detection.intel.clone())?))
+        } else {
+            None
+        };
+        
         // Initialize AI threat detector (optional)
         let threat_detector = if enable_ai {
-            Some(Arc::new(ThreatDetector::new(&config.ai).await?))
+            let mut detector = ThreatDetector::new(&config.ai).await?;
+            let provider = Arc::new(Provider::<Http>::try_from(config.blockchain.rpc_url.as_str())?);
+            let detection = &config.detection;
+            let mut detectors = DetectorRegistry::new(detection.registry.clone());
+            if detection.rules.enabled {
+                let rules = Arc::new(RulesEngine::load(detection.rules.clone())?);
+                tokio::spawn(Arc::clone(&rules).watch());
+                detectors.register(rules);
+            }
+            let drainer_kits = if detection.drainer_kits.enabled {
+                Some(Arc::new(DrainerKits::open(detection.drainer_kits.clone())?))
+            } else {
+                None
+            };
+            if detection.signatures.enabled {
+                let mut signatures = SignatureDb::open(detection.signatures.clone())?;
+                if let Some(kits) = &drainer_kits {
+                    signatures = signatures.with_drainer_kits(Arc::clone(kits));
+                }
+                let signatures = Arc::new(signatures);
+                tokio::spawn(Arc::clone(&signatures).watch(provider.clone()));
+                detectors.register(signatures);
+            } else if drainer_kits.is_some() {
+                warn!("⚠️ Drainer kit fingerprints are synced with detection.signatures; only ones already synced are used");
+            }
+            if let Some(kits) = drainer_kits {
+                detectors.register(DrainerKitDetector::new(kits, provider.clone()));
+            }
+            if detection.approvals.enabled {
+                detectors.register(ApprovalDrainerDetector::new(detection.approvals.clone(), (*provider).clone()));
+            }
+            if detection.bytecode.enabled {
+                detectors.register(BytecodeAnalyzer::new(detection.bytecode.clone(), provider.clone()));
+            }
+            if detection.rug_pull.enabled {
+                detectors.register(RugPullMonitor::new(detection.rug_pull.clone(), provider.clone()));
+            }
+            if detection.honeypot.enabled {
+                match HoneypotSimulator::connect(detection.honeypot.clone()).await {
+                    Ok(simulator) => detectors.register(HoneypotDetector::new(simulator)),
+                    Err(e) => warn!("⚠️ Honeypot simulation disabled: {:#}", e),
+                }
+            }
+            if let Some(intel) = &intel {
+                detectors.register(Arc::clone(intel));
+            }
+            if detection.ensemble.enabled {
+                detectors.register(Ensemble::load(detection.ensemble.clone())?);
+            }
+            if detection.reputation.enabled {
+                let reputation = Arc::new(ReputationStore::open(detection.reputation.clone())?);
+                tokio::spawn(Arc::clone(&reputation).watch(provider.clone()));
+                detectors.register(Arc::clone(&reputation));
+                detector = detector.with_reputation(reputation);
+            }
+            if !detectors.is_empty() {
+                detector = detector.with_detectors(Arc::new(detectors));
+            }
+            
+            let scoring = ScoringModel::new(config.detection.scoring.clone());
+            if config.detection.feedback.enabled {
+                let feedback = Arc::new(FeedbackStore::open(config.detection.feedback.clone())?);
+                tokio::spawn(Arc::clone(&feedback).watch(provider.clone(), scoring.clone(), config.detection.scoring.clone()));
+                if config.detection.calibration.enabled && config.detection.calibration.oracle.is_zero() {
+                    warn!("⚠️ Confidence calibration needs detection.calibration.oracle to submit reports to");
+                } else if config.detection.calibration.enabled {
+                    let mut tracker = OutcomeTracker::new(config.detection.calibration.clone(), Arc::clone(&feedback), provider);
+                    if let Some(events) = &events {
+                        tracker = tracker.with_events(events.clone());
+                    }
+                    tokio::spawn(tracker.run(scoring.clone()));
+                }
+                detector = detector.with_feedback(feedback);
+            } else if config.detection.calibration.enabled {
+                warn!("⚠️ Confidence calibration needs detection.feedback enabled to track outcomes");
+            }
+            detector = detector.with_scoring(scoring);
+            let detector = Arc::new(detector);
+            if config.detection.model_updates.enabled {
+                let manager = ModelManager::new(
+                    config.detection.model_updates.clone(),
+                    provider.clone(),
+                    Arc::clone(&detector),
+                    &config.ai.model_path,
+                )?;
+                tokio::spawn(manager.run());
+            }
+            Some(detector)
         } else {
             None
         };
         
+        let quarantine = if config.detection.quarantine.enabled {
+            Some(Arc::new(Quarantine::open(config.detection.quarantine.clone())?))
+        } else {
+            None
+        };
+        let phishing = if config.detection.phishing.enabled {
+            Some(Arc::new(PhishingDetector::new(config.detection.phishing.clone())?))
+        } else {
+            None
+        };
+        let mempool = config.detection.mempool.enabled.then(|| Arc::new(MempoolAnalyzer::new(config.detection.mempool.clone())));
+        
         // Initialize blockchain client
         let blockchain_client = Arc::new(BlockchainClient::new(&config.blockchain).await?);
         
+        // Initialize U2U DAG client (optional)
+        let u2u = match &config.u2u {
+            Some(u2u_config) => Some(Arc::new(U2UClient::new(u2u_config.clone()).await?)),
+            None => None,
+        };
+        
+        // Reports through the client and through the node suppress each other's replays; without a
+        // client no chain is tracked, so reports are only deduplicated within the window
+        let replays = match (&config.u2u, &u2u) {
+            (Some(u2u_config), Some(client)) => (u2u_config.dedupe.enabled && u2u_config.dedupe.fork_aware)
+                .then(|| (Arc::clone(&client.replay_deduper), Arc::clone(&client.chain_tracker))),
+            _ => Some((
+                Arc::new(std::sync::RwLock::new(ReplayDeduper::new(DedupeConfig::default().window_secs))),
+                Arc::new(std::sync::RwLock::new(ChainTracker::new(1, 0))),
+            )),
+        };
+        
         // Initialize network manager
         let network_manager = Arc::new(NetworkManager::new(&config.network, &node_id).await?);
         
         // Initialize energy monitor
         let energy_monitor = Arc::new(EnergyMonitor::new(&config.energy).await?);
         
-        // Initialize metrics collector
-        let metrics_collector = Arc::new(MetricsCollector::new(&config.metrics).await?);
-        
         let stats = Arc::new(RwLock::new(NodeStats {
             threats_detected: 0,
             challenges_completed: 0,
@@ -89,14 +254,72 @@ impl DAGShieldNode {
             uptime_seconds: 0,
         }));
         
+        // Measured power, attributed to the subsystems instrumented in `start`
+        let power_monitor = if config.energy_monitor.enabled {
+            let mut monitor = PowerMonitor::from_config(config.energy_monitor.clone())?;
+            if let Some(client) = &u2u {
+                monitor = monitor.with_shutdown_hook(Self::shutdown_hook(
+                    Arc::clone(client),
+                    node_id.clone(),
+                    Arc::clone(&stats),
+                    config.energy_monitor.shutdown.clone(),
+                ));
+            }
+            Some(Arc::new(monitor))
+        } else {
+            None
+        };
+        let budget_events = Arc::new(Mutex::new(HashMap::new()));
+        if let Some(monitor) = &power_monitor {
+            let budget_events = Arc::clone(&budget_events);
+            monitor.on_budget_event(Arc::new(move |event: &BudgetEvent| {
+                budget_events.lock().unwrap().insert(event.budget.clone(), event.clone());
+            }));
+        }
+        
+        // Initialize ZK prover; keys are loaded or generated here, before the node starts
+        let zk_prover = if config.zk.enabled {
+            let mut prover = ZKProver::with_config(config.zk.clone());
+            if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+                prover.set_passphrase(passphrase);
+            }
+            if let Some(client) = &u2u {
+                prover.set_relay_operator(client.wallet.clone());
+            }
+            prover.initialize().await?;
+            if let Some(client) = &u2u {
+                match client.get_model_commitment().await {
+                    Ok(commitment) => prover.set_registered_model_commitment(commitment)?,
+                    Err(e) => warn!("⚠️ Could not read the registered model commitment: {}", e),
+                }
+            }
+            Some(Arc::new(prover))
+        } else {
+            None
+        };
+        
+        // Initialize metrics collector
+        let metrics_collector = Arc::new(MetricsCollector::new(&config.metrics).await?);
+        metrics_collector.set_node_labels(&node_id, Some(&config.energy_monitor.activity.device_type));
+        
         Ok(Self {
             node_id,
             config,
             dag_processor,
             threat_detector,
+            quarantine,
+            phishing,
+            mempool,
+            intel,
+            events,
             blockchain_client,
+            u2u,
+            replays,
             network_manager,
             energy_monitor,
+            power_monitor,
+            budget_events,
+            zk_prover,
             metrics_collector,
             storage,
             stats,
@@ -110,6 +333,18 @@ impl DAGShieldNode {
         // Register node on blockchain
         self.register_on_blockchain().await?;
         
+        // Pick up DAG transactions left by a low-battery shutdown and return to service
+        if let Some(client) = &self.u2u {
+            let tx_pool_path = &self.config.energy_monitor.shutdown.tx_pool_path;
+            if std::path::Path::new(tx_pool_path).exists() {
+                client.restore_tx_pool(tx_pool_path)?;
+                std::fs::remove_file(tx_pool_path)?;
+                if let Err(e) = client.set_node_maintenance(&self.node_id, false).await {
+                    warn!("Failed to put node back in service: {}", e);
+                }
+            }
+        }
+        
         // Start all components
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
         
@@ -117,21 +352,21 @@ impl DAGShieldNode {
         let dag_handle = {
             let processor = Arc::clone(&self.dag_processor);
             let mut rx = shutdown_rx.resubscribe();
-            tokio::spawn(async move {
+            tokio::spawn(subsystems::instrument(Subsystem::DagSubmission, async move {
                 processor.start().await.unwrap_or_else(|e| {
                     error!("DAG processor error: {}", e);
                 });
-            })
+            }))
         };
         
         // Start network manager
         let network_handle = {
             let manager = Arc::clone(&self.network_manager);
-            tokio::spawn(async move {
+            tokio::spawn(subsystems::instrument(Subsystem::P2p, async move {
                 manager.start().await.unwrap_or_else(|e| {
                     error!("Network manager error: {}", e);
                 });
-            })
+            }))
         };
         
         // Start energy monitor
@@ -144,6 +379,30 @@ impl DAGShieldNode {
             })
         };
         
+        // Start measured power sampling
+        let mut power_handle = self.power_monitor.as_ref().map(|monitor| monitor.spawn_monitoring());
+        
+        // Run deferrable work (efficiency proofs) in low-carbon windows
+        let scheduler_handle = self.power_monitor.as_ref().map(|monitor| {
+            let monitor = Arc::clone(monitor);
+            tokio::spawn(async move { monitor.run_task_scheduler().await })
+        });
+        
+        // Count host threats (cryptojacking) the power monitor flags
+        let host_threat_handle = self.power_monitor.as_ref().map(|monitor| {
+            let mut threats = monitor.subscribe_threats();
+            let collector = Arc::clone(&self.metrics_collector);
+            tokio::spawn(async move {
+                loop {
+                    match threats.recv().await {
+                        Ok(threat) => collector.record_host_threat(&threat),
+                        Err(RecvError::Lagged(missed)) => debug!("Missed {} host threat events", missed),
+                        Err(RecvError::Closed) => return,
+                    }
+                }
+            })
+        });
+        
         // Start metrics collector
         let metrics_handle = {
             let collector = Arc::clone(&self.metrics_collector);
@@ -154,6 +413,96 @@ impl DAGShieldNode {
             })
         };
         
+        // Start U2U background tasks
+        let mut u2u_handles = Vec::new();
+        if let Some(client) = &self.u2u {
+            let client = Arc::clone(client);
+            u2u_handles.push(tokio::spawn(async move {
+                client.run_session_key_rotation().await.unwrap_or_else(|e| {
+                    error!("Session key rotation error: {}", e);
+                });
+            }));
+        }
+        if let (Some(client), Some(prover)) = (&self.u2u, &self.zk_prover) {
+            let (client, prover) = (Arc::clone(client), Arc::clone(prover));
+            u2u_handles.push(tokio::spawn(async move {
+                client.run_prover_sync(prover).await.unwrap_or_else(|e| {
+                    error!("Prover sync error: {}", e);
+                });
+            }));
+        }
+        if let (Some(client), Some(monitor)) = (&self.u2u, &self.power_monitor) {
+            let (client, monitor, node_id) = (Arc::clone(client), Arc::clone(monitor), self.node_id.clone());
+            u2u_handles.push(tokio::spawn(async move {
+                client.run_efficiency_proofs(node_id, monitor).await.unwrap_or_else(|e| {
+                    error!("Efficiency proof error: {}", e);
+                });
+            }));
+        }
+        
+        // Watch the mempool for sandwiches, publishing them as threat events
+        let mut mempool_handles = Vec::new();
+        if let Some(analyzer) = &self.mempool {
+            if let Some(events) = &self.events {
+                let (mut sandwiches, events) = (analyzer.subscribe(), events.clone());
+                mempool_handles.push(tokio::spawn(async move {
+                    loop {
+                        match sandwiches.recv().await {
+                            Ok(detection) => events.publish(ThreatEvent::detection(&detection, None)),
+                            Err(RecvError::Lagged(missed)) => debug!("Missed {} sandwich detections", missed),
+                            Err(RecvError::Closed) => return,
+                        }
+                    }
+                }));
+            }
+            let analyzer = Arc::clone(analyzer);
+            mempool_handles.push(tokio::spawn(async move {
+                analyzer.run().await.unwrap_or_else(|e| {
+                    error!("Mempool analyzer error: {:#}", e);
+                });
+            }));
+        }
+        
+        // Poll threat feeds, submitting confident indicators through the DAG
+        let intel_handle = self.intel.as_ref().map(|intel| {
+            if self.u2u.is_none() {
+                warn!("⚠️ Threat feed indicators are only matched locally without a U2U client to submit them");
+            }
+            tokio::spawn(Arc::clone(intel).run(self.u2u.as_ref().map(Arc::clone), self.node_id.clone()))
+        });
+        
+        // Export energy and U2U metrics
+        let export_handle = self.config.metrics.enabled.then(|| {
+            let node = self.clone();
+            tokio::spawn(async move { node.export_metrics().await })
+        });
+        
+        // Start REST API
+        let api_handle = self.config.api.enabled.then(|| {
+            let config = self.config.api.clone();
+            let state = ApiState {
+                token: config.token.clone(),
+                reviewers: Arc::new(config.reviewers.clone()),
+                quarantine: self.quarantine.as_ref().map(Arc::clone),
+                feedback: self.threat_detector.as_ref().and_then(|detector| detector.feedback()).map(Arc::clone),
+                events: self.events.clone(),
+                energy: self.power_monitor.as_ref().map(Arc::clone),
+                prover: self.zk_prover.as_ref().map(Arc::clone),
+                detector: self.threat_detector.as_ref().map(|detector| LocalDetector {
+                    node_id: self.node_id.clone(),
+                    detector: Arc::clone(detector),
+                    confidence_threshold: self.config.ai.confidence_threshold,
+                    wallet: self.u2u.as_ref().map(|client| client.wallet.clone()),
+                }),
+                phishing: self.phishing.as_ref().map(Arc::clone),
+            };
+            tokio::spawn(async move {
+                api::serve(&config, state).await.unwrap_or_else(|e| {
+                    error!("REST API error: {:#}", e);
+                });
+            })
+        });
+        
         // Main event loop
         let main_handle = {
             let node = self.clone();
@@ -164,8 +513,20 @@ impl DAGShieldNode {
             })
         };
         
-        // Wait for shutdown signal
-        shutdown_rx.recv().await;
+        // Wait for shutdown signal, or for the power monitor to stop on critical battery
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                result = async { power_handle.as_mut().unwrap().await }, if power_handle.is_some() => {
+                    power_handle = None;
+                    match result {
+                        Ok(Ok(())) => break,
+                        Ok(Err(e)) => error!("Power monitor error: {}", e),
+                        Err(e) => error!("Power monitor task failed: {}", e),
+                    }
+                }
+            }
+        }
         
         info!("🛑 Shutting down node components...");
         
@@ -173,7 +534,35 @@ impl DAGShieldNode {
         dag_handle.abort();
         network_handle.abort();
         energy_handle.abort();
+        if let (Some(monitor), Some(handle)) = (&self.power_monitor, power_handle) {
+            // Lets the loop flush persisted history before it returns
+            monitor.stop_monitoring();
+            if let Ok(Err(e)) = handle.await {
+                error!("Power monitor error: {}", e);
+            }
+        }
+        if let Some(handle) = host_threat_handle {
+            handle.abort();
+        }
+        if let Some(handle) = scheduler_handle {
+            handle.abort();
+        }
         metrics_handle.abort();
+        if let Some(handle) = export_handle {
+            handle.abort();
+        }
+        for handle in u2u_handles {
+            handle.abort();
+        }
+        for handle in mempool_handles {
+            handle.abort();
+        }
+        if let Some(handle) = intel_handle {
+            handle.abort();
+        }
+        if let Some(handle) = api_handle {
+            handle.abort();
+        }
         main_handle.abort();
         
         Ok(())
@@ -203,12 +592,31 @@ impl DAGShieldNode {
             std::time::Duration::from_secs(self.config.node.heartbeat_interval_secs)
         );
         
+        let mut beats: u64 = 0;
         loop {
             heartbeat_interval.tick().await;
+            beats += 1;
             
-            // Process pending threats
-            if let Some(detector) = &self.threat_detector {
-                self.process_threats(detector).await?;
+            // Throttle scans and DAG batches while on battery or thermally limited
+            let policy = self.power_monitor.as_ref()
+                .map(|monitor| monitor.current_power_policy())
+                .unwrap_or_default();
+            if let Some(client) = &self.u2u {
+                client.apply_power_policy(&policy);
+            }
+            if let Some(prover) = &self.zk_prover {
+                prover.apply_power_policy(&policy);
+            }
+            
+            // Process pending threats, less often or not at all once an energy budget runs low
+            let scan_every = match self.budget_action() {
+                Some(BudgetAction::PauseDetection) => None,
+                Some(BudgetAction::LowerDutyCycle) => Some(policy.scan_interval_multiplier.max(1) * 2),
+                _ => Some(policy.scan_interval_multiplier.max(1)),
+            };
+            let scan_due = scan_every.is_some_and(|every| beats % u64::from(every) == 0);
+            if let Some(detector) = self.threat_detector.as_ref().filter(|_| scan_due) {
+                subsystems::instrument(Subsystem::Detection, self.process_threats(detector)).await?;
             }
             
             // Check for challenges
@@ -224,10 +632,80 @@ impl DAGShieldNode {
         }
     }
     
+    /// Flush the DAG pool, send critical threats and a final heartbeat before the power monitor
+    /// stops the node on critical battery
+    fn shutdown_hook(
+        client: Arc<U2UClient>,
+        node_id: String,
+        stats: Arc<RwLock<NodeStats>>,
+        config: LowBatteryShutdownConfig,
+    ) -> ShutdownHook {
+        Arc::new(move || {
+            let (client, node_id, stats, config) =
+                (Arc::clone(&client), node_id.clone(), Arc::clone(&stats), config.clone());
+            Box::pin(async move {
+                let stats = stats.read().await.clone();
+                let heartbeat = NodeHeartbeat {
+                    threats_detected: stats.threats_detected,
+                    // The node sends no other heartbeat, so this covers the whole run
+                    uptime_secs: stats.uptime_seconds,
+                    energy_efficiency: u64::from(stats.energy_efficiency),
+                };
+                client.graceful_shutdown(&node_id, &heartbeat, &config).await
+            })
+        })
+    }
+    
+    /// Strictest action among the budget thresholds crossed in each budget's current period
+    fn budget_action(&self) -> Option<BudgetAction> {
+        let monitor = self.power_monitor.as_ref()?;
+        let status = monitor.get_budget_status();
+        let mut events = self.budget_events.lock().unwrap();
+        // A new period starts the budget over
+        events.retain(|budget, event| {
+            status.iter().any(|s| &s.budget == budget && s.period_start == event.period_start)
+        });
+        [BudgetAction::PauseDetection, BudgetAction::LowerDutyCycle, BudgetAction::Alert]
+            .into_iter()
+            .find(|action| events.values().any(|event| event.action == *action))
+    }
+    
+    /// Push the latest energy reading, energy statistics and U2U transaction metrics to the
+    /// Prometheus exporter
+    async fn export_metrics(&self) {
+        let mut export = tokio::time::interval(
+            std::time::Duration::from_secs(self.config.metrics.export_interval_secs.max(1))
+        );
+        
+        loop {
+            export.tick().await;
+            
+            if let Some(monitor) = &self.power_monitor {
+                if let Some(reading) = monitor.latest_reading() {
+                    self.metrics_collector.record_energy(&reading);
+                }
+                match monitor.get_energy_stats() {
+                    Ok(stats) => self.metrics_collector.record_energy_stats(&stats),
+                    Err(e) => debug!("No energy statistics to export: {}", e),
+                }
+            }
+            if let Some(client) = &self.u2u {
+                self.metrics_collector.record_u2u(&client.get_metrics());
+            }
+            if let Some(prover) = &self.zk_prover {
+                self.metrics_collector.record_proof_queue(&prover.proof_queue_stats());
+            }
+        }
+    }
+    
     async fn process_threats(&self, detector: &Arc<ThreatDetector>) -> Result<()> {
         // Get pending transactions from DAG processor
         let transactions = self.dag_processor.get_pending_transactions().await?;
         
+        if let Some(quarantine) = &self.quarantine {
+            self.submit_approved(detector, quarantine).await;
+        }
+        
         if transactions.is_empty() {
             return Ok();
         }
@@ -236,29 +714,191 @@ impl DAGShieldNode {
         
         // Batch process transactions through AI
         let results = detector.detect_threats_batch(&transactions).await?;
+        self.metrics_collector.record_detectors(&detector.detector_stats());
         
         for (tx, result) in transactions.iter().zip(results.iter()) {
-            if result.confidence > self.config.ai.confidence_threshold {
+            let scored = detector.take_scored(&tx.id).await;
+            let scored = scored.as_ref().map(|scored| (&scored.detection, scored.contributions.as_slice()));
+            // Borderline detections wait for an operator instead of going out as low priority
+            let quarantine = self.quarantine.as_ref().filter(|quarantine| {
+                quarantine.covers(result.confidence as f64) && ThreatCategory::from_label(&result.threat_type).is_some()
+            });
+            if let Some(quarantine) = quarantine {
+                if let Err(e) = quarantine.hold(tx, &result.threat_type, result.confidence as f64, &result.explanation, scored) {
+                    warn!("Failed to quarantine {} on {}: {:#}", result.threat_type, tx.id, e);
+                }
+            } else if result.confidence > self.config.ai.confidence_threshold {
                 info!("🚨 Threat detected: {} (confidence: {:.2})", 
                       result.threat_type, result.confidence);
                 
-                // Report to blockchain
-                self.blockchain_client.report_threat(
-                    &result.threat_type,
-                    &tx.target_address,
-                    (result.confidence * 100.0) as u32,
-                    tx.chain_id,
-                ).await?;
-                
-                // Update stats
-                let mut stats = self.stats.write().await;
-                stats.threats_detected += 1;
+                if let Err(e) = self.report_detection(detector, tx, &result.threat_type, result.confidence as f64, scored).await {
+                    warn!("Failed to report {} on {}: {:#}", result.threat_type, tx.id, e);
+                }
             }
         }
         
         Ok(())
     }
     
+    /// Report a threat on `tx`, proven when this node proves, then keep it for feedback and the
+    /// oracle, publish it and flag its target. Returns false when the finding already went out
+    async fn report_detection(
+        &self,
+        detector: &ThreatDetector,
+        tx: &Transaction,
+        threat_type: &str,
+        confidence: f64,
+        scored: Option<(&Detection, &[Detection])>,
+    ) -> Result<bool> {
+        // The same finding on the same transaction goes out once, also when a reorg replays
+        // the transaction and detectors fire on it again. DAG transactions not named by
+        // their chain hash are told apart by their data
+        let replay = scored.map(|(detection, _)| Detection {
+            tx_hash: detection.tx_hash.or_else(|| tx.id.parse().ok()).or_else(|| Some(threat_hash(&tx.data))),
+            ..detection.clone()
+        });
+        if let Some(duplicate) = replay.as_ref().and_then(|replay| self.reserve_replay(replay)) {
+            info!(
+                "♻️ Suppressed {} on {}{}, already submitted as {}",
+                threat_type,
+                tx.id,
+                if duplicate.replayed { " replayed by a reorg" } else { "" },
+                duplicate.submission
+            );
+            return Ok(false);
+        }
+        
+        // Report to blockchain, with a ZK proof of the detection when this node proves
+        let submitted = match self.submit_proven_threat(tx, confidence).await {
+            Some((threat_hash, proof)) => Ok((threat_hash, Some(proof))),
+            None => self.blockchain_client.report_threat(
+                threat_type,
+                &tx.target_address,
+                (confidence * 100.0) as u32,
+                tx.chain_id,
+            ).await.map(|threat_hash| (threat_hash, None)),
+        };
+        if let Some(replay) = &replay {
+            self.settle_replay(replay, submitted.as_ref().ok().map(|(threat_hash, _)| *threat_hash));
+        }
+        let (threat_hash, threat_proof) = submitted?;
+        
+        // Keep what was reported, under its on-chain id, for dispute and operator labels
+        if let (Some(feedback), Some((detection, contributions))) = (detector.feedback(), scored) {
+            if let Err(e) = feedback.record(threat_hash, &tx.id, tx.chain_id, detection, contributions, threat_proof) {
+                warn!("Failed to record detection for feedback: {:#}", e);
+            }
+            // and with the oracle under the same id, whose verdict calibration tracks
+            let calibration = &self.config.detection.calibration;
+            if calibration.enabled && !calibration.oracle.is_zero() && detection.target.is_some() {
+                if let Err(e) = self
+                    .blockchain_client
+                    .submit_oracle_report(calibration.oracle, detection, tx.chain_id, threat_hash)
+                    .await
+                {
+                    warn!("Failed to submit detection {} to the oracle: {:#}", tx.id, e);
+                }
+            }
+        }
+        
+        // Broadcast it under the id oracle confirmations will name
+        if let (Some(events), Some((detection, _))) = (&self.events, scored) {
+            events.publish(ThreatEvent::detection(detection, Some(threat_hash)));
+        }
+        
+        // Count the report against its target, unless reputation itself drove it
+        if let (Some(reputation), Some((detection, _))) = (detector.reputation(), scored) {
+            let families = detector_families(&detection.detector);
+            if let Some(target) = detection.target.filter(|_| !families.contains(&"reputation")) {
+                if let Err(e) = reputation.flag(target, detection.threat_type) {
+                    warn!("Failed to flag {:?} in the reputation store: {:#}", target, e);
+                }
+            }
+        }
+        
+        // Update stats
+        let mut stats = self.stats.write().await;
+        stats.threats_detected += 1;
+        Ok(true)
+    }
+    
+    /// Earlier report of `detection`'s finding, or else reserve it until `settle_replay`
+    fn reserve_replay(&self, detection: &Detection) -> Option<DuplicateDetection> {
+        let (deduper, chain) = self.replays.as_ref()?;
+        let now = chrono::Utc::now().timestamp() as u64;
+        let chain = chain.read().unwrap();
+        deduper.write().unwrap().reserve(detection, &chain, now)
+    }
+    
+    /// Record the threat hash a reserved detection went out under, or release it if it failed
+    fn settle_replay(&self, detection: &Detection, submitted: Option<H256>) {
+        let Some((deduper, chain)) = &self.replays else { return };
+        let now = chrono::Utc::now().timestamp() as u64;
+        let chain = chain.read().unwrap();
+        let mut deduper = deduper.write().unwrap();
+        match submitted {
+            Some(threat_hash) => deduper.record(detection, &format!("{:?}", threat_hash), &chain, now),
+            None => deduper.forget(detection),
+        }
+    }
+    
+    /// Submit a threat through the threat detector's `submitThreatWithProof`, returning the
+    /// threat hash it went out under that way and the proof's public input hash
+    async fn submit_proven_threat(&self, tx: &Transaction, confidence: f64) -> Option<(H256, H256)> {
+        let (Some(client), Some(prover)) = (&self.u2u, &self.zk_prover) else {
+            return None;
+        };
+        let proof = match prover.generate_threat_proof(&tx.data, confidence, &self.node_id).await {
+            Ok(proof) => proof,
+            Err(e) => {
+                warn!("⚠️ Failed to prove threat {}, reporting it without a proof: {}", tx.id, e);
+                return None;
+            }
+        };
+        match client
+            .submit_threat_with_proof(&tx.data, &proof, confidence, &self.node_id, tx.dependencies.clone())
+            .await
+        {
+            Ok(submission) => {
+                debug!("🔐 Submitted proven threat {} as {}", tx.id, submission);
+                Some((threat_hash(&tx.data), proof.public_input_hash()))
+            }
+            Err(e) => {
+                warn!("⚠️ Failed to submit proven threat {}, reporting it without a proof: {}", tx.id, e);
+                None
+            }
+        }
+    }
+    
+    /// Report quarantined detections an operator approved; one that fails stays approved and
+    /// is retried next time
+    async fn submit_approved(&self, detector: &ThreatDetector, quarantine: &Quarantine) {
+        match quarantine.expire() {
+            Ok(0) => {}
+            Ok(expired) => debug!("🧪 {} quarantined detections expired unreviewed", expired),
+            Err(e) => warn!("Failed to expire quarantined detections: {:#}", e),
+        }
+        let approved = match quarantine.approved() {
+            Ok(approved) => approved,
+            Err(e) => {
+                warn!("Failed to read approved detections: {:#}", e);
+                return;
+            }
+        };
+        for entry in approved {
+            info!("🚨 Reporting approved detection: {} (confidence: {:.2})", entry.threat_type, entry.confidence);
+            let scored = entry.detection.as_ref().map(|detection| (detection, entry.contributions.as_slice()));
+            match self.report_detection(detector, &entry.transaction(), &entry.threat_type, entry.confidence, scored).await {
+                Ok(_) => {
+                    if let Err(e) = quarantine.mark_submitted(&entry.id) {
+                        warn!("Failed to mark approved detection {:?} submitted: {:#}", entry.id, e);
+                    }
+                }
+                Err(e) => warn!("Failed to report approved detection {:?}: {:#}", entry.id, e),
+            }
+        }
+    }
+    
     async fn check_challenges(&self) -> Result<()> {
         let challenges = self.blockchain_client.get_active_challenges().await?;
         
@@ -381,9 +1021,19 @@ impl Clone for DAGShieldNode {
             config: self.config.clone(),
             dag_processor: Arc::clone(&self.dag_processor),
             threat_detector: self.threat_detector.as_ref().map(Arc::clone),
+            quarantine: self.quarantine.as_ref().map(Arc::clone),
+            phishing: self.phishing.as_ref().map(Arc::clone),
+            mempool: self.mempool.as_ref().map(Arc::clone),
+            intel: self.intel.as_ref().map(Arc::clone),
+            events: self.events.clone(),
             blockchain_client: Arc::clone(&self.blockchain_client),
+            u2u: self.u2u.as_ref().map(Arc::clone),
+            replays: self.replays.clone(),
             network_manager: Arc::clone(&self.network_manager),
             energy_monitor: Arc::clone(&self.energy_monitor),
+            power_monitor: self.power_monitor.as_ref().map(Arc::clone),
+            budget_events: Arc::clone(&self.budget_events),
+            zk_prover: self.zk_prover.as_ref().map(Arc::clone),
             metrics_collector: Arc::clone(&self.metrics_collector),
             storage: Arc::clone(&self.storage),
             stats: Arc::clone(&self.stats),
@@ -8,8 +8,9 @@ use anyhow::{Context, Result};
 use ethers::{
     prelude::*,
     providers::{Http, Provider, Ws},
-    types::{Address, Bytes, TransactionRequest, U256},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
 };
+use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, VecDeque},
@@ -23,6 +24,42 @@ use tokio::{
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+#[cfg(feature = "alloy-provider")]
+pub mod alloy_backend;
+pub mod chain_tracker;
+pub mod dedupe;
+pub mod efficiency_proof;
+pub mod heartbeat;
+pub mod meta_tx;
+pub mod read_cache;
+pub mod receipt_poller;
+pub mod reputation;
+pub mod session_keys;
+pub mod simulation;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+use chain_tracker::{BlockRef, ChainTracker, ChainUpdate};
+use dedupe::{threat_hash, DedupeConfig, ReplayDeduper, SubmissionDeduper, ThreatDetectorRegistry};
+use efficiency_proof::{DAGShieldOracleEnergy, EfficiencyProof, EfficiencyProofConfig};
+use heartbeat::{heartbeat_digest, NodeHeartbeat, NodeRegistryHeartbeat};
+use meta_tx::{MetaTxConfig, MetaTxRelayer};
+use read_cache::{CacheStats, CallCache};
+use receipt_poller::ReceiptPoller;
+use reputation::{reputation_from_node_info, stake_from_node_info, NodeRegistryReputation, ReputationConfig, ReputationModel};
+use session_keys::{sweep_amount, NodeRegistrySessionKeys, SessionKey, SessionKeyConfig, SWEEP_GAS};
+use simulation::{SimulationOutcome, Simulator};
+
+use crate::detection::Detection;
+use crate::energy_monitor::{
+    battery_health::BatteryHealth,
+    power_policy::PowerPolicy,
+    scheduler::{DeferrableTask, TaskPolicy},
+    shutdown::LowBatteryShutdownConfig,
+    EnergyMonitor,
+};
+use crate::zk_prover::{revocation::SignedRevocationList, CircuitKind, ThreatProof, ZKProver};
+
 /// U2U Network Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct U2UConfig {
@@ -33,6 +70,28 @@ pub struct U2UConfig {
     pub private_key: String,
     pub contract_addresses: ContractAddresses,
     pub dag_config: DAGConfig,
+    #[serde(default)]
+    pub provider_backend: ProviderKind,
+    #[serde(default)]
+    pub session_keys: SessionKeyConfig,
+    #[serde(default)]
+    pub meta_tx: MetaTxConfig,
+    #[serde(default)]
+    pub reputation: ReputationConfig,
+    #[serde(default)]
+    pub dedupe: DedupeConfig,
+    #[serde(default)]
+    pub efficiency_proofs: EfficiencyProofConfig,
+    /// Simulate all on-chain writes with eth_call instead of broadcasting them
+    #[serde(default)]
+    pub simulation: bool,
+    /// Most recent simulation outcomes kept for `get_simulation_outcomes`
+    #[serde(default = "default_simulation_outcomes")]
+    pub simulation_outcomes: usize,
+}
+
+fn default_simulation_outcomes() -> usize {
+    1000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +101,15 @@ pub enum U2UNetwork {
     Local,
 }
 
+/// Provider/signer stack used to talk to the U2U RPC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ProviderKind {
+    #[default]
+    Ethers,
+    /// Requires the `alloy-provider` feature
+    Alloy,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractAddresses {
     pub dagshield_token: Address,
@@ -50,6 +118,28 @@ pub struct ContractAddresses {
     pub threat_detector: Address,
 }
 
+impl ContractAddresses {
+    /// All configured contract addresses
+    pub fn all(&self) -> Vec<Address> {
+        vec![
+            self.dagshield_token,
+            self.dagshield_oracle,
+            self.node_registry,
+            self.threat_detector,
+        ]
+    }
+
+    /// Contract a DAG transaction of this type is sent to
+    pub fn target(&self, tx_type: &DAGTxType) -> Address {
+        match tx_type {
+            DAGTxType::ThreatSubmission => self.threat_detector,
+            DAGTxType::NodeRegistration | DAGTxType::RewardClaim => self.node_registry,
+            DAGTxType::StakeUpdate => self.dagshield_token,
+            DAGTxType::CrossChainRelay => self.dagshield_oracle,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DAGConfig {
     pub batch_size: usize,
@@ -57,6 +147,20 @@ pub struct DAGConfig {
     pub confirmation_blocks: u64,
     pub gas_limit: U256,
     pub gas_price_multiplier: f64,
+    /// Number of recent block hashes kept for fork detection
+    #[serde(default = "default_tracked_blocks")]
+    pub tracked_blocks: usize,
+    /// Give up waiting for a DAG transaction to reach the safe head after this long
+    #[serde(default = "default_confirmation_timeout_secs")]
+    pub confirmation_timeout_secs: u64,
+}
+
+fn default_tracked_blocks() -> usize {
+    64
+}
+
+fn default_confirmation_timeout_secs() -> u64 {
+    600
 }
 
 impl Default for U2UConfig {
@@ -79,7 +183,17 @@ impl Default for U2UConfig {
                 confirmation_blocks: 3,
                 gas_limit: U256::from(500_000),
                 gas_price_multiplier: 1.2,
+                tracked_blocks: default_tracked_blocks(),
+                confirmation_timeout_secs: default_confirmation_timeout_secs(),
             },
+            provider_backend: ProviderKind::Ethers,
+            session_keys: SessionKeyConfig::default(),
+            meta_tx: MetaTxConfig::default(),
+            reputation: ReputationConfig::default(),
+            dedupe: DedupeConfig::default(),
+            efficiency_proofs: EfficiencyProofConfig::default(),
+            simulation: false,
+            simulation_outcomes: default_simulation_outcomes(),
         }
     }
 }
@@ -98,6 +212,16 @@ pub struct DAGTransaction {
     pub gas_estimate: U256,
 }
 
+impl DAGTransaction {
+    /// Call to `to` carrying the transaction's calldata and gas estimate
+    pub fn request(&self, to: Address) -> TransactionRequest {
+        TransactionRequest::new()
+            .to(to)
+            .data(self.data.clone())
+            .gas(self.gas_estimate)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DAGTxType {
     ThreatSubmission,
@@ -115,6 +239,92 @@ pub enum DAGTxStatus {
     Failed,
 }
 
+/// Backend used to sign and broadcast DAG transactions
+#[derive(Clone)]
+pub enum ProviderBackend {
+    Ethers(Arc<SignerMiddleware<Provider<Http>, LocalWallet>>),
+    #[cfg(feature = "alloy-provider")]
+    Alloy(Arc<alloy_backend::AlloyBackend>),
+    /// Gasless submission through a meta-tx relayer, falling back to direct submission
+    Relayed {
+        relayer: Arc<MetaTxRelayer>,
+        fallback: Box<ProviderBackend>,
+    },
+}
+
+impl ProviderBackend {
+    /// Build the backend selected in the config
+    async fn from_config(
+        config: &U2UConfig,
+        signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    ) -> Result<Self> {
+        match config.provider_backend {
+            ProviderKind::Ethers => Ok(Self::Ethers(signer)),
+            #[cfg(feature = "alloy-provider")]
+            ProviderKind::Alloy => Ok(Self::Alloy(Arc::new(
+                alloy_backend::AlloyBackend::new(&config.rpc_url, &config.private_key)?,
+            ))),
+            #[cfg(not(feature = "alloy-provider"))]
+            ProviderKind::Alloy => Err(anyhow::anyhow!(
+                "alloy provider backend requested but the `alloy-provider` feature is not enabled"
+            )),
+        }
+    }
+
+    /// Get chain ID reported by the RPC
+    pub async fn chain_id(&self) -> Result<u64> {
+        match self {
+            Self::Ethers(signer) => Ok(signer.get_chainid().await?.as_u64()),
+            #[cfg(feature = "alloy-provider")]
+            Self::Alloy(backend) => backend.chain_id().await,
+            Self::Relayed { fallback, .. } => Box::pin(fallback.chain_id()).await,
+        }
+    }
+
+    /// Get latest block number
+    pub async fn block_number(&self) -> Result<u64> {
+        match self {
+            Self::Ethers(signer) => Ok(signer.get_block_number().await?.as_u64()),
+            #[cfg(feature = "alloy-provider")]
+            Self::Alloy(backend) => backend.block_number().await,
+            Self::Relayed { fallback, .. } => Box::pin(fallback.block_number()).await,
+        }
+    }
+
+    /// Sign and broadcast a DAG transaction to contract `to`, returning its hash without
+    /// waiting for inclusion
+    pub async fn send_dag_transaction(&self, dag_tx: DAGTransaction, to: Address) -> Result<H256> {
+        match self {
+            Self::Ethers(signer) => {
+                let pending_tx = signer.send_transaction(dag_tx.request(to), None).await?;
+
+                Ok(pending_tx.tx_hash())
+            }
+            #[cfg(feature = "alloy-provider")]
+            Self::Alloy(backend) => backend.send_dag_transaction(&dag_tx, to).await,
+            Self::Relayed { relayer, fallback } => {
+                match relayer.relay(to, dag_tx.data.clone()).await {
+                    Ok(tx_hash) => Ok(tx_hash),
+                    Err(e) => {
+                        warn!("Meta-tx relay failed ({}), submitting {} directly", e, dag_tx.id);
+                        Box::pin(fallback.send_dag_transaction(dag_tx, to)).await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Backend name for logs and benchmark reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ethers(_) => "ethers",
+            #[cfg(feature = "alloy-provider")]
+            Self::Alloy(_) => "alloy",
+            Self::Relayed { .. } => "meta-tx",
+        }
+    }
+}
+
 /// U2U Network Client
 pub struct U2UClient {
     pub config: U2UConfig,
@@ -122,10 +332,25 @@ pub struct U2UClient {
     pub ws_provider: Option<Arc<Provider<Ws>>>,
     pub wallet: LocalWallet,
     pub signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    pub backend: ProviderBackend,
     pub dag_processor: Arc<RwLock<DAGProcessor>>,
     pub tx_pool: Arc<RwLock<HashMap<String, DAGTransaction>>>,
     pub pending_batches: Arc<RwLock<VecDeque<Vec<DAGTransaction>>>>,
     pub metrics: Arc<RwLock<U2UMetrics>>,
+    pub chain_tracker: Arc<RwLock<ChainTracker>>,
+    pub call_cache: Arc<CallCache>,
+    pub session_key: Arc<RwLock<Option<Arc<SessionKey>>>>,
+    /// Rotated-out session keys still authorized until their in-flight transactions are mined
+    pub retired_session_keys: Arc<RwLock<Vec<Arc<SessionKey>>>>,
+    pub meta_tx_relayer: Option<Arc<MetaTxRelayer>>,
+    pub receipt_poller: Arc<ReceiptPoller>,
+    pub reputation_model: Arc<RwLock<ReputationModel>>,
+    pub deduper: Arc<RwLock<SubmissionDeduper>>,
+    /// Detections submitted per (transaction, category), across reorgs
+    pub replay_deduper: Arc<RwLock<ReplayDeduper>>,
+    pub simulator: Option<Arc<Simulator>>,
+    /// Battery-aware cap on parallel batch size (None = `max_parallel_txs`)
+    pub batch_limit: Arc<RwLock<Option<usize>>>,
 }
 
 /// DAG Processor for parallel transaction handling
@@ -182,6 +407,9 @@ impl U2UClient {
             wallet.clone(),
         ));
 
+        // Select transaction backend (ethers or alloy)
+        let backend = ProviderBackend::from_config(&config, signer.clone()).await?;
+
         // Initialize DAG processor
         let dag_processor = Arc::new(RwLock::new(DAGProcessor {
             active_batches: HashMap::new(),
@@ -190,12 +418,48 @@ impl U2UClient {
             completed_txs: HashMap::new(),
         }));
 
+        let meta_tx_relayer = if config.meta_tx.enabled {
+            info!("📨 Gasless meta-tx mode via relayer {}", config.meta_tx.relayer_url);
+            Some(Arc::new(MetaTxRelayer::new(
+                config.meta_tx.clone(),
+                config.chain_id,
+                wallet.clone(),
+                provider.clone(),
+            )?))
+        } else {
+            None
+        };
+
+        // Single poller for all in-flight transaction receipts
+        let receipt_poller = Arc::new(ReceiptPoller::new(provider.clone(), Duration::from_secs(300)));
+        tokio::spawn(receipt_poller.clone().run());
+
+        let reputation_model = Arc::new(RwLock::new(ReputationModel::new(
+            config.reputation.clone(),
+        )));
+
+        let deduper = Arc::new(RwLock::new(SubmissionDeduper::new(config.dedupe.window_secs)));
+        let replay_deduper = Arc::new(RwLock::new(ReplayDeduper::new(config.dedupe.window_secs)));
+
+        let simulator = if config.simulation {
+            warn!("🧪 Simulation mode: on-chain writes are simulated and never broadcast");
+            Some(Arc::new(Simulator::new(provider.clone(), config.simulation_outcomes)))
+        } else {
+            None
+        };
+
+        let chain_tracker = Arc::new(RwLock::new(ChainTracker::new(
+            config.dag_config.tracked_blocks,
+            config.dag_config.confirmation_blocks,
+        )));
+
         let client = Self {
             config,
             provider,
             ws_provider,
             wallet,
             signer,
+            backend,
             dag_processor,
             tx_pool: Arc::new(RwLock::new(HashMap::new())),
             pending_batches: Arc::new(RwLock::new(VecDeque::new())),
@@ -209,6 +473,17 @@ impl U2UClient {
                 gas_savings: 0.0,
                 last_updated: chrono::Utc::now().timestamp() as u64,
             })),
+            chain_tracker,
+            call_cache: Arc::new(CallCache::new()),
+            session_key: Arc::new(RwLock::new(None)),
+            retired_session_keys: Arc::new(RwLock::new(Vec::new())),
+            meta_tx_relayer,
+            receipt_poller,
+            reputation_model,
+            deduper,
+            replay_deduper,
+            simulator,
+            batch_limit: Arc::new(RwLock::new(None)),
         };
 
         // Verify connection
@@ -220,15 +495,16 @@ impl U2UClient {
 
     /// Verify connection to U2U network
     async fn verify_connection(&self) -> Result<()> {
-        let chain_id = self.provider.get_chainid().await?;
-        let block_number = self.provider.get_block_number().await?;
+        let chain_id = self.backend.chain_id().await?;
+        let block_number = self.backend.block_number().await?;
         
         info!("🔗 Connected to U2U Network:");
         info!("   Chain ID: {}", chain_id);
         info!("   Latest Block: {}", block_number);
         info!("   Wallet Address: {:?}", self.wallet.address());
+        info!("   Provider backend: {}", self.backend.name());
 
-        if chain_id.as_u64() != self.config.chain_id {
+        if chain_id != self.config.chain_id {
             return Err(anyhow::anyhow!(
                 "Chain ID mismatch: expected {}, got {}",
                 self.config.chain_id,
@@ -247,30 +523,145 @@ impl U2UClient {
         node_id: &str,
         dependencies: Vec<String>,
     ) -> Result<String> {
+        self.submit_threat_payload(threat_hash(threat_data), Bytes::from(threat_data.to_vec()), confidence, node_id, dependencies)
+            .await
+    }
+
+    /// Submit a detection unless the same finding on the same transaction was already submitted,
+    /// including before a reorg replayed the transaction. The finding is reserved before any
+    /// await, so a concurrent report of it is suppressed too
+    pub async fn submit_detection(&self, detection: &Detection, node_id: &str) -> Result<String> {
+        let fork_aware = self.config.dedupe.enabled && self.config.dedupe.fork_aware;
+        if fork_aware {
+            let now = chrono::Utc::now().timestamp() as u64;
+            let chain = self.chain_tracker.read().unwrap();
+            if let Some(duplicate) = self.replay_deduper.write().unwrap().reserve(detection, &chain, now) {
+                if duplicate.replayed {
+                    info!(
+                        "♻️ Suppressed {} on reorg-replayed {:?}, already submitted as {}",
+                        detection.threat_type, detection.tx_hash, duplicate.submission
+                    );
+                } else {
+                    debug!("♻️ Duplicate {} on {:?}, reusing submission {}", detection.threat_type, detection.tx_hash, duplicate.submission);
+                }
+                return Ok(duplicate.submission);
+            }
+        }
+
+        let submitted = match serde_json::to_vec(detection) {
+            Ok(data) => self.submit_threat_parallel(&data, detection.confidence, node_id, Vec::new()).await,
+            Err(e) => Err(e.into()),
+        };
+        if fork_aware {
+            let now = chrono::Utc::now().timestamp() as u64;
+            let chain = self.chain_tracker.read().unwrap();
+            let mut deduper = self.replay_deduper.write().unwrap();
+            match &submitted {
+                Ok(tx_id) => deduper.record(detection, tx_id, &chain, now),
+                Err(_) => deduper.forget(detection),
+            }
+        }
+        submitted
+    }
+
+    /// Submit threat data with its ZK proof attached, encoded as the arguments of the
+    /// verifier's `verifyProof` for the threat detector contract to forward
+    pub async fn submit_threat_with_proof(
+        &self,
+        threat_data: &[u8],
+        proof: &ThreatProof,
+        confidence: f64,
+        node_id: &str,
+        dependencies: Vec<String>,
+    ) -> Result<String> {
+        let hash = threat_hash(threat_data);
+        let detector = ThreatDetectorRegistry::new(
+            self.config.contract_addresses.threat_detector,
+            self.provider.clone(),
+        );
+        let calldata = detector
+            .submit_threat_with_proof(hash.into(), Bytes::from(threat_data.to_vec()), Bytes::from(proof.solidity_arguments()?))
+            .calldata()
+            .context("Failed to encode submitThreatWithProof call")?;
+
+        self.submit_threat_payload(hash, calldata, confidence, node_id, dependencies).await
+    }
+
+    /// Deduplicate, throttle and queue a threat submission carrying `payload`
+    async fn submit_threat_payload(
+        &self,
+        hash: H256,
+        payload: Bytes,
+        confidence: f64,
+        node_id: &str,
+        dependencies: Vec<String>,
+    ) -> Result<String> {
+        let now = chrono::Utc::now().timestamp() as u64;
         let tx_id = Uuid::new_v4().to_string();
-        
+
+        // Another detector already reported this threat. The hash is reserved before any await,
+        // so concurrent reports of the same threat cannot both go out
+        if self.config.dedupe.enabled {
+            if let Some(existing) = self.deduper.write().unwrap().reserve(hash, &tx_id, now) {
+                debug!("♻️ Duplicate threat {:?}, reusing submission {}", hash, existing);
+                return Ok(existing);
+            }
+        }
+
+        let queued = self
+            .queue_threat_submission(&tx_id, hash, payload, confidence, node_id, dependencies)
+            .await;
+        if queued.is_err() && self.config.dedupe.enabled {
+            self.deduper.write().unwrap().forget(&hash);
+        }
+        queued.map(|()| tx_id)
+    }
+
+    /// Throttle and queue a threat submission whose hash is already reserved
+    async fn queue_threat_submission(
+        &self,
+        tx_id: &str,
+        hash: H256,
+        payload: Bytes,
+        confidence: f64,
+        node_id: &str,
+        dependencies: Vec<String>,
+    ) -> Result<()> {
+        if self.config.dedupe.enabled && self.config.dedupe.check_on_chain && self.threat_exists_on_chain(hash).await? {
+            return Err(anyhow::anyhow!("Threat {:?} already recorded on-chain", hash));
+        }
+
+        // Skip reports whose expected reputation cost outweighs their value
+        let reputation = self.get_node_reputation(node_id).await? as f64;
+        if !self.reputation_model.read().unwrap().should_submit(reputation, confidence) {
+            warn!(
+                "🛑 Throttling threat submission (confidence {:.2}, reputation {:.0})",
+                confidence, reputation
+            );
+            return Err(anyhow::anyhow!("Threat submission throttled to protect reputation"));
+        }
+
         debug!("📤 Submitting threat data via DAG: {}", tx_id);
 
         // Create DAG transaction
         let dag_tx = DAGTransaction {
-            id: tx_id.clone(),
+            id: tx_id.to_string(),
             tx_type: DAGTxType::ThreatSubmission,
-            data: Bytes::from(threat_data.to_vec()),
+            gas_estimate: self.estimate_gas_for_threat_submission(&payload).await?,
+            data: payload,
             dependencies,
             priority: self.calculate_priority(confidence),
             timestamp: chrono::Utc::now().timestamp() as u64,
             node_id: node_id.to_string(),
             status: DAGTxStatus::Pending,
-            gas_estimate: self.estimate_gas_for_threat_submission(threat_data).await?,
         };
 
         // Add to transaction pool
-        self.tx_pool.write().unwrap().insert(tx_id.clone(), dag_tx.clone());
+        self.tx_pool.write().unwrap().insert(tx_id.to_string(), dag_tx.clone());
+        self.reputation_model.write().unwrap().track_pending(tx_id, confidence);
 
         // Process through DAG
-        self.process_dag_transaction(dag_tx).await?;
-
-        Ok(tx_id)
+        self.process_dag_transaction(dag_tx).await
     }
 
     /// Register DePIN node on U2U network
@@ -310,6 +701,7 @@ impl U2UClient {
 
         // Sort transactions by dependencies and priority
         let sorted_txs = self.sort_transactions_by_dag(&transactions)?;
+        let sorted_ids: Vec<String> = sorted_txs.iter().map(|tx| tx.id.clone()).collect();
 
         // Process in parallel where possible
         let mut results = Vec::new();
@@ -329,7 +721,7 @@ impl U2UClient {
             }
 
             // Process batch if it reaches max size
-            if current_batch.len() >= self.config.dag_config.max_parallel_txs {
+            if current_batch.len() >= self.effective_batch_size() {
                 let batch_results = self.execute_parallel_batch(&current_batch).await?;
                 results.extend(batch_results);
                 current_batch.clear();
@@ -342,6 +734,14 @@ impl U2UClient {
             results.extend(batch_results);
         }
 
+        // Record hashes for the confirmation tracker
+        {
+            let mut processor = self.dag_processor.write().unwrap();
+            for (tx, hash) in sorted_ids.iter().zip(results.iter()) {
+                processor.completed_txs.insert(tx.clone(), *hash);
+            }
+        }
+
         let processing_time = start_time.elapsed();
         self.update_dag_metrics(transactions.len(), processing_time).await;
 
@@ -354,14 +754,26 @@ impl U2UClient {
         &self,
         transactions: &[DAGTransaction],
     ) -> Result<Vec<H256>> {
+        if let Some(simulator) = &self.simulator {
+            return self.simulate_batch(simulator, transactions).await;
+        }
+
         let mut handles = Vec::new();
 
         for tx in transactions {
-            let signer = self.signer.clone();
+            let backend = self.backend_for(tx);
+            let to = self.config.contract_addresses.target(&tx.tx_type);
+            let poller = self.receipt_poller.clone();
             let tx_clone = tx.clone();
             
             let handle = tokio::spawn(async move {
-                Self::execute_single_transaction(signer, tx_clone).await
+                let tx_hash = backend.send_dag_transaction(tx_clone, to).await?;
+                let receipt = poller.wait(tx_hash).await?;
+
+                if receipt.status == Some(U64::zero()) {
+                    return Err(anyhow::anyhow!("Transaction {:?} reverted", tx_hash));
+                }
+                Ok(receipt.transaction_hash)
             });
             
             handles.push(handle);
@@ -369,34 +781,511 @@ impl U2UClient {
 
         // Wait for all transactions to complete
         let mut results = Vec::new();
-        for handle in handles {
-            match handle.await? {
+        let mut failure = None;
+        for (tx, handle) in transactions.iter().zip(handles) {
+            let outcome = handle.await?;
+            if matches!(tx.tx_type, DAGTxType::ThreatSubmission) {
+                self.record_submission_outcome(&tx.id, outcome.is_ok());
+            }
+            match outcome {
                 Ok(tx_hash) => results.push(tx_hash),
                 Err(e) => {
                     error!("Transaction execution failed: {}", e);
-                    return Err(e);
+                    failure.get_or_insert(e);
                 }
             }
         }
 
-        Ok(results)
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(results),
+        }
     }
 
-    /// Execute single transaction
-    async fn execute_single_transaction(
-        signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        dag_tx: DAGTransaction,
+    /// Dry-run a batch: every transaction is executed as a call and gets a placeholder hash
+    async fn simulate_batch(
+        &self,
+        simulator: &Simulator,
+        transactions: &[DAGTransaction],
+    ) -> Result<Vec<H256>> {
+        let outcomes = futures::future::join_all(transactions.iter().map(|tx| {
+            let request: TypedTransaction = tx
+                .request(self.config.contract_addresses.target(&tx.tx_type))
+                .into();
+            let label = format!("{:?} {}", tx.tx_type, tx.id);
+            async move { simulator.simulate(&label, self.wallet.address(), request).await }
+        }))
+        .await;
+
+        Ok(outcomes.into_iter().map(|outcome| outcome.simulated_hash).collect())
+    }
+
+    /// Shrink DAG batches according to the node's power policy
+    pub fn apply_power_policy(&self, policy: &PowerPolicy) {
+        let limit = (policy.batch_fraction < 1.0)
+            .then(|| policy.batch_size(self.config.dag_config.max_parallel_txs));
+        *self.batch_limit.write().unwrap() = limit;
+    }
+
+    fn effective_batch_size(&self) -> usize {
+        self.batch_limit
+            .read()
+            .unwrap()
+            .unwrap_or(self.config.dag_config.max_parallel_txs)
+    }
+
+    /// Pool transactions that have not been broadcast yet
+    fn pending_transactions(&self) -> Vec<DAGTransaction> {
+        let processor = self.dag_processor.read().unwrap();
+        self.tx_pool
+            .read()
+            .unwrap()
+            .values()
+            .filter(|tx| !processor.completed_txs.contains_key(&tx.id))
+            .cloned()
+            .collect()
+    }
+
+    /// Write pending transactions to disk so they survive a restart
+    pub fn flush_tx_pool(&self, path: &str) -> Result<usize> {
+        let pending = self.pending_transactions();
+        std::fs::write(path, serde_json::to_vec(&pending)?)
+            .with_context(|| format!("Failed to write tx pool to {}", path))?;
+
+        info!("💾 Flushed {} pending DAG transactions to {}", pending.len(), path);
+        Ok(pending.len())
+    }
+
+    /// Load transactions written by `flush_tx_pool`
+    pub fn restore_tx_pool(&self, path: &str) -> Result<usize> {
+        if !std::path::Path::new(path).exists() {
+            return Ok(0);
+        }
+
+        let raw = std::fs::read(path).with_context(|| format!("Failed to read tx pool from {}", path))?;
+        let pending: Vec<DAGTransaction> = serde_json::from_slice(&raw)?;
+        let count = pending.len();
+
+        let mut pool = self.tx_pool.write().unwrap();
+        for tx in pending {
+            pool.insert(tx.id.clone(), tx);
+        }
+
+        info!("♻️ Restored {} pending DAG transactions from {}", count, path);
+        Ok(count)
+    }
+
+    /// Broadcast pending threat submissions at or above `min_priority`
+    pub async fn submit_critical_pending(&self, min_priority: u8) -> Result<Vec<H256>> {
+        let critical: Vec<DAGTransaction> = self
+            .pending_transactions()
+            .into_iter()
+            .filter(|tx| matches!(tx.tx_type, DAGTxType::ThreatSubmission) && tx.priority >= min_priority)
+            .collect();
+
+        if critical.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        info!("🚨 Submitting {} critical pending threats before shutdown", critical.len());
+        self.process_transaction_batch(critical).await
+    }
+
+    /// Report node metrics to the registry, refreshing its availability
+    pub async fn send_heartbeat(&self, node_id: &str, heartbeat: &NodeHeartbeat) -> Result<H256> {
+        let registry = NodeRegistryHeartbeat::new(
+            self.config.contract_addresses.node_registry,
+            self.signer.clone(),
+        );
+
+        let digest = heartbeat_digest(node_id, heartbeat);
+        let signature = self.wallet.sign_message(digest.as_bytes()).await?;
+
+        let call = registry.update_node_metrics(
+            node_id.to_string(),
+            heartbeat.threats_detected.into(),
+            heartbeat.uptime_secs.into(),
+            heartbeat.energy_efficiency.into(),
+            signature.to_vec().into(),
+        );
+
+        if let Some(simulator) = &self.simulator {
+            let outcome = simulator.simulate("updateNodeMetrics", self.wallet.address(), call.tx).await;
+            return Ok(outcome.simulated_hash);
+        }
+
+        let receipt = call
+            .send()
+            .await?
+            .await?
+            .context("Heartbeat transaction dropped")?;
+
+        debug!("💓 Heartbeat sent for {}: {:?}", node_id, receipt.transaction_hash);
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Take the node out of service on the registry, or put it back
+    pub async fn set_node_maintenance(&self, node_id: &str, maintenance: bool) -> Result<H256> {
+        let registry = NodeRegistryHeartbeat::new(
+            self.config.contract_addresses.node_registry,
+            self.signer.clone(),
+        );
+
+        let call = registry.set_node_maintenance(node_id.to_string(), maintenance);
+
+        if let Some(simulator) = &self.simulator {
+            let outcome = simulator.simulate("setNodeMaintenance", self.wallet.address(), call.tx).await;
+            return Ok(outcome.simulated_hash);
+        }
+
+        let receipt = call
+            .send()
+            .await?
+            .await?
+            .context("Maintenance transaction dropped")?;
+
+        info!("🔧 Node {} {} service: {:?}", node_id,
+              if maintenance { "taken out of" } else { "back in" }, receipt.transaction_hash);
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Sign an energy efficiency summary and submit it to the oracle for reward weighting
+    pub async fn submit_efficiency_proof(&self, node_id: &str, proof: &EfficiencyProof) -> Result<H256> {
+        let oracle = DAGShieldOracleEnergy::new(
+            self.config.contract_addresses.dagshield_oracle,
+            self.signer.clone(),
+        );
+
+        // Signed by the staking wallet, the operator the registry lists for this node
+        let digest = proof.digest(node_id, self.config.chain_id, self.config.contract_addresses.dagshield_oracle);
+        let signature = self.wallet.sign_hash(digest)?;
+
+        let call = oracle.submit_energy_proof(
+            node_id.to_string(),
+            proof.period_start.into(),
+            proof.period_end.into(),
+            proof.energy_wh.into(),
+            proof.avg_power_milliwatts.into(),
+            proof.efficiency_score.into(),
+            proof.carbon_grams.into(),
+            signature.to_vec().into(),
+        );
+
+        if let Some(simulator) = &self.simulator {
+            let outcome = simulator.simulate("submitEnergyProof", self.wallet.address(), call.tx).await;
+            return Ok(outcome.simulated_hash);
+        }
+
+        let receipt = call
+            .send()
+            .await?
+            .await?
+            .context("Energy proof transaction dropped")?;
+
+        info!("🌿 Energy proof submitted for {} (efficiency {}): {:?}",
+              node_id, proof.efficiency_score, receipt.transaction_hash);
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Submit a recursive epoch proof; the oracle forwards `calldata` to its decider verifier,
+    /// so gas does not grow with `threat_count`
+    pub async fn submit_epoch_proof(
+        &self,
+        node_id: &str,
+        epoch: u64,
+        threat_count: u64,
+        digest: [u8; 32],
+        calldata: Vec<u8>,
     ) -> Result<H256> {
-        let tx_request = TransactionRequest::new()
-            .data(dag_tx.data)
-            .gas(dag_tx.gas_estimate);
+        let oracle = DAGShieldOracleEnergy::new(
+            self.config.contract_addresses.dagshield_oracle,
+            self.signer.clone(),
+        );
+
+        let call = oracle.submit_epoch_proof(
+            node_id.to_string(),
+            epoch.into(),
+            threat_count.into(),
+            digest,
+            calldata.into(),
+        );
+
+        if let Some(simulator) = &self.simulator {
+            let outcome = simulator.simulate("submitEpochProof", self.wallet.address(), call.tx).await;
+            return Ok(outcome.simulated_hash);
+        }
 
-        let pending_tx = signer.send_transaction(tx_request, None).await?;
-        let receipt = pending_tx.await?.context("Transaction failed")?;
+        let receipt = call
+            .send()
+            .await?
+            .await?
+            .context("Epoch proof transaction dropped")?;
 
+        info!("📦 Epoch {} proof submitted for {} ({} threats): {:?}",
+              epoch, node_id, threat_count, receipt.transaction_hash);
         Ok(receipt.transaction_hash)
     }
 
+    /// Queue an efficiency proof for every completed period on the monitor's carbon-aware
+    /// scheduler; `run_task_scheduler` submits it before the next period closes
+    pub async fn run_efficiency_proofs(self: Arc<Self>, node_id: String, monitor: Arc<EnergyMonitor>) -> Result<()> {
+        let config = self.config.efficiency_proofs.clone();
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let mut schedule = interval(Duration::from_secs(config.interval_secs));
+        // First tick fires immediately, before any period has elapsed
+        schedule.tick().await;
+
+        loop {
+            schedule.tick().await;
+
+            let period_end = chrono::Utc::now().timestamp() as u64;
+            let period = (period_end.saturating_sub(config.interval_secs), period_end);
+            let stats = match monitor.get_energy_stats_range(period.0, period.1) {
+                Ok(stats) => stats,
+                Err(e) => {
+                    warn!("No energy stats for efficiency proof: {}", e);
+                    continue;
+                }
+            };
+
+            if stats.uptime_hours < config.min_uptime_hours {
+                debug!("Skipping efficiency proof: only {:.2}h of uptime", stats.uptime_hours);
+                continue;
+            }
+
+            let proof = EfficiencyProof::from_stats(period, &stats);
+            let (client, node_id) = (Arc::clone(&self), node_id.clone());
+            monitor.scheduler.register(DeferrableTask {
+                name: format!("efficiency proof {}-{}", period.0, period.1),
+                policy: TaskPolicy {
+                    // The oracle rejects a period once a later one has landed
+                    max_delay_secs: config.interval_secs / 2,
+                    estimated_duration_secs: 60,
+                    ..TaskPolicy::default()
+                },
+                run: Arc::new(move || {
+                    let (client, node_id, proof) = (Arc::clone(&client), node_id.clone(), proof.clone());
+                    async move { client.submit_efficiency_proof(&node_id, &proof).await.map(|_| ()) }.boxed()
+                }),
+            });
+        }
+    }
+
+    /// Orderly stop: persist the pool, send critical threats and a final heartbeat, then mark the
+    /// node as in maintenance so the refreshed `lastActiveTime` does not advertise it as available
+    pub async fn graceful_shutdown(
+        &self,
+        node_id: &str,
+        heartbeat: &NodeHeartbeat,
+        config: &LowBatteryShutdownConfig,
+    ) -> Result<()> {
+        info!("🛑 Graceful shutdown for node {}", node_id);
+
+        // Persist first in case the battery dies during the network calls below
+        self.flush_tx_pool(&config.tx_pool_path)?;
+
+        match self.submit_critical_pending(config.critical_priority).await {
+            Ok(hashes) if !hashes.is_empty() => {
+                self.flush_tx_pool(&config.tx_pool_path)?;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to submit critical threats: {}", e),
+        }
+
+        if let Err(e) = self.send_heartbeat(node_id, heartbeat).await {
+            warn!("Failed to send final heartbeat: {}", e);
+        }
+        if let Err(e) = self.set_node_maintenance(node_id, true).await {
+            warn!("Failed to take node out of service: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Most recent outcomes predicted in simulation mode, oldest first
+    pub fn get_simulation_outcomes(&self) -> Vec<SimulationOutcome> {
+        self.simulator.as_ref().map(|s| s.outcomes()).unwrap_or_default()
+    }
+
+    /// Pick the signing backend: routine threat submissions use the session key when one is
+    /// active and go through the meta-tx relayer when gasless mode is enabled
+    fn backend_for(&self, tx: &DAGTransaction) -> ProviderBackend {
+        if !matches!(tx.tx_type, DAGTxType::ThreatSubmission) {
+            return self.backend.clone();
+        }
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let direct = match self.session_key.read().unwrap().as_ref() {
+            Some(session) if session.is_usable(now, 60) => {
+                ProviderBackend::Ethers(session.signer.clone())
+            }
+            _ => self.backend.clone(),
+        };
+
+        match &self.meta_tx_relayer {
+            Some(relayer) => ProviderBackend::Relayed {
+                relayer: relayer.clone(),
+                fallback: Box::new(direct),
+            },
+            None => direct,
+        }
+    }
+
+    /// Generate a new session key, authorize it on the node registry with the staking
+    /// wallet, fund it for gas, then sweep and revoke the previous key once its pending
+    /// transactions are mined
+    pub async fn rotate_session_key(&self) -> Result<Address> {
+        let settings = &self.config.session_keys;
+        let registry = NodeRegistrySessionKeys::new(
+            self.config.contract_addresses.node_registry,
+            self.signer.clone(),
+        );
+
+        let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng())
+            .with_chain_id(self.config.chain_id);
+        let expires_at = chrono::Utc::now().timestamp() as u64 + settings.validity_secs;
+
+        info!("🔑 Authorizing session key {:?} (expires at {})", wallet.address(), expires_at);
+
+        if let Some(simulator) = &self.simulator {
+            let call = registry.authorize_session_key(wallet.address(), expires_at);
+            simulator.simulate("authorizeSessionKey", self.wallet.address(), call.tx).await;
+            return Ok(wallet.address());
+        }
+
+        let receipt = registry
+            .authorize_session_key(wallet.address(), expires_at)
+            .send()
+            .await?
+            .await?
+            .context("Session key authorization failed")?;
+
+        if !settings.funding_amount_wei.is_zero() {
+            let funding = TransactionRequest::new()
+                .to(wallet.address())
+                .value(settings.funding_amount_wei);
+            self.signer.send_transaction(funding, None).await?.await?;
+        }
+
+        let session = Arc::new(SessionKey {
+            signer: Arc::new(SignerMiddleware::new(self.provider.clone(), wallet.clone())),
+            wallet,
+            expires_at,
+            authorization_tx: receipt.transaction_hash,
+        });
+        let address = session.address();
+
+        let previous = self.session_key.write().unwrap().replace(session);
+        if let Some(previous) = previous {
+            self.retired_session_keys.write().unwrap().push(previous);
+        }
+
+        self.retire_session_keys(&registry).await;
+
+        Ok(address)
+    }
+
+    /// Sweep and revoke rotated-out keys whose broadcast transactions have all been mined;
+    /// keys still draining stay authorized and are retried on the next rotation
+    async fn retire_session_keys(
+        &self,
+        registry: &NodeRegistrySessionKeys<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    ) {
+        let retired: Vec<_> = self.retired_session_keys.read().unwrap().clone();
+
+        for session in retired {
+            match self.wait_for_session_key_drain(&session).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    warn!("Session key {:?} still has pending transactions, keeping it authorized", session.address());
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to check pending transactions of session key {:?}: {}", session.address(), e);
+                    continue;
+                }
+            }
+
+            // Revoke only after the leftover funding is back, the key can still send until then
+            if let Err(e) = self.sweep_session_key(&session).await {
+                warn!("Failed to sweep session key {:?}: {}", session.address(), e);
+            }
+            debug!("🔑 Revoking previous session key {:?}", session.address());
+            if let Err(e) = registry.revoke_session_key(session.address()).send().await {
+                warn!("Failed to revoke session key {:?}: {}", session.address(), e);
+            }
+
+            self.retired_session_keys
+                .write()
+                .unwrap()
+                .retain(|retired| !Arc::ptr_eq(retired, &session));
+        }
+    }
+
+    /// Wait until every transaction a session key has broadcast is mined, returning false if
+    /// some are still pending after the configured drain timeout
+    async fn wait_for_session_key_drain(&self, session: &SessionKey) -> Result<bool> {
+        let address = session.address();
+        let deadline = Instant::now() + Duration::from_secs(self.config.session_keys.drain_timeout_secs);
+
+        loop {
+            // Submissions that picked the key just before rotation may not be broadcast yet
+            sleep(Duration::from_secs(2)).await;
+
+            let pending = self.provider
+                .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+                .await?;
+            let mined = self.provider
+                .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+                .await?;
+            if pending == mined {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Return the unspent gas funding of a rotated-out session key to the staking wallet
+    async fn sweep_session_key(&self, session: &SessionKey) -> Result<()> {
+        let balance = self.provider.get_balance(session.address(), None).await?;
+        let gas_price = self.provider.get_gas_price().await?;
+        let Some(amount) = sweep_amount(balance, gas_price) else {
+            return Ok(());
+        };
+
+        let sweep = TransactionRequest::new()
+            .to(self.wallet.address())
+            .value(amount)
+            .gas(SWEEP_GAS)
+            .gas_price(gas_price);
+        session.signer.send_transaction(sweep, None).await?.await?;
+
+        debug!("🔑 Swept {} wei from session key {:?}", amount, session.address());
+        Ok(())
+    }
+
+    /// Rotate the session key on the configured schedule
+    pub async fn run_session_key_rotation(&self) -> Result<()> {
+        if !self.config.session_keys.enabled {
+            return Ok(());
+        }
+
+        let mut rotation = interval(Duration::from_secs(self.config.session_keys.rotation_interval_secs));
+
+        loop {
+            rotation.tick().await;
+
+            if let Err(e) = self.rotate_session_key().await {
+                error!("Session key rotation failed: {}", e);
+            }
+        }
+    }
+
     /// Sort transactions by DAG dependencies
     fn sort_transactions_by_dag(
         &self,
@@ -493,6 +1382,37 @@ impl U2UClient {
         self.metrics.read().unwrap().clone()
     }
 
+    /// Measure DAG batch throughput of the active provider backend.
+    /// Broadcasts `tx_count` empty transactions, so only run against local/dev networks.
+    pub async fn benchmark_batch_throughput(&self, tx_count: usize) -> Result<BackendBenchmark> {
+        info!("🏃 Benchmarking {} backend with {} transactions", self.backend.name(), tx_count);
+
+        let transactions: Vec<DAGTransaction> = (0..tx_count)
+            .map(|i| DAGTransaction {
+                id: format!("bench_tx_{}", i),
+                tx_type: DAGTxType::ThreatSubmission,
+                data: Bytes::default(),
+                dependencies: vec![],
+                priority: 40,
+                timestamp: chrono::Utc::now().timestamp() as u64,
+                node_id: "benchmark".to_string(),
+                status: DAGTxStatus::Pending,
+                gas_estimate: U256::from(21_000),
+            })
+            .collect();
+
+        let start_time = Instant::now();
+        let confirmed = self.process_transaction_batch(transactions).await?.len();
+        let duration = start_time.elapsed();
+
+        Ok(BackendBenchmark {
+            backend: self.backend.name().to_string(),
+            transactions: confirmed,
+            duration,
+            throughput_tps: confirmed as f64 / duration.as_secs_f64(),
+        })
+    }
+
     /// Start real-time event monitoring
     pub async fn start_event_monitoring(&self) -> Result<()> {
         if let Some(ws_provider) = &self.ws_provider {
@@ -500,10 +1420,33 @@ impl U2UClient {
             
             // Monitor new blocks
             let mut stream = ws_provider.subscribe_blocks().await?;
+            let tracker = self.chain_tracker.clone();
+            let provider = self.provider.clone();
+            let cache = self.call_cache.clone();
             
             tokio::spawn(async move {
                 while let Some(block) = stream.next().await {
                     debug!("📦 New U2U block: {}", block.number.unwrap_or_default());
+
+                    if let Some(head) = BlockRef::from_block(&block) {
+                        if let Err(e) = Self::apply_block(&tracker, &provider, head).await {
+                            warn!("Chain tracker update failed: {}", e);
+                        }
+                        cache.on_new_block(head.number);
+                    }
+                }
+            });
+
+            // Invalidate cached reads when DAGShield contracts emit events
+            let addresses = self.config.contract_addresses.all();
+            let mut logs = ws_provider
+                .subscribe_logs(&Filter::new().address(addresses))
+                .await?;
+            let cache = self.call_cache.clone();
+
+            tokio::spawn(async move {
+                while let Some(log) = logs.next().await {
+                    cache.invalidate_contract(log.address);
                 }
             });
         }
@@ -511,6 +1454,279 @@ impl U2UClient {
         Ok(())
     }
 
+    /// Feed a new head into the chain tracker, backfilling missing ancestors
+    async fn apply_block(
+        tracker: &RwLock<ChainTracker>,
+        provider: &Provider<Http>,
+        head: BlockRef,
+    ) -> Result<()> {
+        // Blocks waiting for their parent, newest first
+        let mut pending = vec![head];
+
+        while let Some(block) = pending.last().copied() {
+            let update = tracker.write().unwrap().ingest(block);
+
+            match update {
+                ChainUpdate::UnknownParent(parent_hash) => {
+                    let max_depth = tracker.read().unwrap().max_depth();
+                    if pending.len() >= max_depth {
+                        warn!("⚠️ Fork deeper than {} blocks, resetting chain tracker", max_depth);
+                        tracker.write().unwrap().reset(head);
+                        return Ok(());
+                    }
+
+                    let parent = provider
+                        .get_block(parent_hash)
+                        .await?
+                        .and_then(|b| BlockRef::from_block(&b))
+                        .context("Parent block not available")?;
+                    pending.push(parent);
+                    continue;
+                }
+                ChainUpdate::Reorg { depth, old_tip, new_tip } => {
+                    warn!(
+                        "🔀 Chain reorg of depth {}: {} ({:?}) -> {} ({:?})",
+                        depth, old_tip.number, old_tip.hash, new_tip.number, new_tip.hash
+                    );
+                }
+                ChainUpdate::Reset(block) => {
+                    warn!("🔀 Deep reorg, chain tracker restarted at block {}", block.number);
+                }
+                ChainUpdate::Extended(_) | ChainUpdate::Duplicate => {}
+            }
+
+            pending.pop();
+        }
+
+        Ok(())
+    }
+
+    /// Poll the latest head over HTTP (used when no WebSocket stream is running)
+    async fn sync_head(&self) -> Result<()> {
+        let head = self
+            .provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .and_then(|b| BlockRef::from_block(&b))
+            .context("Latest block not available")?;
+
+        Self::apply_block(&self.chain_tracker, &self.provider, head).await
+    }
+
+    /// Highest block considered final on the canonical chain
+    pub fn latest_safe_block(&self) -> Option<u64> {
+        self.chain_tracker.read().unwrap().latest_safe_block()
+    }
+
+    /// eth_call through the per-contract read cache
+    pub async fn cached_call(&self, contract: Address, calldata: Bytes) -> Result<Bytes> {
+        let head = self.chain_tracker.read().unwrap().tip().map(|b| b.number);
+        let head = match head {
+            Some(number) => number,
+            None => self.provider.get_block_number().await?.as_u64(),
+        };
+
+        if let Some(value) = self.call_cache.get(contract, &calldata, head) {
+            return Ok(value);
+        }
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(contract)
+            .data(calldata.clone())
+            .into();
+        let value = self.provider
+            .call(&tx, Some(BlockId::Number(BlockNumber::Number(head.into()))))
+            .await
+            .context("Contract read failed")?;
+
+        self.call_cache.insert(contract, calldata, value.clone(), head);
+        Ok(value)
+    }
+
+    /// Read a node's reputation (0-100) from the registry's `getNodeInfo` (served from the read cache)
+    pub async fn get_node_reputation(&self, node_id: &str) -> Result<u64> {
+        let registry = NodeRegistryReputation::new(
+            self.config.contract_addresses.node_registry,
+            self.provider.clone(),
+        );
+        let calldata = registry
+            .get_node_info(node_id.to_string())
+            .calldata()
+            .context("Failed to encode getNodeInfo call")?;
+
+        let raw = self.cached_call(registry.address(), calldata).await?;
+        reputation_from_node_info(&raw)
+    }
+
+    /// Registered operator and stake of a node (served from the read cache)
+    pub async fn get_node_stake(&self, node_id: &str) -> Result<(Address, U256)> {
+        let registry = NodeRegistryReputation::new(
+            self.config.contract_addresses.node_registry,
+            self.provider.clone(),
+        );
+        let calldata = registry
+            .get_node_info(node_id.to_string())
+            .calldata()
+            .context("Failed to encode getNodeInfo call")?;
+
+        let raw = self.cached_call(registry.address(), calldata).await?;
+        stake_from_node_info(&raw)
+    }
+
+    /// Check whether the threat detector contract already holds a report with this hash
+    pub async fn threat_exists_on_chain(&self, hash: H256) -> Result<bool> {
+        let detector = ThreatDetectorRegistry::new(
+            self.config.contract_addresses.threat_detector,
+            self.provider.clone(),
+        );
+        let calldata = detector
+            .threat_exists(hash.into())
+            .calldata()
+            .context("Failed to encode threatExists call")?;
+
+        let raw = self.cached_call(detector.address(), calldata).await?;
+        bool::decode(raw).context("Invalid threatExists response")
+    }
+
+    /// keccak256 of the Powers of Tau transcript the network's ZK keys must be derived from
+    pub async fn get_powers_of_tau_hash(&self) -> Result<[u8; 32]> {
+        let detector = ThreatDetectorRegistry::new(
+            self.config.contract_addresses.threat_detector,
+            self.provider.clone(),
+        );
+        let calldata = detector
+            .powers_of_tau_hash()
+            .calldata()
+            .context("Failed to encode powersOfTauHash call")?;
+
+        let raw = self.cached_call(detector.address(), calldata).await?;
+        Ok(H256::decode(raw).context("Invalid powersOfTauHash response")?.0)
+    }
+
+    /// Merkle root of the known threat signatures signature match proofs are checked against
+    pub async fn get_threat_signature_root(&self) -> Result<[u8; 32]> {
+        let oracle = DAGShieldOracleEnergy::new(
+            self.config.contract_addresses.dagshield_oracle,
+            self.provider.clone(),
+        );
+        let calldata = oracle
+            .threat_signature_root()
+            .calldata()
+            .context("Failed to encode threatSignatureRoot call")?;
+
+        let raw = self.cached_call(oracle.address(), calldata).await?;
+        Ok(H256::decode(raw).context("Invalid threatSignatureRoot response")?.0)
+    }
+
+    /// Commitment to the currently registered AI model; threat proofs must be bound to it
+    pub async fn get_model_commitment(&self) -> Result<[u8; 32]> {
+        let oracle = DAGShieldOracleEnergy::new(
+            self.config.contract_addresses.dagshield_oracle,
+            self.provider.clone(),
+        );
+        let calldata = oracle
+            .model_commitment()
+            .calldata()
+            .context("Failed to encode modelCommitment call")?;
+
+        let raw = self.cached_call(oracle.address(), calldata).await?;
+        Ok(H256::decode(raw).context("Invalid modelCommitment response")?.0)
+    }
+
+    /// keccak256 of the verifying key registered for circuit `circuit`; zero when none is
+    pub async fn get_verifying_key_hash(&self, circuit: u8) -> Result<[u8; 32]> {
+        let oracle = DAGShieldOracleEnergy::new(
+            self.config.contract_addresses.dagshield_oracle,
+            self.provider.clone(),
+        );
+        let calldata = oracle
+            .verifying_key_hash(circuit)
+            .calldata()
+            .context("Failed to encode verifyingKeyHash call")?;
+
+        let raw = self.cached_call(oracle.address(), calldata).await?;
+        Ok(H256::decode(raw).context("Invalid verifyingKeyHash response")?.0)
+    }
+
+    /// Check `prover`'s keys against the verifying keys registered on the oracle; circuits the
+    /// prover holds no keys for are skipped
+    pub async fn sync_verifying_keys(&self, prover: &ZKProver) -> Result<()> {
+        for kind in CircuitKind::ALL {
+            let hash = self.get_verifying_key_hash(kind.id())
+                .await
+                .with_context(|| format!("Failed to read the registered {:?} verifying key", kind))?;
+            if let Err(e) = prover.set_registered_vk_hash(kind, hash) {
+                debug!("Skipping {:?} verifying key registration: {:#}", kind, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sync `prover` with the oracle at startup and every `zk.chain_sync_secs`, so circuit
+    /// upgrades and revocations are noticed
+    pub async fn run_prover_sync(&self, prover: Arc<ZKProver>) -> Result<()> {
+        let mut sync = interval(Duration::from_secs(prover.config.chain_sync_secs));
+
+        loop {
+            sync.tick().await;
+
+            if let Err(e) = self.sync_verifying_keys(&prover).await {
+                warn!("Verifying key sync failed: {:#}", e);
+            }
+            if prover.config.revocation_signer.is_some() {
+                if let Err(e) = self.sync_revocation_list(&prover).await {
+                    warn!("Revocation list sync failed: {:#}", e);
+                }
+            }
+        }
+    }
+
+    /// Signed proof revocation list published by the oracle; `None` before one is published
+    pub async fn get_revocation_list(&self) -> Result<Option<SignedRevocationList>> {
+        let oracle = DAGShieldOracleEnergy::new(
+            self.config.contract_addresses.dagshield_oracle,
+            self.provider.clone(),
+        );
+        let calldata = oracle
+            .revocation_list()
+            .calldata()
+            .context("Failed to encode revocationList call")?;
+
+        let raw = self.cached_call(oracle.address(), calldata).await?;
+        let (list, signature) = <(Bytes, Bytes)>::decode(raw).context("Invalid revocationList response")?;
+        if list.is_empty() {
+            return Ok(None);
+        }
+        SignedRevocationList::from_oracle(&list, &signature).map(Some)
+    }
+
+    /// Apply the oracle's revocation list to `prover` if it is newer than the one it holds
+    pub async fn sync_revocation_list(&self, prover: &ZKProver) -> Result<()> {
+        let Some(signed) = self.get_revocation_list().await? else {
+            return Ok(());
+        };
+        if signed.list.version > prover.revocations.read().unwrap().version {
+            prover.apply_revocation_list(signed)?;
+        }
+        Ok(())
+    }
+
+    /// Reputation expected after all pending submissions resolve
+    pub async fn predicted_reputation(&self, node_id: &str) -> Result<f64> {
+        let current = self.get_node_reputation(node_id).await? as f64;
+        Ok(self.reputation_model.read().unwrap().predicted_reputation(current))
+    }
+
+    /// Feed whether a submitted threat landed or reverted back into the reputation model
+    fn record_submission_outcome(&self, tx_id: &str, confirmed: bool) {
+        self.reputation_model.write().unwrap().record_outcome(tx_id, confirmed);
+    }
+
+    /// Read cache hit-rate metrics
+    pub fn get_cache_stats(&self) -> CacheStats {
+        self.call_cache.stats()
+    }
+
     // Additional helper methods would be implemented here...
     async fn process_dag_transaction(&self, _tx: DAGTransaction) -> Result<()> {
         // Implementation for DAG transaction processing
@@ -524,21 +1740,87 @@ impl U2UClient {
 
     async fn submit_dag_transaction(
         &self,
-        _tx_type: DAGTxType,
-        _data: Bytes,
-        _dependencies: Vec<String>,
-        _node_id: &str,
+        tx_type: DAGTxType,
+        data: Bytes,
+        dependencies: Vec<String>,
+        node_id: &str,
     ) -> Result<String> {
-        // Implementation for DAG transaction submission
-        Ok(Uuid::new_v4().to_string())
+        let dag_tx = DAGTransaction {
+            id: Uuid::new_v4().to_string(),
+            tx_type,
+            data,
+            dependencies,
+            priority: 60,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            node_id: node_id.to_string(),
+            status: DAGTxStatus::Pending,
+            gas_estimate: self.config.dag_config.gas_limit,
+        };
+        let tx_id = dag_tx.id.clone();
+
+        self.tx_pool.write().unwrap().insert(tx_id.clone(), dag_tx.clone());
+        self.process_transaction_batch(vec![dag_tx]).await?;
+
+        Ok(tx_id)
     }
 
-    async fn wait_for_dag_confirmation(&self, _tx_id: &str) -> Result<H256> {
-        // Implementation for waiting for confirmation
-        Ok(H256::zero())
+    /// Wait until a DAG transaction is included in a canonical block at or below the safe head
+    async fn wait_for_dag_confirmation(&self, tx_id: &str) -> Result<H256> {
+        let tx_hash = self.dag_processor.read().unwrap()
+            .completed_txs
+            .get(tx_id)
+            .copied()
+            .context("Unknown DAG transaction")?;
+
+        // Simulated transactions never land on-chain
+        if self.simulator.is_some() {
+            return Ok(tx_hash);
+        }
+
+        let timeout = Duration::from_secs(self.config.dag_config.confirmation_timeout_secs);
+        let deadline = Instant::now() + timeout;
+        let mut poll = interval(Duration::from_secs(1));
+
+        loop {
+            poll.tick().await;
+
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "DAG transaction {} ({:?}) not confirmed within {:?}",
+                    tx_id, tx_hash, timeout
+                ));
+            }
+
+            if self.ws_provider.is_none() {
+                self.sync_head().await?;
+            }
+
+            let Some(receipt) = self.provider.get_transaction_receipt(tx_hash).await? else {
+                continue;
+            };
+            let (Some(number), Some(block_hash)) = (receipt.block_number, receipt.block_hash) else {
+                continue;
+            };
+
+            let confirmed = self.chain_tracker.read().unwrap()
+                .is_confirmed(number.as_u64(), block_hash);
+            if confirmed {
+                debug!("✅ DAG transaction {} confirmed in block {}", tx_id, number);
+                return Ok(tx_hash);
+            }
+        }
     }
 }
 
+/// Result of a provider backend throughput benchmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendBenchmark {
+    pub backend: String,
+    pub transactions: usize,
+    pub duration: Duration,
+    pub throughput_tps: f64,
+}
+
 /// DePIN Node Information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DePINNodeInfo {
@@ -552,7 +1834,7 @@ pub struct DePINNodeInfo {
     pub hardware_specs: HardwareSpecs,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DeviceType {
     Mobile,
     Desktop,
@@ -577,6 +1859,24 @@ pub struct HardwareSpecs {
     pub storage_gb: u32,
     pub network_bandwidth_mbps: u32,
     pub power_consumption_watts: f64,
+    /// Battery wear, from `EnergyMonitor::hardware_attestation`
+    #[serde(default)]
+    pub battery_health: Vec<BatteryHealth>,
+}
+
+impl HardwareSpecs {
+    /// Specs as attested by the power monitor, battery health included
+    pub fn attested(monitor: &EnergyMonitor, storage_gb: u32, network_bandwidth_mbps: u32) -> Self {
+        let specs = monitor.hardware_attestation();
+        Self {
+            cpu_cores: specs.cpu_cores,
+            ram_gb: specs.memory_size_gb,
+            storage_gb,
+            network_bandwidth_mbps,
+            power_consumption_watts: monitor.latest_reading().map_or(0.0, |reading| reading.total_watts),
+            battery_health: specs.battery_health,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -619,4 +1919,31 @@ mod tests {
 
         // Test sorting logic here
     }
+
+    #[test]
+    fn test_dag_transaction_targets_its_contract() {
+        let addresses = ContractAddresses {
+            dagshield_token: Address::from_low_u64_be(1),
+            dagshield_oracle: Address::from_low_u64_be(2),
+            node_registry: Address::from_low_u64_be(3),
+            threat_detector: Address::from_low_u64_be(4),
+        };
+        let tx = DAGTransaction {
+            id: "tx1".to_string(),
+            dependencies: vec![],
+            priority: 80,
+            tx_type: DAGTxType::ThreatSubmission,
+            data: Bytes::from(vec![0xde, 0xad]),
+            timestamp: 0,
+            node_id: "node1".to_string(),
+            status: DAGTxStatus::Pending,
+            gas_estimate: U256::from(500_000),
+        };
+
+        let request = tx.request(addresses.target(&tx.tx_type));
+        assert_eq!(request.to, Some(addresses.threat_detector.into()));
+        assert_eq!(request.data, Some(tx.data.clone()));
+        assert_eq!(addresses.target(&DAGTxType::RewardClaim), addresses.node_registry);
+        assert_eq!(addresses.target(&DAGTxType::CrossChainRelay), addresses.dagshield_oracle);
+    }
 }
@@ -0,0 +1,127 @@
+/*!
+ * alloy provider/signer backend for the U2U client
+ * Drop-in replacement for the ethers middleware stack on the transaction hot path
+ */
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address as AlloyAddress, Bytes as AlloyBytes, U256 as AlloyU256},
+    providers::{Provider as AlloyProvider, ProviderBuilder},
+    rpc::types::eth::TransactionRequest as AlloyTransactionRequest,
+    signers::local::PrivateKeySigner,
+    transports::http::{Client, Http as AlloyHttp},
+};
+use anyhow::{Context, Result};
+use ethers::types::{Address, H256};
+use std::sync::Arc;
+use tracing::info;
+
+use super::{BackendBenchmark, DAGTransaction, ProviderKind, U2UClient, U2UConfig};
+
+/// alloy-based transaction backend
+pub struct AlloyBackend {
+    provider: Arc<dyn AlloyProvider<AlloyHttp<Client>>>,
+}
+
+impl AlloyBackend {
+    /// Create backend with recommended fillers (nonce, gas, chain id) and a local signer
+    pub fn new(rpc_url: &str, private_key: &str) -> Result<Self> {
+        let signer: PrivateKeySigner = private_key
+            .trim_start_matches("0x")
+            .parse()
+            .context("Invalid private key")?;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer))
+            .on_http(rpc_url.parse().context("Invalid RPC URL")?);
+
+        Ok(Self {
+            provider: Arc::new(provider),
+        })
+    }
+
+    /// Get chain ID reported by the RPC
+    pub async fn chain_id(&self) -> Result<u64> {
+        Ok(self.provider.get_chain_id().await?)
+    }
+
+    /// Get latest block number
+    pub async fn block_number(&self) -> Result<u64> {
+        Ok(self.provider.get_block_number().await?)
+    }
+
+    /// Sign and broadcast a DAG transaction to contract `to`, returning its hash without
+    /// waiting for inclusion
+    pub async fn send_dag_transaction(&self, dag_tx: &DAGTransaction, to: Address) -> Result<H256> {
+        let pending = self
+            .provider
+            .send_transaction(transaction_request(dag_tx, to))
+            .await
+            .context("Transaction broadcast failed")?;
+
+        Ok(H256::from_slice(pending.tx_hash().as_slice()))
+    }
+}
+
+/// Call to `to` carrying the DAG transaction's calldata and gas estimate
+fn transaction_request(dag_tx: &DAGTransaction, to: Address) -> AlloyTransactionRequest {
+    AlloyTransactionRequest::default()
+        .with_to(AlloyAddress::from_slice(to.as_bytes()))
+        .with_input(AlloyBytes::copy_from_slice(&dag_tx.data))
+        .with_gas_limit(dag_tx.gas_estimate.as_u128())
+        .with_value(AlloyU256::ZERO)
+}
+
+/// Run the DAG batch benchmark against both backends with the same config
+pub async fn compare_backends(
+    config: &U2UConfig,
+    tx_count: usize,
+) -> Result<(BackendBenchmark, BackendBenchmark)> {
+    let mut ethers_config = config.clone();
+    ethers_config.provider_backend = ProviderKind::Ethers;
+    let ethers_result = U2UClient::new(ethers_config)
+        .await?
+        .benchmark_batch_throughput(tx_count)
+        .await?;
+
+    let mut alloy_config = config.clone();
+    alloy_config.provider_backend = ProviderKind::Alloy;
+    let alloy_result = U2UClient::new(alloy_config)
+        .await?
+        .benchmark_batch_throughput(tx_count)
+        .await?;
+
+    info!("📊 Provider backend comparison ({} txs):", tx_count);
+    info!("   ethers: {:.2} TPS ({:?})", ethers_result.throughput_tps, ethers_result.duration);
+    info!("   alloy:  {:.2} TPS ({:?})", alloy_result.throughput_tps, alloy_result.duration);
+
+    Ok((ethers_result, alloy_result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u2u_integration::{DAGTxStatus, DAGTxType};
+    use ethers::types::{Bytes, U256};
+
+    #[test]
+    fn test_request_calls_target_contract() {
+        let tx = DAGTransaction {
+            id: "tx1".to_string(),
+            tx_type: DAGTxType::ThreatSubmission,
+            data: Bytes::from(vec![0xde, 0xad]),
+            dependencies: vec![],
+            priority: 80,
+            timestamp: 0,
+            node_id: "node1".to_string(),
+            status: DAGTxStatus::Pending,
+            gas_estimate: U256::from(500_000),
+        };
+        let to = Address::from_low_u64_be(4);
+
+        let request = transaction_request(&tx, to);
+        assert_eq!(TransactionBuilder::to(&request), Some(AlloyAddress::from_slice(to.as_bytes())));
+        assert_eq!(request.input.input().map(|input| input.to_vec()), Some(vec![0xde, 0xad]));
+    }
+}
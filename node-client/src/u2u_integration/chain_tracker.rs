@@ -0,0 +1,219 @@
+/*!
+ * Canonical chain tracking for the U2U block stream
+ * Keeps a sliding window of recent block hashes and resolves small forks
+ */
+
+use ethers::types::{Block, H256};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Minimal block header kept by the tracker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub number: u64,
+    pub hash: H256,
+    pub parent_hash: H256,
+}
+
+impl BlockRef {
+    /// Build from an RPC block, `None` for pending blocks without number/hash
+    pub fn from_block<T>(block: &Block<T>) -> Option<Self> {
+        Some(Self {
+            number: block.number?.as_u64(),
+            hash: block.hash?,
+            parent_hash: block.parent_hash,
+        })
+    }
+}
+
+/// Outcome of feeding a block into the tracker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainUpdate {
+    /// Block extended the canonical tip
+    Extended(BlockRef),
+    /// Block already known on the canonical chain
+    Duplicate,
+    /// Block replaced `depth` canonical blocks
+    Reorg {
+        depth: u64,
+        old_tip: BlockRef,
+        new_tip: BlockRef,
+    },
+    /// Fork point is older than the tracked window; window restarted from this block
+    Reset(BlockRef),
+    /// Parent is not tracked yet; caller must ingest the parent first
+    UnknownParent(H256),
+}
+
+/// Tracks the last N canonical blocks
+#[derive(Debug, Clone)]
+pub struct ChainTracker {
+    max_depth: usize,
+    confirmation_blocks: u64,
+    blocks: VecDeque<BlockRef>,
+}
+
+impl ChainTracker {
+    /// Create tracker keeping `max_depth` blocks
+    pub fn new(max_depth: usize, confirmation_blocks: u64) -> Self {
+        Self {
+            max_depth: max_depth.max(1),
+            confirmation_blocks,
+            blocks: VecDeque::with_capacity(max_depth),
+        }
+    }
+
+    /// Current canonical tip
+    pub fn tip(&self) -> Option<&BlockRef> {
+        self.blocks.back()
+    }
+
+    /// Highest block considered final (tip minus confirmation depth)
+    pub fn latest_safe_block(&self) -> Option<u64> {
+        self.tip()?.number.checked_sub(self.confirmation_blocks)
+    }
+
+    /// Maximum number of tracked blocks
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Check whether a block is on the tracked canonical chain
+    pub fn is_canonical(&self, number: u64, hash: H256) -> bool {
+        self.get(number).map(|b| b.hash == hash).unwrap_or(false)
+    }
+
+    /// Check a block is canonical and at or below the safe head.
+    /// Blocks older than the tracked window are treated as final.
+    pub fn is_confirmed(&self, number: u64, hash: H256) -> bool {
+        let (Some(safe), Some(oldest)) = (self.latest_safe_block(), self.blocks.front()) else {
+            return false;
+        };
+
+        number <= safe && (number < oldest.number || self.is_canonical(number, hash))
+    }
+
+    /// Get canonical block at height, if tracked
+    pub fn get(&self, number: u64) -> Option<&BlockRef> {
+        let oldest = self.blocks.front()?.number;
+        let index = number.checked_sub(oldest)? as usize;
+        self.blocks.get(index)
+    }
+
+    /// Feed a new block. Never mutates state when returning `UnknownParent`.
+    pub fn ingest(&mut self, block: BlockRef) -> ChainUpdate {
+        let Some(tip) = self.tip().copied() else {
+            self.blocks.push_back(block);
+            return ChainUpdate::Extended(block);
+        };
+
+        if self.is_canonical(block.number, block.hash) {
+            return ChainUpdate::Duplicate;
+        }
+
+        if block.number == tip.number + 1 && block.parent_hash == tip.hash {
+            self.push(block);
+            return ChainUpdate::Extended(block);
+        }
+
+        // Fork: find the parent on the canonical window
+        if let Some(parent) = block.number.checked_sub(1).and_then(|n| self.get(n)) {
+            if parent.hash == block.parent_hash {
+                let keep = (parent.number - self.blocks[0].number + 1) as usize;
+                let depth = (self.blocks.len() - keep) as u64;
+                self.blocks.truncate(keep);
+                self.push(block);
+                return ChainUpdate::Reorg {
+                    depth,
+                    old_tip: tip,
+                    new_tip: block,
+                };
+            }
+        }
+
+        let oldest = self.blocks[0].number;
+        if block.number <= oldest {
+            // Fork below our window, nothing to reconcile against
+            self.blocks.clear();
+            self.blocks.push_back(block);
+            return ChainUpdate::Reset(block);
+        }
+
+        ChainUpdate::UnknownParent(block.parent_hash)
+    }
+
+    /// Restart tracking from a single block
+    pub fn reset(&mut self, block: BlockRef) {
+        self.blocks.clear();
+        self.blocks.push_back(block);
+    }
+
+    fn push(&mut self, block: BlockRef) {
+        self.blocks.push_back(block);
+        while self.blocks.len() > self.max_depth {
+            self.blocks.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64, id: u64, parent: u64) -> BlockRef {
+        BlockRef {
+            number,
+            hash: H256::from_low_u64_be(id),
+            parent_hash: H256::from_low_u64_be(parent),
+        }
+    }
+
+    #[test]
+    fn test_extends_and_trims_window() {
+        let mut tracker = ChainTracker::new(3, 1);
+        for n in 1..=5 {
+            assert!(matches!(tracker.ingest(block(n, n, n - 1)), ChainUpdate::Extended(_)));
+        }
+        assert_eq!(tracker.tip().unwrap().number, 5);
+        assert!(tracker.get(2).is_none());
+        assert_eq!(tracker.latest_safe_block(), Some(4));
+        assert!(tracker.is_confirmed(4, H256::from_low_u64_be(4)));
+        assert!(tracker.is_confirmed(1, H256::from_low_u64_be(99)));
+        assert!(!tracker.is_confirmed(5, H256::from_low_u64_be(5)));
+    }
+
+    #[test]
+    fn test_reorg_replaces_tip() {
+        let mut tracker = ChainTracker::new(10, 2);
+        for n in 1..=4 {
+            tracker.ingest(block(n, n, n - 1));
+        }
+
+        // Competing block 3' built on block 2
+        let update = tracker.ingest(block(3, 33, 2));
+        assert!(matches!(update, ChainUpdate::Reorg { depth: 2, .. }));
+        assert!(tracker.is_canonical(3, H256::from_low_u64_be(33)));
+        assert!(tracker.get(4).is_none());
+    }
+
+    #[test]
+    fn test_unknown_parent_is_not_applied() {
+        let mut tracker = ChainTracker::new(10, 2);
+        tracker.ingest(block(1, 1, 0));
+        tracker.ingest(block(2, 2, 1));
+
+        let update = tracker.ingest(block(4, 44, 43));
+        assert_eq!(update, ChainUpdate::UnknownParent(H256::from_low_u64_be(43)));
+        assert_eq!(tracker.tip().unwrap().number, 2);
+
+        tracker.ingest(block(3, 43, 2));
+        assert!(matches!(tracker.ingest(block(4, 44, 43)), ChainUpdate::Extended(_)));
+    }
+
+    #[test]
+    fn test_duplicate_block() {
+        let mut tracker = ChainTracker::new(10, 2);
+        tracker.ingest(block(1, 1, 0));
+        assert_eq!(tracker.ingest(block(1, 1, 0)), ChainUpdate::Duplicate);
+    }
+}
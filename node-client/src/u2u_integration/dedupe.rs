@@ -0,0 +1,339 @@
+/*!
+ * Threat submission deduplication
+ * Several detectors often flag the same transaction; only the first report is submitted. Reorgs
+ * replay transactions into new blocks, where detectors fire on them again with reports that
+ * may differ in confidence or wording, so detections of a transaction are also deduplicated by
+ * (transaction hash, category). Each submission is anchored to the canonical tip it was made
+ * against; a re-fire after that block was reorged out is a replay and suppressed, while a
+ * different target or evidence for the same transaction is a new variant and goes through.
+ * Submissions are kept until their anchor is final, when no reorg can replay them any more
+ */
+
+use ethers::{prelude::*, utils::keccak256};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use super::chain_tracker::{BlockRef, ChainTracker};
+use crate::detection::{Detection, ThreatCategory};
+
+abigen!(
+    ThreatDetectorRegistry,
+    r#"[
+        function threatExists(bytes32 threatHash) external view returns (bool)
+        function powersOfTauHash() external view returns (bytes32)
+        function submitThreatWithProof(bytes32 threatHash, bytes data, bytes proof) external
+    ]"#
+);
+
+/// Deduplication settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupeConfig {
+    pub enabled: bool,
+    /// How long a submitted threat hash suppresses identical reports
+    pub window_secs: u64,
+    /// Ask the threat detector contract (`threatExists`) before submitting a hash not seen
+    /// locally; only for deployments whose detector contract implements it
+    pub check_on_chain: bool,
+    /// Also deduplicate detections by (transaction hash, category) across reorgs
+    #[serde(default = "default_fork_aware")]
+    pub fork_aware: bool,
+}
+
+fn default_fork_aware() -> bool {
+    true
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 600,
+            check_on_chain: false,
+            fork_aware: true,
+        }
+    }
+}
+
+/// Content hash identifying a threat report
+pub fn threat_hash(threat_data: &[u8]) -> H256 {
+    H256::from(keccak256(threat_data))
+}
+
+/// Sliding window of recently submitted threat hashes
+#[derive(Debug, Clone)]
+pub struct SubmissionDeduper {
+    window_secs: u64,
+    submitted: HashMap<H256, String>,
+    order: VecDeque<(u64, H256)>,
+}
+
+impl SubmissionDeduper {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            submitted: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Drop hashes that fell out of the window
+    fn expire(&mut self, now: u64) {
+        while let Some(&(seen_at, hash)) = self.order.front() {
+            if now.saturating_sub(seen_at) < self.window_secs {
+                break;
+            }
+            self.order.pop_front();
+            self.submitted.remove(&hash);
+        }
+    }
+
+    /// Transaction ID of an earlier submission of the same threat inside the window
+    pub fn existing(&mut self, hash: &H256, now: u64) -> Option<String> {
+        self.expire(now);
+        self.submitted.get(hash).cloned()
+    }
+
+    /// Remember a submission
+    pub fn record(&mut self, hash: H256, tx_id: &str, now: u64) {
+        self.expire(now);
+        if self.submitted.insert(hash, tx_id.to_string()).is_none() {
+            self.order.push_back((now, hash));
+        }
+    }
+
+    /// Earlier submission of the same threat, or else reserve the hash for `tx_id` in the same
+    /// step so a concurrent report of it cannot also go out
+    pub fn reserve(&mut self, hash: H256, tx_id: &str, now: u64) -> Option<String> {
+        if let Some(existing) = self.existing(&hash, now) {
+            return Some(existing);
+        }
+        self.record(hash, tx_id, now);
+        None
+    }
+
+    /// Forget a submission, e.g. after it failed and may be retried
+    pub fn forget(&mut self, hash: &H256) {
+        if self.submitted.remove(hash).is_some() {
+            self.order.retain(|(_, h)| h != hash);
+        }
+    }
+
+    /// Number of hashes inside the window
+    pub fn len(&self) -> usize {
+        self.submitted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.submitted.is_empty()
+    }
+}
+
+/// Earlier submission a detection duplicates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateDetection {
+    pub submission: String,
+    /// The chain it was submitted against was reorged since
+    pub replayed: bool,
+}
+
+/// Detections of one transaction in one category
+#[derive(Debug, Clone)]
+struct SubmittedDetection {
+    /// Canonical tip when first submitted; `None` if no chain was tracked yet
+    anchor: Option<BlockRef>,
+    /// Submission of each variant, by its target and evidence
+    variants: HashMap<H256, String>,
+    submitted_at: u64,
+}
+
+/// Submission a reserved detection holds until `ReplayDeduper::record` names the real one
+pub const PENDING_SUBMISSION: &str = "pending";
+
+/// Detections submitted per (transaction, category), kept until their anchor is final
+#[derive(Debug, Clone)]
+pub struct ReplayDeduper {
+    /// How long detections without a final anchor are kept at least
+    window_secs: u64,
+    submitted: HashMap<(H256, ThreatCategory), SubmittedDetection>,
+}
+
+impl ReplayDeduper {
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            submitted: HashMap::new(),
+        }
+    }
+
+    /// What distinguishes findings on the same transaction: target, evidence and URL
+    fn variant(detection: &Detection) -> H256 {
+        let target = detection.target.map(|target| target.as_bytes().to_vec()).unwrap_or_default();
+        let evidence = detection.evidence.map(|evidence| evidence.as_bytes().to_vec()).unwrap_or_default();
+        let url = detection.url.as_deref().unwrap_or_default().as_bytes();
+        threat_hash(&[target.as_slice(), evidence.as_slice(), url].join(&0u8))
+    }
+
+    /// Drop detections whose anchor is final and older than the window; unanchored ones go
+    /// after the window
+    fn expire(&mut self, chain: &ChainTracker, now: u64) {
+        let window_secs = self.window_secs;
+        self.submitted.retain(|_, submitted| {
+            let final_anchor = submitted.anchor.map_or(true, |anchor| chain.is_confirmed(anchor.number, anchor.hash));
+            !final_anchor || now.saturating_sub(submitted.submitted_at) < window_secs
+        });
+    }
+
+    /// Earlier submission of the same finding on the same transaction. A replay is re-anchored
+    /// to the current tip, so it is followed to finality on its new block
+    pub fn existing(&mut self, detection: &Detection, chain: &ChainTracker, now: u64) -> Option<DuplicateDetection> {
+        self.expire(chain, now);
+        let tx_hash = detection.tx_hash?;
+        let submitted = self.submitted.get_mut(&(tx_hash, detection.threat_type))?;
+        let submission = submitted.variants.get(&Self::variant(detection))?.clone();
+        let replayed = submitted.anchor.is_some_and(|anchor| !chain.is_canonical(anchor.number, anchor.hash));
+        if replayed {
+            submitted.anchor = chain.tip().copied();
+        }
+        Some(DuplicateDetection { submission, replayed })
+    }
+
+    /// Remember a submitted detection, anchored to the current tip
+    pub fn record(&mut self, detection: &Detection, submission: &str, chain: &ChainTracker, now: u64) {
+        let Some(tx_hash) = detection.tx_hash else { return };
+        let submitted = self
+            .submitted
+            .entry((tx_hash, detection.threat_type))
+            .or_insert_with(|| SubmittedDetection {
+                anchor: chain.tip().copied(),
+                variants: HashMap::new(),
+                submitted_at: now,
+            });
+        submitted.variants.insert(Self::variant(detection), submission.to_string());
+    }
+
+    /// Earlier submission of the same finding, or else reserve it in the same step so a
+    /// concurrent report of it can't also go out. `record` the submission once it is made, or
+    /// `forget` the reservation if it failed
+    pub fn reserve(&mut self, detection: &Detection, chain: &ChainTracker, now: u64) -> Option<DuplicateDetection> {
+        if let Some(duplicate) = self.existing(detection, chain, now) {
+            return Some(duplicate);
+        }
+        self.record(detection, PENDING_SUBMISSION, chain, now);
+        None
+    }
+
+    /// Forget a detection, e.g. after its submission failed and may be retried
+    pub fn forget(&mut self, detection: &Detection) {
+        let Some(tx_hash) = detection.tx_hash else { return };
+        let key = (tx_hash, detection.threat_type);
+        if let Some(submitted) = self.submitted.get_mut(&key) {
+            submitted.variants.remove(&Self::variant(detection));
+            if submitted.variants.is_empty() {
+                self.submitted.remove(&key);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.submitted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.submitted.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_within_window() {
+        let mut deduper = SubmissionDeduper::new(60);
+        let hash = threat_hash(b"0xdeadbeef");
+
+        assert!(deduper.existing(&hash, 1_000).is_none());
+        deduper.record(hash, "tx_1", 1_000);
+
+        assert_eq!(deduper.existing(&hash, 1_030), Some("tx_1".to_string()));
+        assert!(deduper.existing(&threat_hash(b"0xfeed"), 1_030).is_none());
+    }
+
+    #[test]
+    fn test_window_expiry_and_forget() {
+        let mut deduper = SubmissionDeduper::new(60);
+        let first = threat_hash(b"first");
+        let second = threat_hash(b"second");
+
+        deduper.record(first, "tx_1", 1_000);
+        deduper.record(second, "tx_2", 1_050);
+
+        assert!(deduper.existing(&first, 1_060).is_none());
+        assert_eq!(deduper.len(), 1);
+
+        deduper.forget(&second);
+        assert!(deduper.is_empty());
+
+        assert!(deduper.reserve(second, "tx_3", 1_070).is_none());
+        assert_eq!(deduper.reserve(second, "tx_4", 1_071), Some("tx_3".to_string()));
+    }
+
+    #[test]
+    fn test_replays_suppressed_across_reorg() {
+        let block = |number: u64, id: u64, parent: u64| BlockRef {
+            number,
+            hash: H256::from_low_u64_be(id),
+            parent_hash: H256::from_low_u64_be(parent),
+        };
+        let mut chain = ChainTracker::new(10, 2);
+        for n in 1..=3 {
+            chain.ingest(block(n, n, n - 1));
+        }
+        let detection = Detection {
+            detector: "rules:drainer".to_string(),
+            threat_type: ThreatCategory::Drainer,
+            confidence: 0.8,
+            tx_hash: Some(H256::repeat_byte(7)),
+            target: Some(Address::repeat_byte(1)),
+            url: None,
+            explanation: "drains approvals in block 3".to_string(),
+            evidence: None,
+        };
+
+        let mut deduper = ReplayDeduper::new(60);
+        assert!(deduper.existing(&detection, &chain, 1_000).is_none());
+        deduper.record(&detection, "tx_1", &chain, 1_000);
+
+        // Block 3 is reorged out and the transaction replayed into 3': detectors re-fire with
+        // different confidence and wording, long after the hash window
+        chain.ingest(block(3, 33, 2));
+        let replay = Detection { confidence: 0.85, explanation: "drains approvals in block 3'".to_string(), ..detection.clone() };
+        let duplicate = DuplicateDetection { submission: "tx_1".to_string(), replayed: true };
+        assert_eq!(deduper.existing(&replay, &chain, 5_000), Some(duplicate));
+        // Re-anchored on 3', so it is no longer a replay
+        assert!(!deduper.existing(&replay, &chain, 5_000).unwrap().replayed);
+
+        // A new target on the same transaction, or another category, is a new finding
+        let variant = Detection { target: Some(Address::repeat_byte(2)), ..detection.clone() };
+        assert!(deduper.existing(&variant, &chain, 5_000).is_none());
+        deduper.record(&variant, "tx_2", &chain, 5_000);
+        assert_eq!(deduper.existing(&variant, &chain, 5_000).unwrap().submission, "tx_2");
+        assert!(deduper.existing(&Detection { threat_type: ThreatCategory::Phishing, ..detection.clone() }, &chain, 5_000).is_none());
+
+        // Once final and out of the window, it is forgotten
+        for n in 4..=6 {
+            chain.ingest(block(n, n, if n == 4 { 33 } else { n - 1 }));
+        }
+        assert!(deduper.existing(&replay, &chain, 5_100).is_none());
+        assert!(deduper.is_empty());
+
+        // A reservation holds off concurrent reports until it is recorded or forgotten
+        assert!(deduper.reserve(&detection, &chain, 6_000).is_none());
+        assert_eq!(deduper.reserve(&replay, &chain, 6_000).unwrap().submission, PENDING_SUBMISSION);
+        deduper.forget(&detection);
+        assert!(deduper.is_empty());
+        assert!(deduper.reserve(&detection, &chain, 6_000).is_none());
+        deduper.record(&detection, "tx_3", &chain, 6_000);
+        assert_eq!(deduper.reserve(&replay, &chain, 6_000).unwrap().submission, "tx_3");
+    }
+}
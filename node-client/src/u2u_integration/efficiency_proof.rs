@@ -0,0 +1,147 @@
+/*!
+ * On-chain energy efficiency proofs
+ * Nodes periodically sign a summary of their energy statistics and submit it to the
+ * DAGShield oracle, which weights rewards by the reported efficiency
+ */
+
+use ethers::{
+    abi::{encode, Token},
+    prelude::*,
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::energy_monitor::EnergyStats;
+
+abigen!(
+    DAGShieldOracleEnergy,
+    r#"[
+        function submitEnergyProof(string nodeId, uint256 periodStart, uint256 periodEnd, uint256 energyWh, uint256 avgPowerMilliwatts, uint256 efficiencyScore, uint256 carbonGrams, bytes signature) external
+        function threatSignatureRoot() external view returns (bytes32)
+        function modelCommitment() external view returns (bytes32)
+        function verifyingKeyHash(uint8 circuit) external view returns (bytes32)
+        function revocationList() external view returns (bytes list, bytes signature)
+        function submitEpochProof(string nodeId, uint256 epoch, uint256 threatCount, bytes32 digest, bytes proof) external
+    ]"#
+);
+
+const DOMAIN_TYPEHASH: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const ENERGY_PROOF_TYPEHASH: &str = "EnergyProof(string nodeId,uint256 periodStart,uint256 periodEnd,uint256 energyWh,uint256 avgPowerMilliwatts,uint256 efficiencyScore,uint256 carbonGrams)";
+
+/// Proof submission schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EfficiencyProofConfig {
+    pub enabled: bool,
+    /// Length of each reported period
+    pub interval_secs: u64,
+    /// Periods with less uptime are not submitted
+    pub min_uptime_hours: f64,
+}
+
+impl Default for EfficiencyProofConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 24 * 3600,
+            min_uptime_hours: 1.0,
+        }
+    }
+}
+
+/// Integer summary of `EnergyStats` over one period, as submitted on chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EfficiencyProof {
+    pub period_start: u64,
+    pub period_end: u64,
+    pub energy_wh: u64,
+    pub avg_power_milliwatts: u64,
+    /// Average efficiency score (0-100)
+    pub efficiency_score: u64,
+    /// Net of the node's renewable supply
+    pub carbon_grams: u64,
+}
+
+impl EfficiencyProof {
+    pub fn from_stats((period_start, period_end): (u64, u64), stats: &EnergyStats) -> Self {
+        Self {
+            period_start,
+            period_end,
+            energy_wh: (stats.total_energy_kwh * 1000.0).round() as u64,
+            avg_power_milliwatts: (stats.avg_power_watts * 1000.0).round() as u64,
+            efficiency_score: stats.avg_efficiency_score.min(100) as u64,
+            carbon_grams: (stats.total_carbon_kg * 1000.0).round() as u64,
+        }
+    }
+
+    /// EIP-712 digest of the `EnergyProof` the node operator signs for `oracle` on `chain_id`
+    pub fn digest(&self, node_id: &str, chain_id: u64, oracle: Address) -> H256 {
+        let domain_separator = keccak256(encode(&[
+            Token::FixedBytes(keccak256(DOMAIN_TYPEHASH).to_vec()),
+            Token::FixedBytes(keccak256("DAGShieldOracle").to_vec()),
+            Token::FixedBytes(keccak256("1").to_vec()),
+            Token::Uint(U256::from(chain_id)),
+            Token::Address(oracle),
+        ]));
+
+        let mut fields = vec![
+            Token::FixedBytes(keccak256(ENERGY_PROOF_TYPEHASH).to_vec()),
+            Token::FixedBytes(keccak256(node_id).to_vec()),
+        ];
+        fields.extend(
+            [
+                self.period_start,
+                self.period_end,
+                self.energy_wh,
+                self.avg_power_milliwatts,
+                self.efficiency_score,
+                self.carbon_grams,
+            ]
+            .map(|value| Token::Uint(U256::from(value))),
+        );
+        let struct_hash = keccak256(encode(&fields));
+
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(&[0x19, 0x01]);
+        message.extend_from_slice(&domain_separator);
+        message.extend_from_slice(&struct_hash);
+
+        H256::from(keccak256(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_from_stats() {
+        let stats = EnergyStats {
+            avg_power_watts: 42.5,
+            min_power_watts: 30.0,
+            max_power_watts: 60.0,
+            total_energy_kwh: 1.02,
+            avg_efficiency_score: 87,
+            total_carbon_kg: 0.4081,
+            uptime_hours: 24.0,
+            subsystems: Vec::new(),
+        };
+
+        let proof = EfficiencyProof::from_stats((0, 86_400), &stats);
+        assert_eq!(proof.energy_wh, 1_020);
+        assert_eq!(proof.avg_power_milliwatts, 42_500);
+        assert_eq!(proof.carbon_grams, 408);
+
+        // Digest binds the node id, every field, the chain and the oracle
+        let oracle = Address::from_low_u64_be(0x0c);
+        let mut other = proof.clone();
+        other.efficiency_score = 88;
+        assert_ne!(proof.digest("node-1", 2484, oracle), proof.digest("node-2", 2484, oracle));
+        assert_ne!(proof.digest("node-1", 2484, oracle), other.digest("node-1", 2484, oracle));
+        assert_ne!(proof.digest("node-1", 2484, oracle), proof.digest("node-1", 39, oracle));
+        assert_ne!(
+            proof.digest("node-1", 2484, oracle),
+            proof.digest("node-1", 2484, Address::from_low_u64_be(0x0d))
+        );
+    }
+}
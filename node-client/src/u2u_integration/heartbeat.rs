@@ -0,0 +1,64 @@
+/*!
+ * Node heartbeat on the DePIN registry
+ * `updateNodeMetrics` doubles as the availability heartbeat: it refreshes the node's
+ * `lastActiveTime` and must be signed by the node owner over the packed metrics.
+ * `setNodeMaintenance` marks a node that is going away as out of service
+ */
+
+use ethers::{prelude::*, utils::keccak256};
+use serde::{Deserialize, Serialize};
+
+abigen!(
+    NodeRegistryHeartbeat,
+    r#"[
+        function updateNodeMetrics(string nodeId, uint256 threatsDetected, uint256 uptime, uint256 energyEfficiency, bytes signature) external
+        function setNodeMaintenance(string nodeId, bool maintenance) external
+    ]"#
+);
+
+/// Metrics reported with a heartbeat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeHeartbeat {
+    /// Cumulative threats detected (the registry stores the total, not a delta)
+    pub threats_detected: u64,
+    /// Uptime since the previous heartbeat, in seconds
+    pub uptime_secs: u64,
+    /// Efficiency score (0-100)
+    pub energy_efficiency: u64,
+}
+
+/// `keccak256(abi.encodePacked(nodeId, threatsDetected, uptime, energyEfficiency))`
+pub fn heartbeat_digest(node_id: &str, heartbeat: &NodeHeartbeat) -> H256 {
+    // Packed uint256 keeps its full 32-byte width; `abi::encode_packed` would trim it
+    let mut packed = node_id.as_bytes().to_vec();
+    for value in [heartbeat.threats_detected, heartbeat.uptime_secs, heartbeat.energy_efficiency] {
+        let mut word = [0u8; 32];
+        U256::from(value).to_big_endian(&mut word);
+        packed.extend_from_slice(&word);
+    }
+
+    H256::from(keccak256(packed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_digest_layout() {
+        let heartbeat = NodeHeartbeat {
+            threats_detected: 1,
+            uptime_secs: 2,
+            energy_efficiency: 3,
+        };
+
+        let mut packed = b"node-1".to_vec();
+        for value in [1u8, 2, 3] {
+            let mut word = [0u8; 32];
+            word[31] = value;
+            packed.extend_from_slice(&word);
+        }
+
+        assert_eq!(heartbeat_digest("node-1", &heartbeat), H256::from(keccak256(packed)));
+    }
+}
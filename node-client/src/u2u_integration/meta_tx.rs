@@ -0,0 +1,223 @@
+/*!
+ * Gasless meta-transaction submission (ERC-2771)
+ * Threat submissions are signed as forwarder requests and relayed by a third party that pays gas
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{encode, Token},
+    prelude::*,
+    providers::{Http, Provider},
+    signers::LocalWallet,
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tracing::debug;
+
+abigen!(
+    TrustedForwarder,
+    r#"[
+        function nonces(address owner) external view returns (uint256)
+    ]"#
+);
+
+/// Meta-transaction relay settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaTxConfig {
+    pub enabled: bool,
+    /// ERC-2771 trusted forwarder contract (`contracts/DAGShieldForwarder.sol`)
+    pub forwarder: Address,
+    /// Relayer HTTP endpoint accepting signed forward requests
+    pub relayer_url: String,
+    pub request_timeout_secs: u64,
+    /// Gas limit requested for the inner call
+    pub gas: u64,
+    /// Signed requests expire this long after signing
+    #[serde(default = "default_request_ttl_secs")]
+    pub request_ttl_secs: u64,
+}
+
+fn default_request_ttl_secs() -> u64 {
+    300
+}
+
+impl Default for MetaTxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            forwarder: Address::zero(),
+            relayer_url: String::new(),
+            request_timeout_secs: 15,
+            gas: 500_000,
+            request_ttl_secs: default_request_ttl_secs(),
+        }
+    }
+}
+
+/// OpenZeppelin `ERC2771Forwarder` forward request; the relayer submits it to `execute` as
+/// `ForwardRequestData` and the forwarder checks `nonce` against `nonces(from)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardRequest {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub gas: U256,
+    pub nonce: U256,
+    /// Unix time after which the forwarder rejects the request (`uint48`)
+    pub deadline: u64,
+    pub data: Bytes,
+}
+
+#[derive(Debug, Serialize)]
+struct RelayPayload<'a> {
+    request: &'a ForwardRequest,
+    signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayResponse {
+    tx_hash: H256,
+}
+
+const DOMAIN_TYPEHASH: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const FORWARD_REQUEST_TYPEHASH: &str =
+    "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,uint48 deadline,bytes data)";
+
+/// EIP-712 domain name `DAGShieldForwarder` passes to `ERC2771Forwarder`
+const FORWARDER_NAME: &str = "DAGShieldForwarder";
+const FORWARDER_VERSION: &str = "1";
+
+/// EIP-712 digest of a forward request for the given forwarder
+pub fn forward_request_digest(request: &ForwardRequest, chain_id: u64, forwarder: Address) -> H256 {
+    let domain_separator = keccak256(encode(&[
+        Token::FixedBytes(keccak256(DOMAIN_TYPEHASH).to_vec()),
+        Token::FixedBytes(keccak256(FORWARDER_NAME).to_vec()),
+        Token::FixedBytes(keccak256(FORWARDER_VERSION).to_vec()),
+        Token::Uint(U256::from(chain_id)),
+        Token::Address(forwarder),
+    ]));
+
+    let struct_hash = keccak256(encode(&[
+        Token::FixedBytes(keccak256(FORWARD_REQUEST_TYPEHASH).to_vec()),
+        Token::Address(request.from),
+        Token::Address(request.to),
+        Token::Uint(request.value),
+        Token::Uint(request.gas),
+        Token::Uint(request.nonce),
+        Token::Uint(U256::from(request.deadline)),
+        Token::FixedBytes(keccak256(&request.data).to_vec()),
+    ]));
+
+    let mut message = Vec::with_capacity(66);
+    message.extend_from_slice(&[0x19, 0x01]);
+    message.extend_from_slice(&domain_separator);
+    message.extend_from_slice(&struct_hash);
+
+    H256::from(keccak256(message))
+}
+
+/// Signs forward requests locally and hands them to the relayer
+pub struct MetaTxRelayer {
+    config: MetaTxConfig,
+    chain_id: u64,
+    wallet: LocalWallet,
+    provider: Arc<Provider<Http>>,
+    http: reqwest::Client,
+}
+
+impl MetaTxRelayer {
+    pub fn new(
+        config: MetaTxConfig,
+        chain_id: u64,
+        wallet: LocalWallet,
+        provider: Arc<Provider<Http>>,
+    ) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()?;
+
+        Ok(Self {
+            config,
+            chain_id,
+            wallet,
+            provider,
+            http,
+        })
+    }
+
+    /// Build and sign a forward request for a call to `to`
+    pub async fn sign_request(&self, to: Address, data: Bytes) -> Result<(ForwardRequest, Signature)> {
+        let forwarder = TrustedForwarder::new(self.config.forwarder, self.provider.clone());
+        let nonce = forwarder.nonces(self.wallet.address()).call().await
+            .context("Failed to read forwarder nonce")?;
+
+        let request = ForwardRequest {
+            from: self.wallet.address(),
+            to,
+            value: U256::zero(),
+            gas: U256::from(self.config.gas),
+            nonce,
+            deadline: chrono::Utc::now().timestamp() as u64 + self.config.request_ttl_secs,
+            data,
+        };
+
+        let digest = forward_request_digest(&request, self.chain_id, self.config.forwarder);
+        let signature = self.wallet.sign_hash(digest)?;
+
+        Ok((request, signature))
+    }
+
+    /// Relay a call, returning the relayer's transaction hash
+    pub async fn relay(&self, to: Address, data: Bytes) -> Result<H256> {
+        let (request, signature) = self.sign_request(to, data).await?;
+
+        let response: RelayResponse = self.http
+            .post(&self.config.relayer_url)
+            .json(&RelayPayload {
+                request: &request,
+                signature: format!("0x{}", signature),
+            })
+            .send()
+            .await
+            .context("Relayer unreachable")?
+            .error_for_status()
+            .context("Relayer rejected request")?
+            .json()
+            .await?;
+
+        debug!("📨 Meta-tx relayed: {:?}", response.tx_hash);
+
+        Ok(response.tx_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_request_signature_recovers_signer() {
+        let wallet: LocalWallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let request = ForwardRequest {
+            from: wallet.address(),
+            to: Address::from_low_u64_be(0xdead),
+            value: U256::zero(),
+            gas: U256::from(100_000),
+            nonce: U256::zero(),
+            deadline: 1_700_000_000,
+            data: Bytes::from(vec![0x12, 0x34]),
+        };
+
+        let digest = forward_request_digest(&request, 2484, Address::from_low_u64_be(0xf0));
+        let signature = wallet.sign_hash(digest).unwrap();
+
+        assert_eq!(signature.recover(digest).unwrap(), wallet.address());
+        assert_ne!(digest, forward_request_digest(&request, 39, Address::from_low_u64_be(0xf0)));
+    }
+}
@@ -0,0 +1,181 @@
+/*!
+ * Contract read cache for the U2U client
+ * Caches eth_call results keyed by (contract, calldata), invalidated by new blocks and contract events
+ */
+
+use ethers::types::{Address, Bytes};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+
+/// How long results for a contract stay valid
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CachePolicy {
+    /// Number of blocks a result stays valid after the block it was read at (0 = same block only)
+    pub max_age_blocks: u64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self { max_age_blocks: 0 }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedResult {
+    value: Bytes,
+    block_number: u64,
+}
+
+/// Cache hit/miss counters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// Fraction of lookups served from cache
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Per-contract call result cache
+#[derive(Debug, Default)]
+pub struct CallCache {
+    entries: RwLock<HashMap<(Address, Bytes), CachedResult>>,
+    policies: RwLock<HashMap<Address, CachePolicy>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl CallCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set cache policy for a contract
+    pub fn set_policy(&self, contract: Address, policy: CachePolicy) {
+        self.policies.write().unwrap().insert(contract, policy);
+    }
+
+    fn policy(&self, contract: &Address) -> CachePolicy {
+        self.policies.read().unwrap().get(contract).copied().unwrap_or_default()
+    }
+
+    /// Look up a result valid at `current_block`
+    pub fn get(&self, contract: Address, calldata: &Bytes, current_block: u64) -> Option<Bytes> {
+        let max_age = self.policy(&contract).max_age_blocks;
+        let entries = self.entries.read().unwrap();
+
+        let result = entries
+            .get(&(contract, calldata.clone()))
+            .filter(|cached| {
+                current_block >= cached.block_number
+                    && current_block - cached.block_number <= max_age
+            })
+            .map(|cached| cached.value.clone());
+
+        match result {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        result
+    }
+
+    /// Store a result read at `block_number`
+    pub fn insert(&self, contract: Address, calldata: Bytes, value: Bytes, block_number: u64) {
+        self.entries.write().unwrap().insert(
+            (contract, calldata),
+            CachedResult { value, block_number },
+        );
+    }
+
+    /// Drop entries that can no longer be served at `current_block`
+    pub fn on_new_block(&self, current_block: u64) {
+        let policies = self.policies.read().unwrap();
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.len();
+
+        entries.retain(|(contract, _), cached| {
+            let max_age = policies.get(contract).copied().unwrap_or_default().max_age_blocks;
+            current_block >= cached.block_number && current_block - cached.block_number <= max_age
+        });
+
+        self.invalidations
+            .fetch_add((before - entries.len()) as u64, Ordering::Relaxed);
+    }
+
+    /// Drop all entries for a contract (e.g. after one of its events)
+    pub fn invalidate_contract(&self, contract: Address) {
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.len();
+        entries.retain(|(address, _), _| *address != contract);
+
+        self.invalidations
+            .fetch_add((before - entries.len()) as u64, Ordering::Relaxed);
+    }
+
+    /// Current cache statistics
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: self.entries.read().unwrap().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_block_hit_then_expiry() {
+        let cache = CallCache::new();
+        let contract = Address::from_low_u64_be(1);
+        let calldata = Bytes::from(vec![0x01, 0x02]);
+
+        cache.insert(contract, calldata.clone(), Bytes::from(vec![0xaa]), 10);
+        assert!(cache.get(contract, &calldata, 10).is_some());
+        assert!(cache.get(contract, &calldata, 11).is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert!((stats.hit_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_policy_and_invalidation() {
+        let cache = CallCache::new();
+        let registry = Address::from_low_u64_be(2);
+        let calldata = Bytes::from(vec![0x03]);
+
+        cache.set_policy(registry, CachePolicy { max_age_blocks: 5 });
+        cache.insert(registry, calldata.clone(), Bytes::from(vec![0xbb]), 10);
+
+        cache.on_new_block(14);
+        assert!(cache.get(registry, &calldata, 14).is_some());
+
+        cache.invalidate_contract(registry);
+        assert!(cache.get(registry, &calldata, 14).is_none());
+        assert_eq!(cache.stats().invalidations, 1);
+    }
+}
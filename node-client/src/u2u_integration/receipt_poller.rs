@@ -0,0 +1,240 @@
+/*!
+ * Shared receipt poller for in-flight U2U transactions
+ * One loop scans new blocks for watched hashes instead of every transaction polling on its own
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    prelude::*,
+    providers::{Http, Provider},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+/// Largest gap of blocks scanned one by one before falling back to direct receipt queries
+const MAX_BLOCK_CATCH_UP: u64 = 32;
+
+/// Estimates block time from recent block timestamps and derives a poll interval
+#[derive(Debug, Clone)]
+pub struct BlockTimeEstimator {
+    timestamps: VecDeque<u64>,
+    capacity: usize,
+    min_interval: Duration,
+    max_interval: Duration,
+}
+
+impl BlockTimeEstimator {
+    pub fn new(capacity: usize, min_interval: Duration, max_interval: Duration) -> Self {
+        Self {
+            timestamps: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(2),
+            min_interval,
+            max_interval,
+        }
+    }
+
+    /// Record a block timestamp (seconds)
+    pub fn record(&mut self, timestamp: u64) {
+        if self.timestamps.back().map_or(false, |&last| timestamp <= last) {
+            return;
+        }
+        self.timestamps.push_back(timestamp);
+        while self.timestamps.len() > self.capacity {
+            self.timestamps.pop_front();
+        }
+    }
+
+    /// Average seconds between recorded blocks
+    pub fn average_block_time(&self) -> Option<f64> {
+        let (first, last) = (self.timestamps.front()?, self.timestamps.back()?);
+        let blocks = self.timestamps.len().checked_sub(1).filter(|&n| n > 0)?;
+        Some((last - first) as f64 / blocks as f64)
+    }
+
+    /// Poll at half the block time, clamped to the configured bounds
+    pub fn poll_interval(&self) -> Duration {
+        match self.average_block_time() {
+            Some(block_time) => Duration::from_secs_f64(block_time / 2.0)
+                .clamp(self.min_interval, self.max_interval),
+            None => self.min_interval,
+        }
+    }
+}
+
+struct Watch {
+    waiters: Vec<oneshot::Sender<TransactionReceipt>>,
+    registered_at: Instant,
+}
+
+/// Tracks in-flight transaction hashes and dispatches receipts to waiters
+pub struct ReceiptPoller {
+    provider: Arc<Provider<Http>>,
+    watches: Mutex<HashMap<H256, Watch>>,
+    estimator: Mutex<BlockTimeEstimator>,
+    last_block: Mutex<Option<u64>>,
+    max_wait: Duration,
+}
+
+impl ReceiptPoller {
+    pub fn new(provider: Arc<Provider<Http>>, max_wait: Duration) -> Self {
+        Self {
+            provider,
+            watches: Mutex::new(HashMap::new()),
+            estimator: Mutex::new(BlockTimeEstimator::new(
+                20,
+                Duration::from_millis(250),
+                Duration::from_secs(10),
+            )),
+            last_block: Mutex::new(None),
+            max_wait,
+        }
+    }
+
+    /// Register interest in a transaction receipt
+    pub fn watch(&self, tx_hash: H256) -> oneshot::Receiver<TransactionReceipt> {
+        let (tx, rx) = oneshot::channel();
+        self.watches
+            .lock()
+            .unwrap()
+            .entry(tx_hash)
+            .or_insert_with(|| Watch {
+                waiters: Vec::new(),
+                registered_at: Instant::now(),
+            })
+            .waiters
+            .push(tx);
+        rx
+    }
+
+    /// Wait for a transaction to be mined
+    pub async fn wait(&self, tx_hash: H256) -> Result<TransactionReceipt> {
+        let receipt = self.watch(tx_hash);
+
+        // The transaction was broadcast before the watch existed and may already be in a
+        // block the poll loop has scanned
+        if let Err(e) = self.fetch_and_dispatch(&[tx_hash]).await {
+            debug!("Initial receipt check for {:?} failed, leaving it to the poll loop: {}", tx_hash, e);
+        }
+
+        receipt
+            .await
+            .with_context(|| format!("Receipt watch for {:?} expired", tx_hash))
+    }
+
+    /// Number of hashes currently being watched
+    pub fn in_flight(&self) -> usize {
+        self.watches.lock().unwrap().len()
+    }
+
+    /// Run the polling loop
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let delay = self.estimator.lock().unwrap().poll_interval();
+            tokio::time::sleep(delay).await;
+
+            if self.in_flight() == 0 {
+                continue;
+            }
+
+            if let Err(e) = self.poll_once().await {
+                warn!("Receipt poll failed: {}", e);
+            }
+
+            self.expire_stale_watches();
+        }
+    }
+
+    async fn poll_once(&self) -> Result<()> {
+        let head = self.provider.get_block_number().await?.as_u64();
+        let last = *self.last_block.lock().unwrap();
+
+        let from = match last {
+            Some(last) if head <= last => return Ok(()),
+            Some(last) if head - last <= MAX_BLOCK_CATCH_UP => last + 1,
+            _ => {
+                // First poll or large gap: query every watched hash directly
+                let hashes: Vec<H256> = self.watches.lock().unwrap().keys().copied().collect();
+                self.fetch_and_dispatch(&hashes).await?;
+                *self.last_block.lock().unwrap() = Some(head);
+                return Ok(());
+            }
+        };
+
+        for number in from..=head {
+            let Some(block) = self.provider.get_block(number).await? else {
+                break;
+            };
+
+            self.estimator.lock().unwrap().record(block.timestamp.as_u64());
+
+            let included: Vec<H256> = {
+                let watches = self.watches.lock().unwrap();
+                block.transactions.iter().filter(|h| watches.contains_key(h)).copied().collect()
+            };
+            self.fetch_and_dispatch(&included).await?;
+
+            *self.last_block.lock().unwrap() = Some(number);
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_and_dispatch(&self, hashes: &[H256]) -> Result<()> {
+        let receipts = futures::future::join_all(
+            hashes.iter().map(|hash| self.provider.get_transaction_receipt(*hash)),
+        )
+        .await;
+
+        for (hash, receipt) in hashes.iter().zip(receipts) {
+            if let Some(receipt) = receipt? {
+                if let Some(watch) = self.watches.lock().unwrap().remove(hash) {
+                    debug!("🧾 Receipt for {:?} dispatched to {} waiter(s)", hash, watch.waiters.len());
+                    for waiter in watch.waiters {
+                        let _ = waiter.send(receipt.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expire_stale_watches(&self) {
+        let max_wait = self.max_wait;
+        self.watches.lock().unwrap().retain(|hash, watch| {
+            let keep = watch.registered_at.elapsed() < max_wait;
+            if !keep {
+                warn!("⏱️ Giving up on receipt for {:?}", hash);
+            }
+            keep
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_interval_tracks_block_time() {
+        let mut estimator =
+            BlockTimeEstimator::new(5, Duration::from_millis(250), Duration::from_secs(10));
+        assert_eq!(estimator.poll_interval(), Duration::from_millis(250));
+
+        for ts in [100, 102, 104, 106] {
+            estimator.record(ts);
+        }
+        assert_eq!(estimator.average_block_time(), Some(2.0));
+        assert_eq!(estimator.poll_interval(), Duration::from_secs(1));
+
+        for ts in [200, 300] {
+            estimator.record(ts);
+        }
+        assert_eq!(estimator.poll_interval(), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,260 @@
+/*!
+ * Node reputation: on-chain reader and local prediction model
+ * Predicts how pending threat submissions will move reputation so low-confidence
+ * reports that are likely to be rejected can be throttled before they cost the node
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{ParamType, Token},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+abigen!(
+    NodeRegistryReputation,
+    r#"[
+        function getNodeInfo(string nodeId) external view returns (tuple(address owner, string nodeId, uint8 deviceType, uint8[] capabilities, string location, uint256 stakeAmount, uint256 reputationScore, uint256 energyEfficiency, tuple(uint32 cpuCores, uint32 ramGb, uint32 storageGb, uint32 networkBandwidthMbps, uint32 powerConsumptionWatts) hardwareSpecs, uint8 status, uint256 registrationTime, uint256 lastActiveTime, uint256 totalRewards, uint256 threatsDetected, uint256 uptime, bool isVerified))
+    ]"#
+);
+
+/// Position of `reputationScore` in `DePINNodeRegistry.DePINNode`
+const REPUTATION_FIELD: usize = 6;
+
+/// Positions of `owner` and `stakeAmount` in `DePINNodeRegistry.DePINNode`
+const OWNER_FIELD: usize = 0;
+const STAKE_FIELD: usize = 5;
+
+/// ABI layout of `DePINNodeRegistry.DePINNode`
+fn node_info_type() -> ParamType {
+    ParamType::Tuple(vec![
+        ParamType::Address,                             // owner
+        ParamType::String,                              // nodeId
+        ParamType::Uint(8),                             // deviceType
+        ParamType::Array(Box::new(ParamType::Uint(8))), // capabilities
+        ParamType::String,                              // location
+        ParamType::Uint(256),                           // stakeAmount
+        ParamType::Uint(256),                           // reputationScore
+        ParamType::Uint(256),                           // energyEfficiency
+        ParamType::Tuple(vec![ParamType::Uint(32); 5]), // hardwareSpecs
+        ParamType::Uint(8),                             // status
+        ParamType::Uint(256),                           // registrationTime
+        ParamType::Uint(256),                           // lastActiveTime
+        ParamType::Uint(256),                           // totalRewards
+        ParamType::Uint(256),                           // threatsDetected
+        ParamType::Uint(256),                           // uptime
+        ParamType::Bool,                                // isVerified
+    ])
+}
+
+/// Fields of an encoded `getNodeInfo` response
+fn node_info_fields(raw: &[u8]) -> Result<Vec<Token>> {
+    let node = ethers::abi::decode(&[node_info_type()], raw)
+        .context("Invalid getNodeInfo response")?
+        .pop();
+    let Some(Token::Tuple(fields)) = node else {
+        anyhow::bail!("Invalid getNodeInfo response");
+    };
+    Ok(fields)
+}
+
+/// Reputation (0-100) from an encoded `getNodeInfo` response; the registry keeps basis points
+pub fn reputation_from_node_info(raw: &[u8]) -> Result<u64> {
+    let fields = node_info_fields(raw)?;
+    let score = fields
+        .get(REPUTATION_FIELD)
+        .cloned()
+        .and_then(Token::into_uint)
+        .context("getNodeInfo response has no reputationScore")?;
+
+    Ok(score.low_u64() / 100)
+}
+
+/// Owner and stake from an encoded `getNodeInfo` response; unregistered nodes have a zero owner
+pub fn stake_from_node_info(raw: &[u8]) -> Result<(Address, U256)> {
+    let fields = node_info_fields(raw)?;
+    let owner = fields
+        .get(OWNER_FIELD)
+        .cloned()
+        .and_then(Token::into_address)
+        .context("getNodeInfo response has no owner")?;
+    let stake = fields
+        .get(STAKE_FIELD)
+        .cloned()
+        .and_then(Token::into_uint)
+        .context("getNodeInfo response has no stakeAmount")?;
+    Ok((owner, stake))
+}
+
+/// Reputation model parameters (mirrors the registry's scoring rules)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationConfig {
+    /// Reputation gained when a submission is confirmed
+    pub reward_per_confirmation: f64,
+    /// Reputation lost when a submission is rejected
+    pub penalty_per_rejection: f64,
+    /// Never let predicted reputation drop below this
+    pub min_reputation: f64,
+    /// Minimum expected reputation change for a submission to go out
+    pub min_expected_gain: f64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            reward_per_confirmation: 2.0,
+            penalty_per_rejection: 5.0,
+            min_reputation: 70.0,
+            min_expected_gain: 0.0,
+        }
+    }
+}
+
+/// Observed confirmation rate for one confidence bucket
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Bucket {
+    confirmed: u64,
+    rejected: u64,
+}
+
+const BUCKETS: usize = 10;
+
+/// Local model of how submissions affect reputation
+#[derive(Debug, Clone)]
+pub struct ReputationModel {
+    config: ReputationConfig,
+    buckets: [Bucket; BUCKETS],
+    pending: HashMap<String, f64>,
+}
+
+impl ReputationModel {
+    pub fn new(config: ReputationConfig) -> Self {
+        Self {
+            config,
+            buckets: [Bucket::default(); BUCKETS],
+            pending: HashMap::new(),
+        }
+    }
+
+    fn bucket_index(confidence: f64) -> usize {
+        ((confidence.clamp(0.0, 1.0) * BUCKETS as f64) as usize).min(BUCKETS - 1)
+    }
+
+    /// Estimated probability that a submission with this confidence gets confirmed.
+    /// Starts at the raw confidence and moves toward observed outcomes (Laplace smoothed).
+    pub fn confirmation_probability(&self, confidence: f64) -> f64 {
+        let bucket = self.buckets[Self::bucket_index(confidence)];
+        let prior_weight = 4.0;
+        let prior = confidence.clamp(0.0, 1.0);
+
+        (bucket.confirmed as f64 + prior * prior_weight)
+            / (bucket.confirmed as f64 + bucket.rejected as f64 + prior_weight)
+    }
+
+    /// Expected reputation change of one submission
+    pub fn expected_delta(&self, confidence: f64) -> f64 {
+        let p = self.confirmation_probability(confidence);
+        p * self.config.reward_per_confirmation - (1.0 - p) * self.config.penalty_per_rejection
+    }
+
+    /// Reputation expected once every pending submission resolves
+    pub fn predicted_reputation(&self, current: f64) -> f64 {
+        current + self.pending.values().map(|&c| self.expected_delta(c)).sum::<f64>()
+    }
+
+    /// Decide whether a new submission is worth its reputation risk
+    pub fn should_submit(&self, current: f64, confidence: f64) -> bool {
+        let delta = self.expected_delta(confidence);
+        let worst_case = self.predicted_reputation(current) - self.config.penalty_per_rejection;
+
+        delta >= self.config.min_expected_gain || worst_case >= self.config.min_reputation
+    }
+
+    /// Track a submission until its outcome is known
+    pub fn track_pending(&mut self, submission_id: &str, confidence: f64) {
+        self.pending.insert(submission_id.to_string(), confidence);
+    }
+
+    /// Record the oracle's verdict for a tracked submission
+    pub fn record_outcome(&mut self, submission_id: &str, confirmed: bool) {
+        if let Some(confidence) = self.pending.remove(submission_id) {
+            let bucket = &mut self.buckets[Self::bucket_index(confidence)];
+            if confirmed {
+                bucket.confirmed += 1;
+            } else {
+                bucket.rejected += 1;
+            }
+        }
+    }
+
+    /// Number of unresolved submissions
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_confidence_has_negative_expectation() {
+        let model = ReputationModel::new(ReputationConfig::default());
+        assert!(model.expected_delta(0.95) > 0.0);
+        assert!(model.expected_delta(0.5) < 0.0);
+    }
+
+    #[test]
+    fn test_outcomes_shift_probability() {
+        let mut model = ReputationModel::new(ReputationConfig::default());
+        let before = model.confirmation_probability(0.55);
+
+        for i in 0..20 {
+            let id = format!("sub_{}", i);
+            model.track_pending(&id, 0.55);
+            model.record_outcome(&id, true);
+        }
+
+        assert!(model.confirmation_probability(0.55) > before);
+        assert_eq!(model.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_reputation_from_node_info() {
+        let uint = |v: u64| Token::Uint(v.into());
+        let node = Token::Tuple(vec![
+            Token::Address(Address::repeat_byte(1)),
+            Token::String("node-1".into()),
+            uint(1),
+            Token::Array(vec![uint(0), uint(4)]),
+            Token::String("eu-west".into()),
+            uint(1000),
+            uint(7250),
+            uint(7000),
+            Token::Tuple(vec![uint(8), uint(16), uint(512), uint(100), uint(65)]),
+            uint(1),
+            uint(10),
+            uint(20),
+            uint(0),
+            uint(3),
+            uint(3600),
+            Token::Bool(false),
+        ]);
+
+        let raw = ethers::abi::encode(&[node]);
+        assert_eq!(reputation_from_node_info(&raw).unwrap(), 72);
+        assert_eq!(stake_from_node_info(&raw).unwrap(), (Address::repeat_byte(1), U256::from(1000)));
+    }
+
+    #[test]
+    fn test_throttles_near_min_reputation() {
+        let mut model = ReputationModel::new(ReputationConfig::default());
+        assert!(model.should_submit(100.0, 0.5));
+
+        model.track_pending("a", 0.5);
+        model.track_pending("b", 0.5);
+        assert!(!model.should_submit(72.0, 0.5));
+        assert!(model.should_submit(72.0, 0.95));
+    }
+}
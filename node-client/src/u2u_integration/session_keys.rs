@@ -0,0 +1,109 @@
+/*!
+ * Session key delegation for automated threat submissions
+ * The staking wallet authorizes a short-lived, low-privilege key on the node registry
+ */
+
+use ethers::{
+    prelude::*,
+    providers::{Http, Provider},
+    signers::LocalWallet,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+abigen!(
+    NodeRegistrySessionKeys,
+    r#"[
+        function authorizeSessionKey(address sessionKey, uint64 expiresAt) external
+        function revokeSessionKey(address sessionKey) external
+        function isSessionKeyValid(address operator, address sessionKey) external view returns (bool)
+    ]"#
+);
+
+/// Gas of the plain value transfer that sweeps a rotated-out key
+pub const SWEEP_GAS: u64 = 21_000;
+
+/// Session key settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionKeyConfig {
+    pub enabled: bool,
+    /// How often a fresh key is generated and authorized
+    pub rotation_interval_secs: u64,
+    /// On-chain validity of each key; should exceed the rotation interval
+    pub validity_secs: u64,
+    /// Native tokens sent to each new key to pay for submissions (wei)
+    pub funding_amount_wei: U256,
+    /// How long a rotated-out key may take to get its broadcast transactions mined before
+    /// sweeping and revoking it is retried on the next rotation
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for SessionKeyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rotation_interval_secs: 6 * 3600,
+            validity_secs: 12 * 3600,
+            funding_amount_wei: U256::exp10(16), // 0.01 U2U
+            drain_timeout_secs: default_drain_timeout_secs(),
+        }
+    }
+}
+
+/// Active session key with its signing middleware
+pub struct SessionKey {
+    pub wallet: LocalWallet,
+    pub signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    pub expires_at: u64,
+    pub authorization_tx: H256,
+}
+
+impl SessionKey {
+    /// Address of the session key
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Check the key is still inside its validity window, with a safety margin
+    pub fn is_usable(&self, now: u64, margin_secs: u64) -> bool {
+        now + margin_secs < self.expires_at
+    }
+}
+
+/// Balance left to return from a session key after paying for the sweep transfer itself
+pub fn sweep_amount(balance: U256, gas_price: U256) -> Option<U256> {
+    let fee = gas_price * SWEEP_GAS;
+    (balance > fee).then(|| balance - fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweep_leaves_only_transfer_fee() {
+        let gas_price = U256::from(1_000_000_000u64);
+        let fee = gas_price * SWEEP_GAS;
+
+        assert_eq!(sweep_amount(U256::exp10(16), gas_price), Some(U256::exp10(16) - fee));
+        assert_eq!(sweep_amount(fee, gas_price), None);
+        assert_eq!(sweep_amount(U256::zero(), gas_price), None);
+
+        let session = SessionKey {
+            wallet: LocalWallet::new(&mut ethers::core::rand::thread_rng()),
+            signer: Arc::new(SignerMiddleware::new(
+                Provider::<Http>::try_from("http://localhost:8545").unwrap(),
+                LocalWallet::new(&mut ethers::core::rand::thread_rng()),
+            )),
+            expires_at: 1_000,
+            authorization_tx: H256::zero(),
+        };
+        assert!(session.is_usable(900, 60));
+        assert!(!session.is_usable(950, 60));
+    }
+}
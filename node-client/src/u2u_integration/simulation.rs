@@ -0,0 +1,168 @@
+/*!
+ * Dry-run mode for on-chain writes
+ * Transactions are executed with eth_call/eth_estimateGas and logged instead of being broadcast
+ */
+
+use ethers::{
+    abi::{decode, ParamType},
+    prelude::*,
+    providers::{Http, Provider, RpcError},
+    types::transaction::eip2718::TypedTransaction,
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use tracing::{info, warn};
+
+/// Solidity `Error(string)` selector
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Predicted result of a write that was never broadcast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationOutcome {
+    pub label: String,
+    pub from: Address,
+    pub to: Option<Address>,
+    /// Placeholder hash standing in for the transaction hash
+    pub simulated_hash: H256,
+    pub predicted_gas: Option<U256>,
+    pub success: bool,
+    pub revert_reason: Option<String>,
+    pub timestamp: u64,
+}
+
+/// Deterministic stand-in hash for a simulated transaction
+pub fn simulated_hash(label: &str) -> H256 {
+    H256::from(keccak256(format!("dagshield-simulation:{}", label)))
+}
+
+/// Decode the reason string from `Error(string)` revert data
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    let payload = data.strip_prefix(&ERROR_SELECTOR)?;
+    decode(&[ParamType::String], payload)
+        .ok()?
+        .into_iter()
+        .next()?
+        .into_string()
+}
+
+/// Runs writes as calls and keeps a log of the most recent outcomes
+pub struct Simulator {
+    provider: Arc<Provider<Http>>,
+    outcomes: Mutex<VecDeque<SimulationOutcome>>,
+    capacity: usize,
+}
+
+impl Simulator {
+    /// `capacity` bounds the outcome log; the oldest outcomes are dropped first
+    pub fn new(provider: Arc<Provider<Http>>, capacity: usize) -> Self {
+        Self {
+            provider,
+            outcomes: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Simulate a transaction from `from` and record the predicted outcome
+    pub async fn simulate(&self, label: &str, from: Address, tx: TypedTransaction) -> SimulationOutcome {
+        let mut tx = tx;
+        tx.set_from(from);
+
+        let predicted_gas = self.provider.estimate_gas(&tx, None).await.ok();
+        let (success, revert_reason) = match self.provider.call(&tx, None).await {
+            Ok(_) => (true, None),
+            Err(e) => {
+                let reason = e
+                    .as_error_response()
+                    .and_then(|response| response.as_revert_data())
+                    .and_then(|data| decode_revert_reason(&data))
+                    .unwrap_or_else(|| e.to_string());
+                (false, Some(reason))
+            }
+        };
+
+        let outcome = SimulationOutcome {
+            label: label.to_string(),
+            from,
+            to: tx.to().and_then(|to| to.as_address()).copied(),
+            simulated_hash: simulated_hash(label),
+            predicted_gas,
+            success,
+            revert_reason,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+        };
+
+        if outcome.success {
+            info!(
+                "🧪 [simulation] {} would succeed (gas: {:?})",
+                outcome.label, outcome.predicted_gas
+            );
+        } else {
+            warn!(
+                "🧪 [simulation] {} would revert: {}",
+                outcome.label,
+                outcome.revert_reason.as_deref().unwrap_or("unknown reason")
+            );
+        }
+
+        self.record(outcome.clone());
+        outcome
+    }
+
+    fn record(&self, outcome: SimulationOutcome) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut outcomes = self.outcomes.lock().unwrap();
+        if outcomes.len() == self.capacity {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(outcome);
+    }
+
+    /// Most recent outcomes, oldest first
+    pub fn outcomes(&self) -> Vec<SimulationOutcome> {
+        self.outcomes.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend(encode(&[Token::String("Node not registered".to_string())]));
+
+        assert_eq!(decode_revert_reason(&data), Some("Node not registered".to_string()));
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(simulated_hash("tx_1"), simulated_hash("tx_1"));
+    }
+
+    #[test]
+    fn test_outcome_log_keeps_most_recent() {
+        let provider = Arc::new(Provider::<Http>::try_from("http://localhost:8545").unwrap());
+        let simulator = Simulator::new(provider, 2);
+
+        for label in ["tx_1", "tx_2", "tx_3"] {
+            simulator.record(SimulationOutcome {
+                label: label.to_string(),
+                from: Address::zero(),
+                to: None,
+                simulated_hash: simulated_hash(label),
+                predicted_gas: None,
+                success: true,
+                revert_reason: None,
+                timestamp: 0,
+            });
+        }
+
+        let labels: Vec<_> = simulator.outcomes().into_iter().map(|outcome| outcome.label).collect();
+        assert_eq!(labels, vec!["tx_2", "tx_3"]);
+    }
+}
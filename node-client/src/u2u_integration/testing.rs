@@ -0,0 +1,184 @@
+/*!
+ * Local fork harness for integration tests and development
+ * Spawns (or attaches to) an anvil fork of U2U, deploys the DAGShield contracts and
+ * returns a fully wired U2UClient
+ *
+ * Contract bytecode is embedded from the Hardhat artifacts, so run `npx hardhat compile`
+ * in the repository root before building with the `testing` feature.
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    abi::{Abi, Tokenize},
+    prelude::*,
+    providers::{Http, Provider},
+    signers::LocalWallet,
+    utils::{hex, Anvil, AnvilInstance},
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tracing::info;
+
+use super::{ContractAddresses, U2UClient, U2UConfig, U2UNetwork};
+
+const DAGSHIELD_TOKEN_ARTIFACT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../artifacts/contracts/DAGShieldToken.sol/DAGShieldToken.json"
+));
+const DAGSHIELD_ORACLE_ARTIFACT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../artifacts/contracts/DAGShieldOracle.sol/DAGShieldOracle.json"
+));
+const NODE_REGISTRY_ARTIFACT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../artifacts/contracts/DePINNodeRegistry.sol/DePINNodeRegistry.json"
+));
+const THREAT_DETECTOR_ARTIFACT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../artifacts/contracts/DAGShield.sol/DAGShield.json"
+));
+
+/// Public U2U testnet RPC used as the default fork source
+pub const U2U_TESTNET_RPC: &str = "https://rpc-nebulas-testnet.uniultra.xyz";
+
+/// Hardhat compilation artifact (only the fields needed for deployment)
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    abi: Abi,
+    bytecode: Bytes,
+}
+
+/// Where the harness gets its chain from
+#[derive(Debug, Clone)]
+pub enum HarnessTarget {
+    /// Spawn a local anvil node, optionally forking an upstream RPC
+    Spawn { fork_url: Option<String> },
+    /// Attach to an already running anvil/hardhat node
+    Attach {
+        rpc_url: String,
+        ws_url: String,
+        chain_id: u64,
+        private_key: String,
+    },
+}
+
+/// Running local chain with the DAGShield contracts deployed
+pub struct TestHarness {
+    pub client: U2UClient,
+    pub addresses: ContractAddresses,
+    /// Kept alive for the lifetime of the harness; anvil is killed on drop
+    pub anvil: Option<AnvilInstance>,
+}
+
+impl TestHarness {
+    /// Spawn anvil forking the U2U testnet
+    pub async fn fork_testnet() -> Result<Self> {
+        Self::start(HarnessTarget::Spawn {
+            fork_url: Some(U2U_TESTNET_RPC.to_string()),
+        })
+        .await
+    }
+
+    /// Start the harness against the given target
+    pub async fn start(target: HarnessTarget) -> Result<Self> {
+        let (anvil, rpc_url, ws_url, chain_id, private_key) = match target {
+            HarnessTarget::Spawn { fork_url } => {
+                let mut anvil = Anvil::new().block_time(1u64);
+                if let Some(url) = &fork_url {
+                    info!("🍴 Spawning anvil fork of {}", url);
+                    anvil = anvil.fork(url);
+                }
+                let anvil = anvil.spawn();
+
+                let private_key = hex::encode(anvil.keys()[0].to_bytes());
+                let (rpc_url, ws_url, chain_id) = (anvil.endpoint(), anvil.ws_endpoint(), anvil.chain_id());
+                (Some(anvil), rpc_url, ws_url, chain_id, private_key)
+            }
+            HarnessTarget::Attach { rpc_url, ws_url, chain_id, private_key } => {
+                (None, rpc_url, ws_url, chain_id, private_key)
+            }
+        };
+
+        let provider = Provider::<Http>::try_from(rpc_url.as_str())
+            .context("Failed to create HTTP provider")?
+            .interval(Duration::from_millis(100));
+        let wallet = private_key.parse::<LocalWallet>()
+            .context("Invalid private key")?
+            .with_chain_id(chain_id);
+        let deployer = Arc::new(SignerMiddleware::new(provider, wallet));
+
+        let addresses = deploy_contracts(deployer).await?;
+
+        let config = U2UConfig {
+            network: U2UNetwork::Local,
+            rpc_url,
+            ws_url,
+            chain_id,
+            private_key,
+            contract_addresses: addresses.clone(),
+            ..U2UConfig::default()
+        };
+        let client = U2UClient::new(config).await?;
+
+        Ok(Self {
+            client,
+            addresses,
+            anvil,
+        })
+    }
+}
+
+/// Deploy the four DAGShield contracts from the embedded artifacts
+pub async fn deploy_contracts(
+    deployer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+) -> Result<ContractAddresses> {
+    let dagshield_token = deploy(&deployer, "DAGShieldToken", DAGSHIELD_TOKEN_ARTIFACT, ()).await?;
+    let dagshield_oracle = deploy(&deployer, "DAGShieldOracle", DAGSHIELD_ORACLE_ARTIFACT, ()).await?;
+    let node_registry = deploy(&deployer, "DePINNodeRegistry", NODE_REGISTRY_ARTIFACT, dagshield_token).await?;
+    let threat_detector = deploy(&deployer, "DAGShield", THREAT_DETECTOR_ARTIFACT, dagshield_token).await?;
+
+    Ok(ContractAddresses {
+        dagshield_token,
+        dagshield_oracle,
+        node_registry,
+        threat_detector,
+    })
+}
+
+async fn deploy<T: Tokenize>(
+    deployer: &Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    name: &str,
+    artifact: &str,
+    constructor_args: T,
+) -> Result<Address> {
+    let artifact: Artifact = serde_json::from_str(artifact)
+        .with_context(|| format!("Invalid {} artifact", name))?;
+
+    let contract = ContractFactory::new(artifact.abi, artifact.bytecode, deployer.clone())
+        .deploy(constructor_args)
+        .with_context(|| format!("Failed to encode {} constructor", name))?
+        .send()
+        .await
+        .with_context(|| format!("Failed to deploy {}", name))?;
+
+    info!("📄 Deployed {} at {:?}", name, contract.address());
+    Ok(contract.address())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "requires anvil on PATH"]
+    async fn test_harness_deploys_contracts() {
+        let harness = TestHarness::start(HarnessTarget::Spawn { fork_url: None })
+            .await
+            .unwrap();
+
+        for address in harness.addresses.all() {
+            let code = harness.client.provider.get_code(address, None).await.unwrap();
+            assert!(!code.is_empty());
+        }
+    }
+}
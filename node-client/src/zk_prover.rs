@@ -5,196 +5,2374 @@
 
 use anyhow::{Context, Result};
 use ark_bn254::{Bn254, Fr, G1Projective, G2Projective};
+use ark_ec::{pairing::Pairing, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInteger, Field, PrimeField};
 use ark_groth16::{
-    create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    generate_random_parameters, prepare_verifying_key, verify_proof,
     Parameters, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey,
 };
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{rand::RngCore, UniformRand};
+use ethers::{signers::LocalWallet, types::{Address, H256}};
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 use tracing::{debug, error, info, warn};
 
+pub mod attestation;
+pub mod backend;
+pub mod batch;
+pub mod benchmark;
+pub mod ceremony;
+pub mod circom;
+pub mod disclosure;
+pub mod energy_range;
+pub mod epoch;
+pub mod fixed_point;
+pub mod halo2;
+pub mod merkle;
+pub mod mimc;
+pub mod model;
+pub mod msm;
+pub mod param_file;
+pub mod param_store;
+pub mod pool;
+pub mod profiler;
+pub mod proof_cache;
+pub mod ptau;
+pub mod queue;
+pub mod relay;
+pub mod revocation;
+pub mod rng;
+pub mod signatures;
+pub mod snarkjs;
+pub mod solidity;
+pub mod zkml;
+
+use attestation::{AttestationConfig, AttestedThreatReport, ThreatAttestation};
+use backend::{ProvingBackend, SrsBound, UniversalKeys, UniversalSrs};
+use batch::{batch_commitment, BatchSlot, BatchThreatCircuit, BATCH_CIRCUIT_VERSION, BATCH_SLOTS};
+use benchmark::{constraint_counts, CircuitBenchmark, HeapPeak, ProverBenchmark};
+use ceremony::{initial_parameters, Phase2Transcript};
+use circom::{CircomArtifacts, CircomCircuitConfig};
+use disclosure::{DisclosedMetadata, DisclosureCircuit, DisclosurePolicy, ThreatMetadata, DISCLOSURE_CIRCUIT_VERSION};
+use energy_range::{energy_commitment, node_field, EnergyRangeCircuit};
+use epoch::EpochKeys;
+use fixed_point::{encode_confidence, enforce_fixed_point};
+use halo2::{Halo2Keys, Halo2Srs};
+use mimc::{mimc_hash, mimc_hash_var};
+use model::{enforce_model_openings, ModelCommitment, ModelWitness};
+use msm::{MsmBackend, MsmBenchmark, MsmDevice, MsmEngine, BENCHMARK_SIZE};
+use param_file::{KeyType, ParamHeader};
+use param_store::ParamStore;
+use pool::{ProverPool, ProverPoolConfig};
+use profiler::region;
+use proof_cache::{ProofCache, ProofCacheConfig};
+use queue::{ProofQueue, ProofQueueConfig, ProofQueueStats};
+use ptau::transcript_hash;
+use relay::{BlindedWitness, DelegatedThreatCircuit, RelayConfig, RelayRequest, RelayResponse, DELEGATED_CIRCUIT_VERSION};
+use revocation::{RevocationList, SignedRevocationList};
+use rng::prover_rng;
+use signatures::{signature_of, SignatureMatchCircuit, SignatureTree};
+use snarkjs::{SnarkjsProof, SnarkjsVerifyingKey};
+use zkml::{InferenceCircuit, QuantizedClassifier, PARAM_BYTES};
+
+use crate::energy_monitor::{
+    gpu_adapters,
+    power_policy::PowerPolicy,
+    subsystems::{self, Subsystem},
+};
+
 /// ZK Circuit for threat detection
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ThreatDetectionCircuit {
     // Public inputs
     pub threat_hash: Option<Fr>,
     pub confidence_threshold: Option<Fr>,
+    pub model_commitment: Option<Fr>,
     
     // Private inputs (witness)
     pub transaction_data: Option<Vec<Fr>>,
-    pub ai_model_weights: Option<Vec<Fr>>,
+    pub ai_model_weights: Option<ModelWitness>,
     pub node_reputation: Option<Fr>,
     pub detection_algorithm: Option<Fr>,
 }
 
+/// Transaction data capacity of the threat circuit, in 31-byte chunks
+pub const TX_DATA_CHUNKS: usize = 32;
+
+/// Public input encoding written by this version: hex of the compressed canonical serialization
+pub const PUBLIC_INPUT_ENCODING: u8 = 1;
+
+/// Threat circuit constraint system version; bump whenever its constraints change.
+/// Version 1 was the circuit before `threat_hash` was bound to the transaction data, version 2
+/// before confidences were range-checked, version 3 before proofs were bound to a model commitment,
+/// version 4 before each proof opened 16 model chunks instead of 2
+pub const THREAT_CIRCUIT_VERSION: u32 = 5;
+
+/// Energy range circuit version; version 1 did not bind the node and period start
+pub const ENERGY_RANGE_CIRCUIT_VERSION: u32 = 2;
+
+/// Environment variable holding the node passphrase that encrypts ZK proving keys
+pub const PASSPHRASE_ENV: &str = "DAGSHIELD_NODE_PASSPHRASE";
+
+/// Last applied revocation list, under `params_dir`
+const REVOCATION_LIST_FILE: &str = "revocations.json";
+
+/// Universal SRS file, under `params_dir`
+const UNIVERSAL_SRS_FILE: &str = "universal_srs.bin";
+
+/// KZG SRS of the Halo2 backend, under `params_dir`
+const HALO2_SRS_FILE: &str = "halo2_kzg_srs.bin";
+
+/// Epoch folding and decider keys, under `params_dir`
+const EPOCH_KEYS_FILE: &str = "epoch_keys.bin";
+
+/// Confidence every detection is proven against
+const DETECTION_THRESHOLD: f64 = 0.7;
+
+/// Node reputation proven alongside detections (mock)
+const NODE_REPUTATION: f64 = 0.95;
+
+/// One detection to prove with `ZKProver::generate_threat_proofs_batch`
+#[derive(Debug, Clone)]
+pub struct ThreatInput {
+    pub transaction_data: Vec<u8>,
+    pub ai_confidence: f64,
+    pub node_id: String,
+}
+
 /// ZK Proof for threat detection
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ThreatProof {
     pub proof: Vec<u8>,
     pub public_inputs: Vec<String>,
+    /// Encoding of `public_inputs`; proofs from before versioning decode as 0 and are rejected
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    /// Threat circuit version the proof was made for; 0 for proofs from before versioning
+    #[serde(default)]
+    pub circuit_version: u32,
+    #[serde(default)]
+    pub backend: ProvingBackend,
+    pub verification_key_hash: String,
+    pub timestamp: u64,
+    /// Unix seconds after which verifiers reject the proof; 0 for proofs from before expiry,
+    /// which expire `proof_validity_secs` after `timestamp`
+    #[serde(default)]
+    pub expires_at: u64,
+    pub node_id: String,
+    /// Proved by a relay from a blinded witness; `circuit_version` is then the delegated circuit's
+    #[serde(default)]
+    pub delegated: bool,
+}
+
+impl ThreatProof {
+    /// Keccak256 of the public inputs, identifying the statement proven across re-proofs
+    pub fn public_input_hash(&self) -> H256 {
+        H256::from_slice(&Keccak256::digest(self.public_inputs.join(",").as_bytes()))
+    }
+
+    /// Circuit the proof was made with
+    pub fn circuit_kind(&self) -> CircuitKind {
+        if self.delegated {
+            CircuitKind::DelegatedThreat
+        } else {
+            CircuitKind::Threat
+        }
+    }
+
+    /// Call of the exported verifier's `verifyProof` (Groth16 only)
+    pub fn solidity_calldata(&self) -> Result<Vec<u8>> {
+        let (proof, public_inputs) = self.groth16_statement()?;
+        Ok(solidity::encode_calldata(&proof, &public_inputs))
+    }
+
+    /// `verifyProof` arguments without the selector, as attached to threat submissions
+    pub fn solidity_arguments(&self) -> Result<Vec<u8>> {
+        let (proof, public_inputs) = self.groth16_statement()?;
+        Ok(solidity::encode_arguments(&proof, &public_inputs))
+    }
+
+    fn groth16_statement(&self) -> Result<(Proof<Bn254>, Vec<Fr>)> {
+        if self.backend != ProvingBackend::Groth16 || self.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!("Only current Groth16 proofs can be verified on chain"));
+        }
+        let proof = Proof::<Bn254>::deserialize_compressed(&self.proof[..])
+            .context("Failed to deserialize proof")?;
+        let public_inputs = self.public_inputs
+            .iter()
+            .map(|input| decode_public_input(input))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((proof, public_inputs))
+    }
+
+    /// `proof.json` and `public.json` contents for `snarkjs groth16 verify` (Groth16 only)
+    pub fn snarkjs(&self) -> Result<(SnarkjsProof, Vec<String>)> {
+        if self.backend != ProvingBackend::Groth16 || self.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!("Only current Groth16 proofs can be exported to snarkjs"));
+        }
+        snarkjs_export(&self.proof, &self.public_inputs)
+    }
+}
+
+/// ZK proof from an imported Circom circuit
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircomProof {
+    pub proof: Vec<u8>,
+    /// Name of the circuit in `ZKProverConfig::circom_circuits`
+    pub circuit: String,
+    /// The circuit's public signals, encoded
+    pub public_inputs: Vec<String>,
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    pub verification_key_hash: String,
+    pub timestamp: u64,
+    pub node_id: String,
+}
+
+impl CircomProof {
+    /// `proof.json` and `public.json` contents for `snarkjs groth16 verify`
+    pub fn snarkjs(&self) -> Result<(SnarkjsProof, Vec<String>)> {
+        snarkjs_export(&self.proof, &self.public_inputs)
+    }
+}
+
+fn snarkjs_export(proof: &[u8], public_inputs: &[String]) -> Result<(SnarkjsProof, Vec<String>)> {
+    let proof = Proof::<Bn254>::deserialize_compressed(proof).context("Failed to deserialize proof")?;
+    let public_inputs = public_inputs
+        .iter()
+        .map(|input| decode_public_input(input))
+        .collect::<Result<Vec<_>>>()?;
+    Ok((snarkjs::export_proof(&proof), snarkjs::export_public_inputs(&public_inputs)))
+}
+
+/// One ZK proof covering an epoch's batch of threat detections
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchThreatProof {
+    pub proof: Vec<u8>,
+    pub epoch: u64,
+    /// Encoded threat hashes in slot order
+    pub threat_hashes: Vec<String>,
+    pub confidence_threshold: String,
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    #[serde(default)]
+    pub backend: ProvingBackend,
+    pub verification_key_hash: String,
+    pub timestamp: u64,
+    pub node_id: String,
+}
+
+/// Recursive proof folding every detection of an epoch, verified on chain at constant cost
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EpochProof {
+    /// Calldata for the decider verifier contract
+    pub calldata: Vec<u8>,
+    pub epoch: u64,
+    /// Encoded threat hashes in folding order
+    pub threat_hashes: Vec<String>,
+    pub confidence_threshold: String,
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    pub timestamp: u64,
+    pub node_id: String,
+}
+
+/// ZK proof that average power stayed below a threshold over a period
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnergyRangeProof {
+    pub proof: Vec<u8>,
+    pub max_avg_milliwatts: u64,
+    pub period_start: u64,
+    pub period_end: u64,
+    /// Hex of the compressed commitment to (energy, uptime, salt)
+    pub commitment: String,
+    #[serde(default)]
+    pub backend: ProvingBackend,
+    pub verification_key_hash: String,
+    pub timestamp: u64,
+    pub node_id: String,
+}
+
+/// ZK proof that a transaction matches a signature under the oracle's signature root,
+/// without revealing which one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignatureMatchProof {
+    pub proof: Vec<u8>,
+    pub threat_hash: String,
+    /// Encoded signature root the proof was made against
+    pub signature_root: String,
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    #[serde(default)]
+    pub backend: ProvingBackend,
+    pub verification_key_hash: String,
+    pub timestamp: u64,
+    pub node_id: String,
+}
+
+/// zkML proof that the registered classifier gave a transaction `score` (experimental)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InferenceProof {
+    pub proof: Vec<u8>,
+    pub threat_hash: String,
+    /// Encoded commitment to the classifier that produced the score
+    pub model_commitment: String,
+    pub score: i64,
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    #[serde(default)]
+    pub backend: ProvingBackend,
+    pub verification_key_hash: String,
+    pub timestamp: u64,
+    pub node_id: String,
+}
+
+/// Threat proof that reveals only the metadata fields the node's disclosure policy allows
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DisclosureProof {
+    pub proof: Vec<u8>,
+    /// Encoded threat hash, threshold and model commitment, as in `ThreatProof`
+    pub public_inputs: Vec<String>,
+    /// Encoded salted commitment to every metadata field, disclosed or not
+    pub metadata_commitment: String,
+    pub disclosed: DisclosedMetadata,
+    #[serde(default)]
+    pub public_input_encoding: u8,
+    #[serde(default)]
+    pub backend: ProvingBackend,
     pub verification_key_hash: String,
     pub timestamp: u64,
+    pub expires_at: u64,
     pub node_id: String,
 }
 
+/// ZK prover settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZKProverConfig {
+    pub enabled: bool,
+    pub backend: ProvingBackend,
+    /// Keys, or the universal SRS, are stored here with a checksum manifest
+    pub params_dir: PathBuf,
+    /// Encrypt proving keys at rest with the node passphrase
+    #[serde(default)]
+    pub encrypt_proving_keys: bool,
+    /// Universal SRS size (universal backends only); sized from the largest circuit when unset,
+    /// and never smaller than it
+    #[serde(default)]
+    pub srs_bound: Option<SrsBound>,
+    /// Published KZG SRS the Halo2 backend derives its keys from, in halo2 `ParamsKZG` format
+    /// with at least 2^16 rows; nodes must share it for their proofs to cross-verify. Imported
+    /// into `params_dir` on first start
+    #[serde(default)]
+    pub halo2_srs: Option<PathBuf>,
+    /// Older threat circuit versions still accepted; their verifying keys are read from `params_dir`
+    #[serde(default)]
+    pub accepted_circuit_versions: Vec<u32>,
+    /// Proving threads and queue
+    #[serde(default)]
+    pub pool: ProverPoolConfig,
+    /// Threat proof deadlines and backlog shedding
+    #[serde(default)]
+    pub queue: ProofQueueConfig,
+    /// Where Groth16 MSMs run
+    #[serde(default)]
+    pub msm_backend: MsmBackend,
+    /// Threat proof reuse; proofs are stored under `params_dir`
+    #[serde(default)]
+    pub proof_cache: ProofCacheConfig,
+    /// Set up folding keys for recursive epoch proofs (needs the `folding` feature)
+    #[serde(default)]
+    pub epoch_proofs: bool,
+    /// Externally designed threat circuits compiled with Circom (needs the `circom` feature)
+    #[serde(default)]
+    pub circom_circuits: Vec<CircomCircuitConfig>,
+    /// Deployed ONNX model that threat proofs commit to
+    #[serde(default)]
+    pub model_path: Option<PathBuf>,
+    /// Quantized classifier run inside inference proofs (experimental, needs `inference` in
+    /// `optional_circuits`)
+    #[serde(default)]
+    pub classifier_path: Option<PathBuf>,
+    /// Seconds a threat proof is accepted after it was generated
+    #[serde(default = "default_proof_validity")]
+    pub proof_validity_secs: u64,
+    /// How often verifying key registrations are re-read from the oracle
+    #[serde(default = "default_chain_sync")]
+    pub chain_sync_secs: u64,
+    /// Address of the oracle's revocation key; revocation lists are not applied without it
+    #[serde(default)]
+    pub revocation_signer: Option<Address>,
+    /// Proof delegation to relay provers, and serving other nodes as one
+    #[serde(default)]
+    pub relay: RelayConfig,
+    /// Committee whose threshold-attested threat reports are accepted
+    #[serde(default)]
+    pub attestation: AttestationConfig,
+    /// Threat metadata fields disclosure proofs make public
+    #[serde(default)]
+    pub disclosure: DisclosurePolicy,
+    /// Circuits set up besides the core threat and energy range ones (`batch`,
+    /// `signature_match`, `inference`, `disclosure`); the delegated threat circuit is also set
+    /// up whenever a relay is configured. Proofs of circuits not set up are refused
+    #[serde(default)]
+    pub optional_circuits: Vec<CircuitKind>,
+}
+
+fn default_proof_validity() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_chain_sync() -> u64 {
+    600
+}
+
+impl Default for ZKProverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            backend: ProvingBackend::Groth16,
+            params_dir: PathBuf::from("./zk_params"),
+            encrypt_proving_keys: false,
+            srs_bound: None,
+            halo2_srs: None,
+            accepted_circuit_versions: Vec::new(),
+            pool: ProverPoolConfig::default(),
+            queue: ProofQueueConfig::default(),
+            msm_backend: MsmBackend::default(),
+            proof_cache: ProofCacheConfig::default(),
+            epoch_proofs: false,
+            circom_circuits: Vec::new(),
+            model_path: None,
+            classifier_path: None,
+            proof_validity_secs: default_proof_validity(),
+            chain_sync_secs: default_chain_sync(),
+            revocation_signer: None,
+            relay: RelayConfig::default(),
+            attestation: AttestationConfig::default(),
+            disclosure: DisclosurePolicy::default(),
+            optional_circuits: Vec::new(),
+        }
+    }
+}
+
+/// Circuits the prover holds keys for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitKind {
+    Threat,
+    EnergyRange,
+    Batch,
+    SignatureMatch,
+    Inference,
+    DelegatedThreat,
+    Disclosure,
+}
+
+impl CircuitKind {
+    pub const ALL: [CircuitKind; 7] = [
+        Self::Threat,
+        Self::EnergyRange,
+        Self::Batch,
+        Self::SignatureMatch,
+        Self::Inference,
+        Self::DelegatedThreat,
+        Self::Disclosure,
+    ];
+
+    /// Circuits every node sets up; the rest are opt-in through `optional_circuits`
+    pub const CORE: [CircuitKind; 2] = [Self::Threat, Self::EnergyRange];
+
+    /// Parameter file prefix
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Threat => "",
+            Self::EnergyRange => "energy_",
+            Self::Batch => "batch_",
+            Self::SignatureMatch => "signature_",
+            Self::Inference => "inference_",
+            Self::DelegatedThreat => "delegated_",
+            Self::Disclosure => "disclosure_",
+        }
+    }
+
+    /// Index the oracle contract registers verifying keys under
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Threat => 0,
+            Self::EnergyRange => 1,
+            Self::Batch => 2,
+            Self::SignatureMatch => 3,
+            Self::Inference => 4,
+            Self::DelegatedThreat => 5,
+            Self::Disclosure => 6,
+        }
+    }
+
+    /// Current constraint system version, written into parameter files
+    pub fn version(self) -> u32 {
+        match self {
+            Self::Threat => THREAT_CIRCUIT_VERSION,
+            Self::EnergyRange => ENERGY_RANGE_CIRCUIT_VERSION,
+            Self::SignatureMatch | Self::Inference => 1,
+            Self::Batch => BATCH_CIRCUIT_VERSION,
+            Self::DelegatedThreat => DELEGATED_CIRCUIT_VERSION,
+            Self::Disclosure => DISCLOSURE_CIRCUIT_VERSION,
+        }
+    }
+}
+
+/// Local keys compared with the verifying key registered on chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisteredKeyStatus {
+    Matching,
+    /// Local parameters differ from the registered key; proving is refused
+    Mismatched,
+    /// The registered key changed since it was last read
+    Upgraded,
+    /// No key is registered for the circuit
+    Unregistered,
+}
+
 /// ZK Proving System
 pub struct ZKProver {
     pub enabled: bool,
-    pub proving_key: Option<ProvingKey<Bn254>>,
+    pub config: ZKProverConfig,
+    pub proving_key: Option<Arc<ProvingKey<Bn254>>>,
     pub verifying_key: Option<VerifyingKey<Bn254>>,
     pub prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    /// Verifying keys for older threat circuit versions, by version
+    pub legacy_verifying_keys: HashMap<u32, PreparedVerifyingKey<Bn254>>,
+    pub energy_proving_key: Option<Arc<ProvingKey<Bn254>>>,
+    pub energy_verifying_key: Option<VerifyingKey<Bn254>>,
+    pub energy_prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    pub batch_proving_key: Option<Arc<ProvingKey<Bn254>>>,
+    pub batch_verifying_key: Option<VerifyingKey<Bn254>>,
+    pub batch_prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    pub signature_proving_key: Option<Arc<ProvingKey<Bn254>>>,
+    pub signature_verifying_key: Option<VerifyingKey<Bn254>>,
+    pub signature_prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    pub inference_proving_key: Option<Arc<ProvingKey<Bn254>>>,
+    pub inference_verifying_key: Option<VerifyingKey<Bn254>>,
+    pub inference_prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    pub delegated_proving_key: Option<Arc<ProvingKey<Bn254>>>,
+    pub delegated_verifying_key: Option<VerifyingKey<Bn254>>,
+    pub delegated_prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    pub disclosure_proving_key: Option<Arc<ProvingKey<Bn254>>>,
+    pub disclosure_verifying_key: Option<VerifyingKey<Bn254>>,
+    pub disclosure_prepared_vk: Option<PreparedVerifyingKey<Bn254>>,
+    /// Per-circuit keys indexed from the universal SRS
+    pub universal_keys: HashMap<CircuitKind, Arc<UniversalKeys>>,
+    /// Threat circuit keys derived from the KZG SRS (Halo2 backend)
+    pub halo2_keys: Option<Arc<Halo2Keys>>,
     pub circuit_cache: Arc<RwLock<HashMap<String, ThreatDetectionCircuit>>>,
-    pub proof_cache: Arc<RwLock<HashMap<String, ThreatProof>>>,
+    /// Generated threat proofs, opened at initialization
+    pub proof_cache: Option<ProofCache>,
+    /// Runs proofs off the async runtime
+    pub pool: ProverPool,
+    /// Coalesces and sheds threat proof requests ahead of the pool
+    pub proof_queue: ProofQueue<ThreatProof>,
+    /// Groth16 MSM engine, chosen at initialization
+    pub msm: Arc<MsmEngine>,
+    /// CPU vs GPU timing from the last engine selection
+    pub msm_benchmark: Option<MsmBenchmark>,
+    /// Folding and decider keys for epoch proofs
+    pub epoch_keys: Option<Arc<EpochKeys>>,
+    /// Imported Circom circuits by name
+    pub circom_circuits: HashMap<String, Arc<CircomArtifacts>>,
+    /// Commitment to the model this node's threat proofs are bound to
+    pub model: Option<Arc<ModelCommitment>>,
+    /// Model commitments threat proofs are accepted for (registered model versions)
+    pub model_commitments: HashSet<Fr>,
+    /// Classifier inference proofs run
+    pub classifier: Option<Arc<QuantizedClassifier>>,
+    /// Verifying key hashes registered with the oracle contract, hex like `verification_key_hash`;
+    /// updated by the periodic chain sync while the prover is shared
+    pub registered_vk_hashes: RwLock<HashMap<CircuitKind, String>>,
+    /// Node keys and circuit versions whose threat proofs are rejected; replaced by the periodic
+    /// chain sync while the prover is shared
+    pub revocations: RwLock<RevocationList>,
+    /// Node passphrase proving keys are encrypted with
+    passphrase: Option<String>,
+    /// Node operator key relay requests are signed with
+    relay_operator: Option<LocalWallet>,
 }
 
 impl ZKProver {
     /// Create new ZK prover
     pub fn new(enabled: bool) -> Self {
-        Self {
+        Self::with_config(ZKProverConfig {
             enabled,
+            ..ZKProverConfig::default()
+        })
+    }
+
+    pub fn with_config(config: ZKProverConfig) -> Self {
+        Self {
+            pool: ProverPool::new(config.pool.clone()),
+            proof_queue: ProofQueue::new(config.queue.clone()),
+            msm: Arc::new(MsmEngine::Cpu),
+            msm_benchmark: None,
+            enabled: config.enabled,
+            config,
             proving_key: None,
             verifying_key: None,
             prepared_vk: None,
+            legacy_verifying_keys: HashMap::new(),
+            energy_proving_key: None,
+            energy_verifying_key: None,
+            energy_prepared_vk: None,
+            batch_proving_key: None,
+            batch_verifying_key: None,
+            batch_prepared_vk: None,
+            signature_proving_key: None,
+            signature_verifying_key: None,
+            signature_prepared_vk: None,
+            inference_proving_key: None,
+            inference_verifying_key: None,
+            inference_prepared_vk: None,
+            delegated_proving_key: None,
+            delegated_verifying_key: None,
+            delegated_prepared_vk: None,
+            disclosure_proving_key: None,
+            disclosure_verifying_key: None,
+            disclosure_prepared_vk: None,
+            universal_keys: HashMap::new(),
+            halo2_keys: None,
             circuit_cache: Arc::new(RwLock::new(HashMap::new())),
-            proof_cache: Arc::new(RwLock::new(HashMap::new())),
+            proof_cache: None,
+            epoch_keys: None,
+            circom_circuits: HashMap::new(),
+            model: None,
+            model_commitments: HashSet::new(),
+            classifier: None,
+            registered_vk_hashes: RwLock::new(HashMap::new()),
+            revocations: RwLock::new(RevocationList::default()),
+            passphrase: None,
+            relay_operator: None,
+        }
+    }
+
+    /// Passphrase for encrypted proving keys; set before `initialize`
+    pub fn set_passphrase(&mut self, passphrase: String) {
+        self.passphrase = Some(passphrase);
+    }
+
+    /// Sign relay requests with the key the node is registered under
+    pub fn set_relay_operator(&mut self, operator: LocalWallet) {
+        self.relay_operator = Some(operator);
+    }
+
+    fn param_store(&self) -> ParamStore {
+        ParamStore::new(&self.config.params_dir, self.passphrase.clone())
+    }
+
+    /// Initialize ZK system with trusted setup
+    pub async fn initialize(&mut self) -> Result<()> {
+        if !self.enabled {
+            info!("ZK proofs disabled, skipping initialization");
+            return Ok(());
+        }
+
+        info!("🔐 Initializing ZK proof system ({:?})...", self.config.backend);
+        if rng::is_deterministic() {
+            // Release builds are what ships, so they never prove without hiding randomness
+            if !cfg!(debug_assertions) {
+                return Err(anyhow::anyhow!(
+                    "Built with the deterministic-proving feature, whose proofs are not zero-knowledge; refusing to prove in a release build"
+                ));
+            }
+            warn!("⚠️ Deterministic proving: proofs are reproducible and NOT zero-knowledge, use only in tests");
+        }
+
+        if !self.config.backend.is_available() {
+            return Err(anyhow::anyhow!("Built without the {:?} proving backend", self.config.backend));
+        }
+        if self.config.encrypt_proving_keys && self.passphrase.is_none() {
+            return Err(anyhow::anyhow!("Proving key encryption is enabled but no node passphrase was given"));
+        }
+        self.open_proof_cache();
+        self.load_revocation_list();
+        if self.config.epoch_proofs {
+            self.setup_epoch_keys();
+        }
+        self.load_circom_circuits();
+        if let Some(path) = self.config.model_path.clone() {
+            match ModelCommitment::from_file(&path) {
+                Ok(model) => self.set_model(model),
+                Err(e) => warn!("⚠️ No model commitment, threat proofs unavailable: {}", e),
+            }
+        }
+        if let Some(path) = self.config.classifier_path.clone() {
+            match QuantizedClassifier::from_file(&path) {
+                Ok(classifier) => self.classifier = Some(Arc::new(classifier)),
+                Err(e) => warn!("⚠️ No classifier, inference proofs unavailable: {}", e),
+            }
+        }
+        match self.config.backend {
+            ProvingBackend::Groth16 => {}
+            ProvingBackend::Marlin => return self.initialize_universal(),
+            ProvingBackend::Halo2 => return self.initialize_halo2(),
+        }
+        self.select_msm_engine();
+        if let Err(e) = self.migrate_unversioned_parameters() {
+            warn!("⚠️ Could not migrate unversioned ZK parameters: {:#}", e);
+        }
+
+        // Delegating nodes prove threats through relays, checked against the registered key
+        if self.config.relay.delegate {
+            info!("📡 Threat proofs are delegated to relay provers, skipping threat key generation");
+        } else {
+            // Try to load existing parameters
+            if let Ok((pk, vk)) = self.load_parameters(CircuitKind::Threat).await {
+                self.proving_key = Some(Arc::new(pk));
+                self.verifying_key = Some(vk.clone());
+                self.prepared_vk = Some(prepare_verifying_key(&vk));
+                info!("✅ Loaded existing ZK parameters");
+            } else {
+                // Generate new parameters (trusted setup)
+                info!("🔧 Generating new ZK parameters (this may take a while)...");
+                let (pk, vk) = self.generate_parameters().await?;
+            
+                self.verifying_key = Some(vk.clone());
+                self.prepared_vk = Some(prepare_verifying_key(&vk));
+            
+                // Save parameters for future use
+                self.save_parameters(CircuitKind::Threat, &pk, &vk).await?;
+                self.proving_key = Some(Arc::new(pk));
+                info!("✅ Generated and saved new ZK parameters");
+            }
+
+            for version in self.config.accepted_circuit_versions.clone() {
+                if version == THREAT_CIRCUIT_VERSION {
+                    continue;
+                }
+                match self.load_verifying_key(CircuitKind::Threat, version) {
+                    Ok(vk) => self.register_verifying_key(version, &vk),
+                    Err(e) => warn!("⚠️ Threat circuit v{} keys unavailable, its proofs will be rejected: {}", version, e),
+                }
+            }
+        }
+
+        // Every other circuit has its own keys
+        let (pk, vk) = self.load_or_generate_parameters(CircuitKind::EnergyRange, EnergyRangeCircuit::default()).await?;
+        self.energy_prepared_vk = Some(prepare_verifying_key(&vk));
+        self.energy_proving_key = Some(Arc::new(pk));
+        self.energy_verifying_key = Some(vk);
+
+        if self.circuit_enabled(CircuitKind::Batch) {
+            let (pk, vk) = self.load_or_generate_parameters(CircuitKind::Batch, BatchThreatCircuit::default()).await?;
+            self.batch_prepared_vk = Some(prepare_verifying_key(&vk));
+            self.batch_proving_key = Some(Arc::new(pk));
+            self.batch_verifying_key = Some(vk);
+        }
+
+        if self.circuit_enabled(CircuitKind::SignatureMatch) {
+            let (pk, vk) = self.load_or_generate_parameters(CircuitKind::SignatureMatch, SignatureMatchCircuit::default()).await?;
+            self.signature_prepared_vk = Some(prepare_verifying_key(&vk));
+            self.signature_proving_key = Some(Arc::new(pk));
+            self.signature_verifying_key = Some(vk);
+        }
+
+        if self.circuit_enabled(CircuitKind::Inference) {
+            let (pk, vk) = self.load_or_generate_parameters(CircuitKind::Inference, InferenceCircuit::default()).await?;
+            self.inference_prepared_vk = Some(prepare_verifying_key(&vk));
+            self.inference_proving_key = Some(Arc::new(pk));
+            self.inference_verifying_key = Some(vk);
+        }
+
+        if self.circuit_enabled(CircuitKind::DelegatedThreat) {
+            let (pk, vk) = self.load_or_generate_parameters(CircuitKind::DelegatedThreat, DelegatedThreatCircuit::default()).await?;
+            self.delegated_prepared_vk = Some(prepare_verifying_key(&vk));
+            self.delegated_proving_key = Some(Arc::new(pk));
+            self.delegated_verifying_key = Some(vk);
+        }
+
+        if self.circuit_enabled(CircuitKind::Disclosure) {
+            let (pk, vk) = self.load_or_generate_parameters(CircuitKind::Disclosure, DisclosureCircuit::default()).await?;
+            self.disclosure_prepared_vk = Some(prepare_verifying_key(&vk));
+            self.disclosure_proving_key = Some(Arc::new(pk));
+            self.disclosure_verifying_key = Some(vk);
+        }
+
+        Ok(())
+    }
+
+    /// Whether keys for `kind` are set up: core circuits always, the delegated threat circuit
+    /// when serving as a relay, the rest when listed in `optional_circuits`
+    pub fn circuit_enabled(&self, kind: CircuitKind) -> bool {
+        CircuitKind::CORE.contains(&kind)
+            || self.config.optional_circuits.contains(&kind)
+            || (kind == CircuitKind::DelegatedThreat && self.config.relay.listen.is_some())
+    }
+
+    /// Open the persistent proof cache and drop expired proofs; proving continues uncached
+    /// if it cannot be opened
+    fn open_proof_cache(&mut self) {
+        let path = self.config.params_dir.join("proof_cache");
+        let opened = ProofCache::open(&path, self.config.proof_cache.clone()).and_then(|cache| {
+            cache.prune(chrono::Utc::now().timestamp() as u64)?;
+            Ok(cache)
+        });
+        match opened {
+            Ok(cache) => {
+                info!("✅ Opened proof cache with {} proofs", cache.len());
+                self.proof_cache = Some(cache);
+            }
+            Err(e) => warn!("⚠️ Proof cache unavailable, proofs will not be reused: {}", e),
+        }
+    }
+
+    /// Apply the revocation list saved by the last sync; a missing or invalid one is ignored
+    fn load_revocation_list(&mut self) {
+        let path = self.config.params_dir.join(REVOCATION_LIST_FILE);
+        if !path.exists() {
+            return;
+        }
+        if let Err(e) = SignedRevocationList::load(&path).and_then(|signed| self.apply_revocation_list(signed)) {
+            warn!("⚠️ Saved revocation list ignored: {}", e);
+        }
+    }
+
+    /// Apply a revocation list signed by `revocation_signer` and save it to `params_dir`.
+    /// `false` when it is not newer than the current one
+    pub fn apply_revocation_list(&self, signed: SignedRevocationList) -> Result<bool> {
+        let signer = self.config.revocation_signer.context("No revocation signer configured")?;
+        signed.verify(signer)?;
+        if signed.list.version <= self.revocations.read().unwrap().version {
+            return Ok(false);
+        }
+
+        info!(
+            "🚫 Revocation list v{}: {} nodes, circuit versions {:?}",
+            signed.list.version,
+            signed.list.revoked_nodes.len(),
+            signed.list.revoked_circuit_versions
+        );
+        let path = self.config.params_dir.join(REVOCATION_LIST_FILE);
+        fs::create_dir_all(&self.config.params_dir)?;
+        if let Err(e) = signed.save(&path) {
+            warn!("⚠️ Revocation list not saved, it will be re-synced after a restart: {}", e);
+        }
+        *self.revocations.write().unwrap() = signed.list;
+        Ok(true)
+    }
+
+    /// Whether `proof` has neither expired nor been revoked. The prover declares `expires_at`,
+    /// so it can only shorten the configured validity
+    fn is_live(&self, proof: &ThreatProof, now: u64) -> bool {
+        // Revoked circuit versions are threat circuit versions
+        let revoked = if proof.delegated {
+            self.revocations.read().unwrap().revoked_nodes.contains(&proof.node_id)
+        } else {
+            self.revocations.read().unwrap().is_revoked(&proof.node_id, proof.circuit_version)
+        };
+        self.within_validity(&proof.node_id, proof.timestamp, proof.expires_at, revoked, now)
+    }
+
+    /// Whether a proof made at `timestamp` is unrevoked and unexpired at `now`, expiring at
+    /// `expires_at` or after `proof_validity_secs`, whichever comes first
+    fn within_validity(&self, node_id: &str, timestamp: u64, expires_at: u64, revoked: bool, now: u64) -> bool {
+        let validity_end = timestamp.saturating_add(self.config.proof_validity_secs);
+        let expires_at = match expires_at {
+            0 => validity_end,
+            expires_at => expires_at.min(validity_end),
+        };
+        if now >= expires_at {
+            warn!("❌ Proof from {} expired at {}", node_id, expires_at);
+            return false;
+        }
+        if revoked {
+            warn!("❌ Proof from {} is revoked", node_id);
+            return false;
+        }
+        true
+    }
+
+    /// Load the epoch folding keys, generating and saving them on first start; the deployed
+    /// decider verifier is derived from them. Epoch proofs stay unavailable if this fails
+    fn setup_epoch_keys(&mut self) {
+        let store = self.param_store();
+        if let Ok(keys) = store.read(EPOCH_KEYS_FILE).and_then(|bytes| EpochKeys::from_bytes(&bytes)) {
+            self.epoch_keys = Some(Arc::new(keys));
+            info!("✅ Loaded epoch folding keys");
+            return;
+        }
+
+        info!("🔧 Setting up epoch folding keys (this may take a while)...");
+        match EpochKeys::setup(&mut prover_rng()) {
+            Ok(keys) => {
+                let saved = keys
+                    .to_bytes()
+                    .and_then(|bytes| store.write(EPOCH_KEYS_FILE, &bytes, self.config.encrypt_proving_keys));
+                if let Err(e) = saved {
+                    warn!("⚠️ Could not save epoch folding keys, they will be regenerated on restart: {:#}", e);
+                }
+                self.epoch_keys = Some(Arc::new(keys));
+                info!("✅ Epoch folding keys ready");
+            }
+            Err(e) => warn!("⚠️ Epoch proofs unavailable: {}", e),
+        }
+    }
+
+    /// Bind this node's threat proofs to `model`, and accept proofs made for it
+    pub fn set_model(&mut self, model: ModelCommitment) {
+        let commitment = model.commitment();
+        info!("🧠 Threat proofs bound to model commitment {} ({} chunks)", commitment, model.chunk_count());
+        self.model_commitments.insert(commitment);
+        self.model = Some(Arc::new(model));
+    }
+
+    /// Accept threat proofs made for another registered model version
+    pub fn register_model_commitment(&mut self, commitment: Fr) {
+        if self.model_commitments.insert(commitment) {
+            info!("🧠 Accepting threat proofs for model commitment {}", commitment);
+        }
+    }
+
+    /// Accept threat proofs for the model the oracle has registered (`modelCommitment`, zero
+    /// when none is); a local model other than it makes proofs the oracle will not accept
+    pub fn set_registered_model_commitment(&mut self, commitment: [u8; 32]) -> Result<()> {
+        if commitment == [0u8; 32] {
+            return Ok(());
+        }
+        let registered = Fr::from_be_bytes_mod_order(&commitment);
+        if registered.into_bigint().to_bytes_be() != commitment {
+            return Err(anyhow::anyhow!("Model commitment 0x{} is not a field element", hex::encode(commitment)));
+        }
+
+        if let Some(model) = &self.model {
+            if model.commitment() != registered {
+                error!("🚨 Local model commitment {} is not the registered {}", model.commitment(), registered);
+            }
+        }
+        self.register_model_commitment(registered);
+        Ok(())
+    }
+
+    /// Whether `proof` was made for a registered model; threat circuit versions before model
+    /// binding have no commitment to check
+    fn accepts_model(&self, proof: &ThreatProof) -> Result<bool> {
+        if !proof.delegated && proof.circuit_version < 4 {
+            return Ok(true);
+        }
+        let commitment = proof.public_inputs.get(2).context("Threat proof has no model commitment")?;
+        if !self.model_commitments.contains(&decode_public_input(commitment)?) {
+            warn!("❌ Threat proof from {} is for an unregistered model", proof.node_id);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Record the verifying key hash the oracle has registered for `kind` (zero when none is).
+    /// Proofs of `kind` are refused while the local keys differ from it
+    pub fn set_registered_vk_hash(&self, kind: CircuitKind, hash: [u8; 32]) -> Result<RegisteredKeyStatus> {
+        if hash == [0u8; 32] {
+            if self.registered_vk_hashes.write().unwrap().remove(&kind).is_some() {
+                warn!("⚠️ {:?} verifying key was unregistered on chain", kind);
+            }
+            return Ok(RegisteredKeyStatus::Unregistered);
+        }
+
+        let registered = hex::encode(hash);
+        let local = self.verification_key_hash(kind)?;
+        let previous = self.registered_vk_hashes.write().unwrap().insert(kind, registered.clone());
+
+        if let Some(previous) = previous.as_ref().filter(|previous| **previous != registered) {
+            error!(
+                "🚨 On-chain {:?} circuit upgraded (verifying key {} -> {}){}",
+                kind,
+                previous,
+                registered,
+                if local == registered { "" } else { ", proving disabled until local parameters are updated" }
+            );
+            return Ok(RegisteredKeyStatus::Upgraded);
+        }
+        if local != registered {
+            error!("🚨 Local {:?} verifying key {} is not the registered {}, proving disabled", kind, local, registered);
+            return Ok(RegisteredKeyStatus::Mismatched);
+        }
+        if previous.is_none() {
+            info!("🔑 {:?} verifying key matches the on-chain registration", kind);
+        }
+        Ok(RegisteredKeyStatus::Matching)
+    }
+
+    /// Refuse to prove `kind` with keys whose proofs the chain would reject
+    fn check_registered_vk(&self, kind: CircuitKind) -> Result<()> {
+        let Some(registered) = self.registered_vk_hashes.read().unwrap().get(&kind).cloned() else {
+            return Ok(());
+        };
+        let local = self.verification_key_hash(kind)?;
+        if local != registered {
+            return Err(anyhow::anyhow!(
+                "Local {:?} verifying key {} does not match the registered {}",
+                kind,
+                local,
+                registered
+            ));
+        }
+        Ok(())
+    }
+
+    /// Load the configured Circom circuits; one that fails to load is skipped
+    fn load_circom_circuits(&mut self) {
+        for config in self.config.circom_circuits.clone() {
+            let name = config.name.clone();
+            match CircomArtifacts::load(config) {
+                Ok(artifacts) => {
+                    info!("✅ Loaded Circom circuit {}", name);
+                    self.circom_circuits.insert(name, Arc::new(artifacts));
+                }
+                Err(e) => warn!("⚠️ Circom circuit {} unavailable: {}", name, e),
+            }
+        }
+    }
+
+    /// Pick the MSM engine from the configured backend and the GPUs found by the hardware
+    /// probe; an automatically chosen GPU is kept only if it beats the CPU
+    fn select_msm_engine(&mut self) {
+        let engine = MsmEngine::select(self.config.msm_backend, &gpu_adapters::enumerate_adapters());
+        if engine.device() == MsmDevice::Cpu {
+            self.msm = Arc::new(engine);
+            return;
+        }
+
+        let benchmark = engine.benchmark(BENCHMARK_SIZE);
+        if self.config.msm_backend == MsmBackend::Auto && benchmark.speedup() <= 1.0 {
+            info!("🖥️ {:?} MSM is not faster than the CPU, proving on the CPU", benchmark.device);
+            self.msm = Arc::new(MsmEngine::Cpu);
+        } else {
+            info!("⚡ Proving with {:?} MSM", benchmark.device);
+            self.msm = Arc::new(engine);
+        }
+        self.msm_benchmark = Some(benchmark);
+    }
+
+    /// Load or create the universal SRS and index every circuit from it
+    fn initialize_universal(&mut self) -> Result<()> {
+        fs::create_dir_all(&self.config.params_dir)?;
+        let store = self.param_store();
+
+        // Indexing is deterministic, so circuit changes need no new setup unless they outgrow it
+        let stored = store.read(UNIVERSAL_SRS_FILE).and_then(|bytes| UniversalSrs::from_bytes(&bytes));
+        let keys = match stored.and_then(|srs| self.index_universal(&srs)) {
+            Ok(keys) => keys,
+            Err(e) => {
+                debug!("No usable universal SRS: {:#}", e);
+                let bound = self.universal_srs_bound()?;
+                info!("🔧 Generating universal SRS for {} constraints (this may take a while)...", bound.max_constraints);
+                let srs = UniversalSrs::setup(&bound)?;
+                store.write(UNIVERSAL_SRS_FILE, &srs.to_bytes()?, false)?;
+                self.index_universal(&srs)?
+            }
+        };
+        self.universal_keys = keys;
+        info!("✅ Indexed {} circuits from the universal SRS", self.universal_keys.len());
+
+        Ok(())
+    }
+
+    /// SRS size fitting the largest circuit (and the configured bound, if larger)
+    fn universal_srs_bound(&self) -> Result<SrsBound> {
+        let required = [
+            SrsBound::of(ThreatDetectionCircuit::default())?,
+            SrsBound::of(EnergyRangeCircuit::default())?,
+            SrsBound::of(BatchThreatCircuit::default())?,
+            SrsBound::of(SignatureMatchCircuit::default())?,
+            SrsBound::of(InferenceCircuit::default())?,
+            SrsBound::of(DelegatedThreatCircuit::default())?,
+            SrsBound::of(DisclosureCircuit::default())?,
+        ]
+        .into_iter()
+        .fold(SrsBound::default(), SrsBound::cover);
+        Ok(self.config.srs_bound.map_or(required, |configured| configured.cover(required)).padded())
+    }
+
+    /// Index the enabled circuits; the SRS is sized for all of them, so enabling one later
+    /// needs no new setup
+    fn index_universal(&self, srs: &UniversalSrs) -> Result<HashMap<CircuitKind, Arc<UniversalKeys>>> {
+        CircuitKind::ALL
+            .into_iter()
+            .filter(|kind| self.circuit_enabled(*kind))
+            .map(|kind| {
+                let keys = match kind {
+                    CircuitKind::Threat => srs.index(ThreatDetectionCircuit::default())?,
+                    CircuitKind::EnergyRange => srs.index(EnergyRangeCircuit::default())?,
+                    CircuitKind::Batch => srs.index(BatchThreatCircuit::default())?,
+                    CircuitKind::SignatureMatch => srs.index(SignatureMatchCircuit::default())?,
+                    CircuitKind::Inference => srs.index(InferenceCircuit::default())?,
+                    CircuitKind::DelegatedThreat => srs.index(DelegatedThreatCircuit::default())?,
+                    CircuitKind::Disclosure => srs.index(DisclosureCircuit::default())?,
+                };
+                Ok((kind, Arc::new(keys)))
+            })
+            .collect()
+    }
+
+    /// Load the imported KZG SRS, importing the published one on first start, and derive the
+    /// Halo2 threat circuit keys from it
+    fn initialize_halo2(&mut self) -> Result<()> {
+        fs::create_dir_all(&self.config.params_dir)?;
+        let store = self.param_store();
+
+        // Key generation is deterministic, so circuit changes need no new setup unless they outgrow it
+        let stored = store.read(HALO2_SRS_FILE).and_then(|bytes| Halo2Srs::from_bytes(&bytes));
+        let keys = match stored.and_then(Halo2Srs::keygen) {
+            Ok(keys) => keys,
+            Err(e) => {
+                debug!("No usable imported KZG SRS: {:#}", e);
+                let path = self.config.halo2_srs.as_ref()
+                    .context("The Halo2 backend needs a published KZG SRS (`halo2_srs`)")?;
+                info!("📥 Importing KZG SRS for Halo2 from {}", path.display());
+                let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                let keys = Halo2Srs::from_bytes(&bytes)?.keygen()?;
+                store.write(HALO2_SRS_FILE, &bytes, false)?;
+                keys
+            }
+        };
+        self.halo2_keys = Some(Arc::new(keys));
+        info!("✅ Derived Halo2 threat circuit keys; other circuits are unavailable under Halo2");
+
+        Ok(())
+    }
+
+    /// Prove `circuit` with the configured backend on the proving pool, ahead of queued jobs
+    /// with lower `priority`; returns the serialized proof
+    async fn prove<C>(&self, kind: CircuitKind, priority: f64, circuit: C) -> Result<Vec<u8>>
+    where
+        C: ark_relations::r1cs::ConstraintSynthesizer<Fr> + Send + 'static,
+    {
+        self.prove_before(kind, priority, None, circuit).await
+    }
+
+    /// `prove`, failing if the job is still queued at `deadline`
+    async fn prove_before<C>(&self, kind: CircuitKind, priority: f64, deadline: Option<Instant>, circuit: C) -> Result<Vec<u8>>
+    where
+        C: ark_relations::r1cs::ConstraintSynthesizer<Fr> + Send + 'static,
+    {
+        self.check_registered_vk(kind)?;
+
+        let job = match self.config.backend {
+            ProvingBackend::Groth16 => {
+                let proving_key = match kind {
+                    CircuitKind::Threat => self.proving_key.clone(),
+                    CircuitKind::EnergyRange => self.energy_proving_key.clone(),
+                    CircuitKind::Batch => self.batch_proving_key.clone(),
+                    CircuitKind::SignatureMatch => self.signature_proving_key.clone(),
+                    CircuitKind::Inference => self.inference_proving_key.clone(),
+                    CircuitKind::DelegatedThreat => self.delegated_proving_key.clone(),
+                    CircuitKind::Disclosure => self.disclosure_proving_key.clone(),
+                }
+                .with_context(|| format!("{:?} proving key not initialized", kind))?;
+
+                let msm = Arc::clone(&self.msm);
+                self.pool.submit_before(priority, deadline, move || {
+                    let mut rng = prover_rng();
+                    let proof = subsystems::measure(Subsystem::ZkProving, || msm.prove(circuit, &proving_key, &mut rng))
+                        .with_context(|| format!("Failed to create {:?} proof", kind))?;
+
+                    let mut proof_bytes = Vec::new();
+                    proof.serialize_compressed(&mut proof_bytes)
+                        .context("Failed to serialize proof")?;
+                    Ok(proof_bytes)
+                })?
+            }
+            ProvingBackend::Marlin => {
+                let keys = self.universal_keys.get(&kind).cloned()
+                    .with_context(|| format!("{:?} circuit not indexed", kind))?;
+                self.pool.submit_before(priority, deadline, move || {
+                    subsystems::measure(Subsystem::ZkProving, || keys.prove(circuit))
+                        .with_context(|| format!("Failed to create {:?} proof", kind))
+                })?
+            }
+            ProvingBackend::Halo2 => {
+                let keys = self.halo2_keys.clone().context("Halo2 keys not initialized")?;
+                // Only the threat circuit has a Halo2 version
+                let circuit = (Box::new(circuit) as Box<dyn std::any::Any>)
+                    .downcast::<ThreatDetectionCircuit>()
+                    .map_err(|_| anyhow::anyhow!("{:?} proofs are not available under Halo2", kind))?;
+                self.pool.submit_before(priority, deadline, move || {
+                    subsystems::measure(Subsystem::ZkProving, || keys.prove(&circuit))
+                        .with_context(|| format!("Failed to create {:?} proof", kind))
+                })?
+            }
+        };
+        job.await
+    }
+
+    /// Scale proving concurrency with the node's power policy; deferred proofs stay queued
+    pub fn apply_power_policy(&self, policy: &PowerPolicy) {
+        self.pool.apply_power_policy(policy);
+    }
+
+    /// Threat proof queue depth and drop counts, for metrics and backpressure
+    pub fn proof_queue_stats(&self) -> ProofQueueStats {
+        self.proof_queue.stats(&self.pool)
+    }
+
+    /// Check a serialized proof made with `backend` for `version` of the circuit against `public_inputs`
+    fn verify(
+        &self,
+        kind: CircuitKind,
+        version: u32,
+        backend: ProvingBackend,
+        public_inputs: &[Fr],
+        proof: &[u8],
+    ) -> Result<bool> {
+        if backend != self.config.backend {
+            return Err(anyhow::anyhow!(
+                "Proof uses the {:?} backend, this node verifies {:?}",
+                backend,
+                self.config.backend
+            ));
+        }
+        // Universal keys are re-indexed for the current circuits only
+        if version != kind.version() && backend != ProvingBackend::Groth16 {
+            return Err(anyhow::anyhow!("Unsupported {:?} circuit version {}", kind, version));
         }
+
+        match backend {
+            ProvingBackend::Groth16 => {
+                let prepared_vk = self.groth16_verifying_key(kind, version)?;
+
+                let zk_proof = Proof::<Bn254>::deserialize_compressed(proof)
+                    .context("Failed to deserialize proof")?;
+                verify_proof(prepared_vk, &zk_proof, public_inputs)
+                    .context("Proof verification failed")
+            }
+            ProvingBackend::Marlin => self.universal_keys.get(&kind)
+                .with_context(|| format!("{:?} circuit not indexed", kind))?
+                .verify(public_inputs, proof),
+            ProvingBackend::Halo2 => self.halo2_keys(kind)?.verify(public_inputs, proof),
+        }
+    }
+
+    /// Prepared Groth16 key for `version` of `kind`
+    fn groth16_verifying_key(&self, kind: CircuitKind, version: u32) -> Result<&PreparedVerifyingKey<Bn254>> {
+        if version != kind.version() {
+            return match kind {
+                CircuitKind::Threat => self.legacy_verifying_keys.get(&version),
+                CircuitKind::EnergyRange
+                | CircuitKind::Batch
+                | CircuitKind::SignatureMatch
+                | CircuitKind::Inference
+                | CircuitKind::DelegatedThreat
+                | CircuitKind::Disclosure => None,
+            }
+            .with_context(|| format!("Unsupported {:?} circuit version {}", kind, version));
+        }
+
+        match kind {
+            CircuitKind::Threat => self.prepared_vk.as_ref(),
+            CircuitKind::EnergyRange => self.energy_prepared_vk.as_ref(),
+            CircuitKind::Batch => self.batch_prepared_vk.as_ref(),
+            CircuitKind::SignatureMatch => self.signature_prepared_vk.as_ref(),
+            CircuitKind::Inference => self.inference_prepared_vk.as_ref(),
+            CircuitKind::DelegatedThreat => self.delegated_prepared_vk.as_ref(),
+            CircuitKind::Disclosure => self.disclosure_prepared_vk.as_ref(),
+        }
+        .with_context(|| format!("{:?} verifying key not initialized", kind))
+    }
+
+    /// Accept threat proofs made for an older circuit `version` under `vk`
+    pub fn register_verifying_key(&mut self, version: u32, vk: &VerifyingKey<Bn254>) {
+        self.legacy_verifying_keys.insert(version, prepare_verifying_key(vk));
+        info!("🔑 Accepting threat circuit v{} proofs", version);
+    }
+
+    /// Threat circuit versions this node verifies, current first
+    pub fn supported_circuit_versions(&self) -> Vec<u32> {
+        let mut legacy: Vec<u32> = self.legacy_verifying_keys.keys().copied().collect();
+        legacy.sort_unstable_by(|a, b| b.cmp(a));
+        std::iter::once(THREAT_CIRCUIT_VERSION).chain(legacy).collect()
+    }
+
+    /// Hash of the verifying key proofs of `kind` are checked against
+    fn verification_key_hash(&self, kind: CircuitKind) -> Result<String> {
+        if self.config.backend == ProvingBackend::Groth16 {
+            return self.hash_vk(match kind {
+                CircuitKind::Threat => self.verifying_key.as_ref(),
+                CircuitKind::EnergyRange => self.energy_verifying_key.as_ref(),
+                CircuitKind::Batch => self.batch_verifying_key.as_ref(),
+                CircuitKind::SignatureMatch => self.signature_verifying_key.as_ref(),
+                CircuitKind::Inference => self.inference_verifying_key.as_ref(),
+                CircuitKind::DelegatedThreat => self.delegated_verifying_key.as_ref(),
+                CircuitKind::Disclosure => self.disclosure_verifying_key.as_ref(),
+            });
+        }
+
+        let vk_bytes = match self.config.backend {
+            ProvingBackend::Halo2 => self.halo2_keys(kind)?.verifying_key_bytes()?,
+            _ => self.universal_keys.get(&kind)
+                .with_context(|| format!("{:?} circuit not indexed", kind))?
+                .verifying_key_bytes()?,
+        };
+        Ok(hex::encode(Keccak256::digest(&vk_bytes)))
+    }
+
+    /// Halo2 keys, which only cover the threat circuit
+    fn halo2_keys(&self, kind: CircuitKind) -> Result<&Halo2Keys> {
+        if kind != CircuitKind::Threat {
+            return Err(anyhow::anyhow!("{:?} proofs are not available under Halo2", kind));
+        }
+        self.halo2_keys.as_deref().context("Halo2 keys not initialized")
+    }
+
+    async fn load_or_generate_parameters<C: ark_relations::r1cs::ConstraintSynthesizer<Fr>>(
+        &self,
+        kind: CircuitKind,
+        blank_circuit: C,
+    ) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> {
+        if let Ok(params) = self.load_parameters(kind).await {
+            return Ok(params);
+        }
+
+        let mut rng = prover_rng();
+        let params = generate_random_parameters::<Bn254, _, _>(blank_circuit, &mut rng)
+            .with_context(|| format!("Failed to generate {:?} parameters", kind))?;
+        self.save_parameters(kind, &params.0, &params.1).await?;
+        Ok(params)
+    }
+
+    /// Use threat circuit keys from a phase-2 ceremony run over a community Powers of Tau file.
+    /// `expected_hash` is the keccak256 registered on chain; the file must match it exactly.
+    /// Keys derived from the file alone have delta = gamma = 1 and can be forged, so `transcript`
+    /// must carry at least one contribution
+    pub async fn import_powers_of_tau(
+        &mut self,
+        path: &Path,
+        expected_hash: [u8; 32],
+        transcript: &Phase2Transcript,
+    ) -> Result<()> {
+        if self.config.backend != ProvingBackend::Groth16 {
+            return Err(anyhow::anyhow!("Powers of Tau keys are only used by the Groth16 backend"));
+        }
+
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let hash = transcript_hash(&bytes);
+        if hash != expected_hash {
+            return Err(anyhow::anyhow!(
+                "Powers of Tau hash mismatch: file {}, registered {}",
+                hex::encode(hash),
+                hex::encode(expected_hash)
+            ));
+        }
+
+        info!("🔧 Deriving initial threat circuit keys from Powers of Tau {}...", hex::encode(hash));
+        self.import_ceremony(transcript, &initial_parameters(&bytes)?).await
+    }
+
+    /// Use the threat circuit keys from a finished phase-2 ceremony after verifying the
+    /// transcript against `initial` (keys re-derived from the Powers of Tau file)
+    pub async fn import_ceremony(&mut self, transcript: &Phase2Transcript, initial: &ProvingKey<Bn254>) -> Result<()> {
+        let hashes = transcript.verify(initial)?;
+        if hashes.is_empty() {
+            return Err(anyhow::anyhow!("Ceremony has no contributions"));
+        }
+
+        let pk = transcript.params.clone();
+        let vk = pk.vk.clone();
+        self.save_parameters(CircuitKind::Threat, &pk, &vk).await?;
+        self.prepared_vk = Some(prepare_verifying_key(&vk));
+        self.proving_key = Some(Arc::new(pk));
+        self.verifying_key = Some(vk);
+
+        info!("✅ Imported threat circuit keys from a ceremony with {} contributions", hashes.len());
+        Ok(())
+    }
+
+    /// Solidity verifier for threat proofs under the current verifying key, for deployment
+    /// behind the `threat_detector` contract
+    pub fn export_solidity_verifier(&self) -> Result<String> {
+        if self.config.backend != ProvingBackend::Groth16 {
+            return Err(anyhow::anyhow!("Solidity verifiers are only generated for Groth16"));
+        }
+        let vk = self.verifying_key.as_ref().context("Verifying key not initialized")?;
+        let vk_hash = self.verification_key_hash(CircuitKind::Threat)?;
+
+        info!("📜 Exported Solidity verifier for verifying key {}", vk_hash);
+        Ok(solidity::verifier_contract(vk, &vk_hash))
+    }
+
+    /// Verifying key of `kind` as snarkjs `verification_key.json` (Groth16 only)
+    pub fn export_snarkjs_verifying_key(&self, kind: CircuitKind) -> Result<SnarkjsVerifyingKey> {
+        if self.config.backend != ProvingBackend::Groth16 {
+            return Err(anyhow::anyhow!("snarkjs verifying keys are only exported for Groth16"));
+        }
+        let vk = match kind {
+            CircuitKind::Threat => self.verifying_key.as_ref(),
+            CircuitKind::EnergyRange => self.energy_verifying_key.as_ref(),
+            CircuitKind::Batch => self.batch_verifying_key.as_ref(),
+            CircuitKind::SignatureMatch => self.signature_verifying_key.as_ref(),
+            CircuitKind::Inference => self.inference_verifying_key.as_ref(),
+            CircuitKind::DelegatedThreat => self.delegated_verifying_key.as_ref(),
+            CircuitKind::Disclosure => self.disclosure_verifying_key.as_ref(),
+        }
+        .with_context(|| format!("{:?} verifying key not initialized", kind))?;
+        Ok(snarkjs::export_verifying_key(vk))
+    }
+
+    /// Prove that `energy_mj` over `uptime_secs` averages at most `max_avg_watts`,
+    /// revealing only the threshold, the period and a commitment
+    pub async fn generate_energy_range_proof(
+        &self,
+        energy_mj: u64,
+        uptime_secs: u64,
+        (period_start, period_end): (u64, u64),
+        max_avg_watts: f64,
+        node_id: &str,
+    ) -> Result<EnergyRangeProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+
+        let period_secs = period_end.saturating_sub(period_start);
+        let max_avg_milliwatts = (max_avg_watts * 1000.0).round() as u64;
+        if uptime_secs > period_secs || energy_mj > max_avg_milliwatts.saturating_mul(uptime_secs) {
+            return Err(anyhow::anyhow!("Average power is above {:.1}W for this period", max_avg_watts));
+        }
+
+        debug!("🔐 Generating energy range proof (<= {:.1}W)", max_avg_watts);
+
+        let salt = Fr::rand(&mut prover_rng());
+        let node = node_field(node_id);
+        let period = (period_start, period_end);
+        let circuit = EnergyRangeCircuit::new(max_avg_milliwatts, period, node, energy_mj, uptime_secs, salt);
+        let proof_bytes = self.prove(CircuitKind::EnergyRange, 0.0, circuit).await?;
+
+        let mut commitment_bytes = Vec::new();
+        energy_commitment(energy_mj, uptime_secs, node, period_start, salt).serialize_compressed(&mut commitment_bytes)?;
+
+        Ok(EnergyRangeProof {
+            proof: proof_bytes,
+            max_avg_milliwatts,
+            period_start,
+            period_end,
+            commitment: hex::encode(commitment_bytes),
+            backend: self.config.backend,
+            verification_key_hash: self.verification_key_hash(CircuitKind::EnergyRange)?,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            node_id: node_id.to_string(),
+        })
+    }
+
+    /// Verify an energy range proof against its stated threshold and period
+    pub async fn verify_energy_range_proof(&self, proof: &EnergyRangeProof) -> Result<bool> {
+        if !self.enabled {
+            return Ok(true); // Skip verification if ZK is disabled
+        }
+
+        let commitment = Fr::deserialize_compressed(&hex::decode(&proof.commitment)?[..])
+            .context("Invalid energy commitment")?;
+
+        let public_inputs = EnergyRangeCircuit::public_inputs(
+            proof.max_avg_milliwatts,
+            (proof.period_start, proof.period_end),
+            node_field(&proof.node_id),
+            commitment,
+        );
+
+        let is_valid = self.verify(CircuitKind::EnergyRange, CircuitKind::EnergyRange.version(), proof.backend, &public_inputs, &proof.proof)?;
+
+        if !is_valid {
+            warn!("❌ Energy range proof from {} failed verification", proof.node_id);
+        }
+
+        Ok(is_valid)
+    }
+
+    /// Generate ZK proof for threat detection
+    pub async fn generate_threat_proof(
+        &self,
+        transaction_data: &[u8],
+        ai_confidence: f64,
+        node_id: &str,
+    ) -> Result<ThreatProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+
+        let model = self.model.as_ref().context("No model commitment loaded (`model_path`)")?;
+
+        // Convert inputs to field elements
+        let transaction_fields = transaction_fields(transaction_data)?;
+        let transaction_hash = mimc_hash(Fr::from(0u64), &transaction_fields);
+
+        // The confidence is private, so an earlier proof of the same threat is still valid
+        if let Some(cached) = self.get_cached_proof(&transaction_hash)? {
+            if cached.node_id == node_id {
+                debug!("♻️ Reusing cached ZK proof for threat");
+                return Ok(cached);
+            }
+        }
+
+        // Requests for a threat already being proved share its proof
+        let key = format!("{}:{}", encode_public_input(&transaction_hash)?, node_id);
+        let deadline = self.proof_queue.deadline(ai_confidence);
+        self.proof_queue
+            .run(key, ai_confidence, &self.pool, || {
+                self.prove_threat(model, transaction_fields, transaction_hash, ai_confidence, node_id, deadline)
+            })
+            .await
+    }
+
+    /// Prove a threat, locally or through a relay, unless still queued at `deadline`
+    async fn prove_threat(
+        &self,
+        model: &ModelCommitment,
+        transaction_fields: Vec<Fr>,
+        transaction_hash: Fr,
+        ai_confidence: f64,
+        node_id: &str,
+        deadline: Instant,
+    ) -> Result<ThreatProof> {
+        debug!("🔐 Generating ZK proof for threat detection");
+        let detection = DetectionWitness::new(ai_confidence)?;
+
+        if self.config.relay.delegate {
+            let witness = BlindedWitness {
+                midstate: BlindedWitness::midstate(&transaction_fields)?,
+                confidence_threshold: detection.threshold,
+                model_commitment: model.commitment(),
+                detection: detection.confidence,
+                node_reputation: detection.reputation,
+                model: model.witness(&transaction_hash)?,
+            };
+            return self.generate_threat_proof_via_relay(witness, ai_confidence, node_id).await;
+        }
+
+        // Generate proof
+        let public_inputs = [transaction_hash, detection.threshold, model.commitment()];
+        let circuit = detection.circuit(model, transaction_fields, transaction_hash)?;
+        let proof_bytes = self.prove_before(CircuitKind::Threat, ai_confidence, Some(deadline), circuit).await?;
+
+        let threat_proof = self.threat_proof(CircuitKind::Threat, proof_bytes, &public_inputs, node_id)?;
+
+        debug!("✅ Generated ZK proof successfully");
+        Ok(threat_proof)
+    }
+
+    /// Have the configured relay provers prove a blinded witness, taking the first proof that
+    /// verifies locally
+    async fn generate_threat_proof_via_relay(
+        &self,
+        witness: BlindedWitness,
+        ai_confidence: f64,
+        node_id: &str,
+    ) -> Result<ThreatProof> {
+        let public_inputs = witness.public_inputs();
+        let mut witness_bytes = Vec::new();
+        witness.serialize_compressed(&mut witness_bytes)
+            .context("Failed to serialize blinded witness")?;
+        let operator = self.relay_operator.as_ref().context("Relay requests need the node operator key")?;
+        let request = RelayRequest::signed(node_id, witness_bytes, ai_confidence, operator).await?;
+        let timeout = Duration::from_secs(self.config.relay.timeout_secs);
+
+        for peer in &self.config.relay.provers {
+            debug!("📤 Requesting threat proof from relay prover {}", peer.node_id);
+            let (proof_bytes, verifying_key) = match relay::request_proof(peer, &request, timeout).await {
+                Ok(RelayResponse::Proof { proof, backend, verifying_key }) if backend == self.config.backend => {
+                    (proof, verifying_key)
+                }
+                Ok(RelayResponse::Proof { backend, .. }) => {
+                    warn!("⚠️ Relay prover {} proves with {:?}, skipping it", peer.node_id, backend);
+                    continue;
+                }
+                Ok(RelayResponse::Refused { reason }) => {
+                    warn!("⚠️ Relay prover {} refused the proof: {}", peer.node_id, reason);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("⚠️ Relay prover {} failed: {}", peer.node_id, e);
+                    continue;
+                }
+            };
+
+            // Nothing a relay returns is submitted unchecked
+            let kind = CircuitKind::DelegatedThreat;
+            let verified = if self.config.backend == ProvingBackend::Groth16 && self.delegated_verifying_key.is_none() {
+                self.verify_with_relay_key(&verifying_key, &public_inputs, &proof_bytes)
+            } else {
+                self.verify(kind, kind.version(), self.config.backend, &public_inputs, &proof_bytes)
+                    .and_then(|valid| Ok((valid, self.verification_key_hash(kind)?)))
+            };
+            match verified {
+                Ok((true, vk_hash)) => {
+                    debug!("✅ Relay prover {} returned a valid threat proof", peer.node_id);
+                    return self.threat_proof_with_key(kind, proof_bytes, &public_inputs, node_id, vk_hash);
+                }
+                Ok((false, _)) => error!("🚨 Relay prover {} returned an invalid threat proof", peer.node_id),
+                Err(e) => error!("🚨 Relay prover {} returned an unverifiable threat proof: {:#}", peer.node_id, e),
+            }
+        }
+
+        Err(anyhow::anyhow!("No relay prover returned a valid threat proof"))
+    }
+
+    /// Check a relayed proof against the verifying key the relay sent, which must be the
+    /// delegated threat key registered on chain; returns the validity and the key's hash
+    fn verify_with_relay_key(&self, verifying_key: &[u8], public_inputs: &[Fr], proof: &[u8]) -> Result<(bool, String)> {
+        let kind = CircuitKind::DelegatedThreat;
+        let vk_hash = hex::encode(Keccak256::digest(verifying_key));
+        let registered = self.registered_vk_hashes.read().unwrap().get(&kind).cloned()
+            .context("No delegated threat verifying key is registered on chain")?;
+        if vk_hash != registered {
+            return Err(anyhow::anyhow!("Relay proved with an unregistered verifying key {}", vk_hash));
+        }
+
+        let vk = VerifyingKey::<Bn254>::deserialize_compressed(verifying_key).context("Invalid relay verifying key")?;
+        let proof = Proof::<Bn254>::deserialize_compressed(proof).context("Failed to deserialize proof")?;
+        let valid = verify_proof(&prepare_verifying_key(&vk), &proof, public_inputs).context("Proof verification failed")?;
+        Ok((valid, vk_hash))
+    }
+
+    /// Prove a blinded witness for another node (relay side); returns the serialized proof and,
+    /// under Groth16, the compressed verifying key it checks against
+    pub async fn prove_relayed(&self, request: &RelayRequest) -> Result<(Vec<u8>, Vec<u8>)> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        let witness = BlindedWitness::deserialize_compressed(&request.witness[..])
+            .context("Malformed blinded witness")?;
+        let priority = request.priority.clamp(0.0, relay::MAX_RELAYED_PRIORITY);
+        let proof = self.prove(CircuitKind::DelegatedThreat, priority, witness.circuit()).await?;
+
+        let mut verifying_key = Vec::new();
+        if let Some(vk) = &self.delegated_verifying_key {
+            vk.serialize_compressed(&mut verifying_key)?;
+        }
+        Ok((proof, verifying_key))
+    }
+
+    /// Threat proof of `kind` over `public_inputs`, added to the proof cache
+    fn threat_proof(&self, kind: CircuitKind, proof: Vec<u8>, public_inputs: &[Fr], node_id: &str) -> Result<ThreatProof> {
+        let vk_hash = self.verification_key_hash(kind)?;
+        self.threat_proof_with_key(kind, proof, public_inputs, node_id, vk_hash)
+    }
+
+    /// `threat_proof` for a verifying key with hash `vk_hash`
+    fn threat_proof_with_key(
+        &self,
+        kind: CircuitKind,
+        proof: Vec<u8>,
+        public_inputs: &[Fr],
+        node_id: &str,
+        vk_hash: String,
+    ) -> Result<ThreatProof> {
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        let threat_proof = ThreatProof {
+            proof,
+            public_inputs: public_inputs.iter().map(encode_public_input).collect::<Result<_>>()?,
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            circuit_version: kind.version(),
+            backend: self.config.backend,
+            verification_key_hash: vk_hash,
+            timestamp,
+            expires_at: timestamp.saturating_add(self.config.proof_validity_secs),
+            node_id: node_id.to_string(),
+            delegated: kind == CircuitKind::DelegatedThreat,
+        };
+
+        // Cache the proof
+        if let Some(cache) = &self.proof_cache {
+            let proof_id = self.generate_proof_id(&threat_proof);
+            if let Err(e) = cache.insert(&proof_id, &threat_proof, threat_proof.timestamp) {
+                warn!("⚠️ Failed to cache proof {}: {}", proof_id, e);
+            }
+        }
+
+        Ok(threat_proof)
+    }
+
+    /// Prove independent detections concurrently on the proving pool, sharing its proving key.
+    /// Yields `(index into inputs, proof)` as each finishes, so early proofs can be submitted
+    /// while the rest are still running
+    pub fn generate_threat_proofs_batch<'a>(
+        &'a self,
+        inputs: &'a [ThreatInput],
+    ) -> impl Stream<Item = (usize, Result<ThreatProof>)> + 'a {
+        debug!("🔐 Generating {} threat proofs in parallel", inputs.len());
+
+        // Keep every worker busy without filling the queue other proofs share
+        stream::iter(inputs.iter().enumerate())
+            .map(move |(index, input)| async move {
+                let proof = self.generate_threat_proof(&input.transaction_data, input.ai_confidence, &input.node_id).await;
+                (index, proof)
+            })
+            .buffer_unordered(self.pool.workers())
+    }
+
+    /// Verify ZK proof
+    pub async fn verify_threat_proof(&self, proof: &ThreatProof) -> Result<bool> {
+        if !self.enabled {
+            return Ok(true); // Skip verification if ZK is disabled
+        }
+
+        debug!("🔍 Verifying ZK proof");
+
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
+        }
+
+        // Convert public inputs back to field elements
+        let public_inputs: Result<Vec<Fr>> = proof.public_inputs
+            .iter()
+            .map(|s| decode_public_input(s))
+            .collect();
+        let public_inputs = public_inputs?;
+
+        if !self.is_live(proof, chrono::Utc::now().timestamp() as u64) || !self.accepts_model(proof)? {
+            return Ok(false);
+        }
+
+        // Verify proof
+        let is_valid = self.verify(proof.circuit_kind(), proof.circuit_version, proof.backend, &public_inputs, &proof.proof)?;
+
+        if is_valid {
+            debug!("✅ ZK proof verification successful");
+        } else {
+            warn!("❌ ZK proof verification failed");
+        }
+
+        Ok(is_valid)
+    }
+
+    /// Verify many threat proofs with a random linear combination: one multi-Miller loop and
+    /// final exponentiation for the whole set instead of one per proof. `false` when any proof
+    /// is invalid; `verify_threat_proof` then finds the culprit
+    pub async fn verify_proofs_batch(&self, proofs: &[ThreatProof]) -> Result<bool> {
+        if !self.enabled || proofs.is_empty() {
+            return Ok(true);
+        }
+
+        // The random linear combination is specific to Groth16's pairing check
+        let now = chrono::Utc::now().timestamp() as u64;
+        for proof in proofs {
+            if !self.is_live(proof, now) || !self.accepts_model(proof)? {
+                return Ok(false);
+            }
+        }
+        if self.config.backend != ProvingBackend::Groth16 {
+            for proof in proofs {
+                if !self.verify_threat_proof(proof).await? {
+                    return Ok(false);
+                }
+            }
+            return Ok(true);
+        }
+
+        // Each circuit and version has its own verifying key, so combine per version
+        let mut by_version: HashMap<(CircuitKind, u32), Vec<&ThreatProof>> = HashMap::new();
+        for proof in proofs {
+            by_version.entry((proof.circuit_kind(), proof.circuit_version)).or_default().push(proof);
+        }
+        for ((kind, version), proofs) in by_version {
+            let prepared_vk = self.groth16_verifying_key(kind, version)?;
+            if !self.verify_groth16_batch(prepared_vk, &proofs)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Prove a detection and sign the proof with the node wallet, as this node's share of a
+    /// threshold attestation
+    pub async fn attest_threat(
+        &self,
+        transaction_data: &[u8],
+        ai_confidence: f64,
+        node_id: &str,
+        wallet: &LocalWallet,
+    ) -> Result<ThreatAttestation> {
+        if !self.config.attestation.is_member(node_id) {
+            return Err(anyhow::anyhow!("{} is not on the attestation committee", node_id));
+        }
+        let proof = self.generate_threat_proof(transaction_data, ai_confidence, node_id).await?;
+        ThreatAttestation::sign(proof, wallet).await
+    }
+
+    /// Verify a report attested by `attestation.threshold` committee nodes: every signature,
+    /// and every proof in one batch
+    pub async fn verify_attested_report(&self, report: &AttestedThreatReport) -> Result<bool> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        if let Err(e) = report.check(&self.config.attestation) {
+            warn!("❌ Attested threat report rejected: {}", e);
+            return Ok(false);
+        }
+        debug!(
+            "🔍 Verifying threat attested by {} nodes in {} regions",
+            report.attestations.len(),
+            report.regions(&self.config.attestation).len()
+        );
+        self.verify_proofs_batch(&report.proofs()).await
+    }
+
+    /// Random linear combination check of `proofs` against one verifying key
+    fn verify_groth16_batch(&self, prepared_vk: &PreparedVerifyingKey<Bn254>, proofs: &[&ThreatProof]) -> Result<bool> {
+        let vk = &prepared_vk.vk;
+
+        debug!("🔍 Batch verifying {} ZK proofs", proofs.len());
+
+        // Per-proof weights must be unpredictable to whoever made the proofs
+        let mut rng = ark_std::rand::thread_rng();
+        let mut g1: Vec<<Bn254 as Pairing>::G1Prepared> = Vec::with_capacity(proofs.len() + 2);
+        let mut g2: Vec<<Bn254 as Pairing>::G2Prepared> = Vec::with_capacity(proofs.len() + 2);
+        let mut c_points = Vec::with_capacity(proofs.len());
+        let mut weights = Vec::with_capacity(proofs.len());
+        let mut input_scalars = vec![Fr::from(0u64); vk.gamma_abc_g1.len()];
+
+        for proof in proofs {
+            if proof.backend != ProvingBackend::Groth16 {
+                return Err(anyhow::anyhow!("Proof from {} uses the {:?} backend", proof.node_id, proof.backend));
+            }
+            if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+                return Err(anyhow::anyhow!(
+                    "Unsupported public input encoding {} (expected {})",
+                    proof.public_input_encoding,
+                    PUBLIC_INPUT_ENCODING
+                ));
+            }
+
+            let zk_proof = Proof::<Bn254>::deserialize_compressed(&proof.proof[..])
+                .context("Failed to deserialize proof")?;
+            let public_inputs = proof.public_inputs
+                .iter()
+                .map(|s| decode_public_input(s))
+                .collect::<Result<Vec<_>>>()?;
+            if public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+                return Err(anyhow::anyhow!("Proof from {} has {} public inputs", proof.node_id, public_inputs.len()));
+            }
+
+            let weight = Fr::from(((rng.next_u64() as u128) << 64) | rng.next_u64() as u128);
+
+            // Inputs of every proof fold into one MSM over the verifying key
+            input_scalars[0] += weight;
+            for (scalar, input) in input_scalars[1..].iter_mut().zip(&public_inputs) {
+                *scalar += weight * input;
+            }
+
+            g1.push((zk_proof.a * weight).into_affine().into());
+            g2.push(zk_proof.b.into());
+            c_points.push(zk_proof.c);
+            weights.push(weight);
+        }
+
+        let prepared_inputs = G1Projective::msm(&vk.gamma_abc_g1, &input_scalars)
+            .map_err(|_| anyhow::anyhow!("Malformed verifying key"))?;
+        let combined_c = G1Projective::msm(&c_points, &weights)
+            .map_err(|_| anyhow::anyhow!("Proof count mismatch"))?;
+        g1.push(prepared_inputs.into_affine().into());
+        g2.push(prepared_vk.gamma_g2_neg_pc.clone());
+        g1.push(combined_c.into_affine().into());
+        g2.push(prepared_vk.delta_g2_neg_pc.clone());
+
+        let result = Bn254::final_exponentiation(Bn254::multi_miller_loop(g1, g2))
+            .context("Batch verification hit the identity")?;
+
+        // e(alpha, beta) appears once per proof, weighted
+        let is_valid = result.0 == prepared_vk.alpha_g1_beta_g2.pow(input_scalars[0].into_bigint());
+
+        if is_valid {
+            debug!("✅ Batch of {} ZK proofs verified", proofs.len());
+        } else {
+            warn!("❌ Batch of {} ZK proofs failed verification", proofs.len());
+        }
+
+        Ok(is_valid)
+    }
+
+    /// Prove a batch of detections from `epoch` with one proof; each entry is the transaction
+    /// data and AI confidence of a detection
+    pub async fn generate_batch_proof(
+        &self,
+        detections: &[(&[u8], f64)],
+        epoch: u64,
+        node_id: &str,
+    ) -> Result<BatchThreatProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        if detections.is_empty() || detections.len() > BATCH_SLOTS {
+            return Err(anyhow::anyhow!(
+                "Batch proofs cover 1-{} detections, got {}",
+                BATCH_SLOTS,
+                detections.len()
+            ));
+        }
+
+        debug!("🔐 Generating batch proof for {} detections (epoch {})", detections.len(), epoch);
+
+        let slots = detections
+            .iter()
+            .map(|(transaction_data, confidence)| {
+                Ok(BatchSlot {
+                    transaction_data: transaction_fields(transaction_data)?,
+                    confidence: encode_confidence(*confidence)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let threat_hashes = slots
+            .iter()
+            .map(|slot| encode_public_input(&slot.threat_hash()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let threshold_field = encode_confidence(DETECTION_THRESHOLD)?;
+        let circuit = BatchThreatCircuit::new(epoch, threshold_field, slots, encode_confidence(NODE_REPUTATION)?);
+        let priority = detections.iter().map(|(_, confidence)| *confidence).fold(0.0, f64::max);
+        let proof_bytes = self.prove(CircuitKind::Batch, priority, circuit).await?;
+
+        Ok(BatchThreatProof {
+            proof: proof_bytes,
+            epoch,
+            threat_hashes,
+            confidence_threshold: encode_public_input(&threshold_field)?,
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            backend: self.config.backend,
+            verification_key_hash: self.verification_key_hash(CircuitKind::Batch)?,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            node_id: node_id.to_string(),
+        })
+    }
+
+    /// Verify a batch proof against its listed threat hashes
+    pub async fn verify_batch_proof(&self, proof: &BatchThreatProof) -> Result<bool> {
+        if !self.enabled {
+            return Ok(true); // Skip verification if ZK is disabled
+        }
+
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
+        }
+        if proof.threat_hashes.is_empty() || proof.threat_hashes.len() > BATCH_SLOTS {
+            return Err(anyhow::anyhow!("Batch proof lists {} threat hashes", proof.threat_hashes.len()));
+        }
+
+        let threat_hashes = proof.threat_hashes
+            .iter()
+            .map(|s| decode_public_input(s))
+            .collect::<Result<Vec<_>>>()?;
+
+        let public_inputs = BatchThreatCircuit::public_inputs(
+            proof.epoch,
+            batch_commitment(proof.epoch, &threat_hashes),
+            decode_public_input(&proof.confidence_threshold)?,
+        );
+
+        let is_valid = self.verify(CircuitKind::Batch, CircuitKind::Batch.version(), proof.backend, &public_inputs, &proof.proof)?;
+
+        if !is_valid {
+            warn!("❌ Batch proof from {} for epoch {} failed verification", proof.node_id, proof.epoch);
+        }
+
+        Ok(is_valid)
+    }
+
+    /// Prove an imported Circom circuit on `inputs` (signal name -> values); `priority` orders
+    /// it against other queued proofs like a threat confidence
+    pub async fn generate_circom_proof(
+        &self,
+        circuit: &str,
+        inputs: BTreeMap<String, Vec<Fr>>,
+        priority: f64,
+        node_id: &str,
+    ) -> Result<CircomProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        let artifacts = Arc::clone(
+            self.circom_circuits
+                .get(circuit)
+                .with_context(|| format!("Circom circuit {} not loaded", circuit))?,
+        );
+
+        debug!("🔐 Generating ZK proof for Circom circuit {}", circuit);
+
+        let vk_hash = self.hash_vk(Some(&artifacts.proving_key.vk))?;
+        let msm = Arc::clone(&self.msm);
+        let (proof, public_inputs) = self
+            .pool
+            .submit(priority, move || {
+                subsystems::measure(Subsystem::ZkProving, || {
+                    artifacts.prove(&msm, &inputs, &mut prover_rng())
+                })
+            })?
+            .await?;
+
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes)?;
+
+        Ok(CircomProof {
+            proof: proof_bytes,
+            circuit: circuit.to_string(),
+            public_inputs: public_inputs.iter().map(encode_public_input).collect::<Result<_>>()?,
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            verification_key_hash: vk_hash,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            node_id: node_id.to_string(),
+        })
+    }
+
+    /// Verify a proof from an imported Circom circuit against that circuit's zkey
+    pub async fn verify_circom_proof(&self, proof: &CircomProof) -> Result<bool> {
+        if !self.enabled {
+            return Ok(true); // Skip verification if ZK is disabled
+        }
+
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
+        }
+        let artifacts = self.circom_circuits
+            .get(&proof.circuit)
+            .with_context(|| format!("Circom circuit {} not loaded", proof.circuit))?;
+        let vk = &artifacts.proving_key.vk;
+        if self.hash_vk(Some(vk))? != proof.verification_key_hash {
+            warn!("❌ Circom proof from {} was made with a different {} key", proof.node_id, proof.circuit);
+            return Ok(false);
+        }
+
+        let public_inputs = proof.public_inputs
+            .iter()
+            .map(|s| decode_public_input(s))
+            .collect::<Result<Vec<_>>>()?;
+        let zk_proof = Proof::<Bn254>::deserialize_compressed(&proof.proof[..])
+            .context("Failed to deserialize proof")?;
+        let is_valid = verify_proof(&prepare_verifying_key(vk), &zk_proof, &public_inputs)?;
+
+        if !is_valid {
+            warn!("❌ Circom {} proof from {} failed verification", proof.circuit, proof.node_id);
+        }
+
+        Ok(is_valid)
+    }
+
+    /// Fold every detection of `epoch` into one recursive proof; each entry is the transaction
+    /// data and AI confidence of a detection
+    pub async fn generate_epoch_proof(
+        &self,
+        detections: &[(&[u8], f64)],
+        epoch: u64,
+        node_id: &str,
+    ) -> Result<EpochProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        let keys = Arc::clone(self.epoch_keys.as_ref().context("Epoch folding keys not initialized")?);
+        if detections.is_empty() {
+            return Err(anyhow::anyhow!("Epoch {} has no detections to prove", epoch));
+        }
+
+        debug!("🔐 Folding {} detections into an epoch proof (epoch {})", detections.len(), epoch);
+
+        let threshold_field = encode_confidence(DETECTION_THRESHOLD)?;
+        let reputation_field = encode_confidence(NODE_REPUTATION)?;
+        let mut threat_hashes = Vec::with_capacity(detections.len());
+        let mut steps = Vec::with_capacity(detections.len());
+        for (transaction_data, confidence) in detections {
+            let slot = BatchSlot {
+                transaction_data: transaction_fields(transaction_data)?,
+                confidence: encode_confidence(*confidence)?,
+            };
+            threat_hashes.push(encode_public_input(&slot.threat_hash())?);
+            steps.push(epoch::external_inputs(&slot, reputation_field));
+        }
+
+        // One epoch proof replaces many threat proofs, so it waits behind live detections
+        let z_0 = epoch::initial_state(epoch, threshold_field, epoch::node_field(node_id));
+        let calldata = self
+            .pool
+            .submit(0.0, move || {
+                subsystems::measure(Subsystem::ZkProving, || keys.prove(&mut prover_rng(), z_0, steps))
+            })?
+            .await?;
+
+        Ok(EpochProof {
+            calldata,
+            epoch,
+            threat_hashes,
+            confidence_threshold: encode_public_input(&threshold_field)?,
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            node_id: node_id.to_string(),
+        })
     }
 
-    /// Initialize ZK system with trusted setup
-    pub async fn initialize(&mut self) -> Result<()> {
-        if !self.enabled {
-            info!("ZK proofs disabled, skipping initialization");
-            return Ok(());
+    /// Check that an epoch proof's folded statement covers exactly its listed threat hashes;
+    /// the decider proof itself is checked by the on-chain verifier
+    pub fn verify_epoch_statement(&self, proof: &EpochProof) -> Result<bool> {
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
         }
 
-        info!("🔐 Initializing ZK proof system...");
-
-        // Try to load existing parameters
-        if let Ok((pk, vk)) = self.load_parameters().await {
-            self.proving_key = Some(pk);
-            self.verifying_key = Some(vk.clone());
-            self.prepared_vk = Some(prepare_verifying_key(&vk));
-            info!("✅ Loaded existing ZK parameters");
-        } else {
-            // Generate new parameters (trusted setup)
-            info!("🔧 Generating new ZK parameters (this may take a while)...");
-            let (pk, vk) = self.generate_parameters().await?;
-            
-            self.proving_key = Some(pk.clone());
-            self.verifying_key = Some(vk.clone());
-            self.prepared_vk = Some(prepare_verifying_key(&vk));
-            
-            // Save parameters for future use
-            self.save_parameters(&pk, &vk).await?;
-            info!("✅ Generated and saved new ZK parameters");
+        let threat_hashes = proof.threat_hashes
+            .iter()
+            .map(|s| decode_public_input(s))
+            .collect::<Result<Vec<_>>>()?;
+        let threshold = decode_public_input(&proof.confidence_threshold)?;
+        let (steps, z_0, z_i) = epoch::calldata_state(&proof.calldata)?;
+        let node = epoch::node_field(&proof.node_id);
+
+        let is_valid = steps == threat_hashes.len() as u64
+            && z_0 == epoch::initial_state(proof.epoch, threshold, node)
+            && z_i == epoch::final_state(proof.epoch, threshold, node, &threat_hashes);
+
+        if !is_valid {
+            warn!("❌ Epoch proof from {} for epoch {} does not match its threat hashes", proof.node_id, proof.epoch);
         }
 
-        Ok(())
+        Ok(is_valid)
     }
 
-    /// Generate ZK proof for threat detection
-    pub async fn generate_threat_proof(
+    /// Solidity decider verifier for epoch proofs made with this node's folding keys
+    pub fn export_epoch_verifier(&self) -> Result<String> {
+        Ok(self.epoch_keys.as_ref().context("Epoch folding keys not initialized")?.verifier_contract())
+    }
+
+    /// Prove that `transaction_data` matches one of the known threat signatures in `tree`
+    /// without revealing which
+    pub async fn generate_signature_proof(
         &self,
         transaction_data: &[u8],
-        ai_confidence: f64,
+        tree: &SignatureTree,
         node_id: &str,
-    ) -> Result<ThreatProof> {
+    ) -> Result<SignatureMatchProof> {
         if !self.enabled {
             return Err(anyhow::anyhow!("ZK proofs are disabled"));
         }
 
-        let proving_key = self.proving_key.as_ref()
-            .context("Proving key not initialized")?;
+        debug!("🔐 Generating signature match proof against {} signatures", tree.len());
 
-        debug!("🔐 Generating ZK proof for threat detection");
+        let circuit = SignatureMatchCircuit::new(transaction_fields(transaction_data)?, tree)?;
+        let threat_hash = circuit.threat_hash.context("Signature circuit has no threat hash")?;
+        // Signature matches are certain, so they go ahead of model detections
+        let proof_bytes = self.prove(CircuitKind::SignatureMatch, 1.0, circuit).await?;
 
-        // Convert inputs to field elements
-        let transaction_hash = self.hash_to_field(transaction_data);
-        let confidence_field = self.float_to_field(ai_confidence);
-        let threshold_field = self.float_to_field(0.7); // 70% threshold
+        Ok(SignatureMatchProof {
+            proof: proof_bytes,
+            threat_hash: encode_public_input(&threat_hash)?,
+            signature_root: encode_public_input(&tree.root())?,
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            backend: self.config.backend,
+            verification_key_hash: self.verification_key_hash(CircuitKind::SignatureMatch)?,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            node_id: node_id.to_string(),
+        })
+    }
 
-        // Create circuit
-        let circuit = ThreatDetectionCircuit {
-            threat_hash: Some(transaction_hash),
-            confidence_threshold: Some(threshold_field),
-            transaction_data: Some(self.bytes_to_fields(transaction_data)),
-            ai_model_weights: Some(self.generate_mock_weights()),
-            node_reputation: Some(self.float_to_field(0.95)), // Mock reputation
-            detection_algorithm: Some(confidence_field),
-        };
+    /// Verify a signature match proof; `signature_root` is the root currently committed by
+    /// the oracle, so proofs against stale or made-up sets are rejected
+    pub async fn verify_signature_proof(&self, proof: &SignatureMatchProof, signature_root: &Fr) -> Result<bool> {
+        if !self.enabled {
+            return Ok(true); // Skip verification if ZK is disabled
+        }
 
-        // Generate proof
-        let mut rng = ark_std::rand::thread_rng();
-        let proof = create_random_proof(circuit, proving_key, &mut rng)
-            .context("Failed to create ZK proof")?;
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
+        }
+        if decode_public_input(&proof.signature_root)? != *signature_root {
+            warn!("❌ Signature proof from {} is against a different signature root", proof.node_id);
+            return Ok(false);
+        }
 
-        // Serialize proof
-        let mut proof_bytes = Vec::new();
-        proof.serialize_compressed(&mut proof_bytes)
-            .context("Failed to serialize proof")?;
+        let public_inputs = SignatureMatchCircuit::public_inputs(decode_public_input(&proof.threat_hash)?, *signature_root);
+        let is_valid = self.verify(
+            CircuitKind::SignatureMatch,
+            CircuitKind::SignatureMatch.version(),
+            proof.backend,
+            &public_inputs,
+            &proof.proof,
+        )?;
+
+        if !is_valid {
+            warn!("❌ Signature proof from {} failed verification", proof.node_id);
+        }
+
+        Ok(is_valid)
+    }
+
+    /// Run the classifier on `transaction_data` inside a proof, publishing its score
+    /// (experimental)
+    pub async fn generate_inference_proof(&self, transaction_data: &[u8], node_id: &str) -> Result<InferenceProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        let classifier = self.classifier.as_ref().context("No classifier loaded (`classifier_path`)")?;
 
-        // Public inputs for verification
-        let public_inputs = vec![
-            self.field_to_string(&transaction_hash),
-            self.field_to_string(&threshold_field),
-        ];
+        debug!("🔐 Generating zkML inference proof");
 
-        let vk_hash = self.hash_verifying_key()?;
+        let (circuit, score) = InferenceCircuit::new(transaction_fields(transaction_data)?, classifier)?;
+        let threat_hash = circuit.threat_hash.context("Inference circuit has no threat hash")?;
+        let proof_bytes = self.prove(CircuitKind::Inference, 0.5, circuit).await?;
 
-        let threat_proof = ThreatProof {
+        Ok(InferenceProof {
             proof: proof_bytes,
-            public_inputs,
-            verification_key_hash: vk_hash,
+            threat_hash: encode_public_input(&threat_hash)?,
+            model_commitment: encode_public_input(&classifier.commitment()?)?,
+            score,
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            backend: self.config.backend,
+            verification_key_hash: self.verification_key_hash(CircuitKind::Inference)?,
             timestamp: chrono::Utc::now().timestamp() as u64,
             node_id: node_id.to_string(),
-        };
-
-        // Cache the proof
-        let proof_id = self.generate_proof_id(&threat_proof);
-        self.proof_cache.write().unwrap().insert(proof_id, threat_proof.clone());
-
-        debug!("✅ Generated ZK proof successfully");
-        Ok(threat_proof)
+        })
     }
 
-    /// Verify ZK proof
-    pub async fn verify_threat_proof(&self, proof: &ThreatProof) -> Result<bool> {
+    /// Verify an inference proof; `model_commitment` is the registered classifier's, so
+    /// scores from any other model are rejected
+    pub async fn verify_inference_proof(&self, proof: &InferenceProof, model_commitment: &Fr) -> Result<bool> {
         if !self.enabled {
             return Ok(true); // Skip verification if ZK is disabled
         }
 
-        let prepared_vk = self.prepared_vk.as_ref()
-            .context("Prepared verifying key not initialized")?;
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
+        }
+        if decode_public_input(&proof.model_commitment)? != *model_commitment {
+            warn!("❌ Inference proof from {} is for an unregistered classifier", proof.node_id);
+            return Ok(false);
+        }
 
-        debug!("🔍 Verifying ZK proof");
+        let public_inputs = InferenceCircuit::public_inputs(decode_public_input(&proof.threat_hash)?, *model_commitment, proof.score);
+        let is_valid = self.verify(
+            CircuitKind::Inference,
+            CircuitKind::Inference.version(),
+            proof.backend,
+            &public_inputs,
+            &proof.proof,
+        )?;
+
+        if !is_valid {
+            warn!("❌ Inference proof from {} failed verification", proof.node_id);
+        }
 
-        // Deserialize proof
-        let zk_proof = Proof::<Bn254>::deserialize_compressed(&proof.proof[..])
-            .context("Failed to deserialize proof")?;
+        Ok(is_valid)
+    }
 
-        // Convert public inputs back to field elements
-        let public_inputs: Result<Vec<Fr>> = proof.public_inputs
-            .iter()
-            .map(|s| self.string_to_field(s))
-            .collect();
-        let public_inputs = public_inputs?;
+    /// Prove a detection together with its metadata, publishing only the fields the
+    /// disclosure policy allows
+    pub async fn generate_disclosure_proof(
+        &self,
+        transaction_data: &[u8],
+        ai_confidence: f64,
+        metadata: &ThreatMetadata,
+        node_id: &str,
+    ) -> Result<DisclosureProof> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        let model = self.model.as_ref().context("No model commitment loaded (`model_path`)")?;
+        let policy = self.config.disclosure;
+
+        debug!("🔐 Generating threat proof disclosing {:?}", policy);
+        let transaction_fields = transaction_fields(transaction_data)?;
+        let transaction_hash = mimc_hash(Fr::from(0u64), &transaction_fields);
+        let detection = DetectionWitness::new(ai_confidence)?;
+        let threat_inputs = [transaction_hash, detection.threshold, model.commitment()];
+        let threat = detection.circuit(model, transaction_fields, transaction_hash)?;
+
+        // The salt keeps hidden fields from being guessed through the commitment
+        let fields = metadata.fields()?;
+        let salt = Fr::rand(&mut prover_rng());
+        let metadata_commitment = disclosure::metadata_commitment(&fields, salt);
+        let circuit = DisclosureCircuit::new(threat, fields, salt, &policy);
+        let proof_bytes = self.prove(CircuitKind::Disclosure, ai_confidence, circuit).await?;
+
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        Ok(DisclosureProof {
+            proof: proof_bytes,
+            public_inputs: threat_inputs
+                .iter()
+                .map(encode_public_input)
+                .collect::<Result<_>>()?,
+            metadata_commitment: encode_public_input(&metadata_commitment)?,
+            disclosed: metadata.disclose(&policy),
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            backend: self.config.backend,
+            verification_key_hash: self.verification_key_hash(CircuitKind::Disclosure)?,
+            timestamp,
+            expires_at: timestamp.saturating_add(self.config.proof_validity_secs),
+            node_id: node_id.to_string(),
+        })
+    }
 
-        // Verify proof
-        let is_valid = verify_proof(prepared_vk, &zk_proof, &public_inputs)
-            .context("Proof verification failed")?;
+    /// Verify a disclosure proof against the metadata it discloses
+    pub async fn verify_disclosure_proof(&self, proof: &DisclosureProof) -> Result<bool> {
+        // Disclosures are only checked on request, so a disabled prover can't vouch for one
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
 
-        if is_valid {
-            debug!("✅ ZK proof verification successful");
-        } else {
-            warn!("❌ ZK proof verification failed");
+        if proof.public_input_encoding != PUBLIC_INPUT_ENCODING {
+            return Err(anyhow::anyhow!(
+                "Unsupported public input encoding {} (expected {})",
+                proof.public_input_encoding,
+                PUBLIC_INPUT_ENCODING
+            ));
+        }
+        let revoked = self.revocations.read().unwrap().revoked_nodes.contains(&proof.node_id);
+        if !self.within_validity(&proof.node_id, proof.timestamp, proof.expires_at, revoked, chrono::Utc::now().timestamp() as u64) {
+            return Ok(false);
+        }
+
+        let threat_inputs = proof.public_inputs
+            .iter()
+            .map(|s| decode_public_input(s))
+            .collect::<Result<Vec<_>>>()?;
+        let model_commitment = threat_inputs.get(2).context("Disclosure proof has no model commitment")?;
+        if !self.model_commitments.contains(model_commitment) {
+            warn!("❌ Disclosure proof from {} is for an unregistered model", proof.node_id);
+            return Ok(false);
+        }
+
+        let public_inputs = DisclosureCircuit::public_inputs(
+            &threat_inputs,
+            decode_public_input(&proof.metadata_commitment)?,
+            &proof.disclosed.fields()?,
+        );
+        let is_valid = self.verify(
+            CircuitKind::Disclosure,
+            CircuitKind::Disclosure.version(),
+            proof.backend,
+            &public_inputs,
+            &proof.proof,
+        )?;
+
+        if !is_valid {
+            warn!("❌ Disclosure proof from {} failed verification", proof.node_id);
         }
 
         Ok(is_valid)
@@ -203,104 +2381,102 @@ impl ZKProver {
     /// Generate parameters for the circuit (trusted setup)
     async fn generate_parameters(&self) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> {
         // Create a dummy circuit for parameter generation
-        let circuit = ThreatDetectionCircuit {
-            threat_hash: None,
-            confidence_threshold: None,
-            transaction_data: None,
-            ai_model_weights: None,
-            node_reputation: None,
-            detection_algorithm: None,
-        };
+        let circuit = ThreatDetectionCircuit::default();
 
-        let mut rng = ark_std::rand::thread_rng();
+        let mut rng = prover_rng();
         let params = generate_random_parameters::<Bn254, _, _>(circuit, &mut rng)
             .context("Failed to generate parameters")?;
 
         Ok((params.0, params.1))
     }
 
-    /// Save ZK parameters to disk
+    /// Parameter file name for `version` of `kind`
+    fn parameter_file(&self, kind: CircuitKind, version: u32, key: &str) -> String {
+        format!("{}{}.v{}.bin", kind.prefix(), key, version)
+    }
+
+    /// Move keys saved before circuits were versioned (`<prefix>proving_key.bin`, implicitly
+    /// version 1) to their `.v1.bin` names, so upgrading nodes keep their keys
+    fn migrate_unversioned_parameters(&self) -> Result<()> {
+        let store = self.param_store();
+        for kind in CircuitKind::ALL {
+            for (key, key_type) in [("proving_key", KeyType::Proving), ("verifying_key", KeyType::Verifying)] {
+                let legacy = store.path(&format!("{}{}.bin", kind.prefix(), key));
+                let versioned = self.parameter_file(kind, 1, key);
+                if !legacy.exists() || store.path(&versioned).exists() {
+                    continue;
+                }
+
+                // Unversioned files predate the checksum manifest and encryption
+                let bytes = fs::read(&legacy).with_context(|| format!("Failed to read {}", legacy.display()))?;
+                let encrypt = self.config.encrypt_proving_keys && key_type == KeyType::Proving;
+                store.write_key(&versioned, &ParamHeader::new(kind.id(), 1, key_type), &bytes, encrypt)?;
+                fs::remove_file(&legacy).with_context(|| format!("Failed to remove {}", legacy.display()))?;
+                info!("📦 Migrated {} to {}", legacy.display(), versioned);
+            }
+        }
+        Ok(())
+    }
+
+    /// Save ZK parameters to disk, each file a container naming its circuit and version
     async fn save_parameters(
         &self,
+        kind: CircuitKind,
         pk: &ProvingKey<Bn254>,
         vk: &VerifyingKey<Bn254>,
     ) -> Result<()> {
-        let params_dir = Path::new("./zk_params");
-        fs::create_dir_all(params_dir)?;
+        let store = self.param_store();
+        let version = kind.version();
 
         // Save proving key
         let mut pk_bytes = Vec::new();
         pk.serialize_compressed(&mut pk_bytes)?;
-        fs::write(params_dir.join("proving_key.bin"), pk_bytes)?;
+        let pk_header = ParamHeader::new(kind.id(), version, KeyType::Proving);
+        store.write_key(&self.parameter_file(kind, version, "proving_key"), &pk_header, &pk_bytes, self.config.encrypt_proving_keys)?;
 
         // Save verifying key
         let mut vk_bytes = Vec::new();
         vk.serialize_compressed(&mut vk_bytes)?;
-        fs::write(params_dir.join("verifying_key.bin"), vk_bytes)?;
+        let vk_header = ParamHeader::new(kind.id(), version, KeyType::Verifying);
+        store.write_key(&self.parameter_file(kind, version, "verifying_key"), &vk_header, &vk_bytes, false)?;
 
         Ok(())
     }
 
-    /// Load ZK parameters from disk
-    async fn load_parameters(&self) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> {
-        let params_dir = Path::new("./zk_params");
-
-        // Load proving key
-        let pk_bytes = fs::read(params_dir.join("proving_key.bin"))?;
+    /// Load the current version's ZK parameters from disk
+    async fn load_parameters(&self, kind: CircuitKind) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> {
+        let store = self.param_store();
+        let version = kind.version();
+
+        // Load proving key, encrypting it if it was stored before encryption was enabled
+        let pk_file = self.parameter_file(kind, version, "proving_key");
+        let header = ParamHeader::new(kind.id(), version, KeyType::Proving);
+        let pk_bytes = store.read_key(&pk_file, &header)
+            .with_context(|| format!("{} does not hold {:?} v{} parameters", pk_file, kind, version))?;
+        if self.config.encrypt_proving_keys && !store.is_encrypted(&pk_file)? {
+            store.write_key(&pk_file, &header, &pk_bytes, true)?;
+            info!("🔒 Encrypted {}", pk_file);
+        }
         let pk = ProvingKey::<Bn254>::deserialize_compressed(&pk_bytes[..])?;
 
         // Load verifying key
-        let vk_bytes = fs::read(params_dir.join("verifying_key.bin"))?;
-        let vk = VerifyingKey::<Bn254>::deserialize_compressed(&vk_bytes[..])?;
+        let vk = self.load_verifying_key(kind, version)?;
 
         Ok((pk, vk))
     }
 
-    /// Hash data to field element
-    fn hash_to_field(&self, data: &[u8]) -> Fr {
-        let mut hasher = Keccak256::new();
-        hasher.update(data);
-        let hash = hasher.finalize();
-        
-        // Convert hash to field element (mod p)
-        Fr::from_le_bytes_mod_order(&hash)
-    }
-
-    /// Convert float to field element
-    fn float_to_field(&self, value: f64) -> Fr {
-        let scaled = (value * 1000000.0) as u64; // Scale to avoid decimals
-        Fr::from(scaled)
-    }
-
-    /// Convert bytes to field elements
-    fn bytes_to_fields(&self, data: &[u8]) -> Vec<Fr> {
-        data.chunks(31) // BN254 field elements are ~31 bytes
-            .map(|chunk| Fr::from_le_bytes_mod_order(chunk))
-            .collect()
+    fn load_verifying_key(&self, kind: CircuitKind, version: u32) -> Result<VerifyingKey<Bn254>> {
+        let file = self.parameter_file(kind, version, "verifying_key");
+        let vk_bytes = self.param_store().read_key(&file, &ParamHeader::new(kind.id(), version, KeyType::Verifying))
+            .with_context(|| format!("{} does not hold {:?} v{} parameters", file, kind, version))?;
+        VerifyingKey::<Bn254>::deserialize_compressed(&vk_bytes[..])
+            .with_context(|| format!("Invalid verifying key in {}", file))
     }
 
     /// Generate mock AI model weights for demo
-    fn generate_mock_weights(&self) -> Vec<Fr> {
-        let mut rng = ark_std::rand::thread_rng();
-        (0..10).map(|_| Fr::rand(&mut rng)).collect()
-    }
-
-    /// Convert field element to string
-    fn field_to_string(&self, field: &Fr) -> String {
-        format!("{:?}", field)
-    }
-
-    /// Convert string to field element
-    fn string_to_field(&self, s: &str) -> Result<Fr> {
-        // This is a simplified conversion - in production, use proper serialization
-        let bytes = s.as_bytes();
-        Ok(Fr::from_le_bytes_mod_order(bytes))
-    }
-
     /// Hash verifying key for integrity check
-    fn hash_verifying_key(&self) -> Result<String> {
-        let vk = self.verifying_key.as_ref()
-            .context("Verifying key not initialized")?;
+    fn hash_vk(&self, vk: Option<&VerifyingKey<Bn254>>) -> Result<String> {
+        let vk = vk.context("Verifying key not initialized")?;
 
         let mut vk_bytes = Vec::new();
         vk.serialize_compressed(&mut vk_bytes)?;
@@ -322,21 +2498,300 @@ impl ZKProver {
         hex::encode(hash)
     }
 
+    /// Unexpired cached proof for `threat_hash` made with the current keys, if any
+    pub fn get_cached_proof(&self, threat_hash: &Fr) -> Result<Option<ThreatProof>> {
+        let Some(cache) = &self.proof_cache else {
+            return Ok(None);
+        };
+        let now = chrono::Utc::now().timestamp() as u64;
+        let Some(proof) = cache.get_by_threat(&encode_public_input(threat_hash)?, now)? else {
+            return Ok(None);
+        };
+
+        // Proofs made before a key, circuit or model change would no longer verify, and
+        // expired ones are rejected
+        let model_commitment = self.model.as_ref().map(|model| model.commitment());
+        let kind = proof.circuit_kind();
+        let current = proof.expires_at > now
+            && proof.circuit_version == kind.version()
+            && proof.public_inputs.get(2).map(|input| decode_public_input(input)).transpose()? == model_commitment
+            && proof.backend == self.config.backend
+            && proof.verification_key_hash == self.verification_key_hash(kind)?;
+        Ok(current.then_some(proof))
+    }
+
     /// Get proof statistics
     pub fn get_proof_stats(&self) -> ProofStats {
-        let cache = self.proof_cache.read().unwrap();
         ProofStats {
-            total_proofs: cache.len(),
+            total_proofs: self.proof_cache.as_ref().map_or(0, ProofCache::len),
             enabled: self.enabled,
-            has_parameters: self.proving_key.is_some() && self.verifying_key.is_some(),
+            backend: self.config.backend,
+            has_parameters: match self.config.backend {
+                ProvingBackend::Groth16 => self.config.relay.delegate || (self.proving_key.is_some() && self.verifying_key.is_some()),
+                ProvingBackend::Marlin => CircuitKind::ALL
+                    .into_iter()
+                    .filter(|kind| self.circuit_enabled(*kind))
+                    .all(|kind| self.universal_keys.contains_key(&kind)),
+                ProvingBackend::Halo2 => self.halo2_keys.is_some(),
+            },
+            msm_device: self.msm.device(),
+            msm_benchmark: self.msm_benchmark.clone(),
+        }
+    }
+
+    /// Prove and verify a sample statement of every circuit `iterations` times with the
+    /// configured backend and MSM engine; `profile` also costs each circuit's named regions
+    pub async fn benchmark(&self, iterations: usize, profile: bool) -> Result<ProverBenchmark> {
+        if !self.enabled {
+            return Err(anyhow::anyhow!("ZK proofs are disabled"));
+        }
+        let iterations = iterations.max(1);
+        info!("🏃 Running ZK prover benchmark ({:?}, {} iterations per circuit)", self.config.backend, iterations);
+
+        let threshold = encode_confidence(DETECTION_THRESHOLD)?;
+        let reputation = encode_confidence(NODE_REPUTATION)?;
+
+        // Threat circuit openings have a fixed depth, so a synthetic model costs the same
+        let model = match &self.model {
+            Some(model) => Arc::clone(model),
+            None => Arc::new(ModelCommitment::from_bytes(&[0x5a; 4096])?),
+        };
+        let fields = transaction_fields(b"dagshield benchmark transaction")?;
+        let hash = mimc_hash(Fr::from(0u64), &fields);
+        let threat = ThreatDetectionCircuit {
+            threat_hash: Some(hash),
+            confidence_threshold: Some(threshold),
+            model_commitment: Some(model.commitment()),
+            transaction_data: Some(fields.clone()),
+            ai_model_weights: Some(model.witness(&hash)?),
+            node_reputation: Some(reputation),
+            detection_algorithm: Some(encode_confidence(0.9)?),
+        };
+        let threat_inputs = vec![hash, threshold, model.commitment()];
+
+        let delegated = BlindedWitness {
+            midstate: BlindedWitness::midstate(&fields)?,
+            confidence_threshold: threshold,
+            model_commitment: model.commitment(),
+            detection: encode_confidence(0.9)?,
+            node_reputation: reputation,
+            model: model.witness(&hash)?,
+        }
+        .circuit();
+
+        let metadata = ThreatMetadata {
+            chain_id: 2484,
+            contract_address: Address::repeat_byte(0x5a),
+            threat_category: "benchmark".to_string(),
+        }
+        .fields()?;
+        let disclosure = DisclosureCircuit::new(threat.clone(), metadata, Fr::from(7u64), &self.config.disclosure);
+        let disclosure_inputs = DisclosureCircuit::public_inputs(
+            &threat_inputs,
+            disclosure::metadata_commitment(&metadata, Fr::from(7u64)),
+            &disclosure.disclosed.context("Disclosure circuit has no disclosed fields")?,
+        );
+
+        let salt = Fr::from(7u64);
+        let energy = EnergyRangeCircuit::new(50_000, (0, 86_400), Fr::from(1u64), 40_000 * 72_000, 72_000, salt);
+        let energy_inputs = EnergyRangeCircuit::public_inputs(50_000, (0, 86_400), Fr::from(1u64), energy_commitment(40_000 * 72_000, 72_000, Fr::from(1u64), 0, salt));
+
+        // A full batch
+        let slots = (0..BATCH_SLOTS)
+            .map(|i| {
+                Ok(BatchSlot {
+                    transaction_data: transaction_fields(format!("dagshield benchmark transaction {}", i).as_bytes())?,
+                    confidence: encode_confidence(0.9)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let batch_hashes: Vec<Fr> = slots.iter().map(BatchSlot::threat_hash).collect();
+        let batch = BatchThreatCircuit::new(0, threshold, slots, reputation);
+        let batch_inputs = BatchThreatCircuit::public_inputs(0, batch_commitment(0, &batch_hashes), threshold);
+
+        let tree = SignatureTree::new(vec![signature_of(&fields)])?;
+        let signature = SignatureMatchCircuit::new(fields, &tree)?;
+        let signature_inputs = SignatureMatchCircuit::public_inputs(hash, tree.root());
+
+        let classifier = match &self.classifier {
+            Some(classifier) => Arc::clone(classifier),
+            None => Arc::new(QuantizedClassifier::from_bytes(&[0x11; PARAM_BYTES])?),
+        };
+        let (inference, score) = InferenceCircuit::new(transaction_fields(b"dagshield benchmark transaction")?, &classifier)?;
+        let inference_inputs = InferenceCircuit::public_inputs(hash, classifier.commitment()?, score);
+
+        let mut circuits = vec![self.benchmark_circuit(CircuitKind::Threat, threat, &threat_inputs, iterations, profile).await?];
+        // Halo2 only proves threat circuits, and only set up circuits have keys
+        if self.config.backend != ProvingBackend::Halo2 {
+            circuits.push(self.benchmark_circuit(CircuitKind::EnergyRange, energy, &energy_inputs, iterations, profile).await?);
+            if self.circuit_enabled(CircuitKind::Batch) {
+                circuits.push(self.benchmark_circuit(CircuitKind::Batch, batch, &batch_inputs, iterations, profile).await?);
+            }
+            if self.circuit_enabled(CircuitKind::SignatureMatch) {
+                circuits.push(self.benchmark_circuit(CircuitKind::SignatureMatch, signature, &signature_inputs, iterations, profile).await?);
+            }
+            if self.circuit_enabled(CircuitKind::Inference) {
+                circuits.push(self.benchmark_circuit(CircuitKind::Inference, inference, &inference_inputs, iterations, profile).await?);
+            }
+            if self.circuit_enabled(CircuitKind::DelegatedThreat) {
+                circuits.push(self.benchmark_circuit(CircuitKind::DelegatedThreat, delegated, &threat_inputs, iterations, profile).await?);
+            }
+            if self.circuit_enabled(CircuitKind::Disclosure) {
+                circuits.push(self.benchmark_circuit(CircuitKind::Disclosure, disclosure, &disclosure_inputs, iterations, profile).await?);
+            }
+        }
+        for circuit in &circuits {
+            info!(
+                "📊 {} v{}: {} constraints, prove {:.1}ms, verify {:.2}ms, {} byte proofs, {} peak heap",
+                circuit.circuit,
+                circuit.version,
+                circuit.counts.constraints,
+                circuit.proving_ms,
+                circuit.verification_ms,
+                circuit.proof_bytes,
+                circuit.peak_memory_bytes.map_or("unmeasured".to_string(), |bytes| format!("+{:.1} MB", bytes as f64 / (1024.0 * 1024.0)))
+            );
+            for region in &circuit.regions {
+                info!(
+                    "   {}: {} constraints, {} witnesses, ~{:.1}ms of proving",
+                    region.name,
+                    region.constraints,
+                    region.witness_variables,
+                    region.proving_ms
+                );
+            }
+        }
+
+        Ok(ProverBenchmark {
+            backend: self.config.backend,
+            msm_device: self.msm.device(),
+            iterations,
+            circuits,
+        })
+    }
+
+    /// Time proving and verifying `circuit`; benchmark proofs queue behind every real proof
+    async fn benchmark_circuit<C>(
+        &self,
+        kind: CircuitKind,
+        circuit: C,
+        public_inputs: &[Fr],
+        iterations: usize,
+        profile: bool,
+    ) -> Result<CircuitBenchmark>
+    where
+        C: ark_relations::r1cs::ConstraintSynthesizer<Fr> + Clone + Send + 'static,
+    {
+        let mut result = CircuitBenchmark::new(kind, constraint_counts(circuit.clone())?);
+
+        let heap = HeapPeak::start();
+        let timings: Result<(Duration, Duration)> = async {
+            let (mut proving, mut verification) = (Duration::ZERO, Duration::ZERO);
+            for _ in 0..iterations {
+                let start = Instant::now();
+                let proof = self.prove(kind, 0.0, circuit.clone()).await?;
+                proving += start.elapsed();
+                result.proof_bytes = proof.len();
+
+                let start = Instant::now();
+                if !self.verify(kind, kind.version(), self.config.backend, public_inputs, &proof)? {
+                    return Err(anyhow::anyhow!("{:?} benchmark proof failed verification", kind));
+                }
+                verification += start.elapsed();
+            }
+            Ok((proving, verification))
+        }
+        .await;
+        result.peak_memory_bytes = heap.stop();
+
+        let (proving, verification) = timings?;
+        result.proving_ms = proving.as_secs_f64() * 1000.0 / iterations as f64;
+        result.verification_ms = verification.as_secs_f64() * 1000.0 / iterations as f64;
+        if profile {
+            result.regions = profiler::profile(circuit, result.proving_ms)?;
         }
+        Ok(result)
     }
 
     /// Clear proof cache
-    pub fn clear_cache(&self) {
-        self.proof_cache.write().unwrap().clear();
+    pub fn clear_cache(&self) -> Result<()> {
+        if let Some(cache) = &self.proof_cache {
+            cache.clear()?;
+        }
         self.circuit_cache.write().unwrap().clear();
+        Ok(())
+    }
+}
+
+/// Detection a threat proof attests, shared by local, relayed and disclosure proofs
+struct DetectionWitness {
+    confidence: Fr,
+    threshold: Fr,
+    reputation: Fr,
+}
+
+impl DetectionWitness {
+    fn new(ai_confidence: f64) -> Result<Self> {
+        Ok(Self {
+            confidence: encode_confidence(ai_confidence)?,
+            threshold: encode_confidence(DETECTION_THRESHOLD)?,
+            reputation: encode_confidence(NODE_REPUTATION)?,
+        })
+    }
+
+    /// Threat circuit proving this detection of `transaction_hash` with `model`
+    fn circuit(&self, model: &ModelCommitment, transaction_fields: Vec<Fr>, transaction_hash: Fr) -> Result<ThreatDetectionCircuit> {
+        Ok(ThreatDetectionCircuit {
+            threat_hash: Some(transaction_hash),
+            confidence_threshold: Some(self.threshold),
+            model_commitment: Some(model.commitment()),
+            transaction_data: Some(transaction_fields),
+            ai_model_weights: Some(model.witness(&transaction_hash)?),
+            node_reputation: Some(self.reputation),
+            detection_algorithm: Some(self.confidence),
+        })
+    }
+}
+
+/// Length-prefixed, zero-padded field elements of transaction data, as hashed by the threat circuit
+pub fn transaction_fields(data: &[u8]) -> Result<Vec<Fr>> {
+    if data.len() > TX_DATA_CHUNKS * 31 {
+        return Err(anyhow::anyhow!(
+            "Transaction data is {} bytes, the threat circuit takes at most {}",
+            data.len(),
+            TX_DATA_CHUNKS * 31
+        ));
+    }
+
+    // 31-byte chunks always fit below the BN254 modulus
+    let mut fields = vec![Fr::from(data.len() as u64)];
+    fields.extend(data.chunks(31).map(Fr::from_le_bytes_mod_order));
+    fields.resize(TX_DATA_CHUNKS + 1, Fr::from(0u64));
+    Ok(fields)
+}
+
+/// Binding hash of transaction data; the threat circuit recomputes it from the witness
+pub fn threat_hash(data: &[u8]) -> Result<Fr> {
+    Ok(mimc_hash(Fr::from(0u64), &transaction_fields(data)?))
+}
+
+/// Encode a public input as hex of its compressed canonical serialization
+pub fn encode_public_input(field: &Fr) -> Result<String> {
+    let mut bytes = Vec::new();
+    field.serialize_compressed(&mut bytes)
+        .context("Failed to serialize public input")?;
+    Ok(hex::encode(bytes))
+}
+
+/// Decode a public input written by `encode_public_input`; non-canonical values are rejected
+pub fn decode_public_input(encoded: &str) -> Result<Fr> {
+    let bytes = hex::decode(encoded)
+        .with_context(|| format!("Public input is not hex: {}", encoded))?;
+    if bytes.len() != Fr::default().compressed_size() {
+        return Err(anyhow::anyhow!("Public input has {} bytes: {}", bytes.len(), encoded));
     }
+    Fr::deserialize_compressed(&bytes[..])
+        .with_context(|| format!("Public input is not a canonical field element: {}", encoded))
 }
 
 /// Proof statistics
@@ -344,7 +2799,12 @@ impl ZKProver {
 pub struct ProofStats {
     pub total_proofs: usize,
     pub enabled: bool,
+    pub backend: ProvingBackend,
     pub has_parameters: bool,
+    #[serde(default)]
+    pub msm_device: MsmDevice,
+    #[serde(default)]
+    pub msm_benchmark: Option<MsmBenchmark>,
 }
 
 // Implement the constraint system for the circuit
@@ -353,7 +2813,7 @@ impl ark_relations::r1cs::ConstraintSynthesizer<Fr> for ThreatDetectionCircuit {
         self,
         cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
     ) -> ark_relations::r1cs::Result<()> {
-        use ark_r1cs_std::prelude::*;
+        use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 
         // Allocate public inputs
         let threat_hash = FpVar::new_input(cs.clone(), || {
@@ -364,6 +2824,10 @@ impl ark_relations::r1cs::ConstraintSynthesizer<Fr> for ThreatDetectionCircuit {
             self.confidence_threshold.ok_or(ark_relations::r1cs::SynthesisError::AssignmentMissing)
         })?;
 
+        let model_commitment = FpVar::new_input(cs.clone(), || {
+            self.model_commitment.ok_or(ark_relations::r1cs::SynthesisError::AssignmentMissing)
+        })?;
+
         // Allocate private inputs
         let detection_result = FpVar::new_witness(cs.clone(), || {
             self.detection_algorithm.ok_or(ark_relations::r1cs::SynthesisError::AssignmentMissing)
@@ -373,52 +2837,89 @@ impl ark_relations::r1cs::ConstraintSynthesizer<Fr> for ThreatDetectionCircuit {
             self.node_reputation.ok_or(ark_relations::r1cs::SynthesisError::AssignmentMissing)
         })?;
 
-        // Constraint 1: Detection result must be above threshold
-        let threshold_check = detection_result.is_cmp(&confidence_threshold, std::cmp::Ordering::Greater, false)?;
-        threshold_check.enforce_equal(&Boolean::TRUE)?;
+        // Constraints 1-3: Fixed-point confidences, detection above threshold, high reputation
+        enforce_threat_confidences(&detection_result, &confidence_threshold, &node_reputation)?;
 
-        // Constraint 2: Node reputation must be high (> 0.8)
-        let reputation_threshold = FpVar::constant(Fr::from(800000u64)); // 0.8 * 1000000
-        let reputation_check = node_reputation.is_cmp(&reputation_threshold, std::cmp::Ordering::Greater, false)?;
-        reputation_check.enforce_equal(&Boolean::TRUE)?;
+        // Constraint 4: Threat hash is the MiMC hash of the transaction data
+        let computed_hash = region(&cs, "transaction hash", || transaction_hash_var(cs.clone(), self.transaction_data.as_deref()))?;
+        computed_hash.enforce_equal(&threat_hash)?;
 
-        // Constraint 3: Threat hash integrity
-        if let Some(tx_data) = &self.transaction_data {
-            let computed_hash = self.compute_hash_constraints(cs.clone(), tx_data)?;
-            computed_hash.enforce_equal(&threat_hash)?;
-        }
+        // Constraint 5: Model chunks selected by the threat hash open the model commitment
+        region(&cs, "model openings", || {
+            enforce_model_openings(cs.clone(), &threat_hash, &model_commitment, self.ai_model_weights.as_ref())
+        })?;
 
         Ok(())
     }
 }
 
-impl ThreatDetectionCircuit {
-    /// Compute hash constraints within the circuit
-    fn compute_hash_constraints(
-        &self,
-        cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
-        data: &[Fr],
-    ) -> ark_relations::r1cs::Result<ark_r1cs_std::fields::fp::FpVar<Fr>> {
-        use ark_r1cs_std::prelude::*;
+/// Confidence constraints shared by the threat circuits
+pub(crate) fn enforce_threat_confidences(
+    detection_result: &ark_r1cs_std::fields::fp::FpVar<Fr>,
+    confidence_threshold: &ark_r1cs_std::fields::fp::FpVar<Fr>,
+    node_reputation: &ark_r1cs_std::fields::fp::FpVar<Fr>,
+) -> ark_relations::r1cs::Result<()> {
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+
+    let cs = detection_result.cs();
+
+    // Confidences are fixed-point values in [0, 1], so comparisons cannot wrap
+    region(&cs, "fixed-point range checks", || {
+        [detection_result, confidence_threshold, node_reputation].into_iter().try_for_each(enforce_fixed_point)
+    })?;
+
+    // Detection result must be above threshold
+    region(&cs, "threshold comparison", || {
+        let threshold_check = detection_result.is_cmp(confidence_threshold, std::cmp::Ordering::Greater, false)?;
+        threshold_check.enforce_equal(&Boolean::TRUE)
+    })?;
+
+    // Node reputation must be high (> 0.8)
+    region(&cs, "reputation check", || {
+        let reputation_threshold = FpVar::constant(Fr::from(800000u64)); // 0.8 * 1000000
+        let reputation_check = node_reputation.is_cmp(&reputation_threshold, std::cmp::Ordering::Greater, false)?;
+        reputation_check.enforce_equal(&Boolean::TRUE)
+    })
+}
 
-        // Simplified hash computation for demo
-        // In production, use proper hash function constraints (Poseidon, etc.)
-        let mut result = FpVar::constant(Fr::from(0u64));
-        
-        for (i, &element) in data.iter().enumerate() {
-            let element_var = FpVar::constant(element);
-            let multiplier = FpVar::constant(Fr::from((i + 1) as u64));
-            result = &result + &element_var * &multiplier;
-        }
+/// Hash a `transaction_fields` witness within the circuit
+pub(crate) fn transaction_hash_var(
+    cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+    transaction_data: Option<&[Fr]>,
+) -> ark_relations::r1cs::Result<ark_r1cs_std::fields::fp::FpVar<Fr>> {
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 
-        Ok(result)
-    }
+    let fields = transaction_vars(cs, transaction_data)?;
+    mimc_hash_var(FpVar::constant(Fr::from(0u64)), &fields)
+}
+
+/// Allocate a `transaction_fields` witness
+pub(crate) fn transaction_vars(
+    cs: ark_relations::r1cs::ConstraintSystemRef<Fr>,
+    transaction_data: Option<&[Fr]>,
+) -> ark_relations::r1cs::Result<Vec<ark_r1cs_std::fields::fp::FpVar<Fr>>> {
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+
+    // Fixed number of slots so every proof shares one setup
+    (0..=TX_DATA_CHUNKS)
+        .map(|i| {
+            FpVar::new_witness(cs.clone(), || {
+                transaction_data
+                    .and_then(|data| data.get(i).copied())
+                    .ok_or(ark_relations::r1cs::SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn test_model() -> ModelCommitment {
+        ModelCommitment::from_bytes(&[0x42; 4096]).unwrap()
+    }
+
     #[tokio::test]
     async fn test_zk_prover_initialization() {
         let mut prover = ZKProver::new(true);
@@ -436,6 +2937,10 @@ mod tests {
         let confidence = 0.85;
         let node_id = "test_node";
 
+        // Threat proofs need a model to commit to
+        assert!(prover.generate_threat_proof(transaction_data, confidence, node_id).await.is_err());
+        prover.set_model(test_model());
+
         let proof = prover
             .generate_threat_proof(transaction_data, confidence, node_id)
             .await
@@ -443,6 +2948,279 @@ mod tests {
 
         let is_valid = prover.verify_threat_proof(&proof).await.unwrap();
         assert!(is_valid);
+
+        // Expired proofs and proofs from revoked nodes are rejected
+        let mut expired = proof.clone();
+        expired.expires_at = proof.timestamp;
+        assert!(!prover.verify_threat_proof(&expired).await.unwrap());
+        // A declared expiry past the configured validity is not trusted
+        let mut stale = proof.clone();
+        stale.timestamp = proof.timestamp - prover.config.proof_validity_secs;
+        stale.expires_at = u64::MAX;
+        assert!(!prover.verify_threat_proof(&stale).await.unwrap());
+        prover.revocations.write().unwrap().revoked_nodes.insert(node_id.to_string());
+        assert!(!prover.verify_threat_proof(&proof).await.unwrap());
+        *prover.revocations.write().unwrap() = RevocationList::default();
+
+        // Proofs for a model that was never registered are rejected
+        prover.model_commitments.clear();
+        assert!(!prover.verify_threat_proof(&proof).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_threat_proofs_batch() {
+        let mut prover = ZKProver::new(true);
+        prover.initialize().await.unwrap();
+        prover.set_model(test_model());
+
+        let inputs: Vec<ThreatInput> = (0..3)
+            .map(|i| ThreatInput {
+                transaction_data: format!("batched_transaction_{}", i).into_bytes(),
+                ai_confidence: 0.8 + i as f64 * 0.05,
+                node_id: "test_node".to_string(),
+            })
+            .collect();
+
+        let results: Vec<(usize, Result<ThreatProof>)> = prover.generate_threat_proofs_batch(&inputs).collect().await;
+        let mut indices: Vec<usize> = results.iter().map(|(index, _)| *index).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, vec![0, 1, 2]);
+
+        for (index, proof) in results {
+            let proof = proof.unwrap();
+            assert_eq!(proof.public_inputs[0], encode_public_input(&threat_hash(&inputs[index].transaction_data).unwrap()).unwrap());
+            assert!(prover.verify_threat_proof(&proof).await.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_public_input_round_trip() {
+        let mut rng = ark_std::test_rng();
+        for field in [Fr::from(0u64), Fr::from(700_000u64), -Fr::from(1u64), Fr::rand(&mut rng)] {
+            let encoded = encode_public_input(&field).unwrap();
+            assert_eq!(encoded.len(), 64);
+            assert_eq!(decode_public_input(&encoded).unwrap(), field);
+        }
+
+        // The modulus itself and trailing bytes are not canonical
+        let modulus = "010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430";
+        assert!(decode_public_input(modulus).is_err());
+        assert!(decode_public_input(&format!("{}00", encode_public_input(&Fr::from(1u64)).unwrap())).is_err());
+        assert!(decode_public_input("not hex").is_err());
+    }
+
+    #[test]
+    fn test_threat_hash_binds_transaction_data() {
+        use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+
+        let model = test_model();
+        let is_satisfied = |data: &[u8], claimed_hash: Fr| {
+            let circuit = ThreatDetectionCircuit {
+                threat_hash: Some(claimed_hash),
+                confidence_threshold: Some(Fr::from(700_000u64)),
+                model_commitment: Some(model.commitment()),
+                transaction_data: Some(transaction_fields(data).unwrap()),
+                ai_model_weights: Some(model.witness(&claimed_hash).unwrap()),
+                node_reputation: Some(Fr::from(950_000u64)),
+                detection_algorithm: Some(Fr::from(850_000u64)),
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        let data = b"transfer(0xdead, 1000)";
+        assert!(is_satisfied(data, threat_hash(data).unwrap()));
+        assert!(!is_satisfied(b"transfer(0xdead, 1001)", threat_hash(data).unwrap()));
+
+        // Padding does not collide with explicit zero bytes
+        assert_ne!(threat_hash(b"ab").unwrap(), threat_hash(b"ab\0").unwrap());
+        assert!(threat_hash(&[0u8; TX_DATA_CHUNKS * 31 + 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_energy_range_proof() {
+        let mut prover = ZKProver::new(true);
+        prover.initialize().await.unwrap();
+
+        // 40 W for 20 of 24 hours
+        let energy_mj = 40_000 * 72_000;
+        let proof = prover
+            .generate_energy_range_proof(energy_mj, 72_000, (0, 86_400), 50.0, "test_node")
+            .await
+            .unwrap();
+        assert!(prover.verify_energy_range_proof(&proof).await.unwrap());
+
+        // Claiming a lower threshold invalidates the proof
+        let mut tampered = proof.clone();
+        tampered.max_avg_milliwatts = 30_000;
+        assert!(!prover.verify_energy_range_proof(&tampered).await.unwrap());
+
+        assert!(prover
+            .generate_energy_range_proof(energy_mj, 72_000, (0, 86_400), 30.0, "test_node")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registered_verifying_key() {
+        let mut prover = ZKProver::new(true);
+        prover.initialize().await.unwrap();
+        let energy_mj = 40_000 * 72_000;
+
+        let local: [u8; 32] = hex::decode(prover.verification_key_hash(CircuitKind::EnergyRange).unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(prover.set_registered_vk_hash(CircuitKind::EnergyRange, local).unwrap(), RegisteredKeyStatus::Matching);
+        assert!(prover
+            .generate_energy_range_proof(energy_mj, 72_000, (0, 86_400), 50.0, "test_node")
+            .await
+            .is_ok());
+
+        // An on-chain upgrade stops proving with the old keys
+        assert_eq!(prover.set_registered_vk_hash(CircuitKind::EnergyRange, [7u8; 32]).unwrap(), RegisteredKeyStatus::Upgraded);
+        assert!(prover
+            .generate_energy_range_proof(energy_mj, 72_000, (0, 86_400), 50.0, "test_node")
+            .await
+            .is_err());
+        assert_eq!(prover.set_registered_vk_hash(CircuitKind::EnergyRange, [7u8; 32]).unwrap(), RegisteredKeyStatus::Mismatched);
+
+        assert_eq!(prover.set_registered_vk_hash(CircuitKind::EnergyRange, [0u8; 32]).unwrap(), RegisteredKeyStatus::Unregistered);
+        assert!(prover.registered_vk_hashes.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_proofs_batch() {
+        let mut prover = ZKProver::new(true);
+        prover.initialize().await.unwrap();
+        prover.set_model(test_model());
+
+        let mut proofs = Vec::new();
+        for (data, confidence) in [(&b"tx-a"[..], 0.85), (b"tx-b", 0.91), (b"tx-c", 0.72)] {
+            proofs.push(prover.generate_threat_proof(data, confidence, "test_node").await.unwrap());
+        }
+        assert!(prover.verify_proofs_batch(&proofs).await.unwrap());
+
+        // Reusing another proof's public inputs fails the whole batch
+        proofs[0].public_inputs = proofs[1].public_inputs.clone();
+        assert!(!prover.verify_proofs_batch(&proofs).await.unwrap());
+        assert!(prover.verify_proofs_batch(&proofs[2..]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_version_routing() {
+        let mut prover = ZKProver::new(true);
+        prover.initialize().await.unwrap();
+        prover.set_model(test_model());
+
+        let proof = prover.generate_threat_proof(b"tx-a", 0.85, "test_node").await.unwrap();
+        assert_eq!(proof.circuit_version, THREAT_CIRCUIT_VERSION);
+
+        // Unknown and pre-versioning proofs are rejected, not checked against the wrong key
+        let mut legacy = proof.clone();
+        legacy.circuit_version = 1;
+        assert!(prover.verify_threat_proof(&legacy).await.is_err());
+        legacy.circuit_version = 0;
+        assert!(prover.verify_threat_proof(&legacy).await.is_err());
+
+        // A registered version routes to its own key (here the current one, so it verifies)
+        let vk = prover.verifying_key.clone().unwrap();
+        prover.register_verifying_key(1, &vk);
+        legacy.circuit_version = 1;
+        assert!(prover.verify_threat_proof(&legacy).await.unwrap());
+        assert_eq!(prover.supported_circuit_versions(), vec![THREAT_CIRCUIT_VERSION, 1]);
+        assert!(prover.verify_proofs_batch(&[proof]).await.unwrap());
+
+        // Parameter files carry their circuit and version
+        let file = prover.parameter_file(CircuitKind::Threat, THREAT_CIRCUIT_VERSION, "verifying_key");
+        let header = ParamHeader::new(CircuitKind::Threat.id(), THREAT_CIRCUIT_VERSION, KeyType::Verifying);
+        assert!(prover.param_store().read_key(&file, &header).is_ok());
+        let next = ParamHeader { circuit_version: THREAT_CIRCUIT_VERSION + 1, ..header };
+        assert!(prover.param_store().read_key(&file, &next).is_err());
+        let energy = ParamHeader { circuit: CircuitKind::EnergyRange.id(), ..header };
+        assert!(prover.param_store().read_key(&file, &energy).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_batch_proof() {
+        let mut prover = ZKProver::new(true);
+        prover.initialize().await.unwrap();
+        // Batch keys are opt-in
+        assert!(prover.batch_proving_key.is_none());
+        let single: [(&[u8], f64); 1] = [(b"tx-a", 0.85)];
+        assert!(prover.generate_batch_proof(&single, 7, "test_node").await.is_err());
+
+        prover.config.optional_circuits = vec![CircuitKind::Batch];
+        prover.initialize().await.unwrap();
+
+        let detections: [(&[u8], f64); 3] = [(b"tx-a", 0.85), (b"tx-b", 0.91), (b"tx-c", 0.72)];
+        let proof = prover.generate_batch_proof(&detections, 7, "test_node").await.unwrap();
+        assert_eq!(proof.threat_hashes.len(), 3);
+        assert!(prover.verify_batch_proof(&proof).await.unwrap());
+
+        // Dropping a detection from the report breaks the proof
+        let mut trimmed = proof.clone();
+        trimmed.threat_hashes.pop();
+        assert!(!prover.verify_batch_proof(&trimmed).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_proving_backend_selection() {
+        // Proofs serialized before backends were selectable are Groth16
+        let legacy: ThreatProof = serde_json::from_str(
+            r#"{"proof":[],"public_inputs":[],"verification_key_hash":"","timestamp":0,"node_id":"n"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.backend, ProvingBackend::Groth16);
+
+        let config: ZKProverConfig = serde_json::from_str(
+            &serde_json::to_string(&ZKProverConfig::default()).unwrap().replace("groth16", "marlin"),
+        )
+        .unwrap();
+        assert_eq!(config.backend, ProvingBackend::Marlin);
+
+        if !ProvingBackend::Marlin.is_available() {
+            let mut prover = ZKProver::with_config(config);
+            assert!(prover.initialize().await.is_err());
+        }
+    }
+
+    #[test]
+    fn test_circuit_constraints_match_golden_file() {
+        fn entry<C: ark_relations::r1cs::ConstraintSynthesizer<Fr> + Clone>(kind: CircuitKind, circuit: C) -> serde_json::Value {
+            serde_json::json!({
+                "version": kind.version(),
+                "counts": constraint_counts(circuit.clone()).unwrap(),
+                "digest": benchmark::constraint_digest(circuit).unwrap(),
+            })
+        }
+        let current: BTreeMap<String, serde_json::Value> = CircuitKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let entry = match kind {
+                    CircuitKind::Threat => entry(kind, ThreatDetectionCircuit::default()),
+                    CircuitKind::EnergyRange => entry(kind, EnergyRangeCircuit::default()),
+                    CircuitKind::Batch => entry(kind, BatchThreatCircuit::default()),
+                    CircuitKind::SignatureMatch => entry(kind, SignatureMatchCircuit::default()),
+                    CircuitKind::Inference => entry(kind, InferenceCircuit::default()),
+                    CircuitKind::DelegatedThreat => entry(kind, DelegatedThreatCircuit::default()),
+                    CircuitKind::Disclosure => entry(kind, DisclosureCircuit::default()),
+                };
+                (format!("{:?}", kind), entry)
+            })
+            .collect();
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/circuit_constraints.json");
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, serde_json::to_string_pretty(&current).unwrap() + "\n").unwrap();
+        }
+        let golden: BTreeMap<String, serde_json::Value> = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            current, golden,
+            "Circuit constraints changed: bump the circuit's version, then regenerate {} with UPDATE_GOLDEN=1",
+            path.display()
+        );
     }
 
     #[tokio::test]
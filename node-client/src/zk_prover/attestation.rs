@@ -0,0 +1,294 @@
+/*!
+ * Threshold threat attestation
+ * K of N committee nodes, spread over at least `min_regions` regions, each prove the same threat
+ * with their own threat proof and sign it with their node wallet. The attestations are aggregated
+ * into one report that is only accepted with K valid, distinct committee signatures over the same
+ * statement (threat hash, threshold and model commitment), so one faulty or malicious node cannot
+ * report a threat alone. The report's proofs are batch verified with one pairing check
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, Signature, H256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::warn;
+
+use super::ThreatProof;
+
+/// Node allowed to attest threats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitteeMember {
+    pub node_id: String,
+    /// Node wallet its attestations are signed with
+    pub signer: Address,
+    pub region: String,
+}
+
+/// Attestation committee
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationConfig {
+    /// Attestations a report needs
+    pub threshold: usize,
+    /// Distinct regions the attesting nodes must span
+    #[serde(default = "default_min_regions")]
+    pub min_regions: usize,
+    pub members: Vec<CommitteeMember>,
+}
+
+fn default_min_regions() -> usize {
+    2
+}
+
+impl Default for AttestationConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0,
+            min_regions: default_min_regions(),
+            members: Vec::new(),
+        }
+    }
+}
+
+impl AttestationConfig {
+    /// Whether `node_id` may attest threats
+    pub fn is_member(&self, node_id: &str) -> bool {
+        self.member(node_id).is_some()
+    }
+
+    fn member(&self, node_id: &str) -> Option<&CommitteeMember> {
+        self.members.iter().find(|member| member.node_id == node_id)
+    }
+}
+
+/// One node's signed threat proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreatAttestation {
+    pub proof: ThreatProof,
+    /// EIP-191 signature of `digest`
+    pub signature: String,
+}
+
+impl ThreatAttestation {
+    /// keccak256 of the canonical JSON encoding of the proof
+    pub fn digest(proof: &ThreatProof) -> Result<H256> {
+        Ok(H256::from(keccak256(serde_json::to_vec(proof)?)))
+    }
+
+    /// Sign `proof` with the node wallet
+    pub async fn sign(proof: ThreatProof, wallet: &LocalWallet) -> Result<Self> {
+        let signature = wallet.sign_message(Self::digest(&proof)?.as_bytes()).await?;
+        Ok(Self {
+            proof,
+            signature: format!("0x{}", signature),
+        })
+    }
+
+    /// Threat hash the proof is for, as encoded in its public inputs
+    pub fn threat_hash(&self) -> Result<&str> {
+        self.proof.public_inputs.first().map(String::as_str).context("Threat proof has no public inputs")
+    }
+
+    /// What the proof attests: its threat hash, threshold and model commitment
+    pub fn statement(&self) -> &[String] {
+        &self.proof.public_inputs
+    }
+
+    /// Check the attesting node is on the committee and signed the proof
+    pub fn verify(&self, committee: &AttestationConfig) -> Result<()> {
+        let member = committee.member(&self.proof.node_id)
+            .with_context(|| format!("{} is not on the attestation committee", self.proof.node_id))?;
+        let signature: Signature = self.signature.trim_start_matches("0x").parse()?;
+        signature
+            .verify(Self::digest(&self.proof)?.as_bytes(), member.signer)
+            .with_context(|| format!("Attestation is not signed by {}", member.node_id))
+    }
+}
+
+/// Threat attested by at least `threshold` committee nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestedThreatReport {
+    /// Encoded threat hash every proof is for
+    pub threat_hash: String,
+    pub attestations: Vec<ThreatAttestation>,
+}
+
+impl AttestedThreatReport {
+    /// Keep one valid attestation per committee node for `threat_hash`, out of those agreeing on
+    /// the statement most nodes attested; fails below the threshold
+    pub fn aggregate(
+        threat_hash: &str,
+        attestations: impl IntoIterator<Item = ThreatAttestation>,
+        committee: &AttestationConfig,
+    ) -> Result<Self> {
+        let mut by_statement: BTreeMap<Vec<String>, Vec<ThreatAttestation>> = BTreeMap::new();
+        for attestation in attestations {
+            if attestation.threat_hash()? != threat_hash {
+                continue;
+            }
+            let accepted = by_statement.entry(attestation.statement().to_vec()).or_default();
+            if accepted.iter().any(|other| other.proof.node_id == attestation.proof.node_id) {
+                continue;
+            }
+            if let Err(e) = attestation.verify(committee) {
+                warn!("⚠️ Attestation from {} dropped: {}", attestation.proof.node_id, e);
+                continue;
+            }
+            accepted.push(attestation);
+        }
+
+        let report = Self {
+            threat_hash: threat_hash.to_string(),
+            attestations: by_statement.into_values().max_by_key(Vec::len).unwrap_or_default(),
+        };
+        report.check(committee)?;
+        Ok(report)
+    }
+
+    /// Check the report carries `threshold` attestations of the same statement about its threat
+    /// from distinct committee nodes in at least `min_regions` regions; the proofs themselves are
+    /// checked by `ZKProver::verify_attested_report`
+    pub fn check(&self, committee: &AttestationConfig) -> Result<()> {
+        let committee_regions: BTreeSet<&str> = committee.members.iter().map(|member| member.region.as_str()).collect();
+        if committee.threshold == 0
+            || committee.threshold > committee.members.len()
+            || committee.min_regions > committee.threshold.min(committee_regions.len())
+        {
+            return Err(anyhow::anyhow!(
+                "Attestation threshold {} of {} committee nodes in {} of {} regions is not satisfiable",
+                committee.threshold,
+                committee.members.len(),
+                committee.min_regions,
+                committee_regions.len()
+            ));
+        }
+
+        let mut nodes = BTreeSet::new();
+        let statement = self.attestations.first().map(ThreatAttestation::statement);
+        for attestation in &self.attestations {
+            if attestation.threat_hash()? != self.threat_hash {
+                return Err(anyhow::anyhow!("Attestation from {} is for another threat", attestation.proof.node_id));
+            }
+            if Some(attestation.statement()) != statement {
+                return Err(anyhow::anyhow!(
+                    "Attestation from {} is for another threshold or model",
+                    attestation.proof.node_id
+                ));
+            }
+            attestation.verify(committee)?;
+            if !nodes.insert(attestation.proof.node_id.as_str()) {
+                return Err(anyhow::anyhow!("{} attested twice", attestation.proof.node_id));
+            }
+        }
+
+        if nodes.len() < committee.threshold {
+            return Err(anyhow::anyhow!(
+                "Threat attested by {} of the {} committee nodes required",
+                nodes.len(),
+                committee.threshold
+            ));
+        }
+        let regions = self.regions(committee).len();
+        if regions < committee.min_regions {
+            return Err(anyhow::anyhow!(
+                "Threat attested from {} of the {} regions required",
+                regions,
+                committee.min_regions
+            ));
+        }
+        Ok(())
+    }
+
+    /// Regions of the attesting nodes
+    pub fn regions(&self, committee: &AttestationConfig) -> BTreeSet<String> {
+        self.attestations
+            .iter()
+            .filter_map(|attestation| committee.member(&attestation.proof.node_id))
+            .map(|member| member.region.clone())
+            .collect()
+    }
+
+    pub fn proofs(&self) -> Vec<ThreatProof> {
+        self.attestations.iter().map(|attestation| attestation.proof.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::{backend::ProvingBackend, PUBLIC_INPUT_ENCODING, THREAT_CIRCUIT_VERSION};
+
+    fn proof(node_id: &str, threat_hash: &str, model: &str) -> ThreatProof {
+        ThreatProof {
+            proof: node_id.as_bytes().to_vec(),
+            public_inputs: vec![threat_hash.to_string(), "threshold".to_string(), model.to_string()],
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            circuit_version: THREAT_CIRCUIT_VERSION,
+            backend: ProvingBackend::Groth16,
+            verification_key_hash: "vk".to_string(),
+            timestamp: 0,
+            expires_at: 0,
+            node_id: node_id.to_string(),
+            delegated: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_threshold_attestation() {
+        let wallets: Vec<LocalWallet> = (1..=3).map(|key| format!("{:064x}", key).parse().unwrap()).collect();
+        let mut committee = AttestationConfig {
+            threshold: 2,
+            min_regions: 2,
+            members: wallets
+                .iter()
+                .enumerate()
+                .map(|(i, wallet)| CommitteeMember {
+                    node_id: format!("node-{}", i),
+                    signer: wallet.address(),
+                    region: format!("region-{}", i.min(1)),
+                })
+                .collect(),
+        };
+
+        let attest = |i: usize, threat_hash: &str, model: &str| {
+            ThreatAttestation::sign(proof(&format!("node-{}", i), threat_hash, model), &wallets[i])
+        };
+        let a = attest(0, "threat", "model").await.unwrap();
+        let b = attest(1, "threat", "model").await.unwrap();
+        let same_region = attest(2, "threat", "model").await.unwrap();
+        let other_threat = attest(2, "other", "model").await.unwrap();
+        let other_model = attest(2, "threat", "other model").await.unwrap();
+        let mut forged = attest(2, "threat", "model").await.unwrap();
+        forged.proof.node_id = "node-1".to_string();
+
+        // One node, another threat, another model or a forged signature never make up the threshold
+        assert!(AttestedThreatReport::aggregate("threat", [a.clone(), a.clone(), other_threat], &committee).is_err());
+        assert!(AttestedThreatReport::aggregate("threat", [a.clone(), other_model.clone()], &committee).is_err());
+        assert!(AttestedThreatReport::aggregate("threat", [a.clone(), forged.clone()], &committee).is_err());
+
+        // Nodes of one region don't make up the regions required
+        assert!(AttestedThreatReport::aggregate("threat", [b.clone(), same_region], &committee).is_err());
+
+        let report = AttestedThreatReport::aggregate("threat", [a.clone(), b.clone(), a.clone()], &committee).unwrap();
+        assert_eq!(report.attestations.len(), 2);
+        assert_eq!(report.regions(&committee).len(), 2);
+        let mut mixed = report.clone();
+        mixed.attestations.push(other_model);
+        assert!(mixed.check(&committee).is_err());
+
+        // Tampering with an attested proof breaks its signature
+        let mut tampered = report.clone();
+        tampered.attestations[1].proof.public_inputs[1] = "lower threshold".to_string();
+        assert!(tampered.check(&committee).is_err());
+        let mut padded = report.clone();
+        padded.attestations.push(forged);
+        assert!(padded.check(&committee).is_err());
+
+        // More regions than the committee spans can never be met
+        committee.min_regions = 3;
+        assert!(report.check(&committee).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+/*!
+ * Proving backends
+ * Groth16 needs a trusted setup per circuit; Marlin indexes every circuit from one universal SRS,
+ * so circuits can change without a new ceremony. Halo2 (KZG) also has a universal setup and adds
+ * lookup arguments, but only proves threat circuits. Marlin and Halo2 are behind the `marlin` and
+ * `halo2` features
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+use serde::{Deserialize, Serialize};
+
+/// Proving system used for every circuit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvingBackend {
+    #[default]
+    Groth16,
+    Marlin,
+    Halo2,
+}
+
+impl ProvingBackend {
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::Groth16 => true,
+            Self::Marlin => cfg!(feature = "marlin"),
+            Self::Halo2 => cfg!(feature = "halo2"),
+        }
+    }
+}
+
+/// Size of the universal SRS; the largest circuit must fit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SrsBound {
+    pub max_constraints: usize,
+    pub max_variables: usize,
+    pub max_non_zero: usize,
+}
+
+impl SrsBound {
+    /// Shape of `circuit`, synthesized without a witness
+    pub fn of<C: ConstraintSynthesizer<Fr>>(circuit: C) -> Result<Self> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        let matrices = cs.to_matrices().context("Circuit has no constraint matrices")?;
+        Ok(Self {
+            max_constraints: matrices.num_constraints,
+            max_variables: matrices.num_instance_variables + matrices.num_witness_variables,
+            max_non_zero: matrices.a_num_non_zero.max(matrices.b_num_non_zero).max(matrices.c_num_non_zero),
+        })
+    }
+
+    /// Bound fitting both `self` and `other`
+    pub fn cover(self, other: Self) -> Self {
+        Self {
+            max_constraints: self.max_constraints.max(other.max_constraints),
+            max_variables: self.max_variables.max(other.max_variables),
+            max_non_zero: self.max_non_zero.max(other.max_non_zero),
+        }
+    }
+
+    /// Rounded up to the evaluation domain sizes Marlin indexes into
+    pub fn padded(self) -> Self {
+        Self {
+            max_constraints: self.max_constraints.next_power_of_two(),
+            max_variables: self.max_variables.next_power_of_two(),
+            max_non_zero: self.max_non_zero.next_power_of_two(),
+        }
+    }
+}
+
+#[cfg(feature = "marlin")]
+pub use marlin::{UniversalKeys, UniversalSrs};
+#[cfg(not(feature = "marlin"))]
+pub use disabled::{UniversalKeys, UniversalSrs};
+
+#[cfg(feature = "marlin")]
+mod marlin {
+    use anyhow::{Context, Result};
+    use ark_bn254::{Bn254, Fr};
+    use ark_marlin::{IndexProverKey, IndexVerifierKey, Marlin, Proof, SimpleHashFiatShamirRng, UniversalSRS};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_poly_commit::marlin_pc::MarlinKZG10;
+    use ark_relations::r1cs::ConstraintSynthesizer;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use blake2::Blake2s256;
+    use rand_chacha::ChaChaRng;
+
+    use super::SrsBound;
+
+    type Pc = MarlinKZG10<Bn254, DensePolynomial<Fr>>;
+    type MarlinBn254 = Marlin<Fr, Pc, SimpleHashFiatShamirRng<Blake2s256, ChaChaRng>>;
+
+    /// Universal structured reference string shared by every circuit
+    pub struct UniversalSrs(UniversalSRS<Fr, Pc>);
+
+    impl UniversalSrs {
+        pub fn setup(bound: &SrsBound) -> Result<Self> {
+            let mut rng = crate::zk_prover::rng::prover_rng();
+            MarlinBn254::universal_setup(bound.max_constraints, bound.max_variables, bound.max_non_zero, &mut rng)
+                .map(Self)
+                .map_err(|e| anyhow::anyhow!("Universal setup failed: {:?}", e))
+        }
+
+        pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            Ok(Self(UniversalSRS::<Fr, Pc>::deserialize_compressed(bytes).context("Invalid universal SRS")?))
+        }
+
+        pub fn to_bytes(&self) -> Result<Vec<u8>> {
+            let mut bytes = Vec::new();
+            self.0.serialize_compressed(&mut bytes)?;
+            Ok(bytes)
+        }
+
+        /// Derive a circuit's keys; deterministic, so keys are not stored
+        pub fn index<C: ConstraintSynthesizer<Fr>>(&self, circuit: C) -> Result<UniversalKeys> {
+            let (pk, vk) = MarlinBn254::index(&self.0, circuit)
+                .map_err(|e| anyhow::anyhow!("Circuit does not fit the universal SRS: {:?}", e))?;
+            Ok(UniversalKeys { pk, vk })
+        }
+    }
+
+    /// One circuit's keys indexed from the universal SRS
+    pub struct UniversalKeys {
+        pk: IndexProverKey<Fr, Pc>,
+        vk: IndexVerifierKey<Fr, Pc>,
+    }
+
+    impl UniversalKeys {
+        pub fn prove<C: ConstraintSynthesizer<Fr>>(&self, circuit: C) -> Result<Vec<u8>> {
+            let mut rng = crate::zk_prover::rng::prover_rng();
+            let proof = MarlinBn254::prove(&self.pk, circuit, &mut rng)
+                .map_err(|e| anyhow::anyhow!("Marlin proving failed: {:?}", e))?;
+            let mut bytes = Vec::new();
+            proof.serialize_compressed(&mut bytes)?;
+            Ok(bytes)
+        }
+
+        pub fn verify(&self, public_inputs: &[Fr], proof: &[u8]) -> Result<bool> {
+            let proof = Proof::<Fr, Pc>::deserialize_compressed(proof).context("Failed to deserialize proof")?;
+            let mut rng = ark_std::rand::thread_rng();
+            MarlinBn254::verify(&self.vk, public_inputs, &proof, &mut rng)
+                .map_err(|e| anyhow::anyhow!("Marlin verification failed: {:?}", e))
+        }
+
+        pub fn verifying_key_bytes(&self) -> Result<Vec<u8>> {
+            let mut bytes = Vec::new();
+            self.vk.serialize_compressed(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+#[cfg(not(feature = "marlin"))]
+mod disabled {
+    use anyhow::Result;
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSynthesizer;
+    use std::convert::Infallible;
+
+    use super::SrsBound;
+
+    /// Universal SRS placeholder; setup and import always fail
+    pub struct UniversalSrs(Infallible);
+
+    impl UniversalSrs {
+        pub fn setup(_bound: &SrsBound) -> Result<Self> {
+            Err(anyhow::anyhow!("Built without the `marlin` feature"))
+        }
+
+        pub fn from_bytes(_bytes: &[u8]) -> Result<Self> {
+            Err(anyhow::anyhow!("Built without the `marlin` feature"))
+        }
+
+        pub fn to_bytes(&self) -> Result<Vec<u8>> {
+            match self.0 {}
+        }
+
+        pub fn index<C: ConstraintSynthesizer<Fr>>(&self, _circuit: C) -> Result<UniversalKeys> {
+            match self.0 {}
+        }
+    }
+
+    /// Never constructed without the `marlin` feature
+    pub struct UniversalKeys(Infallible);
+
+    impl UniversalKeys {
+        pub fn prove<C: ConstraintSynthesizer<Fr>>(&self, _circuit: C) -> Result<Vec<u8>> {
+            match self.0 {}
+        }
+
+        pub fn verify(&self, _public_inputs: &[Fr], _proof: &[u8]) -> Result<bool> {
+            match self.0 {}
+        }
+
+        pub fn verifying_key_bytes(&self) -> Result<Vec<u8>> {
+            match self.0 {}
+        }
+    }
+}
@@ -0,0 +1,184 @@
+/*!
+ * Batched threat reports
+ * One proof covers up to `BATCH_SLOTS` detections from an epoch, so a node submits a single
+ * proof per epoch instead of one per threat. The public commitment chains the epoch, the slot
+ * count and every slot's threat hash, so a batch cannot be reordered or padded afterwards
+ */
+
+use ark_bn254::Fr;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use std::cmp::Ordering;
+
+use super::fixed_point::enforce_fixed_point;
+use super::mimc::{mimc_hash, mimc_hash_var};
+use super::profiler::region;
+use super::{transaction_hash_var, TX_DATA_CHUNKS};
+
+/// Detections per batch proof
+pub const BATCH_SLOTS: usize = 4;
+
+/// Batch circuit constraint system version; version 1 did not range-check confidences
+pub const BATCH_CIRCUIT_VERSION: u32 = 2;
+
+/// One detection in a batch
+#[derive(Clone, Debug)]
+pub struct BatchSlot {
+    /// `transaction_fields` of the detected transaction
+    pub transaction_data: Vec<Fr>,
+    pub confidence: Fr,
+}
+
+impl BatchSlot {
+    pub fn threat_hash(&self) -> Fr {
+        mimc_hash(Fr::from(0u64), &self.transaction_data)
+    }
+}
+
+/// Commitment to an epoch's threat hashes in slot order
+pub fn batch_commitment(epoch: u64, threat_hashes: &[Fr]) -> Fr {
+    let mut values = vec![Fr::from(threat_hashes.len() as u64)];
+    values.extend_from_slice(threat_hashes);
+    // Empty slots hash as zero
+    values.resize(BATCH_SLOTS + 1, Fr::from(0u64));
+    mimc_hash(Fr::from(epoch), &values)
+}
+
+/// Up to `BATCH_SLOTS` detections above a public confidence threshold
+///
+/// Public: epoch, batch commitment, threshold. Witness: each slot's transaction data and
+/// confidence, node reputation.
+#[derive(Clone, Debug, Default)]
+pub struct BatchThreatCircuit {
+    // Public inputs
+    pub epoch: Option<u64>,
+    pub commitment: Option<Fr>,
+    pub confidence_threshold: Option<Fr>,
+
+    // Private inputs (witness)
+    pub slots: Option<Vec<BatchSlot>>,
+    pub node_reputation: Option<Fr>,
+}
+
+impl BatchThreatCircuit {
+    /// Circuit with every assignment filled in
+    pub fn new(epoch: u64, confidence_threshold: Fr, slots: Vec<BatchSlot>, node_reputation: Fr) -> Self {
+        let threat_hashes: Vec<Fr> = slots.iter().map(BatchSlot::threat_hash).collect();
+        Self {
+            epoch: Some(epoch),
+            commitment: Some(batch_commitment(epoch, &threat_hashes)),
+            confidence_threshold: Some(confidence_threshold),
+            slots: Some(slots),
+            node_reputation: Some(node_reputation),
+        }
+    }
+
+    /// Public inputs in allocation order
+    pub fn public_inputs(epoch: u64, commitment: Fr, confidence_threshold: Fr) -> Vec<Fr> {
+        vec![Fr::from(epoch), commitment, confidence_threshold]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for BatchThreatCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+
+        let epoch = FpVar::new_input(cs.clone(), || self.epoch.map(Fr::from).ok_or_else(missing))?;
+        let commitment = FpVar::new_input(cs.clone(), || self.commitment.ok_or_else(missing))?;
+        let threshold = FpVar::new_input(cs.clone(), || self.confidence_threshold.ok_or_else(missing))?;
+        enforce_fixed_point(&threshold)?;
+
+        // Constraint 1: Node reputation must be high (> 0.8)
+        let node_reputation = FpVar::new_witness(cs.clone(), || self.node_reputation.ok_or_else(missing))?;
+        region(&cs, "reputation check", || {
+            enforce_fixed_point(&node_reputation)?;
+            node_reputation.enforce_cmp(&FpVar::constant(Fr::from(800000u64)), Ordering::Greater, false)
+        })?;
+
+        let empty = vec![Fr::from(0u64); TX_DATA_CHUNKS + 1];
+        let mut count = FpVar::zero();
+        let mut hashes = Vec::with_capacity(BATCH_SLOTS);
+        let mut previous_active = Boolean::TRUE;
+
+        for i in 0..BATCH_SLOTS {
+            let slot = self.slots.as_ref().map(|slots| slots.get(i));
+
+            let active = Boolean::new_witness(cs.clone(), || slot.map(|slot| slot.is_some()).ok_or_else(missing))?;
+            let confidence = FpVar::new_witness(cs.clone(), || {
+                slot.map(|slot| slot.map_or(Fr::from(0u64), |slot| slot.confidence)).ok_or_else(missing)
+            })?;
+            let hash = region(&cs, "transaction hash", || {
+                transaction_hash_var(
+                    cs.clone(),
+                    slot.map(|slot| slot.map_or(&empty[..], |slot| &slot.transaction_data[..])),
+                )
+            })?;
+
+            // Constraint 2: slots fill in order, so the count fixes which hashes are real
+            previous_active.or(&active.not())?.enforce_equal(&Boolean::TRUE)?;
+
+            // Constraint 3: every active slot clears the threshold; empty slots hold zero, which
+            // is in range too
+            region(&cs, "threshold comparison", || {
+                enforce_fixed_point(&confidence)?;
+                let clears = confidence.is_cmp(&threshold, Ordering::Greater, false)?;
+                clears.or(&active.not())?.enforce_equal(&Boolean::TRUE)
+            })?;
+
+            hashes.push(active.select(&hash, &FpVar::zero())?);
+            count += FpVar::from(active.clone());
+            previous_active = active;
+        }
+
+        // Constraint 4: the public commitment covers the count and every slot's hash
+        let mut values = vec![count];
+        values.extend(hashes);
+        mimc_hash_var(epoch, &values)?.enforce_equal(&commitment)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::transaction_fields;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn is_satisfied(circuit: BatchThreatCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    fn slot(data: &[u8], confidence: u64) -> BatchSlot {
+        BatchSlot {
+            transaction_data: transaction_fields(data).unwrap(),
+            confidence: Fr::from(confidence),
+        }
+    }
+
+    #[test]
+    fn test_batch_circuit() {
+        let threshold = Fr::from(700_000u64);
+        let reputation = Fr::from(950_000u64);
+        let slots = vec![slot(b"tx-a", 850_000), slot(b"tx-b", 910_000), slot(b"tx-c", 720_000)];
+
+        assert!(is_satisfied(BatchThreatCircuit::new(42, threshold, slots.clone(), reputation)));
+
+        // A low-confidence detection cannot be slipped into the batch
+        let mut weak = slots.clone();
+        weak[2] = slot(b"tx-d", 600_000);
+        assert!(!is_satisfied(BatchThreatCircuit::new(42, threshold, weak, reputation)));
+
+        // Commitment binds epoch and order
+        let mut replayed = BatchThreatCircuit::new(42, threshold, slots.clone(), reputation);
+        replayed.epoch = Some(43);
+        assert!(!is_satisfied(replayed));
+
+        let hashes: Vec<Fr> = slots.iter().map(BatchSlot::threat_hash).collect();
+        let mut reordered = BatchThreatCircuit::new(42, threshold, slots, reputation);
+        reordered.commitment = Some(batch_commitment(42, &[hashes[1], hashes[0], hashes[2]]));
+        assert!(!is_satisfied(reordered));
+    }
+}
@@ -0,0 +1,199 @@
+/*!
+ * Prover benchmarks
+ * Constraint counts, proving and verification times, proof sizes and peak memory of each
+ * circuit under the configured backend and MSM engine, for sizing hardware and choosing a backend.
+ * Peak memory is heap growth counted by `TrackingAllocator`, not resident set size, which mostly
+ * reflects pages the allocator kept from earlier work
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+use ark_serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicBool, AtomicIsize, Ordering},
+};
+
+use super::backend::ProvingBackend;
+use super::msm::MsmDevice;
+use super::profiler::RegionProfile;
+use super::CircuitKind;
+
+/// Size of a circuit's constraint system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintCounts {
+    pub constraints: usize,
+    /// Public inputs, excluding the constant one
+    pub public_inputs: usize,
+    pub witness_variables: usize,
+}
+
+/// Synthesize `circuit` as key generation does and count its constraints and variables; no
+/// assignment is needed, so blank circuits work
+pub fn constraint_counts<C: ConstraintSynthesizer<Fr>>(circuit: C) -> Result<ConstraintCounts> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone()).context("Failed to synthesize circuit")?;
+    Ok(ConstraintCounts {
+        constraints: cs.num_constraints(),
+        public_inputs: cs.num_instance_variables() - 1,
+        witness_variables: cs.num_witness_variables(),
+    })
+}
+
+/// keccak256 of a circuit's constraint matrices, hex; changes with any edit to its constraints,
+/// including ones that keep the counts
+pub fn constraint_digest<C: ConstraintSynthesizer<Fr>>(circuit: C) -> Result<String> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone()).context("Failed to synthesize circuit")?;
+    cs.finalize();
+    let matrices = cs.to_matrices().context("Constraint matrices unavailable")?;
+
+    let mut hasher = Keccak256::new();
+    hasher.update((matrices.num_instance_variables as u64).to_le_bytes());
+    hasher.update((matrices.num_witness_variables as u64).to_le_bytes());
+    for matrix in [&matrices.a, &matrices.b, &matrices.c] {
+        hasher.update((matrix.len() as u64).to_le_bytes());
+        for row in matrix {
+            hasher.update((row.len() as u64).to_le_bytes());
+            for (coefficient, variable) in row {
+                let mut bytes = Vec::new();
+                coefficient.serialize_compressed(&mut bytes)?;
+                hasher.update(&bytes);
+                hasher.update((*variable as u64).to_le_bytes());
+            }
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Measurements of one circuit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBenchmark {
+    pub circuit: String,
+    pub version: u32,
+    pub counts: ConstraintCounts,
+    /// Mean over the benchmark iterations
+    pub proving_ms: f64,
+    pub verification_ms: f64,
+    pub proof_bytes: usize,
+    /// Peak heap growth while proving; None unless the binary installs `TrackingAllocator`
+    #[serde(default)]
+    pub peak_memory_bytes: Option<u64>,
+    /// Cost of each named circuit region, when profiled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<RegionProfile>,
+}
+
+impl CircuitBenchmark {
+    pub fn new(kind: CircuitKind, counts: ConstraintCounts) -> Self {
+        Self {
+            circuit: format!("{:?}", kind),
+            version: kind.version(),
+            counts,
+            proving_ms: 0.0,
+            verification_ms: 0.0,
+            proof_bytes: 0,
+            peak_memory_bytes: None,
+            regions: Vec::new(),
+        }
+    }
+}
+
+/// Benchmark of every circuit on this node's prover
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProverBenchmark {
+    pub backend: ProvingBackend,
+    pub msm_device: MsmDevice,
+    pub iterations: usize,
+    pub circuits: Vec<CircuitBenchmark>,
+}
+
+static MEASURING: AtomicBool = AtomicBool::new(false);
+static GROWTH: AtomicIsize = AtomicIsize::new(0);
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+
+/// System allocator that counts heap growth while a `HeapPeak` is measuring; otherwise each
+/// allocation costs one relaxed load
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn track(bytes: isize) {
+        if MEASURING.load(Ordering::Relaxed) {
+            let growth = GROWTH.fetch_add(bytes, Ordering::Relaxed) + bytes;
+            PEAK.fetch_max(growth, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::track(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            Self::track(new_size as isize - layout.size() as isize);
+        }
+        new
+    }
+}
+
+/// Peak heap growth, across all threads, between `start` and `stop`; one measurement at a time
+pub struct HeapPeak(());
+
+impl HeapPeak {
+    pub fn start() -> Self {
+        GROWTH.store(0, Ordering::Relaxed);
+        PEAK.store(0, Ordering::Relaxed);
+        MEASURING.store(true, Ordering::Relaxed);
+        Self(())
+    }
+
+    /// Peak growth in bytes; None unless `TrackingAllocator` is the global allocator
+    pub fn stop(self) -> Option<u64> {
+        // An installed allocator counts this, so the peak is never zero
+        drop(std::hint::black_box(Vec::<u8>::with_capacity(1)));
+        MEASURING.store(false, Ordering::Relaxed);
+        let peak = PEAK.load(Ordering::Relaxed);
+        (peak > 0).then_some(peak as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::energy_range::EnergyRangeCircuit;
+
+    #[test]
+    fn test_constraint_counts() {
+        let counts = constraint_counts(EnergyRangeCircuit::new(50_000, (0, 86_400), Fr::from(1u64), 40_000 * 72_000, 72_000, Fr::from(7u64))).unwrap();
+        assert_eq!(counts.public_inputs, 3);
+        assert!(counts.constraints > 0 && counts.witness_variables > 0);
+
+        // Blank circuits used for key generation have the same shape
+        assert_eq!(constraint_counts(EnergyRangeCircuit::default()).unwrap(), counts);
+    }
+}
@@ -0,0 +1,264 @@
+/*!
+ * Phase-2 trusted setup ceremony
+ * Participants take turns multiplying the threat circuit's delta by a secret factor, starting
+ * from keys derived from Powers of Tau. Each contribution proves knowledge of its factor and
+ * extends a public hash chain, so the keys are sound if any one participant discarded theirs
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand};
+use ark_groth16::ProvingKey;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha3::{Digest, Keccak256};
+use std::{fs, path::Path, time::Duration};
+use tracing::info;
+
+use super::ptau::{same_ratio, PowersOfTau};
+use super::ThreatDetectionCircuit;
+
+/// One participant's update of delta
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution {
+    /// delta_g1 after this contribution
+    pub delta_after: G1Affine,
+    /// Random point and its multiple by the secret factor
+    pub s: G1Affine,
+    pub s_delta: G1Affine,
+    /// Factor applied to a point hashed from the chain, proving knowledge of it
+    pub r_delta: G2Affine,
+}
+
+/// Threat circuit keys with every contribution applied so far
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Phase2Transcript {
+    /// keccak256 of the keys derived from Powers of Tau
+    pub initial_hash: [u8; 32],
+    pub params: ProvingKey<Bn254>,
+    pub contributions: Vec<Contribution>,
+}
+
+impl Phase2Transcript {
+    /// Start a ceremony from keys derived from Powers of Tau
+    pub fn new(initial: ProvingKey<Bn254>) -> Result<Self> {
+        Ok(Self {
+            initial_hash: params_hash(&initial)?,
+            params: initial,
+            contributions: Vec::new(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::deserialize_compressed(&bytes[..]).context("Invalid phase-2 transcript")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)?;
+        fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Chain hash after each contribution, in order
+    pub fn contribution_hashes(&self) -> Result<Vec<[u8; 32]>> {
+        let mut digest = self.initial_hash;
+        self.contributions
+            .iter()
+            .map(|contribution| {
+                digest = chain_hash(&digest, contribution)?;
+                Ok(digest)
+            })
+            .collect()
+    }
+
+    /// Apply a contribution seeded from OS randomness mixed with `entropy`; returns the
+    /// contribution hash to publish. The factor is dropped on return
+    pub fn contribute(&mut self, entropy: &[u8]) -> Result<[u8; 32]> {
+        let mut seed = [0u8; 32];
+        ethers::core::rand::thread_rng().fill_bytes(&mut seed);
+        let mut rng = ChaCha20Rng::from_seed(Keccak256::new().chain_update(seed).chain_update(entropy).finalize().into());
+
+        let delta = Fr::rand(&mut rng);
+        let delta_inverse = delta.inverse().context("Sampled a zero contribution")?;
+        let previous = self.contribution_hashes()?.last().copied().unwrap_or(self.initial_hash);
+
+        let s = G1Projective::rand(&mut rng);
+        let s_delta = s * delta;
+        let r = hash_to_g2(&previous, &s.into_affine(), &s_delta.into_affine())?;
+        let contribution = Contribution {
+            delta_after: (self.params.delta_g1 * delta).into_affine(),
+            s: s.into_affine(),
+            s_delta: s_delta.into_affine(),
+            r_delta: (r * delta).into_affine(),
+        };
+
+        let params = &mut self.params;
+        params.delta_g1 = contribution.delta_after;
+        params.vk.delta_g2 = (params.vk.delta_g2 * delta).into_affine();
+        let scale = |points: &[G1Affine]| {
+            let scaled: Vec<G1Projective> = points.iter().map(|p| *p * delta_inverse).collect();
+            G1Projective::normalize_batch(&scaled)
+        };
+        params.l_query = scale(&params.l_query);
+        params.h_query = scale(&params.h_query);
+
+        let hash = chain_hash(&previous, &contribution)?;
+        self.contributions.push(contribution);
+        info!("🔐 Applied phase-2 contribution #{}: {}", self.contributions.len(), hex::encode(hash));
+        Ok(hash)
+    }
+
+    /// Check the transcript descends from `initial` (keys re-derived from Powers of Tau)
+    /// through valid contributions only; returns every contribution hash
+    pub fn verify(&self, initial: &ProvingKey<Bn254>) -> Result<Vec<[u8; 32]>> {
+        if params_hash(initial)? != self.initial_hash {
+            return Err(anyhow::anyhow!("Transcript does not start from these Powers of Tau keys"));
+        }
+
+        // Contributions may only touch delta and the queries divided by it
+        let (params, vk) = (&self.params, &self.params.vk);
+        let unchanged = vk.alpha_g1 == initial.vk.alpha_g1
+            && vk.beta_g2 == initial.vk.beta_g2
+            && vk.gamma_g2 == initial.vk.gamma_g2
+            && vk.gamma_abc_g1 == initial.vk.gamma_abc_g1
+            && params.beta_g1 == initial.beta_g1
+            && params.a_query == initial.a_query
+            && params.b_g1_query == initial.b_g1_query
+            && params.b_g2_query == initial.b_g2_query
+            && params.l_query.len() == initial.l_query.len()
+            && params.h_query.len() == initial.h_query.len();
+        if !unchanged {
+            return Err(anyhow::anyhow!("Transcript changes keys outside delta"));
+        }
+
+        let mut digest = self.initial_hash;
+        let mut delta = initial.delta_g1;
+        let mut hashes = Vec::with_capacity(self.contributions.len());
+        for (i, contribution) in self.contributions.iter().enumerate() {
+            let r = hash_to_g2(&digest, &contribution.s, &contribution.s_delta)?;
+            let valid = !contribution.s.is_zero()
+                && same_ratio((contribution.s, contribution.s_delta), (r, contribution.r_delta))
+                && same_ratio((delta, contribution.delta_after), (r, contribution.r_delta));
+            if !valid {
+                return Err(anyhow::anyhow!("Contribution #{} is invalid", i + 1));
+            }
+
+            digest = chain_hash(&digest, contribution)?;
+            delta = contribution.delta_after;
+            hashes.push(digest);
+        }
+
+        if params.delta_g1 != delta
+            || !same_ratio((G1Affine::generator(), params.delta_g1), (G2Affine::generator(), vk.delta_g2))
+        {
+            return Err(anyhow::anyhow!("Final delta does not match the last contribution"));
+        }
+
+        // L and H must be the initial queries divided by the accumulated delta
+        let mut rng = ark_std::rand::thread_rng();
+        let mut divided = |current: &[G1Affine], original: &[G1Affine]| {
+            let weights: Vec<Fr> = (0..current.len()).map(|_| Fr::rand(&mut rng)).collect();
+            same_ratio(
+                (G1Projective::msm_unchecked(current, &weights), G1Projective::msm_unchecked(original, &weights)),
+                (initial.vk.delta_g2, vk.delta_g2),
+            )
+        };
+        if !divided(&params.l_query, &initial.l_query) || !divided(&params.h_query, &initial.h_query) {
+            return Err(anyhow::anyhow!("Transcript queries do not match its delta"));
+        }
+
+        Ok(hashes)
+    }
+}
+
+/// Threat circuit keys derived from a Powers of Tau file, where every ceremony starts
+pub fn initial_parameters(ptau: &[u8]) -> Result<ProvingKey<Bn254>> {
+    Ok(PowersOfTau::parse(ptau)?.derive_parameters(ThreatDetectionCircuit::default())?.0)
+}
+
+/// Fetch the coordinator's current transcript and store it at `path`
+pub async fn download_transcript(url: &str, path: &Path) -> Result<Phase2Transcript> {
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()?;
+    let bytes = http.get(url).send().await?.error_for_status()?.bytes().await?;
+
+    let transcript = Phase2Transcript::deserialize_compressed(&bytes[..]).context("Invalid phase-2 transcript")?;
+    fs::write(path, &bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("📥 Downloaded phase-2 transcript with {} contributions", transcript.contributions.len());
+    Ok(transcript)
+}
+
+fn params_hash(params: &ProvingKey<Bn254>) -> Result<[u8; 32]> {
+    let mut bytes = Vec::new();
+    params.serialize_compressed(&mut bytes)?;
+    Ok(Keccak256::digest(&bytes).into())
+}
+
+fn chain_hash(previous: &[u8; 32], contribution: &Contribution) -> Result<[u8; 32]> {
+    let mut bytes = Vec::new();
+    contribution.serialize_compressed(&mut bytes)?;
+    Ok(Keccak256::new().chain_update(previous).chain_update(bytes).finalize().into())
+}
+
+/// G2 point with unknown discrete log, bound to the chain and the contribution's G1 pair
+fn hash_to_g2(previous: &[u8; 32], s: &G1Affine, s_delta: &G1Affine) -> Result<G2Projective> {
+    let mut bytes = Vec::new();
+    s.serialize_compressed(&mut bytes)?;
+    s_delta.serialize_compressed(&mut bytes)?;
+    let seed = Keccak256::new()
+        .chain_update(b"dagshield.phase2")
+        .chain_update(previous)
+        .chain_update(bytes)
+        .finalize();
+    Ok(G2Projective::rand(&mut ChaCha20Rng::from_seed(seed.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::energy_range::{energy_commitment, EnergyRangeCircuit};
+    use ark_ff::One;
+    use ark_groth16::{prepare_verifying_key, Groth16};
+
+    #[test]
+    fn test_contributions_verify_and_keep_keys_usable() {
+        let mut rng = ark_std::test_rng();
+        // Phase-1 output has delta = gamma = 1
+        let initial = Groth16::<Bn254>::generate_parameters_with_qap(
+            EnergyRangeCircuit::default(),
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            Fr::one(),
+            Fr::one(),
+            G1Projective::from(G1Affine::generator()),
+            G2Projective::from(G2Affine::generator()),
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut transcript = Phase2Transcript::new(initial.clone()).unwrap();
+        let first = transcript.contribute(b"alice").unwrap();
+        let second = transcript.contribute(b"bob").unwrap();
+        assert_eq!(transcript.verify(&initial).unwrap(), vec![first, second]);
+
+        let salt = Fr::from(7u64);
+        let circuit = EnergyRangeCircuit::new(50_000, (0, 86_400), Fr::from(1u64), 40_000 * 72_000, 72_000, salt);
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(circuit, &transcript.params, &mut rng).unwrap();
+        let inputs = EnergyRangeCircuit::public_inputs(50_000, (0, 86_400), Fr::from(1u64), energy_commitment(40_000 * 72_000, 72_000, Fr::from(1u64), 0, salt));
+        let pvk = prepare_verifying_key(&transcript.params.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &inputs).unwrap());
+
+        // Rewriting delta without a matching proof of knowledge is caught
+        let mut forged = transcript.clone();
+        forged.contributions[1].delta_after = (forged.contributions[1].delta_after * Fr::from(2u64)).into_affine();
+        assert!(forged.verify(&initial).is_err());
+
+        let mut skewed = transcript.clone();
+        skewed.params.l_query[0] = G1Affine::generator();
+        assert!(skewed.verify(&initial).is_err());
+    }
+}
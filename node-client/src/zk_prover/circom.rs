@@ -0,0 +1,124 @@
+/*!
+ * Circom circuits
+ * Threat circuits designed in Circom are loaded from their compiled artifacts: the `.r1cs`
+ * constraint system, the `.wasm` witness generator and a snarkjs Groth16 `.zkey`. zkeys are made
+ * under Circom's QAP reduction, so proofs go through `MsmEngine::prove_with_reduction`. Loading
+ * needs the `circom` feature
+ */
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Compiled artifacts of one Circom circuit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircomCircuitConfig {
+    /// Name proofs refer to the circuit by
+    pub name: String,
+    pub r1cs: PathBuf,
+    pub wasm: PathBuf,
+    pub zkey: PathBuf,
+}
+
+#[cfg(feature = "circom")]
+pub use compat::CircomArtifacts;
+#[cfg(not(feature = "circom"))]
+pub use disabled::CircomArtifacts;
+
+#[cfg(feature = "circom")]
+mod compat {
+    use anyhow::{Context, Result};
+    use ark_bn254::{Bn254, Fr};
+    use ark_circom::{read_zkey, CircomBuilder, CircomConfig, CircomReduction};
+    use ark_groth16::{Proof, ProvingKey};
+    use ark_std::rand::Rng;
+    use num_bigint::{BigInt, BigUint};
+    use std::{collections::BTreeMap, fs::File};
+
+    use super::CircomCircuitConfig;
+    use crate::zk_prover::msm::MsmEngine;
+
+    /// A loaded Circom circuit and its proving key
+    pub struct CircomArtifacts {
+        pub config: CircomCircuitConfig,
+        pub proving_key: ProvingKey<Bn254>,
+    }
+
+    impl CircomArtifacts {
+        /// Read the zkey and check it belongs to the r1cs
+        pub fn load(config: CircomCircuitConfig) -> Result<Self> {
+            let mut zkey = File::open(&config.zkey)
+                .with_context(|| format!("Failed to open {}", config.zkey.display()))?;
+            let (proving_key, matrices) = read_zkey(&mut zkey)
+                .with_context(|| format!("Invalid zkey {}", config.zkey.display()))?;
+
+            let circom = CircomConfig::<Bn254>::new(&config.wasm, &config.r1cs)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", config.name, e))?;
+            if circom.r1cs.num_inputs != matrices.num_instance_variables
+                || circom.r1cs.constraints.len() != matrices.num_constraints
+            {
+                return Err(anyhow::anyhow!("zkey {} was not made for {}", config.zkey.display(), config.r1cs.display()));
+            }
+
+            Ok(Self { config, proving_key })
+        }
+
+        /// Compute the witness for `inputs` (signal name -> values) and prove it; returns the
+        /// proof and the circuit's public signals
+        pub fn prove<R: Rng>(
+            &self,
+            engine: &MsmEngine,
+            inputs: &BTreeMap<String, Vec<Fr>>,
+            rng: &mut R,
+        ) -> Result<(Proof<Bn254>, Vec<Fr>)> {
+            // The witness generator is not thread-safe, so each proof instantiates its own
+            let circom = CircomConfig::<Bn254>::new(&self.config.wasm, &self.config.r1cs)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", self.config.name, e))?;
+            let mut builder = CircomBuilder::new(circom);
+            for (name, values) in inputs {
+                for value in values {
+                    builder.push_input(name, BigInt::from(BigUint::from(*value)));
+                }
+            }
+
+            let circuit = builder
+                .build()
+                .map_err(|e| anyhow::anyhow!("Witness generation for {} failed: {}", self.config.name, e))?;
+            let public_inputs = circuit.get_public_inputs().context("Circuit has no witness")?;
+            let proof = engine.prove_with_reduction::<CircomReduction, _, _>(circuit, &self.proving_key, rng)?;
+            Ok((proof, public_inputs))
+        }
+    }
+}
+
+#[cfg(not(feature = "circom"))]
+mod disabled {
+    use anyhow::Result;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{Proof, ProvingKey};
+    use ark_std::rand::Rng;
+    use std::collections::BTreeMap;
+
+    use super::CircomCircuitConfig;
+    use crate::zk_prover::msm::MsmEngine;
+
+    /// Circom circuits need the `circom` feature
+    pub struct CircomArtifacts {
+        pub config: CircomCircuitConfig,
+        pub proving_key: ProvingKey<Bn254>,
+    }
+
+    impl CircomArtifacts {
+        pub fn load(config: CircomCircuitConfig) -> Result<Self> {
+            Err(anyhow::anyhow!("Built without the `circom` feature, cannot load {}", config.name))
+        }
+
+        pub fn prove<R: Rng>(
+            &self,
+            _engine: &MsmEngine,
+            _inputs: &BTreeMap<String, Vec<Fr>>,
+            _rng: &mut R,
+        ) -> Result<(Proof<Bn254>, Vec<Fr>)> {
+            Err(anyhow::anyhow!("Built without the `circom` feature"))
+        }
+    }
+}
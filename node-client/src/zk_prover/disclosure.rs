@@ -0,0 +1,229 @@
+/*!
+ * Selective disclosure of threat metadata
+ * A threat proof extended with a salted commitment to the threat's metadata (chain, contract
+ * address, threat category). Each field is either disclosed, as a public input the proof binds to
+ * the committed value, or hidden behind the commitment, as chosen by the node's disclosure
+ * policy. Every policy shares one circuit and one key: a public flag per field says which
+ */
+
+use anyhow::Result;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+use super::mimc::{mimc_hash, mimc_hash_var};
+use super::profiler::region;
+use super::ThreatDetectionCircuit;
+
+/// Metadata fields: chain, contract address, threat category
+pub const METADATA_FIELDS: usize = 3;
+
+/// Domain of metadata commitments
+const METADATA_SEED: u64 = 6;
+
+/// Disclosure circuit constraint system version; bump whenever its constraints change.
+/// Version 1 embedded the threat circuit that opened 2 model chunks instead of 16
+pub const DISCLOSURE_CIRCUIT_VERSION: u32 = 2;
+
+/// Metadata fields made public in disclosure proofs; the rest stay committed but hidden
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisclosurePolicy {
+    pub chain: bool,
+    pub contract_address: bool,
+    pub threat_category: bool,
+}
+
+/// What a node knows about a threat beyond the transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreatMetadata {
+    pub chain_id: u64,
+    pub contract_address: Address,
+    /// At most 31 bytes, such as `phishing`
+    pub threat_category: String,
+}
+
+impl ThreatMetadata {
+    pub fn fields(&self) -> Result<[Fr; METADATA_FIELDS]> {
+        Ok([
+            Fr::from(self.chain_id),
+            address_field(&self.contract_address),
+            category_field(&self.threat_category)?,
+        ])
+    }
+
+    /// The fields `policy` makes public
+    pub fn disclose(&self, policy: &DisclosurePolicy) -> DisclosedMetadata {
+        DisclosedMetadata {
+            chain_id: policy.chain.then_some(self.chain_id),
+            contract_address: policy.contract_address.then_some(self.contract_address),
+            threat_category: policy.threat_category.then(|| self.threat_category.clone()),
+        }
+    }
+}
+
+/// Public metadata of a disclosure proof; `None` fields are hidden
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisclosedMetadata {
+    pub chain_id: Option<u64>,
+    pub contract_address: Option<Address>,
+    pub threat_category: Option<String>,
+}
+
+impl DisclosedMetadata {
+    pub fn fields(&self) -> Result<[Option<Fr>; METADATA_FIELDS]> {
+        Ok([
+            self.chain_id.map(Fr::from),
+            self.contract_address.as_ref().map(address_field),
+            self.threat_category.as_deref().map(category_field).transpose()?,
+        ])
+    }
+}
+
+fn address_field(address: &Address) -> Fr {
+    Fr::from_be_bytes_mod_order(address.as_bytes())
+}
+
+/// Categories are packed into one field element, so they must fit in 31 bytes; NUL bytes would
+/// make two categories pack alike
+fn category_field(category: &str) -> Result<Fr> {
+    if category.len() > 31 || category.contains('\0') {
+        return Err(anyhow::anyhow!("Threat category {:?} must be at most 31 bytes without NULs", category));
+    }
+    Ok(Fr::from_le_bytes_mod_order(category.as_bytes()))
+}
+
+/// Salted commitment to every metadata field
+pub fn metadata_commitment(fields: &[Fr; METADATA_FIELDS], salt: Fr) -> Fr {
+    let mut values = fields.to_vec();
+    values.push(salt);
+    mimc_hash(Fr::from(METADATA_SEED), &values)
+}
+
+/// Threat circuit plus the metadata commitment and its disclosed fields
+#[derive(Clone, Debug, Default)]
+pub struct DisclosureCircuit {
+    pub threat: ThreatDetectionCircuit,
+    pub metadata_commitment: Option<Fr>,
+    pub disclosed: Option<[Option<Fr>; METADATA_FIELDS]>,
+    // Private inputs (witness)
+    pub metadata: Option<[Fr; METADATA_FIELDS]>,
+    pub salt: Option<Fr>,
+}
+
+impl DisclosureCircuit {
+    pub fn new(threat: ThreatDetectionCircuit, metadata: [Fr; METADATA_FIELDS], salt: Fr, policy: &DisclosurePolicy) -> Self {
+        let disclose = [policy.chain, policy.contract_address, policy.threat_category];
+        Self {
+            threat,
+            metadata_commitment: Some(metadata_commitment(&metadata, salt)),
+            disclosed: Some(std::array::from_fn(|i| disclose[i].then_some(metadata[i]))),
+            metadata: Some(metadata),
+            salt: Some(salt),
+        }
+    }
+
+    /// Threat inputs, then the commitment and a flag and value per field (zero when hidden)
+    pub fn public_inputs(threat_inputs: &[Fr], metadata_commitment: Fr, disclosed: &[Option<Fr>; METADATA_FIELDS]) -> Vec<Fr> {
+        let mut inputs = threat_inputs.to_vec();
+        inputs.push(metadata_commitment);
+        for field in disclosed {
+            inputs.push(Fr::from(field.is_some() as u64));
+            inputs.push(field.unwrap_or_default());
+        }
+        inputs
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for DisclosureCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+
+        // The threat statement and its three public inputs come first
+        self.threat.generate_constraints(cs.clone())?;
+
+        let commitment = FpVar::new_input(cs.clone(), || self.metadata_commitment.ok_or_else(missing))?;
+        let mut disclosed = Vec::with_capacity(METADATA_FIELDS);
+        for i in 0..METADATA_FIELDS {
+            let field = self.disclosed.map(|disclosed| disclosed[i]);
+            let flag = Boolean::new_input(cs.clone(), || field.map(|field| field.is_some()).ok_or_else(missing))?;
+            let value = FpVar::new_input(cs.clone(), || field.map(Option::unwrap_or_default).ok_or_else(missing))?;
+            disclosed.push((flag, value));
+        }
+
+        region(&cs, "metadata disclosure", || {
+            let metadata = (0..METADATA_FIELDS)
+                .map(|i| FpVar::new_witness(cs.clone(), || self.metadata.map(|metadata| metadata[i]).ok_or_else(missing)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let salt = FpVar::new_witness(cs.clone(), || self.salt.ok_or_else(missing))?;
+
+            // Every field is committed, disclosed or not
+            let mut values = metadata.clone();
+            values.push(salt);
+            mimc_hash_var(FpVar::constant(Fr::from(METADATA_SEED)), &values)?.enforce_equal(&commitment)?;
+
+            // A disclosed value is the committed one; a hidden one is published as zero
+            for (field, (flag, value)) in metadata.iter().zip(&disclosed) {
+                flag.select(field, &FpVar::zero())?.enforce_equal(value)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::{fixed_point::encode_confidence, model::ModelCommitment, transaction_fields};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn threat_circuit() -> ThreatDetectionCircuit {
+        let model = ModelCommitment::from_bytes(&[0x42; 4096]).unwrap();
+        let fields = transaction_fields(b"disclosed transaction").unwrap();
+        let hash = mimc_hash(Fr::from(0u64), &fields);
+        ThreatDetectionCircuit {
+            threat_hash: Some(hash),
+            confidence_threshold: Some(encode_confidence(0.7).unwrap()),
+            model_commitment: Some(model.commitment()),
+            transaction_data: Some(fields),
+            ai_model_weights: Some(model.witness(&hash).unwrap()),
+            node_reputation: Some(encode_confidence(0.95).unwrap()),
+            detection_algorithm: Some(encode_confidence(0.9).unwrap()),
+        }
+    }
+
+    fn is_satisfied(circuit: DisclosureCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_category_disclosed_address_hidden() {
+        let metadata = ThreatMetadata {
+            chain_id: 2484,
+            contract_address: Address::repeat_byte(0xab),
+            threat_category: "phishing".to_string(),
+        };
+        let policy = DisclosurePolicy {
+            threat_category: true,
+            ..DisclosurePolicy::default()
+        };
+        let circuit = DisclosureCircuit::new(threat_circuit(), metadata.fields().unwrap(), Fr::from(99u64), &policy);
+
+        // Verifiers rebuild the flags and values from what was disclosed
+        let disclosed = metadata.disclose(&policy);
+        assert_eq!(disclosed.contract_address, None);
+        assert_eq!(circuit.disclosed, Some(disclosed.fields().unwrap()));
+        assert!(is_satisfied(circuit.clone()));
+
+        // A disclosed value must be the committed one
+        let mut lying = circuit;
+        lying.disclosed = Some(DisclosedMetadata { threat_category: Some("spam".to_string()), ..disclosed }.fields().unwrap());
+        assert!(!is_satisfied(lying));
+
+        assert!(category_field(&"x".repeat(32)).is_err());
+    }
+}
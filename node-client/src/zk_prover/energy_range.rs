@@ -0,0 +1,147 @@
+/*!
+ * Energy consumption range circuit
+ * Proves a node's average power draw over a reporting period stayed below a public threshold
+ * without revealing its total energy or uptime, which would leak hardware class and usage.
+ * The node and the period start are hashed into the commitment, so a proof cannot be replayed
+ * for another node or period
+ */
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use sha3::{Digest, Keccak256};
+use std::cmp::Ordering;
+
+use super::mimc::{mimc_hash, mimc_hash_var};
+
+/// Node id as a field element (keccak256 reduced mod r)
+pub fn node_field(node_id: &str) -> Fr {
+    Fr::from_le_bytes_mod_order(&Keccak256::digest(node_id.as_bytes()))
+}
+
+/// Commitment to the private usage figures and the node and period they belong to
+/// (MiMC hash keyed by the salt)
+pub fn energy_commitment(energy_mj: u64, uptime_secs: u64, node: Fr, period_start: u64, salt: Fr) -> Fr {
+    mimc_hash(salt, &[Fr::from(energy_mj), Fr::from(uptime_secs), node, Fr::from(period_start)])
+}
+
+/// Average power ≤ `max_avg_milliwatts` over `[period_start, period_end)`
+///
+/// Public: threshold, period length, period start, node, commitment. Witness: energy (mJ),
+/// uptime (s), salt.
+#[derive(Clone, Debug, Default)]
+pub struct EnergyRangeCircuit {
+    // Public inputs
+    pub max_avg_milliwatts: Option<u64>,
+    pub period_secs: Option<u64>,
+    pub period_start: Option<u64>,
+    pub node: Option<Fr>,
+    pub commitment: Option<Fr>,
+
+    // Private inputs (witness)
+    pub energy_mj: Option<u64>,
+    pub uptime_secs: Option<u64>,
+    pub salt: Option<Fr>,
+}
+
+impl EnergyRangeCircuit {
+    /// Circuit with every assignment filled in
+    pub fn new(
+        max_avg_milliwatts: u64,
+        (period_start, period_end): (u64, u64),
+        node: Fr,
+        energy_mj: u64,
+        uptime_secs: u64,
+        salt: Fr,
+    ) -> Self {
+        Self {
+            max_avg_milliwatts: Some(max_avg_milliwatts),
+            period_secs: Some(period_end.saturating_sub(period_start)),
+            period_start: Some(period_start),
+            node: Some(node),
+            commitment: Some(energy_commitment(energy_mj, uptime_secs, node, period_start, salt)),
+            energy_mj: Some(energy_mj),
+            uptime_secs: Some(uptime_secs),
+            salt: Some(salt),
+        }
+    }
+
+    /// Public inputs in allocation order
+    pub fn public_inputs(max_avg_milliwatts: u64, (period_start, period_end): (u64, u64), node: Fr, commitment: Fr) -> Vec<Fr> {
+        vec![
+            Fr::from(max_avg_milliwatts),
+            Fr::from(period_end.saturating_sub(period_start)),
+            Fr::from(period_start),
+            node,
+            commitment,
+        ]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for EnergyRangeCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+
+        let max_avg = FpVar::new_input(cs.clone(), || self.max_avg_milliwatts.map(Fr::from).ok_or_else(missing))?;
+        let period = FpVar::new_input(cs.clone(), || self.period_secs.map(Fr::from).ok_or_else(missing))?;
+        let period_start = FpVar::new_input(cs.clone(), || self.period_start.map(Fr::from).ok_or_else(missing))?;
+        let node = FpVar::new_input(cs.clone(), || self.node.ok_or_else(missing))?;
+        let commitment = FpVar::new_input(cs.clone(), || self.commitment.ok_or_else(missing))?;
+
+        let energy = FpVar::new_witness(cs.clone(), || self.energy_mj.map(Fr::from).ok_or_else(missing))?;
+        let uptime = FpVar::new_witness(cs.clone(), || self.uptime_secs.map(Fr::from).ok_or_else(missing))?;
+        let salt = FpVar::new_witness(cs, || self.salt.ok_or_else(missing))?;
+
+        // Constraint 1: witness matches the committed report for this node and period
+        mimc_hash_var(salt, &[energy.clone(), uptime.clone(), node, period_start])?.enforce_equal(&commitment)?;
+
+        // Constraint 2: the node cannot claim more uptime than the period has
+        uptime.enforce_cmp(&period, Ordering::Less, true)?;
+
+        // Constraint 3: energy / uptime <= threshold, without dividing
+        let budget = &max_avg * &uptime;
+        energy.enforce_cmp(&budget, Ordering::Less, true)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn is_satisfied(circuit: EnergyRangeCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_average_power_bound() {
+        let salt = Fr::from(0xdead_beef_u64);
+        let node = node_field("node-1");
+        // 40 W for 20 of 24 hours
+        let energy_mj = 40_000 * 72_000;
+
+        assert!(is_satisfied(EnergyRangeCircuit::new(50_000, (0, 86_400), node, energy_mj, 72_000, salt)));
+        assert!(!is_satisfied(EnergyRangeCircuit::new(35_000, (0, 86_400), node, energy_mj, 72_000, salt)));
+
+        // Inflating uptime past the period is rejected
+        assert!(!is_satisfied(EnergyRangeCircuit::new(35_000, (0, 86_400), node, energy_mj, 90_000, salt)));
+
+        // Witness must open the public commitment
+        let mut forged = EnergyRangeCircuit::new(50_000, (0, 86_400), node, energy_mj, 72_000, salt);
+        forged.commitment = Some(energy_commitment(energy_mj / 2, 72_000, node, 0, salt));
+        assert!(!is_satisfied(forged));
+
+        // So must the public node and period
+        let mut replayed = EnergyRangeCircuit::new(50_000, (0, 86_400), node, energy_mj, 72_000, salt);
+        replayed.node = Some(node_field("node-2"));
+        assert!(!is_satisfied(replayed));
+        let mut shifted = EnergyRangeCircuit::new(50_000, (0, 86_400), node, energy_mj, 72_000, salt);
+        shifted.period_start = Some(86_400);
+        assert!(!is_satisfied(shifted));
+    }
+}
@@ -0,0 +1,351 @@
+/*!
+ * Recursive epoch proofs
+ * Every detection of an epoch is folded into one Nova instance (BN254/Grumpkin cycle, via sonobe),
+ * then compressed by a Groth16 decider into a proof the on-chain verifier checks at constant gas,
+ * however many threats it covers. Each folding step re-checks the threat relation for one
+ * detection and chains its threat hash into a running digest, which starts from the proving
+ * node's id so a proof cannot be resubmitted under another node. Folding is behind the `folding`
+ * feature
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ethers::utils::keccak256;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::SynthesisError;
+use std::cmp::Ordering;
+
+use super::batch::BatchSlot;
+use super::fixed_point::enforce_fixed_point;
+use super::mimc::{mimc_hash, mimc_hash_var};
+use super::TX_DATA_CHUNKS;
+
+/// Folded state: epoch, confidence threshold, detections folded so far, threat hash digest
+pub const EPOCH_STATE_LEN: usize = 4;
+
+/// Per-step witness: `transaction_fields`, confidence, node reputation
+pub const EPOCH_EXTERNAL_INPUTS: usize = TX_DATA_CHUNKS + 3;
+
+/// Node id as a field element: keccak256 of the id reduced mod r, as the oracle computes it
+pub fn node_field(node_id: &str) -> Fr {
+    Fr::from_be_bytes_mod_order(&keccak256(node_id.as_bytes()))
+}
+
+/// State before the first detection of `epoch`; the digest starts at the node's id
+pub fn initial_state(epoch: u64, confidence_threshold: Fr, node: Fr) -> Vec<Fr> {
+    vec![Fr::from(epoch), confidence_threshold, Fr::from(0u64), node]
+}
+
+/// State after folding `threat_hashes` in order; what a verifier expects the proof to end in
+pub fn final_state(epoch: u64, confidence_threshold: Fr, node: Fr, threat_hashes: &[Fr]) -> Vec<Fr> {
+    let digest = threat_hashes
+        .iter()
+        .fold(node, |digest, hash| mimc_hash(Fr::from(epoch), &[digest, *hash]));
+    vec![Fr::from(epoch), confidence_threshold, Fr::from(threat_hashes.len() as u64), digest]
+}
+
+/// Step witness for one detection
+pub fn external_inputs(slot: &BatchSlot, node_reputation: Fr) -> Vec<Fr> {
+    let mut inputs = slot.transaction_data.clone();
+    inputs.extend([slot.confidence, node_reputation]);
+    inputs
+}
+
+/// One folding step outside the circuit
+pub fn step_native(z_i: &[Fr], external_inputs: &[Fr]) -> Vec<Fr> {
+    let threat_hash = mimc_hash(Fr::from(0u64), &external_inputs[..=TX_DATA_CHUNKS]);
+    let digest = mimc_hash(z_i[0], &[z_i[3], threat_hash]);
+    vec![z_i[0], z_i[1], z_i[2] + Fr::from(1u64), digest]
+}
+
+/// One folding step as constraints
+pub fn step_constraints(z_i: &[FpVar<Fr>], external_inputs: &[FpVar<Fr>]) -> Result<Vec<FpVar<Fr>>, SynthesisError> {
+    let (epoch, threshold, count, digest) = (&z_i[0], &z_i[1], &z_i[2], &z_i[3]);
+    let confidence = &external_inputs[TX_DATA_CHUNKS + 1];
+    let node_reputation = &external_inputs[TX_DATA_CHUNKS + 2];
+
+    // Constraint 1: Confidences are fixed-point values in [0, 1], so comparisons cannot wrap
+    for value in [threshold, confidence, node_reputation] {
+        enforce_fixed_point(value)?;
+    }
+
+    // Constraint 2: Detection clears the threshold
+    confidence.enforce_cmp(threshold, Ordering::Greater, false)?;
+
+    // Constraint 3: Node reputation must be high (> 0.8)
+    node_reputation.enforce_cmp(&FpVar::constant(Fr::from(800000u64)), Ordering::Greater, false)?;
+
+    // Constraint 4: the digest absorbs the MiMC hash of the transaction data
+    let threat_hash = mimc_hash_var(FpVar::constant(Fr::from(0u64)), &external_inputs[..=TX_DATA_CHUNKS])?;
+    let digest = mimc_hash_var(epoch.clone(), &[digest.clone(), threat_hash])?;
+
+    Ok(vec![epoch.clone(), threshold.clone(), count + FpVar::one(), digest])
+}
+
+/// Step count, initial and final state carried by decider calldata
+///
+/// Layout: 4-byte selector, then `i`, `z_0` and `z_i` as big-endian words.
+pub fn calldata_state(calldata: &[u8]) -> Result<(u64, Vec<Fr>, Vec<Fr>)> {
+    let words = calldata
+        .get(4..4 + 32 * (1 + 2 * EPOCH_STATE_LEN))
+        .context("Epoch proof calldata is truncated")?
+        .chunks(32)
+        .map(Fr::from_be_bytes_mod_order)
+        .collect::<Vec<_>>();
+
+    let [steps, 0, 0, 0] = words[0].into_bigint().0 else {
+        return Err(anyhow::anyhow!("Epoch proof step count out of range"));
+    };
+    Ok((
+        steps,
+        words[1..=EPOCH_STATE_LEN].to_vec(),
+        words[EPOCH_STATE_LEN + 1..].to_vec(),
+    ))
+}
+
+#[cfg(feature = "folding")]
+pub use nova::EpochKeys;
+#[cfg(not(feature = "folding"))]
+pub use disabled::EpochKeys;
+
+#[cfg(feature = "folding")]
+mod nova {
+    use anyhow::{Context, Result};
+    use ark_bn254::{constraints::GVar, Bn254, Fr, G1Projective as G1};
+    use ark_groth16::Groth16;
+    use ark_grumpkin::{constraints::GVar as GVar2, Projective as G2};
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::rand::RngCore;
+    use folding_schemes::{
+        commitment::{kzg::KZG, pedersen::Pedersen},
+        folding::nova::{
+            decider_eth::{prepare_calldata, Decider as DeciderEth},
+            Nova, PreprocessorParam,
+        },
+        frontend::FCircuit,
+        transcript::poseidon::poseidon_canonical_config,
+        Decider, Error, FoldingScheme,
+    };
+    use solidity_verifiers::{
+        utils::get_function_selector_for_nova_cyclefold_verifier,
+        verifiers::nova_cyclefold::get_decider_template_for_cyclefold_decider, NovaCycleFoldVerifierKey,
+    };
+
+    use super::{EPOCH_EXTERNAL_INPUTS, EPOCH_STATE_LEN};
+
+    /// The epoch step as a sonobe frontend circuit
+    #[derive(Clone, Copy, Debug)]
+    pub struct EpochStep;
+
+    impl FCircuit<Fr> for EpochStep {
+        type Params = ();
+
+        fn new(_params: Self::Params) -> Result<Self, Error> {
+            Ok(Self)
+        }
+
+        fn state_len(&self) -> usize {
+            EPOCH_STATE_LEN
+        }
+
+        fn external_inputs_len(&self) -> usize {
+            EPOCH_EXTERNAL_INPUTS
+        }
+
+        fn step_native(&self, _i: usize, z_i: Vec<Fr>, external_inputs: Vec<Fr>) -> Result<Vec<Fr>, Error> {
+            Ok(super::step_native(&z_i, &external_inputs))
+        }
+
+        fn generate_step_constraints(
+            &self,
+            _cs: ConstraintSystemRef<Fr>,
+            _i: usize,
+            z_i: Vec<FpVar<Fr>>,
+            external_inputs: Vec<FpVar<Fr>>,
+        ) -> Result<Vec<FpVar<Fr>>, SynthesisError> {
+            super::step_constraints(&z_i, &external_inputs)
+        }
+    }
+
+    type N = Nova<G1, GVar, G2, GVar2, EpochStep, KZG<'static, Bn254>, Pedersen<G2>, false>;
+    type D = DeciderEth<G1, GVar, G2, GVar2, EpochStep, KZG<'static, Bn254>, Pedersen<G2>, Groth16<Bn254>, N>;
+
+    type NovaParams = (
+        <N as FoldingScheme<G1, G2, EpochStep>>::ProverParam,
+        <N as FoldingScheme<G1, G2, EpochStep>>::VerifierParam,
+    );
+
+    /// Folding and decider keys
+    pub struct EpochKeys {
+        nova_params: NovaParams,
+        decider_pp: <D as Decider<G1, G2, EpochStep, N>>::ProverParam,
+        decider_vp: <D as Decider<G1, G2, EpochStep, N>>::VerifierParam,
+    }
+
+    impl EpochKeys {
+        /// Generate keys; the decider setup proves an empty instance, so this takes minutes
+        pub fn setup<R: RngCore>(rng: &mut R) -> Result<Self> {
+            let preprocess = PreprocessorParam::new(poseidon_canonical_config::<Fr>(), EpochStep);
+            let nova_params = N::preprocess(&mut *rng, &preprocess).map_err(folding_error)?;
+            let nova = N::init(&nova_params, EpochStep, super::initial_state(0, Fr::from(0u64), Fr::from(0u64)))
+                .map_err(folding_error)?;
+            let (decider_pp, decider_vp) = D::preprocess(&mut *rng, nova_params.clone(), nova).map_err(folding_error)?;
+
+            Ok(Self { nova_params, decider_pp, decider_vp })
+        }
+
+        /// Serialized keys; the verifier contract is derived from them, so they must outlive restarts
+        pub fn to_bytes(&self) -> Result<Vec<u8>> {
+            let mut bytes = Vec::new();
+            self.nova_params.0.serialize_compressed(&mut bytes)?;
+            self.nova_params.1.serialize_compressed(&mut bytes)?;
+            self.decider_pp.serialize_compressed(&mut bytes)?;
+            self.decider_vp.serialize_compressed(&mut bytes)?;
+            Ok(bytes)
+        }
+
+        pub fn from_bytes(mut bytes: &[u8]) -> Result<Self> {
+            let prover = CanonicalDeserialize::deserialize_compressed(&mut bytes)?;
+            let verifier = CanonicalDeserialize::deserialize_compressed(&mut bytes)?;
+            let decider_pp = CanonicalDeserialize::deserialize_compressed(&mut bytes)?;
+            let decider_vp = CanonicalDeserialize::deserialize_compressed(&mut bytes)?;
+            Ok(Self { nova_params: (prover, verifier), decider_pp, decider_vp })
+        }
+
+        /// Fold every step from `z_0` and compress the result into decider calldata
+        pub fn prove<R: RngCore>(&self, rng: &mut R, z_0: Vec<Fr>, steps: Vec<Vec<Fr>>) -> Result<Vec<u8>> {
+            let mut nova = N::init(&self.nova_params, EpochStep, z_0).map_err(folding_error)?;
+            for external_inputs in steps {
+                nova.prove_step(&mut *rng, external_inputs, None).map_err(folding_error)?;
+            }
+
+            let proof = D::prove(&mut *rng, self.decider_pp.clone(), nova.clone()).map_err(folding_error)?;
+            let verified = D::verify(
+                self.decider_vp.clone(),
+                nova.i,
+                nova.z_0.clone(),
+                nova.z_i.clone(),
+                &nova.U_i,
+                &nova.u_i,
+                &proof,
+            )
+            .map_err(folding_error)?;
+            if !verified {
+                return Err(anyhow::anyhow!("Epoch decider proof failed self-verification"));
+            }
+
+            prepare_calldata(
+                get_function_selector_for_nova_cyclefold_verifier(EPOCH_STATE_LEN * 2 + 1),
+                nova.i,
+                nova.z_0,
+                nova.z_i,
+                &nova.U_i,
+                &nova.u_i,
+                proof,
+            )
+            .map_err(folding_error)
+            .context("Failed to encode epoch proof calldata")
+        }
+
+        /// Solidity decider verifier for these keys
+        pub fn verifier_contract(&self) -> String {
+            let vk = NovaCycleFoldVerifierKey::from((self.decider_vp.clone(), EPOCH_STATE_LEN));
+            get_decider_template_for_cyclefold_decider(vk)
+        }
+    }
+
+    fn folding_error(e: Error) -> anyhow::Error {
+        anyhow::anyhow!("Folding failed: {}", e)
+    }
+}
+
+#[cfg(not(feature = "folding"))]
+mod disabled {
+    use anyhow::Result;
+    use ark_bn254::Fr;
+    use ark_std::rand::RngCore;
+
+    /// Epoch folding needs the `folding` feature
+    pub struct EpochKeys;
+
+    impl EpochKeys {
+        pub fn setup<R: RngCore>(_rng: &mut R) -> Result<Self> {
+            Err(anyhow::anyhow!("Epoch proofs need the `folding` feature"))
+        }
+
+        pub fn to_bytes(&self) -> Result<Vec<u8>> {
+            Err(anyhow::anyhow!("Epoch proofs need the `folding` feature"))
+        }
+
+        pub fn from_bytes(_bytes: &[u8]) -> Result<Self> {
+            Err(anyhow::anyhow!("Epoch proofs need the `folding` feature"))
+        }
+
+        pub fn prove<R: RngCore>(&self, _rng: &mut R, _z_0: Vec<Fr>, _steps: Vec<Vec<Fr>>) -> Result<Vec<u8>> {
+            Err(anyhow::anyhow!("Epoch proofs need the `folding` feature"))
+        }
+
+        pub fn verifier_contract(&self) -> String {
+            String::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::transaction_fields;
+    use ark_ff::BigInteger;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Run one step in a fresh constraint system; returns the next state if satisfied
+    fn step(z_i: &[Fr], external: &[Fr]) -> Option<Vec<Fr>> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let z_vars = z_i.iter().map(|v| FpVar::new_witness(cs.clone(), || Ok(*v)).unwrap()).collect::<Vec<_>>();
+        let ext_vars = external.iter().map(|v| FpVar::new_witness(cs.clone(), || Ok(*v)).unwrap()).collect::<Vec<_>>();
+        let next = step_constraints(&z_vars, &ext_vars).unwrap();
+        cs.is_satisfied().unwrap().then(|| next.iter().map(|v| v.value().unwrap()).collect())
+    }
+
+    #[test]
+    fn test_epoch_steps_match_native() {
+        let threshold = Fr::from(700_000u64);
+        let reputation = Fr::from(950_000u64);
+        let node = node_field("node-1");
+        let slots: Vec<BatchSlot> = [(&b"tx-a"[..], 850_000u64), (b"tx-b", 910_000), (b"tx-c", 720_000)]
+            .iter()
+            .map(|(data, confidence)| BatchSlot {
+                transaction_data: transaction_fields(data).unwrap(),
+                confidence: Fr::from(*confidence),
+            })
+            .collect();
+
+        let mut z = initial_state(7, threshold, node);
+        for slot in &slots {
+            let external = external_inputs(slot, reputation);
+            assert_eq!(external.len(), EPOCH_EXTERNAL_INPUTS);
+            let next = step(&z, &external).unwrap();
+            assert_eq!(next, step_native(&z, &external));
+            z = next;
+        }
+        let hashes: Vec<Fr> = slots.iter().map(BatchSlot::threat_hash).collect();
+        assert_eq!(z, final_state(7, threshold, node, &hashes));
+        assert_ne!(z, final_state(7, threshold, node_field("node-2"), &hashes));
+
+        // A low-confidence detection cannot be folded in
+        let weak = BatchSlot { confidence: Fr::from(600_000u64), ..slots[0].clone() };
+        assert!(step(&z, &external_inputs(&weak, reputation)).is_none());
+
+        // The verifier reads the statement back out of the calldata
+        let mut calldata = vec![0u8; 4];
+        for word in std::iter::once(Fr::from(3u64)).chain(initial_state(7, threshold, node)).chain(z.clone()) {
+            calldata.extend(word.into_bigint().to_bytes_be());
+        }
+        calldata.extend([0u8; 64]);
+        assert_eq!(calldata_state(&calldata).unwrap(), (3, initial_state(7, threshold, node), z));
+        assert!(calldata_state(&calldata[..40]).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+/*!
+ * Fixed-point confidence values
+ * Confidences, thresholds and reputations enter the circuits as integers scaled by
+ * `CONFIDENCE_SCALE`. Field elements wrap around, so the circuits range-check every such value
+ * to `[0, CONFIDENCE_SCALE]` before comparing it
+ */
+
+use anyhow::Result;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::SynthesisError;
+
+/// Fixed-point representation of 1.0
+pub const CONFIDENCE_SCALE: u64 = 1_000_000;
+
+/// Bits needed for `CONFIDENCE_SCALE`
+pub(crate) const CONFIDENCE_BITS: usize = 20;
+
+/// Encode a value in `[0.0, 1.0]`
+pub fn encode_confidence(value: f64) -> Result<Fr> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(anyhow::anyhow!("Confidence {} is outside [0, 1]", value));
+    }
+    Ok(Fr::from((value * CONFIDENCE_SCALE as f64).round() as u64))
+}
+
+/// Scaled value of `field`, if it is a valid fixed-point encoding
+pub fn validate_fixed_point(field: &Fr) -> Result<u64> {
+    let value = field.into_bigint();
+    match value.0 {
+        [low, 0, 0, 0] if low <= CONFIDENCE_SCALE => Ok(low),
+        _ => Err(anyhow::anyhow!("{} is not a fixed-point value in [0, {}]", field, CONFIDENCE_SCALE)),
+    }
+}
+
+/// Constrain `value` to `[0, CONFIDENCE_SCALE]`: both it and `CONFIDENCE_SCALE - value` must fit
+/// in `CONFIDENCE_BITS` bits, which a wrapped-around value cannot
+pub fn enforce_fixed_point(value: &FpVar<Fr>) -> Result<(), SynthesisError> {
+    enforce_bit_length(value)?;
+    enforce_bit_length(&(FpVar::constant(Fr::from(CONFIDENCE_SCALE)) - value))
+}
+
+fn enforce_bit_length(value: &FpVar<Fr>) -> Result<(), SynthesisError> {
+    let cs = value.cs();
+    let assigned = value.value().ok().map(|value| value.into_bigint());
+    let bits = (0..CONFIDENCE_BITS)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                assigned.map(|value| value.get_bit(i)).ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn in_range(value: Fr) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let var = FpVar::new_witness(cs.clone(), || Ok(value)).unwrap();
+        enforce_fixed_point(&var).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_fixed_point_range() {
+        assert!(in_range(Fr::from(0u64)));
+        assert!(in_range(Fr::from(CONFIDENCE_SCALE)));
+        assert!(!in_range(Fr::from(CONFIDENCE_SCALE + 1)));
+        // Negative confidence wraps to just below the modulus
+        assert!(!in_range(-Fr::from(1u64)));
+        // Above 2^20 but still "positive" for is_cmp
+        assert!(!in_range(Fr::from(1u64 << 40)));
+
+        assert_eq!(validate_fixed_point(&encode_confidence(0.85).unwrap()).unwrap(), 850_000);
+        assert!(encode_confidence(1.2).is_err());
+        assert!(encode_confidence(f64::NAN).is_err());
+        assert!(validate_fixed_point(&-Fr::from(1u64)).is_err());
+    }
+}
@@ -0,0 +1,718 @@
+/*!
+ * Halo2 proving backend
+ * A PLONKish threat circuit over KZG on BN254. Keys are derived deterministically from a
+ * published universal SRS, never a local one whose toxic waste the node would know, so circuits
+ * can change without a new setup and every node derives the same keys. Range checks are byte
+ * lookups instead of bit decompositions. Only the threat circuit is ported; it proves the same
+ * statement over the same public inputs as the R1CS one. Behind the `halo2` feature
+ */
+
+/// Threat circuit size, as log2 of its rows; the model openings take ~47k and the transaction
+/// hash ~3.7k of the 64k, nearly all of it MiMC rounds
+pub const THREAT_CIRCUIT_K: u32 = 16;
+
+#[cfg(feature = "halo2")]
+pub use enabled::{Halo2Keys, Halo2Srs};
+#[cfg(not(feature = "halo2"))]
+pub use disabled::{Halo2Keys, Halo2Srs};
+
+#[cfg(feature = "halo2")]
+mod enabled {
+    use anyhow::{Context, Result};
+    use ark_bn254::Fr as ArkFr;
+    use ark_ff::{BigInteger, PrimeField as _};
+    use halo2_axiom::{
+        circuit::{Cell, Layouter, Region, SimpleFloorPlanner, Value},
+        halo2curves::{
+            bn256::{Bn256, Fr, G1Affine},
+            ff::{Field, PrimeField},
+        },
+        plonk::{
+            create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem, Error, Fixed,
+            Instance, ProvingKey, Selector, TableColumn,
+        },
+        poly::{
+            commitment::{Params, ParamsProver},
+            kzg::{
+                commitment::{KZGCommitmentScheme, ParamsKZG},
+                multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                strategy::SingleStrategy,
+            },
+            Rotation,
+        },
+        transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
+        SerdeFormat,
+    };
+    use std::sync::OnceLock;
+
+    use super::THREAT_CIRCUIT_K;
+    use crate::zk_prover::{
+        fixed_point::{CONFIDENCE_BITS, CONFIDENCE_SCALE},
+        merkle::NODE_SEED,
+        mimc::mimc_constants,
+        model::{COMMITMENT_SEED, LEAF_SEED, MODEL_SAMPLES, MODEL_TREE_DEPTH, SAMPLE_BITS, SAMPLE_SEED},
+        rng::prover_rng,
+        ThreatDetectionCircuit, TX_DATA_CHUNKS,
+    };
+
+    /// Node reputation a threat proof needs (0.8), as in the R1CS circuit
+    const MIN_REPUTATION: u64 = 800_000;
+
+    /// 64-bit limbs of a field element
+    const HASH_LIMBS: usize = 4;
+
+    /// Most significant limb of the field modulus; a smaller top limb keeps limbs canonical
+    const MODULUS_TOP_LIMB: u64 = <ArkFr as ark_ff::PrimeField>::MODULUS.0[HASH_LIMBS - 1];
+
+    /// KZG structured reference string shared by every Halo2 circuit up to its size
+    pub struct Halo2Srs(ParamsKZG<Bn256>);
+
+    impl Halo2Srs {
+        /// Local SRS with known toxic waste, for tests only
+        #[cfg(test)]
+        pub fn setup(k: u32) -> Result<Self> {
+            Ok(Self(ParamsKZG::setup(k, prover_rng())))
+        }
+
+        /// Parse an SRS in halo2's `ParamsKZG` format
+        pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            Ok(Self(ParamsKZG::read(&mut &bytes[..]).context("Invalid KZG SRS")?))
+        }
+
+        /// Derive the threat circuit's keys; deterministic, so keys are not stored. A larger SRS
+        /// is cut down to the circuit size
+        pub fn keygen(mut self) -> Result<Halo2Keys> {
+            if self.0.k() < THREAT_CIRCUIT_K {
+                return Err(anyhow::anyhow!("KZG SRS has 2^{} rows, the threat circuit needs 2^{}", self.0.k(), THREAT_CIRCUIT_K));
+            }
+            self.0.downsize(THREAT_CIRCUIT_K);
+
+            let blank = ThreatCircuit::default();
+            let vk = keygen_vk(&self.0, &blank).map_err(|e| anyhow::anyhow!("Halo2 key generation failed: {:?}", e))?;
+            let pk = keygen_pk(&self.0, vk, &blank).map_err(|e| anyhow::anyhow!("Halo2 key generation failed: {:?}", e))?;
+            Ok(Halo2Keys { params: self.0, pk })
+        }
+    }
+
+    /// Threat circuit keys derived from the SRS
+    pub struct Halo2Keys {
+        params: ParamsKZG<Bn256>,
+        pk: ProvingKey<G1Affine>,
+    }
+
+    impl Halo2Keys {
+        pub fn prove(&self, circuit: &ThreatDetectionCircuit) -> Result<Vec<u8>> {
+            let public_inputs = [circuit.threat_hash, circuit.confidence_threshold, circuit.model_commitment]
+                .into_iter()
+                .map(|input| input.map(|input| to_halo2(&input)))
+                .collect::<Option<Vec<_>>>()
+                .context("Threat circuit is missing its public inputs")?;
+            let circuit = ThreatCircuit::from_witness(circuit)?;
+
+            let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+            create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, Challenge255<G1Affine>, _, _, _>(
+                &self.params,
+                &self.pk,
+                &[circuit],
+                &[&[&public_inputs]],
+                prover_rng(),
+                &mut transcript,
+            )
+            .map_err(|e| anyhow::anyhow!("Halo2 proving failed: {:?}", e))?;
+            Ok(transcript.finalize())
+        }
+
+        pub fn verify(&self, public_inputs: &[ArkFr], proof: &[u8]) -> Result<bool> {
+            let public_inputs: Vec<Fr> = public_inputs.iter().map(to_halo2).collect();
+            let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+            let verified = verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+                self.params.verifier_params(),
+                self.pk.get_vk(),
+                SingleStrategy::new(&self.params),
+                &[&[&public_inputs]],
+                &mut transcript,
+            );
+            Ok(verified.is_ok())
+        }
+
+        pub fn verifying_key_bytes(&self) -> Result<Vec<u8>> {
+            Ok(self.pk.get_vk().to_bytes(SerdeFormat::RawBytes))
+        }
+    }
+
+    /// Both curves' scalar fields are the BN254 scalar field
+    fn to_halo2(value: &ArkFr) -> Fr {
+        let mut repr = [0u8; 32];
+        repr.copy_from_slice(&value.into_bigint().to_bytes_le());
+        Option::from(Fr::from_repr(repr)).expect("BN254 scalars are canonical in both libraries")
+    }
+
+    fn limbs(value: &Fr) -> [u64; HASH_LIMBS] {
+        let repr = value.to_repr();
+        std::array::from_fn(|i| u64::from_le_bytes(repr[i * 8..(i + 1) * 8].try_into().unwrap()))
+    }
+
+    fn round_constants() -> &'static [Fr] {
+        static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+        CONSTANTS.get_or_init(|| mimc_constants().iter().map(to_halo2).collect())
+    }
+
+    #[derive(Clone, Debug)]
+    struct Opening {
+        chunk: Value<Fr>,
+        index: Value<Fr>,
+        siblings: Vec<Value<Fr>>,
+    }
+
+    /// `ThreatDetectionCircuit` with Halo2 witnesses
+    #[derive(Clone, Debug)]
+    struct ThreatCircuit {
+        threat_hash: Value<Fr>,
+        threshold: Value<Fr>,
+        model_commitment: Value<Fr>,
+        detection: Value<Fr>,
+        reputation: Value<Fr>,
+        transaction: Vec<Value<Fr>>,
+        root: Value<Fr>,
+        chunk_count: Value<Fr>,
+        openings: Vec<Opening>,
+    }
+
+    impl Default for ThreatCircuit {
+        fn default() -> Self {
+            let opening = Opening {
+                chunk: Value::unknown(),
+                index: Value::unknown(),
+                siblings: vec![Value::unknown(); MODEL_TREE_DEPTH],
+            };
+            Self {
+                threat_hash: Value::unknown(),
+                threshold: Value::unknown(),
+                model_commitment: Value::unknown(),
+                detection: Value::unknown(),
+                reputation: Value::unknown(),
+                transaction: vec![Value::unknown(); TX_DATA_CHUNKS + 1],
+                root: Value::unknown(),
+                chunk_count: Value::unknown(),
+                openings: vec![opening; MODEL_SAMPLES],
+            }
+        }
+    }
+
+    impl ThreatCircuit {
+        fn from_witness(circuit: &ThreatDetectionCircuit) -> Result<Self> {
+            let known = |value: Option<ArkFr>, name: &str| {
+                value
+                    .map(|value| Value::known(to_halo2(&value)))
+                    .with_context(|| format!("Threat circuit is missing its {}", name))
+            };
+            let transaction = circuit.transaction_data.as_ref().context("Threat circuit is missing its transaction data")?;
+            let model = circuit.ai_model_weights.as_ref().context("Threat circuit is missing its model openings")?;
+            if transaction.len() != TX_DATA_CHUNKS + 1 || model.openings.len() != MODEL_SAMPLES {
+                return Err(anyhow::anyhow!("Threat circuit witness has the wrong shape"));
+            }
+
+            Ok(Self {
+                threat_hash: known(circuit.threat_hash, "threat hash")?,
+                threshold: known(circuit.confidence_threshold, "confidence threshold")?,
+                model_commitment: known(circuit.model_commitment, "model commitment")?,
+                detection: known(circuit.detection_algorithm, "detection result")?,
+                reputation: known(circuit.node_reputation, "node reputation")?,
+                transaction: transaction.iter().map(|field| Value::known(to_halo2(field))).collect(),
+                root: Value::known(to_halo2(&model.root)),
+                chunk_count: Value::known(Fr::from(model.chunk_count)),
+                openings: model
+                    .openings
+                    .iter()
+                    .map(|opening| Opening {
+                        chunk: Value::known(to_halo2(&opening.chunk)),
+                        index: Value::known(Fr::from(opening.path.index as u64)),
+                        siblings: opening.path.siblings.iter().map(|sibling| Value::known(to_halo2(sibling))).collect(),
+                    })
+                    .collect(),
+            })
+        }
+
+        /// Lay out the statement; returns the public input cells
+        fn assign(&self, rows: &mut Rows<'_, '_>) -> Result<[Cell; 3], Error> {
+            let threat_hash = rows.witness(self.threat_hash);
+            let threshold = rows.witness(self.threshold);
+            let model_commitment = rows.witness(self.model_commitment);
+            let detection = rows.witness(self.detection);
+            let reputation = rows.witness(self.reputation);
+
+            // Confidences are fixed-point values in [0, 1], so the comparisons cannot wrap
+            for value in [detection, threshold, reputation] {
+                rows.range_check(value, CONFIDENCE_BITS)?;
+                let complement = rows.affine(value, -Fr::ONE, Fr::from(CONFIDENCE_SCALE))?;
+                rows.range_check(complement, CONFIDENCE_BITS)?;
+            }
+
+            // Detection above threshold and reputation above 0.8: each difference less one is small
+            let margin = rows.linear(detection, Fr::ONE, threshold, -Fr::ONE, -Fr::ONE)?;
+            rows.range_check(margin, CONFIDENCE_BITS)?;
+            let reputation_margin = rows.affine(reputation, Fr::ONE, -Fr::from(MIN_REPUTATION + 1))?;
+            rows.range_check(reputation_margin, CONFIDENCE_BITS)?;
+
+            // Threat hash is the MiMC hash of the transaction data
+            let transaction: Vec<Var> = self.transaction.iter().map(|field| rows.witness(*field)).collect();
+            let computed_hash = rows.mimc_hash(0, &transaction)?;
+            rows.assert_equal(computed_hash, threat_hash);
+
+            self.assign_model_openings(rows, threat_hash, model_commitment)?;
+            Ok([threat_hash.cell, threshold.cell, model_commitment.cell])
+        }
+
+        /// `enforce_model_openings`: chunks selected by the threat hash open the model commitment
+        fn assign_model_openings(&self, rows: &mut Rows<'_, '_>, threat_hash: Var, model_commitment: Var) -> Result<(), Error> {
+            let root = rows.witness(self.root);
+            let chunk_count = rows.witness(self.chunk_count);
+            let commitment = rows.mimc_hash(COMMITMENT_SEED, &[root, chunk_count])?;
+            rows.assert_equal(commitment, model_commitment);
+            // Small count, so the index comparison below cannot wrap
+            rows.range_check(chunk_count, MODEL_TREE_DEPTH + 1)?;
+
+            let count_value = self.chunk_count.map(|count| limbs(&count)[0]);
+            for (k, opening) in self.openings.iter().enumerate() {
+                // Each sample is picked by the low limb of its own hash of the threat hash
+                let k = rows.constant(Fr::from(k as u64))?;
+                let sample = rows.mimc_hash(SAMPLE_SEED, &[threat_hash, k])?;
+                let low_limb = rows.canonical_limbs(sample)?[0];
+
+                // index = limb mod count: limb = quotient * count + index with index < count
+                let quotient = rows.witness(
+                    sample.value.zip(count_value).map(|(sample, count)| Fr::from(limbs(&sample)[0].checked_div(count).unwrap_or(0))),
+                );
+                rows.range_check(quotient, SAMPLE_BITS)?;
+                let (index, index_bits) = rows.decompose(opening.index, MODEL_TREE_DEPTH)?;
+                let product = rows.mul(quotient, chunk_count)?;
+                let limb = rows.linear(product, Fr::ONE, index, Fr::ONE, Fr::ZERO)?;
+                rows.assert_equal(limb, low_limb);
+                let slack = rows.linear(chunk_count, Fr::ONE, index, -Fr::ONE, -Fr::ONE)?;
+                rows.range_check(slack, MODEL_TREE_DEPTH + 1)?;
+
+                // The opened chunk hashes up to the committed root
+                let chunk = rows.witness(opening.chunk);
+                let mut node = rows.mimc_hash(LEAF_SEED, &[chunk])?;
+                for (is_right, sibling) in index_bits.iter().zip(&opening.siblings) {
+                    let sibling = rows.witness(*sibling);
+                    let (left, right) = rows.swap(*is_right, node, sibling)?;
+                    node = rows.mimc_hash(NODE_SEED, &[left, right])?;
+                }
+                rows.assert_equal(node, root);
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct ThreatConfig {
+        advice: [Column<Advice>; 5],
+        instance: Column<Instance>,
+        round_constant: Column<Fixed>,
+        /// q_l, q_r, q_m, q_c of the arithmetic gate
+        coefficients: [Column<Fixed>; 4],
+        range_bits: Column<Fixed>,
+        mimc_round: Selector,
+        mimc_output: Selector,
+        arithmetic: Selector,
+        bit: Selector,
+        swap: Selector,
+        range: Selector,
+        /// (bits, value) for every value below 2^bits, bits 1..=8, and (0, 0)
+        byte_table: [TableColumn; 2],
+    }
+
+    impl Circuit<Fr> for ThreatCircuit {
+        type Config = ThreatConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> ThreatConfig {
+            let advice = [(); 5].map(|_| meta.advice_column());
+            for column in advice {
+                meta.enable_equality(column);
+            }
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let config = ThreatConfig {
+                advice,
+                instance,
+                round_constant: meta.fixed_column(),
+                coefficients: [(); 4].map(|_| meta.fixed_column()),
+                range_bits: meta.fixed_column(),
+                mimc_round: meta.selector(),
+                mimc_output: meta.selector(),
+                arithmetic: meta.selector(),
+                bit: meta.selector(),
+                swap: meta.selector(),
+                range: meta.complex_selector(),
+                byte_table: [meta.lookup_table_column(), meta.lookup_table_column()],
+            };
+            let [a, b, c, d, e] = advice;
+
+            // x' = (x + key + c)^5 with the square in its own column; the key carries down
+            meta.create_gate("mimc round", |m| {
+                let s = m.query_selector(config.mimc_round);
+                let key = m.query_advice(b, Rotation::cur());
+                let square = m.query_advice(c, Rotation::cur());
+                let t = m.query_advice(a, Rotation::cur()) + key.clone() + m.query_fixed(config.round_constant, Rotation::cur());
+                vec![
+                    s.clone() * (square.clone() - t.clone() * t.clone()),
+                    s.clone() * (m.query_advice(a, Rotation::next()) - square.clone() * square * t),
+                    s * (m.query_advice(b, Rotation::next()) - key),
+                ]
+            });
+
+            // Miyaguchi-Preneel: state' = mimc(value, key) + value + key = x + 2 key + value
+            meta.create_gate("mimc output", |m| {
+                let s = m.query_selector(config.mimc_output);
+                let x = m.query_advice(a, Rotation::cur());
+                let key = m.query_advice(b, Rotation::cur());
+                let value = m.query_advice(c, Rotation::cur());
+                vec![s * (m.query_advice(d, Rotation::cur()) - x - key.clone() - key - value)]
+            });
+
+            // q_l a + q_r b + q_m a b + q_c = c
+            meta.create_gate("arithmetic", |m| {
+                let s = m.query_selector(config.arithmetic);
+                let [q_l, q_r, q_m, q_c] = config.coefficients.map(|column| m.query_fixed(column, Rotation::cur()));
+                let lhs = m.query_advice(a, Rotation::cur());
+                let rhs = m.query_advice(b, Rotation::cur());
+                vec![s * (q_l * lhs.clone() + q_r * rhs.clone() + q_m * lhs * rhs + q_c - m.query_advice(c, Rotation::cur()))]
+            });
+
+            // z = bit + 2 z' with a boolean bit
+            meta.create_gate("bit", |m| {
+                let s = m.query_selector(config.bit);
+                let bit = m.query_advice(b, Rotation::cur());
+                vec![
+                    s.clone() * (m.query_advice(a, Rotation::cur()) - bit.clone() - m.query_advice(a, Rotation::next()) * Fr::from(2)),
+                    s * bit.clone() * (bit - halo2_axiom::plonk::Expression::Constant(Fr::ONE)),
+                ]
+            });
+
+            // (left, right) = (node, sibling), swapped when the bit is set
+            meta.create_gate("swap", |m| {
+                let s = m.query_selector(config.swap);
+                let bit = m.query_advice(a, Rotation::cur());
+                let node = m.query_advice(b, Rotation::cur());
+                let sibling = m.query_advice(c, Rotation::cur());
+                vec![
+                    s.clone() * (m.query_advice(d, Rotation::cur()) - node.clone() - bit.clone() * (sibling.clone() - node.clone())),
+                    s * (m.query_advice(e, Rotation::cur()) - sibling.clone() - bit * (node - sibling)),
+                ]
+            });
+
+            // Running sum z = byte + 256 z' with the byte below 2^bits of its row
+            meta.lookup("byte range", |m| {
+                let s = m.query_selector(config.range);
+                let byte = m.query_advice(a, Rotation::cur()) - m.query_advice(a, Rotation::next()) * Fr::from(256);
+                vec![
+                    (s.clone() * m.query_fixed(config.range_bits, Rotation::cur()), config.byte_table[0]),
+                    (s * byte, config.byte_table[1]),
+                ]
+            });
+
+            config
+        }
+
+        fn synthesize(&self, config: ThreatConfig, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+            layouter.assign_table(
+                || "bytes",
+                |mut table| {
+                    let entries = (1..=8u64).flat_map(|bits| (0..1u64 << bits).map(move |value| (bits, value)));
+                    for (row, (bits, value)) in std::iter::once((0, 0)).chain(entries).enumerate() {
+                        table.assign_cell(|| "bits", config.byte_table[0], row, || Value::known(Fr::from(bits)))?;
+                        table.assign_cell(|| "value", config.byte_table[1], row, || Value::known(Fr::from(value)))?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            let public_inputs = layouter.assign_region(
+                || "threat",
+                |region| {
+                    let mut rows = Rows { config: &config, region, offset: 0 };
+                    self.assign(&mut rows)
+                },
+            )?;
+            for (row, cell) in public_inputs.into_iter().enumerate() {
+                layouter.constrain_instance(cell, config.instance, row);
+            }
+            Ok(())
+        }
+    }
+
+    /// Assigned advice cell
+    #[derive(Clone, Copy, Debug)]
+    struct Var {
+        cell: Cell,
+        value: Value<Fr>,
+    }
+
+    /// Row allocator over the circuit's single region
+    struct Rows<'a, 'r> {
+        config: &'a ThreatConfig,
+        region: Region<'r, Fr>,
+        offset: usize,
+    }
+
+    impl Rows<'_, '_> {
+        fn take(&mut self, rows: usize) -> usize {
+            let start = self.offset;
+            self.offset += rows;
+            start
+        }
+
+        fn assign(&mut self, column: usize, row: usize, value: Value<Fr>) -> Var {
+            let cell = self.region.assign_advice(self.config.advice[column], row, value).cell();
+            Var { cell, value }
+        }
+
+        fn copy(&mut self, var: Var, column: usize, row: usize) -> Var {
+            let copy = self.assign(column, row, var.value);
+            self.region.constrain_equal(copy.cell, var.cell);
+            copy
+        }
+
+        fn assert_equal(&mut self, left: Var, right: Var) {
+            self.region.constrain_equal(left.cell, right.cell);
+        }
+
+        fn witness(&mut self, value: Value<Fr>) -> Var {
+            let row = self.take(1);
+            self.assign(0, row, value)
+        }
+
+        fn constant(&mut self, value: Fr) -> Result<Var, Error> {
+            let var = self.witness(Value::known(value));
+            self.region.constrain_constant(var.cell, value)?;
+            Ok(var)
+        }
+
+        /// q_l a + q_r b + q_m a b + q_c
+        fn gate(&mut self, a: Var, b: Var, coefficients: [Fr; 4]) -> Result<Var, Error> {
+            let row = self.take(1);
+            self.config.arithmetic.enable(&mut self.region, row)?;
+            self.copy(a, 0, row);
+            self.copy(b, 1, row);
+            for (column, coefficient) in self.config.coefficients.into_iter().zip(coefficients) {
+                self.region.assign_fixed(column, row, coefficient);
+            }
+            let [q_l, q_r, q_m, q_c] = coefficients;
+            let value = a.value.zip(b.value).map(|(a, b)| q_l * a + q_r * b + q_m * a * b + q_c);
+            Ok(self.assign(2, row, value))
+        }
+
+        fn linear(&mut self, a: Var, a_scale: Fr, b: Var, b_scale: Fr, constant: Fr) -> Result<Var, Error> {
+            self.gate(a, b, [a_scale, b_scale, Fr::ZERO, constant])
+        }
+
+        fn affine(&mut self, a: Var, scale: Fr, constant: Fr) -> Result<Var, Error> {
+            self.gate(a, a, [scale, Fr::ZERO, Fr::ZERO, constant])
+        }
+
+        fn mul(&mut self, a: Var, b: Var) -> Result<Var, Error> {
+            self.gate(a, b, [Fr::ZERO, Fr::ZERO, Fr::ONE, Fr::ZERO])
+        }
+
+        /// `mimc_hash` block: mimc(value, key) + value + key
+        fn mimc_block(&mut self, value: Var, key: Var) -> Result<Var, Error> {
+            let constants = round_constants();
+            let start = self.take(constants.len() + 1);
+            let mut x = self.copy(value, 0, start).value;
+            self.copy(key, 1, start);
+
+            for (i, constant) in constants.iter().enumerate() {
+                let row = start + i;
+                self.config.mimc_round.enable(&mut self.region, row)?;
+                self.region.assign_fixed(self.config.round_constant, row, *constant);
+                let t = x + key.value + Value::known(*constant);
+                let square = t * t;
+                self.assign(2, row, square);
+                x = square * square * t;
+                self.assign(0, row + 1, x);
+                self.assign(1, row + 1, key.value);
+            }
+
+            let row = start + constants.len();
+            self.config.mimc_output.enable(&mut self.region, row)?;
+            self.copy(value, 2, row);
+            Ok(self.assign(3, row, x + key.value + key.value + value.value))
+        }
+
+        fn mimc_hash(&mut self, seed: u64, values: &[Var]) -> Result<Var, Error> {
+            let mut state = self.constant(Fr::from(seed))?;
+            for value in values {
+                state = self.mimc_block(*value, state)?;
+            }
+            Ok(state)
+        }
+
+        /// Constrain `var` below 2^bits, a byte per row
+        fn range_check(&mut self, var: Var, bits: usize) -> Result<(), Error> {
+            let bytes = bits.div_ceil(8);
+            let start = self.take(bytes + 1);
+            let mut z = self.copy(var, 0, start);
+            let shift = Fr::from(256).invert().unwrap();
+
+            for i in 0..bytes {
+                let row = start + i;
+                self.config.range.enable(&mut self.region, row)?;
+                self.region.assign_fixed(self.config.range_bits, row, Fr::from((bits - 8 * i).min(8) as u64));
+                let rest = z.value.map(|value| (value - Fr::from(u64::from(value.to_repr()[0]))) * shift);
+                z = self.assign(0, row + 1, rest);
+            }
+            self.region.constrain_constant(z.cell, Fr::ZERO)
+        }
+
+        /// Canonical 64-bit limbs of `var`, least significant first (a top limb equal to the
+        /// modulus' is rejected, which a hash output hits with negligible probability)
+        fn canonical_limbs(&mut self, var: Var) -> Result<Vec<Var>, Error> {
+            let limb_values = var.value.map(|value| limbs(&value));
+            let mut limb_vars = Vec::with_capacity(HASH_LIMBS);
+            for i in 0..HASH_LIMBS {
+                let limb = self.witness(limb_values.map(|limbs| Fr::from(limbs[i])));
+                self.range_check(limb, SAMPLE_BITS)?;
+                limb_vars.push(limb);
+            }
+            let top_margin = self.affine(limb_vars[HASH_LIMBS - 1], -Fr::ONE, Fr::from(MODULUS_TOP_LIMB - 1))?;
+            self.range_check(top_margin, SAMPLE_BITS)?;
+            let mut recomposed = limb_vars[0];
+            for (i, limb) in limb_vars.iter().enumerate().skip(1) {
+                let weight = Fr::from(2).pow_vartime([(i * SAMPLE_BITS) as u64]);
+                recomposed = self.linear(recomposed, Fr::ONE, *limb, weight, Fr::ZERO)?;
+            }
+            self.assert_equal(recomposed, var);
+            Ok(limb_vars)
+        }
+
+        /// Witness `value` with its `bits` low bits, least significant first; the rest must be zero
+        fn decompose(&mut self, value: Value<Fr>, bits: usize) -> Result<(Var, Vec<Var>), Error> {
+            let start = self.take(bits + 1);
+            let var = self.assign(0, start, value);
+            let half = Fr::from(2).invert().unwrap();
+
+            let mut z = var;
+            let mut bit_vars = Vec::with_capacity(bits);
+            for i in 0..bits {
+                let row = start + i;
+                self.config.bit.enable(&mut self.region, row)?;
+                let bit = z.value.map(|z| Fr::from(u64::from(z.to_repr()[0] & 1)));
+                bit_vars.push(self.assign(1, row, bit));
+                z = self.assign(0, row + 1, (z.value - bit) * Value::known(half));
+            }
+            self.region.constrain_constant(z.cell, Fr::ZERO)?;
+            Ok((var, bit_vars))
+        }
+
+        fn swap(&mut self, bit: Var, node: Var, sibling: Var) -> Result<(Var, Var), Error> {
+            let row = self.take(1);
+            self.config.swap.enable(&mut self.region, row)?;
+            self.copy(bit, 0, row);
+            self.copy(node, 1, row);
+            self.copy(sibling, 2, row);
+            let left = node.value + bit.value * (sibling.value - node.value);
+            let right = sibling.value + bit.value * (node.value - sibling.value);
+            Ok((self.assign(3, row, left), self.assign(4, row, right)))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::zk_prover::{fixed_point::encode_confidence, mimc::mimc_hash, model::ModelCommitment, transaction_fields};
+        use halo2_axiom::dev::MockProver;
+
+        fn threat_circuit(detection: f64) -> (ThreatDetectionCircuit, Vec<ArkFr>) {
+            let model = ModelCommitment::from_bytes(&[0x42; 4096]).unwrap();
+            let fields = transaction_fields(b"halo2 threat").unwrap();
+            let hash = mimc_hash(ArkFr::from(0u64), &fields);
+            let threshold = encode_confidence(0.7).unwrap();
+            let circuit = ThreatDetectionCircuit {
+                threat_hash: Some(hash),
+                confidence_threshold: Some(threshold),
+                model_commitment: Some(model.commitment()),
+                transaction_data: Some(fields),
+                ai_model_weights: Some(model.witness(&hash).unwrap()),
+                node_reputation: Some(encode_confidence(0.95).unwrap()),
+                detection_algorithm: Some(encode_confidence(detection).unwrap()),
+            };
+            (circuit, vec![hash, threshold, model.commitment()])
+        }
+
+        fn is_satisfied(circuit: &ThreatDetectionCircuit, public_inputs: &[ArkFr]) -> bool {
+            let circuit = ThreatCircuit::from_witness(circuit).unwrap();
+            let instances = vec![public_inputs.iter().map(to_halo2).collect()];
+            MockProver::run(THREAT_CIRCUIT_K, &circuit, instances).unwrap().verify().is_ok()
+        }
+
+        #[test]
+        fn test_threat_circuit_matches_r1cs_statement() {
+            let (circuit, public_inputs) = threat_circuit(0.9);
+            assert!(is_satisfied(&circuit, &public_inputs));
+
+            // Below the threshold, another transaction's hash or another model all fail
+            let (below, _) = threat_circuit(0.6);
+            assert!(!is_satisfied(&below, &public_inputs));
+            let mut wrong_hash = public_inputs.clone();
+            wrong_hash[0] += ArkFr::from(1u64);
+            assert!(!is_satisfied(&circuit, &wrong_hash));
+            let mut wrong_model = public_inputs.clone();
+            wrong_model[2] = ModelCommitment::from_bytes(&[0x43; 4096]).unwrap().commitment();
+            assert!(!is_satisfied(&circuit, &wrong_model));
+
+            let keys = Halo2Srs::setup(THREAT_CIRCUIT_K).unwrap().keygen().unwrap();
+            let proof = keys.prove(&circuit).unwrap();
+            assert!(keys.verify(&public_inputs, &proof).unwrap());
+            assert!(!keys.verify(&wrong_hash, &proof).unwrap());
+        }
+    }
+}
+
+#[cfg(not(feature = "halo2"))]
+mod disabled {
+    use anyhow::Result;
+    use ark_bn254::Fr;
+    use std::convert::Infallible;
+
+    use crate::zk_prover::ThreatDetectionCircuit;
+
+    /// KZG SRS placeholder; parsing always fails
+    pub struct Halo2Srs(Infallible);
+
+    impl Halo2Srs {
+        pub fn from_bytes(_bytes: &[u8]) -> Result<Self> {
+            Err(anyhow::anyhow!("Built without the `halo2` feature"))
+        }
+
+        pub fn keygen(self) -> Result<Halo2Keys> {
+            match self.0 {}
+        }
+    }
+
+    /// Never constructed without the `halo2` feature
+    pub struct Halo2Keys(Infallible);
+
+    impl Halo2Keys {
+        pub fn prove(&self, _circuit: &ThreatDetectionCircuit) -> Result<Vec<u8>> {
+            match self.0 {}
+        }
+
+        pub fn verify(&self, _public_inputs: &[Fr], _proof: &[u8]) -> Result<bool> {
+            match self.0 {}
+        }
+
+        pub fn verifying_key_bytes(&self) -> Result<Vec<u8>> {
+            match self.0 {}
+        }
+    }
+}
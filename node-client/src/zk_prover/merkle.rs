@@ -0,0 +1,123 @@
+/*!
+ * MiMC Merkle trees
+ * Fixed-depth binary trees over field elements, with native paths and an in-circuit root
+ * recomputation. Missing leaves are zero, so a tree only stores its non-empty prefix
+ */
+
+use anyhow::Result;
+use ark_bn254::Fr;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use super::mimc::{mimc_hash, mimc_hash_var};
+
+/// Hash chain seed of inner nodes, apart from every leaf and hash seed
+pub(crate) const NODE_SEED: u64 = 2;
+
+fn node_hash(left: Fr, right: Fr) -> Fr {
+    mimc_hash(Fr::from(NODE_SEED), &[left, right])
+}
+
+/// Sibling hashes from a leaf up to the root
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MerklePath {
+    pub index: usize,
+    pub siblings: Vec<Fr>,
+}
+
+/// Merkle tree of depth `DEPTH`
+#[derive(Clone, Debug)]
+pub struct MerkleTree<const DEPTH: usize> {
+    /// Non-empty prefix of each level, leaves first
+    layers: Vec<Vec<Fr>>,
+    /// Hash of an empty subtree at each level
+    empty: Vec<Fr>,
+}
+
+impl<const DEPTH: usize> MerkleTree<DEPTH> {
+    pub fn new(leaves: Vec<Fr>) -> Result<Self> {
+        if leaves.len() > 1 << DEPTH {
+            return Err(anyhow::anyhow!("{} leaves exceed the tree capacity of {}", leaves.len(), 1u64 << DEPTH));
+        }
+
+        let mut layers = vec![leaves];
+        let mut empty = vec![Fr::from(0u64)];
+        for level in 0..DEPTH {
+            let layer = layers[level]
+                .chunks(2)
+                .map(|pair| node_hash(pair[0], pair.get(1).copied().unwrap_or(empty[level])))
+                .collect();
+            layers.push(layer);
+            empty.push(node_hash(empty[level], empty[level]));
+        }
+        Ok(Self { layers, empty })
+    }
+
+    pub fn root(&self) -> Fr {
+        self.layers[DEPTH].first().copied().unwrap_or(self.empty[DEPTH])
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers[0].is_empty()
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<Fr> {
+        self.layers[0].get(index).copied()
+    }
+
+    /// Index of `leaf`, if it is in the tree
+    pub fn position(&self, leaf: &Fr) -> Option<usize> {
+        self.layers[0].iter().position(|candidate| candidate == leaf)
+    }
+
+    pub fn path(&self, index: usize) -> Result<MerklePath> {
+        if index >= self.len() {
+            return Err(anyhow::anyhow!("Leaf index {} out of range", index));
+        }
+        let siblings = (0..DEPTH)
+            .map(|level| {
+                let position = (index >> level) ^ 1;
+                self.layers[level].get(position).copied().unwrap_or(self.empty[level])
+            })
+            .collect();
+        Ok(MerklePath { index, siblings })
+    }
+}
+
+/// Index bits (least significant first) and siblings of an allocated path
+pub type PathVars = (Vec<Boolean<Fr>>, Vec<FpVar<Fr>>);
+
+/// `path` as witnesses
+pub fn path_witness(cs: ConstraintSystemRef<Fr>, path: Option<&MerklePath>, depth: usize) -> Result<PathVars, SynthesisError> {
+    let missing = || SynthesisError::AssignmentMissing;
+    (0..depth)
+        .map(|level| {
+            let is_right = Boolean::new_witness(cs.clone(), || {
+                path.map(|path| (path.index >> level) & 1 == 1).ok_or_else(missing)
+            })?;
+            let sibling = FpVar::new_witness(cs.clone(), || {
+                path.and_then(|path| path.siblings.get(level).copied()).ok_or_else(missing)
+            })?;
+            Ok((is_right, sibling))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|levels| levels.into_iter().unzip())
+}
+
+/// Root reached by hashing `leaf` up along `index_bits` and `siblings`
+pub fn root_var(
+    leaf: FpVar<Fr>,
+    index_bits: &[Boolean<Fr>],
+    siblings: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    index_bits.iter().zip(siblings).try_fold(leaf, |node, (is_right, sibling)| {
+        let left = is_right.select(sibling, &node)?;
+        let right = is_right.select(&node, sibling)?;
+        mimc_hash_var(FpVar::constant(Fr::from(NODE_SEED)), &[left, right])
+    })
+}
@@ -0,0 +1,217 @@
+/*!
+ * AI model commitments
+ * The deployed ONNX file is split into 31-byte chunks and committed to as a Merkle root plus the
+ * chunk count. Each threat proof opens `MODEL_SAMPLES` chunks, each at a position picked by its
+ * own hash of the threat hash, so the prover cannot pick which weights to show and proofs only
+ * verify against the registered model commitment
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::{cmp::Ordering, path::Path};
+
+use super::merkle::{path_witness, root_var, MerklePath, MerkleTree};
+use super::mimc::{mimc_hash, mimc_hash_var};
+
+/// Levels of the model tree; models up to 2^22 chunks (~130 MB)
+pub const MODEL_TREE_DEPTH: usize = 22;
+
+/// Chunks opened per threat proof; a model differing from the committed one in a fraction `f`
+/// of its chunks still passes with probability (1 - f)^16
+pub const MODEL_SAMPLES: usize = 16;
+
+/// Bits of each sample hash that pick its chunk
+pub(crate) const SAMPLE_BITS: usize = 64;
+
+/// Hash chain seeds, apart from signature leaves (1), inner nodes (2) and the zkml (5) and
+/// disclosure (6) commitments
+pub(crate) const LEAF_SEED: u64 = 3;
+pub(crate) const COMMITMENT_SEED: u64 = 4;
+pub(crate) const SAMPLE_SEED: u64 = 7;
+
+type ModelTree = MerkleTree<MODEL_TREE_DEPTH>;
+
+/// One opened model chunk
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ModelOpening {
+    pub chunk: Fr,
+    pub path: MerklePath,
+}
+
+/// Witness binding a threat proof to the committed model
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ModelWitness {
+    pub root: Fr,
+    pub chunk_count: u64,
+    pub openings: Vec<ModelOpening>,
+}
+
+/// Commitment to a model file
+#[derive(Clone, Debug)]
+pub struct ModelCommitment {
+    tree: ModelTree,
+    chunks: Vec<Fr>,
+}
+
+impl ModelCommitment {
+    pub fn from_bytes(model: &[u8]) -> Result<Self> {
+        if model.is_empty() {
+            return Err(anyhow::anyhow!("Model file is empty"));
+        }
+        let chunks: Vec<Fr> = model.chunks(31).map(Fr::from_le_bytes_mod_order).collect();
+        let leaves = chunks.iter().map(|chunk| mimc_hash(Fr::from(LEAF_SEED), &[*chunk])).collect();
+        let tree = ModelTree::new(leaves).context("Model is too large to commit to")?;
+        Ok(Self { tree, chunks })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let model = std::fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read model {}", path.as_ref().display()))?;
+        Self::from_bytes(&model)
+    }
+
+    /// Public commitment: root and chunk count
+    pub fn commitment(&self) -> Fr {
+        commitment(self.tree.root(), self.chunk_count())
+    }
+
+    pub fn chunk_count(&self) -> u64 {
+        self.chunks.len() as u64
+    }
+
+    /// Openings a proof for `threat_hash` must show
+    pub fn witness(&self, threat_hash: &Fr) -> Result<ModelWitness> {
+        let openings = sample_indices(threat_hash, self.chunk_count())
+            .into_iter()
+            .map(|index| {
+                Ok(ModelOpening {
+                    chunk: self.chunks[index as usize],
+                    path: self.tree.path(index as usize)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(ModelWitness {
+            root: self.tree.root(),
+            chunk_count: self.chunk_count(),
+            openings,
+        })
+    }
+}
+
+fn commitment(root: Fr, chunk_count: u64) -> Fr {
+    mimc_hash(Fr::from(COMMITMENT_SEED), &[root, Fr::from(chunk_count)])
+}
+
+/// Hash picking sample `k` of `threat_hash`
+pub fn sample_hash(threat_hash: &Fr, k: usize) -> Fr {
+    mimc_hash(Fr::from(SAMPLE_SEED), &[*threat_hash, Fr::from(k as u64)])
+}
+
+/// Chunk indices opened for `threat_hash`: the low 64-bit limb of each sample hash, reduced by
+/// the chunk count
+pub fn sample_indices(threat_hash: &Fr, chunk_count: u64) -> Vec<u64> {
+    (0..MODEL_SAMPLES)
+        .map(|k| sample_hash(threat_hash, k).into_bigint().0[0] % chunk_count)
+        .collect()
+}
+
+/// Constrain `witness` to open the chunks `threat_hash` selects from the model committed to by
+/// `model_commitment`
+pub fn enforce_model_openings(
+    cs: ConstraintSystemRef<Fr>,
+    threat_hash: &FpVar<Fr>,
+    model_commitment: &FpVar<Fr>,
+    witness: Option<&ModelWitness>,
+) -> Result<(), SynthesisError> {
+    let missing = || SynthesisError::AssignmentMissing;
+
+    let root = FpVar::new_witness(cs.clone(), || witness.map(|w| w.root).ok_or_else(missing))?;
+    let chunk_count = FpVar::new_witness(cs.clone(), || witness.map(|w| Fr::from(w.chunk_count)).ok_or_else(missing))?;
+    mimc_hash_var(FpVar::constant(Fr::from(COMMITMENT_SEED)), &[root.clone(), chunk_count.clone()])?
+        .enforce_equal(model_commitment)?;
+
+    // Small count, so the index comparison below cannot wrap
+    let count_bits = bit_witness(cs.clone(), witness.map(|w| Fr::from(w.chunk_count)), MODEL_TREE_DEPTH + 1)?;
+    Boolean::le_bits_to_fp_var(&count_bits)?.enforce_equal(&chunk_count)?;
+
+    for k in 0..MODEL_SAMPLES {
+        let opening = witness.and_then(|w| w.openings.get(k));
+
+        // The sample hash's canonical bits, so its low limb is unique
+        let sample = mimc_hash_var(
+            FpVar::constant(Fr::from(SAMPLE_SEED)),
+            &[threat_hash.clone(), FpVar::constant(Fr::from(k as u64))],
+        )?;
+        let sample_bits = sample.to_bits_le()?;
+        let low_limb = sample.value().ok().map(|sample| sample.into_bigint().0[0]);
+
+        // index = limb mod count: limb = quotient * count + index with index < count; both
+        // sides stay far below the modulus, so the equation cannot wrap
+        let limb = Boolean::le_bits_to_fp_var(&sample_bits[..SAMPLE_BITS])?;
+        let quotient_value = low_limb.zip(witness).map(|(limb, w)| Fr::from(limb / w.chunk_count));
+        let quotient_bits = bit_witness(cs.clone(), quotient_value, SAMPLE_BITS)?;
+        let quotient = Boolean::le_bits_to_fp_var(&quotient_bits)?;
+
+        let (index_bits, siblings) = path_witness(cs.clone(), opening.map(|o| &o.path), MODEL_TREE_DEPTH)?;
+        let index = Boolean::le_bits_to_fp_var(&index_bits)?;
+        (quotient * &chunk_count + &index).enforce_equal(&limb)?;
+        index.enforce_cmp(&chunk_count, Ordering::Less, false)?;
+
+        // The opened chunk hashes up to the committed root
+        let chunk = FpVar::new_witness(cs.clone(), || opening.map(|o| o.chunk).ok_or_else(missing))?;
+        let leaf = mimc_hash_var(FpVar::constant(Fr::from(LEAF_SEED)), &[chunk])?;
+        root_var(leaf, &index_bits, &siblings)?.enforce_equal(&root)?;
+    }
+
+    Ok(())
+}
+
+fn bit_witness(cs: ConstraintSystemRef<Fr>, value: Option<Fr>, bits: usize) -> Result<Vec<Boolean<Fr>>, SynthesisError> {
+    let value = value.map(|value| value.into_bigint());
+    (0..bits)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                value.map(|value| value.get_bit(i)).ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn is_satisfied(threat_hash: Fr, model_commitment: Fr, witness: &ModelWitness) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let threat_hash = FpVar::new_input(cs.clone(), || Ok(threat_hash)).unwrap();
+        let model_commitment = FpVar::new_input(cs.clone(), || Ok(model_commitment)).unwrap();
+        enforce_model_openings(cs.clone(), &threat_hash, &model_commitment, Some(witness)).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_openings_bind_model() {
+        let model: Vec<u8> = (0..4000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let committed = ModelCommitment::from_bytes(&model).unwrap();
+        let threat_hash = mimc_hash(Fr::from(0u64), &[Fr::from(42u64)]);
+
+        let witness = committed.witness(&threat_hash).unwrap();
+        assert!(is_satisfied(threat_hash, committed.commitment(), &witness));
+
+        // Openings for another threat are not the ones this threat selects
+        let other = committed.witness(&mimc_hash(Fr::from(0u64), &[Fr::from(43u64)])).unwrap();
+        assert!(!is_satisfied(threat_hash, committed.commitment(), &other));
+
+        // A retrained model has a different commitment
+        let mut retrained = model.clone();
+        retrained[100] ^= 1;
+        let retrained = ModelCommitment::from_bytes(&retrained).unwrap();
+        assert_ne!(retrained.commitment(), committed.commitment());
+        assert!(!is_satisfied(threat_hash, retrained.commitment(), &witness));
+    }
+}
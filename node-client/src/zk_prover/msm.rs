@@ -0,0 +1,362 @@
+/*!
+ * Multi-scalar multiplication engines for Groth16 proving
+ * Most proving time goes into five MSMs over the proving key. They run on the CPU, or with the
+ * `gpu-msm` feature on a CUDA or Metal device through ICICLE, falling back to the CPU on errors
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_groth16::{
+    r1cs_to_qap::{LibsnarkReduction, R1CSToQAP},
+    Proof, ProvingKey,
+};
+use ark_poly::GeneralEvaluationDomain;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, OptimizationGoal};
+use ark_std::{rand::Rng, UniformRand};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::energy_monitor::gpu_adapters::{GpuAdapter, GpuVendor};
+
+/// Bases in the startup benchmark, about the size of the threat circuit's queries
+pub const BENCHMARK_SIZE: usize = 1 << 16;
+
+/// Requested MSM backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MsmBackend {
+    /// GPU when the hardware probe finds a supported one and it beats the CPU
+    #[default]
+    Auto,
+    Cpu,
+    /// GPU whenever one is usable, without benchmarking
+    Gpu,
+}
+
+/// Where MSMs actually run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MsmDevice {
+    #[default]
+    Cpu,
+    Cuda,
+    Metal,
+}
+
+/// First GPU that ICICLE has a backend for
+pub fn supported_device(adapters: &[GpuAdapter]) -> Option<MsmDevice> {
+    adapters.iter().find_map(|adapter| match (adapter.vendor, adapter.integrated) {
+        (GpuVendor::Nvidia, false) => Some(MsmDevice::Cuda),
+        (GpuVendor::Apple, _) => Some(MsmDevice::Metal),
+        _ => None,
+    })
+}
+
+/// Timing of one G1 MSM on the CPU and on the engine's device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MsmBenchmark {
+    pub device: MsmDevice,
+    pub size: usize,
+    pub cpu_ms: f64,
+    pub device_ms: f64,
+}
+
+impl MsmBenchmark {
+    /// CPU time over device time
+    pub fn speedup(&self) -> f64 {
+        self.cpu_ms / self.device_ms.max(f64::EPSILON)
+    }
+}
+
+/// Multi-scalar multiplication over proving key bases
+trait Msm {
+    fn g1(&self, bases: &[G1Affine], scalars: &[Fr]) -> G1Projective;
+    fn g2(&self, bases: &[G2Affine], scalars: &[Fr]) -> G2Projective;
+}
+
+struct CpuMsm;
+
+impl Msm for CpuMsm {
+    fn g1(&self, bases: &[G1Affine], scalars: &[Fr]) -> G1Projective {
+        G1Projective::msm_unchecked(bases, scalars)
+    }
+
+    fn g2(&self, bases: &[G2Affine], scalars: &[Fr]) -> G2Projective {
+        G2Projective::msm_unchecked(bases, scalars)
+    }
+}
+
+/// MSM engine used by the Groth16 prover
+pub enum MsmEngine {
+    Cpu,
+    #[cfg(feature = "gpu-msm")]
+    Gpu(icicle::IcicleMsm),
+}
+
+impl MsmEngine {
+    /// Engine for `backend` given the GPUs found by the hardware probe; anything that fails
+    /// to initialize falls back to the CPU
+    pub fn select(backend: MsmBackend, adapters: &[GpuAdapter]) -> Self {
+        if backend == MsmBackend::Cpu {
+            return Self::Cpu;
+        }
+        let Some(device) = supported_device(adapters) else {
+            if backend == MsmBackend::Gpu {
+                warn!("⚠️ GPU MSM requested but no CUDA or Metal GPU was found, using the CPU");
+            }
+            return Self::Cpu;
+        };
+
+        #[cfg(feature = "gpu-msm")]
+        {
+            match icicle::IcicleMsm::new(device) {
+                Ok(gpu) => Self::Gpu(gpu),
+                Err(e) => {
+                    warn!("⚠️ {:?} MSM unavailable, using the CPU: {}", device, e);
+                    Self::Cpu
+                }
+            }
+        }
+        #[cfg(not(feature = "gpu-msm"))]
+        {
+            if backend == MsmBackend::Gpu {
+                warn!("⚠️ Found a {:?} GPU but built without the `gpu-msm` feature, using the CPU", device);
+            }
+            Self::Cpu
+        }
+    }
+
+    pub fn device(&self) -> MsmDevice {
+        match self {
+            Self::Cpu => MsmDevice::Cpu,
+            #[cfg(feature = "gpu-msm")]
+            Self::Gpu(gpu) => gpu.device,
+        }
+    }
+
+    /// Groth16 proof of `circuit` with this engine's MSMs
+    pub fn prove<C: ConstraintSynthesizer<Fr>, R: Rng>(
+        &self,
+        circuit: C,
+        pk: &ProvingKey<Bn254>,
+        rng: &mut R,
+    ) -> Result<Proof<Bn254>> {
+        self.prove_with_reduction::<LibsnarkReduction, _, _>(circuit, pk, rng)
+    }
+
+    /// As `prove`, for keys made under another QAP reduction (Circom zkeys)
+    pub fn prove_with_reduction<QAP: R1CSToQAP, C: ConstraintSynthesizer<Fr>, R: Rng>(
+        &self,
+        circuit: C,
+        pk: &ProvingKey<Bn254>,
+        rng: &mut R,
+    ) -> Result<Proof<Bn254>> {
+        match self {
+            Self::Cpu => prove_with::<QAP, _, _>(&CpuMsm, circuit, pk, rng),
+            #[cfg(feature = "gpu-msm")]
+            Self::Gpu(gpu) => prove_with::<QAP, _, _>(gpu, circuit, pk, rng),
+        }
+    }
+
+    /// Time a `size`-point G1 MSM on the CPU and on this engine's device
+    pub fn benchmark(&self, size: usize) -> MsmBenchmark {
+        let mut rng = ark_std::rand::thread_rng();
+        // Consecutive multiples of a random point; only the timing matters
+        let (start, step) = (G1Projective::rand(&mut rng), G1Projective::rand(&mut rng));
+        let points: Vec<G1Projective> = (0..size)
+            .scan(start, |point, _| {
+                *point += step;
+                Some(*point)
+            })
+            .collect();
+        let bases = G1Projective::normalize_batch(&points);
+        let scalars: Vec<Fr> = (0..size).map(|_| Fr::rand(&mut rng)).collect();
+
+        let time = |msm: &dyn Fn() -> G1Projective| {
+            let started = Instant::now();
+            let _ = msm();
+            started.elapsed().as_secs_f64() * 1000.0
+        };
+        let cpu_ms = time(&|| CpuMsm.g1(&bases, &scalars));
+        let device_ms = match self {
+            Self::Cpu => cpu_ms,
+            #[cfg(feature = "gpu-msm")]
+            Self::Gpu(gpu) => {
+                // The first call pays for backend warm-up
+                let _ = gpu.g1(&bases[..1], &scalars[..1]);
+                time(&|| gpu.g1(&bases, &scalars))
+            }
+        };
+
+        let benchmark = MsmBenchmark {
+            device: self.device(),
+            size,
+            cpu_ms,
+            device_ms,
+        };
+        info!(
+            "📊 MSM benchmark ({} points): CPU {:.1}ms, {:?} {:.1}ms ({:.2}x)",
+            size, benchmark.cpu_ms, benchmark.device, benchmark.device_ms, benchmark.speedup()
+        );
+        benchmark
+    }
+}
+
+/// Groth16 prover with the MSMs delegated to `msm`; the same computation as
+/// `ark_groth16`'s prover under the `QAP` reduction
+fn prove_with<QAP: R1CSToQAP, C: ConstraintSynthesizer<Fr>, R: Rng>(
+    msm: &impl Msm,
+    circuit: C,
+    pk: &ProvingKey<Bn254>,
+    rng: &mut R,
+) -> Result<Proof<Bn254>> {
+    let (r, s) = (Fr::rand(rng), Fr::rand(rng));
+
+    let cs = ConstraintSystem::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    circuit.generate_constraints(cs.clone())?;
+    cs.finalize();
+    let h = QAP::witness_map::<Fr, GeneralEvaluationDomain<Fr>>(cs.clone())?;
+
+    let prover = cs.borrow().context("Constraint system still in use")?;
+    let witness = &prover.witness_assignment;
+    // Every variable except the constant one, which the first query entry covers
+    let assignment: Vec<Fr> = prover.instance_assignment[1..].iter().chain(witness).copied().collect();
+
+    let a = pk.vk.alpha_g1 + pk.a_query[0] + msm.g1(&pk.a_query[1..], &assignment) + pk.delta_g1 * r;
+    let b_g1 = pk.beta_g1 + pk.b_g1_query[0] + msm.g1(&pk.b_g1_query[1..], &assignment) + pk.delta_g1 * s;
+    let b_g2 = pk.vk.beta_g2 + pk.b_g2_query[0] + msm.g2(&pk.b_g2_query[1..], &assignment) + pk.vk.delta_g2 * s;
+    let c = a * s + b_g1 * r - pk.delta_g1 * (r * s)
+        + msm.g1(&pk.l_query, witness)
+        + msm.g1(&pk.h_query, &h);
+
+    Ok(Proof {
+        a: a.into_affine(),
+        b: b_g2.into_affine(),
+        c: c.into_affine(),
+    })
+}
+
+#[cfg(feature = "gpu-msm")]
+mod icicle {
+    use anyhow::Result;
+    use ark_bn254::{Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+    use icicle_bn254::curve::{
+        G1Affine as DeviceG1Affine, G1Projective as DeviceG1, G2Affine as DeviceG2Affine,
+        G2Projective as DeviceG2, ScalarField,
+    };
+    use icicle_core::{
+        msm::{msm, MSMConfig},
+        traits::ArkConvertible,
+    };
+    use icicle_runtime::{memory::HostSlice, Device};
+    use tracing::warn;
+
+    use super::{CpuMsm, Msm, MsmDevice};
+
+    /// MSMs on an ICICLE device
+    pub struct IcicleMsm {
+        pub device: MsmDevice,
+        handle: Device,
+    }
+
+    impl IcicleMsm {
+        /// Load the ICICLE backends (`ICICLE_BACKEND_INSTALL_DIR`) and open `device`
+        pub fn new(device: MsmDevice) -> Result<Self> {
+            let name = match device {
+                MsmDevice::Cuda => "CUDA",
+                MsmDevice::Metal => "METAL",
+                MsmDevice::Cpu => return Err(anyhow::anyhow!("Not a GPU device")),
+            };
+            icicle_runtime::load_backend_from_env_or_default()
+                .map_err(|e| anyhow::anyhow!("Failed to load ICICLE backends: {:?}", e))?;
+
+            let handle = Device::new(name, 0);
+            if !icicle_runtime::is_device_available(&handle) {
+                return Err(anyhow::anyhow!("No ICICLE {} backend installed", name));
+            }
+            Ok(Self { device, handle })
+        }
+
+        /// Scalars for the first `len` bases; the active device is per thread, so it is set
+        /// before every MSM
+        fn prepare(&self, scalars: &[Fr], len: usize) -> Result<Vec<ScalarField>> {
+            icicle_runtime::set_device(&self.handle)
+                .map_err(|e| anyhow::anyhow!("Failed to select {:?} device: {:?}", self.device, e))?;
+            Ok(scalars[..len].iter().map(ScalarField::from_ark).collect())
+        }
+
+        fn try_g1(&self, bases: &[G1Affine], scalars: &[Fr]) -> Result<G1Projective> {
+            let len = bases.len().min(scalars.len());
+            let scalars = self.prepare(scalars, len)?;
+            let bases: Vec<DeviceG1Affine> = bases[..len].iter().map(DeviceG1Affine::from_ark).collect();
+            let mut result = [DeviceG1::zero()];
+            msm(HostSlice::from_slice(&scalars), HostSlice::from_slice(&bases), &MSMConfig::default(), HostSlice::from_mut_slice(&mut result))
+                .map_err(|e| anyhow::anyhow!("G1 MSM failed: {:?}", e))?;
+            Ok(result[0].to_ark())
+        }
+
+        fn try_g2(&self, bases: &[G2Affine], scalars: &[Fr]) -> Result<G2Projective> {
+            let len = bases.len().min(scalars.len());
+            let scalars = self.prepare(scalars, len)?;
+            let bases: Vec<DeviceG2Affine> = bases[..len].iter().map(DeviceG2Affine::from_ark).collect();
+            let mut result = [DeviceG2::zero()];
+            msm(HostSlice::from_slice(&scalars), HostSlice::from_slice(&bases), &MSMConfig::default(), HostSlice::from_mut_slice(&mut result))
+                .map_err(|e| anyhow::anyhow!("G2 MSM failed: {:?}", e))?;
+            Ok(result[0].to_ark())
+        }
+    }
+
+    impl Msm for IcicleMsm {
+        fn g1(&self, bases: &[G1Affine], scalars: &[Fr]) -> G1Projective {
+            self.try_g1(bases, scalars).unwrap_or_else(|e| {
+                warn!("⚠️ {:?} MSM failed, retrying on the CPU: {}", self.device, e);
+                CpuMsm.g1(bases, scalars)
+            })
+        }
+
+        fn g2(&self, bases: &[G2Affine], scalars: &[Fr]) -> G2Projective {
+            self.try_g2(bases, scalars).unwrap_or_else(|e| {
+                warn!("⚠️ {:?} MSM failed, retrying on the CPU: {}", self.device, e);
+                CpuMsm.g2(bases, scalars)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::energy_range::{energy_commitment, EnergyRangeCircuit};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+
+    fn adapter(vendor: GpuVendor, integrated: bool) -> GpuAdapter {
+        GpuAdapter {
+            name: format!("{:?}", vendor),
+            vendor,
+            vendor_id: 0,
+            device_id: 0,
+            integrated,
+            vram_mb: None,
+        }
+    }
+
+    #[test]
+    fn test_engine_proofs_verify_and_device_selection() {
+        let mut rng = ark_std::test_rng();
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(EnergyRangeCircuit::default(), &mut rng).unwrap();
+
+        let salt = Fr::from(11u64);
+        let circuit = EnergyRangeCircuit::new(50_000, (0, 86_400), Fr::from(1u64), 40_000 * 72_000, 72_000, salt);
+        let proof = MsmEngine::Cpu.prove(circuit, &pk, &mut rng).unwrap();
+        let inputs = EnergyRangeCircuit::public_inputs(50_000, (0, 86_400), Fr::from(1u64), energy_commitment(40_000 * 72_000, 72_000, Fr::from(1u64), 0, salt));
+        assert!(Groth16::<Bn254>::verify_proof(&prepare_verifying_key(&pk.vk), &proof, &inputs).unwrap());
+
+        assert_eq!(supported_device(&[adapter(GpuVendor::Intel, true), adapter(GpuVendor::Nvidia, false)]), Some(MsmDevice::Cuda));
+        assert_eq!(supported_device(&[adapter(GpuVendor::Apple, true)]), Some(MsmDevice::Metal));
+        assert_eq!(supported_device(&[adapter(GpuVendor::Nvidia, true), adapter(GpuVendor::Amd, false)]), None);
+        assert_eq!(MsmEngine::select(MsmBackend::Cpu, &[adapter(GpuVendor::Nvidia, false)]).device(), MsmDevice::Cpu);
+    }
+}
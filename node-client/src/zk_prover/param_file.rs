@@ -0,0 +1,140 @@
+/*!
+ * Parameter file format
+ * Key files are containers: magic bytes, format version, curve, circuit, circuit version and key
+ * type, then the compressed key. Headers are checked against what the loader expects before the
+ * key is deserialized, so a file from another circuit, version or curve fails with an error naming
+ * the mismatch. The header only says what a file holds; integrity is the `ParamStore` manifest's
+ * job, as for every other parameter file. Files from before the container led with the circuit
+ * version only
+ */
+
+use anyhow::Result;
+
+/// Leads every parameter file
+const MAGIC: &[u8; 4] = b"DSZK";
+
+/// Container layout written by this version
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Curve ids
+pub const CURVE_BN254: u8 = 1;
+
+/// Magic, format version, curve, circuit, circuit version, key type
+const HEADER_LEN: usize = MAGIC.len() + 2 + 1 + 1 + 4 + 1;
+
+/// Half of a key pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    Proving,
+    Verifying,
+}
+
+impl KeyType {
+    fn id(self) -> u8 {
+        match self {
+            Self::Proving => 0,
+            Self::Verifying => 1,
+        }
+    }
+}
+
+/// What a parameter file holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamHeader {
+    pub curve: u8,
+    /// `CircuitKind::id`
+    pub circuit: u8,
+    pub circuit_version: u32,
+    pub key: KeyType,
+}
+
+impl ParamHeader {
+    pub fn new(circuit: u8, circuit_version: u32, key: KeyType) -> Self {
+        Self {
+            curve: CURVE_BN254,
+            circuit,
+            circuit_version,
+            key,
+        }
+    }
+
+    /// Container of `payload` under this header
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.push(self.curve);
+        bytes.push(self.circuit);
+        bytes.extend_from_slice(&self.circuit_version.to_le_bytes());
+        bytes.push(self.key.id());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    /// Payload of a container, which must hold exactly what this header describes
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        if !is_container(bytes) {
+            return Err(anyhow::anyhow!("Not a DAGShield parameter file"));
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(anyhow::anyhow!("Parameter file header is truncated"));
+        }
+        let (header, payload) = bytes.split_at(HEADER_LEN);
+        let u32_at = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+
+        let format = u16::from_le_bytes([header[4], header[5]]);
+        if format != FORMAT_VERSION {
+            return Err(anyhow::anyhow!("Parameter file format {} is not supported (this node reads {})", format, FORMAT_VERSION));
+        }
+        if header[6] != self.curve {
+            return Err(anyhow::anyhow!("Parameters are for curve {}, expected {}", header[6], self.curve));
+        }
+        if header[7] != self.circuit {
+            return Err(anyhow::anyhow!("Parameters are for circuit {}, expected {}", header[7], self.circuit));
+        }
+        if u32_at(8) != self.circuit_version {
+            return Err(anyhow::anyhow!(
+                "Parameters are for circuit version {}, expected {}",
+                u32_at(8),
+                self.circuit_version
+            ));
+        }
+        if header[12] != self.key.id() {
+            let found = if header[12] == KeyType::Proving.id() { KeyType::Proving } else { KeyType::Verifying };
+            return Err(anyhow::anyhow!("File holds a {:?} key, expected a {:?} key", found, self.key));
+        }
+        Ok(payload.to_vec())
+    }
+}
+
+/// Whether `bytes` are a container rather than a file from before it
+pub fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_rejects_mixed_files() {
+        let header = ParamHeader::new(0, 4, KeyType::Verifying);
+        let file = header.encode(b"verifying key");
+        assert_eq!(header.decode(&file).unwrap(), b"verifying key");
+
+        // Another circuit, version, key type or curve is named in the error
+        for (expected, mismatch) in [
+            (ParamHeader::new(2, 4, KeyType::Verifying), "circuit 0"),
+            (ParamHeader::new(0, 3, KeyType::Verifying), "circuit version 4"),
+            (ParamHeader::new(0, 4, KeyType::Proving), "Verifying key"),
+            (ParamHeader { curve: 2, ..header }, "curve 1"),
+        ] {
+            let error = expected.decode(&file).unwrap_err().to_string();
+            assert!(error.contains(mismatch), "{}", error);
+        }
+
+        // Truncated headers and files from before the container are not read as containers
+        assert!(header.decode(&file[..HEADER_LEN - 1]).is_err());
+        assert!(header.decode(&4u32.to_le_bytes()).is_err());
+    }
+}
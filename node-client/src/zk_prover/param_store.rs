@@ -0,0 +1,278 @@
+/*!
+ * Parameter storage
+ * Every key and SRS file under `params_dir` goes through this store, whose keccak256 checksum
+ * manifest is checked on every load; it is the only integrity check parameter files get. Key
+ * files also carry a `param_file` header naming the circuit, version and key they hold. Proving keys can be encrypted at rest with the node passphrase: scrypt derives the key,
+ * AES-128-CTR encrypts and a keccak256 MAC authenticates, as in Ethereum keystores
+ */
+
+use aes::Aes128;
+use anyhow::{Context, Result};
+use ark_std::rand::RngCore;
+use ctr::{
+    cipher::{KeyIvInit, StreamCipher},
+    Ctr128BE,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{error, info, warn};
+
+use super::param_file::{self, ParamHeader};
+
+/// Checksums of the files in a parameter directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Leads every encrypted file
+const ENCRYPTED_MAGIC: &[u8; 4] = b"DSEK";
+
+/// scrypt cost (N = 2^15, r = 8, p = 1), paid once per encrypted file
+const SCRYPT_LOG_N: u8 = 15;
+
+/// Highest scrypt cost accepted from a file header (1 GiB of memory at r = 8)
+const MAX_SCRYPT_LOG_N: u8 = 20;
+
+const SALT_LEN: usize = 32;
+const IV_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+const HEADER_LEN: usize = ENCRYPTED_MAGIC.len() + 1 + SALT_LEN + IV_LEN;
+
+/// Checksum of one stored file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// keccak256 of the file as stored, hex
+    pub keccak256: String,
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+/// Parameter files in one directory
+pub struct ParamStore {
+    dir: PathBuf,
+    passphrase: Option<String>,
+}
+
+impl ParamStore {
+    pub fn new<P: AsRef<Path>>(dir: P, passphrase: Option<String>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            passphrase,
+        }
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn manifest(&self) -> Result<Manifest> {
+        let path = self.path(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        let data = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&data).with_context(|| format!("Malformed parameter manifest {}", path.display()))
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let path = self.path(MANIFEST_FILE);
+        fs::write(&path, serde_json::to_vec_pretty(manifest)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Write `name`, encrypted with the passphrase if `encrypt`, and record its checksum
+    pub fn write(&self, name: &str, bytes: &[u8], encrypt: bool) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let stored = if encrypt {
+            let passphrase = self.passphrase.as_deref().context("Encrypting parameters needs the node passphrase")?;
+            encrypt_bytes(passphrase, bytes)?
+        } else {
+            bytes.to_vec()
+        };
+
+        let path = self.path(name);
+        fs::write(&path, &stored).with_context(|| format!("Failed to write {}", path.display()))?;
+        self.record_checksum(name, &stored, encrypt)
+    }
+
+    /// Read `name`, checking its checksum and decrypting it if it is encrypted
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let path = self.path(name);
+        let stored = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        self.check_checksum(name, &stored)?;
+
+        if !is_encrypted(&stored) {
+            return Ok(stored);
+        }
+        let passphrase = self.passphrase.as_deref()
+            .with_context(|| format!("{} is encrypted and no node passphrase was given", name))?;
+        decrypt_bytes(passphrase, &stored).with_context(|| format!("Failed to decrypt {}", name))
+    }
+
+    /// Write a key file holding `key` under `header`
+    pub fn write_key(&self, name: &str, header: &ParamHeader, key: &[u8], encrypt: bool) -> Result<()> {
+        self.write(name, &header.encode(key), encrypt)
+    }
+
+    /// Key in `name` after checking its header against `header`. Files from before the
+    /// container, led by the circuit version only, are rewritten as containers
+    pub fn read_key(&self, name: &str, header: &ParamHeader) -> Result<Vec<u8>> {
+        let stored = self.read(name)?;
+        if param_file::is_container(&stored) {
+            return header.decode(&stored);
+        }
+
+        let found = stored.get(..4).map(|version| u32::from_le_bytes(version.try_into().unwrap()));
+        if found != Some(header.circuit_version) {
+            return Err(anyhow::anyhow!("Legacy parameters are not for circuit version {}", header.circuit_version));
+        }
+        let key = stored[4..].to_vec();
+        self.write_key(name, header, &key, self.is_encrypted(name)?)?;
+        info!("📦 Upgraded {} to the versioned parameter format", name);
+        Ok(key)
+    }
+
+    /// Whether `name` was stored encrypted
+    pub fn is_encrypted(&self, name: &str) -> Result<bool> {
+        Ok(self.manifest()?.files.get(name).is_some_and(|entry| entry.encrypted))
+    }
+
+    fn record_checksum(&self, name: &str, stored: &[u8], encrypted: bool) -> Result<()> {
+        let mut manifest = self.manifest()?;
+        manifest.files.insert(
+            name.to_string(),
+            ManifestEntry {
+                keccak256: hex::encode(Keccak256::digest(stored)),
+                encrypted,
+            },
+        );
+        self.save_manifest(&manifest)
+    }
+
+    /// Files without a recorded checksum are refused, so removing the manifest does not
+    /// bypass the check
+    fn check_checksum(&self, name: &str, stored: &[u8]) -> Result<()> {
+        let Some(entry) = self.manifest()?.files.remove(name) else {
+            warn!("⚠️ {} has no recorded checksum and will not be loaded", name);
+            return Err(anyhow::anyhow!("{} is not in the parameter manifest", name));
+        };
+
+        let checksum = hex::encode(Keccak256::digest(stored));
+        if checksum != entry.keccak256 {
+            error!("🚨 {} checksum {} does not match the manifest's {}", name, checksum, entry.keccak256);
+            return Err(anyhow::anyhow!("{} is corrupted or was modified", name));
+        }
+        Ok(())
+    }
+}
+
+fn is_encrypted(stored: &[u8]) -> bool {
+    stored.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// 16-byte cipher key followed by the 16-byte MAC key
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8) -> Result<[u8; 32]> {
+    let params = scrypt::Params::new(log_n, 8, 1).map_err(|e| anyhow::anyhow!("Invalid scrypt parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn mac(key: &[u8; 32], ciphertext: &[u8]) -> [u8; MAC_LEN] {
+    Keccak256::new().chain_update(&key[16..]).chain_update(ciphertext).finalize().into()
+}
+
+/// Magic, scrypt cost, salt, IV, ciphertext, MAC
+fn encrypt_bytes(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut rng = ark_std::rand::thread_rng();
+    let mut salt = [0u8; SALT_LEN];
+    let mut iv = [0u8; IV_LEN];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut iv);
+
+    let key = derive_key(passphrase, &salt, SCRYPT_LOG_N)?;
+    let mut ciphertext = plaintext.to_vec();
+    Ctr128BE::<Aes128>::new(key[..16].into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+    let mut stored = Vec::with_capacity(HEADER_LEN + ciphertext.len() + MAC_LEN);
+    stored.extend_from_slice(ENCRYPTED_MAGIC);
+    stored.push(SCRYPT_LOG_N);
+    stored.extend_from_slice(&salt);
+    stored.extend_from_slice(&iv);
+    stored.extend_from_slice(&ciphertext);
+    stored.extend_from_slice(&mac(&key, &ciphertext));
+    Ok(stored)
+}
+
+fn decrypt_bytes(passphrase: &str, stored: &[u8]) -> Result<Vec<u8>> {
+    if stored.len() < HEADER_LEN + MAC_LEN {
+        return Err(anyhow::anyhow!("Encrypted file is truncated"));
+    }
+    let log_n = stored[ENCRYPTED_MAGIC.len()];
+    if log_n > MAX_SCRYPT_LOG_N {
+        return Err(anyhow::anyhow!("scrypt cost 2^{} exceeds the 2^{} limit", log_n, MAX_SCRYPT_LOG_N));
+    }
+    let salt = &stored[ENCRYPTED_MAGIC.len() + 1..ENCRYPTED_MAGIC.len() + 1 + SALT_LEN];
+    let iv = &stored[HEADER_LEN - IV_LEN..HEADER_LEN];
+    let (ciphertext, tag) = stored[HEADER_LEN..].split_at(stored.len() - HEADER_LEN - MAC_LEN);
+
+    let key = derive_key(passphrase, salt, log_n)?;
+    if mac(&key, ciphertext) != tag {
+        return Err(anyhow::anyhow!("Wrong node passphrase"));
+    }
+    let mut plaintext = ciphertext.to_vec();
+    Ctr128BE::<Aes128>::new(key[..16].into(), iv.into()).apply_keystream(&mut plaintext);
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_store_and_integrity() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ParamStore::new(dir.path(), Some("correct horse".to_string()));
+        let key = vec![7u8; 1000];
+
+        store.write("proving_key.v1.bin", &key, true).unwrap();
+        store.write("verifying_key.v1.bin", b"vk", false).unwrap();
+        assert!(store.is_encrypted("proving_key.v1.bin").unwrap());
+        assert_ne!(fs::read(store.path("proving_key.v1.bin")).unwrap()[HEADER_LEN..HEADER_LEN + 1000], key[..]);
+        assert_eq!(store.read("proving_key.v1.bin").unwrap(), key);
+        assert_eq!(store.read("verifying_key.v1.bin").unwrap(), b"vk");
+
+        // Without the right passphrase the proving key stays sealed; plaintext files still load
+        assert!(ParamStore::new(dir.path(), None).read("proving_key.v1.bin").is_err());
+        assert!(ParamStore::new(dir.path(), Some("wrong".to_string())).read("proving_key.v1.bin").is_err());
+        assert!(ParamStore::new(dir.path(), None).read("verifying_key.v1.bin").is_ok());
+
+        // Modified files fail the checksum
+        fs::write(store.path("verifying_key.v1.bin"), b"vK").unwrap();
+        assert!(store.read("verifying_key.v1.bin").is_err());
+
+        // An inflated scrypt cost is refused before any key derivation
+        let mut stored = fs::read(store.path("proving_key.v1.bin")).unwrap();
+        stored[ENCRYPTED_MAGIC.len()] = 40;
+        assert!(decrypt_bytes("correct horse", &stored).is_err());
+
+        // Key files are checked against both the manifest and their header
+        let header = ParamHeader::new(0, 4, param_file::KeyType::Proving);
+        store.write_key("proving_key.v4.bin", &header, &key, true).unwrap();
+        assert_eq!(store.read_key("proving_key.v4.bin", &header).unwrap(), key);
+        assert!(store.read_key("proving_key.v4.bin", &ParamHeader { circuit_version: 3, ..header }).is_err());
+
+        // Without the manifest nothing is trusted
+        fs::remove_file(store.path(MANIFEST_FILE)).unwrap();
+        assert!(store.read("proving_key.v1.bin").is_err());
+        assert!(store.read_key("proving_key.v4.bin", &header).is_err());
+    }
+}
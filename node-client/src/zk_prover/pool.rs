@@ -0,0 +1,379 @@
+/*!
+ * Proving worker pool
+ * Proofs take seconds of CPU, so they run on dedicated OS threads instead of the async runtime.
+ * Jobs wait in a bounded queue ordered by threat confidence, then deadline, and the number
+ * running at once follows the node's power policy. Jobs still queued at their deadline fail
+ * without running
+ */
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use crate::energy_monitor::power_policy::{PowerMode, PowerPolicy};
+
+/// Worker pool settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProverPoolConfig {
+    /// Worker threads; 0 uses the available parallelism
+    pub workers: usize,
+    /// Jobs waiting beyond this are rejected
+    pub queue_capacity: usize,
+    /// Proofs running at once in reduced power mode
+    pub reduced_concurrency: usize,
+    /// Proofs running at once in minimal power mode; 0 holds every job until power returns
+    pub minimal_concurrency: usize,
+    /// A held job due within this many seconds keeps one proof running whatever the power mode
+    #[serde(default = "default_urgent_within")]
+    pub urgent_within_secs: u64,
+}
+
+fn default_urgent_within() -> u64 {
+    30
+}
+
+impl Default for ProverPoolConfig {
+    fn default() -> Self {
+        Self {
+            workers: 0,
+            queue_capacity: 64,
+            reduced_concurrency: 1,
+            minimal_concurrency: 0,
+            urgent_within_secs: default_urgent_within(),
+        }
+    }
+}
+
+/// Runs the job, or only reports it expired when passed `true`
+type Task = Box<dyn FnOnce(bool) + Send>;
+
+struct Job {
+    /// Threat confidence scaled to an integer so jobs are totally ordered
+    priority: u64,
+    deadline: Option<Instant>,
+    seq: u64,
+    task: Task,
+}
+
+impl Ord for Job {
+    /// Highest confidence first, then earliest deadline (jobs without one last), then oldest first
+    fn cmp(&self, other: &Self) -> Ordering {
+        let deadline = match (self.deadline, other.deadline) {
+            (Some(own), Some(other)) => other.cmp(&own),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        };
+        self.priority
+            .cmp(&other.priority)
+            .then(deadline)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Job {}
+
+struct State {
+    queue: BinaryHeap<Job>,
+    running: usize,
+    limit: usize,
+    next_seq: u64,
+    /// Jobs dropped at their deadline
+    expired: u64,
+    shutdown: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// Result of a queued proving job
+pub struct ProofHandle<T> {
+    receiver: oneshot::Receiver<Result<T>>,
+}
+
+impl<T> Future for ProofHandle<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.unwrap_or_else(|_| Err(anyhow::anyhow!("Proving pool shut down before the job ran"))))
+    }
+}
+
+/// Dedicated proving threads
+pub struct ProverPool {
+    config: ProverPoolConfig,
+    workers: usize,
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl ProverPool {
+    pub fn new(config: ProverPoolConfig) -> Self {
+        let workers = match config.workers {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: BinaryHeap::new(),
+                running: 0,
+                limit: workers,
+                next_seq: 0,
+                expired: 0,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let threads = (0..workers)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("zk-prover-{}", i))
+                    .spawn(move || worker(&shared))
+                    .expect("Failed to spawn proving thread")
+            })
+            .collect();
+
+        Self { config, workers, shared, threads }
+    }
+
+    /// Queue `job` ahead of every job with lower `confidence`; fails when the queue is full
+    pub fn submit<T, F>(&self, confidence: f64, job: F) -> Result<ProofHandle<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        self.submit_before(confidence, None, job)
+    }
+
+    /// Like `submit`, ahead of equally confident jobs due later; fails through the handle if no
+    /// worker picks it up before `deadline`
+    pub fn submit_before<T, F>(&self, confidence: f64, deadline: Option<Instant>, job: F) -> Result<ProofHandle<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let task: Task = Box::new(move |expired| {
+            let result = if expired {
+                Err(anyhow::anyhow!("Proving job expired in the queue"))
+            } else {
+                catch_unwind(AssertUnwindSafe(job)).unwrap_or_else(|_| Err(anyhow::anyhow!("Proving job panicked")))
+            };
+            let _ = sender.send(result);
+        });
+
+        let mut state = self.shared.state.lock().unwrap();
+        if state.queue.len() >= self.config.queue_capacity {
+            return Err(anyhow::anyhow!("Proving queue is full ({} jobs)", state.queue.len()));
+        }
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queue.push(Job {
+            priority: (confidence.clamp(0.0, 1.0) * 1_000_000.0) as u64,
+            deadline,
+            seq,
+            task,
+        });
+        drop(state);
+
+        self.shared.changed.notify_one();
+        Ok(ProofHandle { receiver })
+    }
+
+    /// Limit concurrent proofs to what the power policy allows, keeping one running while a
+    /// held job is close to its deadline; re-applied with every power reading
+    pub fn apply_power_policy(&self, policy: &PowerPolicy) {
+        let mut state = self.shared.state.lock().unwrap();
+        let limit = match policy.mode {
+            _ if policy.should_defer_proofs() => self.config.minimal_concurrency,
+            PowerMode::Full => self.workers,
+            PowerMode::Reduced => self.config.reduced_concurrency,
+            PowerMode::Minimal => self.config.minimal_concurrency,
+        }
+        .min(self.workers);
+
+        let urgent_before = Instant::now() + Duration::from_secs(self.config.urgent_within_secs);
+        let urgent = state.queue.iter().any(|job| job.deadline.is_some_and(|deadline| deadline <= urgent_before));
+        let limit = if urgent { limit.max(1) } else { limit };
+
+        if state.limit != limit {
+            debug!("⚙️ Proving concurrency {} -> {} ({:?})", state.limit, limit, policy.mode);
+            state.limit = limit;
+            drop(state);
+            self.shared.changed.notify_all();
+        }
+    }
+
+    /// Proving threads
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Jobs waiting for a worker
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().queue.len()
+    }
+
+    /// Jobs waiting beyond this are rejected
+    pub fn capacity(&self) -> usize {
+        self.config.queue_capacity
+    }
+
+    /// Jobs running now
+    pub fn running(&self) -> usize {
+        self.shared.state.lock().unwrap().running
+    }
+
+    /// Jobs dropped at their deadline since the pool started
+    pub fn expired(&self) -> u64 {
+        self.shared.state.lock().unwrap().expired
+    }
+}
+
+impl Drop for ProverPool {
+    fn drop(&mut self) {
+        let pending = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.shutdown = true;
+            std::mem::take(&mut state.queue)
+        };
+        if !pending.is_empty() {
+            warn!("⚠️ Dropping {} queued proving jobs", pending.len());
+        }
+        self.shared.changed.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn worker(shared: &Shared) {
+    loop {
+        let job = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if state.shutdown {
+                    return;
+                }
+                // Expired jobs are failed whatever the limit, so callers are not kept waiting
+                let expired = take_expired(&mut state);
+                if !expired.is_empty() {
+                    state.expired += expired.len() as u64;
+                    drop(state);
+                    for job in expired {
+                        (job.task)(true);
+                    }
+                    state = shared.state.lock().unwrap();
+                    continue;
+                }
+                if state.running < state.limit {
+                    if let Some(job) = state.queue.pop() {
+                        state.running += 1;
+                        break job;
+                    }
+                }
+                state = match next_deadline(&state) {
+                    Some(deadline) => {
+                        let timeout = deadline.saturating_duration_since(Instant::now());
+                        shared.changed.wait_timeout(state, timeout).unwrap().0
+                    }
+                    None => shared.changed.wait(state).unwrap(),
+                };
+            }
+        };
+
+        (job.task)(false);
+
+        shared.state.lock().unwrap().running -= 1;
+        shared.changed.notify_all();
+    }
+}
+
+/// Remove the queued jobs whose deadline has passed
+fn take_expired(state: &mut State) -> Vec<Job> {
+    let now = Instant::now();
+    let is_expired = |job: &Job| job.deadline.is_some_and(|deadline| deadline <= now);
+    if !state.queue.iter().any(is_expired) {
+        return Vec::new();
+    }
+    let (expired, live): (Vec<_>, Vec<_>) = std::mem::take(&mut state.queue).into_iter().partition(is_expired);
+    state.queue = live.into();
+    expired
+}
+
+fn next_deadline(state: &State) -> Option<Instant> {
+    state.queue.iter().filter_map(|job| job.deadline).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_priority_order_and_power_limits() {
+        let pool = ProverPool::new(ProverPoolConfig {
+            workers: 1,
+            queue_capacity: 3,
+            ..ProverPoolConfig::default()
+        });
+
+        // Hold every job until the battery recovers
+        let minimal = PowerPolicy {
+            mode: PowerMode::Minimal,
+            ..PowerPolicy::default()
+        };
+        pool.apply_power_policy(&minimal);
+
+        let (order_tx, order_rx) = mpsc::channel();
+        let handles: Vec<_> = [0.6, 0.95, 0.8]
+            .into_iter()
+            .map(|confidence| {
+                let order_tx = order_tx.clone();
+                pool.submit(confidence, move || Ok(order_tx.send(confidence)?)).unwrap()
+            })
+            .collect();
+        assert!(pool.submit(0.99, || Ok(())).is_err());
+        assert_eq!(pool.queued(), 3);
+
+        pool.apply_power_policy(&PowerPolicy::default());
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(order_rx.try_iter().collect::<Vec<_>>(), vec![0.95, 0.8, 0.6]);
+
+        // A failing job reports its error through the handle
+        let failed = pool.submit(0.5, || -> Result<()> { Err(anyhow::anyhow!("bad witness")) }).unwrap();
+        assert!(failed.await.is_err());
+    }
+}
@@ -0,0 +1,143 @@
+/*!
+ * Circuit profiler
+ * Circuits mark named regions (hash gadget, comparisons, reputation check) with `region`; when a
+ * circuit is synthesized under `profile`, each region's constraints, witness variables and
+ * synthesis time are recorded. Proving time is apportioned to regions by constraint share, since
+ * the prover's MSMs and FFTs grow with the constraint system. Outside `profile` regions cost
+ * nothing, and they never change the constraints. Regions do not nest
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, time::Instant};
+
+/// Region covering whatever no named region claimed, such as input allocation
+pub const UNATTRIBUTED: &str = "unattributed";
+
+thread_local! {
+    /// Regions of the circuit being profiled on this thread
+    static REGIONS: RefCell<Option<Vec<RegionProfile>>> = const { RefCell::new(None) };
+}
+
+/// Cost of one named circuit region
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionProfile {
+    pub name: String,
+    pub constraints: usize,
+    pub witness_variables: usize,
+    /// Witness generation and constraint synthesis
+    pub synthesis_ms: f64,
+    /// Share of the circuit's proving time, by constraints
+    pub proving_ms: f64,
+}
+
+/// Synthesize a named region, recording its cost when profiling
+pub fn region<T>(
+    cs: &ConstraintSystemRef<Fr>,
+    name: &'static str,
+    synthesize: impl FnOnce() -> Result<T, SynthesisError>,
+) -> Result<T, SynthesisError> {
+    if REGIONS.with(|regions| regions.borrow().is_none()) {
+        return synthesize();
+    }
+
+    let (constraints, witness_variables) = (cs.num_constraints(), cs.num_witness_variables());
+    let start = Instant::now();
+    let result = synthesize()?;
+    record(RegionProfile {
+        name: name.to_string(),
+        constraints: cs.num_constraints() - constraints,
+        witness_variables: cs.num_witness_variables() - witness_variables,
+        synthesis_ms: start.elapsed().as_secs_f64() * 1000.0,
+        proving_ms: 0.0,
+    });
+    Ok(result)
+}
+
+/// Regions entered more than once (one per batch slot, say) are summed
+fn record(profile: RegionProfile) {
+    REGIONS.with(|regions| {
+        let mut regions = regions.borrow_mut();
+        let Some(regions) = regions.as_mut() else {
+            return;
+        };
+        match regions.iter_mut().find(|region| region.name == profile.name) {
+            Some(region) => {
+                region.constraints += profile.constraints;
+                region.witness_variables += profile.witness_variables;
+                region.synthesis_ms += profile.synthesis_ms;
+            }
+            None => regions.push(profile),
+        }
+    });
+}
+
+/// Synthesize `circuit` with its assignment and cost each region, apportioning `proving_ms`;
+/// the regions, `UNATTRIBUTED` last, add up to the whole circuit
+pub fn profile<C: ConstraintSynthesizer<Fr>>(circuit: C, proving_ms: f64) -> Result<Vec<RegionProfile>> {
+    REGIONS.with(|regions| *regions.borrow_mut() = Some(Vec::new()));
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let start = Instant::now();
+    let synthesized = circuit.generate_constraints(cs.clone());
+    let synthesis_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let mut regions = REGIONS.with(|regions| regions.borrow_mut().take()).unwrap_or_default();
+    synthesized.context("Failed to synthesize circuit")?;
+
+    let rest = RegionProfile {
+        name: UNATTRIBUTED.to_string(),
+        constraints: cs.num_constraints() - regions.iter().map(|region| region.constraints).sum::<usize>(),
+        witness_variables: cs.num_witness_variables() - regions.iter().map(|region| region.witness_variables).sum::<usize>(),
+        synthesis_ms: (synthesis_ms - regions.iter().map(|region| region.synthesis_ms).sum::<f64>()).max(0.0),
+        proving_ms: 0.0,
+    };
+    regions.push(rest);
+
+    let total = cs.num_constraints().max(1) as f64;
+    for region in &mut regions {
+        region.proving_ms = proving_ms * region.constraints as f64 / total;
+    }
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::{
+        benchmark::constraint_counts, fixed_point::encode_confidence, mimc::mimc_hash, model::ModelCommitment,
+        transaction_fields, ThreatDetectionCircuit,
+    };
+
+    #[test]
+    fn test_threat_circuit_regions() {
+        let model = ModelCommitment::from_bytes(&[0x42; 4096]).unwrap();
+        let fields = transaction_fields(b"profiled transaction").unwrap();
+        let hash = mimc_hash(Fr::from(0u64), &fields);
+        let circuit = ThreatDetectionCircuit {
+            threat_hash: Some(hash),
+            confidence_threshold: Some(encode_confidence(0.7).unwrap()),
+            model_commitment: Some(model.commitment()),
+            transaction_data: Some(fields),
+            ai_model_weights: Some(model.witness(&hash).unwrap()),
+            node_reputation: Some(encode_confidence(0.95).unwrap()),
+            detection_algorithm: Some(encode_confidence(0.9).unwrap()),
+        };
+
+        let regions = profile(circuit, 100.0).unwrap();
+        let names: Vec<&str> = regions.iter().map(|region| region.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["fixed-point range checks", "threshold comparison", "reputation check", "transaction hash", "model openings", UNATTRIBUTED]
+        );
+
+        // Regions partition the circuit and its proving time
+        let counts = constraint_counts(ThreatDetectionCircuit::default()).unwrap();
+        assert_eq!(regions.iter().map(|region| region.constraints).sum::<usize>(), counts.constraints);
+        assert_eq!(regions.iter().map(|region| region.witness_variables).sum::<usize>(), counts.witness_variables);
+        assert!((regions.iter().map(|region| region.proving_ms).sum::<f64>() - 100.0).abs() < 1e-6);
+
+        // Outside profiling nothing is recorded
+        assert!(REGIONS.with(|regions| regions.borrow().is_none()));
+    }
+}
@@ -0,0 +1,236 @@
+/*!
+ * Persistent proof cache
+ * Threat proofs are kept in an embedded sled database keyed by proof id, with an index by threat
+ * hash so an identical threat reuses its proof. Entries expire after a TTL and the least recently
+ * used are evicted beyond a size limit
+ */
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::debug;
+
+use super::ThreatProof;
+
+/// Proof cache limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofCacheConfig {
+    /// Seconds a proof stays reusable after it was generated
+    pub ttl_secs: u64,
+    /// Least recently used proofs are evicted beyond this many
+    pub max_entries: usize,
+}
+
+impl Default for ProofCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 7 * 24 * 3600,
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    proof: ThreatProof,
+    threat_hash: String,
+    created_at: u64,
+    /// Position in the LRU tree
+    access_key: [u8; 16],
+}
+
+/// Key ordering = access ordering; the sequence suffix keeps same-second accesses apart
+fn access_key(timestamp: u64, sequence: u64) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    key[..8].copy_from_slice(&timestamp.to_be_bytes());
+    key[8..].copy_from_slice(&sequence.to_be_bytes());
+    key
+}
+
+/// sled-backed threat proof cache
+pub struct ProofCache {
+    db: sled::Db,
+    /// proof id -> entry
+    proofs: sled::Tree,
+    /// threat hash -> proof id
+    threats: sled::Tree,
+    /// access key -> proof id, least recently used first
+    lru: sled::Tree,
+    config: ProofCacheConfig,
+}
+
+impl ProofCache {
+    /// Open (or create) the cache at `path`
+    pub fn open<P: AsRef<Path>>(path: P, config: ProofCacheConfig) -> Result<Self> {
+        let db = sled::open(path.as_ref())
+            .with_context(|| format!("Failed to open proof cache at {}", path.as_ref().display()))?;
+        Self::with_db(db, config)
+    }
+
+    fn with_db(db: sled::Db, config: ProofCacheConfig) -> Result<Self> {
+        Ok(Self {
+            proofs: db.open_tree("proofs")?,
+            threats: db.open_tree("proofs_by_threat")?,
+            lru: db.open_tree("proofs_lru")?,
+            db,
+            config,
+        })
+    }
+
+    /// Store `proof` under `proof_id`, evicting the least recently used beyond the size limit
+    pub fn insert(&self, proof_id: &str, proof: &ThreatProof, now: u64) -> Result<()> {
+        let threat_hash = proof.public_inputs.first().context("Proof has no threat hash")?.clone();
+        if let Some(previous) = self.threats.get(&threat_hash)? {
+            self.remove(&String::from_utf8_lossy(&previous))?;
+        }
+        self.remove(proof_id)?;
+
+        let entry = Entry {
+            proof: proof.clone(),
+            threat_hash: threat_hash.clone(),
+            created_at: now,
+            access_key: access_key(now, self.db.generate_id()?),
+        };
+        self.proofs.insert(proof_id, serde_json::to_vec(&entry)?)?;
+        self.threats.insert(threat_hash, proof_id)?;
+        self.lru.insert(entry.access_key, proof_id)?;
+
+        while self.proofs.len() > self.config.max_entries {
+            let Some((_, oldest)) = self.lru.first()? else { break };
+            self.remove(&String::from_utf8_lossy(&oldest))?;
+            debug!("🗑️ Evicted least recently used proof {}", String::from_utf8_lossy(&oldest));
+        }
+        Ok(())
+    }
+
+    /// Unexpired proof for `threat_hash` (an encoded public input), marking it recently used
+    pub fn get_by_threat(&self, threat_hash: &str, now: u64) -> Result<Option<ThreatProof>> {
+        let Some(proof_id) = self.threats.get(threat_hash)? else {
+            return Ok(None);
+        };
+        let proof_id = String::from_utf8_lossy(&proof_id).into_owned();
+        let Some(raw) = self.proofs.get(&proof_id)? else {
+            return Ok(None);
+        };
+
+        let mut entry: Entry = serde_json::from_slice(&raw)?;
+        if self.is_expired(&entry, now) {
+            self.remove(&proof_id)?;
+            return Ok(None);
+        }
+
+        self.lru.remove(entry.access_key)?;
+        entry.access_key = access_key(now, self.db.generate_id()?);
+        self.lru.insert(entry.access_key, proof_id.as_str())?;
+        self.proofs.insert(&proof_id, serde_json::to_vec(&entry)?)?;
+        Ok(Some(entry.proof))
+    }
+
+    /// Delete a proof and its index entries
+    pub fn remove(&self, proof_id: &str) -> Result<()> {
+        let Some(raw) = self.proofs.remove(proof_id)? else {
+            return Ok(());
+        };
+        let entry: Entry = serde_json::from_slice(&raw)?;
+        self.lru.remove(entry.access_key)?;
+        if self.threats.get(&entry.threat_hash)?.as_deref() == Some(proof_id.as_bytes()) {
+            self.threats.remove(&entry.threat_hash)?;
+        }
+        Ok(())
+    }
+
+    /// Delete proofs past the TTL; returns how many were removed
+    pub fn prune(&self, now: u64) -> Result<usize> {
+        let mut expired = Vec::new();
+        for item in self.proofs.iter() {
+            let (proof_id, raw) = item?;
+            if self.is_expired(&serde_json::from_slice(&raw)?, now) {
+                expired.push(String::from_utf8_lossy(&proof_id).into_owned());
+            }
+        }
+        for proof_id in &expired {
+            self.remove(proof_id)?;
+        }
+
+        if !expired.is_empty() {
+            debug!("🗑️ Pruned {} proofs past their TTL", expired.len());
+        }
+        Ok(expired.len())
+    }
+
+    fn is_expired(&self, entry: &Entry, now: u64) -> bool {
+        now.saturating_sub(entry.created_at) >= self.config.ttl_secs
+    }
+
+    /// Number of cached proofs
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.proofs.clear()?;
+        self.threats.clear()?;
+        self.lru.clear()?;
+        Ok(())
+    }
+
+    /// Flush pending writes to disk
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::{backend::ProvingBackend, PUBLIC_INPUT_ENCODING, THREAT_CIRCUIT_VERSION};
+
+    fn proof(threat_hash: &str) -> ThreatProof {
+        ThreatProof {
+            proof: vec![1, 2, 3],
+            public_inputs: vec![threat_hash.to_string(), "threshold".to_string()],
+            public_input_encoding: PUBLIC_INPUT_ENCODING,
+            circuit_version: THREAT_CIRCUIT_VERSION,
+            backend: ProvingBackend::Groth16,
+            verification_key_hash: "vk".to_string(),
+            timestamp: 0,
+            expires_at: 0,
+            node_id: "node".to_string(),
+            delegated: false,
+        }
+    }
+
+    #[test]
+    fn test_ttl_lru_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ProofCacheConfig { ttl_secs: 100, max_entries: 2 };
+        let cache = ProofCache::open(dir.path(), config.clone()).unwrap();
+
+        cache.insert("a", &proof("threat-a"), 0).unwrap();
+        cache.insert("b", &proof("threat-b"), 10).unwrap();
+        // Touching `a` makes `b` the least recently used
+        assert!(cache.get_by_threat("threat-a", 20).unwrap().is_some());
+        cache.insert("c", &proof("threat-c"), 30).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_by_threat("threat-b", 30).unwrap().is_none());
+
+        // Re-proving a threat replaces its old proof
+        cache.insert("c2", &proof("threat-c"), 40).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        cache.flush().unwrap();
+        drop(cache);
+        let cache = ProofCache::open(dir.path(), config).unwrap();
+        assert!(cache.get_by_threat("threat-c", 50).unwrap().is_some());
+
+        // TTL runs from creation, not last use
+        assert!(cache.get_by_threat("threat-a", 100).unwrap().is_none());
+        assert_eq!(cache.prune(140).unwrap(), 1);
+        assert!(cache.is_empty());
+    }
+}
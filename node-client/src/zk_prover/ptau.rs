@@ -0,0 +1,405 @@
+/*!
+ * Powers of Tau import
+ * Reads a community phase-1 transcript in the snarkjs `.ptau` format and derives Groth16 keys
+ * for a circuit from it, so no single party knows the toxic waste behind tau, alpha or beta.
+ * Derived keys have delta = gamma = 1 and still need a phase-2 contribution before use
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInt, BigInteger, One, PrimeField, UniformRand, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"ptau";
+const FIELD_BYTES: usize = 32;
+
+// Section ids in the snarkjs layout
+const SECTION_HEADER: u32 = 1;
+const SECTION_TAU_G1: u32 = 2;
+const SECTION_TAU_G2: u32 = 3;
+const SECTION_ALPHA_TAU_G1: u32 = 4;
+const SECTION_BETA_TAU_G1: u32 = 5;
+const SECTION_BETA_G2: u32 = 6;
+
+/// keccak256 of a transcript, as registered on chain
+pub fn transcript_hash(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+/// Parsed phase-1 transcript; points are decoded on demand
+pub struct PowersOfTau<'a> {
+    /// The transcript holds powers up to 2^power
+    pub power: u32,
+    sections: HashMap<u32, &'a [u8]>,
+}
+
+impl<'a> PowersOfTau<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < 12 || &bytes[..4] != MAGIC {
+            return Err(anyhow::anyhow!("Not a Powers of Tau file"));
+        }
+        let num_sections = read_u32(bytes, 8)?;
+
+        let mut sections = HashMap::new();
+        let mut offset = 12;
+        for _ in 0..num_sections {
+            let id = read_u32(bytes, offset)?;
+            let size = read_u64(bytes, offset + 4)? as usize;
+            let start = offset + 12;
+            let data = bytes
+                .get(start..start.saturating_add(size))
+                .with_context(|| format!("Section {} is truncated", id))?;
+            sections.insert(id, data);
+            offset = start + size;
+        }
+
+        let header = sections.get(&SECTION_HEADER).context("Missing header section")?;
+        if read_u32(header, 0)? as usize != FIELD_BYTES {
+            return Err(anyhow::anyhow!("Transcript is not over a 254-bit curve"));
+        }
+        let modulus = header.get(4..4 + FIELD_BYTES).context("Truncated header")?;
+        if modulus != &Fq::MODULUS.to_bytes_le()[..] {
+            return Err(anyhow::anyhow!("Transcript is not over BN254"));
+        }
+        let power = read_u32(header, 4 + FIELD_BYTES)?;
+
+        Ok(Self { power, sections })
+    }
+
+    /// Largest evaluation domain the transcript supports
+    pub fn max_domain_size(&self) -> usize {
+        1 << self.power
+    }
+
+    fn section(&self, id: u32, count: usize, point_size: usize) -> Result<&'a [u8]> {
+        let data = self.sections.get(&id).with_context(|| format!("Missing section {}", id))?;
+        data.get(..count * point_size)
+            .with_context(|| format!("Section {} holds fewer than {} points", id, count))
+    }
+
+    fn g1_points(&self, id: u32, count: usize) -> Result<Vec<G1Affine>> {
+        self.section(id, count, 2 * FIELD_BYTES)?
+            .chunks_exact(2 * FIELD_BYTES)
+            .map(read_g1)
+            .collect()
+    }
+
+    fn g2_points(&self, id: u32, count: usize) -> Result<Vec<G2Affine>> {
+        self.section(id, count, 4 * FIELD_BYTES)?
+            .chunks_exact(4 * FIELD_BYTES)
+            .map(read_g2)
+            .collect()
+    }
+
+    /// Derive Groth16 keys for `circuit`, checking the powers it uses are consistent
+    pub fn derive_parameters<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        circuit: C,
+    ) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone()).context("Constraint synthesis failed")?;
+        cs.finalize();
+
+        // Same domain and QAP layout as ark-groth16's libsnark reduction
+        let num_constraints = cs.num_constraints();
+        let num_instance = cs.num_instance_variables();
+        let domain = GeneralEvaluationDomain::<Fr>::new(num_constraints + num_instance)
+            .context("Circuit too large for an evaluation domain")?;
+        let n = domain.size();
+        if n > self.max_domain_size() {
+            return Err(anyhow::anyhow!(
+                "Circuit needs 2^{} powers, transcript has 2^{}",
+                n.trailing_zeros(),
+                self.power
+            ));
+        }
+
+        let tau_g1 = self.g1_points(SECTION_TAU_G1, 2 * n - 1)?;
+        let tau_g2 = self.g2_points(SECTION_TAU_G2, n)?;
+        let alpha_tau_g1 = self.g1_points(SECTION_ALPHA_TAU_G1, n)?;
+        let beta_tau_g1 = self.g1_points(SECTION_BETA_TAU_G1, n)?;
+        let beta_g2 = self.g2_points(SECTION_BETA_G2, 1)?[0];
+        check_powers(&tau_g1, &tau_g2, &alpha_tau_g1, &beta_tau_g1, beta_g2)?;
+
+        // Lagrange basis over the domain: [L_j(tau)] is the inverse FFT of [tau^i]
+        let lagrange_g1 = |points: &[G1Affine]| {
+            let mut points: Vec<G1Projective> = points.iter().map(|p| p.into_group()).collect();
+            domain.ifft_in_place(&mut points);
+            points
+        };
+        let l_tau_g1 = lagrange_g1(&tau_g1[..n]);
+        let l_alpha_g1 = lagrange_g1(&alpha_tau_g1);
+        let l_beta_g1 = lagrange_g1(&beta_tau_g1);
+        let mut l_tau_g2: Vec<G2Projective> = tau_g2.iter().map(|p| p.into_group()).collect();
+        domain.ifft_in_place(&mut l_tau_g2);
+
+        let matrices = cs.to_matrices().context("Failed to build constraint matrices")?;
+        let num_variables = num_instance + cs.num_witness_variables();
+        let mut a_query = vec![G1Projective::zero(); num_variables];
+        let mut b_g1_query = vec![G1Projective::zero(); num_variables];
+        let mut b_g2_query = vec![G2Projective::zero(); num_variables];
+        // beta * u_i(tau) + alpha * v_i(tau) + w_i(tau)
+        let mut abc = vec![G1Projective::zero(); num_variables];
+
+        // Public inputs also appear in A at the rows after the constraints
+        for i in 0..num_instance {
+            a_query[i] += l_tau_g1[num_constraints + i];
+            abc[i] += l_beta_g1[num_constraints + i];
+        }
+        for (row, ((a, b), c)) in matrices.a.iter().zip(&matrices.b).zip(&matrices.c).enumerate() {
+            for &(coeff, var) in a {
+                a_query[var] += scale(l_tau_g1[row], coeff);
+                abc[var] += scale(l_beta_g1[row], coeff);
+            }
+            for &(coeff, var) in b {
+                b_g1_query[var] += scale(l_tau_g1[row], coeff);
+                b_g2_query[var] += scale(l_tau_g2[row], coeff);
+                abc[var] += scale(l_alpha_g1[row], coeff);
+            }
+            for &(coeff, var) in c {
+                abc[var] += scale(l_tau_g1[row], coeff);
+            }
+        }
+
+        // tau^i * t(tau) with t(x) = x^n - 1
+        let h_query: Vec<G1Projective> = (0..n - 1).map(|i| tau_g1[n + i].into_group() - tau_g1[i]).collect();
+
+        let vk = VerifyingKey::<Bn254> {
+            alpha_g1: alpha_tau_g1[0],
+            beta_g2,
+            gamma_g2: G2Affine::generator(),
+            delta_g2: G2Affine::generator(),
+            gamma_abc_g1: G1Projective::normalize_batch(&abc[..num_instance]),
+        };
+        let pk = ProvingKey {
+            vk: vk.clone(),
+            beta_g1: beta_tau_g1[0],
+            delta_g1: G1Affine::generator(),
+            a_query: G1Projective::normalize_batch(&a_query),
+            b_g1_query: G1Projective::normalize_batch(&b_g1_query),
+            b_g2_query: G2Projective::normalize_batch(&b_g2_query),
+            h_query: G1Projective::normalize_batch(&h_query),
+            l_query: G1Projective::normalize_batch(&abc[num_instance..]),
+        };
+
+        Ok((pk, vk))
+    }
+}
+
+/// Most coefficients are one, so skip the scalar multiplication for them
+fn scale<G: CurveGroup<ScalarField = Fr>>(point: G, coeff: Fr) -> G {
+    if coeff.is_one() {
+        point
+    } else {
+        point * coeff
+    }
+}
+
+/// Check consecutive powers share one tau and the alpha/beta powers follow them, using a
+/// random linear combination per sequence
+fn check_powers(
+    tau_g1: &[G1Affine],
+    tau_g2: &[G2Affine],
+    alpha_tau_g1: &[G1Affine],
+    beta_tau_g1: &[G1Affine],
+    beta_g2: G2Affine,
+) -> Result<()> {
+    if tau_g1[0] != G1Affine::generator() || tau_g2[0] != G2Affine::generator() {
+        return Err(anyhow::anyhow!("Transcript does not start at the group generators"));
+    }
+
+    let mut rng = ark_std::rand::thread_rng();
+    let mut combine = |points: &[G1Affine]| -> (G1Projective, G1Projective) {
+        let weights: Vec<Fr> = (1..points.len()).map(|_| Fr::rand(&mut rng)).collect();
+        (
+            G1Projective::msm_unchecked(&points[..points.len() - 1], &weights),
+            G1Projective::msm_unchecked(&points[1..], &weights),
+        )
+    };
+
+    let (tau_lo, tau_hi) = combine(tau_g1);
+    let (alpha_lo, alpha_hi) = combine(alpha_tau_g1);
+    let weights: Vec<Fr> = (1..tau_g2.len()).map(|_| Fr::rand(&mut rng)).collect();
+    let tau_g2_lo = G2Projective::msm_unchecked(&tau_g2[..tau_g2.len() - 1], &weights);
+    let tau_g2_hi = G2Projective::msm_unchecked(&tau_g2[1..], &weights);
+
+    let g1 = G1Affine::generator();
+    let g2 = G2Affine::generator();
+    let consistent = same_ratio((tau_lo, tau_hi), (g2, tau_g2[1]))
+        && same_ratio((g1, tau_g1[1]), (tau_g2_lo, tau_g2_hi))
+        && same_ratio((alpha_lo, alpha_hi), (g2, tau_g2[1]))
+        && same_ratio((tau_g1[0], beta_tau_g1[0]), (g2, beta_g2))
+        && same_ratio((tau_g1[1], beta_tau_g1[1]), (g2, beta_g2));
+
+    if consistent {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Transcript powers are inconsistent"))
+    }
+}
+
+/// e(a.0, b.1) == e(a.1, b.0), i.e. both pairs differ by the same secret factor
+pub(crate) fn same_ratio(
+    a: (impl Into<G1Affine>, impl Into<G1Affine>),
+    b: (impl Into<G2Affine>, impl Into<G2Affine>),
+) -> bool {
+    Bn254::pairing(a.0.into(), b.1.into()) == Bn254::pairing(a.1.into(), b.0.into())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    let raw = bytes.get(offset..offset + 4).context("Unexpected end of transcript")?;
+    Ok(u32::from_le_bytes(raw.try_into()?))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64> {
+    let raw = bytes.get(offset..offset + 8).context("Unexpected end of transcript")?;
+    Ok(u64::from_le_bytes(raw.try_into()?))
+}
+
+/// Field element in little-endian Montgomery form (the same R as arkworks)
+fn read_fq(bytes: &[u8]) -> Result<Fq> {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into()?);
+    }
+    let repr = BigInt(limbs);
+    if repr >= Fq::MODULUS {
+        return Err(anyhow::anyhow!("Coordinate is not a canonical field element"));
+    }
+    Ok(Fq::new_unchecked(repr))
+}
+
+fn read_g1(bytes: &[u8]) -> Result<G1Affine> {
+    let (x, y) = (read_fq(&bytes[..FIELD_BYTES])?, read_fq(&bytes[FIELD_BYTES..])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::identity());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(anyhow::anyhow!("G1 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+fn read_g2(bytes: &[u8]) -> Result<G2Affine> {
+    let x = Fq2::new(read_fq(&bytes[..32])?, read_fq(&bytes[32..64])?);
+    let y = Fq2::new(read_fq(&bytes[64..96])?, read_fq(&bytes[96..])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::identity());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(anyhow::anyhow!("G2 point is not in the prime-order subgroup"));
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::Field;
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+    use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+    /// x^3 + x + 5 == out, with `out` public
+    #[derive(Clone, Default)]
+    struct CubicCircuit {
+        x: Option<Fr>,
+    }
+
+    impl ConstraintSynthesizer<Fr> for CubicCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let out = self.x.map(|x| x * x * x + x + Fr::from(5u64));
+            let out = FpVar::new_input(cs.clone(), || out.ok_or(SynthesisError::AssignmentMissing))?;
+            let x = FpVar::new_witness(cs, || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            (&x * &x * &x + &x + FpVar::constant(Fr::from(5u64))).enforce_equal(&out)
+        }
+    }
+
+    fn write_fq(out: &mut Vec<u8>, value: Fq) {
+        out.extend(value.0 .0.iter().flat_map(|limb| limb.to_le_bytes()));
+    }
+
+    fn write_g1(out: &mut Vec<u8>, point: G1Affine) {
+        write_fq(out, point.x);
+        write_fq(out, point.y);
+    }
+
+    fn write_g2(out: &mut Vec<u8>, point: G2Affine) {
+        for coordinate in [point.x.c0, point.x.c1, point.y.c0, point.y.c1] {
+            write_fq(out, coordinate);
+        }
+    }
+
+    /// Transcript for known secrets in the snarkjs layout
+    fn transcript(power: u32, tau: Fr, alpha: Fr, beta: Fr) -> Vec<u8> {
+        let size = 1usize << power;
+        let powers: Vec<Fr> = (0..2 * size - 1).map(|i| tau.pow([i as u64])).collect();
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+
+        let mut header = vec![32, 0, 0, 0];
+        header.extend(Fq::MODULUS.to_bytes_le());
+        header.extend(power.to_le_bytes());
+        header.extend(power.to_le_bytes());
+
+        let mut sections = vec![(SECTION_HEADER, header)];
+        let mut g1_section = |id, scalars: &mut dyn Iterator<Item = Fr>| {
+            let mut data = Vec::new();
+            scalars.for_each(|s| write_g1(&mut data, (g1 * s).into_affine()));
+            sections.push((id, data));
+        };
+        g1_section(SECTION_TAU_G1, &mut powers.iter().copied());
+        g1_section(SECTION_ALPHA_TAU_G1, &mut powers[..size].iter().map(|p| alpha * p));
+        g1_section(SECTION_BETA_TAU_G1, &mut powers[..size].iter().map(|p| beta * p));
+        let mut tau_g2 = Vec::new();
+        powers[..size].iter().for_each(|p| write_g2(&mut tau_g2, (g2 * p).into_affine()));
+        sections.push((SECTION_TAU_G2, tau_g2));
+        let mut beta_g2 = Vec::new();
+        write_g2(&mut beta_g2, (g2 * beta).into_affine());
+        sections.push((SECTION_BETA_G2, beta_g2));
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend((sections.len() as u32).to_le_bytes());
+        for (id, data) in sections {
+            bytes.extend(id.to_le_bytes());
+            bytes.extend((data.len() as u64).to_le_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_derived_parameters_prove_and_verify() {
+        let mut rng = ark_std::test_rng();
+        let (tau, alpha, beta) = (Fr::rand(&mut rng), Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let bytes = transcript(3, tau, alpha, beta);
+        let ptau = PowersOfTau::parse(&bytes).unwrap();
+        assert_eq!(ptau.max_domain_size(), 8);
+
+        let (pk, vk) = ptau.derive_parameters(CubicCircuit::default()).unwrap();
+        let x = Fr::from(3u64);
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(CubicCircuit { x: Some(x) }, &pk, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(35u64)]).unwrap());
+        assert!(!Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(36u64)]).unwrap());
+
+        // A transcript with a broken power is rejected
+        let mut tampered = bytes.clone();
+        // File header, header section, tau_g1 section header, first point
+        let second_point = 12 + (12 + 44) + 12 + 64;
+        tampered[second_point..second_point + 64].copy_from_slice(&bytes[second_point + 64..second_point + 128]);
+        let tampered = PowersOfTau::parse(&tampered).unwrap();
+        assert!(tampered.derive_parameters(CubicCircuit::default()).is_err());
+        assert_ne!(transcript_hash(&bytes), transcript_hash(&bytes[1..]));
+    }
+}
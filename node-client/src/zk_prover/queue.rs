@@ -0,0 +1,245 @@
+/*!
+ * Threat proof queue
+ * Sits in front of the proving pool for threat proofs. A request for a threat already being
+ * proved waits for that proof instead of proving it again, every request carries a deadline so a
+ * proof nobody can use any more is dropped instead of proved late (confident detections are worth
+ * proving later than marginal ones, so their deadlines are longer), and low-confidence requests
+ * are shed while the pool is backlogged. Its depth and drop counts are exported as metrics, and
+ * the deadlines of held jobs keep one proof running under power policies that defer proofs
+ */
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use super::pool::ProverPool;
+
+/// Threat proof queue settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofQueueConfig {
+    /// Seconds a threat proof request at full confidence may wait for a worker
+    pub deadline_secs: u64,
+    /// Seconds a request at zero confidence may wait; deadlines scale linearly in between
+    #[serde(default = "default_min_deadline")]
+    pub min_deadline_secs: u64,
+    /// Pool jobs waiting at which the queue counts as backlogged
+    pub backlog_high_water: usize,
+    /// Requests below this confidence are refused while backlogged
+    pub shed_below_confidence: f64,
+}
+
+impl Default for ProofQueueConfig {
+    fn default() -> Self {
+        Self {
+            deadline_secs: 300,
+            min_deadline_secs: default_min_deadline(),
+            backlog_high_water: 48,
+            shed_below_confidence: 0.9,
+        }
+    }
+}
+
+fn default_min_deadline() -> u64 {
+    30
+}
+
+/// Queue depth and drop counts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProofQueueStats {
+    /// Pool jobs waiting for a worker
+    pub queued: usize,
+    pub running: usize,
+    /// Pool jobs waiting beyond this are rejected
+    pub capacity: usize,
+    /// Distinct threats being proved, queued or running
+    pub in_flight: usize,
+    /// Requests that joined a proof already in flight
+    pub coalesced: u64,
+    /// Requests refused while backlogged
+    pub shed: u64,
+    /// Jobs dropped at their deadline
+    pub expired: u64,
+}
+
+impl ProofQueueStats {
+    /// Fraction of the pool queue in use
+    pub fn backlog(&self) -> f64 {
+        self.queued as f64 / self.capacity.max(1) as f64
+    }
+}
+
+/// Requests waiting on a proof in flight; errors are passed on as their message
+type Waiters<T> = Vec<oneshot::Sender<Result<T, String>>>;
+
+/// Coalescing, deadline-aware admission in front of the proving pool
+pub struct ProofQueue<T> {
+    config: ProofQueueConfig,
+    in_flight: Mutex<HashMap<String, Waiters<T>>>,
+    coalesced: AtomicU64,
+    shed: AtomicU64,
+}
+
+impl<T: Clone> ProofQueue<T> {
+    pub fn new(config: ProofQueueConfig) -> Self {
+        Self {
+            config,
+            in_flight: Mutex::new(HashMap::new()),
+            coalesced: AtomicU64::new(0),
+            shed: AtomicU64::new(0),
+        }
+    }
+
+    /// Deadline of a request made now at `confidence`
+    pub fn deadline(&self, confidence: f64) -> Instant {
+        let min = self.config.min_deadline_secs.min(self.config.deadline_secs) as f64;
+        let span = self.config.deadline_secs as f64 - min;
+        Instant::now() + Duration::from_secs_f64(min + span * confidence.clamp(0.0, 1.0))
+    }
+
+    /// Prove `key` with `prove`, or wait for the proof of `key` already in flight; the first
+    /// request's priority and deadline stand for every request it serves
+    pub async fn run<F, Fut>(&self, key: String, confidence: f64, pool: &ProverPool, prove: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let joined = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get_mut(&key) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    let queued = pool.queued();
+                    if queued >= self.config.backlog_high_water && confidence < self.config.shed_below_confidence {
+                        self.shed.fetch_add(1, Ordering::Relaxed);
+                        return Err(anyhow::anyhow!(
+                            "Proving queue backlogged ({} jobs); confidence {:.2} is below {:.2}",
+                            queued,
+                            confidence,
+                            self.config.shed_below_confidence
+                        ));
+                    }
+                    in_flight.insert(key.clone(), Vec::new());
+                    None
+                }
+            }
+        };
+
+        if let Some(receiver) = joined {
+            self.coalesced.fetch_add(1, Ordering::Relaxed);
+            debug!("🔗 Joined the proof already in flight for this threat");
+            return match receiver.await {
+                Ok(result) => result.map_err(anyhow::Error::msg),
+                Err(_) => Err(anyhow::anyhow!("Proof this request joined was abandoned")),
+            };
+        }
+
+        let ticket = InFlight { queue: self, key };
+        let result = prove().await;
+        ticket.finish(&result);
+        result
+    }
+
+    pub fn stats(&self, pool: &ProverPool) -> ProofQueueStats {
+        ProofQueueStats {
+            queued: pool.queued(),
+            running: pool.running(),
+            capacity: pool.capacity(),
+            in_flight: self.in_flight.lock().unwrap().len(),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+            expired: pool.expired(),
+        }
+    }
+}
+
+/// The proof in flight for a key; dropping it unfinished fails the requests that joined it
+struct InFlight<'a, T> {
+    queue: &'a ProofQueue<T>,
+    key: String,
+}
+
+impl<T: Clone> InFlight<'_, T> {
+    fn finish(self, result: &Result<T>) {
+        let waiters = self.queue.in_flight.lock().unwrap().remove(&self.key).unwrap_or_default();
+        for waiter in waiters {
+            let shared = match result {
+                Ok(proof) => Ok(proof.clone()),
+                Err(e) => Err(format!("{:#}", e)),
+            };
+            let _ = waiter.send(shared);
+        }
+    }
+}
+
+impl<T> Drop for InFlight<'_, T> {
+    fn drop(&mut self) {
+        if let Some(waiters) = self.queue.in_flight.lock().unwrap().remove(&self.key) {
+            if !waiters.is_empty() {
+                warn!("⚠️ Abandoned a proof {} other requests were waiting for", waiters.len());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy_monitor::power_policy::{PowerMode, PowerPolicy};
+    use crate::zk_prover::pool::ProverPoolConfig;
+
+    #[tokio::test]
+    async fn test_duplicates_coalesce_and_backlog_sheds() {
+        let pool = ProverPool::new(ProverPoolConfig {
+            workers: 1,
+            ..ProverPoolConfig::default()
+        });
+        let queue = ProofQueue::new(ProofQueueConfig {
+            backlog_high_water: 0,
+            ..ProofQueueConfig::default()
+        });
+
+        // The second request for the threat joins the first instead of proving again
+        let (release, released) = oneshot::channel::<()>();
+        let first = queue.run("threat".to_string(), 0.95, &pool, || async {
+            released.await?;
+            Ok(7)
+        });
+        let second = queue.run("threat".to_string(), 0.95, &pool, || async { Ok(8) });
+        let (first, second, _) = tokio::join!(first, second, async { release.send(()).unwrap() });
+        assert_eq!((first.unwrap(), second.unwrap()), (7, 7));
+
+        // Anything below the shedding confidence is refused while backlogged
+        assert!(queue.run("other".to_string(), 0.5, &pool, || async { Ok(9) }).await.is_err());
+
+        let stats = queue.stats(&pool);
+        assert_eq!((stats.coalesced, stats.shed, stats.in_flight), (1, 1, 0));
+
+        // Marginal detections give up sooner than confident ones
+        assert!(queue.deadline(0.5) < queue.deadline(0.95));
+        assert!(queue.deadline(0.0) >= Instant::now() + Duration::from_secs(29));
+
+        // A job still queued at its deadline fails without running
+        pool.apply_power_policy(&PowerPolicy {
+            mode: PowerMode::Minimal,
+            ..PowerPolicy::default()
+        });
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let expired = pool.submit_before(0.95, Some(deadline), || Ok(())).unwrap();
+        assert!(expired.await.is_err());
+        assert_eq!(queue.stats(&pool).expired, 1);
+    }
+}
@@ -0,0 +1,404 @@
+/*!
+ * Proof relay
+ * Nodes too weak to prove hand threat proofs to a relay prover peer. The node hashes the
+ * transaction itself and sends a blinded witness: the MiMC chain state before the last (padding)
+ * transaction field instead of the data, plus its confidences and model openings. The relay
+ * proves the delegated threat circuit, which finishes the hash from that state, and the node
+ * verifies the proof before submitting it. The chain state is as one-way as the public threat
+ * hash, so the relay learns nothing about the transaction it could not learn from the proof.
+ * Requests are signed by the requesting node's operator key and only served for nodes staked in
+ * the node registry. A delegating node generates no threat keys: the relay returns its verifying
+ * key, which is used only if it matches the key registered on chain. Requests and responses are
+ * JSON lines over TCP
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, Signature, U256},
+    utils::keccak256,
+};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+use tracing::{debug, info, warn};
+
+use super::backend::ProvingBackend;
+use super::mimc::{mimc_hash, mimc_hash_var};
+use super::model::{enforce_model_openings, ModelWitness};
+use super::profiler::region;
+use super::{enforce_threat_confidences, ZKProver, TX_DATA_CHUNKS};
+
+/// Delegated threat circuit constraint system version; bump whenever its constraints change.
+/// Version 1 opened 2 model chunks instead of 16
+pub const DELEGATED_CIRCUIT_VERSION: u32 = 2;
+
+/// Longest request or response line accepted
+const MAX_MESSAGE_BYTES: u64 = 1 << 20;
+
+/// Highest proving priority a relayed job gets; the relay's own threat proofs are prioritised by
+/// their confidence, at least the 0.7 report threshold, so relayed jobs never jump ahead of them
+pub const MAX_RELAYED_PRIORITY: f64 = 0.5;
+
+/// Registered operator and stake of a node id in the node registry
+pub type StakeLookup = Arc<dyn Fn(String) -> BoxFuture<'static, Result<(Address, U256)>> + Send + Sync>;
+
+/// Relay prover peer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayPeer {
+    pub node_id: String,
+    /// `host:port` of its relay listener
+    pub address: String,
+}
+
+/// Proof delegation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayConfig {
+    /// Prove threat proofs through `provers` instead of locally
+    pub delegate: bool,
+    /// Trusted or staked provers, tried in order; their proofs are verified before use
+    pub provers: Vec<RelayPeer>,
+    /// Serve relay requests from other nodes on this address
+    pub listen: Option<SocketAddr>,
+    pub timeout_secs: u64,
+    /// Requests proved at once when serving; further connections are turned away
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+    /// Stake (wei) a requesting node needs in the node registry to be served
+    #[serde(default)]
+    pub min_client_stake: U256,
+}
+
+fn default_max_concurrent() -> usize {
+    4
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            delegate: false,
+            provers: Vec::new(),
+            listen: None,
+            timeout_secs: 120,
+            max_concurrent: default_max_concurrent(),
+            min_client_stake: U256::zero(),
+        }
+    }
+}
+
+/// What a relay prover sees of a threat: no transaction data
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BlindedWitness {
+    /// MiMC chain state over every transaction field but the last, which is zero padding
+    pub midstate: Fr,
+    pub confidence_threshold: Fr,
+    pub model_commitment: Fr,
+    pub detection: Fr,
+    pub node_reputation: Fr,
+    pub model: ModelWitness,
+}
+
+impl BlindedWitness {
+    /// Chain state of `transaction_fields`; data that fills the last field cannot be delegated
+    pub fn midstate(fields: &[Fr]) -> Result<Fr> {
+        match fields.split_last() {
+            Some((last, prefix)) if fields.len() == TX_DATA_CHUNKS + 1 && *last == Fr::from(0u64) => {
+                Ok(mimc_hash(Fr::from(0u64), prefix))
+            }
+            _ => Err(anyhow::anyhow!(
+                "Only transactions of at most {} bytes can be proved by a relay",
+                (TX_DATA_CHUNKS - 1) * 31
+            )),
+        }
+    }
+
+    /// `mimc_hash` of the transaction fields, finished from the midstate
+    pub fn threat_hash(&self) -> Fr {
+        mimc_hash(self.midstate, &[Fr::from(0u64)])
+    }
+
+    pub fn public_inputs(&self) -> Vec<Fr> {
+        vec![self.threat_hash(), self.confidence_threshold, self.model_commitment]
+    }
+
+    pub fn circuit(self) -> DelegatedThreatCircuit {
+        DelegatedThreatCircuit {
+            threat_hash: Some(self.threat_hash()),
+            confidence_threshold: Some(self.confidence_threshold),
+            model_commitment: Some(self.model_commitment),
+            midstate: Some(self.midstate),
+            ai_model_weights: Some(self.model),
+            node_reputation: Some(self.node_reputation),
+            detection_algorithm: Some(self.detection),
+        }
+    }
+}
+
+/// Threat circuit with the transaction hash finished from a midstate; same public inputs
+#[derive(Clone, Debug, Default)]
+pub struct DelegatedThreatCircuit {
+    pub threat_hash: Option<Fr>,
+    pub confidence_threshold: Option<Fr>,
+    pub model_commitment: Option<Fr>,
+    pub midstate: Option<Fr>,
+    pub ai_model_weights: Option<ModelWitness>,
+    pub node_reputation: Option<Fr>,
+    pub detection_algorithm: Option<Fr>,
+}
+
+impl ConstraintSynthesizer<Fr> for DelegatedThreatCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+
+        let threat_hash = FpVar::new_input(cs.clone(), || self.threat_hash.ok_or_else(missing))?;
+        let confidence_threshold = FpVar::new_input(cs.clone(), || self.confidence_threshold.ok_or_else(missing))?;
+        let model_commitment = FpVar::new_input(cs.clone(), || self.model_commitment.ok_or_else(missing))?;
+
+        let detection_result = FpVar::new_witness(cs.clone(), || self.detection_algorithm.ok_or_else(missing))?;
+        let node_reputation = FpVar::new_witness(cs.clone(), || self.node_reputation.ok_or_else(missing))?;
+        enforce_threat_confidences(&detection_result, &confidence_threshold, &node_reputation)?;
+
+        // Threat hash is the transaction hash chain finished over the zero padding field
+        let midstate = FpVar::new_witness(cs.clone(), || self.midstate.ok_or_else(missing))?;
+        region(&cs, "transaction hash", || mimc_hash_var(midstate, &[FpVar::constant(Fr::from(0u64))]))?
+            .enforce_equal(&threat_hash)?;
+
+        region(&cs, "model openings", || {
+            enforce_model_openings(cs.clone(), &threat_hash, &model_commitment, self.ai_model_weights.as_ref())
+        })
+    }
+}
+
+/// Sent to a relay prover
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayRequest {
+    pub node_id: String,
+    /// Compressed `BlindedWitness`
+    pub witness: Vec<u8>,
+    /// Detection confidence, the priority of the proving job (capped at `MAX_RELAYED_PRIORITY`)
+    pub priority: f64,
+    /// Unix seconds the request was signed at; stale requests are refused
+    pub timestamp: u64,
+    /// Node operator's EIP-191 signature of `digest`
+    pub signature: String,
+}
+
+impl RelayRequest {
+    /// Build and sign a request with the node's operator key
+    pub async fn signed(node_id: &str, witness: Vec<u8>, priority: f64, operator: &LocalWallet) -> Result<Self> {
+        let mut request = Self {
+            node_id: node_id.to_string(),
+            witness,
+            priority,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            signature: String::new(),
+        };
+        let signature = operator.sign_message(request.digest()).await.context("Failed to sign relay request")?;
+        request.signature = format!("0x{}", hex::encode(signature.to_vec()));
+        Ok(request)
+    }
+
+    /// keccak256 of the node id, timestamp, priority and witness
+    pub fn digest(&self) -> [u8; 32] {
+        let mut message = self.node_id.as_bytes().to_vec();
+        message.extend_from_slice(&self.timestamp.to_be_bytes());
+        message.extend_from_slice(&self.priority.to_bits().to_be_bytes());
+        message.extend_from_slice(&keccak256(&self.witness));
+        keccak256(message)
+    }
+
+    /// Address that signed the request
+    pub fn signer(&self) -> Result<Address> {
+        let signature: Signature = self.signature.trim_start_matches("0x").parse().context("Malformed request signature")?;
+        Ok(signature.recover(self.digest().to_vec())?)
+    }
+}
+
+/// A relay prover's answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RelayResponse {
+    Proof {
+        proof: Vec<u8>,
+        backend: ProvingBackend,
+        /// Compressed delegated threat verifying key the proof was made for (Groth16)
+        #[serde(default)]
+        verifying_key: Vec<u8>,
+    },
+    Refused { reason: String },
+}
+
+/// Ask `peer` to prove `request`
+pub async fn request_proof(peer: &RelayPeer, request: &RelayRequest, timeout: Duration) -> Result<RelayResponse> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(&peer.address).await
+            .with_context(|| format!("Relay prover {} unreachable", peer.node_id))?;
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        stream.write_all(&line).await?;
+
+        let mut response = String::new();
+        BufReader::new(stream).take(MAX_MESSAGE_BYTES).read_line(&mut response).await?;
+        serde_json::from_str(&response).with_context(|| format!("Malformed response from relay prover {}", peer.node_id))
+    };
+    tokio::time::timeout(timeout, exchange).await
+        .with_context(|| format!("Relay prover {} timed out", peer.node_id))?
+}
+
+/// Prove relay requests from staked nodes on `listen` until the listener fails; at most
+/// `max_concurrent` connections are handled at once
+pub async fn serve(prover: Arc<ZKProver>, listen: SocketAddr, stakes: StakeLookup) -> Result<()> {
+    let listener = TcpListener::bind(listen).await
+        .with_context(|| format!("Failed to listen for relay requests on {}", listen))?;
+    let slots = Arc::new(Semaphore::new(prover.config.relay.max_concurrent.max(1)));
+    let timeout = Duration::from_secs(prover.config.relay.timeout_secs);
+    info!("📡 Proving relay requests on {}", listen);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let Ok(slot) = Arc::clone(&slots).try_acquire_owned() else {
+            debug!("Relay busy, dropping connection from {}", peer);
+            continue;
+        };
+        let (prover, stakes) = (Arc::clone(&prover), Arc::clone(&stakes));
+        tokio::spawn(async move {
+            let _slot = slot;
+            match tokio::time::timeout(timeout, handle(&prover, &stakes, stream)).await {
+                Ok(Err(e)) => warn!("⚠️ Relay request from {} failed: {}", peer, e),
+                Err(_) => warn!("⚠️ Relay request from {} timed out", peer),
+                Ok(Ok(())) => {}
+            }
+        });
+    }
+}
+
+/// Check `request` is recent and signed by the registered operator of a sufficiently staked node
+async fn authenticate(request: &RelayRequest, stakes: &StakeLookup, config: &RelayConfig) -> Result<()> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    if request.timestamp.abs_diff(now) > config.timeout_secs {
+        return Err(anyhow::anyhow!("Request signed at {} is stale", request.timestamp));
+    }
+    let signer = request.signer()?;
+    let (operator, stake) = stakes(request.node_id.clone()).await
+        .with_context(|| format!("Node {} is not registered", request.node_id))?;
+    if signer != operator {
+        return Err(anyhow::anyhow!("Request is not signed by the operator of node {}", request.node_id));
+    }
+    if stake.is_zero() || stake < config.min_client_stake {
+        return Err(anyhow::anyhow!("Node {} has insufficient stake", request.node_id));
+    }
+    Ok(())
+}
+
+async fn handle(prover: &ZKProver, stakes: &StakeLookup, stream: TcpStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).take(MAX_MESSAGE_BYTES).read_line(&mut line).await?;
+    let request: RelayRequest = serde_json::from_str(&line).context("Malformed relay request")?;
+
+    debug!("📨 Relay request from node {}", request.node_id);
+    let proved = match authenticate(&request, stakes, &prover.config.relay).await {
+        Ok(()) => prover.prove_relayed(&request).await,
+        Err(e) => Err(e),
+    };
+    let response = match proved {
+        Ok((proof, verifying_key)) => RelayResponse::Proof {
+            proof,
+            backend: prover.config.backend,
+            verifying_key,
+        },
+        Err(e) => {
+            warn!("⚠️ Refused relay request from node {}: {}", request.node_id, e);
+            RelayResponse::Refused { reason: e.to_string() }
+        }
+    };
+
+    let mut line = serde_json::to_vec(&response)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::{fixed_point::encode_confidence, model::ModelCommitment, transaction_fields};
+    use ark_relations::r1cs::ConstraintSystem;
+    use futures::FutureExt;
+
+    #[test]
+    fn test_blinded_witness_proves_threat_statement() {
+        let model = ModelCommitment::from_bytes(&[0x42; 4096]).unwrap();
+        let fields = transaction_fields(b"relayed transaction").unwrap();
+        let hash = mimc_hash(Fr::from(0u64), &fields);
+        let witness = BlindedWitness {
+            midstate: BlindedWitness::midstate(&fields).unwrap(),
+            confidence_threshold: encode_confidence(0.7).unwrap(),
+            model_commitment: model.commitment(),
+            detection: encode_confidence(0.9).unwrap(),
+            node_reputation: encode_confidence(0.95).unwrap(),
+            model: model.witness(&hash).unwrap(),
+        };
+        assert_eq!(witness.threat_hash(), hash);
+
+        let mut bytes = Vec::new();
+        witness.serialize_compressed(&mut bytes).unwrap();
+        let received = BlindedWitness::deserialize_compressed(&bytes[..]).unwrap();
+
+        let is_satisfied = |circuit: DelegatedThreatCircuit| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+        assert!(is_satisfied(received.clone().circuit()));
+        let mut forged = received.circuit();
+        forged.threat_hash = Some(mimc_hash(Fr::from(0u64), &transaction_fields(b"another").unwrap()));
+        assert!(!is_satisfied(forged));
+
+        // Data reaching the last field cannot be blinded
+        assert!(BlindedWitness::midstate(&transaction_fields(&[7u8; TX_DATA_CHUNKS * 31]).unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_relay_requests_need_a_staked_operator() {
+        let operator: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let address = operator.address();
+        let stakes: StakeLookup = Arc::new(move |node_id: String| {
+            async move {
+                match node_id.as_str() {
+                    "staked" => Ok((address, U256::exp10(20))),
+                    "unstaked" => Ok((address, U256::zero())),
+                    _ => Err(anyhow::anyhow!("unknown node")),
+                }
+            }
+            .boxed()
+        });
+        let config = RelayConfig::default();
+
+        let request = RelayRequest::signed("staked", vec![1, 2, 3], 0.9, &operator).await.unwrap();
+        assert_eq!(request.signer().unwrap(), address);
+        assert!(authenticate(&request, &stakes, &config).await.is_ok());
+
+        // Another node's operator, an unstaked node, a tampered or stale request are refused
+        let other: LocalWallet = "0x0123456789012345678901234567890123456789012345678901234567890123".parse().unwrap();
+        let forged = RelayRequest::signed("staked", vec![1, 2, 3], 0.9, &other).await.unwrap();
+        assert!(authenticate(&forged, &stakes, &config).await.is_err());
+        let unstaked = RelayRequest::signed("unstaked", vec![1, 2, 3], 0.9, &operator).await.unwrap();
+        assert!(authenticate(&unstaked, &stakes, &config).await.is_err());
+        let mut tampered = request.clone();
+        tampered.witness = vec![4, 5, 6];
+        assert!(authenticate(&tampered, &stakes, &config).await.is_err());
+        let mut stale = request.clone();
+        stale.timestamp -= 2 * config.timeout_secs;
+        assert!(authenticate(&stale, &stakes, &config).await.is_err());
+    }
+}
@@ -0,0 +1,121 @@
+/*!
+ * Proof revocation list
+ * Node IDs whose keys were compromised and threat circuit versions that were withdrawn, signed by
+ * the DAGShield oracle's revocation key. The list is synced from the oracle and kept in
+ * `params_dir`; a newer version replaces an older one. The signature covers the list's bytes
+ * exactly as the oracle published them
+ */
+
+use anyhow::{Context, Result};
+use ethers::{
+    types::{Address, Signature, H256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, path::Path};
+
+/// Revoked node keys and circuit versions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// Increases with every change; older lists are never applied over newer ones
+    pub version: u64,
+    pub issued_at: u64,
+    /// Every proof from these nodes is rejected
+    pub revoked_nodes: BTreeSet<String>,
+    /// Threat circuit versions whose proofs are rejected
+    pub revoked_circuit_versions: BTreeSet<u32>,
+}
+
+impl RevocationList {
+    pub fn is_revoked(&self, node_id: &str, circuit_version: u32) -> bool {
+        self.revoked_nodes.contains(node_id) || self.revoked_circuit_versions.contains(&circuit_version)
+    }
+}
+
+/// Revocation list with the oracle's EIP-191 signature of the keccak256 of its published bytes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRevocationList {
+    pub list: RevocationList,
+    /// The list as published, hex encoded; `list` must decode from it
+    #[serde(default)]
+    pub raw: String,
+    pub signature: String,
+}
+
+impl SignedRevocationList {
+    /// Decode the list and signature as returned by the oracle
+    pub fn from_oracle(list: &[u8], signature: &[u8]) -> Result<Self> {
+        Ok(Self {
+            list: serde_json::from_slice(list).context("Malformed revocation list")?,
+            raw: format!("0x{}", hex::encode(list)),
+            signature: format!("0x{}", hex::encode(signature)),
+        })
+    }
+
+    /// keccak256 of the published bytes
+    pub fn digest(&self) -> Result<H256> {
+        let raw = hex::decode(self.raw.trim_start_matches("0x")).context("Malformed revocation list bytes")?;
+        Ok(H256::from(keccak256(raw)))
+    }
+
+    /// Check the signature over the published bytes recovers to `signer`, and that `list` is
+    /// what those bytes say
+    pub fn verify(&self, signer: Address) -> Result<()> {
+        let signature: Signature = self.signature.trim_start_matches("0x").parse()?;
+        signature
+            .verify(self.digest()?.as_bytes(), signer)
+            .context("Revocation list is not signed by the revocation key")?;
+        let raw = hex::decode(self.raw.trim_start_matches("0x"))?;
+        let published: RevocationList = serde_json::from_slice(&raw).context("Malformed revocation list")?;
+        if published != self.list {
+            return Err(anyhow::anyhow!("Revocation list does not match its signed bytes"));
+        }
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read revocation list {}", path.as_ref().display()))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path.as_ref(), serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write revocation list {}", path.as_ref().display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+
+    #[tokio::test]
+    async fn test_signed_revocation_list() {
+        let wallet: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let list = RevocationList {
+            version: 3,
+            issued_at: 1_700_000_000,
+            revoked_nodes: ["node-7".to_string()].into(),
+            revoked_circuit_versions: [2].into(),
+        };
+        // Published with whitespace the node's own encoding would not produce
+        let published = serde_json::to_vec_pretty(&list).unwrap();
+        let signature = wallet.sign_message(keccak256(&published)).await.unwrap();
+        let signed = SignedRevocationList::from_oracle(&published, &signature.to_vec()).unwrap();
+
+        signed.verify(wallet.address()).unwrap();
+        assert!(signed.verify(Address::random()).is_err());
+        assert!(signed.list.is_revoked("node-7", 4));
+        assert!(signed.list.is_revoked("node-1", 2));
+        assert!(!signed.list.is_revoked("node-1", 4));
+
+        // Tampering with the list or its bytes breaks the signature
+        let mut tampered = signed.clone();
+        tampered.list.revoked_nodes.clear();
+        assert!(tampered.verify(wallet.address()).is_err());
+        let mut tampered = signed.clone();
+        tampered.raw = format!("0x{}", hex::encode(serde_json::to_vec(&RevocationList::default()).unwrap()));
+        assert!(tampered.verify(wallet.address()).is_err());
+    }
+}
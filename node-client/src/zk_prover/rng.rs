@@ -0,0 +1,59 @@
+/*!
+ * Prover randomness
+ * Parameter generation and proving draw from `prover_rng`. With the `deterministic-proving`
+ * feature it is a ChaCha20 stream from a fixed seed, so keys and proofs are reproducible in tests
+ * and fixtures; such proofs are not zero-knowledge, so `ZKProver::initialize` refuses the
+ * feature in release builds. Verification keeps drawing from the thread RNG either way
+ */
+
+use ark_std::rand::Rng;
+
+/// Seed of every deterministic stream; `DAGSHIELD_PROVER_SEED` overrides it
+#[cfg(feature = "deterministic-proving")]
+pub const DETERMINISTIC_SEED: u64 = 0xda65_5e1d;
+
+/// Whether proofs are reproducible (and therefore not hiding)
+pub const fn is_deterministic() -> bool {
+    cfg!(feature = "deterministic-proving")
+}
+
+/// Randomness for one key generation or proof; in deterministic mode every call restarts the
+/// same stream
+#[cfg(feature = "deterministic-proving")]
+pub fn prover_rng() -> impl Rng {
+    use ark_std::rand::SeedableRng;
+
+    let seed = std::env::var("DAGSHIELD_PROVER_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(DETERMINISTIC_SEED);
+    rand_chacha::ChaCha20Rng::seed_from_u64(seed)
+}
+
+#[cfg(not(feature = "deterministic-proving"))]
+pub fn prover_rng() -> impl Rng {
+    ark_std::rand::thread_rng()
+}
+
+#[cfg(all(test, feature = "deterministic-proving"))]
+mod tests {
+    use super::*;
+    use crate::zk_prover::energy_range::EnergyRangeCircuit;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalSerialize;
+
+    #[test]
+    fn test_reproducible_parameters_and_proofs() {
+        let circuit = || EnergyRangeCircuit::new(50_000, (0, 86_400), Fr::from(1u64), 40_000 * 72_000, 72_000, Fr::from(7u64));
+        let prove = || {
+            let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(EnergyRangeCircuit::default(), &mut prover_rng()).unwrap();
+            let proof = Groth16::<Bn254>::create_random_proof_with_reduction(circuit(), &pk, &mut prover_rng()).unwrap();
+            let mut bytes = Vec::new();
+            proof.serialize_compressed(&mut bytes).unwrap();
+            bytes
+        };
+        assert!(is_deterministic());
+        assert_eq!(prove(), prove());
+    }
+}
@@ -0,0 +1,141 @@
+/*!
+ * Known-threat signature membership
+ * The oracle commits to a Merkle root over known threat signatures. A signature match proof
+ * shows that a transaction's signature is a leaf under that root without revealing which leaf,
+ * so nodes can claim signature-based detections while keeping the matched entry private
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use super::merkle::{path_witness, root_var, MerklePath, MerkleTree};
+use super::mimc::{mimc_hash, mimc_hash_var};
+use super::transaction_vars;
+
+/// Levels of the signature tree; up to 65536 signatures
+pub const SIGNATURE_TREE_DEPTH: usize = 16;
+
+/// Leading data chunks of a transaction that make up its signature (selector, target and
+/// first arguments), so variants of a known attack share one leaf
+pub const SIGNATURE_CHUNKS: usize = 2;
+
+/// Hash chain seed of signature leaves, apart from inner nodes and threat hashes
+const LEAF_SEED: u64 = 1;
+
+/// Merkle tree over known threat signatures
+pub type SignatureTree = MerkleTree<SIGNATURE_TREE_DEPTH>;
+
+/// Signature leaf of a transaction's `transaction_fields`
+pub fn signature_of(transaction_fields: &[Fr]) -> Fr {
+    mimc_hash(Fr::from(LEAF_SEED), &transaction_fields[1..=SIGNATURE_CHUNKS])
+}
+
+/// Root as stored by the oracle contract (big-endian uint256)
+pub fn root_to_bytes(root: &Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&root.into_bigint().to_bytes_be());
+    bytes
+}
+
+/// Root from the oracle contract; rejects values that are not canonical field elements
+pub fn root_from_bytes(bytes: &[u8; 32]) -> Result<Fr> {
+    let root = Fr::from_be_bytes_mod_order(bytes);
+    if root_to_bytes(&root) != *bytes {
+        return Err(anyhow::anyhow!("Signature root 0x{} is not a field element", hex::encode(bytes)));
+    }
+    Ok(root)
+}
+
+/// A transaction whose signature is in the committed set
+///
+/// Public: threat hash, signature root. Witness: transaction data, Merkle path.
+#[derive(Clone, Debug, Default)]
+pub struct SignatureMatchCircuit {
+    // Public inputs
+    pub threat_hash: Option<Fr>,
+    pub root: Option<Fr>,
+
+    // Private inputs (witness)
+    pub transaction_data: Option<Vec<Fr>>,
+    pub path: Option<MerklePath>,
+}
+
+impl SignatureMatchCircuit {
+    /// Circuit proving `transaction_fields` matches a signature in `tree`
+    pub fn new(transaction_fields: Vec<Fr>, tree: &SignatureTree) -> Result<Self> {
+        let index = tree
+            .position(&signature_of(&transaction_fields))
+            .context("Transaction matches no known threat signature")?;
+        Ok(Self {
+            threat_hash: Some(mimc_hash(Fr::from(0u64), &transaction_fields)),
+            root: Some(tree.root()),
+            transaction_data: Some(transaction_fields),
+            path: Some(tree.path(index)?),
+        })
+    }
+
+    /// Public inputs in allocation order
+    pub fn public_inputs(threat_hash: Fr, root: Fr) -> Vec<Fr> {
+        vec![threat_hash, root]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for SignatureMatchCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+
+        let threat_hash = FpVar::new_input(cs.clone(), || self.threat_hash.ok_or_else(missing))?;
+        let root = FpVar::new_input(cs.clone(), || self.root.ok_or_else(missing))?;
+
+        // Constraint 1: Threat hash is the MiMC hash of the transaction data
+        let fields = transaction_vars(cs.clone(), self.transaction_data.as_deref())?;
+        mimc_hash_var(FpVar::constant(Fr::from(0u64)), &fields)?.enforce_equal(&threat_hash)?;
+
+        // Constraint 2: the transaction's signature hashes up to the committed root
+        let leaf = mimc_hash_var(FpVar::constant(Fr::from(LEAF_SEED)), &fields[1..=SIGNATURE_CHUNKS])?;
+        let (index_bits, siblings) = path_witness(cs, self.path.as_ref(), SIGNATURE_TREE_DEPTH)?;
+        root_var(leaf, &index_bits, &siblings)?.enforce_equal(&root)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::transaction_fields;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn is_satisfied(circuit: SignatureMatchCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_membership_and_root_encoding() {
+        let known: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 64]).collect();
+        let signatures = known.iter().map(|tx| signature_of(&transaction_fields(tx).unwrap())).collect();
+        let tree = SignatureTree::new(signatures).unwrap();
+
+        // Same leading chunks as a known attack, different tail
+        let mut variant = known[3].clone();
+        variant.extend_from_slice(b"different trailing arguments");
+        let fields = transaction_fields(&variant).unwrap();
+        assert!(is_satisfied(SignatureMatchCircuit::new(fields.clone(), &tree).unwrap()));
+
+        // A path for another leaf does not reach the root
+        let mut wrong_leaf = SignatureMatchCircuit::new(fields.clone(), &tree).unwrap();
+        wrong_leaf.path = Some(tree.path(1).unwrap());
+        assert!(!is_satisfied(wrong_leaf));
+
+        let unknown = transaction_fields(b"benign transfer").unwrap();
+        assert!(SignatureMatchCircuit::new(unknown, &tree).is_err());
+
+        assert_eq!(root_from_bytes(&root_to_bytes(&tree.root())).unwrap(), tree.root());
+        assert!(root_from_bytes(&[0xff; 32]).is_err());
+    }
+}
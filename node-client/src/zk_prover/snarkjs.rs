@@ -0,0 +1,195 @@
+/*!
+ * snarkjs JSON interchange
+ * Groth16 proofs, public inputs and verifying keys in the `proof.json` / `public.json` /
+ * `verification_key.json` layout snarkjs reads and writes, so proofs can be cross-checked with
+ * `snarkjs groth16 verify` and Circom-generated keys can be used here
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Projective coordinates as decimal strings; the point at infinity has z = 0
+type G1Json = [String; 3];
+type G2Json = [[String; 2]; 3];
+
+/// `proof.json`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnarkjsProof {
+    pub pi_a: G1Json,
+    pub pi_b: G2Json,
+    pub pi_c: G1Json,
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// `verification_key.json`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnarkjsVerifyingKey {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: G1Json,
+    pub vk_beta_2: G2Json,
+    pub vk_gamma_2: G2Json,
+    pub vk_delta_2: G2Json,
+    #[serde(rename = "IC")]
+    pub ic: Vec<G1Json>,
+}
+
+const PROTOCOL: &str = "groth16";
+/// snarkjs' name for BN254
+const CURVE: &str = "bn128";
+
+fn decimal<F: PrimeField>(value: F) -> String {
+    value.into_bigint().to_string()
+}
+
+fn parse<F: PrimeField>(value: &str) -> Result<F> {
+    F::from_str(value).map_err(|_| anyhow::anyhow!("Invalid field element {:?}", value))
+}
+
+fn g1_to_json(point: &G1Affine) -> G1Json {
+    if point.is_zero() {
+        return ["0".into(), "1".into(), "0".into()];
+    }
+    [decimal(point.x), decimal(point.y), "1".into()]
+}
+
+fn g1_from_json(json: &G1Json) -> Result<G1Affine> {
+    if parse::<Fq>(&json[2])?.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    let point = G1Affine::new_unchecked(parse(&json[0])?, parse(&json[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(anyhow::anyhow!("G1 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+fn fq2_to_json(value: Fq2) -> [String; 2] {
+    [decimal(value.c0), decimal(value.c1)]
+}
+
+fn fq2_from_json(json: &[String; 2]) -> Result<Fq2> {
+    Ok(Fq2::new(parse(&json[0])?, parse(&json[1])?))
+}
+
+fn g2_to_json(point: &G2Affine) -> G2Json {
+    if point.is_zero() {
+        return [fq2_to_json(Fq2::zero()), fq2_to_json(Fq2::from(1u64)), fq2_to_json(Fq2::zero())];
+    }
+    [fq2_to_json(point.x), fq2_to_json(point.y), fq2_to_json(Fq2::from(1u64))]
+}
+
+fn g2_from_json(json: &G2Json) -> Result<G2Affine> {
+    if fq2_from_json(&json[2])?.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+    let point = G2Affine::new_unchecked(fq2_from_json(&json[0])?, fq2_from_json(&json[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(anyhow::anyhow!("G2 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+fn check_header(protocol: &str, curve: &str) -> Result<()> {
+    if protocol != PROTOCOL || curve != CURVE {
+        return Err(anyhow::anyhow!("Unsupported snarkjs artifact ({} over {})", protocol, curve));
+    }
+    Ok(())
+}
+
+pub fn export_proof(proof: &Proof<Bn254>) -> SnarkjsProof {
+    SnarkjsProof {
+        pi_a: g1_to_json(&proof.a),
+        pi_b: g2_to_json(&proof.b),
+        pi_c: g1_to_json(&proof.c),
+        protocol: PROTOCOL.into(),
+        curve: CURVE.into(),
+    }
+}
+
+pub fn import_proof(json: &SnarkjsProof) -> Result<Proof<Bn254>> {
+    check_header(&json.protocol, &json.curve)?;
+    Ok(Proof {
+        a: g1_from_json(&json.pi_a).context("Invalid pi_a")?,
+        b: g2_from_json(&json.pi_b).context("Invalid pi_b")?,
+        c: g1_from_json(&json.pi_c).context("Invalid pi_c")?,
+    })
+}
+
+/// `public.json`
+pub fn export_public_inputs(inputs: &[Fr]) -> Vec<String> {
+    inputs.iter().copied().map(decimal).collect()
+}
+
+pub fn import_public_inputs(json: &[String]) -> Result<Vec<Fr>> {
+    json.iter().map(|input| parse(input)).collect()
+}
+
+pub fn export_verifying_key(vk: &VerifyingKey<Bn254>) -> SnarkjsVerifyingKey {
+    SnarkjsVerifyingKey {
+        protocol: PROTOCOL.into(),
+        curve: CURVE.into(),
+        n_public: vk.gamma_abc_g1.len() - 1,
+        vk_alpha_1: g1_to_json(&vk.alpha_g1),
+        vk_beta_2: g2_to_json(&vk.beta_g2),
+        vk_gamma_2: g2_to_json(&vk.gamma_g2),
+        vk_delta_2: g2_to_json(&vk.delta_g2),
+        ic: vk.gamma_abc_g1.iter().map(g1_to_json).collect(),
+    }
+}
+
+pub fn import_verifying_key(json: &SnarkjsVerifyingKey) -> Result<VerifyingKey<Bn254>> {
+    check_header(&json.protocol, &json.curve)?;
+    if json.ic.len() != json.n_public + 1 {
+        return Err(anyhow::anyhow!("Verifying key has {} IC points for {} public inputs", json.ic.len(), json.n_public));
+    }
+    Ok(VerifyingKey {
+        alpha_g1: g1_from_json(&json.vk_alpha_1).context("Invalid vk_alpha_1")?,
+        beta_g2: g2_from_json(&json.vk_beta_2).context("Invalid vk_beta_2")?,
+        gamma_g2: g2_from_json(&json.vk_gamma_2).context("Invalid vk_gamma_2")?,
+        delta_g2: g2_from_json(&json.vk_delta_2).context("Invalid vk_delta_2")?,
+        gamma_abc_g1: json.ic.iter().map(g1_from_json).collect::<Result<_>>().context("Invalid IC")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::energy_range::{energy_commitment, EnergyRangeCircuit};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+
+    #[test]
+    fn test_snarkjs_round_trip() {
+        let mut rng = ark_std::test_rng();
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(EnergyRangeCircuit::default(), &mut rng).unwrap();
+
+        let salt = Fr::from(7u64);
+        let circuit = EnergyRangeCircuit::new(50_000, (0, 86_400), Fr::from(1u64), 40_000 * 72_000, 72_000, salt);
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(circuit, &pk, &mut rng).unwrap();
+        let public_inputs = EnergyRangeCircuit::public_inputs(50_000, (0, 86_400), Fr::from(1u64), energy_commitment(40_000 * 72_000, 72_000, Fr::from(1u64), 0, salt));
+
+        // Through JSON text, as another tool would see it
+        let proof_json = serde_json::to_string(&export_proof(&proof)).unwrap();
+        let vk_json = serde_json::to_string(&export_verifying_key(&pk.vk)).unwrap();
+        let inputs_json = serde_json::to_string(&export_public_inputs(&public_inputs)).unwrap();
+        assert!(vk_json.contains("\"nPublic\":3") && vk_json.contains("\"IC\""));
+
+        let proof = import_proof(&serde_json::from_str(&proof_json).unwrap()).unwrap();
+        let vk = import_verifying_key(&serde_json::from_str(&vk_json).unwrap()).unwrap();
+        let inputs = import_public_inputs(&serde_json::from_str::<Vec<String>>(&inputs_json).unwrap()).unwrap();
+        assert_eq!(vk, pk.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&prepare_verifying_key(&vk), &proof, &inputs).unwrap());
+
+        let mut off_curve = export_proof(&proof);
+        off_curve.pi_a[1] = "1".into();
+        assert!(import_proof(&off_curve).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+/*!
+ * Solidity verifier export
+ * Renders a Groth16 verifier contract for a verifying key, using the EIP-196/197 precompiles,
+ * and encodes proofs as calldata for its `verifyProof`
+ */
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use sha3::{Digest, Keccak256};
+use std::fmt::Write;
+
+/// Name of the generated contract
+pub const VERIFIER_CONTRACT: &str = "ThreatProofVerifier";
+
+fn function_signature(num_inputs: usize) -> String {
+    format!("verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[{}])", num_inputs)
+}
+
+/// Decimal literal for a field element
+fn literal<F: PrimeField>(value: F) -> String {
+    value.into_bigint().to_string()
+}
+
+fn g1_words(point: &G1Affine) -> [Fq; 2] {
+    if point.is_zero() {
+        return [Fq::zero(); 2];
+    }
+    [point.x, point.y]
+}
+
+/// The precompile takes each Fq2 coordinate imaginary part first
+fn g2_words(point: &G2Affine) -> [Fq; 4] {
+    let (x, y) = if point.is_zero() { (Fq2::zero(), Fq2::zero()) } else { (point.x, point.y) };
+    [x.c1, x.c0, y.c1, y.c0]
+}
+
+/// Verifier contract with `vk` baked in; `vk_hash` is recorded in the header
+pub fn verifier_contract(vk: &VerifyingKey<Bn254>, vk_hash: &str) -> String {
+    let num_inputs = vk.gamma_abc_g1.len() - 1;
+    let mut constants = String::new();
+    let mut constant = |name: &str, value: Fq| {
+        let _ = writeln!(constants, "    uint256 constant {} = {};", name, literal(value));
+    };
+
+    let [x, y] = g1_words(&vk.alpha_g1);
+    constant("ALPHA_X", x);
+    constant("ALPHA_Y", y);
+    for (name, point) in [("BETA", &vk.beta_g2), ("GAMMA", &vk.gamma_g2), ("DELTA", &vk.delta_g2)] {
+        for (suffix, value) in ["X1", "X0", "Y1", "Y0"].iter().zip(g2_words(point)) {
+            constant(&format!("{}_{}", name, suffix), value);
+        }
+    }
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        let [x, y] = g1_words(point);
+        constant(&format!("IC{}_X", i), x);
+        constant(&format!("IC{}_Y", i), y);
+    }
+
+    let mut accumulate = String::new();
+    for i in 0..num_inputs {
+        let _ = writeln!(accumulate, "        if (input[{i}] >= R) return false;");
+        let _ = writeln!(accumulate, "        (x, y) = _add(x, y, _mul(IC{}_X, IC{}_Y, input[{i}]));", i + 1, i + 1);
+    }
+
+    format!(
+        r#"// SPDX-License-Identifier: MIT
+// Generated by dagshield-node for verifying key {vk_hash}; regenerate instead of editing
+pragma solidity ^0.8.24;
+
+/// Groth16 verifier for DAGShield threat proofs over BN254
+contract {name} {{
+    uint256 constant Q = {q};
+    uint256 constant R = {r};
+
+{constants}
+    /// `{signature}`; encode with `ThreatProof::solidity_calldata`
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[{num_inputs}] calldata input
+    ) external view returns (bool) {{
+        // vk_x = IC0 + sum(input[i] * IC[i + 1])
+        uint256 x = IC0_X;
+        uint256 y = IC0_Y;
+{accumulate}
+        // e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (Q - (a[1] % Q)) % Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = x;
+        p[13] = y;
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+
+        uint256[1] memory out;
+        bool ok;
+        assembly {{
+            ok := staticcall(gas(), 0x08, p, 768, out, 32)
+        }}
+        return ok && out[0] == 1;
+    }}
+
+    function _add(uint256 x, uint256 y, uint256[2] memory point) private view returns (uint256, uint256) {{
+        uint256[4] memory input = [x, y, point[0], point[1]];
+        uint256[2] memory out;
+        bool ok;
+        assembly {{
+            ok := staticcall(gas(), 0x06, input, 128, out, 64)
+        }}
+        require(ok, "bn254 add failed");
+        return (out[0], out[1]);
+    }}
+
+    function _mul(uint256 x, uint256 y, uint256 s) private view returns (uint256[2] memory out) {{
+        uint256[3] memory input = [x, y, s];
+        bool ok;
+        assembly {{
+            ok := staticcall(gas(), 0x07, input, 96, out, 64)
+        }}
+        require(ok, "bn254 mul failed");
+    }}
+}}
+"#,
+        name = VERIFIER_CONTRACT,
+        q = Fq::MODULUS,
+        r = Fr::MODULUS,
+        signature = function_signature(num_inputs),
+    )
+}
+
+/// ABI-encoded `verifyProof` call
+pub fn encode_calldata(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Vec<u8> {
+    let selector = Keccak256::digest(function_signature(public_inputs.len()).as_bytes());
+    let mut calldata = selector[..4].to_vec();
+    calldata.extend(encode_arguments(proof, public_inputs));
+    calldata
+}
+
+/// `verifyProof` arguments without the selector, for contracts that forward them to the
+/// verifier: every argument is a static array, so this is just the point coordinates (G2
+/// imaginary parts first) and public inputs as big-endian words
+pub fn encode_arguments(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Vec<u8> {
+    g1_words(&proof.a)
+        .into_iter()
+        .chain(g2_words(&proof.b))
+        .chain(g1_words(&proof.c))
+        .map(|word| word.into_bigint().to_bytes_be())
+        .chain(public_inputs.iter().map(|input| input.into_bigint().to_bytes_be()))
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+    use ark_ff::One;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+    use ark_std::UniformRand;
+
+    /// a * b == out, with `out` public
+    #[derive(Clone, Default)]
+    struct ProductCircuit {
+        a: Option<Fr>,
+        b: Option<Fr>,
+    }
+
+    impl ConstraintSynthesizer<Fr> for ProductCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let product = self.a.zip(self.b).map(|(a, b)| a * b);
+            let out = FpVar::new_input(cs.clone(), || product.ok_or(SynthesisError::AssignmentMissing))?;
+            let a = FpVar::new_witness(cs.clone(), || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+            let b = FpVar::new_witness(cs, || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+            (a * b).enforce_equal(&out)
+        }
+    }
+
+    fn fq(word: &[u8]) -> Fq {
+        Fq::from_be_bytes_mod_order(word)
+    }
+
+    #[test]
+    fn test_calldata_satisfies_contract_pairing_check() {
+        let mut rng = ark_std::test_rng();
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(ProductCircuit::default(), &mut rng).unwrap();
+        let (a, b) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(ProductCircuit { a: Some(a), b: Some(b) }, &pk, &mut rng).unwrap();
+        let inputs = [a * b];
+
+        let calldata = encode_calldata(&proof, &inputs);
+        assert_eq!(calldata.len(), 4 + 32 * 9);
+        assert_eq!(calldata[..4], Keccak256::digest(b"verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[1])")[..4]);
+        assert_eq!(calldata[4..], encode_arguments(&proof, &inputs)[..]);
+
+        // Rebuild the points the way the precompile reads them and run the contract's check
+        let words: Vec<&[u8]> = calldata[4..].chunks(32).collect();
+        let proof_a = G1Affine::new(fq(words[0]), fq(words[1]));
+        let proof_b = G2Affine::new(
+            Fq2::new(fq(words[3]), fq(words[2])),
+            Fq2::new(fq(words[5]), fq(words[4])),
+        );
+        let proof_c = G1Affine::new(fq(words[6]), fq(words[7]));
+        let input = Fr::from_be_bytes_mod_order(words[8]);
+
+        let vk = &pk.vk;
+        let vk_x = vk.gamma_abc_g1[1] * input + vk.gamma_abc_g1[0];
+        let product = Bn254::multi_pairing(
+            [-proof_a, vk.alpha_g1, vk_x.into(), proof_c],
+            [proof_b, vk.beta_g2, vk.gamma_g2, vk.delta_g2],
+        );
+        assert!(product.0.is_one());
+
+        let contract = verifier_contract(vk, "test");
+        assert!(contract.contains("uint256[1] calldata input"));
+        assert!(contract.contains(&format!("IC1_Y = {};", literal(vk.gamma_abc_g1[1].y))));
+        assert!(contract.contains(&format!("DELTA_X1 = {};", literal(vk.delta_g2.x.c1))));
+    }
+}
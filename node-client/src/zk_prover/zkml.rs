@@ -0,0 +1,297 @@
+/*!
+ * zkML inference (experimental)
+ * A small quantized classifier (int8 weights, one ReLU hidden layer) evaluated inside the
+ * constraint system over the leading bytes of a transaction. The proof shows that the
+ * registered model computed the published score for this transaction, rather than only that
+ * some private confidence cleared a threshold
+ */
+
+use anyhow::{Context, Result};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::mimc::{mimc_hash, mimc_hash_var};
+use super::transaction_vars;
+
+/// Input features: the first bytes of the transaction data
+pub const FEATURES: usize = 16;
+pub const HIDDEN: usize = 8;
+
+/// Serialized parameters: int8 weights and 24-bit two's complement biases, little-endian
+pub const PARAM_BYTES: usize = HIDDEN * FEATURES + HIDDEN * 3 + HIDDEN + 3;
+
+/// Right shift requantizing hidden activations back towards int8 range
+const SHIFT: usize = 8;
+
+/// Range of every accumulator; byte features and int8 weights keep sums far below it
+const ACC_BITS: usize = 32;
+
+const BIAS_BITS: usize = 24;
+
+/// Hash chain seed of classifier commitments, apart from signatures (1), Merkle nodes (2)
+/// and model chunks (3, 4)
+const COMMITMENT_SEED: u64 = 5;
+
+/// Quantized two-layer classifier; the output is an unscaled logit
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuantizedClassifier {
+    pub hidden_weights: [[i8; FEATURES]; HIDDEN],
+    pub hidden_biases: [i32; HIDDEN],
+    pub output_weights: [i8; HIDDEN],
+    pub output_bias: i32,
+}
+
+impl QuantizedClassifier {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != PARAM_BYTES {
+            return Err(anyhow::anyhow!("Classifier has {} parameter bytes, expected {}", bytes.len(), PARAM_BYTES));
+        }
+        let (weights, rest) = bytes.split_at(HIDDEN * FEATURES);
+        let (biases, rest) = rest.split_at(HIDDEN * 3);
+        let (output_weights, output_bias) = rest.split_at(HIDDEN);
+
+        let mut model = Self {
+            hidden_weights: [[0; FEATURES]; HIDDEN],
+            hidden_biases: [0; HIDDEN],
+            output_weights: [0; HIDDEN],
+            output_bias: bias_from_bytes(output_bias),
+        };
+        for (row, bytes) in model.hidden_weights.iter_mut().zip(weights.chunks(FEATURES)) {
+            for (weight, byte) in row.iter_mut().zip(bytes) {
+                *weight = *byte as i8;
+            }
+        }
+        for (bias, bytes) in model.hidden_biases.iter_mut().zip(biases.chunks(3)) {
+            *bias = bias_from_bytes(bytes);
+        }
+        for (weight, byte) in model.output_weights.iter_mut().zip(output_weights) {
+            *weight = *byte as i8;
+        }
+        Ok(model)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = std::fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read classifier {}", path.as_ref().display()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Fails when a bias does not fit in 24 bits
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut biases = self.hidden_biases.iter().chain(std::iter::once(&self.output_bias));
+        if let Some(bias) = biases.find(|bias| !(-(1 << (BIAS_BITS - 1))..1 << (BIAS_BITS - 1)).contains(*bias)) {
+            return Err(anyhow::anyhow!("Bias {} does not fit in {} bits", bias, BIAS_BITS));
+        }
+
+        let mut bytes = Vec::with_capacity(PARAM_BYTES);
+        bytes.extend(self.hidden_weights.iter().flatten().map(|weight| *weight as u8));
+        for bias in &self.hidden_biases {
+            bytes.extend(&bias.to_le_bytes()[..3]);
+        }
+        bytes.extend(self.output_weights.iter().map(|weight| *weight as u8));
+        bytes.extend(&self.output_bias.to_le_bytes()[..3]);
+        Ok(bytes)
+    }
+
+    /// Public commitment the circuit checks the parameters against
+    pub fn commitment(&self) -> Result<Fr> {
+        Ok(mimc_hash(Fr::from(COMMITMENT_SEED), &param_fields(&self.to_bytes()?)))
+    }
+
+    /// Score of `features`, exactly as the circuit computes it
+    pub fn score(&self, features: &[u8; FEATURES]) -> i64 {
+        let hidden = self.hidden_weights.iter().zip(&self.hidden_biases).map(|(weights, bias)| {
+            let acc = *bias as i64 + weights.iter().zip(features).map(|(w, x)| *w as i64 * *x as i64).sum::<i64>();
+            acc.max(0) >> SHIFT
+        });
+        self.output_bias as i64 + hidden.zip(&self.output_weights).map(|(h, w)| h * *w as i64).sum::<i64>()
+    }
+}
+
+fn bias_from_bytes(bytes: &[u8]) -> i32 {
+    // Sign-extend from 24 bits
+    i32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) << 8 >> 8
+}
+
+/// Parameter bytes packed 31 to a field element
+fn param_fields(bytes: &[u8]) -> Vec<Fr> {
+    bytes.chunks(31).map(Fr::from_le_bytes_mod_order).collect()
+}
+
+/// Features of a transaction: the low bytes of its first data chunk
+pub fn features_of(transaction_fields: &[Fr]) -> [u8; FEATURES] {
+    let mut features = [0u8; FEATURES];
+    features.copy_from_slice(&transaction_fields[1].into_bigint().to_bytes_le()[..FEATURES]);
+    features
+}
+
+/// Signed integer as a field element
+pub fn signed_field(value: i64) -> Fr {
+    if value < 0 {
+        -Fr::from(value.unsigned_abs())
+    } else {
+        Fr::from(value as u64)
+    }
+}
+
+/// The registered classifier scored a transaction
+///
+/// Public: threat hash, classifier commitment, score. Witness: transaction data, parameters.
+#[derive(Clone, Debug, Default)]
+pub struct InferenceCircuit {
+    // Public inputs
+    pub threat_hash: Option<Fr>,
+    pub model_commitment: Option<Fr>,
+    pub score: Option<Fr>,
+
+    // Private inputs (witness)
+    pub transaction_data: Option<Vec<Fr>>,
+    pub model: Option<QuantizedClassifier>,
+}
+
+impl InferenceCircuit {
+    /// Circuit scoring `transaction_fields` with `model`; returns it with the score
+    pub fn new(transaction_fields: Vec<Fr>, model: &QuantizedClassifier) -> Result<(Self, i64)> {
+        let score = model.score(&features_of(&transaction_fields));
+        let circuit = Self {
+            threat_hash: Some(mimc_hash(Fr::from(0u64), &transaction_fields)),
+            model_commitment: Some(model.commitment()?),
+            score: Some(signed_field(score)),
+            transaction_data: Some(transaction_fields),
+            model: Some(model.clone()),
+        };
+        Ok((circuit, score))
+    }
+
+    /// Public inputs in allocation order
+    pub fn public_inputs(threat_hash: Fr, model_commitment: Fr, score: i64) -> Vec<Fr> {
+        vec![threat_hash, model_commitment, signed_field(score)]
+    }
+}
+
+/// Little-endian bits of `value`, constrained to recompose to it; `value` must be below 2^bits
+fn bits_of(cs: ConstraintSystemRef<Fr>, value: &FpVar<Fr>, bits: usize) -> Result<Vec<Boolean<Fr>>, SynthesisError> {
+    let native = value.value().ok().map(|value| value.into_bigint());
+    let bits = (0..bits)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                native.map(|value| value.get_bit(i)).ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(value)?;
+    Ok(bits)
+}
+
+/// Two's complement value of `bits`
+fn signed_var(bits: &[Boolean<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+    let (sign, magnitude) = bits.split_last().ok_or(SynthesisError::Unsatisfiable)?;
+    Ok(Boolean::le_bits_to_fp_var(magnitude)? - FpVar::from(sign.clone()) * Fr::from(1u64 << magnitude.len()))
+}
+
+/// `max(acc, 0) >> SHIFT`
+fn relu_shift(cs: ConstraintSystemRef<Fr>, acc: &FpVar<Fr>) -> Result<FpVar<Fr>, SynthesisError> {
+    // acc + 2^31 has its top bit set exactly when acc >= 0, and then the bits below are acc
+    let offset = acc + FpVar::constant(Fr::from(1u64 << (ACC_BITS - 1)));
+    let bits = bits_of(cs, &offset, ACC_BITS)?;
+    let shifted = Boolean::le_bits_to_fp_var(&bits[SHIFT..ACC_BITS - 1])?;
+    bits[ACC_BITS - 1].select(&shifted, &FpVar::zero())
+}
+
+impl ConstraintSynthesizer<Fr> for InferenceCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+
+        let threat_hash = FpVar::new_input(cs.clone(), || self.threat_hash.ok_or_else(missing))?;
+        let model_commitment = FpVar::new_input(cs.clone(), || self.model_commitment.ok_or_else(missing))?;
+        let score = FpVar::new_input(cs.clone(), || self.score.ok_or_else(missing))?;
+
+        // Constraint 1: Threat hash is the MiMC hash of the transaction data
+        let fields = transaction_vars(cs.clone(), self.transaction_data.as_deref())?;
+        mimc_hash_var(FpVar::constant(Fr::from(0u64)), &fields)?.enforce_equal(&threat_hash)?;
+
+        // Constraint 2: features are the leading bytes of the data
+        let data_bits = bits_of(cs.clone(), &fields[1], 31 * 8)?;
+        let features = data_bits[..FEATURES * 8]
+            .chunks(8)
+            .map(Boolean::le_bits_to_fp_var)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Constraint 3: the parameters are the committed classifier's
+        let param_bytes = self.model.as_ref().map(QuantizedClassifier::to_bytes).transpose().map_err(|_| SynthesisError::Unsatisfiable)?;
+        let mut param_bits = Vec::with_capacity(PARAM_BYTES * 8);
+        let mut chunks = Vec::new();
+        for (k, len) in (0..PARAM_BYTES).step_by(31).map(|start| (start / 31, (PARAM_BYTES - start).min(31))) {
+            let chunk = FpVar::new_witness(cs.clone(), || {
+                param_bytes.as_ref().map(|bytes| param_fields(bytes)[k]).ok_or_else(missing)
+            })?;
+            param_bits.extend(bits_of(cs.clone(), &chunk, len * 8)?);
+            chunks.push(chunk);
+        }
+        mimc_hash_var(FpVar::constant(Fr::from(COMMITMENT_SEED)), &chunks)?.enforce_equal(&model_commitment)?;
+
+        let weight = |byte: usize| signed_var(&param_bits[byte * 8..(byte + 1) * 8]);
+        let bias = |byte: usize| signed_var(&param_bits[byte * 8..byte * 8 + BIAS_BITS]);
+        let biases_at = HIDDEN * FEATURES;
+        let output_weights_at = biases_at + HIDDEN * 3;
+
+        // Constraint 4: the classifier's output on the features is the public score
+        let mut output = bias(output_weights_at + HIDDEN)?;
+        for j in 0..HIDDEN {
+            let mut acc = bias(biases_at + j * 3)?;
+            for (i, feature) in features.iter().enumerate() {
+                acc += weight(j * FEATURES + i)? * feature;
+            }
+            output += weight(output_weights_at + j)? * relu_shift(cs.clone(), &acc)?;
+        }
+        output.enforce_equal(&score)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk_prover::transaction_fields;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn test_classifier() -> QuantizedClassifier {
+        let bytes: Vec<u8> = (0..PARAM_BYTES as u32).map(|i| (i * 37 % 251) as u8).collect();
+        QuantizedClassifier::from_bytes(&bytes).unwrap()
+    }
+
+    fn is_satisfied(circuit: InferenceCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_circuit_matches_native_inference() {
+        let model = test_classifier();
+        assert_eq!(QuantizedClassifier::from_bytes(&model.to_bytes().unwrap()).unwrap(), model);
+
+        for data in [&b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"[..], b"transfer(0xdeadbeef, 1000000)", b"\xff"] {
+            let (circuit, score) = InferenceCircuit::new(transaction_fields(data).unwrap(), &model).unwrap();
+            assert_eq!(score, model.score(&features_of(&transaction_fields(data).unwrap())));
+            assert!(is_satisfied(circuit.clone()));
+
+            // Claiming another score fails
+            let mut inflated = circuit;
+            inflated.score = Some(signed_field(score + 1));
+            assert!(!is_satisfied(inflated));
+        }
+
+        // A different model is not the committed one
+        let (mut circuit, _) = InferenceCircuit::new(transaction_fields(b"swap").unwrap(), &model).unwrap();
+        let mut other = model.clone();
+        other.output_bias += 1;
+        circuit.model = Some(other);
+        assert!(!is_satisfied(circuit));
+    }
+}
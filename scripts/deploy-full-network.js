@@ -24,7 +24,7 @@ async function main() {
     // 2. Deploy DAGShield main contract
     console.log("\n🛡️  Deploying DAGShield...")
     const DAGShield = await ethers.getContractFactory("DAGShield")
-    const dagShield = await DAGShield.deploy(dagToken.address)
+    const dagShield = await DAGShield.deploy(dagToken.address, ethers.constants.AddressZero)
     await dagShield.deployed()
     deployments.dagShield = dagShield.address
     console.log("✅ DAGShield deployed to:", dagShield.address)
@@ -16,10 +16,18 @@ async function main() {
   const tokenAddress = await dagToken.getAddress()
   console.log("✅ DAGToken deployed to:", tokenAddress)
 
+  // Deploy the ERC-2771 forwarder gasless node calls are relayed through
+  console.log("\n📨 Deploying DAGShieldForwarder...")
+  const DAGShieldForwarder = await ethers.getContractFactory("DAGShieldForwarder")
+  const forwarder = await DAGShieldForwarder.deploy()
+  await forwarder.waitForDeployment()
+  const forwarderAddress = await forwarder.getAddress()
+  console.log("✅ DAGShieldForwarder deployed to:", forwarderAddress)
+
   // Deploy DAGShield main contract
   console.log("\n🛡️ Deploying DAGShield...")
   const DAGShield = await ethers.getContractFactory("DAGShield")
-  const dagShield = await DAGShield.deploy(tokenAddress)
+  const dagShield = await DAGShield.deploy(tokenAddress, forwarderAddress)
   await dagShield.waitForDeployment()
   const shieldAddress = await dagShield.getAddress()
   console.log("✅ DAGShield deployed to:", shieldAddress)
@@ -43,6 +51,7 @@ async function main() {
   console.log("\n🎉 Deployment completed successfully!")
   console.log("📋 Contract Addresses:")
   console.log("   DAGToken:", tokenAddress)
+  console.log("   DAGShieldForwarder:", forwarderAddress)
   console.log("   DAGShield:", shieldAddress)
 
   console.log("\n📊 Network Stats:")
@@ -58,6 +67,7 @@ async function main() {
     deployer: deployer.address,
     contracts: {
       DAGToken: tokenAddress,
+      DAGShieldForwarder: forwarderAddress,
       DAGShield: shieldAddress,
     },
     timestamp: new Date().toISOString(),
@@ -16,7 +16,7 @@ describe("DAGShield Integration Tests", () => {
     contracts.dagToken = dagToken
 
     const DAGShield = await ethers.getContractFactory("DAGShield")
-    dagShield = await DAGShield.deploy(dagToken.address)
+    dagShield = await DAGShield.deploy(dagToken.address, ethers.constants.AddressZero)
     contracts.dagShield = dagShield
 
     const DAGOracle = await ethers.getContractFactory("DAGOracle")
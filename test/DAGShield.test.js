@@ -17,7 +17,7 @@ describe("DAGShield", () => {
 
     // Deploy DAGShield
     const DAGShield = await ethers.getContractFactory("DAGShield")
-    dagShield = await DAGShield.deploy(tokenAddress)
+    dagShield = await DAGShield.deploy(tokenAddress, ethers.ZeroAddress)
     await dagShield.waitForDeployment()
     shieldAddress = await dagShield.getAddress()
   })
@@ -47,6 +47,57 @@ describe("DAGShield", () => {
     })
   })
 
+  describe("Meta-transactions", () => {
+    it("Should attribute relayed calls to the signing node", async () => {
+      const DAGShieldForwarder = await ethers.getContractFactory("DAGShieldForwarder")
+      const forwarder = await DAGShieldForwarder.deploy()
+      await forwarder.waitForDeployment()
+      const forwarderAddress = await forwarder.getAddress()
+
+      const DAGShield = await ethers.getContractFactory("DAGShield")
+      const relayedShield = await DAGShield.deploy(tokenAddress, forwarderAddress)
+      await relayedShield.waitForDeployment()
+
+      const stakeAmount = ethers.parseEther("100")
+      const request = {
+        from: node1.address,
+        to: await relayedShield.getAddress(),
+        value: stakeAmount,
+        gas: 500000n,
+        nonce: await forwarder.nonces(node1.address),
+        deadline: (await ethers.provider.getBlock("latest")).timestamp + 300,
+        data: relayedShield.interface.encodeFunctionData("registerNode", ["node_001"]),
+      }
+      const signature = await node1.signTypedData(
+        {
+          name: "DAGShieldForwarder",
+          version: "1",
+          chainId: (await ethers.provider.getNetwork()).chainId,
+          verifyingContract: forwarderAddress,
+        },
+        {
+          ForwardRequest: [
+            { name: "from", type: "address" },
+            { name: "to", type: "address" },
+            { name: "value", type: "uint256" },
+            { name: "gas", type: "uint256" },
+            { name: "nonce", type: "uint256" },
+            { name: "deadline", type: "uint48" },
+            { name: "data", type: "bytes" },
+          ],
+        },
+        request,
+      )
+
+      const { nonce, ...requestData } = request
+      await forwarder.connect(owner).execute({ ...requestData, signature }, { value: stakeAmount })
+
+      const nodeInfo = await relayedShield.getNode(node1.address)
+      expect(nodeInfo.active).to.be.true
+      expect(nodeInfo.nodeAddress).to.equal(node1.address)
+    })
+  })
+
   describe("Threat Reporting", () => {
     beforeEach(async () => {
       // Register a node first
@@ -82,6 +133,69 @@ describe("DAGShield", () => {
     })
   })
 
+  describe("Proven Threats", () => {
+    const commitment = ethers.zeroPadValue("0x2a", 32)
+    const threshold = ethers.zeroPadValue(ethers.toBeHex(800000), 32)
+
+    // verifyProof arguments with placeholder points; the mock verifier accepts any
+    const encodeProof = (provenHash, modelCommitment = commitment, salt = 1) =>
+      ethers.concat([
+        ...Array.from({ length: 8 }, (_, i) => ethers.zeroPadValue(ethers.toBeHex(salt + i), 32)),
+        ethers.zeroPadValue(ethers.toBeHex(provenHash), 32),
+        threshold,
+        modelCommitment,
+      ])
+
+    beforeEach(async () => {
+      const MockThreatVerifier = await ethers.getContractFactory("MockThreatVerifier")
+      const verifier = await MockThreatVerifier.deploy()
+      await verifier.waitForDeployment()
+      const MockModelOracle = await ethers.getContractFactory("MockModelOracle")
+      const oracle = await MockModelOracle.deploy()
+      await oracle.waitForDeployment()
+      await oracle.setModelCommitment(commitment)
+
+      await dagShield.setThreatVerifier(await verifier.getAddress())
+      await dagShield.setModelOracle(await oracle.getAddress())
+      await dagShield.connect(node1).registerNode("node_001", { value: ethers.parseEther("100") })
+    })
+
+    it("Should accept a proof over the submitted data", async () => {
+      const data = ethers.toUtf8Bytes("drainer approval")
+      const proof = encodeProof(await dagShield.threatDataHash(data))
+
+      await expect(dagShield.connect(node1).submitThreatWithProof(ethers.keccak256(data), data, proof)).to.emit(
+        dagShield,
+        "ThreatProven",
+      )
+    })
+
+    it("Should reject a proof replayed with different data", async () => {
+      const data = ethers.toUtf8Bytes("drainer approval")
+      const proof = encodeProof(await dagShield.threatDataHash(data))
+      await dagShield.connect(node1).submitThreatWithProof(ethers.keccak256(data), data, proof)
+
+      const other = ethers.toUtf8Bytes("benign transfer")
+      await expect(
+        dagShield.connect(node1).submitThreatWithProof(ethers.keccak256(other), other, proof),
+      ).to.be.revertedWith("Proof already used")
+
+      const reshaped = encodeProof(await dagShield.threatDataHash(data), commitment, 100)
+      await expect(
+        dagShield.connect(node1).submitThreatWithProof(ethers.keccak256(other), other, reshaped),
+      ).to.be.revertedWith("Proof not over threat data")
+    })
+
+    it("Should reject a proof against another model commitment", async () => {
+      const data = ethers.toUtf8Bytes("drainer approval")
+      const proof = encodeProof(await dagShield.threatDataHash(data), ethers.zeroPadValue("0x2b", 32))
+
+      await expect(
+        dagShield.connect(node1).submitThreatWithProof(ethers.keccak256(data), data, proof),
+      ).to.be.revertedWith("Model commitment mismatch")
+    })
+  })
+
   describe("Threat Voting", () => {
     let alertId
 